@@ -2,7 +2,9 @@
 
 #include <dirent.h>
 #include <fcntl.h>
+#include <limits.h>
 #include <linux/types.h>
+#include <signal.h>
 #include <stddef.h>
 #include <stdint.h>
 #include <stdio.h>
@@ -12,10 +14,14 @@
 #include <sys/mount.h>
 #include <sys/stat.h>
 #include <sys/statfs.h>
+#include <sys/socket.h>
 #include <sys/types.h>
+#include <sys/un.h>
 #include <sys/xattr.h>
+#include <time.h>
 #include <unistd.h>
 
+#include <algorithm>
 #include <cassert>
 #include <cerrno>
 #include <cstddef>
@@ -23,7 +29,7 @@
 #include <cstdlib>
 #include <cstring>
 #include <filesystem>
-#include <random>
+#include <regex>
 #include <string>
 #include <utility>
 #include <vector>
@@ -58,13 +64,20 @@
   } while (0)
 
 #define BUFFER_SIZE 1024 * 1024
-#define RANDOM_SEED 123
+
+// Same as EXT4_IOC_SHUTDOWN and XFS_IOC_GOINGDOWN, journal is committed but
+// dirty data is dropped.
+#define FS_IOC_SHUTDOWN _IOR('X', 125, uint32_t)
+#define FS_SHUTDOWN_FLAGS_LOGFLUSH 0x1
 
 const char *MKDIR = "MKDIR";
 const char *RMDIR = "RMDIR";
 const char *CREATE = "CREATE";
 const char *CLOSE = "CLOSE";
 const char *UNLINK = "UNLINK";
+const char *MKSOCKET = "MKSOCKET";
+const char *SYMLINK = "SYMLINK";
+const char *READLINK = "READLINK";
 const char *STAT = "STAT";
 const char *HARDLINK = "HARDLINK";
 const char *RENAME = "RENAME";
@@ -72,6 +85,24 @@ const char *OPEN = "OPEN";
 const char *WRITE = "WRITE";
 const char *READ = "READ";
 const char *FSYNC = "FSYNC";
+const char *FDATASYNC = "FDATASYNC";
+const char *SYNCFILERANGE = "SYNCFILERANGE";
+const char *LSEEK = "LSEEK";
+const char *OPENDIR = "OPENDIR";
+const char *READDIR = "READDIR";
+const char *CLOSEDIR = "CLOSEDIR";
+const char *LISTDIR = "LISTDIR";
+const char *NAMETOHANDLE = "NAMETOHANDLE";
+const char *OPENBYHANDLE = "OPENBYHANDLE";
+const char *PREALLOC = "PREALLOC";
+const char *TRUNCATE = "TRUNCATE";
+const char *CHMOD = "CHMOD";
+const char *MKNOD = "MKNOD";
+const char *SETXATTR = "SETXATTR";
+const char *REMOVEXATTR = "REMOVEXATTR";
+const char *UTIMENS = "UTIMENS";
+const char *FALLOCATE = "FALLOCATE";
+const char *CRASHPOINT = "CRASHPOINT";
 
 enum ExitCode : int {
   OK = 0,
@@ -80,40 +111,155 @@ enum ExitCode : int {
   ERROR = 2,
 };
 
-struct Trace {
-  int idx;
-  std::string cmd;
-  int ret_code;
-  int err;
-  std::string extra;
-};
+static FILE *trace_fp = nullptr;
 
-std::vector<Trace> traces;
+// Index of current operation.
+static int idx = -1;
 
+// Start of current operation, measured with monotonic clock.
+static struct timespec op_start;
+
+// Wall-clock time since start of current operation, in microseconds.
+static long long op_duration_us() {
+  struct timespec now;
+  clock_gettime(CLOCK_MONOTONIC, &now);
+  return (now.tv_sec - op_start.tv_sec) * 1000000LL +
+         (now.tv_nsec - op_start.tv_nsec) / 1000;
+}
+
+// Rows are flushed one by one, so trace is kept if executor dies mid-workload.
 static void append_trace(int idx, const char *cmd, int ret_code, int err,
                          std::string extra) {
-  traces.push_back(Trace{idx, cmd, ret_code, err, extra});
+  fprintf(trace_fp, "%4d,%12s,%8d,%s(%d),%s,%lld\n", idx, cmd, ret_code,
+          strerror(err), err, extra.c_str(), op_duration_us());
+  fflush(trace_fp);
+}
+
+static const int FATAL_SIGNALS[] = {SIGSEGV, SIGBUS, SIGILL, SIGFPE, SIGABRT};
+
+static const char *signal_name(int sig) {
+  switch (sig) {
+    case SIGSEGV:
+      return "SIGSEGV";
+    case SIGBUS:
+      return "SIGBUS";
+    case SIGILL:
+      return "SIGILL";
+    case SIGFPE:
+      return "SIGFPE";
+    case SIGABRT:
+      return "SIGABRT";
+    default:
+      return "UNKNOWN";
+  }
+}
+
+// Only async-signal-safe calls are allowed in signal handler, so no printf.
+static size_t append_str(char *buf, size_t len, const char *str) {
+  while (*str) {
+    buf[len++] = *str++;
+  }
+  return len;
+}
+
+static size_t append_num(char *buf, size_t len, long long n) {
+  if (n < 0) {
+    buf[len++] = '-';
+    n = -n;
+  }
+  char digits[20];
+  int d = 0;
+  do {
+    digits[d++] = '0' + n % 10;
+    n /= 10;
+  } while (n > 0);
+  while (d > 0) {
+    buf[len++] = digits[--d];
+  }
+  return len;
+}
+
+// Row of operation executor died at is replaced with SIGNAL row, then signal
+// is raised again with default action, so exit status is kept.
+static void on_fatal_signal(int sig) {
+  char row[256];
+  size_t len = append_num(row, 0, idx);
+  len = append_str(row, len, ",SIGNAL,-1,Success(0),signal=");
+  len = append_str(row, len, signal_name(sig));
+  len = append_str(row, len, ",");
+  len = append_num(row, len, op_duration_us());
+  len = append_str(row, len, "\n");
+  if (trace_fp && write(fileno(trace_fp), row, len) == -1) {
+    // nothing can be done
+  }
+  raise(sig);
+}
+
+// Handlers run on their own stack, so stack overflow is reported too.
+static bool install_signal_handlers() {
+  static char stack[64 * 1024];
+  stack_t ss = {};
+  ss.ss_sp = stack;
+  ss.ss_size = sizeof(stack);
+  if (sigaltstack(&ss, nullptr) == -1) {
+    return false;
+  }
+  struct sigaction sa = {};
+  sa.sa_handler = on_fatal_signal;
+  sa.sa_flags = SA_ONSTACK | SA_RESETHAND | SA_NODEFER;
+  sigemptyset(&sa.sa_mask);
+  for (int sig : FATAL_SIGNALS) {
+    if (sigaction(sig, &sa, nullptr) == -1) {
+      return false;
+    }
+  }
+  return true;
 }
 
 const char *workspace = nullptr;
 
+static bool verify_writes = false;
+
 static int failure_n = 0;
 static int success_n = 0;
 
 const char *write_buffer;
 char *read_buffer;
 
+std::vector<DIR *> dir_streams;
+// Paths directory streams were opened with, to skip internal entries.
+std::vector<std::string> dir_stream_paths;
+
+// Filesystem internal directories (e.g. `lost+found`) are never listed, so
+// listings of different filesystems are comparable.
+static std::vector<std::regex> internal_dirs;
+std::vector<struct file_handle *> file_handles;
+
+// Any descriptor on tested filesystem, needed to open by handle.
+static int mount_fd = -1;
+
 static uint64_t buffer_hashcode(const char *buffer, size_t len) {
   uint64_t h = 1;
   for (size_t i = 0; i < len; i++) {
-    h = 31 * h + buffer[i];
+    h = 31 * h + (unsigned char)buffer[i];
   }
   return h;
 }
 
+// splitmix64 of offset, so every byte of buffer depends only on seed and its
+// offset (model computes the same bytes)
+static char content_byte(uint64_t seed, uint64_t offset) {
+  uint64_t z = seed + (offset + 1) * 0x9E3779B97F4A7C15ULL;
+  z = (z ^ (z >> 30)) * 0xBF58476D1CE4E5B9ULL;
+  z = (z ^ (z >> 27)) * 0x94D049BB133111EBULL;
+  return (char)(z ^ (z >> 31));
+}
+
 int main(int argc, char *argv[]) {
-  if (argc != 2) {
-    DPRINTF("[USAGE] CMD <workspace>");
+  if (argc == 3 && !strcmp(argv[2], "--verify-writes")) {
+    verify_writes = true;
+  } else if (argc != 2) {
+    DPRINTF("[USAGE] CMD <workspace> [--verify-writes]");
     return ERROR;
   }
 
@@ -123,6 +269,24 @@ int main(int argc, char *argv[]) {
     return ERROR;
   }
 
+  // patterns are separated by newlines
+  if (const char *patterns = getenv("DIFFUZZER_INTERNAL_DIRS")) {
+    std::string pattern;
+    for (const char *c = patterns;; c++) {
+      if (*c == '\n' || *c == '\0') {
+        if (!pattern.empty()) {
+          internal_dirs.emplace_back(pattern);
+        }
+        pattern.clear();
+        if (*c == '\0') {
+          break;
+        }
+      } else {
+        pattern += *c;
+      }
+    }
+  }
+
   GOAL("prepare workspace '%s'", workspace);
   SUBGOAL("mkdir '%s'", workspace);
   if (mkdir(workspace, S_IRWXU | S_IRWXG | S_IROTH | S_IXOTH) == -1) {
@@ -171,13 +335,27 @@ int main(int argc, char *argv[]) {
   auto write_buffer_mut = new char[BUFFER_SIZE];
   write_buffer = write_buffer_mut;
   read_buffer = new char[BUFFER_SIZE];
-  std::default_random_engine gen(RANDOM_SEED);
-  std::uniform_int_distribution<char> dist(0);
   for (size_t i = 0; i < BUFFER_SIZE; i++) {
-    write_buffer_mut[i] = dist(gen);
+    write_buffer_mut[i] = content_byte(content_seed, i);
     read_buffer[i] = 0;
   }
 
+  GOAL("open trace");
+  std::filesystem::path trace_p = "trace.csv";
+  trace_fp = fopen(trace_p.c_str(), "w");
+  if (!trace_fp) {
+    DPRINTF("[ERROR] when opening trace file: %s", strerror(errno));
+    return ERROR;
+  }
+  fprintf(trace_fp, "Index,Command,ReturnCode,Errno,Extra,Duration\n");
+  fflush(trace_fp);
+
+  GOAL("install signal handlers");
+  if (!install_signal_handlers()) {
+    DPRINTF("[ERROR] when installing signal handlers: %s", strerror(errno));
+    return ERROR;
+  }
+
   GOAL("test workload");
   test_workload();
   SUBGOAL("done");
@@ -219,23 +397,11 @@ int main(int argc, char *argv[]) {
     SUBGOAL("done");
   }
 
-  GOAL("dump trace");
-  std::filesystem::path trace_p = "trace.csv";
-  FILE *trace_dump_fp = fopen(trace_p.c_str(), "w");
-  if (!trace_dump_fp) {
-    DPRINTF("[ERROR] when opening trace dump file: %s", strerror(errno));
-    return ERROR;
-  }
-  fprintf(trace_dump_fp, "Index,Command,ReturnCode,Errno,Extra\n");
-  for (const Trace &t : traces) {
-    fprintf(trace_dump_fp, "%4d,%12s,%8d,%s(%d),%s\n", t.idx, t.cmd.c_str(),
-            t.ret_code, strerror(t.err), t.err, t.extra.c_str());
-  }
-  if (!fclose(trace_dump_fp)) {
-    SUBGOAL("trace dump saved at '%s'",
-            std::filesystem::absolute(trace_p).c_str());
+  GOAL("close trace");
+  if (!fclose(trace_fp)) {
+    SUBGOAL("trace saved at '%s'", std::filesystem::absolute(trace_p).c_str());
   } else {
-    DPRINTF("[ERROR] when closing trace dump file: %s", strerror(errno));
+    DPRINTF("[ERROR] when closing trace file: %s", strerror(errno));
     return ERROR;
   }
 
@@ -261,7 +427,21 @@ static std::string path_join(const std::string &prefix,
   return prefix + "/" + file_name;
 }
 
-static int idx = -1;
+static bool is_listed(const std::string &dir, const char *name) {
+  if (!strcmp(name, ".") || !strcmp(name, "..")) {
+    return false;
+  }
+  std::string path = dir.back() == '/' ? dir + name : path_join(dir, name);
+  return std::none_of(internal_dirs.begin(), internal_dirs.end(),
+                      [&](const std::regex &re) {
+                        return std::regex_search(path, re);
+                      });
+}
+
+static void start_op() {
+  idx++;
+  clock_gettime(CLOCK_MONOTONIC, &op_start);
+}
 
 static void success(int status, const char *cmd, std::string extra) {
   append_trace(idx, cmd, status, 0, extra);
@@ -287,8 +467,36 @@ static void minor_failure(const char *cmd, const char *path) {
   DPRINTF("[WARNING] %s('%s') FAIL(%s) <minor>", cmd, path, strerror(errno));
 }
 
+// Inode number of file referenced by descriptor, used to check inode reuse.
+static std::string inode_extra(int fd) {
+  struct stat file_stat;
+  if (fstat(fd, &file_stat) == -1) {
+    minor_failure("FSTAT", std::to_string(fd).c_str());
+    return "";
+  }
+  return "ino=" + std::to_string(file_stat.st_ino);
+}
+
+static long long timespec_ns(const struct timespec &ts) {
+  return ts.tv_sec * 1000000000LL + ts.tv_nsec;
+}
+
+// Access time before and after read along with modification and change time,
+// in nanoseconds.
+static std::string atime_extra(int fd, const struct stat &before) {
+  struct stat after;
+  if (fstat(fd, &after) == -1) {
+    minor_failure("FSTAT", std::to_string(fd).c_str());
+    return "";
+  }
+  return "atime0=" + std::to_string(timespec_ns(before.st_atim)) +
+         " atime=" + std::to_string(timespec_ns(after.st_atim)) +
+         " mtime=" + std::to_string(timespec_ns(after.st_mtim)) +
+         " ctime=" + std::to_string(timespec_ns(after.st_ctim));
+}
+
 int do_mkdir(const char *path, mode_t param) {
-  idx++;
+  start_op();
   int status = mkdir(patch_path(path).c_str(), param);
   if (status == -1) {
     failure(status, MKDIR, path, "");
@@ -299,14 +507,15 @@ int do_mkdir(const char *path, mode_t param) {
 }
 
 int do_create(const char *path, mode_t param) {
-  idx++;
+  start_op();
   int status = creat(patch_path(path).c_str(), param);
   if (status == -1) {
     failure(status, CREATE, path, "");
   } else {
+    std::string extra = inode_extra(status);
     int close_status = close(status);
     if (!close_status) {
-      success(status, CREATE, "");
+      success(status, CREATE, extra);
     } else {
       minor_failure(CLOSE, path);
       failure(status, CREATE, path, "");
@@ -360,7 +569,7 @@ static int remove_dir(const char *p) {
 }
 
 int do_remove(const char *p) {
-  idx++;
+  start_op();
   const std::string path = patch_path(p);
   struct stat file_stat;
   int status = 0;
@@ -391,7 +600,7 @@ int do_remove(const char *p) {
 }
 
 int do_hardlink(const char *old_path, const char *new_path) {
-  idx++;
+  start_op();
   int status = link(patch_path(old_path).c_str(), patch_path(new_path).c_str());
   if (status == -1) {
     failure2(status, HARDLINK, old_path, new_path, "");
@@ -401,42 +610,132 @@ int do_hardlink(const char *old_path, const char *new_path) {
   return status;
 }
 
+// Link counts of both parents, moved subdirectory takes its '..' entry along.
+static std::string parents_nlink_extra(const char *old_path,
+                                       const char *new_path) {
+  std::filesystem::path old_parent =
+      std::filesystem::path(patch_path(old_path)).parent_path();
+  std::filesystem::path new_parent =
+      std::filesystem::path(patch_path(new_path)).parent_path();
+  struct stat old_stat, new_stat;
+  if (lstat(old_parent.c_str(), &old_stat) == -1) {
+    minor_failure(STAT, old_parent.c_str());
+    return "";
+  }
+  if (lstat(new_parent.c_str(), &new_stat) == -1) {
+    minor_failure(STAT, new_parent.c_str());
+    return "";
+  }
+  return "old_parent_nlink=" + std::to_string(old_stat.st_nlink) +
+         " new_parent_nlink=" + std::to_string(new_stat.st_nlink);
+}
+
 int do_rename(const char *old_path, const char *new_path) {
-  idx++;
+  start_op();
   int status =
       rename(patch_path(old_path).c_str(), patch_path(new_path).c_str());
   if (status == -1) {
     failure2(status, RENAME, old_path, new_path, "");
   } else {
-    success(status, RENAME, "");
+    success(status, RENAME, parents_nlink_extra(old_path, new_path));
   }
   return status;
 }
 
-int do_open(const char *path) {
-  idx++;
-  int fd = open(patch_path(path).c_str(), O_RDWR);
+int do_renameat2(const char *old_path, const char *new_path,
+                 unsigned int flags) {
+  start_op();
+  int status = renameat2(AT_FDCWD, patch_path(old_path).c_str(), AT_FDCWD,
+                         patch_path(new_path).c_str(), flags);
+  if (status == -1) {
+    failure2(status, RENAME, old_path, new_path, "");
+  } else {
+    success(status, RENAME, parents_nlink_extra(old_path, new_path));
+  }
+  return status;
+}
+
+// Socket node is created by binding Unix domain socket to path, socket itself
+// is closed right away (node stays).
+int do_mksocket(const char *p) {
+  start_op();
+  const std::string path = patch_path(p);
+  struct sockaddr_un addr;
+  memset(&addr, 0, sizeof(addr));
+  addr.sun_family = AF_UNIX;
+  if (path.size() >= sizeof(addr.sun_path)) {
+    errno = ENAMETOOLONG;
+    failure(-1, MKSOCKET, p, "");
+    return -1;
+  }
+  strcpy(addr.sun_path, path.c_str());
+  int sock = socket(AF_UNIX, SOCK_STREAM, 0);
+  if (sock == -1) {
+    failure(sock, MKSOCKET, p, "");
+    return -1;
+  }
+  int status = bind(sock, (struct sockaddr *)&addr, sizeof(addr));
+  if (status == -1) {
+    failure(status, MKSOCKET, p, "");
+  } else {
+    success(status, MKSOCKET, "");
+  }
+  if (close(sock)) {
+    minor_failure(CLOSE, p);
+  }
+  return status;
+}
+
+int do_open(const char *path, int flags) {
+  start_op();
+  // mode is only used with O_CREAT, same as empty mode of created file in model
+  int fd = open(patch_path(path).c_str(), O_RDWR | flags, 0);
   if (fd == -1) {
     failure(fd, OPEN, path, "");
   } else {
-    success(fd, OPEN, "");
+    success(fd, OPEN, inode_extra(fd));
   }
   return fd;
 }
 
 int do_close(int fd) {
-  idx++;
+  start_op();
+  std::string extra = inode_extra(fd);
   int status = close(fd);
   if (status == -1) {
     failure(status, CLOSE, std::to_string(fd).c_str(), "");
   } else {
-    success(status, CLOSE, "");
+    success(status, CLOSE, extra);
   }
   return status;
 }
 
+// Reads back just written range (without moving file offset) and compares it
+// with source buffer.
+static std::string verify_write(int fd, size_t src_offset, size_t size) {
+  off_t end = lseek(fd, 0, SEEK_CUR);
+  if (end == -1) {
+    minor_failure("LSEEK", std::to_string(fd).c_str());
+    return "verify=error";
+  }
+  ssize_t nr = pread(fd, read_buffer, size, end - size);
+  if (nr == -1) {
+    minor_failure("PREAD", std::to_string(fd).c_str());
+    return "verify=error";
+  }
+  for (ssize_t i = 0; i < nr; i++) {
+    if (read_buffer[i] != write_buffer[src_offset + i]) {
+      return "verify=mismatch@" + std::to_string(i);
+    }
+  }
+  if (std::cmp_less(nr, size)) {
+    return "verify=short@" + std::to_string(nr);
+  }
+  return "verify=ok";
+}
+
 int do_write(int fd, size_t src_offset, size_t size) {
-  idx++;
+  start_op();
   if (src_offset + size > BUFFER_SIZE) {
     DPRINTF(
         "[ERROR] offset %ld + %ld is too big to write from (buffer size is %d)",
@@ -448,18 +747,20 @@ int do_write(int fd, size_t src_offset, size_t size) {
     failure(nw, WRITE, std::to_string(fd).c_str(), "");
     return -1;
   } else {
-    success(nw, WRITE, "");
+    success(nw, WRITE, verify_writes ? verify_write(fd, src_offset, nw) : "");
     return nw;
   }
 }
 
 int do_read(int fd, size_t size) {
-  idx++;
+  start_op();
   if (size > BUFFER_SIZE) {
     DPRINTF("[ERROR] size %ld is too big to read to (buffer size is %d)", size,
             BUFFER_SIZE);
     exit(ERROR);
   }
+  struct stat before;
+  bool has_before = fstat(fd, &before) != -1;
   int nr = read(fd, read_buffer, size);
   if (nr == -1 || std::cmp_greater(nr, size)) {
     failure(nr, READ, std::to_string(fd).c_str(), "");
@@ -467,13 +768,16 @@ int do_read(int fd, size_t size) {
   } else {
     std::stringstream extra;
     extra << "hash=" << std::hex << buffer_hashcode(read_buffer, nr);
+    if (has_before) {
+      extra << " " << atime_extra(fd, before);
+    }
     success(nr, READ, extra.str());
     return nr;
   }
 }
 
 int do_fsync(int fd) {
-  idx++;
+  start_op();
   int status = fsync(fd);
   if (status == -1) {
     failure(status, FSYNC, std::to_string(fd).c_str(), "");
@@ -482,3 +786,438 @@ int do_fsync(int fd) {
   }
   return status;
 }
+
+int do_fdatasync(int fd) {
+  start_op();
+  int status = fdatasync(fd);
+  if (status == -1) {
+    failure(status, FDATASYNC, std::to_string(fd).c_str(), "");
+  } else {
+    success(status, FDATASYNC, "");
+  }
+  return status;
+}
+
+int do_sync_file_range(int fd, off_t offset, off_t nbytes, unsigned int flags) {
+  start_op();
+  int status = sync_file_range(fd, offset, nbytes, flags);
+  if (status == -1) {
+    failure(status, SYNCFILERANGE, std::to_string(fd).c_str(), "");
+  } else {
+    success(status, SYNCFILERANGE, "");
+  }
+  return status;
+}
+
+int do_lseek(int fd, off_t offset, int whence) {
+  start_op();
+  off_t status = lseek(fd, offset, whence);
+  if (status == -1) {
+    failure(-1, LSEEK, std::to_string(fd).c_str(), "");
+  } else {
+    success(0, LSEEK, "offset=" + std::to_string(status));
+  }
+  return status == -1 ? -1 : 0;
+}
+
+static DIR *get_dir_stream(int ds) {
+  if (ds < 0 || std::cmp_greater_equal(ds, dir_streams.size()) ||
+      !dir_streams[ds]) {
+    errno = EBADF;
+    return nullptr;
+  }
+  return dir_streams[ds];
+}
+
+int do_opendir(const char *path) {
+  start_op();
+  DIR *d = opendir(patch_path(path).c_str());
+  if (!d) {
+    failure(-1, OPENDIR, path, "");
+    return -1;
+  }
+  int ds = dir_streams.size();
+  dir_streams.push_back(d);
+  dir_stream_paths.push_back(path);
+  success(ds, OPENDIR, "");
+  return ds;
+}
+
+int do_readdir(int ds, size_t count) {
+  start_op();
+  DIR *d = get_dir_stream(ds);
+  if (!d) {
+    failure(-1, READDIR, std::to_string(ds).c_str(), "");
+    return -1;
+  }
+  std::string entries;
+  bool eof = false;
+  size_t n = 0;
+  while (n < count) {
+    errno = 0;
+    struct dirent *entry = readdir(d);
+    if (!entry) {
+      if (errno) {
+        failure(-1, READDIR, std::to_string(ds).c_str(), "");
+        return -1;
+      }
+      eof = true;
+      break;
+    }
+    if (!is_listed(dir_stream_paths[ds], entry->d_name)) {
+      continue;
+    }
+    if (n > 0) {
+      entries += ":";
+    }
+    entries += entry->d_name;
+    n++;
+  }
+  std::string extra = "stream=" + std::to_string(ds) + " entries=" + entries;
+  if (eof) {
+    extra += " eof";
+  }
+  success(n, READDIR, extra);
+  return n;
+}
+
+int do_closedir(int ds) {
+  start_op();
+  DIR *d = get_dir_stream(ds);
+  int status = d ? closedir(d) : -1;
+  if (status == -1) {
+    failure(status, CLOSEDIR, std::to_string(ds).c_str(), "");
+  } else {
+    dir_streams[ds] = nullptr;
+    success(status, CLOSEDIR, "");
+  }
+  return status;
+}
+
+// Entries are recorded in order they are returned, trace comparison ignores it.
+int do_listdir(const char *path) {
+  start_op();
+  DIR *d = opendir(patch_path(path).c_str());
+  if (!d) {
+    failure(-1, LISTDIR, path, "");
+    return -1;
+  }
+  std::string entries;
+  int n = 0;
+  while (true) {
+    errno = 0;
+    struct dirent *entry = readdir(d);
+    if (!entry) {
+      break;
+    }
+    if (!is_listed(path, entry->d_name)) {
+      continue;
+    }
+    if (n > 0) {
+      entries += ":";
+    }
+    entries += entry->d_name;
+    n++;
+  }
+  if (errno) {
+    int saved = errno;
+    closedir(d);
+    errno = saved;
+    failure(-1, LISTDIR, path, "");
+    return -1;
+  }
+  closedir(d);
+  success(n, LISTDIR, "entries=" + entries);
+  return n;
+}
+
+int do_name_to_handle(const char *path) {
+  start_op();
+  struct file_handle *fh =
+      (struct file_handle *)malloc(sizeof(struct file_handle) + MAX_HANDLE_SZ);
+  fh->handle_bytes = MAX_HANDLE_SZ;
+  int mount_id;
+  int status =
+      name_to_handle_at(AT_FDCWD, patch_path(path).c_str(), fh, &mount_id, 0);
+  if (status == -1) {
+    free(fh);
+    failure(status, NAMETOHANDLE, path, "");
+    return -1;
+  }
+  int handle = file_handles.size();
+  file_handles.push_back(fh);
+  success(handle, NAMETOHANDLE, "");
+  return handle;
+}
+
+// Opened with O_PATH, so handle of any kind of node can be resolved.
+int do_open_by_handle(int fh) {
+  start_op();
+  if (fh < 0 || std::cmp_greater_equal(fh, file_handles.size())) {
+    errno = EBADF;
+    failure(-1, OPENBYHANDLE, std::to_string(fh).c_str(), "");
+    return -1;
+  }
+  if (mount_fd == -1) {
+    mount_fd = open(workspace, O_RDONLY | O_DIRECTORY);
+    if (mount_fd == -1) {
+      failure(-1, OPENBYHANDLE, workspace, "");
+      return -1;
+    }
+  }
+  int fd = open_by_handle_at(mount_fd, file_handles[fh], O_PATH);
+  if (fd == -1) {
+    failure(fd, OPENBYHANDLE, std::to_string(fh).c_str(), "");
+    return -1;
+  }
+  std::string extra = inode_extra(fd);
+  if (close(fd)) {
+    minor_failure(CLOSE, std::to_string(fh).c_str());
+  }
+  success(0, OPENBYHANDLE, extra);
+  return 0;
+}
+
+// File gets its final size with a single ftruncate, then it is filled with
+// write buffer repeated, so content is the same as after that many writes.
+int do_prealloc(const char *path, size_t size) {
+  start_op();
+  int fd = open(patch_path(path).c_str(), O_WRONLY);
+  if (fd == -1) {
+    failure(fd, PREALLOC, path, "");
+    return -1;
+  }
+  if (ftruncate(fd, size) == -1) {
+    failure(-1, PREALLOC, path, "");
+    close(fd);
+    return -1;
+  }
+  size_t filled = 0;
+  while (filled < size) {
+    size_t chunk = std::min(size - filled, (size_t)BUFFER_SIZE);
+    ssize_t nw = pwrite(fd, write_buffer, chunk, filled);
+    if (nw <= 0) {
+      if (nw == 0) {
+        errno = EIO;
+      }
+      failure(-1, PREALLOC, path, "");
+      close(fd);
+      return -1;
+    }
+    filled += nw;
+  }
+  if (close(fd)) {
+    minor_failure(CLOSE, path);
+  }
+  success(0, PREALLOC, "");
+  return 0;
+}
+
+int do_truncate(const char *path, off_t size) {
+  start_op();
+  int status = truncate(patch_path(path).c_str(), size);
+  if (status == -1) {
+    failure(status, TRUNCATE, path, "");
+  } else {
+    success(status, TRUNCATE, "");
+  }
+  return status;
+}
+
+int do_chmod(const char *path, mode_t mode) {
+  start_op();
+  int status = chmod(patch_path(path).c_str(), mode);
+  if (status == -1) {
+    failure(status, CHMOD, path, "");
+  } else {
+    success(status, CHMOD, "");
+  }
+  return status;
+}
+
+// Device nodes get device number 0, they are never opened.
+int do_mknod(const char *path, mode_t type, mode_t mode) {
+  start_op();
+  int status = mknod(patch_path(path).c_str(), type | mode, 0);
+  if (status == -1) {
+    failure(status, MKNOD, path, "");
+  } else {
+    success(status, MKNOD, "");
+  }
+  return status;
+}
+
+// Value is taken from the start of write buffer.
+int do_setxattr(const char *path, const char *name, size_t size) {
+  start_op();
+  if (size > BUFFER_SIZE) {
+    DPRINTF("[ERROR] size %ld is too big to set from (buffer size is %d)", size,
+            BUFFER_SIZE);
+    exit(ERROR);
+  }
+  int status =
+      setxattr(patch_path(path).c_str(), name, write_buffer, size, 0);
+  if (status == -1) {
+    failure(status, SETXATTR, path, "");
+  } else {
+    success(status, SETXATTR, "");
+  }
+  return status;
+}
+
+int do_removexattr(const char *path, const char *name) {
+  start_op();
+  int status = removexattr(patch_path(path).c_str(), name);
+  if (status == -1) {
+    failure(status, REMOVEXATTR, path, "");
+  } else {
+    success(status, REMOVEXATTR, "");
+  }
+  return status;
+}
+
+// Target is stored as is, it is not patched to point inside of mount point.
+int do_symlink(const char *target, const char *path) {
+  start_op();
+  int status = symlink(target, patch_path(path).c_str());
+  if (status == -1) {
+    failure(status, SYMLINK, path, "");
+  } else {
+    success(status, SYMLINK, "");
+  }
+  return status;
+}
+
+// Raw target is reported, bytes that would break trace (separators, non
+// printable) are escaped as \xNN.
+int do_readlink(const char *path) {
+  start_op();
+  char target[PATH_MAX];
+  ssize_t n = readlink(patch_path(path).c_str(), target, sizeof(target));
+  if (n == -1) {
+    failure(-1, READLINK, path, "");
+    return -1;
+  }
+  std::string escaped;
+  for (ssize_t i = 0; i < n; i++) {
+    unsigned char c = target[i];
+    if (c > ' ' && c < 0x7f && c != ',' && c != '\\') {
+      escaped += (char)c;
+    } else {
+      char hex[5];
+      snprintf(hex, sizeof(hex), "\\x%02x", c);
+      escaped += hex;
+    }
+  }
+  success(0, READLINK, "target=" + escaped);
+  return 0;
+}
+
+int do_utimens(const char *path, time_t atime, time_t mtime) {
+  start_op();
+  struct timespec times[2] = {{atime, 0}, {mtime, 0}};
+  int status = utimensat(AT_FDCWD, patch_path(path).c_str(), times,
+                         AT_SYMLINK_NOFOLLOW);
+  if (status == -1) {
+    failure(status, UTIMENS, path, "");
+  } else {
+    success(status, UTIMENS, "");
+  }
+  return status;
+}
+
+int do_stat(const char *path) {
+  start_op();
+  struct stat st;
+  int status = lstat(patch_path(path).c_str(), &st);
+  if (status == -1) {
+    failure(status, STAT, path, "");
+  } else {
+    std::stringstream extra;
+    extra << "ino=" << st.st_ino << " st_size=" << st.st_size
+          << " st_blocks=" << st.st_blocks << " st_mode=" << std::oct
+          << st.st_mode << std::dec << " st_nlink=" << st.st_nlink;
+    success(status, STAT, extra.str());
+  }
+  return status;
+}
+
+int do_fallocate(int fd, off_t offset, off_t len, int mode) {
+  start_op();
+  int status = fallocate(fd, mode, offset, len);
+  if (status == -1) {
+    failure(status, FALLOCATE, std::to_string(fd).c_str(), "");
+  } else {
+    success(status, FALLOCATE, "");
+  }
+  return status;
+}
+
+// Simulated power cut: filesystem is shut down without writing back data,
+// everything opened on it is closed and it is remounted with command fuzzer
+// passes in DIFFUZZER_REMOUNT environment variable, so only data that was
+// synced is guaranteed to be there. Journal is committed before shutdown, since
+// model does not track durability of names. Filesystems that can not be shut
+// down are only remounted.
+int do_crashpoint() {
+  start_op();
+  for (auto &d : dir_streams) {
+    if (d) {
+      closedir(d);
+      d = nullptr;
+    }
+  }
+  if (mount_fd != -1) {
+    close(mount_fd);
+    mount_fd = -1;
+  }
+  struct stat workspace_stat;
+  if (stat(workspace, &workspace_stat) == -1) {
+    failure(-1, CRASHPOINT, workspace, "");
+    return -1;
+  }
+  // trace and kcov descriptors are on other devices
+  std::vector<int> fds;
+  for (const auto &entry :
+       std::filesystem::directory_iterator("/proc/self/fd")) {
+    int fd = std::stoi(entry.path().filename());
+    struct stat fd_stat;
+    if (fstat(fd, &fd_stat) == 0 && fd_stat.st_dev == workspace_stat.st_dev) {
+      fds.push_back(fd);
+    }
+  }
+  int shutdown_fd = open(workspace, O_RDONLY | O_DIRECTORY);
+  if (shutdown_fd == -1) {
+    failure(-1, CRASHPOINT, workspace, "");
+    return -1;
+  }
+  uint32_t shutdown_flags = FS_SHUTDOWN_FLAGS_LOGFLUSH;
+  if (ioctl(shutdown_fd, FS_IOC_SHUTDOWN, &shutdown_flags) == -1) {
+    minor_failure("SHUTDOWN", workspace);
+  }
+  if (close(shutdown_fd)) {
+    minor_failure(CLOSE, workspace);
+  }
+  for (int fd : fds) {
+    if (close(fd)) {
+      minor_failure(CLOSE, std::to_string(fd).c_str());
+    }
+  }
+  const char *remount = getenv("DIFFUZZER_REMOUNT");
+  if (!remount) {
+    errno = ENOTSUP;
+    failure(-1, CRASHPOINT, workspace, "");
+    return -1;
+  }
+  fflush(stdout);
+  int status = system(remount);
+  if (status != 0) {
+    errno = EIO;
+    failure(-1, CRASHPOINT, remount, "");
+    return -1;
+  }
+  success(0, CRASHPOINT, "");
+  return 0;
+}
+
+int do_delay(unsigned int us) { return usleep(us); }
@@ -1,24 +1,52 @@
 #pragma once
 
 #include <fcntl.h>
+#include <linux/falloc.h>
 #include <sys/stat.h>
 
 #include <cerrno>
+#include <cstdint>
 #include <cstdio>
 #include <cstdlib>
 #include <cstring>
 
 extern "C" {
 void test_workload();
+// write buffer is filled from it, defined along with workload
+extern const uint64_t content_seed;
 
 int do_mkdir(const char *path, mode_t param);
 int do_create(const char *path, mode_t param);
 int do_remove(const char *path);
+int do_mksocket(const char *path);
+int do_symlink(const char *target, const char *path);
+int do_readlink(const char *path);
 int do_hardlink(const char *old_path, const char *new_path);
 int do_rename(const char *old_path, const char *new_path);
-int do_open(const char *path);
+int do_renameat2(const char *old_path, const char *new_path, unsigned int flags);
+int do_open(const char *path, int flags);
 int do_close(int fd);
 int do_write(int fd, size_t src_offset, size_t size);
 int do_read(int fd, size_t size);
 int do_fsync(int fd);
+int do_fdatasync(int fd);
+int do_sync_file_range(int fd, off_t offset, off_t nbytes, unsigned int flags);
+int do_lseek(int fd, off_t offset, int whence);
+int do_opendir(const char *path);
+int do_readdir(int ds, size_t count);
+int do_closedir(int ds);
+int do_listdir(const char *path);
+int do_name_to_handle(const char *path);
+int do_open_by_handle(int fh);
+int do_prealloc(const char *path, size_t size);
+int do_truncate(const char *path, off_t size);
+int do_chmod(const char *path, mode_t mode);
+int do_mknod(const char *path, mode_t type, mode_t mode);
+int do_setxattr(const char *path, const char *name, size_t size);
+int do_removexattr(const char *path, const char *name);
+int do_utimens(const char *path, time_t atime, time_t mtime);
+int do_stat(const char *path);
+int do_fallocate(int fd, off_t offset, off_t len, int mode);
+int do_crashpoint();
+int do_delay(unsigned int us);
 }
@@ -1,5 +1,7 @@
 #include "executor.h"
 
+const uint64_t content_seed = 0;
+
 int fd_0, fd_1;
 
 void test_workload()
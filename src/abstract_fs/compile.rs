@@ -2,20 +2,29 @@ use std::{fs, path::Path, process::Command};
 
 use anyhow::{bail, Context};
 
-use super::workload::Workload;
+use super::{encode::Delays, workload::Workload};
 
 pub const TEST_SOURCE_FILENAME: &str = "test.c";
 pub const TEST_EXE_FILENAME: &str = "test.out";
 
 impl Workload {
-    pub fn compile(&self, dir: &Path) -> anyhow::Result<Box<Path>> {
-        let encoded = self.encode_c();
+    /// Source is not rewritten if it is the same, so `make` reuses object files from the previous build.
+    pub fn compile(
+        &self,
+        dir: &Path,
+        delays: Option<&Delays>,
+        jobs: u8,
+    ) -> anyhow::Result<Box<Path>> {
+        let encoded = self.encode_c(delays);
         let test_path = dir.join(TEST_SOURCE_FILENAME);
         let test_exec = dir.join(TEST_EXE_FILENAME);
-        fs::write(&test_path, encoded)
-            .with_context(|| format!("failed to write test source at '{}'", test_path.display()))?;
-        let mut make = Command::new("make");
-        make.arg("-C").arg(dir.as_os_str());
+        let unchanged = fs::read_to_string(&test_path).is_ok_and(|source| source == encoded);
+        if !unchanged {
+            fs::write(&test_path, encoded).with_context(|| {
+                format!("failed to write test source at '{}'", test_path.display())
+            })?;
+        }
+        let mut make = make_command(dir, jobs);
         let output = make.output().with_context(|| {
             format!(
                 "failed to run makefile command at '{}': '{:?}'",
@@ -32,3 +41,27 @@ impl Workload {
         Ok(test_exec.into_boxed_path())
     }
 }
+
+fn make_command(dir: &Path, jobs: u8) -> Command {
+    let mut make = Command::new("make");
+    make.arg("-C").arg(dir.as_os_str());
+    make.arg(format!("-j{}", jobs.max(1)));
+    make
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_make_jobs() {
+        let args = |jobs| -> Vec<String> {
+            make_command(Path::new("/tmp/test"), jobs)
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect()
+        };
+        assert_eq!(vec!["-C", "/tmp/test", "-j4"], args(4));
+        assert_eq!(vec!["-C", "/tmp/test", "-j1"], args(0));
+    }
+}
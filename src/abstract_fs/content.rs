@@ -2,10 +2,23 @@ use std::{cmp::max, collections::VecDeque};
 
 use thiserror::Error;
 
+/// Size of executor buffer that writes copy data from.
+pub const WRITE_BUFFER_SIZE: u64 = 1024 * 1024;
+
+/// Byte at `offset` of executor write buffer filled with `seed` (splitmix64 of offset, as in executor).
+pub fn source_byte(seed: u64, offset: u64) -> u8 {
+    let mut z = seed.wrapping_add((offset + 1).wrapping_mul(0x9E3779B97F4A7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    (z ^ (z >> 31)) as u8
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct SourceSlice {
     pub from: u64,
     pub to: u64,
+    /// Zeroes that were never written (e.g. after extending truncate), range is meaningless.
+    pub hole: bool,
 }
 
 impl SourceSlice {
@@ -18,6 +31,15 @@ impl SourceSlice {
     }
 }
 
+/// Region of content as seen by reader, with offset from the start of content.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Extent {
+    /// Written data.
+    Data { offset: u64, size: u64 },
+    /// Region that was never written, reads as zeroes.
+    Hole { offset: u64, size: u64 },
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Content {
     slices: VecDeque<SourceSlice>,
@@ -36,32 +58,76 @@ impl Content {
         }
     }
 
+    /// Write buffer repeated until content is `size` bytes long.
+    pub fn pattern(size: u64) -> Self {
+        let mut content = Content::new();
+        let mut left = size;
+        while left > 0 {
+            let chunk = left.min(WRITE_BUFFER_SIZE);
+            content.write_back(0, chunk);
+            left -= chunk;
+        }
+        content
+    }
+
     pub fn slices(&self) -> Vec<SourceSlice> {
         self.slices.iter().map(|s| s.to_owned()).collect()
     }
 
     pub fn write_back(&mut self, src_offset: u64, size: u64) {
+        self.push_back(src_offset, size, false);
+    }
+
+    fn push_back(&mut self, from: u64, size: u64, hole: bool) {
         if size > 0 {
             self.slices.push_back(SourceSlice {
-                from: src_offset,
-                to: src_offset + size - 1,
+                from,
+                to: from + size - 1,
+                hole,
             });
         }
     }
 
+    /// Drops data past `new_size` or extends content with a hole.
+    pub fn truncate(&mut self, new_size: u64) {
+        let size = self.size();
+        if new_size >= size {
+            self.push_back(0, new_size - size, true);
+            return;
+        }
+        let mut kept = 0;
+        let mut keep_slices = 0;
+        for slice in self.slices.iter_mut() {
+            if kept == new_size {
+                break;
+            }
+            keep_slices += 1;
+            let left = new_size - kept;
+            if slice.size() > left {
+                slice.to = slice.from + left - 1;
+            }
+            kept += slice.size();
+        }
+        self.slices.truncate(keep_slices);
+    }
+
     pub fn write(
         &mut self,
         src_offset: u64,
         write_offset: u64,
         size: u64,
     ) -> Result<(), ContentError> {
+        if write_offset > self.size() {
+            if size == 0 {
+                return Ok(());
+            }
+            // gap between end of file and written data reads as zeroes
+            self.truncate(write_offset);
+        }
         if write_offset == self.size() {
             self.write_back(src_offset, size);
             return Ok(());
         }
-        if write_offset > self.size() {
-            return Err(ContentError::BadOffset(write_offset, self.size()));
-        }
         let old_size = self.size();
         if size > 0 {
             let mut curr_offset = 0;
@@ -74,6 +140,7 @@ impl Content {
                     break;
                 } else if next_offset > write_offset {
                     let old_size = slice.size();
+                    let hole = slice.hole;
                     let fst_half_to = slice.from + (write_offset - curr_offset - 1);
                     let snd_half_to = slice.to;
                     slice.to = fst_half_to;
@@ -82,6 +149,7 @@ impl Content {
                         SourceSlice {
                             from: fst_half_to + 1,
                             to: snd_half_to,
+                            hole,
                         },
                     );
                     let fst_half_size = self.slices[i].size();
@@ -103,6 +171,7 @@ impl Content {
                 SourceSlice {
                     from: src_offset,
                     to: src_offset + size - 1,
+                    hole: false,
                 },
             );
             let truncate_from_index = write_at_index + 1;
@@ -136,6 +205,32 @@ impl Content {
         Ok(())
     }
 
+    /// Replaces `size` bytes at `offset` with a hole (used by punch hole and zero range).
+    ///
+    /// Range past the end of file grows content, unless `keep_size` is set.
+    pub fn zero_range(&mut self, offset: u64, size: u64, keep_size: bool) {
+        let old_size = self.size();
+        let new_size = if keep_size {
+            old_size
+        } else {
+            max(old_size, offset + size)
+        };
+        let hole_end = (offset + size).min(new_size);
+        let mut content = self.read(0, offset.min(old_size)).unwrap();
+        content.truncate(hole_end);
+        if hole_end < old_size {
+            let tail = self.read(hole_end, old_size - hole_end).unwrap();
+            content.slices.extend(tail.slices);
+        }
+        assert!(
+            content.size() == new_size,
+            "new_size = {}, expected_size = {}",
+            content.size(),
+            new_size
+        );
+        *self = content;
+    }
+
     pub fn read(&self, offset: u64, size: u64) -> Result<Content, ContentError> {
         if offset > self.size() {
             return Err(ContentError::BadOffset(offset, self.size()));
@@ -155,7 +250,7 @@ impl Content {
                 if read_size + slice_read_size > size {
                     slice_read_size = size - read_size;
                 }
-                content.write_back(s.from, slice_read_size);
+                content.push_back(s.from, slice_read_size, s.hole);
                 read_size += slice_read_size;
             } else {
                 let next_offset = current_offset + s.size();
@@ -166,7 +261,7 @@ impl Content {
                     if slice_read_size > size {
                         slice_read_size = size;
                     }
-                    content.write_back(s.from + read_from, slice_read_size);
+                    content.push_back(s.from + read_from, slice_read_size, s.hole);
                     read_size += slice_read_size;
                     continue;
                 }
@@ -186,13 +281,56 @@ impl Content {
     pub fn size(&self) -> u64 {
         self.slices.iter().fold(0, |acc: u64, s| acc + s.size())
     }
+
+    /// Bytes backed by written data, unlike [`Content::size`] holes are not counted.
+    pub fn data_size(&self) -> u64 {
+        self.slices
+            .iter()
+            .filter(|s| !s.hole)
+            .fold(0, |acc: u64, s| acc + s.size())
+    }
+
+    /// Bytes reader sees when write buffer is filled with `seed`, holes read as zeroes.
+    pub fn bytes(&self, seed: u64) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.size() as usize);
+        for s in self.slices.iter() {
+            if s.hole {
+                bytes.resize(bytes.len() + s.size() as usize, 0);
+            } else {
+                bytes.extend((s.from..=s.to).map(|offset| source_byte(seed, offset)));
+            }
+        }
+        bytes
+    }
+
+    /// Data and holes in order, neighbouring slices of the same kind are merged.
+    pub fn extents(&self) -> Vec<Extent> {
+        let mut extents: Vec<Extent> = vec![];
+        let mut offset = 0;
+        for s in self.slices.iter() {
+            match (extents.last_mut(), s.hole) {
+                (Some(Extent::Data { size, .. }), false)
+                | (Some(Extent::Hole { size, .. }), true) => *size += s.size(),
+                (_, false) => extents.push(Extent::Data {
+                    offset,
+                    size: s.size(),
+                }),
+                (_, true) => extents.push(Extent::Hole {
+                    offset,
+                    size: s.size(),
+                }),
+            }
+            offset += s.size();
+        }
+        extents
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::abstract_fs::content::ContentError;
 
-    use super::Content;
+    use super::{source_byte, Content, Extent, SourceSlice, WRITE_BUFFER_SIZE};
 
     #[test]
     fn test_read_empty() {
@@ -249,9 +387,33 @@ mod tests {
     }
 
     #[test]
-    fn test_write_bad_offset() {
+    fn test_write_past_end() {
         let mut content = Content::new();
-        assert_eq!(Err(ContentError::BadOffset(42, 0)), content.write(0, 42, 0));
+        content.write(0, 42, 0).unwrap();
+        assert_eq!(Content::new(), content);
+        content.write_back(7, 10);
+        content.write(13, 42, 5).unwrap();
+        assert_eq!(
+            vec![
+                SourceSlice {
+                    from: 7,
+                    to: 16,
+                    hole: false
+                },
+                SourceSlice {
+                    from: 0,
+                    to: 31,
+                    hole: true
+                },
+                SourceSlice {
+                    from: 13,
+                    to: 17,
+                    hole: false
+                },
+            ],
+            content.slices()
+        );
+        assert_eq!(47, content.size());
     }
 
     #[test]
@@ -291,6 +453,81 @@ mod tests {
         assert_eq!(expected, content)
     }
 
+    #[test]
+    fn test_pattern() {
+        assert_eq!(Content::new(), Content::pattern(0));
+        let mut expected = Content::new();
+        expected.write_back(0, WRITE_BUFFER_SIZE);
+        expected.write_back(0, WRITE_BUFFER_SIZE);
+        expected.write_back(0, 10);
+        let content = Content::pattern(2 * WRITE_BUFFER_SIZE + 10);
+        assert_eq!(expected, content);
+        let mut expected = Content::new();
+        expected.write_back(WRITE_BUFFER_SIZE - 5, 5);
+        expected.write_back(0, 5);
+        assert_eq!(expected, content.read(WRITE_BUFFER_SIZE - 5, 10).unwrap());
+    }
+
+    #[test]
+    fn test_truncate() {
+        let mut content = Content::new();
+        content.write_back(42, 100);
+        content.write_back(1, 2);
+        content.truncate(101);
+        let mut expected = Content::new();
+        expected.write_back(42, 100);
+        expected.write_back(1, 1);
+        assert_eq!(expected, content);
+        content.truncate(50);
+        let mut expected = Content::new();
+        expected.write_back(42, 50);
+        assert_eq!(expected, content);
+        content.truncate(0);
+        assert_eq!(Content::new(), content);
+    }
+
+    #[test]
+    fn test_truncate_extend() {
+        let mut content = Content::new();
+        content.write_back(42, 100);
+        content.truncate(1000);
+        assert_eq!(1000, content.size());
+        assert_eq!(
+            vec![
+                SourceSlice {
+                    from: 42,
+                    to: 141,
+                    hole: false
+                },
+                SourceSlice {
+                    from: 0,
+                    to: 899,
+                    hole: true
+                }
+            ],
+            content.slices()
+        );
+        content.write(7, 500, 10).unwrap();
+        let read = content.read(95, 10).unwrap();
+        assert_eq!(
+            vec![
+                SourceSlice {
+                    from: 137,
+                    to: 141,
+                    hole: false
+                },
+                SourceSlice {
+                    from: 0,
+                    to: 4,
+                    hole: true
+                }
+            ],
+            read.slices()
+        );
+        assert!(content.slices()[3].hole);
+        assert_eq!(1000, content.size());
+    }
+
     #[test]
     fn test_write_overwrite() {
         let mut content = Content::new();
@@ -300,4 +537,185 @@ mod tests {
         expected.write_back(512, 100000);
         assert_eq!(expected, content)
     }
+
+    #[test]
+    fn test_read_hole() {
+        let mut content = Content::new();
+        content.write(42, 1000, 24).unwrap();
+        assert_eq!(1024, content.size());
+        assert_eq!(24, content.data_size());
+        let read = content.read(0, 2000).unwrap();
+        assert_eq!(
+            vec![
+                Extent::Hole {
+                    offset: 0,
+                    size: 1000
+                },
+                Extent::Data {
+                    offset: 1000,
+                    size: 24
+                },
+            ],
+            read.extents()
+        );
+        let read = content.read(990, 20).unwrap();
+        assert_eq!(
+            vec![
+                Extent::Hole {
+                    offset: 0,
+                    size: 10
+                },
+                Extent::Data {
+                    offset: 10,
+                    size: 10
+                },
+            ],
+            read.extents()
+        );
+        assert_eq!(10, read.data_size());
+    }
+
+    #[test]
+    fn test_extents_merged() {
+        let mut content = Content::new();
+        content.write_back(1, 10);
+        content.write_back(42, 5);
+        content.truncate(100);
+        content.write(7, 50, 10).unwrap();
+        assert_eq!(
+            vec![
+                Extent::Data {
+                    offset: 0,
+                    size: 15
+                },
+                Extent::Hole {
+                    offset: 15,
+                    size: 35
+                },
+                Extent::Data {
+                    offset: 50,
+                    size: 10
+                },
+                Extent::Hole {
+                    offset: 60,
+                    size: 40
+                },
+            ],
+            content.extents()
+        );
+        assert_eq!(25, content.data_size());
+        assert!(Content::new().extents().is_empty());
+    }
+
+    #[test]
+    fn test_punch_hole_in_middle() {
+        let mut content = Content::new();
+        content.write(42, 0, 100).unwrap();
+        content.zero_range(30, 20, true);
+        assert_eq!(
+            vec![
+                Extent::Data {
+                    offset: 0,
+                    size: 30
+                },
+                Extent::Hole {
+                    offset: 30,
+                    size: 20
+                },
+                Extent::Data {
+                    offset: 50,
+                    size: 50
+                },
+            ],
+            content.extents()
+        );
+        assert_eq!(100, content.size());
+        assert_eq!(80, content.data_size());
+        let mut expected = Content::new();
+        expected.write_back(42, 30);
+        expected.push_back(0, 20, true);
+        expected.write_back(42 + 50, 50);
+        assert_eq!(expected, content);
+        let bytes = content.bytes(7);
+        assert_eq!(vec![0; 20], bytes[30..50].to_vec());
+        assert_eq!(source_byte(7, 42 + 50), bytes[50]);
+    }
+
+    #[test]
+    fn test_zero_range_keep_size() {
+        let mut content = Content::new();
+        content.write_back(42, 100);
+        content.zero_range(90, 100, true);
+        assert_eq!(100, content.size());
+        assert_eq!(90, content.data_size());
+        content.zero_range(200, 100, true);
+        assert_eq!(100, content.size());
+        content.zero_range(0, 100, true);
+        assert_eq!(0, content.data_size());
+        assert_eq!(100, content.size());
+    }
+
+    #[test]
+    fn test_zero_range_grow() {
+        let mut content = Content::new();
+        content.write_back(42, 100);
+        content.zero_range(90, 20, false);
+        assert_eq!(
+            vec![
+                Extent::Data {
+                    offset: 0,
+                    size: 90
+                },
+                Extent::Hole {
+                    offset: 90,
+                    size: 20
+                },
+            ],
+            content.extents()
+        );
+        content.zero_range(200, 10, false);
+        assert_eq!(210, content.size());
+        assert_eq!(90, content.data_size());
+    }
+
+    #[test]
+    fn test_source_bytes() {
+        // same as executor fills its buffer with (splitmix64)
+        assert_eq!(
+            vec![0xaf, 0xf4, 0x4f, 0xec],
+            (0..4)
+                .map(|offset| source_byte(0, offset))
+                .collect::<Vec<u8>>()
+        );
+        assert_eq!(
+            vec![0x95, 0x03, 0x52, 0x94],
+            (0..4)
+                .map(|offset| source_byte(42, offset))
+                .collect::<Vec<u8>>()
+        );
+    }
+
+    #[test]
+    fn test_write_read_bytes() {
+        let seed = 42;
+        let source = |from: u64, size: u64| -> Vec<u8> {
+            (from..from + size)
+                .map(|offset| source_byte(seed, offset))
+                .collect()
+        };
+        let mut content = Content::new();
+        content.write(100, 0, 10).unwrap();
+        content.write(500, 20, 10).unwrap();
+        content.write(7, 5, 10).unwrap();
+        let mut expected = source(100, 5);
+        expected.extend(source(7, 10));
+        expected.extend(vec![0; 5]);
+        expected.extend(source(500, 10));
+        assert_eq!(expected, content.bytes(seed));
+        assert_eq!(
+            expected[3..23].to_vec(),
+            content.read(3, 20).unwrap().bytes(seed)
+        );
+        assert_ne!(expected, content.bytes(seed + 1));
+    }
 }
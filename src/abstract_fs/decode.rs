@@ -0,0 +1,1061 @@
+use log::warn;
+use thiserror::Error;
+
+use super::{
+    encode::CONTENT_SEED_DECL,
+    flags::{
+        FallocateFlag, FallocateFlags, Mode, ModeFlag, OpenFlag, OpenFlags, RenameFlag,
+        RenameFlags, SpecialKind, SyncFileRangeFlag, SyncFileRangeFlags, Whence, OPEN_FLAGS,
+    },
+    fs::AbstractFS,
+    generator::{PERMISSION_BITS, SPECIAL_BITS},
+    node::{DirStreamIndex, FileDescriptorIndex, FileHandleIndex},
+    operation::Operation,
+    pathname::PathName,
+    workload::Workload,
+};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum DecodeError {
+    #[error("unknown call '{0}'")]
+    UnknownCall(String),
+    #[error("invalid arguments '{0}'")]
+    InvalidArguments(String),
+    #[error("invalid variable '{0}'")]
+    InvalidVariable(String),
+    #[error("invalid mode '{0}'")]
+    InvalidMode(String),
+    #[error("invalid flags '{0}'")]
+    InvalidFlags(String),
+}
+
+type Result<T> = std::result::Result<T, DecodeError>;
+
+impl Workload {
+    /// Reconstructs workload from C source in the format produced by [`Workload::encode_c`].
+    ///
+    /// Lines that can not be decoded are skipped with a warning.
+    pub fn decode_c(source: &str) -> Workload {
+        let mut workload = Workload::new();
+        let mut in_body = false;
+        for (n, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if !in_body {
+                if let Some(seed) = line
+                    .strip_prefix(CONTENT_SEED_DECL)
+                    .and_then(|seed| seed.strip_suffix(';'))
+                {
+                    match decode_number(seed) {
+                        Ok(seed) => workload.seed = seed,
+                        Err(err) => warn!("skipping line {} '{}': {}", n + 1, line, err),
+                    }
+                }
+                in_body = line == "{";
+                continue;
+            }
+            if line == "}" {
+                break;
+            }
+            if line.is_empty() || line.starts_with("//") || line.starts_with("do_delay(") {
+                continue;
+            }
+            match decode_line(line) {
+                Ok(op) => workload.push(op),
+                Err(err) => warn!("skipping line {} '{}': {}", n + 1, line, err),
+            }
+        }
+        workload
+    }
+
+    /// Reconstructs workload from bash script in the format produced by [`Workload::encode_shell`].
+    ///
+    /// Script does not keep every detail: modes are decoded bit by bit, `O_NOFOLLOW` is lost next to `O_EXCL`
+    /// and opens of files missing in model (replayed along) get `O_CREAT`, since redirection creates them.
+    /// Lines that can not be decoded are skipped with a warning.
+    pub fn decode_shell(source: &str) -> Workload {
+        let mut workload = Workload::new();
+        let mut lines = source.lines().map(str::trim).enumerate();
+        // header ends with definition of `content` function
+        for (n, line) in lines.by_ref() {
+            if let Some(seed) = line.strip_prefix("SEED=") {
+                match decode_number(seed) {
+                    Ok(seed) => workload.seed = seed,
+                    Err(err) => warn!("skipping line {} '{}': {}", n + 1, line, err),
+                }
+            }
+            if line == "}" {
+                break;
+            }
+        }
+        let body: Vec<(usize, &str)> = lines
+            .filter(|(_, line)| {
+                !line.is_empty()
+                    && (!line.starts_with('#')
+                        || line.starts_with("# read ")
+                        || line.starts_with("# write "))
+            })
+            .collect();
+        let mut fs = AbstractFS::new();
+        let mut i = 0;
+        while i < body.len() {
+            let (n, line) = body[i];
+            let next = body.get(i + 1).map(|(_, line)| *line);
+            match decode_shell_line(line, next) {
+                Ok((mut op, taken)) => {
+                    if let Operation::OPEN { path, flags, .. } = &mut op {
+                        if !flags.contains(&OpenFlag::O_CREAT)
+                            && fs
+                                .follow_symlinks(path.clone())
+                                .and_then(|path| fs.resolve_node(path))
+                                .is_err()
+                        {
+                            flags.insert(0, OpenFlag::O_CREAT);
+                        }
+                    }
+                    // failing operations leave model as is
+                    fs.replay(&Workload {
+                        ops: vec![op.clone()],
+                        seed: workload.seed,
+                    })
+                    .unwrap_or(());
+                    workload.push(op);
+                    i += taken;
+                }
+                Err(err) => {
+                    warn!("skipping line {} '{}': {}", n + 1, line, err);
+                    i += 1;
+                }
+            }
+        }
+        workload
+    }
+}
+
+fn decode_line(line: &str) -> Result<Operation> {
+    let (var, call) = match line.split_once(" = ") {
+        Some((var, call)) => (Some(var.trim()), call),
+        None => (None, line),
+    };
+    let (name, args) = call
+        .strip_suffix(");")
+        .and_then(|call| call.split_once('('))
+        .ok_or(DecodeError::UnknownCall(call.to_owned()))?;
+    let args: Vec<&str> = if args.trim().is_empty() {
+        vec![]
+    } else {
+        args.split(',').map(|arg| arg.trim()).collect()
+    };
+    let op = match (name, var, args.as_slice()) {
+        ("do_create", None, [path, mode]) => Operation::CREATE {
+            path: decode_path(path)?.into(),
+            mode: decode_mode(mode)?,
+        },
+        ("do_mkdir", None, [path, mode]) => Operation::MKDIR {
+            path: decode_path(path)?.into(),
+            mode: decode_mode(mode)?,
+        },
+        ("do_remove", None, [path]) => Operation::REMOVE {
+            path: decode_path(path)?.into(),
+        },
+        ("do_mksocket", None, [path]) => Operation::MKSOCKET {
+            path: decode_path(path)?.into(),
+        },
+        ("do_symlink", None, [target, path]) => Operation::SYMLINK {
+            target: decode_path(target)?.to_owned(),
+            path: decode_path(path)?.into(),
+        },
+        ("do_readlink", None, [path]) => Operation::READLINK {
+            path: decode_path(path)?.into(),
+        },
+        ("do_hardlink", None, [old_path, new_path]) => Operation::HARDLINK {
+            old_path: decode_path(old_path)?.into(),
+            new_path: decode_path(new_path)?.into(),
+        },
+        ("do_rename", None, [old_path, new_path]) => Operation::RENAME {
+            old_path: decode_path(old_path)?.into(),
+            new_path: decode_path(new_path)?.into(),
+            flags: vec![],
+        },
+        ("do_renameat2", None, [old_path, new_path, flags]) => Operation::RENAME {
+            old_path: decode_path(old_path)?.into(),
+            new_path: decode_path(new_path)?.into(),
+            flags: decode_rename_flags(flags)?,
+        },
+        ("do_open", Some(var), [path]) => Operation::OPEN {
+            path: decode_path(path)?.into(),
+            flags: vec![],
+            des: decode_descriptor(var)?,
+        },
+        ("do_open", Some(var), [path, flags]) => Operation::OPEN {
+            path: decode_path(path)?.into(),
+            flags: decode_open_flags(flags)?,
+            des: decode_descriptor(var)?,
+        },
+        ("do_close", None, [des]) => Operation::CLOSE {
+            des: decode_descriptor(des)?,
+        },
+        ("do_read", None, [des, size]) => Operation::READ {
+            des: decode_descriptor(des)?,
+            size: decode_number(size)?,
+        },
+        ("do_write", None, [des, src_offset, size]) => Operation::WRITE {
+            des: decode_descriptor(des)?,
+            src_offset: decode_number(src_offset)?,
+            size: decode_number(size)?,
+        },
+        ("do_fsync", None, [des]) => Operation::FSYNC {
+            des: decode_descriptor(des)?,
+        },
+        ("do_fdatasync", None, [des]) => Operation::FDATASYNC {
+            des: decode_descriptor(des)?,
+        },
+        ("do_sync_file_range", None, [des, offset, nbytes, flags]) => Operation::SYNCFILERANGE {
+            des: decode_descriptor(des)?,
+            offset: decode_number(offset)?,
+            nbytes: decode_number(nbytes)?,
+            flags: decode_sync_file_range_flags(flags)?,
+        },
+        ("do_lseek", None, [des, offset, whence]) => Operation::LSEEK {
+            des: decode_descriptor(des)?,
+            offset: offset
+                .parse()
+                .map_err(|_| DecodeError::InvalidArguments(offset.to_string()))?,
+            whence: Whence::try_from(*whence)
+                .map_err(|_| DecodeError::InvalidArguments(whence.to_string()))?,
+        },
+        ("do_opendir", Some(var), [path]) => Operation::OPENDIR {
+            path: decode_path(path)?.into(),
+            stream: decode_dir_stream(var)?,
+        },
+        ("do_readdir", None, [stream, count]) => Operation::READDIR {
+            stream: decode_dir_stream(stream)?,
+            count: decode_number(count)?,
+        },
+        ("do_closedir", None, [stream]) => Operation::CLOSEDIR {
+            stream: decode_dir_stream(stream)?,
+        },
+        ("do_name_to_handle", Some(var), [path]) => Operation::NAMETOHANDLE {
+            path: decode_path(path)?.into(),
+            handle: decode_file_handle(var)?,
+        },
+        ("do_open_by_handle", None, [handle]) => Operation::OPENBYHANDLE {
+            handle: decode_file_handle(handle)?,
+        },
+        ("do_prealloc", None, [path, size]) => Operation::PREALLOC {
+            path: decode_path(path)?.into(),
+            size: decode_number(size)?,
+        },
+        ("do_truncate", None, [path, size]) => Operation::TRUNCATE {
+            path: decode_path(path)?.into(),
+            size: decode_number(size)?,
+        },
+        ("do_chmod", None, [path, mode]) => Operation::CHMOD {
+            path: decode_path(path)?.into(),
+            mode: decode_mode(mode)?,
+        },
+        ("do_setxattr", None, [path, name, value_size]) => Operation::SETXATTR {
+            path: decode_path(path)?.into(),
+            name: decode_path(name)?.to_owned(),
+            value_size: decode_number(value_size)?,
+        },
+        ("do_listdir", None, [path]) => Operation::LISTDIR {
+            path: decode_path(path)?.into(),
+        },
+        ("do_removexattr", None, [path, name]) => Operation::REMOVEXATTR {
+            path: decode_path(path)?.into(),
+            name: decode_path(name)?.to_owned(),
+        },
+        ("do_mknod", None, [path, kind, mode]) => Operation::MKNOD {
+            path: decode_path(path)?.into(),
+            kind: SpecialKind::try_from(*kind)
+                .map_err(|_| DecodeError::InvalidArguments(kind.to_string()))?,
+            mode: decode_mode(mode)?,
+        },
+        ("do_utimens", None, [path, atime, mtime]) => Operation::UTIMENS {
+            path: decode_path(path)?.into(),
+            atime: decode_signed(atime)?,
+            mtime: decode_signed(mtime)?,
+        },
+        ("do_stat", None, [path]) => Operation::STAT {
+            path: decode_path(path)?.into(),
+        },
+        ("do_fallocate", None, [des, offset, len, mode]) => Operation::FALLOCATE {
+            des: decode_descriptor(des)?,
+            offset: decode_number(offset)?,
+            len: decode_number(len)?,
+            mode: decode_fallocate_flags(mode)?,
+        },
+        ("do_crashpoint", None, []) => Operation::CRASHPOINT,
+        (
+            "do_create" | "do_mkdir" | "do_remove" | "do_mksocket" | "do_symlink" | "do_readlink"
+            | "do_hardlink" | "do_rename" | "do_renameat2" | "do_open" | "do_close" | "do_read"
+            | "do_write" | "do_fsync" | "do_opendir" | "do_readdir" | "do_closedir"
+            | "do_name_to_handle" | "do_open_by_handle" | "do_prealloc" | "do_truncate"
+            | "do_chmod" | "do_mknod" | "do_setxattr" | "do_removexattr" | "do_fdatasync"
+            | "do_sync_file_range" | "do_lseek" | "do_listdir" | "do_utimens" | "do_stat"
+            | "do_fallocate" | "do_crashpoint",
+            _,
+            _,
+        ) => return Err(DecodeError::InvalidArguments(line.to_owned())),
+        _ => return Err(DecodeError::UnknownCall(name.to_owned())),
+    };
+    Ok(op)
+}
+
+/// Prefix of python calls to libc made by shell script.
+const PYTHON_LIBC: &str = "import ctypes, os, sys; libc = ctypes.CDLL(None, use_errno=True); libc.";
+
+/// Suffix of python calls to libc made by shell script, exiting with error of failed call.
+const PYTHON_ERRNO: &str = " == 0 or sys.exit(os.strerror(ctypes.get_errno()))";
+
+/// Operation done by line of shell script and the number of lines it takes
+/// (some operations take the next line too).
+fn decode_shell_line(line: &str, next: Option<&str>) -> Result<(Operation, usize)> {
+    let words = shell_words(line);
+    let next = next.map(shell_words).unwrap_or_default();
+    let op = match words.as_slice() {
+        ["mkdir", "-m", mode, path] => Operation::MKDIR {
+            path: decode_mnt_path(path)?,
+            mode: decode_mode_octal(mode)?,
+        },
+        ["touch", path] => match next.as_slice() {
+            ["chmod", mode, next_path] if next_path == path => {
+                let op = Operation::CREATE {
+                    path: decode_mnt_path(path)?,
+                    mode: decode_mode_octal(mode)?,
+                };
+                return Ok((op, 2));
+            }
+            _ => return Err(DecodeError::InvalidArguments(line.to_owned())),
+        },
+        ["rm", "-rf", path] => Operation::REMOVE {
+            path: decode_mnt_path(path)?,
+        },
+        ["ln", "-s", target, path] => Operation::SYMLINK {
+            target: decode_quoted(target, '\'')?.to_owned(),
+            path: decode_mnt_path(path)?,
+        },
+        ["readlink", path] => Operation::READLINK {
+            path: decode_mnt_path(path)?,
+        },
+        ["ln", old_path, new_path] => Operation::HARDLINK {
+            old_path: decode_mnt_path(old_path)?,
+            new_path: decode_mnt_path(new_path)?,
+        },
+        ["mv", "-T", "--exchange", old_path, new_path] => Operation::RENAME {
+            old_path: decode_mnt_path(old_path)?,
+            new_path: decode_mnt_path(new_path)?,
+            flags: vec![RenameFlag::RENAME_EXCHANGE],
+        },
+        ["mv", "-T", old_path, new_path] => Operation::RENAME {
+            old_path: decode_mnt_path(old_path)?,
+            new_path: decode_mnt_path(new_path)?,
+            flags: vec![],
+        },
+        ["python3", "-c", code, args @ ..] => {
+            decode_python(line, decode_quoted(code, '\'')?, args)?
+        }
+        ["exec", redirect] if redirect.ends_with(">&-") => Operation::CLOSE {
+            des: redirect
+                .strip_prefix('{')
+                .and_then(|var| var.strip_suffix("}>&-"))
+                .ok_or(DecodeError::InvalidArguments(line.to_owned()))
+                .and_then(decode_descriptor)?,
+        },
+        ["exec" | "[" | "{", ..] => decode_shell_open(line, &words)?,
+        ["dd", block_size, "count=1", "status=none", des, ">", "/dev/null"] => Operation::READ {
+            des: decode_descriptor_ref(des, "<&$")?,
+            size: decode_prefixed_number(block_size, "bs=")?,
+        },
+        ["#", "read", size, "bytes", "from", des] => Operation::READ {
+            des: decode_descriptor(des)?,
+            size: decode_number(size)?,
+        },
+        ["content", src_offset, size, "|", "dd", _, "count=1", "iflag=fullblock", "status=none", des] => {
+            Operation::WRITE {
+                des: decode_descriptor_ref(des, ">&$")?,
+                src_offset: decode_number(src_offset)?,
+                size: decode_number(size)?,
+            }
+        }
+        ["#", "write", size, "bytes", "to", des] => Operation::WRITE {
+            des: decode_descriptor(des)?,
+            src_offset: 0,
+            size: decode_number(size)?,
+        },
+        ["sync", des] => Operation::FSYNC {
+            des: decode_descriptor_ref(des, "/proc/$$/fd/$")?,
+        },
+        ["sync", "-d", des] => Operation::FDATASYNC {
+            des: decode_descriptor_ref(des, "/proc/$$/fd/$")?,
+        },
+        ["ls", "-a", "-f", path] => Operation::LISTDIR {
+            path: decode_mnt_path(path)?,
+        },
+        ["truncate", "-s", size, path] => {
+            let fill = format!("of={}", path);
+            match next.as_slice() {
+                ["content", "0", next_size, "|", "dd", of, "conv=notrunc", "status=none"]
+                    if next_size == size && *of == fill =>
+                {
+                    let op = Operation::PREALLOC {
+                        path: decode_mnt_path(path)?,
+                        size: decode_number(size)?,
+                    };
+                    return Ok((op, 2));
+                }
+                _ => Operation::TRUNCATE {
+                    path: decode_mnt_path(path)?,
+                    size: decode_number(size)?,
+                },
+            }
+        }
+        ["chmod", mode, path] => Operation::CHMOD {
+            path: decode_mnt_path(path)?,
+            mode: decode_mode_octal(mode)?,
+        },
+        ["mkfifo", "-m", mode, path] => Operation::MKNOD {
+            path: decode_mnt_path(path)?,
+            kind: SpecialKind::S_IFIFO,
+            mode: decode_mode_octal(mode)?,
+        },
+        ["mknod", "-m", mode, path, kind, "0", "0"] => Operation::MKNOD {
+            path: decode_mnt_path(path)?,
+            kind: match *kind {
+                "c" => SpecialKind::S_IFCHR,
+                "b" => SpecialKind::S_IFBLK,
+                _ => return Err(DecodeError::InvalidArguments(kind.to_string())),
+            },
+            mode: decode_mode_octal(mode)?,
+        },
+        ["setfattr", "-n", name, path] => Operation::SETXATTR {
+            path: decode_mnt_path(path)?,
+            name: name.to_string(),
+            value_size: 0,
+        },
+        ["setfattr", "-n", name, "-v", value, path] => Operation::SETXATTR {
+            path: decode_mnt_path(path)?,
+            name: name.to_string(),
+            value_size: value
+                .strip_prefix("\"0s$(content 0 ")
+                .and_then(|value| value.strip_suffix(" | base64 -w 0)\""))
+                .ok_or(DecodeError::InvalidArguments(value.to_string()))
+                .and_then(decode_number)?,
+        },
+        ["setfattr", "-x", name, path] => Operation::REMOVEXATTR {
+            path: decode_mnt_path(path)?,
+            name: name.to_string(),
+        },
+        ["touch", "-h", "-a", "-d", atime, path] => match next.as_slice() {
+            ["touch", "-h", "-m", "-d", mtime, next_path] if next_path == path => {
+                let op = Operation::UTIMENS {
+                    path: decode_mnt_path(path)?,
+                    atime: decode_signed(atime.strip_prefix('@').unwrap_or(atime))?,
+                    mtime: decode_signed(mtime.strip_prefix('@').unwrap_or(mtime))?,
+                };
+                return Ok((op, 2));
+            }
+            _ => return Err(DecodeError::InvalidArguments(line.to_owned())),
+        },
+        ["stat", path] => Operation::STAT {
+            path: decode_mnt_path(path)?,
+        },
+        // punching hole implies keeping size in util-linux
+        ["fallocate", options @ .., "-o", offset, "-l", len, des] => Operation::FALLOCATE {
+            des: decode_descriptor_ref(des, "/proc/$$/fd/$")?,
+            offset: decode_number(offset)?,
+            len: decode_number(len)?,
+            mode: options
+                .iter()
+                .map(|option| match *option {
+                    "-n" => Ok(vec![FallocateFlag::FALLOC_FL_KEEP_SIZE]),
+                    "-p" => Ok(vec![
+                        FallocateFlag::FALLOC_FL_PUNCH_HOLE,
+                        FallocateFlag::FALLOC_FL_KEEP_SIZE,
+                    ]),
+                    "-z" => Ok(vec![FallocateFlag::FALLOC_FL_ZERO_RANGE]),
+                    _ => Err(DecodeError::InvalidFlags(option.to_string())),
+                })
+                .collect::<Result<Vec<_>>>()?
+                .concat(),
+        },
+        ["mkdir" | "touch" | "rm" | "ln" | "readlink" | "mv" | "python3" | "dd" | "#"
+        | "content" | "sync" | "ls" | "truncate" | "chmod" | "mkfifo" | "mknod" | "setfattr"
+        | "stat" | "fallocate", ..] => return Err(DecodeError::InvalidArguments(line.to_owned())),
+        [name, ..] => return Err(DecodeError::UnknownCall(name.to_string())),
+        [] => return Err(DecodeError::UnknownCall(line.to_owned())),
+    };
+    Ok((op, 1))
+}
+
+/// Operation done from python, code is unquoted.
+fn decode_python(line: &str, code: &str, args: &[&str]) -> Result<Operation> {
+    let libc_call = code
+        .strip_prefix(PYTHON_LIBC)
+        .and_then(|code| code.strip_suffix(PYTHON_ERRNO));
+    let op = match (code, libc_call, args) {
+        ("import socket, sys; socket.socket(socket.AF_UNIX).bind(sys.argv[1])", _, [path]) => {
+            Operation::MKSOCKET {
+                path: decode_mnt_path(path)?,
+            }
+        }
+        (
+            _,
+            Some("renameat2(-100, os.fsencode(sys.argv[1]), -100, os.fsencode(sys.argv[2]), 1)"),
+            [old_path, new_path],
+        ) => Operation::RENAME {
+            old_path: decode_mnt_path(old_path)?,
+            new_path: decode_mnt_path(new_path)?,
+            flags: vec![RenameFlag::RENAME_NOREPLACE],
+        },
+        (_, Some(call), [des]) if call.starts_with("sync_file_range(") => {
+            let args: Vec<&str> = call
+                .strip_prefix("sync_file_range(int(sys.argv[1]), ")
+                .and_then(|args| args.strip_suffix(')'))
+                .ok_or(DecodeError::InvalidArguments(line.to_owned()))?
+                .split(", ")
+                .collect();
+            let [offset, nbytes, flags] = args.as_slice() else {
+                return Err(DecodeError::InvalidArguments(line.to_owned()));
+            };
+            let int64 = |arg: &str| {
+                arg.strip_prefix("ctypes.c_int64(")
+                    .and_then(|arg| arg.strip_suffix(')'))
+                    .ok_or(DecodeError::InvalidArguments(arg.to_owned()))
+                    .and_then(decode_number)
+            };
+            Operation::SYNCFILERANGE {
+                des: decode_descriptor_ref(des, "$")?,
+                offset: int64(offset)?,
+                nbytes: int64(nbytes)?,
+                flags: decode_sync_file_range_value(flags)?,
+            }
+        }
+        (_, None, [des]) if code.starts_with("import os, sys; os.lseek(") => {
+            let (offset, whence) = code
+                .strip_prefix("import os, sys; os.lseek(int(sys.argv[1]), ")
+                .and_then(|args| args.strip_suffix(')'))
+                .and_then(|args| args.split_once(", os."))
+                .ok_or(DecodeError::InvalidArguments(line.to_owned()))?;
+            Operation::LSEEK {
+                des: decode_descriptor_ref(des, "$")?,
+                offset: decode_signed(offset)?,
+                whence: Whence::try_from(whence)
+                    .map_err(|_| DecodeError::InvalidArguments(whence.to_string()))?,
+            }
+        }
+        _ => return Err(DecodeError::InvalidArguments(line.to_owned())),
+    };
+    Ok(op)
+}
+
+/// Open emulated by redirection, optionally after checks of path and with appending set from python.
+fn decode_shell_open(line: &str, words: &[&str]) -> Result<Operation> {
+    let invalid = || DecodeError::InvalidArguments(line.to_owned());
+    let mut words = words;
+    let mut checks = vec![];
+    while let ["[", test, path, "]", "||", rest @ ..] = words {
+        checks.push((*test, *path));
+        words = rest;
+    }
+    let (redirect, append) = match words {
+        ["exec", redirect] => (*redirect, false),
+        ["{", "exec", redirect, "&&", "python3", "-c", code, _, "}"]
+            if decode_quoted(code, '\'')?
+                == "import fcntl, os, sys; fd = int(sys.argv[1]); \
+                    fcntl.fcntl(fd, fcntl.F_SETFL, fcntl.fcntl(fd, fcntl.F_GETFL) | os.O_APPEND)" =>
+        {
+            (*redirect, true)
+        }
+        _ => return Err(invalid()),
+    };
+    let (var, path) = redirect
+        .strip_prefix('{')
+        .and_then(|redirect| redirect.split_once("}<>"))
+        .ok_or_else(invalid)?;
+    if checks.iter().any(|(_, checked)| *checked != path) {
+        return Err(invalid());
+    }
+    let mut flags = match checks.as_slice() {
+        [] => vec![],
+        [("-L", _)] => vec![OpenFlag::O_NOFOLLOW],
+        [("-e", _), ("-L", _)] => vec![OpenFlag::O_CREAT, OpenFlag::O_EXCL],
+        _ => return Err(invalid()),
+    };
+    if append {
+        flags.push(OpenFlag::O_APPEND);
+    }
+    Ok(Operation::OPEN {
+        path: decode_mnt_path(path)?,
+        flags,
+        des: decode_descriptor(var)?,
+    })
+}
+
+/// Words of shell line split by whitespace, quoted words are kept whole (with quotes).
+fn shell_words(line: &str) -> Vec<&str> {
+    let mut words = vec![];
+    let mut start = None;
+    let mut quote = None;
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '\'' || c == '"' => {
+                quote = Some(c);
+                start.get_or_insert(i);
+            }
+            None if c.is_whitespace() => {
+                if let Some(start) = start.take() {
+                    words.push(&line[start..i]);
+                }
+            }
+            None => {
+                start.get_or_insert(i);
+            }
+        }
+    }
+    if let Some(start) = start {
+        words.push(&line[start..]);
+    }
+    words
+}
+
+fn decode_quoted(arg: &str, quote: char) -> Result<&str> {
+    arg.strip_prefix(quote)
+        .and_then(|arg| arg.strip_suffix(quote))
+        .ok_or(DecodeError::InvalidArguments(arg.to_owned()))
+}
+
+/// Path under mount point, as in `"$MNT/foo"`.
+fn decode_mnt_path(arg: &str) -> Result<PathName> {
+    match decode_quoted(arg, '"')?.strip_prefix("$MNT") {
+        Some("") => Ok("/".into()),
+        Some(path) if path.starts_with('/') => Ok(path.into()),
+        _ => Err(DecodeError::InvalidArguments(arg.to_owned())),
+    }
+}
+
+fn decode_prefixed_number(arg: &str, prefix: &str) -> Result<u64> {
+    arg.strip_prefix(prefix)
+        .ok_or(DecodeError::InvalidArguments(arg.to_owned()))
+        .and_then(decode_number)
+}
+
+/// Descriptor variable referenced after `prefix`, as in `$fd_0`.
+fn decode_descriptor_ref(arg: &str, prefix: &str) -> Result<FileDescriptorIndex> {
+    arg.strip_prefix(prefix)
+        .ok_or(DecodeError::InvalidVariable(arg.to_owned()))
+        .and_then(decode_descriptor)
+}
+
+fn decode_path(arg: &str) -> Result<&str> {
+    arg.strip_prefix('"')
+        .and_then(|arg| arg.strip_suffix('"'))
+        .ok_or(DecodeError::InvalidArguments(arg.to_owned()))
+}
+
+fn decode_number(arg: &str) -> Result<u64> {
+    arg.parse()
+        .map_err(|_| DecodeError::InvalidArguments(arg.to_owned()))
+}
+
+fn decode_signed(arg: &str) -> Result<i64> {
+    arg.parse()
+        .map_err(|_| DecodeError::InvalidArguments(arg.to_owned()))
+}
+
+fn decode_index(var: &str, prefix: &str) -> Result<usize> {
+    var.strip_prefix(prefix)
+        .and_then(|index| index.parse().ok())
+        .ok_or(DecodeError::InvalidVariable(var.to_owned()))
+}
+
+fn decode_descriptor(var: &str) -> Result<FileDescriptorIndex> {
+    decode_index(var, "fd_").map(FileDescriptorIndex)
+}
+
+fn decode_dir_stream(var: &str) -> Result<DirStreamIndex> {
+    decode_index(var, "ds_").map(DirStreamIndex)
+}
+
+fn decode_file_handle(var: &str) -> Result<FileHandleIndex> {
+    decode_index(var, "fh_").map(FileHandleIndex)
+}
+
+fn decode_mode(arg: &str) -> Result<Mode> {
+    if arg == "0" {
+        return Ok(vec![]);
+    }
+    arg.split('|')
+        .map(|flag| {
+            ModeFlag::try_from(flag.trim()).map_err(|_| DecodeError::InvalidMode(arg.to_owned()))
+        })
+        .collect()
+}
+
+/// Octal mode, bits are in the order generator picks them.
+fn decode_mode_octal(arg: &str) -> Result<Mode> {
+    let value =
+        u32::from_str_radix(arg, 8).map_err(|_| DecodeError::InvalidMode(arg.to_owned()))?;
+    let mode: Mode = PERMISSION_BITS
+        .iter()
+        .chain(SPECIAL_BITS)
+        .filter(|bit| value & **bit as u32 != 0)
+        .copied()
+        .collect();
+    if mode.iter().fold(0, |acc, bit| acc | *bit as u32) != value {
+        return Err(DecodeError::InvalidMode(arg.to_owned()));
+    }
+    Ok(mode)
+}
+
+fn decode_open_flags(arg: &str) -> Result<OpenFlags> {
+    if arg == "0" {
+        return Ok(vec![]);
+    }
+    let flags: OpenFlags = arg
+        .split('|')
+        .map(|flag| {
+            OpenFlag::try_from(flag.trim())
+                .ok()
+                .filter(|flag| OPEN_FLAGS.contains(flag))
+                .ok_or(DecodeError::InvalidFlags(arg.to_owned()))
+        })
+        .collect::<Result<_>>()?;
+    if flags
+        .iter()
+        .enumerate()
+        .any(|(i, flag)| flags[..i].contains(flag))
+    {
+        return Err(DecodeError::InvalidFlags(arg.to_owned()));
+    }
+    Ok(flags)
+}
+
+fn decode_rename_flags(arg: &str) -> Result<RenameFlags> {
+    if arg == "0" {
+        return Ok(vec![]);
+    }
+    arg.split('|')
+        .map(|flag| {
+            RenameFlag::try_from(flag.trim()).map_err(|_| DecodeError::InvalidFlags(arg.to_owned()))
+        })
+        .collect()
+}
+
+fn decode_sync_file_range_flags(arg: &str) -> Result<SyncFileRangeFlags> {
+    if arg == "0" {
+        return Ok(vec![]);
+    }
+    arg.split('|')
+        .map(|flag| {
+            SyncFileRangeFlag::try_from(flag.trim())
+                .map_err(|_| DecodeError::InvalidFlags(arg.to_owned()))
+        })
+        .collect()
+}
+
+/// Flags of `sync_file_range` passed as number.
+fn decode_sync_file_range_value(arg: &str) -> Result<SyncFileRangeFlags> {
+    let value: u32 = arg
+        .parse()
+        .map_err(|_| DecodeError::InvalidFlags(arg.to_owned()))?;
+    let flags: SyncFileRangeFlags = [
+        (1, SyncFileRangeFlag::SYNC_FILE_RANGE_WAIT_BEFORE),
+        (2, SyncFileRangeFlag::SYNC_FILE_RANGE_WRITE),
+        (4, SyncFileRangeFlag::SYNC_FILE_RANGE_WAIT_AFTER),
+    ]
+    .into_iter()
+    .filter(|(bit, _)| value & bit != 0)
+    .map(|(_, flag)| flag)
+    .collect();
+    if value & !7 != 0 {
+        return Err(DecodeError::InvalidFlags(arg.to_owned()));
+    }
+    Ok(flags)
+}
+
+fn decode_fallocate_flags(arg: &str) -> Result<FallocateFlags> {
+    if arg == "0" {
+        return Ok(vec![]);
+    }
+    arg.split('|')
+        .map(|flag| {
+            FallocateFlag::try_from(flag.trim())
+                .map_err(|_| DecodeError::InvalidFlags(arg.to_owned()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::abstract_fs::{
+        generator::{generate_new, GeneratorConfig},
+        operation::{OperationKind, OperationWeights},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for i in 0..100 {
+            let mut rng = StdRng::seed_from_u64(i);
+            let workload = generate_new(
+                &mut rng,
+                100,
+                &GeneratorConfig {
+                    max_nodes: Some(1000),
+                    special_mode_bits: Some(0.5),
+                    root_bias: 0.2,
+                    ..Default::default()
+                },
+            );
+            assert_eq!(workload, Workload::decode_c(&workload.encode_c(None)));
+        }
+    }
+
+    #[test]
+    fn test_round_trip_every_operation() {
+        let mut rng = StdRng::seed_from_u64(42);
+        // crash points are rare by default
+        let weights = OperationWeights::new(
+            OperationWeights::uniform()
+                .weights
+                .into_iter()
+                .map(|(kind, _)| (kind, 100))
+                .collect(),
+        );
+        let workload = generate_new(
+            &mut rng,
+            1000,
+            &GeneratorConfig {
+                weights: weights.clone(),
+                special_mode_bits: Some(1.0),
+                ..Default::default()
+            },
+        );
+        for (kind, _) in weights.weights {
+            assert!(workload.ops.iter().any(|op| op.kind() == kind));
+        }
+        assert_eq!(workload, Workload::decode_c(&workload.encode_c(None)));
+    }
+
+    #[test]
+    fn test_round_trip_shell() {
+        let weights = OperationWeights::new(
+            OperationWeights::uniform()
+                .weights
+                .into_iter()
+                .filter(|(kind, _)| {
+                    !matches!(
+                        kind,
+                        OperationKind::OPENDIR
+                            | OperationKind::READDIR
+                            | OperationKind::CLOSEDIR
+                            | OperationKind::NAMETOHANDLE
+                            | OperationKind::OPENBYHANDLE
+                            | OperationKind::CRASHPOINT
+                    )
+                })
+                .collect(),
+        );
+        let mut kinds = vec![];
+        for i in 0..100 {
+            let mut rng = StdRng::seed_from_u64(i);
+            let workload = generate_new(
+                &mut rng,
+                100,
+                &GeneratorConfig {
+                    weights: weights.clone(),
+                    max_nodes: Some(1000),
+                    special_mode_bits: Some(0.5),
+                    ..Default::default()
+                },
+            );
+            let script = workload.encode_shell().unwrap();
+            let decoded = Workload::decode_shell(&script);
+            assert_eq!(workload.seed, decoded.seed);
+            assert_eq!(workload.ops.len(), decoded.ops.len());
+            assert_eq!(script, decoded.encode_shell().unwrap());
+            kinds.extend(decoded.ops.iter().map(|op| op.kind()));
+        }
+        for (kind, _) in weights.weights {
+            assert!(kinds.contains(&kind), "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn test_decode_shell_lossy() {
+        let des = FileDescriptorIndex(0);
+        let workload = Workload {
+            ops: vec![
+                Operation::MKDIR {
+                    path: "/foo".into(),
+                    mode: vec![ModeFlag::S_IRWXU, ModeFlag::S_ISVTX],
+                },
+                Operation::OPEN {
+                    path: "/foo/bar".into(),
+                    flags: vec![OpenFlag::O_APPEND],
+                    des,
+                },
+                Operation::OPEN {
+                    path: "/foo".into(),
+                    flags: vec![],
+                    des: FileDescriptorIndex(1),
+                },
+                Operation::OPEN {
+                    path: "/foo/baz".into(),
+                    flags: vec![OpenFlag::O_NOFOLLOW, OpenFlag::O_EXCL],
+                    des: FileDescriptorIndex(2),
+                },
+                Operation::WRITE {
+                    des,
+                    src_offset: 42,
+                    size: 0,
+                },
+            ],
+            seed: 42,
+        };
+        assert_eq!(
+            Workload {
+                ops: vec![
+                    Operation::MKDIR {
+                        path: "/foo".into(),
+                        mode: vec![
+                            ModeFlag::S_IRUSR,
+                            ModeFlag::S_IWUSR,
+                            ModeFlag::S_IXUSR,
+                            ModeFlag::S_ISVTX
+                        ],
+                    },
+                    Operation::OPEN {
+                        path: "/foo/bar".into(),
+                        flags: vec![OpenFlag::O_CREAT, OpenFlag::O_APPEND],
+                        des,
+                    },
+                    Operation::OPEN {
+                        path: "/foo".into(),
+                        flags: vec![],
+                        des: FileDescriptorIndex(1),
+                    },
+                    Operation::OPEN {
+                        path: "/foo/baz".into(),
+                        flags: vec![OpenFlag::O_CREAT, OpenFlag::O_EXCL],
+                        des: FileDescriptorIndex(2),
+                    },
+                    Operation::WRITE {
+                        des,
+                        src_offset: 0,
+                        size: 0,
+                    },
+                ],
+                seed: 42,
+            },
+            Workload::decode_shell(&workload.encode_shell().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_decode_shell_errors() {
+        assert_eq!(
+            Err(DecodeError::UnknownCall("cp".to_owned())),
+            decode_shell_line("cp \"$MNT/foo\" \"$MNT/bar\"", None)
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidArguments("mkdir foo".to_owned())),
+            decode_shell_line("mkdir foo", None)
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidArguments("/foo".to_owned())),
+            decode_shell_line("rm -rf /foo", None)
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidArguments(
+                "touch \"$MNT/foo\"".to_owned()
+            )),
+            decode_shell_line("touch \"$MNT/foo\"", Some("chmod 0700 \"$MNT/bar\""))
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidMode("0778".to_owned())),
+            decode_shell_line("mkdir -m 0778 \"$MNT/foo\"", None)
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidVariable("$ds_0".to_owned())),
+            decode_shell_line("sync $ds_0", None)
+        );
+    }
+
+    #[test]
+    fn test_skip_unsupported() {
+        let source = r#"
+#include "executor.h"
+
+int fd_0;
+
+void test_workload()
+{
+do_mkdir("/foo", S_IRWXU | S_ISVTX);
+do_delay(100);
+system("rm -rf /");
+do_remove("/foo", "/bar");
+do_create("/foo/bar", S_IRWXU | S_IWHAT);
+fd_0 = do_open("/foo");
+do_fsync(fd_x);
+}
+"#;
+        assert_eq!(
+            Workload {
+                ops: vec![
+                    Operation::MKDIR {
+                        path: "/foo".into(),
+                        mode: vec![ModeFlag::S_IRWXU, ModeFlag::S_ISVTX],
+                    },
+                    Operation::OPEN {
+                        path: "/foo".into(),
+                        flags: vec![],
+                        des: FileDescriptorIndex(0),
+                    },
+                ],
+                seed: 0,
+            },
+            Workload::decode_c(source)
+        );
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert_eq!(
+            Err(DecodeError::UnknownCall("system".to_owned())),
+            decode_line("system(\"ls\");")
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidArguments(
+                "do_close(fd_0, 1);".to_owned()
+            )),
+            decode_line("do_close(fd_0, 1);")
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidVariable("ds_0".to_owned())),
+            decode_line("do_close(ds_0);")
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidFlags("O_APPEND | 8".to_owned())),
+            decode_line("fd_0 = do_open(\"/foo\", O_APPEND | 8);")
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidFlags("O_RDONLY".to_owned())),
+            decode_line("fd_0 = do_open(\"/foo\", O_RDONLY);")
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidFlags("O_CREAT | O_CREAT".to_owned())),
+            decode_line("fd_0 = do_open(\"/foo\", O_CREAT | O_CREAT);")
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidMode("0777".to_owned())),
+            decode_line("do_mkdir(\"/foo\", 0777);")
+        );
+    }
+}
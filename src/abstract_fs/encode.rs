@@ -1,96 +1,527 @@
 use std::cmp::max;
 
-use super::{flags::Mode, node::FileDescriptorIndex, operation::Operation, workload::Workload};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{
+    content::WRITE_BUFFER_SIZE,
+    flags::{
+        FallocateFlag, FallocateFlags, Mode, OpenFlag, OpenFlags, RenameFlag, RenameFlags,
+        SpecialKind, SyncFileRangeFlag, SyncFileRangeFlags,
+    },
+    fstest::encode_mode_octal,
+    node::{DirStreamIndex, FileDescriptorIndex, FileHandleIndex},
+    operation::{Operation, OperationKind},
+    pathname::PathName,
+    workload::Workload,
+};
 
 fn descriptor_to_var(des: &FileDescriptorIndex) -> String {
     format!("fd_{}", des.0)
 }
 
+fn dir_stream_to_var(stream: &DirStreamIndex) -> String {
+    format!("ds_{}", stream.0)
+}
+
+fn file_handle_to_var(handle: &FileHandleIndex) -> String {
+    format!("fh_{}", handle.0)
+}
+
+/// Definition of seed executor fills write buffer with, followed by the value.
+pub const CONTENT_SEED_DECL: &str = "const uint64_t content_seed = ";
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ShellError {
+    #[error("operation {0} ({1:?}) has no shell equivalent")]
+    Unsupported(usize, OperationKind),
+}
+
+/// Shell function printing `size` bytes of executor write buffer starting at `offset`
+/// (repeated past its end, as preallocated content is), see [`super::content::source_byte`].
+const SHELL_CONTENT_FN: &str = r#"content() {
+    python3 -c '
+import sys
+seed, offset, size, buffer_size = map(int, sys.argv[1:])
+mask = (1 << 64) - 1
+def byte(i):
+    z = (seed + (i % buffer_size + 1) * 0x9E3779B97F4A7C15) & mask
+    z = ((z ^ (z >> 30)) * 0xBF58476D1CE4E5B9) & mask
+    z = ((z ^ (z >> 27)) * 0x94D049BB133111EB) & mask
+    return (z ^ (z >> 31)) & 0xFF
+sys.stdout.buffer.write(bytes(map(byte, range(offset, offset + size))))
+' "$SEED" "$1" "$2" "$BUFFER_SIZE"
+}
+"#;
+
+/// Sleeps inserted between operations, the same seed gives the same delays.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Delays {
+    pub max_micros: u32,
+    pub seed: u64,
+}
+
 impl Workload {
-    pub fn encode_c(&self) -> String {
+    pub fn encode_c(&self, delays: Option<&Delays>) -> String {
         let mut result = String::new();
         result.push_str("#include \"executor.h\"\n");
+        result.push_str(format!("\n{}{};\n", CONTENT_SEED_DECL, self.seed).as_str());
         let mut descriptors_n = 0;
+        let mut dir_streams_n = 0;
+        let mut file_handles_n = 0;
         for op in self.ops.iter() {
             match op {
-                Operation::OPEN { path: _, des } => {
+                Operation::OPEN { des, .. } => {
                     descriptors_n = max(descriptors_n, des.0 + 1);
                 }
-                _ => {}
+                Operation::OPENDIR { path: _, stream } => {
+                    dir_streams_n = max(dir_streams_n, stream.0 + 1);
+                }
+                Operation::NAMETOHANDLE { path: _, handle } => {
+                    file_handles_n = max(file_handles_n, handle.0 + 1);
+                }
+                Operation::MKDIR { .. }
+                | Operation::CREATE { .. }
+                | Operation::REMOVE { .. }
+                | Operation::MKSOCKET { .. }
+                | Operation::SYMLINK { .. }
+                | Operation::READLINK { .. }
+                | Operation::HARDLINK { .. }
+                | Operation::RENAME { .. }
+                | Operation::CLOSE { .. }
+                | Operation::READ { .. }
+                | Operation::WRITE { .. }
+                | Operation::FSYNC { .. }
+                | Operation::FDATASYNC { .. }
+                | Operation::SYNCFILERANGE { .. }
+                | Operation::LSEEK { .. }
+                | Operation::READDIR { .. }
+                | Operation::CLOSEDIR { .. }
+                | Operation::LISTDIR { .. }
+                | Operation::OPENBYHANDLE { .. }
+                | Operation::PREALLOC { .. }
+                | Operation::TRUNCATE { .. }
+                | Operation::CHMOD { .. }
+                | Operation::MKNOD { .. }
+                | Operation::SETXATTR { .. }
+                | Operation::REMOVEXATTR { .. }
+                | Operation::UTIMENS { .. }
+                | Operation::STAT { .. }
+                | Operation::FALLOCATE { .. }
+                | Operation::CRASHPOINT => {}
             }
         }
-        if descriptors_n > 0 {
-            let descriptors_vars: Vec<String> =
-                (0..descriptors_n).map(|it| format!("fd_{}", it)).collect();
+        if descriptors_n > 0 || dir_streams_n > 0 || file_handles_n > 0 {
+            let descriptors_vars: Vec<String> = (0..descriptors_n)
+                .map(|it| descriptor_to_var(&FileDescriptorIndex(it)))
+                .chain((0..dir_streams_n).map(|it| dir_stream_to_var(&DirStreamIndex(it))))
+                .chain((0..file_handles_n).map(|it| file_handle_to_var(&FileHandleIndex(it))))
+                .collect();
             result.push_str(format!("\nint {};\n\n", descriptors_vars.join(", ")).as_str());
         } else {
             result.push_str("\n// no descriptors\n\n");
         }
         result.push_str("void test_workload()\n");
         result.push_str("{\n");
-        for op in &self.ops {
-            match op {
-                Operation::CREATE { path, mode } => {
-                    result.push_str(
-                        format!("do_create(\"{}\", {});\n", path, encode_mode(mode).as_str())
-                            .as_str(),
-                    );
-                }
-                Operation::MKDIR { path, mode } => {
-                    result.push_str(
-                        format!("do_mkdir(\"{}\", {});\n", path, encode_mode(mode).as_str())
-                            .as_str(),
-                    );
-                }
-                Operation::REMOVE { path } => {
-                    result.push_str(format!("do_remove(\"{}\");\n", path).as_str());
-                }
-                Operation::HARDLINK { old_path, new_path } => {
-                    result.push_str(
-                        format!("do_hardlink(\"{}\", \"{}\");\n", old_path, new_path).as_str(),
-                    );
-                }
-                Operation::RENAME { old_path, new_path } => {
-                    result.push_str(
-                        format!("do_rename(\"{}\", \"{}\");\n", old_path, new_path).as_str(),
-                    );
-                }
-                Operation::OPEN { path, des } => {
-                    result.push_str(
-                        format!("{} = do_open(\"{}\");\n", descriptor_to_var(des), path).as_str(),
-                    );
-                }
-                Operation::CLOSE { des } => {
-                    result.push_str(format!("do_close({});\n", descriptor_to_var(des)).as_str());
-                }
-                Operation::READ { des, size } => {
-                    result.push_str(
-                        format!("do_read({}, {});\n", descriptor_to_var(des), size).as_str(),
-                    );
-                }
-                Operation::WRITE {
-                    des,
-                    src_offset,
-                    size,
-                } => {
+        let mut rng = delays.map(|delays| (StdRng::seed_from_u64(delays.seed), delays.max_micros));
+        for (i, op) in self.ops.iter().enumerate() {
+            if let Some((rng, max_micros)) = rng.as_mut() {
+                if i > 0 {
                     result.push_str(
-                        format!(
-                            "do_write({}, {}, {});\n",
-                            descriptor_to_var(des),
-                            src_offset,
-                            size
-                        )
-                        .as_str(),
+                        format!("do_delay({});\n", rng.gen_range(0..=*max_micros)).as_str(),
                     );
                 }
-                Operation::FSYNC { des } => {
-                    result.push_str(format!("do_fsync({});\n", descriptor_to_var(des)).as_str());
-                }
             }
+            result.push_str(encode_op(op).as_str());
         }
         result.push_str("}");
         result
     }
+
+    /// Renders workload as bash script of coreutils commands run against mount point argument.
+    ///
+    /// Written data is the same as executor writes, system calls without coreutils equivalent are made from python.
+    /// Workloads with operations that can not be made from shell (directory streams, file handles, crash points)
+    /// are not rendered.
+    pub fn encode_shell(&self) -> Result<String, ShellError> {
+        let mut result = String::new();
+        result.push_str("#!/bin/bash\n");
+        result.push_str("# Reproducer generated by DIFFuzzer.\n");
+        result.push_str("#\n");
+        result.push_str("# Run: ./reproducer.sh <mount point>\n\n");
+        result.push_str("if [ $# -ne 1 ]; then\n");
+        result.push_str("    echo \"usage: $0 <mount point>\" >&2\n");
+        result.push_str("    exit 1\n");
+        result.push_str("fi\n");
+        result.push_str("MNT=\"$1\"\n");
+        result.push_str(&format!("SEED={}\n", self.seed));
+        result.push_str(&format!("BUFFER_SIZE={}\n\n", WRITE_BUFFER_SIZE));
+        result.push_str(SHELL_CONTENT_FN);
+        result.push('\n');
+        for (i, op) in self.ops.iter().enumerate() {
+            let line = encode_shell_op(op).ok_or(ShellError::Unsupported(i, op.kind()))?;
+            result.push_str(&line);
+        }
+        Ok(result)
+    }
+}
+
+impl Operation {
+    /// Line of C test the operation is encoded to, without line break.
+    pub fn encode_c(&self) -> String {
+        encode_op(self).trim_end().to_owned()
+    }
+}
+
+/// Single line calling executor for operation.
+fn encode_op(op: &Operation) -> String {
+    match op {
+        Operation::CREATE { path, mode } => {
+            format!("do_create(\"{}\", {});\n", path, encode_mode(mode))
+        }
+        Operation::MKDIR { path, mode } => {
+            format!("do_mkdir(\"{}\", {});\n", path, encode_mode(mode))
+        }
+        Operation::REMOVE { path } => format!("do_remove(\"{}\");\n", path),
+        Operation::MKSOCKET { path } => format!("do_mksocket(\"{}\");\n", path),
+        Operation::SYMLINK { target, path } => {
+            format!("do_symlink(\"{}\", \"{}\");\n", target, path)
+        }
+        Operation::READLINK { path } => format!("do_readlink(\"{}\");\n", path),
+        Operation::HARDLINK { old_path, new_path } => {
+            format!("do_hardlink(\"{}\", \"{}\");\n", old_path, new_path)
+        }
+        Operation::RENAME {
+            old_path,
+            new_path,
+            flags,
+        } if flags.is_empty() => format!("do_rename(\"{}\", \"{}\");\n", old_path, new_path),
+        Operation::RENAME {
+            old_path,
+            new_path,
+            flags,
+        } => format!(
+            "do_renameat2(\"{}\", \"{}\", {});\n",
+            old_path,
+            new_path,
+            encode_rename_flags(flags)
+        ),
+        Operation::OPEN { path, flags, des } => format!(
+            "{} = do_open(\"{}\", {});\n",
+            descriptor_to_var(des),
+            path,
+            encode_open_flags(flags)
+        ),
+        Operation::CLOSE { des } => format!("do_close({});\n", descriptor_to_var(des)),
+        Operation::READ { des, size } => {
+            format!("do_read({}, {});\n", descriptor_to_var(des), size)
+        }
+        Operation::WRITE {
+            des,
+            src_offset,
+            size,
+        } => format!(
+            "do_write({}, {}, {});\n",
+            descriptor_to_var(des),
+            src_offset,
+            size
+        ),
+        Operation::FSYNC { des } => format!("do_fsync({});\n", descriptor_to_var(des)),
+        Operation::FDATASYNC { des } => format!("do_fdatasync({});\n", descriptor_to_var(des)),
+        Operation::SYNCFILERANGE {
+            des,
+            offset,
+            nbytes,
+            flags,
+        } => format!(
+            "do_sync_file_range({}, {}, {}, {});\n",
+            descriptor_to_var(des),
+            offset,
+            nbytes,
+            encode_sync_file_range_flags(flags)
+        ),
+        Operation::LSEEK {
+            des,
+            offset,
+            whence,
+        } => format!(
+            "do_lseek({}, {}, {});\n",
+            descriptor_to_var(des),
+            offset,
+            whence
+        ),
+        Operation::OPENDIR { path, stream } => format!(
+            "{} = do_opendir(\"{}\");\n",
+            dir_stream_to_var(stream),
+            path
+        ),
+        Operation::READDIR { stream, count } => {
+            format!("do_readdir({}, {});\n", dir_stream_to_var(stream), count)
+        }
+        Operation::CLOSEDIR { stream } => {
+            format!("do_closedir({});\n", dir_stream_to_var(stream))
+        }
+        Operation::LISTDIR { path } => format!("do_listdir(\"{}\");\n", path),
+        Operation::NAMETOHANDLE { path, handle } => format!(
+            "{} = do_name_to_handle(\"{}\");\n",
+            file_handle_to_var(handle),
+            path
+        ),
+        Operation::OPENBYHANDLE { handle } => {
+            format!("do_open_by_handle({});\n", file_handle_to_var(handle))
+        }
+        Operation::PREALLOC { path, size } => format!("do_prealloc(\"{}\", {});\n", path, size),
+        Operation::TRUNCATE { path, size } => format!("do_truncate(\"{}\", {});\n", path, size),
+        Operation::CHMOD { path, mode } => {
+            format!("do_chmod(\"{}\", {});\n", path, encode_mode(mode))
+        }
+        Operation::MKNOD { path, kind, mode } => {
+            format!("do_mknod(\"{}\", {}, {});\n", path, kind, encode_mode(mode))
+        }
+        Operation::SETXATTR {
+            path,
+            name,
+            value_size,
+        } => format!("do_setxattr(\"{}\", \"{}\", {});\n", path, name, value_size),
+        Operation::REMOVEXATTR { path, name } => {
+            format!("do_removexattr(\"{}\", \"{}\");\n", path, name)
+        }
+        Operation::UTIMENS { path, atime, mtime } => {
+            format!("do_utimens(\"{}\", {}, {});\n", path, atime, mtime)
+        }
+        Operation::STAT { path } => format!("do_stat(\"{}\");\n", path),
+        Operation::FALLOCATE {
+            des,
+            offset,
+            len,
+            mode,
+        } => format!(
+            "do_fallocate({}, {}, {}, {});\n",
+            descriptor_to_var(des),
+            offset,
+            len,
+            encode_fallocate_flags(mode)
+        ),
+        Operation::CRASHPOINT => "do_crashpoint();\n".to_owned(),
+    }
+}
+
+/// Shell lines doing operation, `None` if it can not be done from shell.
+fn encode_shell_op(op: &Operation) -> Option<String> {
+    let line = match op {
+        Operation::MKDIR { path, mode } => {
+            format!("mkdir -m {} {}\n", encode_mode_octal(mode), mnt(path))
+        }
+        Operation::CREATE { path, mode } => format!(
+            "touch {}\nchmod {} {}\n",
+            mnt(path),
+            encode_mode_octal(mode),
+            mnt(path)
+        ),
+        Operation::REMOVE { path } => format!("rm -rf {}\n", mnt(path)),
+        Operation::MKSOCKET { path } => format!(
+            "python3 -c 'import socket, sys; socket.socket(socket.AF_UNIX).bind(sys.argv[1])' {}\n",
+            mnt(path)
+        ),
+        Operation::SYMLINK { target, path } => format!("ln -s '{}' {}\n", target, mnt(path)),
+        Operation::READLINK { path } => format!("readlink {}\n", mnt(path)),
+        Operation::HARDLINK { old_path, new_path } => {
+            format!("ln {} {}\n", mnt(old_path), mnt(new_path))
+        }
+        // needs coreutils 9.5 or newer
+        Operation::RENAME {
+            old_path,
+            new_path,
+            flags,
+        } if flags.contains(&RenameFlag::RENAME_EXCHANGE) => {
+            format!("mv -T --exchange {} {}\n", mnt(old_path), mnt(new_path))
+        }
+        // `mv -n` skips existing target without failing
+        Operation::RENAME {
+            old_path,
+            new_path,
+            flags,
+        } if flags.contains(&RenameFlag::RENAME_NOREPLACE) => format!(
+            "python3 -c 'import ctypes, os, sys; libc = ctypes.CDLL(None, use_errno=True); \
+             libc.renameat2(-100, os.fsencode(sys.argv[1]), -100, os.fsencode(sys.argv[2]), 1) == 0 \
+             or sys.exit(os.strerror(ctypes.get_errno()))' {} {}\n",
+            mnt(old_path),
+            mnt(new_path)
+        ),
+        Operation::RENAME {
+            old_path, new_path, ..
+        } => format!("mv -T {} {}\n", mnt(old_path), mnt(new_path)),
+        // redirection always creates missing file and follows symlink,
+        // exclusive and no-follow opens are emulated with checks
+        Operation::OPEN { path, flags, des } => {
+            let mut line = String::new();
+            if flags.contains(&OpenFlag::O_EXCL) {
+                line.push_str(&format!("[ -e {} ] || ", mnt(path)));
+            }
+            if flags.contains(&OpenFlag::O_EXCL) || flags.contains(&OpenFlag::O_NOFOLLOW) {
+                line.push_str(&format!("[ -L {} ] || ", mnt(path)));
+            }
+            let open = format!("exec {{{}}}<>{}", descriptor_to_var(des), mnt(path));
+            // shell can not open file for both reading and appending,
+            // flag is set on open file (shared with child process)
+            if flags.contains(&OpenFlag::O_APPEND) {
+                line.push_str(&format!(
+                    "{{ {} && python3 -c 'import fcntl, os, sys; fd = int(sys.argv[1]); \
+                     fcntl.fcntl(fd, fcntl.F_SETFL, fcntl.fcntl(fd, fcntl.F_GETFL) | os.O_APPEND)' ${}; }}\n",
+                    open,
+                    descriptor_to_var(des)
+                ));
+            } else {
+                line.push_str(&open);
+                line.push('\n');
+            }
+            line
+        }
+        Operation::CLOSE { des } => format!("exec {{{}}}>&-\n", descriptor_to_var(des)),
+        // single block, so data is read and written in one call as in executor
+        Operation::READ { des, size } if *size > 0 => format!(
+            "dd bs={} count=1 status=none <&${} > /dev/null\n",
+            size,
+            descriptor_to_var(des)
+        ),
+        Operation::READ { des, size } => {
+            format!("# read {} bytes from {}\n", size, descriptor_to_var(des))
+        }
+        Operation::WRITE {
+            des,
+            src_offset,
+            size,
+        } if *size > 0 => format!(
+            "content {} {} | dd bs={} count=1 iflag=fullblock status=none >&${}\n",
+            src_offset,
+            size,
+            size,
+            descriptor_to_var(des)
+        ),
+        Operation::WRITE { des, size, .. } => {
+            format!("# write {} bytes to {}\n", size, descriptor_to_var(des))
+        }
+        Operation::FSYNC { des } => format!("sync /proc/$$/fd/${}\n", descriptor_to_var(des)),
+        Operation::FDATASYNC { des } => {
+            format!("sync -d /proc/$$/fd/${}\n", descriptor_to_var(des))
+        }
+        Operation::SYNCFILERANGE {
+            des,
+            offset,
+            nbytes,
+            flags,
+        } => format!(
+            "python3 -c 'import ctypes, os, sys; libc = ctypes.CDLL(None, use_errno=True); \
+             libc.sync_file_range(int(sys.argv[1]), ctypes.c_int64({}), ctypes.c_int64({}), {}) == 0 \
+             or sys.exit(os.strerror(ctypes.get_errno()))' ${}\n",
+            offset,
+            nbytes,
+            sync_file_range_flags_value(flags),
+            descriptor_to_var(des)
+        ),
+        // child process shares offset of inherited descriptor
+        Operation::LSEEK {
+            des,
+            offset,
+            whence,
+        } => format!(
+            "python3 -c 'import os, sys; os.lseek(int(sys.argv[1]), {}, os.{})' ${}\n",
+            offset,
+            whence,
+            descriptor_to_var(des)
+        ),
+        Operation::LISTDIR { path } => format!("ls -a -f {}\n", mnt(path)),
+        Operation::OPENDIR { .. }
+        | Operation::READDIR { .. }
+        | Operation::CLOSEDIR { .. }
+        | Operation::NAMETOHANDLE { .. }
+        | Operation::OPENBYHANDLE { .. }
+        | Operation::CRASHPOINT => return None,
+        Operation::PREALLOC { path, size } => format!(
+            "truncate -s {} {}\ncontent 0 {} | dd of={} conv=notrunc status=none\n",
+            size,
+            mnt(path),
+            size,
+            mnt(path)
+        ),
+        Operation::TRUNCATE { path, size } => format!("truncate -s {} {}\n", size, mnt(path)),
+        Operation::CHMOD { path, mode } => {
+            format!("chmod {} {}\n", encode_mode_octal(mode), mnt(path))
+        }
+        Operation::MKNOD { path, kind, mode } => {
+            let mode = encode_mode_octal(mode);
+            match kind {
+                SpecialKind::S_IFIFO => format!("mkfifo -m {} {}\n", mode, mnt(path)),
+                SpecialKind::S_IFCHR => format!("mknod -m {} {} c 0 0\n", mode, mnt(path)),
+                SpecialKind::S_IFBLK => format!("mknod -m {} {} b 0 0\n", mode, mnt(path)),
+            }
+        }
+        Operation::SETXATTR {
+            path,
+            name,
+            value_size: 0,
+        } => format!("setfattr -n {} {}\n", name, mnt(path)),
+        // value is base64 encoded, so it has exactly that many bytes
+        Operation::SETXATTR {
+            path,
+            name,
+            value_size,
+        } => format!(
+            "setfattr -n {} -v \"0s$(content 0 {} | base64 -w 0)\" {}\n",
+            name,
+            value_size,
+            mnt(path)
+        ),
+        Operation::REMOVEXATTR { path, name } => {
+            format!("setfattr -x {} {}\n", name, mnt(path))
+        }
+        Operation::UTIMENS { path, atime, mtime } => format!(
+            "touch -h -a -d @{} {}\ntouch -h -m -d @{} {}\n",
+            atime,
+            mnt(path),
+            mtime,
+            mnt(path)
+        ),
+        Operation::STAT { path } => format!("stat {}\n", mnt(path)),
+        // punching hole implies keeping size in util-linux
+        Operation::FALLOCATE {
+            des,
+            offset,
+            len,
+            mode,
+        } => {
+            let options: String = mode
+                .iter()
+                .filter_map(|flag| match flag {
+                    FallocateFlag::FALLOC_FL_KEEP_SIZE
+                        if mode.contains(&FallocateFlag::FALLOC_FL_PUNCH_HOLE) =>
+                    {
+                        None
+                    }
+                    FallocateFlag::FALLOC_FL_KEEP_SIZE => Some(" -n"),
+                    FallocateFlag::FALLOC_FL_PUNCH_HOLE => Some(" -p"),
+                    FallocateFlag::FALLOC_FL_ZERO_RANGE => Some(" -z"),
+                })
+                .collect();
+            format!(
+                "fallocate{} -o {} -l {} /proc/$$/fd/${}\n",
+                options,
+                offset,
+                len,
+                descriptor_to_var(des)
+            )
+        }
+    };
+    Some(line)
+}
+
+fn mnt(path: &PathName) -> String {
+    if path.is_root() {
+        "\"$MNT\"".to_owned()
+    } else {
+        format!("\"$MNT{}\"", path)
+    }
 }
 
 fn encode_mode(mode: &Mode) -> String {
@@ -102,17 +533,78 @@ fn encode_mode(mode: &Mode) -> String {
     }
 }
 
+fn encode_open_flags(flags: &OpenFlags) -> String {
+    if flags.is_empty() {
+        0.to_string()
+    } else {
+        let flags_str: Vec<String> = flags.iter().map(|f| f.to_string()).collect();
+        flags_str.join(" | ")
+    }
+}
+
+fn encode_rename_flags(flags: &RenameFlags) -> String {
+    if flags.is_empty() {
+        0.to_string()
+    } else {
+        let flags_str: Vec<String> = flags.iter().map(|f| f.to_string()).collect();
+        flags_str.join(" | ")
+    }
+}
+
+fn sync_file_range_flags_value(flags: &SyncFileRangeFlags) -> u32 {
+    flags
+        .iter()
+        .map(|flag| match flag {
+            SyncFileRangeFlag::SYNC_FILE_RANGE_WAIT_BEFORE => 1,
+            SyncFileRangeFlag::SYNC_FILE_RANGE_WRITE => 2,
+            SyncFileRangeFlag::SYNC_FILE_RANGE_WAIT_AFTER => 4,
+        })
+        .fold(0, |value, flag| value | flag)
+}
+
+fn encode_sync_file_range_flags(flags: &SyncFileRangeFlags) -> String {
+    if flags.is_empty() {
+        0.to_string()
+    } else {
+        let flags_str: Vec<String> = flags.iter().map(|f| f.to_string()).collect();
+        flags_str.join(" | ")
+    }
+}
+
+fn encode_fallocate_flags(flags: &FallocateFlags) -> String {
+    if flags.is_empty() {
+        0.to_string()
+    } else {
+        let flags_str: Vec<String> = flags.iter().map(|f| f.to_string()).collect();
+        flags_str.join(" | ")
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::abstract_fs::{flags::ModeFlag, node::FileDescriptorIndex};
+    use crate::abstract_fs::{
+        flags::{ModeFlag, OpenFlag, RenameFlag, SpecialKind, SyncFileRangeFlag, Whence},
+        node::{DirStreamIndex, FileDescriptorIndex, FileHandleIndex},
+        operation::{OperationKind, OperationWeights},
+    };
+
+    use std::{env, fs, process::Command};
+
+    use crate::abstract_fs::content::source_byte;
 
     use super::*;
 
+    fn shell(op: &Operation) -> String {
+        encode_shell_op(op).expect("operation has shell equivalent")
+    }
+
     #[test]
     fn test_encode_c_empty() {
         let expected = r#"
 #include "executor.h"
 
+const uint64_t content_seed = 42;
+
 // no descriptors
 
 void test_workload()
@@ -120,7 +612,93 @@ void test_workload()
 }
 "#
         .trim();
-        let actual = Workload { ops: vec![] }.encode_c();
+        let actual = Workload {
+            ops: vec![],
+            seed: 42,
+        }
+        .encode_c(None);
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_encode_c_dir_streams() {
+        let expected = r#"
+#include "executor.h"
+
+const uint64_t content_seed = 0;
+
+int ds_0;
+
+void test_workload()
+{
+ds_0 = do_opendir("/");
+do_readdir(ds_0, 2);
+do_mkdir("/foo", 0);
+do_readdir(ds_0, 2);
+do_closedir(ds_0);
+}
+"#
+        .trim();
+        let stream = DirStreamIndex(0);
+        let actual = Workload {
+            ops: vec![
+                Operation::OPENDIR {
+                    path: "/".into(),
+                    stream,
+                },
+                Operation::READDIR { stream, count: 2 },
+                Operation::MKDIR {
+                    path: "/foo".into(),
+                    mode: vec![],
+                },
+                Operation::READDIR { stream, count: 2 },
+                Operation::CLOSEDIR { stream },
+            ],
+            seed: 0,
+        }
+        .encode_c(None);
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_encode_c_file_handles() {
+        let expected = r#"
+#include "executor.h"
+
+const uint64_t content_seed = 0;
+
+int fh_0;
+
+void test_workload()
+{
+do_mkdir("/foo", 0);
+fh_0 = do_name_to_handle("/foo");
+do_rename("/foo", "/bar");
+do_open_by_handle(fh_0);
+}
+"#
+        .trim();
+        let handle = FileHandleIndex(0);
+        let actual = Workload {
+            ops: vec![
+                Operation::MKDIR {
+                    path: "/foo".into(),
+                    mode: vec![],
+                },
+                Operation::NAMETOHANDLE {
+                    path: "/foo".into(),
+                    handle,
+                },
+                Operation::RENAME {
+                    old_path: "/foo".into(),
+                    new_path: "/bar".into(),
+                    flags: vec![],
+                },
+                Operation::OPENBYHANDLE { handle },
+            ],
+            seed: 0,
+        }
+        .encode_c(None);
         assert_eq!(expected, actual);
     }
 
@@ -129,17 +707,19 @@ void test_workload()
         let expected = r#"
 #include "executor.h"
 
+const uint64_t content_seed = 0;
+
 int fd_0, fd_1;
 
 void test_workload()
 {
 do_mkdir("/foo", 0);
 do_create("/foo/bar", S_IRWXU | S_IRWXG | S_IROTH | S_IXOTH);
-fd_0 = do_open("/foo/bar");
+fd_0 = do_open("/foo/bar", O_APPEND);
 do_write(fd_0, 999, 1024);
 do_close(fd_0);
 do_hardlink("/foo/bar", "/baz");
-fd_1 = do_open("/baz");
+fd_1 = do_open("/baz", 0);
 do_read(fd_1, 1024);
 do_fsync(fd_1);
 do_close(fd_1);
@@ -166,6 +746,7 @@ do_remove("/foo");
                 },
                 Operation::OPEN {
                     path: "/foo/bar".into(),
+                    flags: vec![OpenFlag::O_APPEND],
                     des: FileDescriptorIndex(0),
                 },
                 Operation::WRITE {
@@ -182,6 +763,7 @@ do_remove("/foo");
                 },
                 Operation::OPEN {
                     path: "/baz".into(),
+                    flags: vec![],
                     des: FileDescriptorIndex(1),
                 },
                 Operation::READ {
@@ -197,13 +779,492 @@ do_remove("/foo");
                 Operation::RENAME {
                     old_path: "/baz".into(),
                     new_path: "/gaz".into(),
+                    flags: vec![],
                 },
                 Operation::REMOVE {
                     path: "/foo".into(),
                 },
             ],
+            seed: 0,
         }
-        .encode_c();
+        .encode_c(None);
         assert_eq!(expected, actual);
     }
+
+    /// Match is exhaustive, so new operation kind can not be added without an example.
+    fn example(kind: OperationKind) -> Operation {
+        match kind {
+            OperationKind::MKDIR => Operation::MKDIR {
+                path: "/foo".into(),
+                mode: vec![ModeFlag::S_IRWXU],
+            },
+            OperationKind::CREATE => Operation::CREATE {
+                path: "/foo/bar".into(),
+                mode: vec![],
+            },
+            OperationKind::REMOVE => Operation::REMOVE {
+                path: "/foo".into(),
+            },
+            OperationKind::MKSOCKET => Operation::MKSOCKET {
+                path: "/foo/sock".into(),
+            },
+            OperationKind::SYMLINK => Operation::SYMLINK {
+                target: "../bar/".into(),
+                path: "/foo/link".into(),
+            },
+            OperationKind::READLINK => Operation::READLINK {
+                path: "/foo/link".into(),
+            },
+            OperationKind::HARDLINK => Operation::HARDLINK {
+                old_path: "/foo".into(),
+                new_path: "/bar".into(),
+            },
+            OperationKind::RENAME => Operation::RENAME {
+                old_path: "/foo".into(),
+                new_path: "/bar".into(),
+                flags: vec![],
+            },
+            OperationKind::OPEN => Operation::OPEN {
+                path: "/foo".into(),
+                flags: vec![OpenFlag::O_APPEND],
+                des: FileDescriptorIndex(1),
+            },
+            OperationKind::CLOSE => Operation::CLOSE {
+                des: FileDescriptorIndex(1),
+            },
+            OperationKind::READ => Operation::READ {
+                des: FileDescriptorIndex(1),
+                size: 1024,
+            },
+            OperationKind::WRITE => Operation::WRITE {
+                des: FileDescriptorIndex(1),
+                src_offset: 42,
+                size: 1024,
+            },
+            OperationKind::FSYNC => Operation::FSYNC {
+                des: FileDescriptorIndex(1),
+            },
+            OperationKind::FDATASYNC => Operation::FDATASYNC {
+                des: FileDescriptorIndex(1),
+            },
+            OperationKind::SYNCFILERANGE => Operation::SYNCFILERANGE {
+                des: FileDescriptorIndex(1),
+                offset: 0,
+                nbytes: 4096,
+                flags: vec![
+                    SyncFileRangeFlag::SYNC_FILE_RANGE_WRITE,
+                    SyncFileRangeFlag::SYNC_FILE_RANGE_WAIT_AFTER,
+                ],
+            },
+            OperationKind::LSEEK => Operation::LSEEK {
+                des: FileDescriptorIndex(1),
+                offset: -42,
+                whence: Whence::SEEK_END,
+            },
+            OperationKind::OPENDIR => Operation::OPENDIR {
+                path: "/foo".into(),
+                stream: DirStreamIndex(1),
+            },
+            OperationKind::READDIR => Operation::READDIR {
+                stream: DirStreamIndex(1),
+                count: 3,
+            },
+            OperationKind::CLOSEDIR => Operation::CLOSEDIR {
+                stream: DirStreamIndex(1),
+            },
+            OperationKind::LISTDIR => Operation::LISTDIR {
+                path: "/foo".into(),
+            },
+            OperationKind::NAMETOHANDLE => Operation::NAMETOHANDLE {
+                path: "/foo".into(),
+                handle: FileHandleIndex(1),
+            },
+            OperationKind::OPENBYHANDLE => Operation::OPENBYHANDLE {
+                handle: FileHandleIndex(1),
+            },
+            OperationKind::PREALLOC => Operation::PREALLOC {
+                path: "/foo".into(),
+                size: 1048576,
+            },
+            OperationKind::TRUNCATE => Operation::TRUNCATE {
+                path: "/foo".into(),
+                size: 4096,
+            },
+            OperationKind::CHMOD => Operation::CHMOD {
+                path: "/foo".into(),
+                mode: vec![ModeFlag::S_IRUSR, ModeFlag::S_ISGID],
+            },
+            OperationKind::MKNOD => Operation::MKNOD {
+                path: "/foo/fifo".into(),
+                kind: SpecialKind::S_IFIFO,
+                mode: vec![ModeFlag::S_IRWXU],
+            },
+            OperationKind::SETXATTR => Operation::SETXATTR {
+                path: "/foo".into(),
+                name: "user.0".into(),
+                value_size: 255,
+            },
+            OperationKind::REMOVEXATTR => Operation::REMOVEXATTR {
+                path: "/foo".into(),
+                name: "user.0".into(),
+            },
+            OperationKind::UTIMENS => Operation::UTIMENS {
+                path: "/foo".into(),
+                atime: -1,
+                mtime: 4294967296,
+            },
+            OperationKind::STAT => Operation::STAT {
+                path: "/foo".into(),
+            },
+            OperationKind::FALLOCATE => Operation::FALLOCATE {
+                des: FileDescriptorIndex(1),
+                offset: 4096,
+                len: 65536,
+                mode: vec![
+                    FallocateFlag::FALLOC_FL_PUNCH_HOLE,
+                    FallocateFlag::FALLOC_FL_KEEP_SIZE,
+                ],
+            },
+            OperationKind::CRASHPOINT => Operation::CRASHPOINT,
+        }
+    }
+
+    #[test]
+    fn test_encode_every_operation() {
+        let header = include_str!("../../executor/executor.h");
+        let call = regex::Regex::new(r"^((fd|ds|fh)_\d+ = )?(do_\w+)\(.*\);\n$").unwrap();
+        for (kind, _) in OperationWeights::uniform().weights {
+            let op = example(kind);
+            assert_eq!(kind, op.kind());
+            let encoded = encode_op(&op);
+            let captures = call
+                .captures(&encoded)
+                .unwrap_or_else(|| panic!("{:?}: bad fragment '{}'", kind, encoded));
+            let function = &captures[3];
+            assert!(
+                header.contains(&format!("int {}(", function)),
+                "{:?}: '{}' is not declared by executor",
+                kind,
+                function
+            );
+            let workload = Workload {
+                ops: vec![op],
+                seed: 0,
+            }
+            .encode_c(None);
+            assert!(workload.contains(&encoded));
+        }
+    }
+
+    fn delay_workload() -> Workload {
+        Workload {
+            ops: vec![
+                Operation::MKDIR {
+                    path: "/foo".into(),
+                    mode: vec![],
+                },
+                Operation::CREATE {
+                    path: "/foo/bar".into(),
+                    mode: vec![],
+                },
+                Operation::REMOVE {
+                    path: "/foo".into(),
+                },
+            ],
+            seed: 0,
+        }
+    }
+
+    #[test]
+    fn test_encode_c_delays() {
+        let workload = delay_workload();
+        let delays = Delays {
+            max_micros: 1000,
+            seed: 42,
+        };
+        let encoded = workload.encode_c(Some(&delays));
+        let lines: Vec<&str> = encoded.lines().collect();
+        let body = &lines[lines.iter().position(|l| *l == "{").unwrap() + 1..lines.len() - 1];
+        assert_eq!(5, body.len());
+        assert!(body[0].starts_with("do_mkdir"));
+        assert!(body[1].starts_with("do_delay("));
+        assert!(body[2].starts_with("do_create"));
+        assert!(body[3].starts_with("do_delay("));
+        assert!(body[4].starts_with("do_remove"));
+        for line in [body[1], body[3]] {
+            let us: u32 = line
+                .trim_start_matches("do_delay(")
+                .trim_end_matches(");")
+                .parse()
+                .unwrap();
+            assert!(us <= delays.max_micros);
+        }
+        assert!(!workload.encode_c(None).contains("do_delay"));
+    }
+
+    #[test]
+    fn test_encode_c_delays_reproducible() {
+        let workload = Workload {
+            ops: (0..10).flat_map(|_| delay_workload().ops).collect(),
+            seed: 0,
+        };
+        let delays = Delays {
+            max_micros: 1_000_000,
+            seed: 42,
+        };
+        assert_eq!(
+            workload.encode_c(Some(&delays)),
+            workload.encode_c(Some(&delays.clone()))
+        );
+        let other = Delays {
+            seed: 43,
+            ..delays.clone()
+        };
+        assert_ne!(
+            workload.encode_c(Some(&delays)),
+            workload.encode_c(Some(&other))
+        );
+    }
+
+    #[test]
+    fn test_encode_shell_every_operation() {
+        for (kind, _) in OperationWeights::uniform().weights {
+            let expected = match kind {
+                OperationKind::MKDIR => Some("mkdir -m 0700 \"$MNT/foo\"\n"),
+                OperationKind::CREATE => Some("touch \"$MNT/foo/bar\"\nchmod 0000 \"$MNT/foo/bar\"\n"),
+                OperationKind::REMOVE => Some("rm -rf \"$MNT/foo\"\n"),
+                OperationKind::MKSOCKET => Some("python3 -c 'import socket, sys; socket.socket(socket.AF_UNIX).bind(sys.argv[1])' \"$MNT/foo/sock\"\n"),
+                OperationKind::SYMLINK => Some("ln -s '../bar/' \"$MNT/foo/link\"\n"),
+                OperationKind::READLINK => Some("readlink \"$MNT/foo/link\"\n"),
+                OperationKind::HARDLINK => Some("ln \"$MNT/foo\" \"$MNT/bar\"\n"),
+                OperationKind::RENAME => Some("mv -T \"$MNT/foo\" \"$MNT/bar\"\n"),
+                OperationKind::OPEN => Some("{ exec {fd_1}<>\"$MNT/foo\" && python3 -c 'import fcntl, os, sys; fd = int(sys.argv[1]); fcntl.fcntl(fd, fcntl.F_SETFL, fcntl.fcntl(fd, fcntl.F_GETFL) | os.O_APPEND)' $fd_1; }\n"),
+                OperationKind::CLOSE => Some("exec {fd_1}>&-\n"),
+                OperationKind::READ => Some("dd bs=1024 count=1 status=none <&$fd_1 > /dev/null\n"),
+                OperationKind::WRITE => Some("content 42 1024 | dd bs=1024 count=1 iflag=fullblock status=none >&$fd_1\n"),
+                OperationKind::FSYNC => Some("sync /proc/$$/fd/$fd_1\n"),
+                OperationKind::FDATASYNC => Some("sync -d /proc/$$/fd/$fd_1\n"),
+                OperationKind::SYNCFILERANGE => Some("python3 -c 'import ctypes, os, sys; libc = ctypes.CDLL(None, use_errno=True); libc.sync_file_range(int(sys.argv[1]), ctypes.c_int64(0), ctypes.c_int64(4096), 6) == 0 or sys.exit(os.strerror(ctypes.get_errno()))' $fd_1\n"),
+                OperationKind::LSEEK => Some("python3 -c 'import os, sys; os.lseek(int(sys.argv[1]), -42, os.SEEK_END)' $fd_1\n"),
+                OperationKind::OPENDIR => None,
+                OperationKind::READDIR => None,
+                OperationKind::CLOSEDIR => None,
+                OperationKind::LISTDIR => Some("ls -a -f \"$MNT/foo\"\n"),
+                OperationKind::NAMETOHANDLE => None,
+                OperationKind::OPENBYHANDLE => None,
+                OperationKind::PREALLOC => Some("truncate -s 1048576 \"$MNT/foo\"\ncontent 0 1048576 | dd of=\"$MNT/foo\" conv=notrunc status=none\n"),
+                OperationKind::TRUNCATE => Some("truncate -s 4096 \"$MNT/foo\"\n"),
+                OperationKind::CHMOD => Some("chmod 2400 \"$MNT/foo\"\n"),
+                OperationKind::MKNOD => Some("mkfifo -m 0700 \"$MNT/foo/fifo\"\n"),
+                OperationKind::SETXATTR => Some("setfattr -n user.0 -v \"0s$(content 0 255 | base64 -w 0)\" \"$MNT/foo\"\n"),
+                OperationKind::REMOVEXATTR => Some("setfattr -x user.0 \"$MNT/foo\"\n"),
+                OperationKind::UTIMENS => Some("touch -h -a -d @-1 \"$MNT/foo\"\ntouch -h -m -d @4294967296 \"$MNT/foo\"\n"),
+                OperationKind::STAT => Some("stat \"$MNT/foo\"\n"),
+                OperationKind::FALLOCATE => Some("fallocate -p -o 4096 -l 65536 /proc/$$/fd/$fd_1\n"),
+                OperationKind::CRASHPOINT => None,
+            };
+            assert_eq!(
+                expected.map(str::to_owned),
+                encode_shell_op(&example(kind)),
+                "{:?}",
+                kind
+            );
+        }
+    }
+
+    #[test]
+    fn test_encode_shell_edge_cases() {
+        let des = FileDescriptorIndex(0);
+        assert_eq!(
+            "exec {fd_0}<>\"$MNT\"\n",
+            shell(&Operation::OPEN {
+                path: "/".into(),
+                flags: vec![],
+                des,
+            })
+        );
+        assert_eq!(
+            "# read 0 bytes from fd_0\n",
+            shell(&Operation::READ { des, size: 0 })
+        );
+        assert_eq!(
+            "# write 0 bytes to fd_0\n",
+            shell(&Operation::WRITE {
+                des,
+                src_offset: 0,
+                size: 0
+            })
+        );
+        assert_eq!(
+            "setfattr -n user.0 \"$MNT/foo\"\n",
+            shell(&Operation::SETXATTR {
+                path: "/foo".into(),
+                name: "user.0".into(),
+                value_size: 0
+            })
+        );
+        assert_eq!(
+            "mknod -m 0000 \"$MNT/foo\" b 0 0\n",
+            shell(&Operation::MKNOD {
+                path: "/foo".into(),
+                kind: SpecialKind::S_IFBLK,
+                mode: vec![]
+            })
+        );
+        let script = Workload {
+            ops: vec![example(OperationKind::MKDIR)],
+            seed: 42,
+        }
+        .encode_shell()
+        .unwrap();
+        assert!(script.starts_with("#!/bin/bash\n"));
+        assert!(script.contains("MNT=\"$1\"\nSEED=42\n"));
+        assert!(script.ends_with("\nmkdir -m 0700 \"$MNT/foo\"\n"));
+        // directory stream can not be kept open between commands
+        assert_eq!(
+            Err(ShellError::Unsupported(1, OperationKind::OPENDIR)),
+            Workload {
+                ops: vec![
+                    example(OperationKind::MKDIR),
+                    example(OperationKind::OPENDIR)
+                ],
+                seed: 0,
+            }
+            .encode_shell()
+        );
+    }
+
+    #[test]
+    fn test_shell_content() {
+        let seed = 42;
+        let output = Command::new("bash")
+            .arg("-c")
+            .arg(format!(
+                "SEED={}\nBUFFER_SIZE={}\n{}content {} 8",
+                seed,
+                WRITE_BUFFER_SIZE,
+                SHELL_CONTENT_FN,
+                WRITE_BUFFER_SIZE - 4
+            ))
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        // buffer is repeated past its end
+        let expected: Vec<u8> = (WRITE_BUFFER_SIZE - 4..WRITE_BUFFER_SIZE)
+            .chain(0..4)
+            .map(|offset| source_byte(seed, offset))
+            .collect();
+        assert_eq!(expected, output.stdout);
+    }
+
+    #[test]
+    fn test_encode_open_create() {
+        let open = |flags| Operation::OPEN {
+            path: "/foo".into(),
+            flags,
+            des: FileDescriptorIndex(0),
+        };
+        let create = open(vec![OpenFlag::O_CREAT]);
+        let exclusive = open(vec![OpenFlag::O_CREAT, OpenFlag::O_EXCL]);
+        assert_eq!("fd_0 = do_open(\"/foo\", O_CREAT);\n", encode_op(&create));
+        assert_eq!(
+            "fd_0 = do_open(\"/foo\", O_CREAT | O_EXCL);\n",
+            encode_op(&exclusive)
+        );
+        assert_eq!("exec {fd_0}<>\"$MNT/foo\"\n", shell(&create));
+        assert_eq!(
+            "[ -e \"$MNT/foo\" ] || [ -L \"$MNT/foo\" ] || exec {fd_0}<>\"$MNT/foo\"\n",
+            shell(&exclusive)
+        );
+        let nofollow = open(vec![OpenFlag::O_NOFOLLOW]);
+        assert_eq!(
+            "fd_0 = do_open(\"/foo\", O_NOFOLLOW);\n",
+            encode_op(&nofollow)
+        );
+        assert_eq!(
+            "[ -L \"$MNT/foo\" ] || exec {fd_0}<>\"$MNT/foo\"\n",
+            shell(&nofollow)
+        );
+        // checks are not skipped for appending open
+        let exclusive_append = open(vec![
+            OpenFlag::O_CREAT,
+            OpenFlag::O_EXCL,
+            OpenFlag::O_APPEND,
+        ]);
+        assert!(shell(&exclusive_append).starts_with(
+            "[ -e \"$MNT/foo\" ] || [ -L \"$MNT/foo\" ] || { exec {fd_0}<>\"$MNT/foo\" && "
+        ));
+        let workload = Workload {
+            ops: vec![create, exclusive, nofollow, exclusive_append],
+            seed: 0,
+        };
+        assert_eq!(workload, Workload::decode_c(&workload.encode_c(None)));
+    }
+
+    #[test]
+    fn test_shell_append_open() {
+        let dir = env::temp_dir().join("DIFFuzzer-shell-append-test");
+        fs::remove_dir_all(&dir).unwrap_or(());
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("foo"), "abc").unwrap();
+        let open = shell(&Operation::OPEN {
+            path: "/foo".into(),
+            flags: vec![OpenFlag::O_APPEND],
+            des: FileDescriptorIndex(0),
+        });
+        let output = Command::new("bash")
+            .arg("-c")
+            .arg(format!(
+                "MNT=\"$1\"\n{}echo -n x >&$fd_0\n\
+                 python3 -c 'import os, sys; os.lseek(int(sys.argv[1]), 0, os.SEEK_SET)' $fd_0\n\
+                 dd bs=4 count=1 status=none <&$fd_0\n",
+                open
+            ))
+            .arg("bash")
+            .arg(&dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        // written at the end and read back through the same descriptor
+        assert_eq!(b"abcx".to_vec(), output.stdout);
+        assert_eq!("abcx", fs::read_to_string(dir.join("foo")).unwrap());
+    }
+
+    #[test]
+    fn test_encode_renameat2() {
+        let rename = |flags| Operation::RENAME {
+            old_path: "/foo".into(),
+            new_path: "/bar".into(),
+            flags,
+        };
+        let exchange = rename(vec![RenameFlag::RENAME_EXCHANGE]);
+        let noreplace = rename(vec![RenameFlag::RENAME_NOREPLACE]);
+        assert_eq!(
+            "do_renameat2(\"/foo\", \"/bar\", RENAME_EXCHANGE);\n",
+            encode_op(&exchange)
+        );
+        assert_eq!(
+            "do_renameat2(\"/foo\", \"/bar\", RENAME_NOREPLACE);\n",
+            encode_op(&noreplace)
+        );
+        assert_eq!(
+            "do_rename(\"/foo\", \"/bar\");\n",
+            encode_op(&rename(vec![]))
+        );
+        assert_eq!(
+            "mv -T --exchange \"$MNT/foo\" \"$MNT/bar\"\n",
+            shell(&exchange)
+        );
+        assert_eq!(
+            "python3 -c 'import ctypes, os, sys; libc = ctypes.CDLL(None, use_errno=True); \
+             libc.renameat2(-100, os.fsencode(sys.argv[1]), -100, os.fsencode(sys.argv[2]), 1) == 0 \
+             or sys.exit(os.strerror(ctypes.get_errno()))' \"$MNT/foo\" \"$MNT/bar\"\n",
+            shell(&noreplace)
+        );
+        let workload = Workload {
+            ops: vec![exchange, noreplace],
+            seed: 0,
+        };
+        assert_eq!(workload, Workload::decode_c(&workload.encode_c(None)));
+    }
 }
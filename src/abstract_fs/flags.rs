@@ -9,7 +9,7 @@ use serde::{Deserialize, Serialize};
 /// Flags for `open(path, flags, mode)` syscall.
 ///
 /// Applications *shall* specify __exactly one__ of the __first 5__ values.
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
 #[allow(nonstandard_style)]
 pub enum OpenFlag {
     /// Open for execute only (non-directory files).
@@ -74,6 +74,53 @@ pub enum OpenFlag {
 
     O_TTY_INIT,
 }
+
+impl Display for OpenFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl TryFrom<&str> for OpenFlag {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "O_EXEC" => Ok(OpenFlag::O_EXEC),
+            "O_RDONLY" => Ok(OpenFlag::O_RDONLY),
+            "O_RDWR" => Ok(OpenFlag::O_RDWR),
+            "O_SEARCH" => Ok(OpenFlag::O_SEARCH),
+            "O_WRONLY" => Ok(OpenFlag::O_WRONLY),
+            "O_APPEND" => Ok(OpenFlag::O_APPEND),
+            "O_CLOEXEC" => Ok(OpenFlag::O_CLOEXEC),
+            "O_CLOFORK" => Ok(OpenFlag::O_CLOFORK),
+            "O_CREAT" => Ok(OpenFlag::O_CREAT),
+            "O_DIRECTORY" => Ok(OpenFlag::O_DIRECTORY),
+            "O_DSYNC" => Ok(OpenFlag::O_DSYNC),
+            "O_EXCL" => Ok(OpenFlag::O_EXCL),
+            "O_NOCTTY" => Ok(OpenFlag::O_NOCTTY),
+            "O_NOFOLLOW" => Ok(OpenFlag::O_NOFOLLOW),
+            "O_NONBLOCK" => Ok(OpenFlag::O_NONBLOCK),
+            "O_RSYNC" => Ok(OpenFlag::O_RSYNC),
+            "O_SYNC" => Ok(OpenFlag::O_SYNC),
+            "O_TRUNC" => Ok(OpenFlag::O_TRUNC),
+            "O_TTY_INIT" => Ok(OpenFlag::O_TTY_INIT),
+            flag => Err(format!("unknown open flag '{}'", flag)),
+        }
+    }
+}
+
+/// Flags added to access mode, which is always `O_RDWR` in executor.
+pub type OpenFlags = Vec<OpenFlag>;
+
+/// Flags model knows how to handle, access modes and the rest are never passed to executor.
+pub const OPEN_FLAGS: [OpenFlag; 4] = [
+    OpenFlag::O_APPEND,
+    OpenFlag::O_CREAT,
+    OpenFlag::O_EXCL,
+    OpenFlag::O_NOFOLLOW,
+];
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
 #[allow(nonstandard_style)]
 pub enum ModeFlag {
@@ -132,4 +179,189 @@ impl Display for ModeFlag {
     }
 }
 
+impl TryFrom<&str> for ModeFlag {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "S_IRWXU" => Ok(ModeFlag::S_IRWXU),
+            "S_IRUSR" => Ok(ModeFlag::S_IRUSR),
+            "S_IWUSR" => Ok(ModeFlag::S_IWUSR),
+            "S_IXUSR" => Ok(ModeFlag::S_IXUSR),
+            "S_IRWXG" => Ok(ModeFlag::S_IRWXG),
+            "S_IRGRP" => Ok(ModeFlag::S_IRGRP),
+            "S_IWGRP" => Ok(ModeFlag::S_IWGRP),
+            "S_IXGRP" => Ok(ModeFlag::S_IXGRP),
+            "S_IRWXO" => Ok(ModeFlag::S_IRWXO),
+            "S_IROTH" => Ok(ModeFlag::S_IROTH),
+            "S_IWOTH" => Ok(ModeFlag::S_IWOTH),
+            "S_IXOTH" => Ok(ModeFlag::S_IXOTH),
+            "S_ISUID" => Ok(ModeFlag::S_ISUID),
+            "S_ISGID" => Ok(ModeFlag::S_ISGID),
+            "S_ISVTX" => Ok(ModeFlag::S_ISVTX),
+            flag => Err(format!("unknown mode flag '{}'", flag)),
+        }
+    }
+}
+
 pub type Mode = Vec<ModeFlag>;
+
+/// Type of special file created with `mknod(path, type | mode, dev)`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+#[allow(nonstandard_style)]
+pub enum SpecialKind {
+    /// FIFO special (named pipe).
+    S_IFIFO,
+    /// Character special.
+    S_IFCHR,
+    /// Block special.
+    S_IFBLK,
+}
+
+impl Display for SpecialKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl TryFrom<&str> for SpecialKind {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "S_IFIFO" => Ok(SpecialKind::S_IFIFO),
+            "S_IFCHR" => Ok(SpecialKind::S_IFCHR),
+            "S_IFBLK" => Ok(SpecialKind::S_IFBLK),
+            kind => Err(format!("unknown special file kind '{}'", kind)),
+        }
+    }
+}
+
+/// Flags for `sync_file_range(fd, offset, nbytes, flags)` syscall (Linux specific).
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+#[allow(nonstandard_style)]
+pub enum SyncFileRangeFlag {
+    /// Wait for write-out of already submitted pages in range before writing.
+    SYNC_FILE_RANGE_WAIT_BEFORE,
+    /// Start write-out of dirty pages in range.
+    SYNC_FILE_RANGE_WRITE,
+    /// Wait for write-out of pages in range after writing.
+    SYNC_FILE_RANGE_WAIT_AFTER,
+}
+
+impl Display for SyncFileRangeFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl TryFrom<&str> for SyncFileRangeFlag {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "SYNC_FILE_RANGE_WAIT_BEFORE" => Ok(SyncFileRangeFlag::SYNC_FILE_RANGE_WAIT_BEFORE),
+            "SYNC_FILE_RANGE_WRITE" => Ok(SyncFileRangeFlag::SYNC_FILE_RANGE_WRITE),
+            "SYNC_FILE_RANGE_WAIT_AFTER" => Ok(SyncFileRangeFlag::SYNC_FILE_RANGE_WAIT_AFTER),
+            flag => Err(format!("unknown sync file range flag '{}'", flag)),
+        }
+    }
+}
+
+pub type SyncFileRangeFlags = Vec<SyncFileRangeFlag>;
+
+/// Flags for `fallocate(fd, mode, offset, len)` syscall (Linux specific).
+///
+/// Without flags range is allocated and file grows if range ends past the end of file.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+#[allow(nonstandard_style)]
+pub enum FallocateFlag {
+    /// File size does not change, even if range ends past the end of file.
+    FALLOC_FL_KEEP_SIZE,
+    /// Deallocate range, it reads as zeroes afterwards. Must be used with `FALLOC_FL_KEEP_SIZE`.
+    FALLOC_FL_PUNCH_HOLE,
+    /// Zero range, file grows unless `FALLOC_FL_KEEP_SIZE` is set. Can not be used with `FALLOC_FL_PUNCH_HOLE`.
+    FALLOC_FL_ZERO_RANGE,
+}
+
+impl Display for FallocateFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl TryFrom<&str> for FallocateFlag {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "FALLOC_FL_KEEP_SIZE" => Ok(FallocateFlag::FALLOC_FL_KEEP_SIZE),
+            "FALLOC_FL_PUNCH_HOLE" => Ok(FallocateFlag::FALLOC_FL_PUNCH_HOLE),
+            "FALLOC_FL_ZERO_RANGE" => Ok(FallocateFlag::FALLOC_FL_ZERO_RANGE),
+            flag => Err(format!("unknown fallocate flag '{}'", flag)),
+        }
+    }
+}
+
+pub type FallocateFlags = Vec<FallocateFlag>;
+
+/// Flags for `renameat2(olddirfd, oldpath, newdirfd, newpath, flags)` syscall (Linux specific).
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+#[allow(nonstandard_style)]
+pub enum RenameFlag {
+    /// Fail with `EEXIST` instead of replacing existing target.
+    RENAME_NOREPLACE,
+    /// Atomically exchange both paths, which must exist (can not be used with `RENAME_NOREPLACE`).
+    RENAME_EXCHANGE,
+}
+
+impl Display for RenameFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl TryFrom<&str> for RenameFlag {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "RENAME_NOREPLACE" => Ok(RenameFlag::RENAME_NOREPLACE),
+            "RENAME_EXCHANGE" => Ok(RenameFlag::RENAME_EXCHANGE),
+            flag => Err(format!("unknown rename flag '{}'", flag)),
+        }
+    }
+}
+
+pub type RenameFlags = Vec<RenameFlag>;
+
+/// Base of new offset for `lseek(fd, offset, whence)` syscall.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+#[allow(nonstandard_style)]
+pub enum Whence {
+    /// The file offset shall be set to offset bytes.
+    SEEK_SET,
+    /// The file offset shall be set to its current location plus offset.
+    SEEK_CUR,
+    /// The file offset shall be set to the size of the file plus offset.
+    SEEK_END,
+}
+
+impl Display for Whence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl TryFrom<&str> for Whence {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "SEEK_SET" => Ok(Whence::SEEK_SET),
+            "SEEK_CUR" => Ok(Whence::SEEK_CUR),
+            "SEEK_END" => Ok(Whence::SEEK_END),
+            whence => Err(format!("unknown whence '{}'", whence)),
+        }
+    }
+}
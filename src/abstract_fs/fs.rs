@@ -1,11 +1,22 @@
-use std::collections::{HashMap, VecDeque};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::Display,
+};
 
+use log::{log_enabled, trace, Level};
 use thiserror::Error;
 
 use super::{
     content::{Content, ContentError},
-    flags::Mode,
-    node::{Dir, DirIndex, File, FileDescriptor, FileDescriptorIndex, FileIndex, Node},
+    flags::{
+        FallocateFlag, FallocateFlags, Mode, OpenFlag, OpenFlags, RenameFlag, RenameFlags,
+        SpecialKind, SyncFileRangeFlags, Whence, OPEN_FLAGS,
+    },
+    node::{
+        Dir, DirIndex, DirStream, DirStreamIndex, File, FileDescriptor, FileDescriptorIndex,
+        FileHandle, FileHandleIndex, FileIndex, Node, Socket, SocketIndex, Special, SpecialIndex,
+        Symlink, SymlinkIndex, Times, Xattrs,
+    },
     operation::Operation,
     pathname::{Name, PathName},
     workload::Workload,
@@ -13,6 +24,9 @@ use super::{
 
 type Result<T> = std::result::Result<T, FsError>;
 
+/// Symlinks followed while resolving a single path, same as `MAXSYMLINKS` in Linux.
+const MAX_SYMLINK_HOPS: usize = 40;
+
 #[derive(Error, Debug, PartialEq, Eq)]
 pub enum FsError {
     #[error("'{0}' is not a file")]
@@ -37,6 +51,44 @@ pub enum FsError {
     FileAlreadyOpened(PathName),
     #[error("tried to rename '{0}' into subdirectory of itself '{1}'")]
     RenameToSubdirectoryError(PathName, PathName),
+    #[error("hardlink to directory '{0}' is not permitted")]
+    HardlinkToDir(PathName),
+    #[error("RENAME_EXCHANGE can not be used with RENAME_NOREPLACE")]
+    ExchangeWithNoReplace,
+    #[error("seek to negative offset '{1}' with descriptor '{0}'")]
+    NegativeOffset(FileDescriptorIndex, i64),
+    #[error("bad directory stream '{0}' ({1} created)")]
+    BadDirStream(DirStreamIndex, usize),
+    #[error("directory stream '{0}' was already closed")]
+    DirStreamWasClosed(DirStreamIndex),
+    #[error("bad file handle '{0}' ({1} created)")]
+    BadFileHandle(FileHandleIndex, usize),
+    #[error("node limit reached ({0} nodes)")]
+    NodeLimitReached(usize),
+    #[error("'{0}' can not have extended attributes")]
+    XattrNotSupported(PathName),
+    #[error("no extended attribute '{1}' at '{0}'")]
+    XattrNotFound(PathName, String),
+    #[error("fallocate of empty range with descriptor '{0}'")]
+    EmptyFallocateRange(FileDescriptorIndex),
+    #[error("unsupported fallocate mode {0:?}")]
+    BadFallocateMode(FallocateFlags),
+    #[error("unsupported open flags {0:?}")]
+    BadOpenFlags(OpenFlags),
+    #[error("'{0}' is not a symlink")]
+    NotASymlink(PathName),
+    #[error("symlink '{0}' would be followed")]
+    SymlinkFollowed(PathName),
+    #[error("symlink '{0}' is not followed with O_NOFOLLOW")]
+    SymlinkNotFollowed(PathName),
+    #[error("invalid symlink target '{0}'")]
+    InvalidSymlinkTarget(String),
+    #[error("symlink '{0}' points outside of workspace")]
+    SymlinkOutside(PathName),
+    #[error("too many levels of symlinks at '{0}'")]
+    SymlinkLoop(PathName),
+    #[error("bad node index '{0:?}'")]
+    BadNodeIndex(Node),
     #[error(transparent)]
     ContentError(#[from] ContentError),
 }
@@ -44,169 +96,845 @@ pub enum FsError {
 pub struct AbstractFS {
     pub dirs: Vec<Dir>,
     pub files: Vec<File>,
+    pub sockets: Vec<Socket>,
+    pub specials: Vec<Special>,
+    pub symlinks: Vec<Symlink>,
 
     pub descriptors: Vec<FileDescriptor>,
+    pub dir_streams: Vec<DirStream>,
+    pub file_handles: Vec<FileHandle>,
 
     pub recording: Workload,
+    /// Indices of recorded operations that filesystem is expected to fail.
+    pub expected_failures: Vec<u32>,
+    /// Indices of recorded operations which outcome depends on data that survived crash point
+    /// (see [`File::crashed`]).
+    pub crash_dependent: Vec<u32>,
+    model_trace: Option<ModelTrace>,
+
+    /// Deleted nodes are never reclaimed, so total (not alive) number of nodes is limited.
+    pub max_nodes: Option<usize>,
+    /// Only respected by generator, model itself accepts any number of alive nodes.
+    pub max_alive_nodes: Option<usize>,
+    /// Only respected by generator, new nodes are not created deeper than that.
+    pub max_path_depth: Option<usize>,
+    /// Only respected by generator, extended attributes of one node do not take more bytes
+    /// (see [`super::generator::XATTR_ENTRY_OVERHEAD`]).
+    pub max_xattr_space: Option<u64>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct AliveNodes {
     pub dirs: Vec<PathName>,
     pub files: Vec<(FileIndex, PathName)>,
+    pub sockets: Vec<PathName>,
+    pub specials: Vec<PathName>,
+    pub symlinks: Vec<PathName>,
+}
+
+impl AliveNodes {
+    /// Hardlinked files are counted once.
+    pub fn node_count(&self) -> usize {
+        let files: HashSet<&FileIndex> = self.files.iter().map(|(idx, _)| idx).collect();
+        self.dirs.len()
+            + files.len()
+            + self.sockets.len()
+            + self.specials.len()
+            + self.symlinks.len()
+    }
+}
+
+/// Operation applied to model along with paths it made alive and paths it removed.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ModelStep {
+    pub op: Operation,
+    pub added: Vec<PathName>,
+    pub removed: Vec<PathName>,
+}
+
+impl Display for ModelStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |paths: &[PathName]| {
+            paths
+                .iter()
+                .map(|p| p.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        write!(
+            f,
+            "{:?} added [{}] removed [{}]",
+            self.op,
+            join(&self.added),
+            join(&self.removed)
+        )
+    }
+}
+
+struct ModelTrace {
+    steps: Vec<ModelStep>,
+    alive: Vec<PathName>,
 }
 
 impl AbstractFS {
+    /// Model traces every applied operation if trace level is enabled for this module.
     pub fn new() -> Self {
-        AbstractFS {
+        let mut fs = AbstractFS {
             dirs: vec![Dir {
                 children: HashMap::new(),
+                mode: vec![],
+                xattrs: Xattrs::new(),
+                times: Times::default(),
             }],
             files: vec![],
+            sockets: vec![],
+            specials: vec![],
+            symlinks: vec![],
             descriptors: vec![],
+            dir_streams: vec![],
+            file_handles: vec![],
             recording: Workload::new(),
+            expected_failures: vec![],
+            crash_dependent: vec![],
+            model_trace: None,
+            max_nodes: None,
+            max_alive_nodes: None,
+            max_path_depth: None,
+            max_xattr_space: None,
+        };
+        if log_enabled!(Level::Trace) {
+            fs.enable_trace();
+        }
+        fs
+    }
+
+    /// Starts keeping (and logging) every applied operation with alive paths it adds and removes.
+    ///
+    /// Each step takes a snapshot of alive nodes, so it is disabled by default.
+    pub fn enable_trace(&mut self) {
+        self.model_trace = Some(ModelTrace {
+            steps: vec![],
+            alive: self.alive_paths(),
+        });
+    }
+
+    #[allow(dead_code)]
+    pub fn trace(&self) -> Option<&[ModelStep]> {
+        self.model_trace.as_ref().map(|t| t.steps.as_slice())
+    }
+
+    fn record(&mut self, op: Operation) {
+        if let Some(mut model_trace) = self.model_trace.take() {
+            let alive = self.alive_paths();
+            let step = ModelStep {
+                op: op.clone(),
+                added: alive
+                    .iter()
+                    .filter(|p| !model_trace.alive.contains(p))
+                    .cloned()
+                    .collect(),
+                removed: model_trace
+                    .alive
+                    .iter()
+                    .filter(|p| !alive.contains(p))
+                    .cloned()
+                    .collect(),
+            };
+            trace!("model step {}: {}", model_trace.steps.len(), step);
+            model_trace.steps.push(step);
+            model_trace.alive = alive;
+            self.model_trace = Some(model_trace);
+        }
+        self.recording.push(op);
+    }
+
+    /// Records operation that filesystem must reject.
+    fn record_failure(&mut self, op: Operation) {
+        self.expected_failures.push(self.recording.ops.len() as u32);
+        self.record(op);
+    }
+
+    /// Records operation that can legitimately have different outcome on filesystems after crash.
+    fn record_crash_dependent(&mut self, op: Operation) {
+        self.crash_dependent.push(self.recording.ops.len() as u32);
+        self.record(op);
+    }
+
+    /// Indices of operations of workload that are expected to fail, none if model rejects workload.
+    pub fn expected_failures_of(workload: &Workload) -> Vec<u32> {
+        let mut fs = AbstractFS::new();
+        match fs.replay(workload) {
+            Ok(()) => fs.expected_failures,
+            Err(_) => vec![],
+        }
+    }
+
+    /// Indices of operations of workload which outcome depends on crash, none if model rejects workload.
+    pub fn crash_dependent_of(workload: &Workload) -> Vec<u32> {
+        let mut fs = AbstractFS::new();
+        match fs.replay(workload) {
+            Ok(()) => fs.crash_dependent,
+            Err(_) => vec![],
+        }
+    }
+
+    /// Indices of reads of files whose access or modification time was set (by `UTIMENS`) later than `now`
+    /// (in seconds since epoch), none if model rejects workload.
+    pub fn future_time_reads_of(workload: &Workload, now: i64) -> Vec<u32> {
+        let mut fs = AbstractFS::new();
+        let mut reads = vec![];
+        for (index, op) in workload.ops.iter().enumerate() {
+            if let Operation::READ { des, .. } = op {
+                let future = fs
+                    .descriptor(des)
+                    .and_then(|des| fs.file(&des.file))
+                    .is_ok_and(|file| {
+                        [file.times.atime, file.times.mtime]
+                            .into_iter()
+                            .flatten()
+                            .any(|time| time > now)
+                    });
+                if future {
+                    reads.push(index as u32);
+                }
+            }
+            let op = Workload {
+                ops: vec![op.clone()],
+                seed: workload.seed,
+            };
+            if fs.replay(&op).is_err() {
+                return vec![];
+            }
+        }
+        reads
+    }
+
+    /// Paths of files that may have lost data at crash point, when workload is completed.
+    pub fn crashed_paths_of(workload: &Workload) -> Vec<PathName> {
+        let mut fs = AbstractFS::new();
+        if fs.replay(workload).is_err() {
+            return vec![];
+        }
+        fs.alive()
+            .files
+            .into_iter()
+            .filter(|(idx, _)| fs.file(idx).is_ok_and(|file| file.crashed.is_some()))
+            .map(|(_, path)| path)
+            .collect()
+    }
+
+    /// Sorted paths of every alive node, unlike [`AbstractFS::alive`] does not depend on creation order.
+    pub fn alive_paths(&self) -> Vec<PathName> {
+        let alive = self.alive();
+        let mut paths: Vec<PathName> = alive
+            .dirs
+            .into_iter()
+            .chain(alive.files.into_iter().map(|(_, path)| path))
+            .chain(alive.sockets)
+            .chain(alive.specials)
+            .chain(alive.symlinks)
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    /// Total number of nodes ever created, including removed ones.
+    pub fn node_count(&self) -> usize {
+        self.dirs.len()
+            + self.files.len()
+            + self.sockets.len()
+            + self.specials.len()
+            + self.symlinks.len()
+    }
+
+    fn check_node_limit(&self) -> Result<()> {
+        match self.max_nodes {
+            Some(limit) if self.node_count() >= limit => Err(FsError::NodeLimitReached(limit)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Drops nodes that are not reachable from root or any descriptor and rebuilds indices.
+    ///
+    /// Only useful for standalone replays, because indices returned earlier become invalid.
+    pub fn compact(&mut self) {
+        let mut dirs = vec![Dir {
+            children: HashMap::new(),
+            mode: self.root().mode.clone(),
+            xattrs: self.root().xattrs.clone(),
+            times: self.root().times,
+        }];
+        let mut files: Vec<File> = vec![];
+        let mut sockets: Vec<Socket> = vec![];
+        let mut specials: Vec<Special> = vec![];
+        let mut symlinks: Vec<Symlink> = vec![];
+        let mut dirs_map: HashMap<DirIndex, DirIndex> = HashMap::new();
+        let mut files_map: HashMap<FileIndex, FileIndex> = HashMap::new();
+        let mut sockets_map: HashMap<SocketIndex, SocketIndex> = HashMap::new();
+        let mut specials_map: HashMap<SpecialIndex, SpecialIndex> = HashMap::new();
+        let mut symlinks_map: HashMap<SymlinkIndex, SymlinkIndex> = HashMap::new();
+        let mut queue: VecDeque<(DirIndex, DirIndex)> = VecDeque::new();
+        dirs_map.insert(AbstractFS::root_index(), AbstractFS::root_index());
+        queue.push_back((AbstractFS::root_index(), AbstractFS::root_index()));
+        while let Some((old_idx, new_idx)) = queue.pop_front() {
+            let mut children = HashMap::new();
+            let Ok(old_dir) = self.dir(&old_idx) else {
+                continue;
+            };
+            for (name, node) in old_dir.children.iter() {
+                let node = match node {
+                    Node::DIR(idx) => {
+                        let new_dir = DirIndex(dirs.len());
+                        dirs.push(Dir {
+                            children: HashMap::new(),
+                            mode: self.dirs[idx.0].mode.clone(),
+                            xattrs: self.dirs[idx.0].xattrs.clone(),
+                            times: self.dirs[idx.0].times,
+                        });
+                        dirs_map.insert(*idx, new_dir);
+                        queue.push_back((*idx, new_dir));
+                        Node::DIR(new_dir)
+                    }
+                    Node::FILE(idx) => {
+                        let new_file = *files_map.entry(*idx).or_insert_with(|| {
+                            files.push(self.files[idx.0].clone());
+                            FileIndex(files.len() - 1)
+                        });
+                        Node::FILE(new_file)
+                    }
+                    Node::SOCKET(idx) => {
+                        sockets.push(Socket {});
+                        let new_socket = SocketIndex(sockets.len() - 1);
+                        sockets_map.insert(*idx, new_socket);
+                        Node::SOCKET(new_socket)
+                    }
+                    Node::SPECIAL(idx) => {
+                        let new_special = *specials_map.entry(*idx).or_insert_with(|| {
+                            specials.push(self.specials[idx.0].clone());
+                            SpecialIndex(specials.len() - 1)
+                        });
+                        Node::SPECIAL(new_special)
+                    }
+                    Node::SYMLINK(idx) => {
+                        symlinks.push(self.symlinks[idx.0].clone());
+                        let new_symlink = SymlinkIndex(symlinks.len() - 1);
+                        symlinks_map.insert(*idx, new_symlink);
+                        Node::SYMLINK(new_symlink)
+                    }
+                };
+                children.insert(name.clone(), node);
+            }
+            dirs[new_idx.0].children = children;
+        }
+        for des in self.descriptors.iter_mut() {
+            des.file = *files_map.entry(des.file).or_insert_with(|| {
+                files.push(self.files[des.file.0].clone());
+                FileIndex(files.len() - 1)
+            });
+        }
+        for stream in self.dir_streams.iter_mut() {
+            // nothing is left to read from removed directory
+            stream.dir = *dirs_map.entry(stream.dir).or_insert_with(|| {
+                dirs.push(Dir {
+                    children: HashMap::new(),
+                    mode: self.dirs[stream.dir.0].mode.clone(),
+                    xattrs: self.dirs[stream.dir.0].xattrs.clone(),
+                    times: self.dirs[stream.dir.0].times,
+                });
+                DirIndex(dirs.len() - 1)
+            });
         }
+        for handle in self.file_handles.iter_mut() {
+            // handles of removed nodes get a fresh unreachable node, so they stay stale
+            handle.node = match handle.node {
+                Node::DIR(idx) => Node::DIR(*dirs_map.entry(idx).or_insert_with(|| {
+                    dirs.push(Dir {
+                        children: HashMap::new(),
+                        mode: self.dirs[idx.0].mode.clone(),
+                        xattrs: self.dirs[idx.0].xattrs.clone(),
+                        times: self.dirs[idx.0].times,
+                    });
+                    DirIndex(dirs.len() - 1)
+                })),
+                Node::FILE(idx) => Node::FILE(*files_map.entry(idx).or_insert_with(|| {
+                    files.push(self.files[idx.0].clone());
+                    FileIndex(files.len() - 1)
+                })),
+                Node::SOCKET(idx) => Node::SOCKET(*sockets_map.entry(idx).or_insert_with(|| {
+                    sockets.push(Socket {});
+                    SocketIndex(sockets.len() - 1)
+                })),
+                Node::SPECIAL(idx) => {
+                    Node::SPECIAL(*specials_map.entry(idx).or_insert_with(|| {
+                        specials.push(self.specials[idx.0].clone());
+                        SpecialIndex(specials.len() - 1)
+                    }))
+                }
+                Node::SYMLINK(idx) => {
+                    Node::SYMLINK(*symlinks_map.entry(idx).or_insert_with(|| {
+                        symlinks.push(self.symlinks[idx.0].clone());
+                        SymlinkIndex(symlinks.len() - 1)
+                    }))
+                }
+            };
+        }
+        self.dirs = dirs;
+        self.files = files;
+        self.sockets = sockets;
+        self.specials = specials;
+        self.symlinks = symlinks;
     }
 
     pub fn remove(&mut self, path: PathName) -> Result<()> {
         if path.is_root() {
             return Err(FsError::RootRemovalForbidden);
         }
-        let (parent_path, name) = path.split();
+        let (parent_path, name) = AbstractFS::split(&path)?;
         let parent_idx = self.resolve_dir(parent_path.to_owned())?;
-        let parent = self.dir_mut(&parent_idx);
-        if parent.children.remove(&name).is_none() {
+        if !self.name_exists(&parent_idx, &name)? {
             return Err(FsError::NotFound(path));
         }
-        self.recording
-            .push(Operation::REMOVE { path: path.clone() });
+        let node = self.entries_mut(&parent_idx)?.remove(&name).unwrap();
+        self.unlink(&node)?;
+        self.record(Operation::REMOVE { path: path.clone() });
         Ok(())
     }
 
     pub fn mkdir(&mut self, path: PathName, mode: Mode) -> Result<DirIndex> {
-        let (parent_path, name) = path.split();
+        let (parent_path, name) = AbstractFS::split(&path)?;
         let parent = self.resolve_dir(parent_path.to_owned())?;
-        if self.name_exists(&parent, &name) {
+        if self.name_exists(&parent, &name)? {
             return Err(FsError::NameAlreadyExists(path));
         }
+        self.check_node_limit()?;
         let dir = Dir {
             children: HashMap::new(),
+            mode: mode.clone(),
+            xattrs: Xattrs::new(),
+            times: Times::default(),
         };
         let dir_idx = DirIndex(self.dirs.len());
         self.dirs.push(dir);
-        self.dir_mut(&parent)
-            .children
-            .insert(name, Node::DIR(dir_idx));
-        self.recording.push(Operation::MKDIR { path, mode });
+        self.entries_mut(&parent)?.insert(name, Node::DIR(dir_idx));
+        self.record(Operation::MKDIR { path, mode });
         Ok(dir_idx)
     }
 
     pub fn create(&mut self, path: PathName, mode: Mode) -> Result<FileIndex> {
-        let (parent_path, name) = path.split();
+        let (parent_path, name) = AbstractFS::split(&path)?;
         let parent = self.resolve_dir(parent_path.to_owned())?;
-        if self.name_exists(&parent, &name) {
+        if self.name_exists(&parent, &name)? {
             return Err(FsError::NameAlreadyExists(path));
         }
+        let file_idx = self.new_file(&parent, name, mode.clone())?;
+        self.record(Operation::CREATE { path, mode });
+        Ok(file_idx)
+    }
+
+    fn new_file(&mut self, parent: &DirIndex, name: Name, mode: Mode) -> Result<FileIndex> {
+        self.check_node_limit()?;
         let file = File {
-            descriptor: None,
+            descriptors: vec![],
             content: Content::new(),
+            mode,
+            nlink: 1,
+            xattrs: Xattrs::new(),
+            times: Times::default(),
+            unsynced: vec![],
+            synced: Content::new(),
+            crashed: None,
         };
         let file_idx = FileIndex(self.files.len());
         self.files.push(file);
-        self.dir_mut(&parent)
-            .children
-            .insert(name.clone(), Node::FILE(file_idx));
-        self.recording.push(Operation::CREATE { path, mode });
+        self.entries_mut(parent)?.insert(name, Node::FILE(file_idx));
         Ok(file_idx)
     }
 
+    pub fn mksocket(&mut self, path: PathName) -> Result<SocketIndex> {
+        let (parent_path, name) = AbstractFS::split(&path)?;
+        let parent = self.resolve_dir(parent_path.to_owned())?;
+        if self.name_exists(&parent, &name)? {
+            return Err(FsError::NameAlreadyExists(path));
+        }
+        self.check_node_limit()?;
+        let socket_idx = SocketIndex(self.sockets.len());
+        self.sockets.push(Socket {});
+        self.entries_mut(&parent)?
+            .insert(name, Node::SOCKET(socket_idx));
+        self.record(Operation::MKSOCKET { path });
+        Ok(socket_idx)
+    }
+
+    /// Target must be a path of safe characters (see [`is_valid_symlink_target`]), it does not have to exist.
+    pub fn symlink(&mut self, target: String, path: PathName) -> Result<SymlinkIndex> {
+        if !is_valid_symlink_target(&target) {
+            return Err(FsError::InvalidSymlinkTarget(target));
+        }
+        let (parent_path, name) = AbstractFS::split(&path)?;
+        let parent = self.resolve_dir(parent_path.to_owned())?;
+        if self.name_exists(&parent, &name)? {
+            return Err(FsError::NameAlreadyExists(path));
+        }
+        self.check_node_limit()?;
+        let symlink_idx = SymlinkIndex(self.symlinks.len());
+        self.symlinks.push(Symlink {
+            target: target.clone(),
+        });
+        self.entries_mut(&parent)?
+            .insert(name, Node::SYMLINK(symlink_idx));
+        self.record(Operation::SYMLINK { target, path });
+        Ok(symlink_idx)
+    }
+
+    /// Target exactly as it was given when symlink was created.
+    pub fn readlink(&mut self, path: PathName) -> Result<String> {
+        let target = match self.resolve_node(path.clone())? {
+            Node::SYMLINK(idx) => self.symlink_target(&idx)?.to_owned(),
+            _ => return Err(FsError::NotASymlink(path)),
+        };
+        self.record(Operation::READLINK { path });
+        Ok(target)
+    }
+
+    /// Device nodes always get device number 0, they are never opened.
+    pub fn mknod(&mut self, path: PathName, kind: SpecialKind, mode: Mode) -> Result<SpecialIndex> {
+        let (parent_path, name) = AbstractFS::split(&path)?;
+        let parent = self.resolve_dir(parent_path.to_owned())?;
+        if self.name_exists(&parent, &name)? {
+            return Err(FsError::NameAlreadyExists(path));
+        }
+        self.check_node_limit()?;
+        let special_idx = SpecialIndex(self.specials.len());
+        self.specials.push(Special {
+            kind,
+            mode: mode.clone(),
+        });
+        self.entries_mut(&parent)?
+            .insert(name, Node::SPECIAL(special_idx));
+        self.record(Operation::MKNOD { path, kind, mode });
+        Ok(special_idx)
+    }
+
     pub fn hardlink(&mut self, old_path: PathName, new_path: PathName) -> Result<FileIndex> {
+        if let Node::DIR(_) = self.resolve_node(old_path.clone())? {
+            return Err(FsError::HardlinkToDir(old_path));
+        }
         let old_file = self.resolve_file(old_path.clone())?;
-        let (parent_path, name) = new_path.split();
+        let (parent_path, name) = AbstractFS::split(&new_path)?;
         let parent = self.resolve_dir(parent_path.to_owned())?;
-        if self.name_exists(&parent, &name) {
+        if self.name_exists(&parent, &name)? {
             return Err(FsError::NameAlreadyExists(new_path));
         }
-        let parent_dir = self.dir_mut(&parent);
-        parent_dir
-            .children
+        self.entries_mut(&parent)?
             .insert(name.clone(), Node::FILE(old_file.to_owned()));
-        self.recording
-            .push(Operation::HARDLINK { old_path, new_path });
+        self.file_mut(&old_file)?.nlink += 1;
+        self.record(Operation::HARDLINK { old_path, new_path });
         Ok(old_file.to_owned())
     }
 
+    /// Records `link` of a directory, which kernel must refuse with `EPERM`.
+    ///
+    /// Target name has to be free in existing parent, so that `EPERM` is the only error left to return.
+    pub fn hardlink_dir(&mut self, old_path: PathName, new_path: PathName) -> Result<FsError> {
+        self.resolve_dir(old_path.clone())?;
+        let (parent_path, name) = AbstractFS::split(&new_path)?;
+        let parent = self.resolve_dir(parent_path.to_owned())?;
+        if self.name_exists(&parent, &name)? {
+            return Err(FsError::NameAlreadyExists(new_path));
+        }
+        self.record_failure(Operation::HARDLINK {
+            old_path: old_path.clone(),
+            new_path,
+        });
+        Ok(FsError::HardlinkToDir(old_path))
+    }
+
+    /// Records `link` into directory reached through symlink, that kernel follows in the middle of path.
+    ///
+    /// Returns error kernel must refuse with: `ENOENT` if symlink is dangling or `ENOTDIR` if it leads
+    /// to something else than directory, `None` if file is linked into directory symlink leads to.
+    pub fn hardlink_through_symlink(
+        &mut self,
+        old_path: PathName,
+        new_path: PathName,
+    ) -> Result<Option<FsError>> {
+        let old_file = self.resolve_file(old_path.clone())?;
+        let (link_path, name) = AbstractFS::split(&new_path)?;
+        if !matches!(self.resolve_node(link_path.clone())?, Node::SYMLINK(_)) {
+            return Err(FsError::NotASymlink(link_path));
+        }
+        let parent = self
+            .follow_symlinks(link_path)
+            .and_then(|target| self.resolve_dir(target));
+        let expected = match parent {
+            Ok(parent) => {
+                if self.name_exists(&parent, &name)? {
+                    return Err(FsError::NameAlreadyExists(new_path));
+                }
+                self.entries_mut(&parent)?
+                    .insert(name, Node::FILE(old_file.to_owned()));
+                self.file_mut(&old_file)?.nlink += 1;
+                self.record(Operation::HARDLINK { old_path, new_path });
+                return Ok(None);
+            }
+            Err(err @ (FsError::NotFound(_) | FsError::NotADir(_))) => err,
+            Err(err) => return Err(err),
+        };
+        self.record_failure(Operation::HARDLINK { old_path, new_path });
+        Ok(Some(expected))
+    }
+
     pub fn rename(&mut self, old_path: PathName, new_path: PathName) -> Result<Node> {
+        self.rename_with_flags(old_path, new_path, vec![])
+    }
+
+    /// Rename as done by `renameat2`, returns node that was at old path.
+    pub fn rename_with_flags(
+        &mut self,
+        old_path: PathName,
+        new_path: PathName,
+        flags: RenameFlags,
+    ) -> Result<Node> {
+        if flags.contains(&RenameFlag::RENAME_EXCHANGE) {
+            if flags.contains(&RenameFlag::RENAME_NOREPLACE) {
+                return Err(FsError::ExchangeWithNoReplace);
+            }
+            return self.exchange(old_path, new_path, flags);
+        }
+        // checked first by kernel too, even if new path is old one or inside of it
+        if flags.contains(&RenameFlag::RENAME_NOREPLACE)
+            && self.resolve_node(new_path.clone()).is_ok()
+        {
+            return Err(FsError::NameAlreadyExists(new_path));
+        }
         if old_path.is_prefix_of(&new_path) {
             return Err(FsError::RenameToSubdirectoryError(old_path, new_path));
         }
+        let node = self.resolve_node(old_path.clone())?;
+        // both are links to the same node, nothing is changed
+        if self.resolve_node(new_path.clone()).as_ref() == Ok(&node) {
+            AbstractFS::split(&new_path)?;
+            AbstractFS::split(&old_path)?;
+            self.record(Operation::RENAME {
+                old_path,
+                new_path,
+                flags,
+            });
+            return Ok(node);
+        }
         if let Ok(dir_idx) = self.resolve_dir(new_path.clone()) {
-            if !self.dir(&dir_idx).children.is_empty() {
+            if !self.dir(&dir_idx)?.children.is_empty() {
                 return Err(FsError::DirNotEmpty(new_path));
             }
         }
-        let node = self.resolve_node(old_path.clone())?;
 
-        let (parent_path, name) = new_path.split();
+        let (new_parent_path, new_name) = AbstractFS::split(&new_path)?;
+        let new_parent = self.resolve_dir(new_parent_path.to_owned())?;
+        let (parent_path, name) = AbstractFS::split(&old_path)?;
         let parent = self.resolve_dir(parent_path.to_owned())?;
-        let parent_dir = self.dir_mut(&parent);
-        parent_dir.children.insert(name.clone(), node.clone());
 
-        let (parent_path, name) = old_path.split();
-        let parent = self.resolve_dir(parent_path.to_owned())?;
-        let parent_dir = self.dir_mut(&parent);
-        parent_dir.children.remove(&name);
+        if let Some(replaced) = self
+            .entries_mut(&new_parent)?
+            .insert(new_name.clone(), node.clone())
+        {
+            self.unlink(&replaced)?;
+        }
+
+        self.entries_mut(&parent)?.remove(&name);
 
-        self.recording
-            .push(Operation::RENAME { old_path, new_path });
+        self.record(Operation::RENAME {
+            old_path,
+            new_path,
+            flags,
+        });
         Ok(node)
     }
 
-    pub fn open(&mut self, path: PathName) -> Result<FileDescriptorIndex> {
-        let des = FileDescriptorIndex(self.descriptors.len());
-        let file_idx = self.resolve_file(path.clone())?;
-        let file = self.file_mut(&file_idx);
-        if file.descriptor.is_some() {
-            return Err(FsError::FileAlreadyOpened(path));
+    /// Swaps nodes at both paths, they can be of different kinds and directories need not be empty.
+    fn exchange(
+        &mut self,
+        old_path: PathName,
+        new_path: PathName,
+        flags: RenameFlags,
+    ) -> Result<Node> {
+        if old_path.is_prefix_of(&new_path) || new_path.is_prefix_of(&old_path) {
+            return Err(FsError::RenameToSubdirectoryError(old_path, new_path));
         }
-        file.descriptor = Some(des);
+        let old_node = self.resolve_node(old_path.clone())?;
+        let new_node = self.resolve_node(new_path.clone())?;
+
+        let (new_parent_path, new_name) = AbstractFS::split(&new_path)?;
+        let new_parent = self.resolve_dir(new_parent_path.to_owned())?;
+        let (parent_path, name) = AbstractFS::split(&old_path)?;
+        let parent = self.resolve_dir(parent_path.to_owned())?;
+
+        self.entries_mut(&parent)?.insert(name, new_node);
+        self.entries_mut(&new_parent)?
+            .insert(new_name, old_node.clone());
+
+        self.record(Operation::RENAME {
+            old_path,
+            new_path,
+            flags,
+        });
+        Ok(old_node)
+    }
+
+    /// Rename into parent that is a file (or does not exist) fails on real filesystem without any changes.
+    ///
+    /// Such rename is recorded and the error filesystem is expected to return is given back.
+    pub fn rename_to_bad_parent(
+        &mut self,
+        old_path: PathName,
+        new_path: PathName,
+        flags: RenameFlags,
+    ) -> Result<FsError> {
+        self.resolve_node(old_path.clone())?;
+        let (new_parent_path, _) = AbstractFS::split(&new_path)?;
+        let expected = match self.resolve_dir(new_parent_path) {
+            Ok(_) => return Err(FsError::InvalidPath(new_path)),
+            Err(err @ (FsError::NotADir(_) | FsError::NotFound(_))) => err,
+            Err(err) => return Err(err),
+        };
+        self.record_failure(Operation::RENAME {
+            old_path,
+            new_path,
+            flags,
+        });
+        Ok(expected)
+    }
+
+    /// Rename with `RENAME_NOREPLACE` onto existing node of any kind fails on real filesystem
+    /// with `EEXIST` without any changes.
+    ///
+    /// Such rename is recorded and the error filesystem is expected to return is given back.
+    pub fn rename_noreplace_existing(
+        &mut self,
+        old_path: PathName,
+        new_path: PathName,
+    ) -> Result<FsError> {
+        self.resolve_node(old_path.clone())?;
+        AbstractFS::split(&new_path)?;
+        self.resolve_node(new_path.clone())?;
+        self.record_failure(Operation::RENAME {
+            old_path,
+            new_path: new_path.clone(),
+            flags: vec![RenameFlag::RENAME_NOREPLACE],
+        });
+        Ok(FsError::NameAlreadyExists(new_path))
+    }
+
+    /// With `O_CREAT` missing file is created (with empty mode), with `O_EXCL` too
+    /// open fails if node of any kind already exists.
+    ///
+    /// Symlink is followed unless `O_NOFOLLOW` or `O_EXCL` is given,
+    /// with `O_CREAT` missing target of dangling symlink is created.
+    ///
+    /// File can be opened many times, every descriptor has its own offset.
+    pub fn open(&mut self, path: PathName, flags: OpenFlags) -> Result<FileDescriptorIndex> {
+        AbstractFS::check_open_flags(&flags)?;
+        let des = FileDescriptorIndex(self.descriptors.len());
+        let exclusive = flags.contains(&OpenFlag::O_CREAT) && flags.contains(&OpenFlag::O_EXCL);
+        let followed = if exclusive || flags.contains(&OpenFlag::O_NOFOLLOW) {
+            path.clone()
+        } else {
+            self.follow_symlinks(path.clone())?
+        };
+        let file_idx = if flags.contains(&OpenFlag::O_CREAT) {
+            let (parent_path, name) = AbstractFS::split(&followed)?;
+            let parent = self.resolve_dir(parent_path.to_owned())?;
+            if !self.name_exists(&parent, &name)? {
+                self.new_file(&parent, name, vec![])?
+            } else if exclusive {
+                return Err(FsError::NameAlreadyExists(path));
+            } else {
+                self.resolve_file(followed)?
+            }
+        } else {
+            self.resolve_file(followed)?
+        };
+        self.file_mut(&file_idx)?.descriptors.push(des);
         self.descriptors.push(FileDescriptor {
             file: file_idx,
             offset: 0,
+            flags: flags.clone(),
         });
-        self.recording.push(Operation::OPEN { path, des });
+        self.record(Operation::OPEN { path, flags, des });
         Ok(des)
     }
 
+    /// Records `open` which kernel must refuse: `O_CREAT | O_EXCL` of existing node of any kind
+    /// (`EEXIST`, symlinks are not followed) or symlink with `O_NOFOLLOW` (`ELOOP`).
+    ///
+    /// No descriptor is made, so its index is given to the next open.
+    pub fn open_failing(&mut self, path: PathName, flags: OpenFlags) -> Result<FsError> {
+        AbstractFS::check_open_flags(&flags)?;
+        let node = self.resolve_node(path.clone())?;
+        let expected = if flags.contains(&OpenFlag::O_CREAT) && flags.contains(&OpenFlag::O_EXCL) {
+            AbstractFS::split(&path)?;
+            FsError::NameAlreadyExists(path.clone())
+        } else if !flags.contains(&OpenFlag::O_NOFOLLOW) {
+            return Err(FsError::SymlinkFollowed(path));
+        } else if !matches!(node, Node::SYMLINK(_)) {
+            return Err(FsError::NotASymlink(path));
+        } else {
+            FsError::SymlinkNotFollowed(path.clone())
+        };
+        let des = FileDescriptorIndex(self.descriptors.len());
+        self.record_failure(Operation::OPEN { path, flags, des });
+        Ok(expected)
+    }
+
+    /// Flags outside of [`OPEN_FLAGS`] are not modeled, so executor would do something else.
+    fn check_open_flags(flags: &OpenFlags) -> Result<()> {
+        if flags.iter().any(|flag| !OPEN_FLAGS.contains(flag)) {
+            return Err(FsError::BadOpenFlags(flags.clone()));
+        }
+        Ok(())
+    }
+
     pub fn close(&mut self, des_idx: FileDescriptorIndex) -> Result<()> {
         let des = self.descriptor(&des_idx)?.clone();
-        let file = self.file_mut(&des.file);
-        if file.descriptor != Some(des_idx) {
+        let file = self.file_mut(&des.file)?;
+        if !file.descriptors.contains(&des_idx) {
             return Err(FsError::DescriptorWasClosed(des_idx));
         }
-        file.descriptor = None;
-        self.recording.push(Operation::CLOSE { des: des_idx });
+        file.descriptors.retain(|des| *des != des_idx);
+        self.record(Operation::CLOSE { des: des_idx });
         Ok(())
     }
 
     pub fn read(&mut self, des_idx: FileDescriptorIndex, size: u64) -> Result<Content> {
         let des = self.descriptor(&des_idx)?.clone();
         let offset = des.offset;
-        let file = self.file_mut(&des.file);
-        if file.descriptor != Some(des_idx) {
+        let file = self.file_mut(&des.file)?;
+        if !file.descriptors.contains(&des_idx) {
             return Err(FsError::DescriptorWasClosed(des_idx));
         }
-        let content = file.content.read(offset, size)?;
         let file_size = file.content.size();
+        // nothing is read past the end of file
+        let content = if offset > file_size {
+            Content::new()
+        } else {
+            file.content.read(offset, size)?
+        };
+        if size > 0 {
+            self.file_mut(&des.file)?.times.atime = None;
+        }
+        let crashed = self.file(&des.file)?.crashed.is_some();
         let des = self.descriptor_mut(&des_idx)?;
         des.offset += content.size();
         assert!(
-            des.offset <= file_size,
+            des.offset <= file_size.max(offset),
             "offset: {}, size: {}",
             des.offset,
             file_size
         );
-        self.recording.push(Operation::READ { des: des_idx, size });
+        if crashed {
+            self.record_crash_dependent(Operation::READ { des: des_idx, size });
+        } else {
+            self.record(Operation::READ { des: des_idx, size });
+        }
         Ok(content)
     }
 
@@ -217,22 +945,35 @@ impl AbstractFS {
         size: u64,
     ) -> Result<()> {
         let des = self.descriptor(&des_idx)?.clone();
-        let file = self.file_mut(&des.file);
-        if file.descriptor != Some(des_idx) {
+        let file = self.file_mut(&des.file)?;
+        if !file.descriptors.contains(&des_idx) {
             return Err(FsError::DescriptorWasClosed(des_idx));
         }
-        let offset = des.offset;
+        let offset = if des.flags.contains(&OpenFlag::O_APPEND) {
+            file.content.size()
+        } else {
+            des.offset
+        };
+        let old_size = file.content.size();
         file.content.write(src_offset, offset, size)?;
+        if let Some(crashed) = file.crashed.as_mut() {
+            crashed.write(src_offset, offset, size)?;
+        }
+        if size > 0 {
+            file.times.mtime = None;
+            // hole made by write past the end is lost along with new size
+            file.mark_unsynced(offset.min(old_size), offset + size);
+        }
         let file_size = file.content.size();
         let des = self.descriptor_mut(&des_idx)?;
-        des.offset += size;
+        des.offset = offset + size;
         assert!(
-            des.offset <= file_size,
+            size == 0 || des.offset <= file_size,
             "offset: {}, size: {}",
             des.offset,
             file_size
         );
-        self.recording.push(Operation::WRITE {
+        self.record(Operation::WRITE {
             des: des_idx,
             src_offset,
             size,
@@ -242,94 +983,609 @@ impl AbstractFS {
 
     pub fn fsync(&mut self, des_idx: FileDescriptorIndex) -> Result<()> {
         let des = self.descriptor(&des_idx)?.clone();
-        let file = self.file_mut(&des.file);
-        if file.descriptor != Some(des_idx) {
+        let file = self.file_mut(&des.file)?;
+        if !file.descriptors.contains(&des_idx) {
             return Err(FsError::DescriptorWasClosed(des_idx));
         }
-        self.recording.push(Operation::FSYNC { des: des_idx });
+        file.sync();
+        self.record(Operation::FSYNC { des: des_idx });
         Ok(())
     }
 
-    pub fn replay(&mut self, workload: &Workload) -> Result<()> {
-        for op in &workload.ops {
-            match op {
-                Operation::MKDIR { path, mode } => {
-                    self.mkdir(path.clone(), mode.clone())?;
-                }
-                Operation::CREATE { path, mode } => {
-                    self.create(path.clone(), mode.clone())?;
-                }
-                Operation::REMOVE { path } => self.remove(path.clone())?,
-                Operation::HARDLINK { old_path, new_path } => {
-                    self.hardlink(old_path.clone(), new_path.clone())?;
-                }
-                Operation::RENAME { old_path, new_path } => {
-                    self.rename(old_path.clone(), new_path.clone())?;
-                }
-                Operation::OPEN { path, des: _ } => {
-                    self.open(path.clone())?;
-                }
-                Operation::CLOSE { des } => {
-                    self.close(des.clone())?;
-                }
-                Operation::READ { des, size } => {
-                    self.read(des.clone(), size.clone())?;
-                }
-                Operation::WRITE {
-                    des,
-                    src_offset,
-                    size,
-                } => {
-                    self.write(des.clone(), src_offset.clone(), size.clone())?;
-                }
-                Operation::FSYNC { des } => {
-                    self.fsync(des.clone())?;
-                }
-            };
+    pub fn fdatasync(&mut self, des_idx: FileDescriptorIndex) -> Result<()> {
+        let des = self.descriptor(&des_idx)?.clone();
+        let file = self.file_mut(&des.file)?;
+        if !file.descriptors.contains(&des_idx) {
+            return Err(FsError::DescriptorWasClosed(des_idx));
         }
+        file.sync();
+        self.record(Operation::FDATASYNC { des: des_idx });
         Ok(())
     }
 
-    fn name_exists(&self, idx: &DirIndex, name: &Name) -> bool {
-        self.dir(idx).children.contains_key(name)
+    /// Range is only written back, it is not made durable (metadata is not synced).
+    pub fn sync_file_range(
+        &mut self,
+        des_idx: FileDescriptorIndex,
+        offset: u64,
+        nbytes: u64,
+        flags: SyncFileRangeFlags,
+    ) -> Result<()> {
+        let des = self.descriptor(&des_idx)?.clone();
+        let file = self.file_mut(&des.file)?;
+        if !file.descriptors.contains(&des_idx) {
+            return Err(FsError::DescriptorWasClosed(des_idx));
+        }
+        self.record(Operation::SYNCFILERANGE {
+            des: des_idx,
+            offset,
+            nbytes,
+            flags,
+        });
+        Ok(())
     }
 
-    fn dir(&self, idx: &DirIndex) -> &Dir {
-        self.dirs.get(idx.0).unwrap()
+    /// Punched hole and zeroed range are both modelled as hole, descriptor offset is not changed.
+    ///
+    /// Same as Linux, punching hole requires `FALLOC_FL_KEEP_SIZE` and can not be combined with zeroing.
+    pub fn fallocate(
+        &mut self,
+        des_idx: FileDescriptorIndex,
+        offset: u64,
+        len: u64,
+        mode: FallocateFlags,
+    ) -> Result<()> {
+        let des = self.descriptor(&des_idx)?.clone();
+        let file = self.file_mut(&des.file)?;
+        if !file.descriptors.contains(&des_idx) {
+            return Err(FsError::DescriptorWasClosed(des_idx));
+        }
+        if len == 0 {
+            return Err(FsError::EmptyFallocateRange(des_idx));
+        }
+        let keep_size = mode.contains(&FallocateFlag::FALLOC_FL_KEEP_SIZE);
+        let punch_hole = mode.contains(&FallocateFlag::FALLOC_FL_PUNCH_HOLE);
+        let zero_range = mode.contains(&FallocateFlag::FALLOC_FL_ZERO_RANGE);
+        if punch_hole && (!keep_size || zero_range) {
+            return Err(FsError::BadFallocateMode(mode));
+        }
+        if punch_hole || zero_range {
+            file.content.zero_range(offset, len, keep_size);
+            if let Some(crashed) = file.crashed.as_mut() {
+                crashed.zero_range(offset, len, keep_size);
+            }
+            file.times.mtime = None;
+            file.mark_unsynced(offset, offset + len);
+        } else if !keep_size && offset + len > file.content.size() {
+            let size = file.content.size();
+            file.content.truncate(offset + len);
+            file.times.mtime = None;
+            file.mark_unsynced(size, offset + len);
+        }
+        if let Some(crashed) = file.crashed.as_mut() {
+            if !punch_hole && !zero_range && !keep_size && offset + len > crashed.size() {
+                crashed.truncate(offset + len);
+            }
+        }
+        self.record(Operation::FALLOCATE {
+            des: des_idx,
+            offset,
+            len,
+            mode,
+        });
+        Ok(())
     }
 
-    fn dir_mut(&mut self, idx: &DirIndex) -> &mut Dir {
-        self.dirs.get_mut(idx.0).unwrap()
+    /// Offset can be set past the end of file, following write leaves a hole before written data.
+    pub fn lseek(
+        &mut self,
+        des_idx: FileDescriptorIndex,
+        offset: i64,
+        whence: Whence,
+    ) -> Result<u64> {
+        let des = self.descriptor(&des_idx)?.clone();
+        let file = self.file_mut(&des.file)?;
+        if !file.descriptors.contains(&des_idx) {
+            return Err(FsError::DescriptorWasClosed(des_idx));
+        }
+        let base = match whence {
+            Whence::SEEK_SET => 0,
+            Whence::SEEK_CUR => des.offset,
+            Whence::SEEK_END => file.content.size(),
+        };
+        let crashed = whence == Whence::SEEK_END && file.crashed.is_some();
+        let new_offset = base
+            .checked_add_signed(offset)
+            .ok_or(FsError::NegativeOffset(des_idx, offset))?;
+        self.descriptor_mut(&des_idx)?.offset = new_offset;
+        let op = Operation::LSEEK {
+            des: des_idx,
+            offset,
+            whence,
+        };
+        if crashed {
+            self.record_crash_dependent(op);
+        } else {
+            self.record(op);
+        }
+        Ok(new_offset)
     }
 
-    pub fn file(&self, idx: &FileIndex) -> &File {
-        self.files.get(idx.0).unwrap()
+    pub fn opendir(&mut self, path: PathName) -> Result<DirStreamIndex> {
+        let stream = DirStreamIndex(self.dir_streams.len());
+        let dir = self.resolve_dir(path.clone())?;
+        self.dir_streams.push(DirStream { dir, is_open: true });
+        self.record(Operation::OPENDIR { path, stream });
+        Ok(stream)
     }
 
-    fn file_mut(&mut self, idx: &FileIndex) -> &mut File {
-        self.files.get_mut(idx.0).unwrap()
+    /// Which entries are returned is unspecified if directory is modified after `opendir`,
+    /// so model only checks that stream is valid.
+    pub fn readdir(&mut self, stream_idx: DirStreamIndex, count: u64) -> Result<()> {
+        let stream = self.dir_stream(&stream_idx)?.clone();
+        if !stream.is_open {
+            return Err(FsError::DirStreamWasClosed(stream_idx));
+        }
+        self.dir_mut(&stream.dir)?.times.atime = None;
+        self.record(Operation::READDIR {
+            stream: stream_idx,
+            count,
+        });
+        Ok(())
     }
 
-    #[allow(dead_code)]
-    fn root(&self) -> &Dir {
-        self.dirs.get(0).unwrap()
+    /// Names of entries in directory, sorted since filesystems return them in different order.
+    pub fn listdir(&mut self, path: PathName) -> Result<Vec<Name>> {
+        let dir_idx = self.resolve_dir(path.clone())?;
+        let dir = self.dir_mut(&dir_idx)?;
+        dir.times.atime = None;
+        let mut names: Vec<Name> = dir.children.keys().cloned().collect();
+        names.sort();
+        self.record(Operation::LISTDIR { path });
+        Ok(names)
     }
 
-    fn descriptor(&self, idx: &FileDescriptorIndex) -> Result<&FileDescriptor> {
-        Ok(self
-            .descriptors
-            .get(idx.0)
-            .ok_or(FsError::BadDescriptor(idx.clone(), self.descriptors.len()))?)
+    pub fn closedir(&mut self, stream_idx: DirStreamIndex) -> Result<()> {
+        let len = self.dir_streams.len();
+        let stream = self
+            .dir_streams
+            .get_mut(stream_idx.0)
+            .ok_or(FsError::BadDirStream(stream_idx, len))?;
+        if !stream.is_open {
+            return Err(FsError::DirStreamWasClosed(stream_idx));
+        }
+        stream.is_open = false;
+        self.record(Operation::CLOSEDIR { stream: stream_idx });
+        Ok(())
     }
 
-    fn descriptor_mut(&mut self, idx: &FileDescriptorIndex) -> Result<&mut FileDescriptor> {
-        let len = self.descriptors.len();
-        Ok(self
-            .descriptors
+    pub fn name_to_handle(&mut self, path: PathName) -> Result<FileHandleIndex> {
+        let handle = FileHandleIndex(self.file_handles.len());
+        let node = self.resolve_node(path.clone())?;
+        self.file_handles.push(FileHandle { node });
+        self.record(Operation::NAMETOHANDLE { path, handle });
+        Ok(handle)
+    }
+
+    /// Node referenced by handle, or `None` if handle is stale.
+    ///
+    /// Handle goes stale once node is not reachable from root by any name,
+    /// node recreated at the same path is a different node.
+    pub fn resolve_handle(&self, handle_idx: FileHandleIndex) -> Result<Option<Node>> {
+        let handle = self
+            .file_handles
+            .get(handle_idx.0)
+            .ok_or(FsError::BadFileHandle(handle_idx, self.file_handles.len()))?;
+        if self.is_reachable(&handle.node) {
+            Ok(Some(handle.node.clone()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Opening by stale handle is recorded too, filesystem is expected to fail with `ESTALE`.
+    pub fn open_by_handle(&mut self, handle_idx: FileHandleIndex) -> Result<Option<Node>> {
+        let node = self.resolve_handle(handle_idx)?;
+        if node.is_some() {
+            self.record(Operation::OPENBYHANDLE { handle: handle_idx });
+        } else {
+            self.record_failure(Operation::OPENBYHANDLE { handle: handle_idx });
+        }
+        Ok(node)
+    }
+
+    /// File is truncated to `size` bytes of write buffer pattern (see [`Content::pattern`]).
+    ///
+    /// Opened files are rejected, otherwise descriptor offset could end up past the end of file.
+    pub fn prealloc(&mut self, path: PathName, size: u64) -> Result<()> {
+        let file_idx = self.resolve_file(path.clone())?;
+        let file = self.file_mut(&file_idx)?;
+        if !file.descriptors.is_empty() {
+            return Err(FsError::FileAlreadyOpened(path));
+        }
+        let old_size = file.content.size();
+        file.content = Content::pattern(size);
+        // whole content is replaced, so it is the same in both states
+        file.crashed = None;
+        file.times.mtime = None;
+        file.mark_unsynced(0, old_size.max(size));
+        self.record(Operation::PREALLOC { path, size });
+        Ok(())
+    }
+
+    /// Sockets have no mode in model, so only the operation is recorded for them.
+    ///
+    /// Symlinks are rejected, `chmod` changes mode of their target instead.
+    pub fn chmod(&mut self, path: PathName, mode: Mode) -> Result<()> {
+        match self.resolve_node(path.clone())? {
+            Node::FILE(idx) => self.file_mut(&idx)?.mode = mode.clone(),
+            Node::DIR(idx) => self.dir_mut(&idx)?.mode = mode.clone(),
+            Node::SPECIAL(idx) => self.special_mut(&idx)?.mode = mode.clone(),
+            Node::SOCKET(_) => {}
+            Node::SYMLINK(_) => return Err(FsError::SymlinkFollowed(path)),
+        }
+        self.record(Operation::CHMOD { path, mode });
+        Ok(())
+    }
+
+    /// Sockets, special nodes and symlinks have no times in model, so only the operation is recorded for them.
+    pub fn utimens(&mut self, path: PathName, atime: i64, mtime: i64) -> Result<()> {
+        let times = Times {
+            atime: Some(atime),
+            mtime: Some(mtime),
+        };
+        match self.resolve_node(path.clone())? {
+            Node::FILE(idx) => self.file_mut(&idx)?.times = times,
+            Node::DIR(idx) => self.dir_mut(&idx)?.times = times,
+            Node::SOCKET(_) | Node::SPECIAL(_) | Node::SYMLINK(_) => {}
+        }
+        self.record(Operation::UTIMENS { path, atime, mtime });
+        Ok(())
+    }
+
+    /// Node is only resolved, attributes are compared between filesystems (not with model).
+    /// Size of file that may have lost data at crash point is not known.
+    pub fn stat(&mut self, path: PathName) -> Result<()> {
+        let crashed = match self.resolve_node(path.clone())? {
+            Node::FILE(idx) => self.file(&idx)?.crashed.is_some(),
+            _ => false,
+        };
+        if crashed {
+            self.record_crash_dependent(Operation::STAT { path });
+        } else {
+            self.record(Operation::STAT { path });
+        }
+        Ok(())
+    }
+
+    /// Times of file or directory at `path`, sockets, special nodes and symlinks have none.
+    pub fn times(&self, path: PathName) -> Result<Option<Times>> {
+        match self.resolve_node(path)? {
+            Node::FILE(idx) => Ok(Some(self.file(&idx)?.times)),
+            Node::DIR(idx) => Ok(Some(self.dir(&idx)?.times)),
+            Node::SOCKET(_) | Node::SPECIAL(_) | Node::SYMLINK(_) => Ok(None),
+        }
+    }
+
+    /// Creates attribute or replaces its value, only files and directories have attributes in model.
+    pub fn setxattr(&mut self, path: PathName, name: String, value_size: u64) -> Result<()> {
+        self.xattrs_mut(path.clone())?
+            .insert(name.clone(), value_size);
+        self.record(Operation::SETXATTR {
+            path,
+            name,
+            value_size,
+        });
+        Ok(())
+    }
+
+    pub fn removexattr(&mut self, path: PathName, name: String) -> Result<()> {
+        if self.xattrs_mut(path.clone())?.remove(&name).is_none() {
+            return Err(FsError::XattrNotFound(path, name));
+        }
+        self.record(Operation::REMOVEXATTR { path, name });
+        Ok(())
+    }
+
+    /// Extended attributes of file or directory at `path`.
+    pub fn xattrs(&self, path: PathName) -> Result<&Xattrs> {
+        match self.resolve_node(path.clone())? {
+            Node::FILE(idx) => Ok(&self.file(&idx)?.xattrs),
+            Node::DIR(idx) => Ok(&self.dir(&idx)?.xattrs),
+            Node::SOCKET(_) | Node::SPECIAL(_) | Node::SYMLINK(_) => {
+                Err(FsError::XattrNotSupported(path))
+            }
+        }
+    }
+
+    fn xattrs_mut(&mut self, path: PathName) -> Result<&mut Xattrs> {
+        match self.resolve_node(path.clone())? {
+            Node::FILE(idx) => Ok(&mut self.file_mut(&idx)?.xattrs),
+            Node::DIR(idx) => Ok(&mut self.dir_mut(&idx)?.xattrs),
+            Node::SOCKET(_) | Node::SPECIAL(_) | Node::SYMLINK(_) => {
+                Err(FsError::XattrNotSupported(path))
+            }
+        }
+    }
+
+    /// Offset of opened descriptor is kept, so it can end up past the end of file.
+    pub fn truncate(&mut self, path: PathName, size: u64) -> Result<()> {
+        let file_idx = self.resolve_file(path.clone())?;
+        let file = self.file_mut(&file_idx)?;
+        let old_size = file.content.size();
+        file.content.truncate(size);
+        if let Some(crashed) = file.crashed.as_mut() {
+            crashed.truncate(size);
+        }
+        file.times.mtime = None;
+        file.mark_unsynced(old_size.min(size), old_size.max(size));
+        self.record(Operation::TRUNCATE { path, size });
+        Ok(())
+    }
+
+    /// Simulated crash closes every descriptor and directory stream, tree itself is kept.
+    ///
+    /// Unsynced ranges (see [`File::unsynced`]) may or may not survive it, so file with any
+    /// is expected to have either the latest content or the one it had when it was last synced.
+    pub fn crashpoint(&mut self) -> Result<()> {
+        for file in self.files.iter_mut() {
+            file.descriptors.clear();
+            if !file.unsynced.is_empty() {
+                file.crashed = Some(file.synced.clone());
+            }
+        }
+        for stream in self.dir_streams.iter_mut() {
+            stream.is_open = false;
+        }
+        self.record(Operation::CRASHPOINT);
+        Ok(())
+    }
+
+    /// Applies operations of workload, its seed is kept in recording.
+    pub fn replay(&mut self, workload: &Workload) -> Result<()> {
+        self.recording.seed = workload.seed;
+        for op in &workload.ops {
+            match op {
+                Operation::MKDIR { path, mode } => {
+                    self.mkdir(path.clone(), mode.clone())?;
+                }
+                Operation::CREATE { path, mode } => {
+                    self.create(path.clone(), mode.clone())?;
+                }
+                Operation::REMOVE { path } => self.remove(path.clone())?,
+                Operation::MKSOCKET { path } => {
+                    self.mksocket(path.clone())?;
+                }
+                Operation::SYMLINK { target, path } => {
+                    self.symlink(target.clone(), path.clone())?;
+                }
+                Operation::READLINK { path } => {
+                    self.readlink(path.clone())?;
+                }
+                Operation::HARDLINK { old_path, new_path } => {
+                    if let Err(err) = self.hardlink(old_path.clone(), new_path.clone()) {
+                        if self
+                            .hardlink_dir(old_path.clone(), new_path.clone())
+                            .is_err()
+                        {
+                            self.hardlink_through_symlink(old_path.clone(), new_path.clone())
+                                .map_err(|_| err)?;
+                        }
+                    }
+                }
+                Operation::RENAME {
+                    old_path,
+                    new_path,
+                    flags,
+                } => {
+                    if let Err(err) =
+                        self.rename_with_flags(old_path.clone(), new_path.clone(), flags.clone())
+                    {
+                        let expected = if *flags == vec![RenameFlag::RENAME_NOREPLACE]
+                            && matches!(err, FsError::NameAlreadyExists(_))
+                        {
+                            self.rename_noreplace_existing(old_path.clone(), new_path.clone())
+                        } else {
+                            self.rename_to_bad_parent(
+                                old_path.clone(),
+                                new_path.clone(),
+                                flags.clone(),
+                            )
+                        };
+                        expected.map_err(|_| err)?;
+                    }
+                }
+                Operation::OPEN {
+                    path,
+                    flags,
+                    des: _,
+                } => {
+                    if let Err(err) = self.open(path.clone(), flags.clone()) {
+                        self.open_failing(path.clone(), flags.clone())
+                            .map_err(|_| err)?;
+                    }
+                }
+                Operation::CLOSE { des } => {
+                    self.close(des.clone())?;
+                }
+                Operation::READ { des, size } => {
+                    self.read(des.clone(), size.clone())?;
+                }
+                Operation::WRITE {
+                    des,
+                    src_offset,
+                    size,
+                } => {
+                    self.write(des.clone(), src_offset.clone(), size.clone())?;
+                }
+                Operation::LSEEK {
+                    des,
+                    offset,
+                    whence,
+                } => {
+                    self.lseek(*des, *offset, *whence)?;
+                }
+                Operation::FDATASYNC { des } => {
+                    self.fdatasync(des.clone())?;
+                }
+                Operation::SYNCFILERANGE {
+                    des,
+                    offset,
+                    nbytes,
+                    flags,
+                } => {
+                    self.sync_file_range(des.clone(), *offset, *nbytes, flags.clone())?;
+                }
+                Operation::FSYNC { des } => {
+                    self.fsync(des.clone())?;
+                }
+                Operation::OPENDIR { path, stream: _ } => {
+                    self.opendir(path.clone())?;
+                }
+                Operation::READDIR { stream, count } => {
+                    self.readdir(*stream, *count)?;
+                }
+                Operation::CLOSEDIR { stream } => {
+                    self.closedir(*stream)?;
+                }
+                Operation::LISTDIR { path } => {
+                    self.listdir(path.clone())?;
+                }
+                Operation::NAMETOHANDLE { path, handle: _ } => {
+                    self.name_to_handle(path.clone())?;
+                }
+                Operation::OPENBYHANDLE { handle } => {
+                    self.open_by_handle(*handle)?;
+                }
+                Operation::PREALLOC { path, size } => {
+                    self.prealloc(path.clone(), *size)?;
+                }
+                Operation::TRUNCATE { path, size } => {
+                    self.truncate(path.clone(), *size)?;
+                }
+                Operation::CRASHPOINT => self.crashpoint()?,
+                Operation::CHMOD { path, mode } => {
+                    self.chmod(path.clone(), mode.clone())?;
+                }
+                Operation::MKNOD { path, kind, mode } => {
+                    self.mknod(path.clone(), *kind, mode.clone())?;
+                }
+                Operation::SETXATTR {
+                    path,
+                    name,
+                    value_size,
+                } => {
+                    self.setxattr(path.clone(), name.clone(), *value_size)?;
+                }
+                Operation::REMOVEXATTR { path, name } => {
+                    self.removexattr(path.clone(), name.clone())?;
+                }
+                Operation::UTIMENS { path, atime, mtime } => {
+                    self.utimens(path.clone(), *atime, *mtime)?;
+                }
+                Operation::STAT { path } => {
+                    self.stat(path.clone())?;
+                }
+                Operation::FALLOCATE {
+                    des,
+                    offset,
+                    len,
+                    mode,
+                } => {
+                    self.fallocate(*des, *offset, *len, mode.clone())?;
+                }
+            };
+        }
+        Ok(())
+    }
+
+    fn split(path: &PathName) -> Result<(PathName, Name)> {
+        if !path.is_valid() || path.is_root() {
+            return Err(FsError::InvalidPath(path.clone()));
+        }
+        Ok(path.split())
+    }
+
+    fn name_exists(&self, idx: &DirIndex, name: &Name) -> Result<bool> {
+        Ok(self.dir(idx)?.children.contains_key(name))
+    }
+
+    fn dir(&self, idx: &DirIndex) -> Result<&Dir> {
+        self.dirs
+            .get(idx.0)
+            .ok_or(FsError::BadNodeIndex(Node::DIR(*idx)))
+    }
+
+    fn dir_mut(&mut self, idx: &DirIndex) -> Result<&mut Dir> {
+        self.dirs
+            .get_mut(idx.0)
+            .ok_or(FsError::BadNodeIndex(Node::DIR(*idx)))
+    }
+
+    /// Entries of directory for adding or removing names, which updates its modification time.
+    fn entries_mut(&mut self, idx: &DirIndex) -> Result<&mut HashMap<Name, Node>> {
+        let dir = self.dir_mut(idx)?;
+        dir.times.mtime = None;
+        Ok(&mut dir.children)
+    }
+
+    pub fn file(&self, idx: &FileIndex) -> Result<&File> {
+        self.files
+            .get(idx.0)
+            .ok_or(FsError::BadNodeIndex(Node::FILE(*idx)))
+    }
+
+    fn file_mut(&mut self, idx: &FileIndex) -> Result<&mut File> {
+        self.files
+            .get_mut(idx.0)
+            .ok_or(FsError::BadNodeIndex(Node::FILE(*idx)))
+    }
+
+    pub fn special(&self, idx: &SpecialIndex) -> Result<&Special> {
+        self.specials
+            .get(idx.0)
+            .ok_or(FsError::BadNodeIndex(Node::SPECIAL(*idx)))
+    }
+
+    fn special_mut(&mut self, idx: &SpecialIndex) -> Result<&mut Special> {
+        self.specials
+            .get_mut(idx.0)
+            .ok_or(FsError::BadNodeIndex(Node::SPECIAL(*idx)))
+    }
+
+    fn symlink_target(&self, idx: &SymlinkIndex) -> Result<&str> {
+        self.symlinks
+            .get(idx.0)
+            .map(|symlink| symlink.target.as_str())
+            .ok_or(FsError::BadNodeIndex(Node::SYMLINK(*idx)))
+    }
+
+    #[allow(dead_code)]
+    fn root(&self) -> &Dir {
+        self.dirs.get(0).unwrap()
+    }
+
+    fn descriptor(&self, idx: &FileDescriptorIndex) -> Result<&FileDescriptor> {
+        Ok(self
+            .descriptors
+            .get(idx.0)
+            .ok_or(FsError::BadDescriptor(idx.clone(), self.descriptors.len()))?)
+    }
+
+    fn descriptor_mut(&mut self, idx: &FileDescriptorIndex) -> Result<&mut FileDescriptor> {
+        let len = self.descriptors.len();
+        Ok(self
+            .descriptors
             .get_mut(idx.0)
             .ok_or(FsError::BadDescriptor(idx.clone(), len))?)
     }
 
+    fn dir_stream(&self, idx: &DirStreamIndex) -> Result<&DirStream> {
+        self.dir_streams
+            .get(idx.0)
+            .ok_or(FsError::BadDirStream(*idx, self.dir_streams.len()))
+    }
+
     pub fn resolve_node(&self, path: PathName) -> Result<Node> {
         if !path.is_valid() {
             return Err(FsError::InvalidPath(path));
@@ -341,7 +1597,7 @@ impl AbstractFS {
             path.push_str("/");
             path.push_str(segment);
             let dir = match last {
-                Node::DIR(dir_index) => self.dir(&dir_index),
+                Node::DIR(dir_index) => self.dir(&dir_index)?,
                 _ => return Err(FsError::NotADir(path.into())),
             };
             last = dir
@@ -353,6 +1609,53 @@ impl AbstractFS {
         Ok(last)
     }
 
+    /// Path that `path` resolves to when symlink in its last component is followed (as `open`
+    /// does without `O_NOFOLLOW`), `path` itself if it is not a symlink or does not exist.
+    ///
+    /// Executor is not confined to workspace, so absolute targets and targets leading out of it
+    /// are refused. Model does not follow symlinks in the middle of path, so targets going
+    /// through them (e.g. `link/..`) fail to resolve.
+    pub fn follow_symlinks(&self, path: PathName) -> Result<PathName> {
+        let mut path = path;
+        for _ in 0..MAX_SYMLINK_HOPS {
+            let target = match self.resolve_node(path.clone()) {
+                Ok(Node::SYMLINK(idx)) => self.symlink_target(&idx)?,
+                _ => return Ok(path),
+            };
+            if target.starts_with('/') {
+                return Err(FsError::SymlinkOutside(path));
+            }
+            let mut next = path.split().0;
+            for segment in target.split('/') {
+                match segment {
+                    "" => {}
+                    "." => {
+                        self.resolve_dir(next.clone())?;
+                    }
+                    ".." => {
+                        self.resolve_dir(next.clone())?;
+                        if next.is_root() {
+                            return Err(FsError::SymlinkOutside(path));
+                        }
+                        next = next.split().0;
+                    }
+                    name => next = next.join(name.to_owned()),
+                }
+            }
+            if target.ends_with('/') {
+                self.resolve_dir(next.clone())?;
+            }
+            path = next;
+        }
+        Err(FsError::SymlinkLoop(path))
+    }
+
+    /// Names of entries in directory at `path`.
+    pub fn child_names(&self, path: PathName) -> Result<Vec<Name>> {
+        let idx = self.resolve_dir(path)?;
+        Ok(self.dir(&idx)?.children.keys().cloned().collect())
+    }
+
     pub fn resolve_file(&self, path: PathName) -> Result<FileIndex> {
         match self.resolve_node(path.clone())? {
             Node::FILE(idx) => Ok(idx),
@@ -367,6 +1670,62 @@ impl AbstractFS {
         }
     }
 
+    fn is_reachable(&self, node: &Node) -> bool {
+        let root = Node::DIR(AbstractFS::root_index());
+        let mut queue: VecDeque<&Node> = VecDeque::new();
+        queue.push_back(&root);
+        while let Some(next) = queue.pop_front() {
+            if next == node {
+                return true;
+            }
+            if let Node::DIR(idx) = next {
+                if let Ok(dir) = self.dir(idx) {
+                    queue.extend(dir.children.values());
+                }
+            }
+        }
+        false
+    }
+
+    /// Link count of directory: its entry in parent, its own `.` and `..` of every subdirectory.
+    pub fn dir_nlink(&self, path: PathName) -> Result<u64> {
+        let dir = self.resolve_dir(path)?;
+        let subdirs = self
+            .dir(&dir)?
+            .children
+            .values()
+            .filter(|node| matches!(node, Node::DIR(_)))
+            .count();
+        Ok(2 + subdirs as u64)
+    }
+
+    /// Link count of any node, see [`AbstractFS::dir_nlink`] for directories.
+    pub fn nlink(&self, path: PathName) -> Result<u64> {
+        match self.resolve_node(path.clone())? {
+            Node::FILE(idx) => Ok(self.file(&idx)?.nlink),
+            Node::DIR(_) => self.dir_nlink(path),
+            Node::SOCKET(_) | Node::SPECIAL(_) | Node::SYMLINK(_) => Ok(1),
+        }
+    }
+
+    /// Drops one name of node, everything inside removed directory loses its name too.
+    fn unlink(&mut self, node: &Node) -> Result<()> {
+        match node {
+            Node::FILE(idx) => {
+                let file = self.file_mut(idx)?;
+                file.nlink -= 1;
+            }
+            Node::DIR(idx) => {
+                let children: Vec<Node> = self.dir(idx)?.children.values().cloned().collect();
+                for child in children.iter() {
+                    self.unlink(child)?;
+                }
+            }
+            Node::SOCKET(_) | Node::SPECIAL(_) | Node::SYMLINK(_) => {}
+        }
+        Ok(())
+    }
+
     pub fn root_index() -> DirIndex {
         DirIndex(0)
     }
@@ -376,12 +1735,17 @@ impl AbstractFS {
         let mut alive = AliveNodes {
             dirs: vec![],
             files: vec![],
+            sockets: vec![],
+            specials: vec![],
+            symlinks: vec![],
         };
         let mut queue: VecDeque<(PathName, &DirIndex)> = VecDeque::new();
         queue.push_back(("/".into(), &root));
         alive.dirs.push("/".into());
         while let Some((path, idx)) = queue.pop_front() {
-            let dir = self.dir(idx);
+            let Ok(dir) = self.dir(idx) else {
+                continue;
+            };
             for (name, node) in dir.children.iter() {
                 match node {
                     Node::DIR(idx) => {
@@ -392,18 +1756,41 @@ impl AbstractFS {
                     Node::FILE(idx) => {
                         alive.files.push((idx.clone(), path.join(name.to_owned())));
                     }
+                    Node::SOCKET(_) => {
+                        alive.sockets.push(path.join(name.to_owned()));
+                    }
+                    Node::SPECIAL(_) => {
+                        alive.specials.push(path.join(name.to_owned()));
+                    }
+                    Node::SYMLINK(_) => {
+                        alive.symlinks.push(path.join(name.to_owned()));
+                    }
                 }
             }
         }
         alive.dirs.sort();
         alive.files.sort();
+        alive.sockets.sort();
+        alive.specials.sort();
+        alive.symlinks.sort();
         alive
     }
 }
 
+/// Target is written unquoted in traces and quoted in encoded workloads, so only safe characters are allowed.
+pub fn is_valid_symlink_target(target: &str) -> bool {
+    !target.is_empty()
+        && target
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '.' | '-' | '_'))
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::abstract_fs::content::SourceSlice;
+    use crate::abstract_fs::{
+        content::SourceSlice,
+        flags::{FallocateFlag, ModeFlag, SyncFileRangeFlag, Whence},
+    };
 
     use super::*;
 
@@ -413,7 +1800,10 @@ mod tests {
         assert_eq!(
             AliveNodes {
                 dirs: vec!["/".into()],
-                files: vec![]
+                files: vec![],
+                sockets: vec![],
+                specials: vec![],
+                symlinks: vec![],
             },
             fs.alive()
         )
@@ -436,13 +1826,17 @@ mod tests {
                     path: "/foobar".into(),
                     mode: vec![],
                 }],
+                seed: 0,
             },
             fs.recording
         );
         assert_eq!(
             AliveNodes {
                 dirs: vec!["/".into(), "/foobar".into()],
-                files: vec![]
+                files: vec![],
+                sockets: vec![],
+                specials: vec![],
+                symlinks: vec![],
             },
             fs.alive()
         );
@@ -467,7 +1861,10 @@ mod tests {
         assert_eq!(
             AliveNodes {
                 dirs: vec!["/".into()],
-                files: vec![(foo, "/foobar".into())]
+                files: vec![(foo, "/foobar".into())],
+                sockets: vec![],
+                specials: vec![],
+                symlinks: vec![],
             },
             fs.alive()
         );
@@ -476,7 +1873,8 @@ mod tests {
                 ops: vec![Operation::CREATE {
                     path: "/foobar".into(),
                     mode: vec![],
-                }]
+                }],
+                seed: 0,
             },
             fs.recording
         );
@@ -502,7 +1900,10 @@ mod tests {
         assert_eq!(
             AliveNodes {
                 dirs: vec!["/".into()],
-                files: vec![(foo, "/foobar".into()), (boo, "/boo".into())]
+                files: vec![(foo, "/foobar".into()), (boo, "/boo".into())],
+                sockets: vec![],
+                specials: vec![],
+                symlinks: vec![],
             },
             fs.alive()
         );
@@ -514,7 +1915,10 @@ mod tests {
         assert_eq!(
             AliveNodes {
                 dirs: vec!["/".into()],
-                files: vec![(boo, "/boo".into())]
+                files: vec![(boo, "/boo".into())],
+                sockets: vec![],
+                specials: vec![],
+                symlinks: vec![],
             },
             fs.alive()
         );
@@ -533,6 +1937,7 @@ mod tests {
                         path: "/foobar".into(),
                     }
                 ],
+                seed: 0,
             },
             fs.recording
         );
@@ -550,13 +1955,16 @@ mod tests {
         assert_eq!(
             AliveNodes {
                 dirs: vec!["/".into(), "/bar".into()],
-                files: vec![(boo, "/bar/boo".into()), (foo, "/foo".into())]
+                files: vec![(boo, "/bar/boo".into()), (foo, "/foo".into())],
+                sockets: vec![],
+                specials: vec![],
+                symlinks: vec![],
             },
             fs.alive()
         );
 
         let root = fs.root();
-        let bar_dir = fs.dir(&bar);
+        let bar_dir = fs.dir(&bar).unwrap();
         assert_eq!(2, root.children.len());
         assert_eq!(1, bar_dir.children.len());
         assert_eq!(
@@ -580,6 +1988,7 @@ mod tests {
                         new_path: "/bar/boo".into(),
                     }
                 ],
+                seed: 0,
             },
             fs.recording
         );
@@ -596,7 +2005,10 @@ mod tests {
         assert_eq!(
             AliveNodes {
                 dirs: vec!["/".into()],
-                files: vec![(foo, "/foo".into())]
+                files: vec![(foo, "/foo".into())],
+                sockets: vec![],
+                specials: vec![],
+                symlinks: vec![],
             },
             fs.alive()
         );
@@ -618,6 +2030,7 @@ mod tests {
                         path: "/bar".into(),
                     }
                 ],
+                seed: 0,
             },
             fs.recording
         );
@@ -640,6 +2053,45 @@ mod tests {
         assert_eq!(Ok(zero), fs.resolve_file("/0".into()));
     }
 
+    #[test]
+    fn test_nlink() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        assert_eq!(1, fs.nlink("/foo".into()).unwrap());
+        fs.hardlink("/foo".into(), "/bar".into()).unwrap();
+        fs.mkdir("/dir".into(), vec![]).unwrap();
+        fs.hardlink("/foo".into(), "/dir/baz".into()).unwrap();
+        assert_eq!(3, fs.nlink("/foo".into()).unwrap());
+        assert_eq!(3, fs.nlink("/dir/baz".into()).unwrap());
+        fs.remove("/foo".into()).unwrap();
+        assert_eq!(2, fs.nlink("/bar".into()).unwrap());
+        fs.remove("/dir".into()).unwrap();
+        assert_eq!(1, fs.nlink("/bar".into()).unwrap());
+        fs.remove("/bar".into()).unwrap();
+        assert_eq!(0, fs.file(&foo).unwrap().nlink);
+        assert_eq!(
+            Err(FsError::NotFound("/bar".into())),
+            fs.nlink("/bar".into())
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_nlink_rename() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let bar = fs.create("/bar".into(), vec![]).unwrap();
+        fs.hardlink("/bar".into(), "/baz".into()).unwrap();
+        fs.rename("/foo".into(), "/boo".into()).unwrap();
+        assert_eq!(1, fs.nlink("/boo".into()).unwrap());
+        fs.rename("/boo".into(), "/bar".into()).unwrap();
+        assert_eq!(1, fs.file(&foo).unwrap().nlink);
+        assert_eq!(1, fs.file(&bar).unwrap().nlink);
+        assert_eq!(1, fs.nlink("/baz".into()).unwrap());
+        assert_eq!(2, fs.nlink("/".into()).unwrap());
+        test_replay(fs.recording);
+    }
+
     #[test]
     fn test_hardlink_name_exists() {
         let mut fs = AbstractFS::new();
@@ -652,35 +2104,117 @@ mod tests {
     }
 
     #[test]
-    fn test_remove_dir() {
+    fn test_hardlink_dir() {
         let mut fs = AbstractFS::new();
-        fs.mkdir("/foobar".into(), vec![]).unwrap();
-        let boo = fs.mkdir("/boo".into(), vec![]).unwrap();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.mkdir("/bar".into(), vec![]).unwrap();
+        let alive = fs.alive();
 
         assert_eq!(
-            AliveNodes {
-                dirs: vec!["/".into(), "/boo".into(), "/foobar".into()],
-                files: vec![]
-            },
-            fs.alive()
+            Err(FsError::HardlinkToDir("/foo".into())),
+            fs.hardlink("/foo".into(), "/bar/foo".into())
         );
+        assert_eq!(
+            Err(FsError::HardlinkToDir("/".into())),
+            fs.hardlink("/".into(), "/bar/root".into())
+        );
+        assert_eq!(2, fs.recording.ops.len());
 
-        fs.remove("/foobar".into()).unwrap();
-
-        assert_eq!(1, fs.root().children.len());
-        assert_eq!(Node::DIR(boo), *fs.root().children.get("boo").unwrap());
+        let expected = fs.hardlink_dir("/foo".into(), "/bar/foo".into()).unwrap();
+        assert_eq!(FsError::HardlinkToDir("/foo".into()), expected);
         assert_eq!(
-            AliveNodes {
-                dirs: vec!["/".into(), "/boo".into()],
-                files: vec![]
-            },
-            fs.alive()
+            Err(FsError::NameAlreadyExists("/bar".into())),
+            fs.hardlink_dir("/foo".into(), "/bar".into())
         );
         assert_eq!(
-            Workload {
-                ops: vec![
-                    Operation::MKDIR {
-                        path: "/foobar".into(),
+            Err(FsError::NotFound("/baz".into())),
+            fs.hardlink_dir("/baz".into(), "/bar/baz".into())
+        );
+
+        assert_eq!(alive, fs.alive());
+        assert_eq!(3, fs.recording.ops.len());
+        assert_eq!(vec![2], fs.expected_failures);
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_hardlink_through_symlink() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        fs.mkdir("/bar".into(), vec![]).unwrap();
+        fs.symlink("bar".into(), "/dir".into()).unwrap();
+        fs.symlink("foo".into(), "/file".into()).unwrap();
+        fs.symlink("baz".into(), "/dangling".into()).unwrap();
+
+        assert_eq!(
+            Err(FsError::NotADir("/dir".into())),
+            fs.hardlink("/foo".into(), "/dir/boo".into())
+        );
+        assert_eq!(
+            Ok(None),
+            fs.hardlink_through_symlink("/foo".into(), "/dir/boo".into())
+        );
+        assert_eq!(Ok(Node::FILE(foo)), fs.resolve_node("/bar/boo".into()));
+        assert_eq!(2, fs.file(&foo).unwrap().nlink);
+        assert_eq!(
+            Err(FsError::NameAlreadyExists("/dir/boo".into())),
+            fs.hardlink_through_symlink("/foo".into(), "/dir/boo".into())
+        );
+        assert_eq!(
+            Err(FsError::NotASymlink("/bar".into())),
+            fs.hardlink_through_symlink("/foo".into(), "/bar/zoo".into())
+        );
+
+        let alive = fs.alive();
+        assert_eq!(
+            Ok(Some(FsError::NotADir("/foo".into()))),
+            fs.hardlink_through_symlink("/foo".into(), "/file/zoo".into())
+        );
+        assert_eq!(
+            Ok(Some(FsError::NotFound("/baz".into()))),
+            fs.hardlink_through_symlink("/foo".into(), "/dangling/zoo".into())
+        );
+        assert_eq!(alive, fs.alive());
+        assert_eq!(vec![6, 7], fs.expected_failures);
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_remove_dir() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foobar".into(), vec![]).unwrap();
+        let boo = fs.mkdir("/boo".into(), vec![]).unwrap();
+
+        assert_eq!(
+            AliveNodes {
+                dirs: vec!["/".into(), "/boo".into(), "/foobar".into()],
+                files: vec![],
+                sockets: vec![],
+                specials: vec![],
+                symlinks: vec![],
+            },
+            fs.alive()
+        );
+
+        fs.remove("/foobar".into()).unwrap();
+
+        assert_eq!(1, fs.root().children.len());
+        assert_eq!(Node::DIR(boo), *fs.root().children.get("boo").unwrap());
+        assert_eq!(
+            AliveNodes {
+                dirs: vec!["/".into(), "/boo".into()],
+                files: vec![],
+                sockets: vec![],
+                specials: vec![],
+                symlinks: vec![],
+            },
+            fs.alive()
+        );
+        assert_eq!(
+            Workload {
+                ops: vec![
+                    Operation::MKDIR {
+                        path: "/foobar".into(),
                         mode: vec![],
                     },
                     Operation::MKDIR {
@@ -691,6 +2225,7 @@ mod tests {
                         path: "/foobar".into(),
                     }
                 ],
+                seed: 0,
             },
             fs.recording
         );
@@ -713,7 +2248,10 @@ mod tests {
         assert_eq!(
             AliveNodes {
                 dirs: vec!["/".into()],
-                files: vec![(foo, "/bar".into())]
+                files: vec![(foo, "/bar".into())],
+                sockets: vec![],
+                specials: vec![],
+                symlinks: vec![],
             },
             fs.alive()
         );
@@ -727,14 +2265,62 @@ mod tests {
                     Operation::RENAME {
                         old_path: "/foo".into(),
                         new_path: "/bar".into(),
+                        flags: vec![],
                     }
-                ]
+                ],
+                seed: 0,
             },
             fs.recording
         );
         test_replay(fs.recording);
     }
 
+    #[test]
+    fn test_rename_to_bad_parent() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        let bar = fs.create("/bar".into(), vec![]).unwrap();
+        let alive = fs.alive();
+
+        let err = fs.rename("/foo".into(), "/bar/baz".into()).unwrap_err();
+        assert_eq!(FsError::NotADir("/bar".into()), err);
+        let err = fs.rename("/foo".into(), "/boo/baz".into()).unwrap_err();
+        assert_eq!(FsError::NotFound("/boo".into()), err);
+
+        let expected = fs
+            .rename_to_bad_parent("/foo".into(), "/bar/baz".into(), vec![])
+            .unwrap();
+        assert_eq!(FsError::NotADir("/bar".into()), expected);
+        let expected = fs
+            .rename_to_bad_parent("/foo".into(), "/boo/baz".into(), vec![])
+            .unwrap();
+        assert_eq!(FsError::NotFound("/boo".into()), expected);
+
+        assert_eq!(
+            Err(FsError::InvalidPath("/baz".into())),
+            fs.rename_to_bad_parent("/foo".into(), "/baz".into(), vec![])
+        );
+        assert_eq!(
+            Err(FsError::NotFound("/boo".into())),
+            fs.rename_to_bad_parent("/boo".into(), "/bar/baz".into(), vec![])
+        );
+
+        assert_eq!(vec![2, 3], fs.expected_failures);
+        assert_eq!(alive, fs.alive());
+        assert_eq!(
+            AliveNodes {
+                dirs: vec!["/".into(), "/foo".into()],
+                files: vec![(bar, "/bar".into())],
+                sockets: vec![],
+                specials: vec![],
+                symlinks: vec![],
+            },
+            fs.alive()
+        );
+        assert_eq!(4, fs.recording.ops.len());
+        test_replay(fs.recording);
+    }
+
     #[test]
     fn test_rename_dir() {
         let mut fs = AbstractFS::new();
@@ -743,7 +2329,10 @@ mod tests {
         assert_eq!(
             AliveNodes {
                 dirs: vec!["/".into(), "/bar".into()],
-                files: vec![]
+                files: vec![],
+                sockets: vec![],
+                specials: vec![],
+                symlinks: vec![],
             },
             fs.alive()
         );
@@ -757,8 +2346,10 @@ mod tests {
                     Operation::RENAME {
                         old_path: "/foo".into(),
                         new_path: "/bar".into(),
+                        flags: vec![],
                     }
-                ]
+                ],
+                seed: 0,
             },
             fs.recording
         );
@@ -779,6 +2370,114 @@ mod tests {
         fs.rename("/foo".into(), "/bar".into()).unwrap();
     }
 
+    #[test]
+    fn test_rename_exchange_file_and_dir() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.create("/foo/baz".into(), vec![]).unwrap();
+        fs.mkdir("/bar".into(), vec![]).unwrap();
+        let file = fs.create("/bar/boo".into(), vec![]).unwrap();
+        let dir = fs.resolve_node("/foo".into()).unwrap();
+        assert_eq!(
+            Ok(dir.clone()),
+            fs.rename_with_flags(
+                "/foo".into(),
+                "/bar/boo".into(),
+                vec![RenameFlag::RENAME_EXCHANGE]
+            )
+        );
+        assert_eq!(Ok(Node::FILE(file)), fs.resolve_node("/foo".into()));
+        assert_eq!(Ok(dir), fs.resolve_node("/bar/boo".into()));
+        assert_eq!(
+            AliveNodes {
+                dirs: vec!["/".into(), "/bar".into(), "/bar/boo".into()],
+                files: vec![(FileIndex(0), "/bar/boo/baz".into()), (file, "/foo".into())],
+                sockets: vec![],
+                specials: vec![],
+                symlinks: vec![],
+            },
+            fs.alive()
+        );
+        assert_eq!(Ok(3), fs.dir_nlink("/bar".into()));
+        assert_eq!(
+            Err(FsError::RenameToSubdirectoryError(
+                "/bar/boo/baz".into(),
+                "/bar".into()
+            )),
+            fs.rename_with_flags(
+                "/bar/boo/baz".into(),
+                "/bar".into(),
+                vec![RenameFlag::RENAME_EXCHANGE]
+            )
+        );
+        assert_eq!(
+            Err(FsError::NotFound("/boo".into())),
+            fs.rename_with_flags(
+                "/foo".into(),
+                "/boo".into(),
+                vec![RenameFlag::RENAME_EXCHANGE]
+            )
+        );
+        assert_eq!(5, fs.recording.ops.len());
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_rename_noreplace() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        fs.create("/bar".into(), vec![]).unwrap();
+        assert_eq!(
+            Err(FsError::NameAlreadyExists("/bar".into())),
+            fs.rename_with_flags(
+                "/foo".into(),
+                "/bar".into(),
+                vec![RenameFlag::RENAME_NOREPLACE]
+            )
+        );
+        assert_eq!(
+            Err(FsError::ExchangeWithNoReplace),
+            fs.rename_with_flags(
+                "/foo".into(),
+                "/bar".into(),
+                vec![RenameFlag::RENAME_EXCHANGE, RenameFlag::RENAME_NOREPLACE]
+            )
+        );
+        assert_eq!(2, fs.recording.ops.len());
+        assert_eq!(
+            Ok(FsError::NameAlreadyExists("/bar".into())),
+            fs.rename_noreplace_existing("/foo".into(), "/bar".into())
+        );
+        assert_eq!(
+            Err(FsError::NotFound("/boo".into())),
+            fs.rename_noreplace_existing("/foo".into(), "/boo".into())
+        );
+        assert_eq!(vec![2], fs.expected_failures);
+        fs.rename_with_flags(
+            "/foo".into(),
+            "/baz".into(),
+            vec![RenameFlag::RENAME_NOREPLACE],
+        )
+        .unwrap();
+        let files: Vec<PathName> = fs.alive().files.into_iter().map(|(_, p)| p).collect();
+        assert_eq!(vec![PathName::from("/baz"), "/bar".into()], files);
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_rename_hardlinks_of_same_file() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        fs.mkdir("/dir".into(), vec![]).unwrap();
+        fs.hardlink("/foo".into(), "/dir/bar".into()).unwrap();
+        let alive = fs.alive();
+        fs.rename("/foo".into(), "/dir/bar".into()).unwrap();
+        assert_eq!(alive, fs.alive());
+        assert_eq!(2, fs.file(&foo).unwrap().nlink);
+        assert_eq!(4, fs.recording.ops.len());
+        test_replay(fs.recording);
+    }
+
     #[test]
     fn test_rename_old_prefix() {
         let mut fs = AbstractFS::new();
@@ -796,12 +2495,12 @@ mod tests {
     fn test_open_close_file() {
         let mut fs = AbstractFS::new();
         let foo = fs.create("/foo".into(), vec![]).unwrap();
-        let des = fs.open("/foo".into()).unwrap();
-        let file = fs.file(&foo);
-        assert_eq!(Some(des), file.descriptor);
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        let file = fs.file(&foo).unwrap();
+        assert_eq!(vec![des], file.descriptors);
         fs.close(des).unwrap();
-        let file = fs.file(&foo);
-        assert_eq!(None, file.descriptor);
+        let file = fs.file(&foo).unwrap();
+        assert!(file.descriptors.is_empty());
         assert_eq!(
             Workload {
                 ops: vec![
@@ -811,10 +2510,12 @@ mod tests {
                     },
                     Operation::OPEN {
                         path: "/foo".into(),
+                        flags: vec![],
                         des
                     },
                     Operation::CLOSE { des }
-                ]
+                ],
+                seed: 0,
             },
             fs.recording
         );
@@ -832,7 +2533,7 @@ mod tests {
     fn test_close_twice() {
         let mut fs = AbstractFS::new();
         fs.create("/foo".into(), vec![]).unwrap();
-        let des = fs.open("/foo".into()).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
         fs.close(des).unwrap();
         assert_eq!(Err(FsError::DescriptorWasClosed(des)), fs.close(des));
     }
@@ -840,12 +2541,43 @@ mod tests {
     #[test]
     fn test_open_twice() {
         let mut fs = AbstractFS::new();
-        fs.create("/foo".into(), vec![]).unwrap();
-        fs.open("/foo".into()).unwrap();
-        assert_eq!(
-            Err(FsError::FileAlreadyOpened("/foo".into())),
-            fs.open("/foo".into())
-        );
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let fst = fs.open("/foo".into(), vec![]).unwrap();
+        let snd = fs.open("/foo".into(), vec![]).unwrap();
+        assert_ne!(fst, snd);
+        assert_eq!(vec![fst, snd], fs.file(&foo).unwrap().descriptors);
+        fs.close(fst).unwrap();
+        assert_eq!(Err(FsError::DescriptorWasClosed(fst)), fs.read(fst, 1));
+        assert_eq!(vec![snd], fs.file(&foo).unwrap().descriptors);
+        fs.write(snd, 0, 10).unwrap();
+        fs.close(snd).unwrap();
+        assert!(fs.file(&foo).unwrap().descriptors.is_empty());
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_write_with_two_descriptors() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let fst = fs.open("/foo".into(), vec![]).unwrap();
+        let snd = fs.open("/foo".into(), vec![]).unwrap();
+        fs.write(fst, 0, 100).unwrap();
+        // offset of second descriptor is not moved by first one
+        fs.lseek(snd, 50, Whence::SEEK_SET).unwrap();
+        fs.write(snd, 1000, 100).unwrap();
+        assert_eq!(100, fs.descriptor(&fst).unwrap().offset);
+        assert_eq!(150, fs.descriptor(&snd).unwrap().offset);
+        // first descriptor reads what second one wrote
+        let expected = fs.file(&foo).unwrap().content.read(100, 50).unwrap();
+        assert_eq!(expected, fs.read(fst, 100).unwrap());
+        assert_eq!(150, fs.descriptor(&fst).unwrap().offset);
+
+        let appender = fs.open("/foo".into(), vec![OpenFlag::O_APPEND]).unwrap();
+        fs.write(appender, 0, 10).unwrap();
+        fs.write(fst, 0, 10).unwrap();
+        assert_eq!(160, fs.file(&foo).unwrap().content.size());
+        assert_eq!(160, fs.descriptor(&appender).unwrap().offset);
+        test_replay(fs.recording);
     }
 
     #[test]
@@ -859,7 +2591,7 @@ mod tests {
     fn test_read_closed() {
         let mut fs = AbstractFS::new();
         fs.create("/foo".into(), vec![]).unwrap();
-        let des = fs.open("/foo".into()).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
         fs.close(des).unwrap();
         assert_eq!(Err(FsError::DescriptorWasClosed(des)), fs.read(des, 0));
     }
@@ -868,7 +2600,7 @@ mod tests {
     fn test_read_empty() {
         let mut fs = AbstractFS::new();
         fs.create("/foo".into(), vec![]).unwrap();
-        let des = fs.open("/foo".into()).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
         let content = fs.read(des, 1024).unwrap();
         fs.close(des).unwrap();
 
@@ -882,11 +2614,13 @@ mod tests {
                     },
                     Operation::OPEN {
                         path: "/foo".into(),
+                        flags: vec![],
                         des
                     },
                     Operation::READ { des, size: 1024 },
                     Operation::CLOSE { des },
-                ]
+                ],
+                seed: 0,
             },
             fs.recording
         );
@@ -904,7 +2638,7 @@ mod tests {
     fn test_write_closed() {
         let mut fs = AbstractFS::new();
         fs.create("/foo".into(), vec![]).unwrap();
-        let des = fs.open("/foo".into()).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
         fs.close(des).unwrap();
         assert_eq!(Err(FsError::DescriptorWasClosed(des)), fs.write(des, 0, 0));
     }
@@ -913,16 +2647,17 @@ mod tests {
     fn test_write() {
         let mut fs = AbstractFS::new();
         let foo = fs.create("/foo".into(), vec![]).unwrap();
-        let des = fs.open("/foo".into()).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
         fs.write(des, 999, 1024).unwrap();
         fs.close(des).unwrap();
 
         assert_eq!(
             vec![SourceSlice {
                 from: 999,
-                to: 999 + 1024 - 1
+                to: 999 + 1024 - 1,
+                hole: false,
             }],
-            fs.file(&foo).content.slices()
+            fs.file(&foo).unwrap().content.slices()
         );
 
         assert_eq!(
@@ -934,6 +2669,7 @@ mod tests {
                     },
                     Operation::OPEN {
                         path: "/foo".into(),
+                        flags: vec![],
                         des
                     },
                     Operation::WRITE {
@@ -942,7 +2678,8 @@ mod tests {
                         size: 1024
                     },
                     Operation::CLOSE { des },
-                ]
+                ],
+                seed: 0,
             },
             fs.recording
         );
@@ -953,10 +2690,10 @@ mod tests {
     fn test_write_rewrite() {
         let mut fs = AbstractFS::new();
         let foo = fs.create("/foo".into(), vec![]).unwrap();
-        let des_1 = fs.open("/foo".into()).unwrap();
+        let des_1 = fs.open("/foo".into(), vec![]).unwrap();
         fs.write(des_1, 13, 100).unwrap();
         fs.close(des_1).unwrap();
-        let des_2 = fs.open("/foo".into()).unwrap();
+        let des_2 = fs.open("/foo".into(), vec![]).unwrap();
         fs.write(des_2, 42, 55).unwrap();
         fs.close(des_2).unwrap();
 
@@ -964,14 +2701,16 @@ mod tests {
             vec![
                 SourceSlice {
                     from: 42,
-                    to: 42 + 55 - 1
+                    to: 42 + 55 - 1,
+                    hole: false,
                 },
                 SourceSlice {
                     from: 13 + 55,
-                    to: 13 + 100 - 1
+                    to: 13 + 100 - 1,
+                    hole: false,
                 }
             ],
-            fs.file(&foo).content.slices()
+            fs.file(&foo).unwrap().content.slices()
         );
 
         assert_eq!(
@@ -983,6 +2722,7 @@ mod tests {
                     },
                     Operation::OPEN {
                         path: "/foo".into(),
+                        flags: vec![],
                         des: des_1
                     },
                     Operation::WRITE {
@@ -993,6 +2733,7 @@ mod tests {
                     Operation::CLOSE { des: des_1 },
                     Operation::OPEN {
                         path: "/foo".into(),
+                        flags: vec![],
                         des: des_2
                     },
                     Operation::WRITE {
@@ -1001,22 +2742,307 @@ mod tests {
                         size: 55
                     },
                     Operation::CLOSE { des: des_2 },
-                ]
+                ],
+                seed: 0,
             },
             fs.recording
         );
         test_replay(fs.recording);
     }
 
+    #[test]
+    fn test_write_append_interleaved() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let des_1 = fs.open("/foo".into(), vec![]).unwrap();
+        let des_2 = fs.open("/foo".into(), vec![OpenFlag::O_APPEND]).unwrap();
+        fs.write(des_1, 13, 100).unwrap();
+        fs.write(des_2, 42, 55).unwrap();
+        // plain descriptor keeps its own offset, append one always writes at the end
+        fs.write(des_1, 7, 10).unwrap();
+        fs.write(des_2, 0, 5).unwrap();
+        fs.close(des_1).unwrap();
+        fs.close(des_2).unwrap();
+
+        assert_eq!(
+            vec![
+                SourceSlice {
+                    from: 13,
+                    to: 13 + 100 - 1,
+                    hole: false,
+                },
+                SourceSlice {
+                    from: 7,
+                    to: 7 + 10 - 1,
+                    hole: false,
+                },
+                SourceSlice {
+                    from: 42 + 10,
+                    to: 42 + 55 - 1,
+                    hole: false,
+                },
+                SourceSlice {
+                    from: 0,
+                    to: 5 - 1,
+                    hole: false,
+                },
+            ],
+            fs.file(&foo).unwrap().content.slices()
+        );
+        assert_eq!(
+            Operation::OPEN {
+                path: "/foo".into(),
+                flags: vec![OpenFlag::O_APPEND],
+                des: des_2
+            },
+            fs.recording.ops[2]
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_open_create() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        let des = fs.open("/foo/bar".into(), vec![OpenFlag::O_CREAT]).unwrap();
+        fs.write(des, 0, 100).unwrap();
+        fs.close(des).unwrap();
+        assert_eq!(
+            vec![PathName::from("/"), "/foo".into(), "/foo/bar".into()],
+            fs.alive_paths()
+        );
+        // existing file is opened as is
+        let des = fs.open("/foo/bar".into(), vec![OpenFlag::O_CREAT]).unwrap();
+        assert_eq!(
+            100,
+            fs.file(&fs.descriptors[des.0].file).unwrap().content.size()
+        );
+        fs.close(des).unwrap();
+        assert_eq!(
+            Err(FsError::NotFound("/baz".into())),
+            fs.open("/baz/bar".into(), vec![OpenFlag::O_CREAT])
+        );
+        assert_eq!(
+            Err(FsError::NotAFile("/foo".into())),
+            fs.open("/foo".into(), vec![OpenFlag::O_CREAT])
+        );
+        assert_eq!(6, fs.recording.ops.len());
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_open_exclusive() {
+        let mut fs = AbstractFS::new();
+        let flags = vec![OpenFlag::O_CREAT, OpenFlag::O_EXCL];
+        let des = fs.open("/foo".into(), flags.clone()).unwrap();
+        fs.close(des).unwrap();
+        assert_eq!(
+            Err(FsError::NameAlreadyExists("/foo".into())),
+            fs.open("/foo".into(), flags.clone())
+        );
+        // without O_CREAT it has no effect
+        let des = fs.open("/foo".into(), vec![OpenFlag::O_EXCL]).unwrap();
+        fs.close(des).unwrap();
+        assert_eq!(4, fs.recording.ops.len());
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_open_unsupported_flags() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        for flags in [
+            vec![OpenFlag::O_TRUNC],
+            vec![OpenFlag::O_CREAT, OpenFlag::O_DIRECTORY],
+        ] {
+            assert_eq!(
+                Err(FsError::BadOpenFlags(flags.clone())),
+                fs.open("/foo".into(), flags.clone())
+            );
+            assert_eq!(
+                Err(FsError::BadOpenFlags(flags.clone())),
+                fs.open_failing("/foo".into(), flags)
+            );
+        }
+        assert_eq!(1, fs.recording.ops.len());
+        assert!(fs.descriptors.is_empty());
+    }
+
+    #[test]
+    fn test_open_exclusive_over_other_kinds() {
+        // any existing node fails with EEXIST, symlink is not followed even if it is dangling
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/dir".into(), vec![]).unwrap();
+        fs.mksocket("/socket".into()).unwrap();
+        fs.mknod("/fifo".into(), SpecialKind::S_IFIFO, vec![])
+            .unwrap();
+        fs.symlink("missing".into(), "/link".into()).unwrap();
+        let flags = vec![OpenFlag::O_CREAT, OpenFlag::O_EXCL];
+        for path in ["/dir", "/socket", "/fifo", "/link"] {
+            assert_eq!(
+                Err(FsError::NameAlreadyExists(path.into())),
+                fs.open(path.into(), flags.clone())
+            );
+        }
+        // without O_EXCL symlink would be followed
+        for path in ["/dir", "/socket", "/fifo"] {
+            assert_eq!(
+                Err(FsError::NotAFile(path.into())),
+                fs.open(path.into(), vec![OpenFlag::O_CREAT])
+            );
+        }
+        assert_eq!(4, fs.recording.ops.len());
+        let mut flags_nofollow = flags.clone();
+        flags_nofollow.push(OpenFlag::O_NOFOLLOW);
+        for (path, flags) in [
+            ("/dir", flags.clone()),
+            ("/socket", flags.clone()),
+            ("/fifo", flags.clone()),
+            ("/link", flags.clone()),
+            ("/link", flags_nofollow),
+        ] {
+            assert_eq!(
+                Ok(FsError::NameAlreadyExists(path.into())),
+                fs.open_failing(path.into(), flags)
+            );
+        }
+        assert_eq!(
+            Err(FsError::InvalidPath("/".into())),
+            fs.open_failing("/".into(), flags.clone())
+        );
+        assert_eq!(
+            Err(FsError::NotFound("/missing".into())),
+            fs.open_failing("/missing".into(), flags.clone())
+        );
+        assert_eq!(vec![4, 5, 6, 7, 8], fs.expected_failures);
+        assert!(fs.descriptors.is_empty());
+        let des = fs.open("/file".into(), flags.clone()).unwrap();
+        assert_eq!(FileDescriptorIndex(0), des);
+        fs.close(des).unwrap();
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_open_nofollow() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        fs.symlink("foo".into(), "/bar".into()).unwrap();
+        let flags = vec![OpenFlag::O_NOFOLLOW];
+        // without flag symlink resolves to its target
+        let des = fs.open("/bar".into(), vec![]).unwrap();
+        assert_eq!(foo, fs.descriptor(&des).unwrap().file);
+        fs.close(des).unwrap();
+        assert_eq!(
+            Err(FsError::NotAFile("/bar".into())),
+            fs.open("/bar".into(), flags.clone())
+        );
+        assert_eq!(
+            Ok(FsError::SymlinkNotFollowed("/bar".into())),
+            fs.open_failing("/bar".into(), flags.clone())
+        );
+        assert_eq!(
+            Err(FsError::SymlinkFollowed("/bar".into())),
+            fs.open_failing("/bar".into(), vec![])
+        );
+        assert_eq!(
+            Err(FsError::NotASymlink("/foo".into())),
+            fs.open_failing("/foo".into(), flags.clone())
+        );
+        // flag has no effect on regular file, descriptor of failed open is taken again
+        let des = fs.open("/foo".into(), flags.clone()).unwrap();
+        assert_eq!(FileDescriptorIndex(1), des);
+        assert_eq!(
+            Operation::OPEN {
+                path: "/bar".into(),
+                flags: flags.clone(),
+                des,
+            },
+            fs.recording.ops[4]
+        );
+        assert_eq!(vec![4], fs.expected_failures);
+        assert_eq!(2, fs.descriptors.len());
+        fs.close(des).unwrap();
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_open_follow_symlink() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        let file = fs.create("/foo/file".into(), vec![]).unwrap();
+        fs.symlink("file".into(), "/foo/link".into()).unwrap();
+        fs.symlink("../foo//link".into(), "/foo/chain".into())
+            .unwrap();
+        fs.symlink("foo/./file".into(), "/root".into()).unwrap();
+        for path in ["/foo/link", "/foo/chain", "/root"] {
+            let des = fs.open(path.into(), vec![]).unwrap();
+            assert_eq!(file, fs.descriptor(&des).unwrap().file);
+            fs.close(des).unwrap();
+        }
+        // dangling symlink gets its target created
+        fs.symlink("missing".into(), "/foo/dangling".into())
+            .unwrap();
+        assert_eq!(
+            Err(FsError::NotFound("/foo/missing".into())),
+            fs.open("/foo/dangling".into(), vec![])
+        );
+        let des = fs
+            .open("/foo/dangling".into(), vec![OpenFlag::O_CREAT])
+            .unwrap();
+        fs.close(des).unwrap();
+        assert!(fs.resolve_file("/foo/missing".into()).is_ok());
+        test_replay(fs.recording.clone());
+
+        fs.symlink("/foo/file".into(), "/absolute".into()).unwrap();
+        fs.symlink("../foo".into(), "/outside".into()).unwrap();
+        fs.symlink("file/".into(), "/foo/slash".into()).unwrap();
+        fs.symlink("link/../file".into(), "/foo/through".into())
+            .unwrap();
+        fs.symlink("loop".into(), "/loop".into()).unwrap();
+        assert_eq!(
+            Err(FsError::SymlinkOutside("/absolute".into())),
+            fs.open("/absolute".into(), vec![])
+        );
+        assert_eq!(
+            Err(FsError::SymlinkOutside("/outside".into())),
+            fs.open("/outside".into(), vec![])
+        );
+        assert_eq!(
+            Err(FsError::NotADir("/foo/file".into())),
+            fs.open("/foo/slash".into(), vec![])
+        );
+        assert_eq!(
+            Err(FsError::NotADir("/foo/link".into())),
+            fs.open("/foo/through".into(), vec![])
+        );
+        assert_eq!(
+            Err(FsError::SymlinkLoop("/loop".into())),
+            fs.open("/loop".into(), vec![OpenFlag::O_CREAT])
+        );
+    }
+
+    #[test]
+    fn test_write_append_after_truncate() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs.open("/foo".into(), vec![OpenFlag::O_APPEND]).unwrap();
+        fs.truncate("/foo".into(), 100).unwrap();
+        fs.write(des, 42, 10).unwrap();
+        assert_eq!(110, fs.descriptor(&des).unwrap().offset);
+        assert_eq!(110, fs.file(&foo).unwrap().content.size());
+        fs.close(des).unwrap();
+        test_replay(fs.recording);
+    }
+
     #[test]
     fn test_read() {
         let mut fs = AbstractFS::new();
         fs.create("/foo".into(), vec![]).unwrap();
-        let des_write = fs.open("/foo".into()).unwrap();
+        let des_write = fs.open("/foo".into(), vec![]).unwrap();
         fs.write(des_write, 13, 100).unwrap();
         fs.write(des_write, 42, 55).unwrap();
         fs.close(des_write).unwrap();
-        let des_read = fs.open("/foo".into()).unwrap();
+        let des_read = fs.open("/foo".into(), vec![]).unwrap();
         assert_eq!(
             Vec::<SourceSlice>::new(),
             fs.read(des_read, 0).unwrap().slices()
@@ -1024,14 +3050,16 @@ mod tests {
         assert_eq!(
             vec![SourceSlice {
                 from: 13,
-                to: 13 + 10 - 1
+                to: 13 + 10 - 1,
+                hole: false,
             }],
             fs.read(des_read, 10).unwrap().slices()
         );
         assert_eq!(
             vec![SourceSlice {
                 from: (13 + 10),
-                to: (13 + 10) + 10 - 1
+                to: (13 + 10) + 10 - 1,
+                hole: false,
             }],
             fs.read(des_read, 10).unwrap().slices()
         );
@@ -1039,11 +3067,13 @@ mod tests {
             vec![
                 SourceSlice {
                     from: (13 + 20),
-                    to: 13 + 100 - 1
+                    to: 13 + 100 - 1,
+                    hole: false,
                 },
                 SourceSlice {
                     from: 42,
-                    to: 42 + 55 - 1
+                    to: 42 + 55 - 1,
+                    hole: false,
                 },
             ],
             fs.read(des_read, 1024).unwrap().slices()
@@ -1059,6 +3089,7 @@ mod tests {
                     },
                     Operation::OPEN {
                         path: "/foo".into(),
+                        flags: vec![],
                         des: des_write
                     },
                     Operation::WRITE {
@@ -1074,6 +3105,7 @@ mod tests {
                     Operation::CLOSE { des: des_write },
                     Operation::OPEN {
                         path: "/foo".into(),
+                        flags: vec![],
                         des: des_read
                     },
                     Operation::READ {
@@ -1093,7 +3125,8 @@ mod tests {
                         size: 1024
                     },
                     Operation::CLOSE { des: des_read },
-                ]
+                ],
+                seed: 0,
             },
             fs.recording
         );
@@ -1111,7 +3144,7 @@ mod tests {
     fn test_fsync_closed() {
         let mut fs = AbstractFS::new();
         fs.create("/foo".into(), vec![]).unwrap();
-        let des = fs.open("/foo".into()).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
         fs.close(des).unwrap();
         assert_eq!(Err(FsError::DescriptorWasClosed(des)), fs.fsync(des));
     }
@@ -1120,7 +3153,7 @@ mod tests {
     fn test_fsync() {
         let mut fs = AbstractFS::new();
         fs.create("/foo".into(), vec![]).unwrap();
-        let des = fs.open("/foo".into()).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
         fs.fsync(des).unwrap();
         fs.close(des).unwrap();
 
@@ -1133,11 +3166,13 @@ mod tests {
                     },
                     Operation::OPEN {
                         path: "/foo".into(),
+                        flags: vec![],
                         des
                     },
                     Operation::FSYNC { des },
                     Operation::CLOSE { des },
-                ]
+                ],
+                seed: 0,
             },
             fs.recording
         );
@@ -1145,10 +3180,479 @@ mod tests {
     }
 
     #[test]
-    fn test_resolve_node() {
+    fn test_fdatasync_bad_descriptor() {
         let mut fs = AbstractFS::new();
-        assert_eq!(
-            Node::DIR(AbstractFS::root_index()),
+        let des = FileDescriptorIndex(0);
+        assert_eq!(Err(FsError::BadDescriptor(des, 0)), fs.fdatasync(des));
+    }
+
+    #[test]
+    fn test_fdatasync_closed() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        fs.close(des).unwrap();
+        assert_eq!(Err(FsError::DescriptorWasClosed(des)), fs.fdatasync(des));
+    }
+
+    #[test]
+    fn test_fdatasync() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        fs.fdatasync(des).unwrap();
+        fs.close(des).unwrap();
+
+        assert_eq!(
+            Workload {
+                ops: vec![
+                    Operation::CREATE {
+                        path: "/foo".into(),
+                        mode: vec![]
+                    },
+                    Operation::OPEN {
+                        path: "/foo".into(),
+                        flags: vec![],
+                        des
+                    },
+                    Operation::FDATASYNC { des },
+                    Operation::CLOSE { des },
+                ],
+                seed: 0,
+            },
+            fs.recording
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_sync_file_range_bad_descriptor() {
+        let mut fs = AbstractFS::new();
+        let des = FileDescriptorIndex(0);
+        assert_eq!(
+            Err(FsError::BadDescriptor(des, 0)),
+            fs.sync_file_range(des, 0, 0, vec![])
+        );
+    }
+
+    #[test]
+    fn test_sync_file_range_closed() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        fs.close(des).unwrap();
+        assert_eq!(
+            Err(FsError::DescriptorWasClosed(des)),
+            fs.sync_file_range(des, 0, 4096, vec![SyncFileRangeFlag::SYNC_FILE_RANGE_WRITE])
+        );
+    }
+
+    #[test]
+    fn test_sync_file_range() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        let flags = vec![
+            SyncFileRangeFlag::SYNC_FILE_RANGE_WAIT_BEFORE,
+            SyncFileRangeFlag::SYNC_FILE_RANGE_WRITE,
+            SyncFileRangeFlag::SYNC_FILE_RANGE_WAIT_AFTER,
+        ];
+        fs.sync_file_range(des, 4096, 0, flags.clone()).unwrap();
+        fs.close(des).unwrap();
+
+        assert_eq!(
+            Workload {
+                ops: vec![
+                    Operation::CREATE {
+                        path: "/foo".into(),
+                        mode: vec![]
+                    },
+                    Operation::OPEN {
+                        path: "/foo".into(),
+                        flags: vec![],
+                        des
+                    },
+                    Operation::SYNCFILERANGE {
+                        des,
+                        offset: 4096,
+                        nbytes: 0,
+                        flags,
+                    },
+                    Operation::CLOSE { des },
+                ],
+                seed: 0,
+            },
+            fs.recording
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_fallocate_closed() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        fs.close(des).unwrap();
+        assert_eq!(
+            Err(FsError::DescriptorWasClosed(des)),
+            fs.fallocate(des, 0, 4096, vec![])
+        );
+    }
+
+    #[test]
+    fn test_fallocate_bad_mode() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        assert_eq!(
+            Err(FsError::EmptyFallocateRange(des)),
+            fs.fallocate(des, 0, 0, vec![])
+        );
+        let mode = vec![FallocateFlag::FALLOC_FL_PUNCH_HOLE];
+        assert_eq!(
+            Err(FsError::BadFallocateMode(mode.clone())),
+            fs.fallocate(des, 0, 4096, mode)
+        );
+        let mode = vec![
+            FallocateFlag::FALLOC_FL_PUNCH_HOLE,
+            FallocateFlag::FALLOC_FL_KEEP_SIZE,
+            FallocateFlag::FALLOC_FL_ZERO_RANGE,
+        ];
+        assert_eq!(
+            Err(FsError::BadFallocateMode(mode.clone())),
+            fs.fallocate(des, 0, 4096, mode)
+        );
+    }
+
+    #[test]
+    fn test_fallocate() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        fs.write(des, 42, 100).unwrap();
+        fs.fallocate(des, 0, 200, vec![FallocateFlag::FALLOC_FL_KEEP_SIZE])
+            .unwrap();
+        assert_eq!(100, fs.file(&foo).unwrap().content.size());
+        fs.fallocate(
+            des,
+            30,
+            20,
+            vec![
+                FallocateFlag::FALLOC_FL_PUNCH_HOLE,
+                FallocateFlag::FALLOC_FL_KEEP_SIZE,
+            ],
+        )
+        .unwrap();
+        fs.fallocate(des, 90, 20, vec![FallocateFlag::FALLOC_FL_ZERO_RANGE])
+            .unwrap();
+        fs.fallocate(des, 150, 50, vec![]).unwrap();
+        let mut expected = Content::new();
+        expected.write_back(42, 30);
+        expected.truncate(50);
+        expected.write_back(42 + 50, 40);
+        expected.truncate(200);
+        assert_eq!(expected.extents(), fs.file(&foo).unwrap().content.extents());
+        assert_eq!(200, fs.file(&foo).unwrap().content.size());
+        assert_eq!(100, fs.descriptor(&des).unwrap().offset);
+        fs.close(des).unwrap();
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_lseek_bad_descriptor() {
+        let mut fs = AbstractFS::new();
+        let des = FileDescriptorIndex(0);
+        assert_eq!(
+            Err(FsError::BadDescriptor(des, 0)),
+            fs.lseek(des, 0, Whence::SEEK_SET)
+        );
+    }
+
+    #[test]
+    fn test_lseek_closed() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        fs.close(des).unwrap();
+        assert_eq!(
+            Err(FsError::DescriptorWasClosed(des)),
+            fs.lseek(des, 0, Whence::SEEK_SET)
+        );
+    }
+
+    #[test]
+    fn test_lseek() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        fs.write(des, 0, 100).unwrap();
+        assert_eq!(Ok(10), fs.lseek(des, 10, Whence::SEEK_SET));
+        assert_eq!(Ok(15), fs.lseek(des, 5, Whence::SEEK_CUR));
+        assert_eq!(Ok(5), fs.lseek(des, -10, Whence::SEEK_CUR));
+        assert_eq!(Ok(90), fs.lseek(des, -10, Whence::SEEK_END));
+        assert_eq!(10, fs.read(des, 42).unwrap().size());
+        assert_eq!(
+            Err(FsError::NegativeOffset(des, -101)),
+            fs.lseek(des, -101, Whence::SEEK_END)
+        );
+        assert_eq!(100, fs.descriptor(&des).unwrap().offset);
+        fs.close(des).unwrap();
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_lseek_past_end_and_write() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        fs.write(des, 13, 10).unwrap();
+        assert_eq!(Ok(1000), fs.lseek(des, 990, Whence::SEEK_END));
+        assert_eq!(Content::new(), fs.read(des, 100).unwrap());
+        assert_eq!(10, fs.file(&foo).unwrap().content.size());
+        fs.write(des, 42, 24).unwrap();
+        assert_eq!(1024, fs.descriptor(&des).unwrap().offset);
+        assert_eq!(
+            vec![
+                SourceSlice {
+                    from: 13,
+                    to: 13 + 10 - 1,
+                    hole: false,
+                },
+                SourceSlice {
+                    from: 0,
+                    to: 1000 - 10 - 1,
+                    hole: true,
+                },
+                SourceSlice {
+                    from: 42,
+                    to: 42 + 24 - 1,
+                    hole: false,
+                },
+            ],
+            fs.file(&foo).unwrap().content.slices()
+        );
+        fs.close(des).unwrap();
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_prealloc() {
+        let mut fs = AbstractFS::new();
+        let file = fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        fs.write(des, 42, 100).unwrap();
+        assert_eq!(
+            Err(FsError::FileAlreadyOpened("/foo".into())),
+            fs.prealloc("/foo".into(), 10)
+        );
+        fs.close(des).unwrap();
+        fs.prealloc("/foo".into(), 1048577).unwrap();
+        assert_eq!(Content::pattern(1048577), fs.file(&file).unwrap().content);
+        assert_eq!(1048577, fs.file(&file).unwrap().content.size());
+
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        let mut expected = Content::new();
+        expected.write_back(1048575, 1);
+        expected.write_back(0, 1);
+        fs.read(des, 1048575).unwrap();
+        assert_eq!(expected, fs.read(des, 10).unwrap());
+        fs.close(des).unwrap();
+
+        fs.prealloc("/foo".into(), 0).unwrap();
+        assert_eq!(Content::new(), fs.file(&file).unwrap().content);
+        assert_eq!(
+            Err(FsError::NotAFile("/".into())),
+            fs.prealloc("/".into(), 10)
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_truncate_grow() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        fs.write(des, 999, 100).unwrap();
+        fs.truncate("/foo".into(), 4096).unwrap();
+        assert_eq!(
+            vec![
+                SourceSlice {
+                    from: 999,
+                    to: 999 + 100 - 1,
+                    hole: false,
+                },
+                SourceSlice {
+                    from: 0,
+                    to: 4096 - 100 - 1,
+                    hole: true,
+                },
+            ],
+            fs.file(&foo).unwrap().content.slices()
+        );
+        assert_eq!(
+            vec![SourceSlice {
+                from: 0,
+                to: 9,
+                hole: true,
+            }],
+            fs.read(des, 10).unwrap().slices()
+        );
+        fs.close(des).unwrap();
+        assert_eq!(
+            Err(FsError::NotAFile("/".into())),
+            fs.truncate("/".into(), 10)
+        );
+
+        assert_eq!(
+            Workload {
+                ops: vec![
+                    Operation::CREATE {
+                        path: "/foo".into(),
+                        mode: vec![]
+                    },
+                    Operation::OPEN {
+                        path: "/foo".into(),
+                        flags: vec![],
+                        des
+                    },
+                    Operation::WRITE {
+                        des,
+                        src_offset: 999,
+                        size: 100
+                    },
+                    Operation::TRUNCATE {
+                        path: "/foo".into(),
+                        size: 4096
+                    },
+                    Operation::READ { des, size: 10 },
+                    Operation::CLOSE { des },
+                ],
+                seed: 0,
+            },
+            fs.recording
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_truncate_shrink() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        fs.write(des, 13, 100).unwrap();
+        fs.truncate("/foo".into(), 10).unwrap();
+        assert_eq!(100, fs.descriptor(&des).unwrap().offset);
+        assert_eq!(Content::new(), fs.read(des, 10).unwrap());
+        fs.write(des, 42, 5).unwrap();
+        fs.close(des).unwrap();
+        assert_eq!(
+            vec![
+                SourceSlice {
+                    from: 13,
+                    to: 13 + 10 - 1,
+                    hole: false,
+                },
+                SourceSlice {
+                    from: 0,
+                    to: 100 - 10 - 1,
+                    hole: true,
+                },
+                SourceSlice {
+                    from: 42,
+                    to: 42 + 5 - 1,
+                    hole: false,
+                },
+            ],
+            fs.file(&foo).unwrap().content.slices()
+        );
+        fs.truncate("/foo".into(), 0).unwrap();
+        assert_eq!(Content::new(), fs.file(&foo).unwrap().content);
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_xattr() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.create("/foo/bar".into(), vec![]).unwrap();
+        fs.setxattr("/foo/bar".into(), "user.0".into(), 100)
+            .unwrap();
+        fs.setxattr("/foo/bar".into(), "user.1".into(), 0).unwrap();
+        fs.setxattr("/foo".into(), "user.0".into(), 4096).unwrap();
+        fs.setxattr("/foo/bar".into(), "user.0".into(), 65536)
+            .unwrap();
+        fs.hardlink("/foo/bar".into(), "/baz".into()).unwrap();
+        assert_eq!(
+            &Xattrs::from([("user.0".to_owned(), 65536), ("user.1".to_owned(), 0)]),
+            fs.xattrs("/baz".into()).unwrap()
+        );
+        fs.removexattr("/baz".into(), "user.1".into()).unwrap();
+        assert_eq!(
+            Err(FsError::XattrNotFound("/foo/bar".into(), "user.1".into())),
+            fs.removexattr("/foo/bar".into(), "user.1".into())
+        );
+        fs.mksocket("/sock".into()).unwrap();
+        assert_eq!(
+            Err(FsError::XattrNotSupported("/sock".into())),
+            fs.setxattr("/sock".into(), "user.0".into(), 1)
+        );
+        assert_eq!(
+            &Xattrs::from([("user.0".to_owned(), 65536)]),
+            fs.xattrs("/foo/bar".into()).unwrap()
+        );
+        assert_eq!(
+            &Xattrs::from([("user.0".to_owned(), 4096)]),
+            fs.xattrs("/foo".into()).unwrap()
+        );
+
+        let mut replayed = AbstractFS::new();
+        replayed.replay(&fs.recording).unwrap();
+        assert_eq!(fs.recording, replayed.recording);
+        assert_eq!(
+            fs.xattrs("/foo/bar".into()),
+            replayed.xattrs("/foo/bar".into())
+        );
+        assert_eq!(fs.xattrs("/foo".into()), replayed.xattrs("/foo".into()));
+        fs.compact();
+        assert_eq!(fs.xattrs("/baz".into()), replayed.xattrs("/baz".into()));
+    }
+
+    #[test]
+    fn test_chmod() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.mkdir("/foo".into(), vec![ModeFlag::S_IRWXU]).unwrap();
+        let bar = fs
+            .create("/foo/bar".into(), vec![ModeFlag::S_IRUSR])
+            .unwrap();
+        fs.chmod(
+            "/foo/bar".into(),
+            vec![ModeFlag::S_IRWXU, ModeFlag::S_ISUID],
+        )
+        .unwrap();
+        fs.chmod("/foo/bar".into(), vec![ModeFlag::S_IWGRP])
+            .unwrap();
+        fs.chmod("/foo".into(), vec![ModeFlag::S_IRWXO]).unwrap();
+        fs.mksocket("/sock".into()).unwrap();
+        fs.chmod("/sock".into(), vec![]).unwrap();
+        assert_eq!(
+            Err(FsError::NotFound("/baz".into())),
+            fs.chmod("/baz".into(), vec![])
+        );
+        assert_eq!(vec![ModeFlag::S_IWGRP], fs.file(&bar).unwrap().mode);
+        assert_eq!(vec![ModeFlag::S_IRWXO], fs.dir(&foo).unwrap().mode);
+
+        let mut replayed = AbstractFS::new();
+        replayed.replay(&fs.recording).unwrap();
+        assert_eq!(fs.recording, replayed.recording);
+        assert_eq!(vec![ModeFlag::S_IWGRP], replayed.file(&bar).unwrap().mode);
+        assert_eq!(vec![ModeFlag::S_IRWXO], replayed.dir(&foo).unwrap().mode);
+    }
+
+    #[test]
+    fn test_resolve_node() {
+        let mut fs = AbstractFS::new();
+        assert_eq!(
+            Node::DIR(AbstractFS::root_index()),
             fs.resolve_node("/".into()).unwrap()
         );
         let foo = fs.mkdir("/foo".into(), vec![]).unwrap();
@@ -1175,6 +3679,807 @@ mod tests {
         test_replay(fs.recording);
     }
 
+    #[test]
+    fn test_dir_stream() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.create("/foo/bar".into(), vec![]).unwrap();
+        let stream = fs.opendir("/foo".into()).unwrap();
+        fs.readdir(stream, 1).unwrap();
+        fs.create("/foo/baz".into(), vec![]).unwrap();
+        fs.readdir(stream, 1).unwrap();
+        fs.closedir(stream).unwrap();
+        assert_eq!(
+            Workload {
+                ops: vec![
+                    Operation::MKDIR {
+                        path: "/foo".into(),
+                        mode: vec![]
+                    },
+                    Operation::CREATE {
+                        path: "/foo/bar".into(),
+                        mode: vec![]
+                    },
+                    Operation::OPENDIR {
+                        path: "/foo".into(),
+                        stream
+                    },
+                    Operation::READDIR { stream, count: 1 },
+                    Operation::CREATE {
+                        path: "/foo/baz".into(),
+                        mode: vec![]
+                    },
+                    Operation::READDIR { stream, count: 1 },
+                    Operation::CLOSEDIR { stream },
+                ],
+                seed: 0,
+            },
+            fs.recording
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_opendir_file() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        assert_eq!(
+            Err(FsError::NotADir("/foo".into())),
+            fs.opendir("/foo".into())
+        );
+    }
+
+    #[test]
+    fn test_utimens() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.create("/foo/bar".into(), vec![]).unwrap();
+        fs.mksocket("/sock".into()).unwrap();
+        fs.utimens("/foo/bar".into(), -1, 4294967296).unwrap();
+        fs.utimens("/foo".into(), 0, 1).unwrap();
+        fs.utimens("/sock".into(), 0, 0).unwrap();
+        assert_eq!(
+            Err(FsError::NotFound("/baz".into())),
+            fs.utimens("/baz".into(), 0, 0)
+        );
+        let bar_times = Times {
+            atime: Some(-1),
+            mtime: Some(4294967296),
+        };
+        let foo_times = Times {
+            atime: Some(0),
+            mtime: Some(1),
+        };
+        assert_eq!(Some(bar_times), fs.times("/foo/bar".into()).unwrap());
+        assert_eq!(Some(foo_times), fs.times("/foo".into()).unwrap());
+        assert_eq!(None, fs.times("/sock".into()).unwrap());
+
+        let mut replayed = AbstractFS::new();
+        replayed.replay(&fs.recording).unwrap();
+        assert_eq!(fs.recording, replayed.recording);
+        assert_eq!(Some(bar_times), replayed.times("/foo/bar".into()).unwrap());
+
+        // adding names updates modification time of parent only
+        fs.create("/foo/baz".into(), vec![]).unwrap();
+        assert_eq!(
+            Some(Times {
+                atime: Some(0),
+                mtime: None
+            }),
+            fs.times("/foo".into()).unwrap()
+        );
+        assert_eq!(Some(bar_times), fs.times("/foo/bar".into()).unwrap());
+    }
+
+    #[test]
+    fn test_times_updated_by_read_and_write() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.create("/foo/bar".into(), vec![]).unwrap();
+        let des = fs.open("/foo/bar".into(), vec![]).unwrap();
+        fs.utimens("/foo/bar".into(), 1, 2).unwrap();
+        fs.utimens("/foo".into(), 1, 2).unwrap();
+
+        fs.read(des, 0).unwrap();
+        fs.write(des, 0, 0).unwrap();
+        let explicit = Times {
+            atime: Some(1),
+            mtime: Some(2),
+        };
+        assert_eq!(Some(explicit), fs.times("/foo/bar".into()).unwrap());
+
+        fs.read(des, 100).unwrap();
+        assert_eq!(
+            Some(Times {
+                atime: None,
+                mtime: Some(2)
+            }),
+            fs.times("/foo/bar".into()).unwrap()
+        );
+        fs.write(des, 0, 100).unwrap();
+        assert_eq!(Some(Times::default()), fs.times("/foo/bar".into()).unwrap());
+        fs.utimens("/foo/bar".into(), 1, 2).unwrap();
+        fs.truncate("/foo/bar".into(), 0).unwrap();
+        assert_eq!(
+            Some(Times {
+                atime: Some(1),
+                mtime: None
+            }),
+            fs.times("/foo/bar".into()).unwrap()
+        );
+
+        // reading directory updates its access time
+        assert_eq!(Some(explicit), fs.times("/foo".into()).unwrap());
+        fs.listdir("/foo".into()).unwrap();
+        assert_eq!(
+            Some(Times {
+                atime: None,
+                mtime: Some(2)
+            }),
+            fs.times("/foo".into()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_listdir() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.create("/foo/b".into(), vec![]).unwrap();
+        fs.create("/foo/a".into(), vec![]).unwrap();
+        assert_eq!(vec!["a", "b"], fs.listdir("/foo".into()).unwrap());
+        fs.rename("/foo/a".into(), "/c".into()).unwrap();
+        assert_eq!(vec!["b"], fs.listdir("/foo".into()).unwrap());
+        assert_eq!(vec!["c", "foo"], fs.listdir("/".into()).unwrap());
+        assert_eq!(Err(FsError::NotADir("/c".into())), fs.listdir("/c".into()));
+        assert_eq!(
+            Operation::LISTDIR {
+                path: "/foo".into()
+            },
+            fs.recording.ops[3]
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_readdir_bad_stream() {
+        let mut fs = AbstractFS::new();
+        let stream = DirStreamIndex(0);
+        assert_eq!(Err(FsError::BadDirStream(stream, 0)), fs.readdir(stream, 1));
+        assert_eq!(Err(FsError::BadDirStream(stream, 0)), fs.closedir(stream));
+    }
+
+    #[test]
+    fn test_readdir_closed() {
+        let mut fs = AbstractFS::new();
+        let stream = fs.opendir("/".into()).unwrap();
+        fs.closedir(stream).unwrap();
+        assert_eq!(
+            Err(FsError::DirStreamWasClosed(stream)),
+            fs.readdir(stream, 1)
+        );
+        assert_eq!(
+            Err(FsError::DirStreamWasClosed(stream)),
+            fs.closedir(stream)
+        );
+    }
+
+    #[test]
+    fn test_node_count_grows() {
+        let mut fs = AbstractFS::new();
+        for _ in 0..100 {
+            fs.create("/foo".into(), vec![]).unwrap();
+            fs.mkdir("/bar".into(), vec![]).unwrap();
+            fs.remove("/foo".into()).unwrap();
+            fs.remove("/bar".into()).unwrap();
+        }
+        assert_eq!(1 + 200, fs.node_count());
+        assert_eq!(
+            AliveNodes {
+                dirs: vec!["/".into()],
+                files: vec![],
+                sockets: vec![],
+                specials: vec![],
+                symlinks: vec![],
+            },
+            fs.alive()
+        );
+    }
+
+    #[test]
+    fn test_node_limit() {
+        let mut fs = AbstractFS::new();
+        fs.max_nodes = Some(3);
+        fs.create("/foo".into(), vec![]).unwrap();
+        fs.remove("/foo".into()).unwrap();
+        fs.mkdir("/bar".into(), vec![]).unwrap();
+        assert_eq!(
+            Err(FsError::NodeLimitReached(3)),
+            fs.create("/foo".into(), vec![])
+        );
+        assert_eq!(
+            Err(FsError::NodeLimitReached(3)),
+            fs.mkdir("/foo".into(), vec![])
+        );
+        fs.hardlink("/bar".into(), "/baz".into()).unwrap_err();
+        assert_eq!(3, fs.node_count());
+    }
+
+    #[test]
+    fn test_compact() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/0".into(), vec![]).unwrap();
+        fs.create("/1".into(), vec![]).unwrap();
+        fs.mkdir("/0/2".into(), vec![]).unwrap();
+        fs.create("/0/2/3".into(), vec![]).unwrap();
+        fs.hardlink("/0/2/3".into(), "/4".into()).unwrap();
+        let des = fs.open("/1".into(), vec![]).unwrap();
+        fs.write(des, 0, 100).unwrap();
+        fs.remove("/1".into()).unwrap();
+        fs.mkdir("/5".into(), vec![]).unwrap();
+        fs.remove("/5".into()).unwrap();
+        let alive = fs.alive().dirs;
+        assert_eq!(6, fs.node_count());
+
+        fs.compact();
+
+        assert_eq!(5, fs.node_count());
+        assert_eq!(alive, fs.alive().dirs);
+        assert_eq!(
+            fs.resolve_file("/0/2/3".into()),
+            fs.resolve_file("/4".into())
+        );
+        let file = fs.descriptors[des.0].file;
+        assert_eq!(vec![des], fs.file(&file).unwrap().descriptors);
+        assert_eq!(100, fs.file(&file).unwrap().content.size());
+        fs.close(des).unwrap();
+    }
+
+    #[test]
+    fn test_model_trace() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.enable_trace();
+        fs.create("/foo/bar".into(), vec![]).unwrap();
+        let des = fs.open("/foo/bar".into(), vec![]).unwrap();
+        fs.rename("/foo".into(), "/baz".into()).unwrap();
+        fs.remove("/baz".into()).unwrap();
+        assert!(fs.mkdir("/baz/boo".into(), vec![]).is_err());
+        let steps = fs.trace().unwrap();
+        assert_eq!(
+            vec![
+                ModelStep {
+                    op: Operation::CREATE {
+                        path: "/foo/bar".into(),
+                        mode: vec![]
+                    },
+                    added: vec!["/foo/bar".into()],
+                    removed: vec![],
+                },
+                ModelStep {
+                    op: Operation::OPEN {
+                        path: "/foo/bar".into(),
+                        flags: vec![],
+                        des
+                    },
+                    added: vec![],
+                    removed: vec![],
+                },
+                ModelStep {
+                    op: Operation::RENAME {
+                        old_path: "/foo".into(),
+                        new_path: "/baz".into(),
+                        flags: vec![],
+                    },
+                    added: vec!["/baz".into(), "/baz/bar".into()],
+                    removed: vec!["/foo".into(), "/foo/bar".into()],
+                },
+                ModelStep {
+                    op: Operation::REMOVE {
+                        path: "/baz".into()
+                    },
+                    added: vec![],
+                    removed: vec!["/baz".into(), "/baz/bar".into()],
+                },
+            ],
+            steps
+        );
+        assert_eq!(
+            "REMOVE { path: PathName(\"/baz\") } added [] removed [/baz, /baz/bar]",
+            steps[3].to_string()
+        );
+        assert_eq!(
+            &fs.recording.ops[1..],
+            steps
+                .iter()
+                .map(|s| s.op.clone())
+                .collect::<Vec<Operation>>()
+        );
+    }
+
+    #[test]
+    fn test_file_handle_across_rename() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        let file = fs.create("/foo/bar".into(), vec![]).unwrap();
+        let handle = fs.name_to_handle("/foo/bar".into()).unwrap();
+        fs.rename("/foo".into(), "/baz".into()).unwrap();
+        assert_eq!(Some(Node::FILE(file)), fs.open_by_handle(handle).unwrap());
+        fs.hardlink("/baz/bar".into(), "/bar".into()).unwrap();
+        fs.remove("/baz".into()).unwrap();
+        assert_eq!(Some(Node::FILE(file)), fs.open_by_handle(handle).unwrap());
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_file_handle_stale() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        let handle = fs.name_to_handle("/foo".into()).unwrap();
+        fs.remove("/foo".into()).unwrap();
+        assert_eq!(None, fs.open_by_handle(handle).unwrap());
+        let foo = fs.mkdir("/foo".into(), vec![]).unwrap();
+        assert_eq!(None, fs.open_by_handle(handle).unwrap());
+        let new_handle = fs.name_to_handle("/foo".into()).unwrap();
+        assert_eq!(Some(Node::DIR(foo)), fs.open_by_handle(new_handle).unwrap());
+        assert_eq!(vec![3, 5], fs.expected_failures);
+        assert_eq!(
+            Workload {
+                ops: vec![
+                    Operation::MKDIR {
+                        path: "/foo".into(),
+                        mode: vec![]
+                    },
+                    Operation::NAMETOHANDLE {
+                        path: "/foo".into(),
+                        handle
+                    },
+                    Operation::REMOVE {
+                        path: "/foo".into()
+                    },
+                    Operation::OPENBYHANDLE { handle },
+                    Operation::MKDIR {
+                        path: "/foo".into(),
+                        mode: vec![]
+                    },
+                    Operation::OPENBYHANDLE { handle },
+                    Operation::NAMETOHANDLE {
+                        path: "/foo".into(),
+                        handle: new_handle
+                    },
+                    Operation::OPENBYHANDLE { handle: new_handle },
+                ],
+                seed: 0,
+            },
+            fs.recording
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_file_handle_compact() {
+        let mut fs = AbstractFS::new();
+        fs.mksocket("/foo".into()).unwrap();
+        fs.create("/bar".into(), vec![]).unwrap();
+        let socket = fs.name_to_handle("/foo".into()).unwrap();
+        let file = fs.name_to_handle("/bar".into()).unwrap();
+        fs.remove("/bar".into()).unwrap();
+        fs.compact();
+        assert_eq!(
+            Some(fs.resolve_node("/foo".into()).unwrap()),
+            fs.resolve_handle(socket).unwrap()
+        );
+        assert_eq!(None, fs.resolve_handle(file).unwrap());
+        assert_eq!(
+            Err(FsError::BadFileHandle(FileHandleIndex(2), 2)),
+            fs.open_by_handle(FileHandleIndex(2))
+        );
+    }
+
+    #[test]
+    fn test_dir_nlink_subdirectory_move() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.mkdir("/bar".into(), vec![]).unwrap();
+        fs.mkdir("/foo/baz".into(), vec![]).unwrap();
+        fs.create("/foo/file".into(), vec![]).unwrap();
+        assert_eq!(Ok(3), fs.dir_nlink("/foo".into()));
+        assert_eq!(Ok(2), fs.dir_nlink("/bar".into()));
+        assert_eq!(Ok(4), fs.dir_nlink("/".into()));
+
+        fs.rename("/foo/baz".into(), "/bar/baz".into()).unwrap();
+        assert_eq!(Ok(2), fs.dir_nlink("/foo".into()));
+        assert_eq!(Ok(3), fs.dir_nlink("/bar".into()));
+
+        fs.rename("/foo/file".into(), "/bar/file".into()).unwrap();
+        assert_eq!(Ok(2), fs.dir_nlink("/foo".into()));
+        assert_eq!(Ok(3), fs.dir_nlink("/bar".into()));
+
+        fs.rename("/bar".into(), "/foo/bar".into()).unwrap();
+        assert_eq!(Ok(3), fs.dir_nlink("/".into()));
+        assert_eq!(Ok(3), fs.dir_nlink("/foo".into()));
+        assert_eq!(
+            Err(FsError::NotADir("/foo/bar/file".into())),
+            fs.dir_nlink("/foo/bar/file".into())
+        );
+    }
+
+    #[test]
+    fn test_rename_root() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.mkdir("/foo/bar".into(), vec![]).unwrap();
+        assert_eq!(
+            Err(FsError::RenameToSubdirectoryError(
+                "/".into(),
+                "/baz".into()
+            )),
+            fs.rename("/".into(), "/baz".into())
+        );
+        assert_eq!(
+            Err(FsError::DirNotEmpty("/".into())),
+            fs.rename("/foo/bar".into(), "/".into())
+        );
+        fs.rename("/foo/bar".into(), "/bar".into()).unwrap();
+        fs.rename("/foo".into(), "/bar/foo".into()).unwrap();
+        fs.rename("/bar/foo".into(), "/foo".into()).unwrap();
+        let dirs: Vec<PathName> = vec!["/".into(), "/bar".into(), "/foo".into()];
+        assert_eq!(dirs, fs.alive().dirs);
+        assert_eq!(Err(FsError::RootRemovalForbidden), fs.remove("/".into()));
+        assert_eq!(
+            Err(FsError::InvalidPath("/".into())),
+            fs.mkdir("/".into(), vec![]).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn test_replay_bad_descriptor() {
+        let mut fs = AbstractFS::new();
+        let workload = Workload {
+            ops: vec![
+                Operation::CREATE {
+                    path: "/foo".into(),
+                    mode: vec![],
+                },
+                Operation::WRITE {
+                    des: FileDescriptorIndex(42),
+                    src_offset: 0,
+                    size: 1,
+                },
+            ],
+            seed: 0,
+        };
+        assert_eq!(
+            Err(FsError::BadDescriptor(FileDescriptorIndex(42), 0)),
+            fs.replay(&workload)
+        );
+    }
+
+    #[test]
+    fn test_replay_bad_dir_stream() {
+        let mut fs = AbstractFS::new();
+        let workload = Workload {
+            ops: vec![Operation::CLOSEDIR {
+                stream: DirStreamIndex(7),
+            }],
+            seed: 0,
+        };
+        assert_eq!(
+            Err(FsError::BadDirStream(DirStreamIndex(7), 0)),
+            fs.replay(&workload)
+        );
+    }
+
+    #[test]
+    fn test_replay_invalid_path() {
+        for path in ["", "foo", "/foo/", "/"] {
+            let mut fs = AbstractFS::new();
+            let workload = Workload {
+                ops: vec![Operation::MKDIR {
+                    path: path.into(),
+                    mode: vec![],
+                }],
+                seed: 0,
+            };
+            assert_eq!(Err(FsError::InvalidPath(path.into())), fs.replay(&workload));
+        }
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        assert_eq!(
+            Err(FsError::InvalidPath("bar".into())),
+            fs.hardlink("/foo".into(), "bar".into())
+        );
+        assert_eq!(
+            Err(FsError::InvalidPath("bar".into())),
+            fs.rename("/foo".into(), "bar".into())
+        );
+        assert_eq!(
+            Err(FsError::InvalidPath("foo".into())),
+            fs.remove("foo".into())
+        );
+    }
+
+    #[test]
+    fn test_bad_node_index() {
+        let mut fs = AbstractFS::new();
+        fs.descriptors.push(FileDescriptor {
+            file: FileIndex(42),
+            offset: 0,
+            flags: vec![],
+        });
+        let des = FileDescriptorIndex(0);
+        let err = Err(FsError::BadNodeIndex(Node::FILE(FileIndex(42))));
+        assert_eq!(err, fs.read(des, 1).map(|_| ()));
+        assert_eq!(err, fs.write(des, 0, 1));
+        assert_eq!(err, fs.fsync(des));
+        assert_eq!(err, fs.close(des));
+        fs.dirs[0]
+            .children
+            .insert("bar".into(), Node::DIR(DirIndex(42)));
+        assert_eq!(
+            Err(FsError::BadNodeIndex(Node::DIR(DirIndex(42)))),
+            fs.resolve_node("/bar/baz".into())
+        );
+        assert_eq!(
+            Err(FsError::BadNodeIndex(Node::DIR(DirIndex(42)))),
+            fs.mkdir("/bar/baz".into(), vec![]).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn test_mksocket() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        let socket = fs.mksocket("/foo/sock".into()).unwrap();
+        assert_eq!(
+            Node::SOCKET(socket),
+            fs.resolve_node("/foo/sock".into()).unwrap()
+        );
+        assert_eq!(
+            AliveNodes {
+                dirs: vec!["/".into(), "/foo".into()],
+                files: vec![],
+                sockets: vec!["/foo/sock".into()],
+                specials: vec![],
+                symlinks: vec![],
+            },
+            fs.alive()
+        );
+        assert_eq!(
+            Err(FsError::NameAlreadyExists("/foo/sock".into())),
+            fs.mksocket("/foo/sock".into())
+        );
+        assert_eq!(
+            Err(FsError::NotAFile("/foo/sock".into())),
+            fs.open("/foo/sock".into(), vec![])
+        );
+        assert_eq!(
+            Err(FsError::NotAFile("/foo/sock".into())),
+            fs.hardlink("/foo/sock".into(), "/bar".into())
+        );
+        assert_eq!(
+            Err(FsError::NotADir("/foo/sock".into())),
+            fs.create("/foo/sock/bar".into(), vec![])
+        );
+        assert_eq!(
+            Err(FsError::NotADir("/foo/sock".into())),
+            fs.opendir("/foo/sock".into())
+        );
+        fs.rename("/foo/sock".into(), "/sock".into()).unwrap();
+        fs.remove("/foo".into()).unwrap();
+        assert_eq!(vec![PathName::from("/sock")], fs.alive().sockets);
+        assert_eq!(3, fs.node_count());
+        fs.compact();
+        assert_eq!(2, fs.node_count());
+        fs.remove("/sock".into()).unwrap();
+        assert!(fs.alive().sockets.is_empty());
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_symlink() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        let link = fs.symlink("../bar/".into(), "/foo/link".into()).unwrap();
+        assert_eq!(
+            Node::SYMLINK(link),
+            fs.resolve_node("/foo/link".into()).unwrap()
+        );
+        assert_eq!(
+            AliveNodes {
+                dirs: vec!["/".into(), "/foo".into()],
+                files: vec![],
+                sockets: vec![],
+                specials: vec![],
+                symlinks: vec!["/foo/link".into()],
+            },
+            fs.alive()
+        );
+        assert_eq!("../bar/", fs.readlink("/foo/link".into()).unwrap());
+        assert_eq!(
+            Err(FsError::NotASymlink("/foo".into())),
+            fs.readlink("/foo".into())
+        );
+        assert_eq!(
+            Err(FsError::NameAlreadyExists("/foo/link".into())),
+            fs.symlink("bar".into(), "/foo/link".into()).map(|_| ())
+        );
+        assert_eq!(
+            Err(FsError::InvalidSymlinkTarget("a b".into())),
+            fs.symlink("a b".into(), "/baz".into()).map(|_| ())
+        );
+        // target with trailing slash must be a directory
+        assert_eq!(
+            Err(FsError::NotFound("/bar".into())),
+            fs.open("/foo/link".into(), vec![])
+        );
+        assert_eq!(
+            Err(FsError::NotADir("/foo/link".into())),
+            fs.create("/foo/link/bar".into(), vec![])
+        );
+        assert_eq!(
+            Err(FsError::SymlinkFollowed("/foo/link".into())),
+            fs.chmod("/foo/link".into(), vec![])
+        );
+        assert_eq!(
+            Err(FsError::XattrNotSupported("/foo/link".into())),
+            fs.setxattr("/foo/link".into(), "user.0".into(), 0)
+        );
+        fs.rename("/foo/link".into(), "/link".into()).unwrap();
+        fs.remove("/foo".into()).unwrap();
+        assert_eq!("../bar/", fs.readlink("/link".into()).unwrap());
+        assert_eq!(3, fs.node_count());
+        fs.compact();
+        assert_eq!(2, fs.node_count());
+        assert_eq!("../bar/", fs.readlink("/link".into()).unwrap());
+        fs.remove("/link".into()).unwrap();
+        assert!(fs.alive().symlinks.is_empty());
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_mknod() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        let fifo = fs
+            .mknod(
+                "/foo/fifo".into(),
+                SpecialKind::S_IFIFO,
+                vec![ModeFlag::S_IRUSR],
+            )
+            .unwrap();
+        fs.mknod("/dev".into(), SpecialKind::S_IFCHR, vec![])
+            .unwrap();
+        assert_eq!(
+            Node::SPECIAL(fifo),
+            fs.resolve_node("/foo/fifo".into()).unwrap()
+        );
+        assert_eq!(
+            AliveNodes {
+                dirs: vec!["/".into(), "/foo".into()],
+                files: vec![],
+                sockets: vec![],
+                specials: vec!["/dev".into(), "/foo/fifo".into()],
+                symlinks: vec![],
+            },
+            fs.alive()
+        );
+        assert_eq!(1, fs.nlink("/foo/fifo".into()).unwrap());
+        assert_eq!(
+            Err(FsError::NameAlreadyExists("/foo/fifo".into())),
+            fs.mknod("/foo/fifo".into(), SpecialKind::S_IFBLK, vec![])
+        );
+        assert_eq!(
+            Err(FsError::NotAFile("/foo/fifo".into())),
+            fs.open("/foo/fifo".into(), vec![])
+        );
+        fs.chmod("/foo/fifo".into(), vec![ModeFlag::S_IRWXO])
+            .unwrap();
+        assert_eq!(vec![ModeFlag::S_IRWXO], fs.special(&fifo).unwrap().mode);
+        assert_eq!(SpecialKind::S_IFIFO, fs.special(&fifo).unwrap().kind);
+        fs.remove("/foo/fifo".into()).unwrap();
+        assert_eq!(vec![PathName::from("/dev")], fs.alive().specials);
+        fs.remove("/dev".into()).unwrap();
+        assert!(fs.alive().specials.is_empty());
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_crashpoint_synced_ranges() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        fs.write(des, 0, 100).unwrap();
+        fs.write(des, 0, 100).unwrap();
+        assert_eq!(vec![(0, 200)], fs.file(&foo).unwrap().unsynced);
+        fs.fsync(des).unwrap();
+        assert!(fs.file(&foo).unwrap().is_synced(0, 200));
+        fs.lseek(des, 1000, Whence::SEEK_SET).unwrap();
+        fs.write(des, 0, 10).unwrap();
+        fs.sync_file_range(des, 0, 0, vec![]).unwrap();
+        fs.crashpoint().unwrap();
+        let file = fs.file(&foo).unwrap();
+        // latest content is kept, file may also have the synced one
+        assert_eq!(1010, file.content.size());
+        assert_eq!(Some(200), file.crashed.as_ref().map(Content::size));
+        assert_eq!(vec![(200, 1010)], file.unsynced);
+        assert!(file.is_synced(0, 200));
+        assert!(!file.is_synced(100, 300));
+        assert!(file.is_synced(1010, 2000));
+
+        fs.truncate("/foo".into(), 100).unwrap();
+        assert_eq!(vec![(100, 1010)], fs.file(&foo).unwrap().unsynced);
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        fs.fdatasync(des).unwrap();
+        assert!(fs.file(&foo).unwrap().unsynced.is_empty());
+        assert_eq!(None, fs.file(&foo).unwrap().crashed);
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_crashpoint_either_state() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let bar = fs.create("/bar".into(), vec![]).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        fs.write(des, 0, 100).unwrap();
+        fs.fsync(des).unwrap();
+        fs.lseek(des, 0, Whence::SEEK_SET).unwrap();
+        fs.write(des, 500, 10).unwrap();
+        fs.crashpoint().unwrap();
+        assert_eq!(None, fs.file(&bar).unwrap().crashed);
+        let synced = fs.file(&foo).unwrap().synced.clone();
+        assert_eq!(Some(synced), fs.file(&foo).unwrap().crashed);
+
+        // later changes apply to both states
+        fs.truncate("/foo".into(), 50).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        fs.lseek(des, 0, Whence::SEEK_END).unwrap();
+        fs.write(des, 0, 10).unwrap();
+        let file = fs.file(&foo).unwrap();
+        assert_eq!(Some(60), file.crashed.as_ref().map(Content::size));
+        assert_eq!(60, file.content.size());
+        assert_ne!(Some(&file.content), file.crashed.as_ref());
+
+        fs.lseek(des, 0, Whence::SEEK_SET).unwrap();
+        fs.read(des, 10).unwrap();
+        fs.stat("/foo".into()).unwrap();
+        fs.stat("/bar".into()).unwrap();
+        assert_eq!(vec![10, 13, 14], fs.crash_dependent);
+        assert_eq!(
+            fs.crash_dependent,
+            AbstractFS::crash_dependent_of(&fs.recording)
+        );
+        assert_eq!(
+            vec![PathName::from("/foo")],
+            AbstractFS::crashed_paths_of(&fs.recording)
+        );
+
+        fs.fsync(des).unwrap();
+        assert_eq!(None, fs.file(&foo).unwrap().crashed);
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_crashpoint_closes_everything() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.create("/bar".into(), vec![]).unwrap();
+        let stream = fs.opendir("/foo".into()).unwrap();
+        let des = fs.open("/bar".into(), vec![]).unwrap();
+        fs.crashpoint().unwrap();
+        assert_eq!(Err(FsError::DescriptorWasClosed(des)), fs.write(des, 0, 1));
+        assert_eq!(
+            Err(FsError::DirStreamWasClosed(stream)),
+            fs.readdir(stream, 1)
+        );
+        assert!(fs.files.iter().all(|file| file.descriptors.is_empty()));
+        assert!(fs.dir_streams.iter().all(|stream| !stream.is_open));
+        // file can be opened again
+        fs.open("/bar".into(), vec![]).unwrap();
+        assert_eq!(Operation::CRASHPOINT, fs.recording.ops[4]);
+        test_replay(fs.recording);
+    }
+
     fn test_replay(workload: Workload) {
         let mut fs = AbstractFS::new();
         fs.replay(&workload).unwrap();
@@ -0,0 +1,510 @@
+//! Export of workload as xfstests (fstests) test skeleton.
+//!
+//! Skeleton still needs manual work before it can land:
+//! - create test with `./new` and copy the body, which assigns test number and copyright;
+//! - replace comments left for operations without shell equivalent (directory streams, file handles);
+//! - add checks of expected state (e.g. `ls`/`stat` after `_scratch_cycle_mount`);
+//! - run on fixed kernel and save output as golden `.out` file.
+
+use std::collections::BTreeSet;
+
+use super::{
+    flags::{
+        FallocateFlag, FallocateFlags, Mode, OpenFlag, RenameFlag, SpecialKind, SyncFileRangeFlag,
+    },
+    node::{DirStreamIndex, FileDescriptorIndex, FileHandleIndex},
+    operation::Operation,
+    pathname::PathName,
+    workload::Workload,
+};
+
+impl Workload {
+    /// Renders workload as fstests shell test, `summary` goes into the header.
+    pub fn encode_fstest(&self, summary: &str) -> String {
+        let mut result = String::new();
+        result.push_str("#! /bin/bash\n");
+        result.push_str("# SPDX-License-Identifier: GPL-2.0\n");
+        result.push_str("#\n");
+        result.push_str("# FS QA Test No. XXX\n");
+        result.push_str("#\n");
+        for line in summary.lines() {
+            result.push_str(&format!("# {}\n", line));
+        }
+        result.push_str("#\n");
+        result.push_str("# TODO: create test with ./new, replace commented out operations,\n");
+        result
+            .push_str("# check expected state and save output on fixed kernel as golden output.\n");
+        result.push_str("#\n");
+        result.push_str(". ./common/preamble\n");
+        result.push_str("_begin_fstest auto quick\n\n");
+        for require in self.fstest_requires() {
+            result.push_str(&format!("{}\n", require));
+        }
+        result.push_str("\n_scratch_mkfs >> $seqres.full 2>&1\n");
+        result.push_str("_scratch_mount\n\n");
+        // scratch can not be unmounted while shell keeps descriptors open
+        let mut open = BTreeSet::new();
+        for op in self.ops.iter() {
+            match op {
+                Operation::OPEN { des, .. } => {
+                    open.insert(*des);
+                }
+                Operation::CLOSE { des } => {
+                    open.remove(des);
+                }
+                Operation::CRASHPOINT => {
+                    for des in std::mem::take(&mut open) {
+                        result.push_str(&format!("exec {{{}}}>&-\n", fd_var(&des)));
+                    }
+                }
+                _ => {}
+            }
+            result.push_str(&encode_fstest_op(op));
+        }
+        result.push_str("\n_scratch_cycle_mount\n\n");
+        result.push_str("# success, all done\n");
+        result.push_str("status=0\n");
+        result.push_str("exit\n");
+        result
+    }
+
+    fn fstest_requires(&self) -> BTreeSet<&'static str> {
+        let mut requires = BTreeSet::from(["_require_scratch"]);
+        for op in self.ops.iter() {
+            match op {
+                Operation::MKSOCKET { .. } => {
+                    requires.insert("_require_test_program \"af_unix\"");
+                }
+                Operation::SYMLINK { .. } => {
+                    requires.insert("_require_symlinks");
+                }
+                Operation::HARDLINK { .. } => {
+                    requires.insert("_require_hardlinks");
+                }
+                Operation::RENAME { flags, .. } => {
+                    if flags.contains(&RenameFlag::RENAME_EXCHANGE) {
+                        requires.insert("_require_renameat2 exchange");
+                    }
+                    if flags.contains(&RenameFlag::RENAME_NOREPLACE) {
+                        requires.insert("_require_renameat2 noreplace");
+                    }
+                }
+                Operation::SETXATTR { .. } | Operation::REMOVEXATTR { .. } => {
+                    requires.insert("_require_attrs");
+                }
+                Operation::FALLOCATE { mode, .. } => {
+                    requires.insert(match xfs_io_falloc_command(mode) {
+                        "fpunch" => "_require_xfs_io_command \"fpunch\"",
+                        "fzero" => "_require_xfs_io_command \"fzero\"",
+                        _ => "_require_xfs_io_command \"falloc\"",
+                    });
+                }
+                _ => {}
+            }
+        }
+        requires
+    }
+}
+
+fn encode_fstest_op(op: &Operation) -> String {
+    match op {
+        Operation::MKDIR { path, mode } => {
+            format!("mkdir -m {} {}\n", encode_mode_octal(mode), scratch(path))
+        }
+        Operation::CREATE { path, mode } => format!(
+            "touch {}\nchmod {} {}\n",
+            scratch(path),
+            encode_mode_octal(mode),
+            scratch(path)
+        ),
+        Operation::REMOVE { path } => format!("rm -rf {}\n", scratch(path)),
+        Operation::MKSOCKET { path } => format!("$here/src/af_unix {}\n", scratch(path)),
+        Operation::SYMLINK { target, path } => {
+            format!("ln -s '{}' {}\n", target, scratch(path))
+        }
+        Operation::READLINK { path } => format!("readlink {} >> $seqres.full\n", scratch(path)),
+        Operation::HARDLINK { old_path, new_path } => {
+            format!("ln {} {}\n", scratch(old_path), scratch(new_path))
+        }
+        Operation::RENAME {
+            old_path,
+            new_path,
+            flags,
+        } if flags.is_empty() => format!("mv -T {} {}\n", scratch(old_path), scratch(new_path)),
+        Operation::RENAME {
+            old_path,
+            new_path,
+            flags,
+        } => {
+            let options: Vec<&str> = flags
+                .iter()
+                .map(|flag| match flag {
+                    RenameFlag::RENAME_NOREPLACE => "-n",
+                    RenameFlag::RENAME_EXCHANGE => "-x",
+                })
+                .collect();
+            format!(
+                "$here/src/renameat2 {} {} {}\n",
+                options.join(" "),
+                scratch(old_path),
+                scratch(new_path)
+            )
+        }
+        // shell can not open file for both reading and appending
+        Operation::OPEN { path, flags, des } if flags.contains(&OpenFlag::O_APPEND) => {
+            format!("exec {{{}}}>>{}\n", fd_var(des), scratch(path))
+        }
+        // redirection always follows symlink
+        Operation::OPEN { path, flags, des } if flags.contains(&OpenFlag::O_NOFOLLOW) => format!(
+            "[ -L {} ] || exec {{{}}}<>{}\n",
+            scratch(path),
+            fd_var(des),
+            scratch(path)
+        ),
+        Operation::OPEN { path, flags, des } if flags.contains(&OpenFlag::O_EXCL) => format!(
+            "[ -e {} ] || [ -L {} ] || exec {{{}}}<>{}\n",
+            scratch(path),
+            scratch(path),
+            fd_var(des),
+            scratch(path)
+        ),
+        Operation::OPEN { path, des, .. } => {
+            format!("exec {{{}}}<>{}\n", fd_var(des), scratch(path))
+        }
+        Operation::CLOSE { des } => format!("exec {{{}}}>&-\n", fd_var(des)),
+        // single read call, so descriptor offset moves exactly as in executor
+        Operation::READ { des, size } if *size > 0 => format!(
+            "dd bs={} count=1 status=none <&${} > /dev/null\n",
+            size,
+            fd_var(des)
+        ),
+        Operation::READ { des, size } => format!("# read {} bytes from {}\n", size, fd_var(des)),
+        Operation::WRITE {
+            des,
+            src_offset: _,
+            size,
+        } => format!("yes | head -c {} >&${}\n", size, fd_var(des)),
+        Operation::FSYNC { des } => {
+            format!("$XFS_IO_PROG -c fsync /proc/$$/fd/${}\n", fd_var(des))
+        }
+        Operation::FDATASYNC { des } => {
+            format!("$XFS_IO_PROG -c fdatasync /proc/$$/fd/${}\n", fd_var(des))
+        }
+        // xfs_io starts write-out when no flags are given, syscall does nothing
+        Operation::SYNCFILERANGE {
+            des,
+            offset,
+            nbytes,
+            flags,
+        } if flags.is_empty() => format!(
+            "# sync_file_range {} {} {} without flags\n",
+            fd_var(des),
+            offset,
+            nbytes
+        ),
+        Operation::SYNCFILERANGE {
+            des,
+            offset,
+            nbytes,
+            flags,
+        } => {
+            let options: Vec<&str> = flags
+                .iter()
+                .map(|flag| match flag {
+                    SyncFileRangeFlag::SYNC_FILE_RANGE_WAIT_BEFORE => "-b",
+                    SyncFileRangeFlag::SYNC_FILE_RANGE_WRITE => "-w",
+                    SyncFileRangeFlag::SYNC_FILE_RANGE_WAIT_AFTER => "-a",
+                })
+                .collect();
+            format!(
+                "$XFS_IO_PROG -c \"sync_range {} {} {}\" /proc/$$/fd/${}\n",
+                options.join(" "),
+                offset,
+                nbytes,
+                fd_var(des)
+            )
+        }
+        // shell can not move offset of already opened descriptor
+        Operation::LSEEK {
+            des,
+            offset,
+            whence,
+        } => format!("# lseek {} {} {}\n", fd_var(des), offset, whence),
+        Operation::OPENDIR { path, stream } => format!(
+            "ls -a {} >> $seqres.full # {} = opendir\n",
+            scratch(path),
+            dir_stream_var(stream)
+        ),
+        Operation::READDIR { stream, count } => {
+            format!(
+                "# readdir {} entries from {}\n",
+                count,
+                dir_stream_var(stream)
+            )
+        }
+        Operation::CLOSEDIR { stream } => format!("# closedir {}\n", dir_stream_var(stream)),
+        // order of entries is filesystem-specific, so output is sorted
+        Operation::LISTDIR { path } => {
+            format!("ls -a {} >> $seqres.full\n", scratch(path))
+        }
+        Operation::NAMETOHANDLE { path, handle } => format!(
+            "# {} = name_to_handle_at {}\n",
+            file_handle_var(handle),
+            scratch(path)
+        ),
+        Operation::OPENBYHANDLE { handle } => {
+            format!("# open_by_handle_at {}\n", file_handle_var(handle))
+        }
+        Operation::PREALLOC { path, size } => format!(
+            "$XFS_IO_PROG -c \"truncate {}\" -c \"pwrite -S 0x61 0 {}\" {} >> $seqres.full\n",
+            size,
+            size,
+            scratch(path)
+        ),
+        Operation::TRUNCATE { path, size } => format!("truncate -s {} {}\n", size, scratch(path)),
+        Operation::CHMOD { path, mode } => {
+            format!("chmod {} {}\n", encode_mode_octal(mode), scratch(path))
+        }
+        Operation::MKNOD { path, kind, mode } => {
+            let mode = encode_mode_octal(mode);
+            match kind {
+                SpecialKind::S_IFIFO => format!("mkfifo -m {} {}\n", mode, scratch(path)),
+                SpecialKind::S_IFCHR => format!("mknod -m {} {} c 0 0\n", mode, scratch(path)),
+                SpecialKind::S_IFBLK => format!("mknod -m {} {} b 0 0\n", mode, scratch(path)),
+            }
+        }
+        // executor takes value from write buffer, any bytes of the same size should do
+        Operation::SETXATTR {
+            path,
+            name,
+            value_size: 0,
+        } => format!("$SETFATTR_PROG -n {} {}\n", name, scratch(path)),
+        Operation::SETXATTR {
+            path,
+            name,
+            value_size,
+        } => format!(
+            "$SETFATTR_PROG -n {} -v \"$(yes | head -c {} | tr '\\n' y)\" {}\n",
+            name,
+            value_size,
+            scratch(path)
+        ),
+        Operation::REMOVEXATTR { path, name } => {
+            format!("$SETFATTR_PROG -x {} {}\n", name, scratch(path))
+        }
+        Operation::UTIMENS { path, atime, mtime } => format!(
+            "touch -h -a -d @{} {}\ntouch -h -m -d @{} {}\n",
+            atime,
+            scratch(path),
+            mtime,
+            scratch(path)
+        ),
+        Operation::STAT { path } => format!("stat {} >> $seqres.full\n", scratch(path)),
+        // punching hole always keeps size in xfs_io
+        Operation::FALLOCATE {
+            des,
+            offset,
+            len,
+            mode,
+        } => {
+            let command = xfs_io_falloc_command(mode);
+            let keep_size =
+                command != "fpunch" && mode.contains(&FallocateFlag::FALLOC_FL_KEEP_SIZE);
+            format!(
+                "$XFS_IO_PROG -c \"{}{} {} {}\" /proc/$$/fd/${}\n",
+                command,
+                if keep_size { " -k" } else { "" },
+                offset,
+                len,
+                fd_var(des)
+            )
+        }
+        Operation::CRASHPOINT => "_scratch_cycle_mount\n".to_owned(),
+    }
+}
+
+fn xfs_io_falloc_command(mode: &FallocateFlags) -> &'static str {
+    if mode.contains(&FallocateFlag::FALLOC_FL_PUNCH_HOLE) {
+        "fpunch"
+    } else if mode.contains(&FallocateFlag::FALLOC_FL_ZERO_RANGE) {
+        "fzero"
+    } else {
+        "falloc"
+    }
+}
+
+fn scratch(path: &PathName) -> String {
+    if path.is_root() {
+        "\"$SCRATCH_MNT\"".to_owned()
+    } else {
+        format!("\"$SCRATCH_MNT{}\"", path)
+    }
+}
+
+fn fd_var(des: &FileDescriptorIndex) -> String {
+    format!("fd_{}", des.0)
+}
+
+fn dir_stream_var(stream: &DirStreamIndex) -> String {
+    format!("ds_{}", stream.0)
+}
+
+fn file_handle_var(handle: &FileHandleIndex) -> String {
+    format!("fh_{}", handle.0)
+}
+
+pub(super) fn encode_mode_octal(mode: &Mode) -> String {
+    format!(
+        "{:04o}",
+        mode.iter().fold(0, |acc, flag| acc | *flag as u32)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::abstract_fs::{
+        flags::{FallocateFlag, ModeFlag},
+        fs::AbstractFS,
+    };
+
+    fn body(encoded: &str) -> Vec<&str> {
+        let lines: Vec<&str> = encoded.lines().collect();
+        let start = lines.iter().position(|l| *l == "_scratch_mount").unwrap() + 2;
+        let end = lines
+            .iter()
+            .rposition(|l| *l == "_scratch_cycle_mount")
+            .unwrap()
+            - 1;
+        lines[start..end].to_vec()
+    }
+
+    fn requires(encoded: &str) -> Vec<&str> {
+        encoded
+            .lines()
+            .filter(|l| l.starts_with("_require_"))
+            .collect()
+    }
+
+    #[test]
+    fn test_encode_fstest() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![ModeFlag::S_IRWXU, ModeFlag::S_ISVTX])
+            .unwrap();
+        fs.create("/foo/bar".into(), vec![ModeFlag::S_IRUSR])
+            .unwrap();
+        let des = fs.open("/foo/bar".into(), vec![]).unwrap();
+        fs.write(des, 42, 100).unwrap();
+        fs.fsync(des).unwrap();
+        fs.close(des).unwrap();
+        fs.hardlink("/foo/bar".into(), "/baz".into()).unwrap();
+        fs.rename("/baz".into(), "/gaz".into()).unwrap();
+        fs.remove("/foo".into()).unwrap();
+        let encoded = fs.recording.encode_fstest("trace objective");
+        assert!(encoded.starts_with("#! /bin/bash\n# SPDX-License-Identifier: GPL-2.0\n"));
+        assert!(encoded.contains("# trace objective\n"));
+        assert_eq!(
+            vec!["_require_hardlinks", "_require_scratch"],
+            requires(&encoded)
+        );
+        assert_eq!(
+            vec![
+                "mkdir -m 1700 \"$SCRATCH_MNT/foo\"",
+                "touch \"$SCRATCH_MNT/foo/bar\"",
+                "chmod 0400 \"$SCRATCH_MNT/foo/bar\"",
+                "exec {fd_0}<>\"$SCRATCH_MNT/foo/bar\"",
+                "yes | head -c 100 >&$fd_0",
+                "$XFS_IO_PROG -c fsync /proc/$$/fd/$fd_0",
+                "exec {fd_0}>&-",
+                "ln \"$SCRATCH_MNT/foo/bar\" \"$SCRATCH_MNT/baz\"",
+                "mv -T \"$SCRATCH_MNT/baz\" \"$SCRATCH_MNT/gaz\"",
+                "rm -rf \"$SCRATCH_MNT/foo\"",
+            ],
+            body(&encoded)
+        );
+    }
+
+    #[test]
+    fn test_encode_fstest_requires() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        let encoded = fs.recording.encode_fstest("");
+        assert_eq!(vec!["_require_scratch"], requires(&encoded));
+
+        fs.mksocket("/foo/sock".into()).unwrap();
+        fs.symlink("sock/".into(), "/foo/link".into()).unwrap();
+        fs.readlink("/foo/link".into()).unwrap();
+        fs.create("/bar".into(), vec![]).unwrap();
+        let des = fs.open("/bar".into(), vec![]).unwrap();
+        fs.fallocate(
+            des,
+            0,
+            4096,
+            vec![
+                FallocateFlag::FALLOC_FL_ZERO_RANGE,
+                FallocateFlag::FALLOC_FL_KEEP_SIZE,
+            ],
+        )
+        .unwrap();
+        fs.close(des).unwrap();
+        let stream = fs.opendir("/".into()).unwrap();
+        fs.readdir(stream, 2).unwrap();
+        let encoded = fs.recording.encode_fstest("");
+        assert_eq!(
+            vec![
+                "_require_scratch",
+                "_require_symlinks",
+                "_require_test_program \"af_unix\"",
+                "_require_xfs_io_command \"fzero\"",
+            ],
+            requires(&encoded)
+        );
+        assert_eq!(
+            vec![
+                "mkdir -m 0000 \"$SCRATCH_MNT/foo\"",
+                "$here/src/af_unix \"$SCRATCH_MNT/foo/sock\"",
+                "ln -s 'sock/' \"$SCRATCH_MNT/foo/link\"",
+                "readlink \"$SCRATCH_MNT/foo/link\" >> $seqres.full",
+                "touch \"$SCRATCH_MNT/bar\"",
+                "chmod 0000 \"$SCRATCH_MNT/bar\"",
+                "exec {fd_0}<>\"$SCRATCH_MNT/bar\"",
+                "$XFS_IO_PROG -c \"fzero -k 0 4096\" /proc/$$/fd/$fd_0",
+                "exec {fd_0}>&-",
+                "ls -a \"$SCRATCH_MNT\" >> $seqres.full # ds_0 = opendir",
+                "# readdir 2 entries from ds_0",
+            ],
+            body(&encoded)
+        );
+    }
+
+    #[test]
+    fn test_encode_fstest_crashpoint() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        fs.create("/bar".into(), vec![]).unwrap();
+        fs.create("/baz".into(), vec![]).unwrap();
+        let foo = fs.open("/foo".into(), vec![]).unwrap();
+        fs.open("/bar".into(), vec![]).unwrap();
+        fs.close(foo).unwrap();
+        fs.open("/baz".into(), vec![]).unwrap();
+        fs.crashpoint().unwrap();
+        let encoded = fs.recording.encode_fstest("");
+        assert_eq!(
+            vec![
+                "touch \"$SCRATCH_MNT/foo\"",
+                "chmod 0000 \"$SCRATCH_MNT/foo\"",
+                "touch \"$SCRATCH_MNT/bar\"",
+                "chmod 0000 \"$SCRATCH_MNT/bar\"",
+                "touch \"$SCRATCH_MNT/baz\"",
+                "chmod 0000 \"$SCRATCH_MNT/baz\"",
+                "exec {fd_0}<>\"$SCRATCH_MNT/foo\"",
+                "exec {fd_1}<>\"$SCRATCH_MNT/bar\"",
+                "exec {fd_0}>&-",
+                "exec {fd_2}<>\"$SCRATCH_MNT/baz\"",
+                "exec {fd_1}>&-",
+                "exec {fd_2}>&-",
+                "_scratch_cycle_mount",
+            ],
+            body(&encoded)
+        );
+    }
+}
@@ -1,10 +1,15 @@
-use rand::{seq::SliceRandom, Rng};
+use rand::{
+    seq::{IteratorRandom, SliceRandom},
+    Rng,
+};
 
 use super::{
-    flags::ModeFlag,
-    fs::AbstractFS,
-    node::FileDescriptorIndex,
-    operation::{OperationKind, OperationWeights},
+    flags::{
+        FallocateFlag, Mode, ModeFlag, OpenFlag, RenameFlag, SpecialKind, SyncFileRangeFlag, Whence,
+    },
+    fs::{AbstractFS, FsError},
+    node::{DirStreamIndex, FileDescriptorIndex, FileHandleIndex},
+    operation::{Operation, OperationKind, OperationWeights},
     pathname::{Name, PathName},
     workload::Workload,
 };
@@ -29,8 +34,437 @@ fn random_interesting_unsigned(rng: &mut impl Rng) -> u64 {
     INTERESTING_UNSIGNED.choose(rng).unwrap().clone()
 }
 
-pub fn generate_new(rng: &mut impl Rng, size: usize, weights: &OperationWeights) -> Workload {
-    let mut fs = AbstractFS::new();
+/// Large enough to span several blocks and extents, small enough to fit a few on test device.
+const PREALLOC_SIZES: &[u64] = &[4096, 65536, 1048576, 1048577, 4194304];
+
+/// Modes Linux accepts, punching hole has to keep size.
+pub const FALLOCATE_MODES: &[&[FallocateFlag]] = &[
+    &[],
+    &[FallocateFlag::FALLOC_FL_KEEP_SIZE],
+    &[
+        FallocateFlag::FALLOC_FL_PUNCH_HOLE,
+        FallocateFlag::FALLOC_FL_KEEP_SIZE,
+    ],
+    &[FallocateFlag::FALLOC_FL_ZERO_RANGE],
+    &[
+        FallocateFlag::FALLOC_FL_ZERO_RANGE,
+        FallocateFlag::FALLOC_FL_KEEP_SIZE,
+    ],
+];
+
+/// Few names, so that attributes are often replaced and removed.
+const XATTR_NAMES: &[&str] = &["user.0", "user.1", "user.2", "user.3"];
+
+/// Around inline limits (inode, block) and `XATTR_SIZE_MAX`.
+const XATTR_SIZES: &[u64] = &[0, 1, 100, 255, 1024, 4000, 4096, 16384, 65536];
+
+/// Upper bound of space one attribute takes besides its value (entry header, name and padding).
+pub const XATTR_ENTRY_OVERHEAD: u64 = 32;
+
+/// Seconds around epoch and overflows of 32-bit `time_t` (signed and unsigned).
+const INTERESTING_TIMES: &[i64] = &[
+    -2147483649,
+    -2147483648,
+    -1,
+    0,
+    1,
+    2147483647,
+    2147483648,
+    4294967295,
+    4294967296,
+];
+
+/// Number of last operations which targets are preferred with locality bias.
+const LOCALITY_WINDOW: usize = 3;
+
+/// Chance that file is opened with `O_APPEND`.
+const APPEND_RATE: f64 = 0.2;
+
+/// Chance that hardlink targets a directory, which must fail with `EPERM`.
+const DIR_HARDLINK_RATE: f64 = 0.05;
+
+/// Chance that hardlink is made through symlink, which fails unless symlink leads to directory.
+const SYMLINK_HARDLINK_RATE: f64 = 0.1;
+
+/// Chance that open creates new file (`O_CREAT`), if creating files is allowed.
+const CREATE_OPEN_RATE: f64 = 0.1;
+
+/// Chance that creating open is exclusive (`O_EXCL`).
+const EXCLUSIVE_OPEN_RATE: f64 = 0.5;
+
+/// Chance that exclusive creating open targets existing node of any kind, which must fail with `EEXIST`.
+const EXISTING_EXCLUSIVE_OPEN_RATE: f64 = 0.05;
+
+/// Chance that open targets symlink with `O_NOFOLLOW`, which must fail with `ELOOP`.
+const NOFOLLOW_OPEN_RATE: f64 = 0.1;
+
+/// Once this share of alive node limit is used, removals get [`NEAR_ALIVE_LIMIT_REMOVE_BOOST`] times more weight.
+const NEAR_ALIVE_LIMIT: f64 = 0.75;
+const NEAR_ALIVE_LIMIT_REMOVE_BOOST: u32 = 4;
+
+/// Chance that new name has letters, so that later names can collide with it.
+const LETTERED_NAME_RATE: f64 = 0.1;
+
+/// Chance that new name differs only in case from a sibling that has letters.
+const CASE_COLLISION_RATE: f64 = 0.3;
+
+/// Chance that rename targets a parent that is a file or does not exist.
+const BAD_PARENT_RENAME_RATE: f64 = 0.05;
+
+/// Chance that rename moves a directory, which changes link counts of both parents.
+const SUBDIRECTORY_RENAME_RATE: f64 = 0.3;
+
+/// Chance that rename atomically swaps two existing nodes (`RENAME_EXCHANGE`).
+const EXCHANGE_RENAME_RATE: f64 = 0.1;
+
+/// Chance that rename to new name is done with `RENAME_NOREPLACE`.
+const NOREPLACE_RENAME_RATE: f64 = 0.1;
+
+/// Chance that `RENAME_NOREPLACE` targets existing sibling instead, which must fail.
+const EXISTING_NOREPLACE_RENAME_RATE: f64 = 0.3;
+
+/// Chance that symlink target is a name that does not exist.
+const DANGLING_SYMLINK_RATE: f64 = 0.2;
+
+pub(super) const PERMISSION_BITS: &[ModeFlag] = &[
+    ModeFlag::S_IRUSR,
+    ModeFlag::S_IWUSR,
+    ModeFlag::S_IXUSR,
+    ModeFlag::S_IRGRP,
+    ModeFlag::S_IWGRP,
+    ModeFlag::S_IXGRP,
+    ModeFlag::S_IROTH,
+    ModeFlag::S_IWOTH,
+    ModeFlag::S_IXOTH,
+];
+
+pub(super) const SPECIAL_BITS: &[ModeFlag] =
+    &[ModeFlag::S_ISUID, ModeFlag::S_ISGID, ModeFlag::S_ISVTX];
+
+/// Value sizes that attribute `name` can have at `path` without exceeding
+/// [`AbstractFS::max_xattr_space`] (value of replaced attribute does not count).
+fn xattr_sizes(fs: &AbstractFS, path: &PathName, name: &str) -> Vec<u64> {
+    let used: u64 = fs
+        .xattrs(path.clone())
+        .unwrap()
+        .iter()
+        .filter(|(other, _)| *other != name)
+        .map(|(_, size)| size + XATTR_ENTRY_OVERHEAD)
+        .sum();
+    XATTR_SIZES
+        .iter()
+        .filter(|size| {
+            fs.max_xattr_space
+                .is_none_or(|space| used + *size + XATTR_ENTRY_OVERHEAD <= space)
+        })
+        .copied()
+        .collect()
+}
+
+/// Root and its children are special for filesystems (e.g. root has no parent),
+/// so with `root_bias` chance path is picked only among those.
+///
+/// Root itself is never removed or renamed: executor runs workload in a directory of mounted
+/// filesystem, so on real filesystem such operation succeeds on that directory instead of failing,
+/// and there is nothing for objective to compare with model.
+fn choose_path(
+    rng: &mut impl Rng,
+    paths: &[PathName],
+    root_bias: f64,
+    locality: &Locality,
+) -> PathName {
+    if let Some(path) = locality.choose(rng, &locality.paths, paths) {
+        return path;
+    }
+    if rng.gen_bool(root_bias) {
+        let root_adjacent: Vec<&PathName> =
+            paths.iter().filter(|p| p.segments().len() <= 1).collect();
+        if let Some(path) = root_adjacent.choose(rng) {
+            return (*path).clone();
+        }
+    }
+    paths.choose(rng).unwrap().to_owned()
+}
+
+/// Parent directory for new node, with `deep_path_bias` chance picked among the deepest ones,
+/// so that generated trees grow deep instead of wide (`dirs` already respect depth limit).
+fn choose_parent(
+    rng: &mut impl Rng,
+    dirs: &[PathName],
+    root_bias: f64,
+    deep_path_bias: f64,
+    locality: &Locality,
+) -> PathName {
+    // without bias random generator is not touched, so workloads stay the same
+    if deep_path_bias > 0.0 && rng.gen_bool(deep_path_bias) {
+        let depth = dirs.iter().map(|d| d.segments().len()).max().unwrap();
+        let deepest: Vec<&PathName> = dirs
+            .iter()
+            .filter(|d| d.segments().len() == depth)
+            .collect();
+        return (*deepest.choose(rng).unwrap()).clone();
+    }
+    choose_path(rng, dirs, root_bias, locality)
+}
+
+/// Number of levels below `path` among `paths`, zero for files and empty directories.
+fn subtree_height(paths: &[PathName], path: &PathName) -> usize {
+    let depth = path.segments().len();
+    paths
+        .iter()
+        .filter(|p| path.is_prefix_of(p))
+        .map(|p| p.segments().len() - depth)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Target of new symlink, it is stored as is, so the same node is referred to in different forms
+/// (e.g. relative, with trailing or doubled slashes) that filesystem must not normalize.
+fn random_symlink_target(
+    rng: &mut impl Rng,
+    paths: &[PathName],
+    gen_name: &mut impl FnMut() -> Name,
+) -> String {
+    let path = paths.choose(rng).unwrap();
+    let path = if rng.gen_bool(DANGLING_SYMLINK_RATE) {
+        path.join(gen_name()).to_string()
+    } else {
+        path.to_string()
+    };
+    let target = match rng.gen_range(0..5) {
+        0 => path,
+        1 => format!("{}/", path),
+        2 => path.trim_start_matches('/').to_owned(),
+        3 => format!("..{}", path),
+        _ => path.replace('/', "//"),
+    };
+    if target.is_empty() {
+        ".".to_owned()
+    } else {
+        target
+    }
+}
+
+/// Like [`choose_path`], but without root bias.
+fn choose_any(rng: &mut impl Rng, paths: &[PathName], locality: &Locality) -> PathName {
+    locality
+        .choose(rng, &locality.paths, paths)
+        .unwrap_or_else(|| paths.choose(rng).unwrap().to_owned())
+}
+
+fn choose_descriptor(
+    rng: &mut impl Rng,
+    descriptors: &[FileDescriptorIndex],
+    locality: &Locality,
+) -> FileDescriptorIndex {
+    locality
+        .choose(rng, &locality.descriptors, descriptors)
+        .unwrap_or_else(|| *descriptors.choose(rng).unwrap())
+}
+
+/// Paths and descriptors used by the last [`LOCALITY_WINDOW`] operations.
+///
+/// With `bias` chance operation targets one of them (if any fits), so that operations
+/// form chains on the same node, e.g. create, open and write or mkdir and create inside.
+struct Locality {
+    bias: f64,
+    paths: Vec<PathName>,
+    descriptors: Vec<FileDescriptorIndex>,
+}
+
+impl Locality {
+    fn new(fs: &AbstractFS, bias: f64) -> Self {
+        let mut paths = vec![];
+        let mut descriptors = vec![];
+        for op in fs.recording.ops.iter().rev().take(LOCALITY_WINDOW) {
+            match op {
+                Operation::MKDIR { path, .. }
+                | Operation::CREATE { path, .. }
+                | Operation::MKSOCKET { path }
+                | Operation::SYMLINK { path, .. }
+                | Operation::READLINK { path }
+                | Operation::MKNOD { path, .. }
+                | Operation::OPENDIR { path, .. }
+                | Operation::LISTDIR { path }
+                | Operation::NAMETOHANDLE { path, .. }
+                | Operation::PREALLOC { path, .. }
+                | Operation::TRUNCATE { path, .. }
+                | Operation::CHMOD { path, .. }
+                | Operation::SETXATTR { path, .. }
+                | Operation::REMOVEXATTR { path, .. }
+                | Operation::UTIMENS { path, .. }
+                | Operation::STAT { path } => paths.push(path.clone()),
+                Operation::HARDLINK { old_path, new_path } => {
+                    paths.push(old_path.clone());
+                    paths.push(new_path.clone());
+                }
+                Operation::RENAME { new_path, .. } => paths.push(new_path.clone()),
+                Operation::OPEN { path, des, .. } => {
+                    paths.push(path.clone());
+                    descriptors.push(*des);
+                }
+                Operation::READ { des, .. }
+                | Operation::WRITE { des, .. }
+                | Operation::FSYNC { des }
+                | Operation::FDATASYNC { des }
+                | Operation::SYNCFILERANGE { des, .. }
+                | Operation::LSEEK { des, .. }
+                | Operation::FALLOCATE { des, .. } => descriptors.push(*des),
+                Operation::REMOVE { .. }
+                | Operation::CLOSE { .. }
+                | Operation::READDIR { .. }
+                | Operation::CLOSEDIR { .. }
+                | Operation::OPENBYHANDLE { .. }
+                | Operation::CRASHPOINT => {}
+            }
+        }
+        Self {
+            bias,
+            paths,
+            descriptors,
+        }
+    }
+
+    /// Recent target among `candidates`, `None` if there are none or bias was not hit.
+    fn choose<T: Clone + PartialEq>(
+        &self,
+        rng: &mut impl Rng,
+        recent: &[T],
+        candidates: &[T],
+    ) -> Option<T> {
+        // without bias random generator is not touched, so workloads stay the same
+        if self.bias <= 0.0 {
+            return None;
+        }
+        let recent: Vec<&T> = recent.iter().filter(|t| candidates.contains(t)).collect();
+        if recent.is_empty() || !rng.gen_bool(self.bias) {
+            return None;
+        }
+        recent.choose(rng).map(|t| (*t).clone())
+    }
+}
+
+/// Fresh name for new entry in `parent`.
+///
+/// Names generated by `gen_name` are numbers, so some names get a letter
+/// and later names can flip its case to collide on case-insensitive filesystems (if `case_collisions` is set).
+fn new_name(
+    rng: &mut impl Rng,
+    fs: &AbstractFS,
+    parent: &PathName,
+    case_collisions: bool,
+    mut gen_name: impl FnMut() -> Name,
+) -> Name {
+    let siblings = fs.child_names(parent.clone()).unwrap_or_default();
+    if case_collisions && rng.gen_bool(CASE_COLLISION_RATE) {
+        let collisions: Vec<Name> = siblings
+            .iter()
+            .filter(|name| name.chars().any(|c| c.is_ascii_alphabetic()))
+            .map(|name| flip_case(name))
+            .filter(|name| !siblings.contains(name))
+            .collect();
+        if let Some(name) = collisions.choose(rng) {
+            return name.clone();
+        }
+    }
+    let name = gen_name();
+    if rng.gen_bool(LETTERED_NAME_RATE) {
+        let lettered = format!("n{}", name);
+        if !siblings.contains(&lettered) {
+            return lettered;
+        }
+    }
+    name
+}
+
+fn flip_case(name: &str) -> Name {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_lowercase() {
+                c.to_ascii_uppercase()
+            } else {
+                c.to_ascii_lowercase()
+            }
+        })
+        .collect()
+}
+
+/// Without `special_bits_rate` mode is always the same (`0775`).
+/// Otherwise each permission bit is set with 50% chance and each special bit with given rate.
+pub fn random_mode(rng: &mut impl Rng, special_bits_rate: Option<f64>) -> Mode {
+    match special_bits_rate {
+        None => vec![
+            ModeFlag::S_IRWXU,
+            ModeFlag::S_IRWXG,
+            ModeFlag::S_IROTH,
+            ModeFlag::S_IXOTH,
+        ],
+        Some(rate) => {
+            let mut mode: Mode = PERMISSION_BITS
+                .iter()
+                .filter(|_| rng.gen_bool(0.5))
+                .copied()
+                .collect();
+            for bit in SPECIAL_BITS {
+                if rng.gen_bool(rate) {
+                    mode.push(*bit);
+                }
+            }
+            mode
+        }
+    }
+}
+
+/// Settings shared by generation of new workloads and insertion of operations by mutator.
+#[derive(Clone)]
+pub struct GeneratorConfig {
+    pub weights: OperationWeights,
+    pub max_nodes: Option<usize>,
+    pub max_alive_nodes: Option<usize>,
+    pub max_path_depth: Option<usize>,
+    pub max_xattr_space: Option<u64>,
+    /// Subset of [`FALLOCATE_MODES`] that compared filesystems support, no `fallocate` if empty.
+    pub fallocate_modes: Vec<Vec<FallocateFlag>>,
+    pub special_mode_bits: Option<f64>,
+    pub root_bias: f64,
+    pub deep_path_bias: f64,
+    pub locality_bias: f64,
+    /// Names may differ only in case from sibling, see [`crate::fuzzing::objective::case::CaseObjective`].
+    pub case_collisions: bool,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        Self {
+            weights: OperationWeights::uniform(),
+            max_nodes: None,
+            max_alive_nodes: None,
+            max_path_depth: None,
+            max_xattr_space: None,
+            fallocate_modes: FALLOCATE_MODES.iter().map(|mode| mode.to_vec()).collect(),
+            special_mode_bits: None,
+            root_bias: 0.0,
+            deep_path_bias: 0.0,
+            locality_bias: 0.0,
+            case_collisions: true,
+        }
+    }
+}
+
+impl GeneratorConfig {
+    /// Model with node limits applied.
+    pub fn model(&self) -> AbstractFS {
+        let mut fs = AbstractFS::new();
+        fs.max_nodes = self.max_nodes;
+        fs.max_alive_nodes = self.max_alive_nodes;
+        fs.max_path_depth = self.max_path_depth;
+        fs.max_xattr_space = self.max_xattr_space;
+        fs
+    }
+}
+
+pub fn generate_new(rng: &mut impl Rng, size: usize, config: &GeneratorConfig) -> Workload {
+    let mut fs = config.model();
     let mut name_idx: usize = 0;
     let mut gen_name = || {
         let name = name_idx.to_string();
@@ -38,23 +472,62 @@ pub fn generate_new(rng: &mut impl Rng, size: usize, weights: &OperationWeights)
         name
     };
     for _ in 0..size {
-        append_one(rng, &mut fs, &weights, &mut gen_name);
+        append_one(rng, &mut fs, config, &mut gen_name);
     }
-    fs.recording
+    let mut workload = fs.recording;
+    workload.seed = rng.gen();
+    workload
 }
 
-pub fn append_one(
-    rng: &mut impl Rng,
-    fs: &mut AbstractFS,
-    weights: &OperationWeights,
-    mut gen_name: impl FnMut() -> Name,
-) {
+/// Builds several nested directories and then shuffles them around with renames,
+/// so that some of them try to form a cycle (those are rejected by the model).
+pub fn generate_rename_loops(rng: &mut impl Rng, dirs: usize, renames: usize) -> Workload {
     let mode = vec![
         ModeFlag::S_IRWXU,
         ModeFlag::S_IRWXG,
         ModeFlag::S_IROTH,
         ModeFlag::S_IXOTH,
     ];
+    let mut fs = AbstractFS::new();
+    let mut name_idx: usize = 0;
+    let mut gen_name = || {
+        let name = name_idx.to_string();
+        name_idx += 1;
+        name
+    };
+    for _ in 0..dirs {
+        let parent = fs.alive().dirs.choose(rng).unwrap().to_owned();
+        fs.mkdir(parent.join(gen_name()), mode.clone()).unwrap();
+    }
+    for _ in 0..renames {
+        let alive = fs.alive();
+        let alive_dirs_except_root: Vec<PathName> = alive
+            .dirs
+            .iter()
+            .filter(|d| !d.is_root())
+            .map(|d| d.clone())
+            .collect();
+        if alive_dirs_except_root.is_empty() {
+            break;
+        }
+        let old_path = alive_dirs_except_root.choose(rng).unwrap().to_owned();
+        let new_parent = alive.dirs.choose(rng).unwrap().to_owned();
+        // renames into own subdirectory are rejected and not recorded
+        let _ = fs.rename(old_path, new_parent.join(gen_name()));
+    }
+    fs.recording
+}
+
+pub fn append_one(
+    rng: &mut impl Rng,
+    fs: &mut AbstractFS,
+    config: &GeneratorConfig,
+    mut gen_name: impl FnMut() -> Name,
+) {
+    let special_mode_bits = config.special_mode_bits;
+    let root_bias = config.root_bias;
+    let deep_path_bias = config.deep_path_bias;
+    let case_collisions = config.case_collisions;
     let alive = fs.alive();
     let alive_dirs_except_root: Vec<PathName> = alive
         .dirs
@@ -62,94 +535,414 @@ pub fn append_one(
         .filter(|d| **d != "/".into())
         .map(|d| d.clone())
         .collect();
+    let locality = Locality::new(fs, config.locality_bias);
+    // node of `depth` and nodes below it up to `height` levels fit under depth limit
+    let max_path_depth = fs.max_path_depth;
+    let fits =
+        |depth: usize, height: usize| max_path_depth.is_none_or(|limit| depth + height <= limit);
+    let alive_parent_dirs: Vec<PathName> = alive
+        .dirs
+        .iter()
+        .filter(|d| fits(d.segments().len() + 1, 0))
+        .map(|d| d.clone())
+        .collect();
+    let alive_file_paths: Vec<PathName> = alive.files.iter().map(|(_, p)| p.clone()).collect();
     let alive_closed_files: Vec<PathName> = alive
         .files
         .iter()
-        .filter(|(idx, _)| fs.file(idx).descriptor.is_none())
+        .filter(|(idx, _)| fs.file(idx).unwrap().descriptors.is_empty())
         .map(|(_, p)| p.clone())
         .collect();
     let alive_open_files: Vec<FileDescriptorIndex> = alive
         .files
         .iter()
-        .map(|(idx, _)| fs.file(idx).descriptor)
-        .flatten()
+        .flat_map(|(idx, _)| fs.file(idx).unwrap().descriptors.clone())
+        .collect();
+    let open_dir_streams: Vec<DirStreamIndex> = fs
+        .dir_streams
+        .iter()
+        .enumerate()
+        .filter(|(_, stream)| stream.is_open)
+        .map(|(idx, _)| DirStreamIndex(idx))
+        .collect();
+    let alive_xattrs: Vec<(PathName, String)> = alive
+        .dirs
+        .iter()
+        .chain(alive.files.iter().map(|(_, path)| path))
+        .flat_map(|path| {
+            fs.xattrs(path.clone())
+                .unwrap()
+                .keys()
+                .map(|name| (path.clone(), name.clone()))
+        })
         .collect();
-    let mut ops = weights.clone();
+    let mut ops = config.weights.clone();
     if alive_dirs_except_root.is_empty() {
         ops.weights.retain(|(op, _)| *op != OperationKind::REMOVE);
     }
     if alive.files.is_empty() {
+        ops.weights.retain(|(op, _)| *op != OperationKind::OPEN);
         ops.weights.retain(|(op, _)| *op != OperationKind::HARDLINK);
+        ops.weights.retain(|(op, _)| *op != OperationKind::TRUNCATE);
+    }
+    if alive.symlinks.is_empty() {
+        ops.weights.retain(|(op, _)| *op != OperationKind::READLINK);
     }
-    if alive_dirs_except_root.is_empty() && alive.files.is_empty() {
+    if alive_dirs_except_root.is_empty()
+        && alive.files.is_empty()
+        && alive.sockets.is_empty()
+        && alive.specials.is_empty()
+        && alive.symlinks.is_empty()
+    {
         ops.weights.retain(|(op, _)| *op != OperationKind::RENAME);
     }
     if alive_closed_files.is_empty() {
-        ops.weights.retain(|(op, _)| *op != OperationKind::OPEN);
+        ops.weights.retain(|(op, _)| *op != OperationKind::PREALLOC);
     }
     if alive_open_files.is_empty() {
         ops.weights.retain(|(op, _)| *op != OperationKind::CLOSE);
         ops.weights.retain(|(op, _)| *op != OperationKind::READ);
         ops.weights.retain(|(op, _)| *op != OperationKind::WRITE);
         ops.weights.retain(|(op, _)| *op != OperationKind::FSYNC);
+        ops.weights
+            .retain(|(op, _)| *op != OperationKind::FDATASYNC);
+        ops.weights
+            .retain(|(op, _)| *op != OperationKind::SYNCFILERANGE);
+        ops.weights.retain(|(op, _)| *op != OperationKind::LSEEK);
+        ops.weights
+            .retain(|(op, _)| *op != OperationKind::FALLOCATE);
+    }
+    if config.fallocate_modes.is_empty() {
+        ops.weights
+            .retain(|(op, _)| *op != OperationKind::FALLOCATE);
+    }
+    if open_dir_streams.is_empty() {
+        ops.weights.retain(|(op, _)| *op != OperationKind::READDIR);
+        ops.weights.retain(|(op, _)| *op != OperationKind::CLOSEDIR);
+    }
+    if alive_xattrs.is_empty() {
+        ops.weights
+            .retain(|(op, _)| *op != OperationKind::REMOVEXATTR);
+    }
+    if fs.file_handles.is_empty() {
+        ops.weights
+            .retain(|(op, _)| *op != OperationKind::OPENBYHANDLE);
+    }
+    if fs.max_nodes.is_some_and(|limit| fs.node_count() >= limit) {
+        ops.weights.retain(|(op, _)| *op != OperationKind::MKDIR);
+        ops.weights.retain(|(op, _)| *op != OperationKind::CREATE);
+        ops.weights.retain(|(op, _)| *op != OperationKind::MKSOCKET);
+        ops.weights.retain(|(op, _)| *op != OperationKind::SYMLINK);
+        ops.weights.retain(|(op, _)| *op != OperationKind::MKNOD);
+    }
+    if alive_parent_dirs.is_empty() {
+        ops.weights.retain(|(op, _)| *op != OperationKind::MKDIR);
+        ops.weights.retain(|(op, _)| *op != OperationKind::CREATE);
+        ops.weights.retain(|(op, _)| *op != OperationKind::MKSOCKET);
+        ops.weights.retain(|(op, _)| *op != OperationKind::SYMLINK);
+        ops.weights.retain(|(op, _)| *op != OperationKind::MKNOD);
+        ops.weights.retain(|(op, _)| *op != OperationKind::HARDLINK);
+    }
+    if let Some(limit) = fs.max_alive_nodes {
+        let alive_nodes = alive.node_count();
+        if alive_nodes >= limit {
+            ops.weights.retain(|(op, _)| *op != OperationKind::MKDIR);
+            ops.weights.retain(|(op, _)| *op != OperationKind::CREATE);
+            ops.weights.retain(|(op, _)| *op != OperationKind::MKSOCKET);
+            ops.weights.retain(|(op, _)| *op != OperationKind::SYMLINK);
+            ops.weights.retain(|(op, _)| *op != OperationKind::MKNOD);
+        }
+        if alive_nodes as f64 >= limit as f64 * NEAR_ALIVE_LIMIT {
+            for (op, weight) in ops.weights.iter_mut() {
+                if *op == OperationKind::REMOVE {
+                    *weight *= NEAR_ALIVE_LIMIT_REMOVE_BOOST;
+                }
+            }
+        }
+    }
+    if ops.weights.is_empty() {
+        return;
     }
+    // e.g. node limits are reached
+    let can_create = ops
+        .weights
+        .iter()
+        .any(|(op, _)| *op == OperationKind::CREATE);
     match ops.weights.choose_weighted(rng, |item| item.1).unwrap().0 {
         OperationKind::MKDIR => {
-            let path = alive.dirs.choose(rng).unwrap().to_owned();
-            fs.mkdir(path.join(gen_name()), mode.clone()).unwrap();
+            let path = choose_parent(
+                rng,
+                &alive_parent_dirs,
+                root_bias,
+                deep_path_bias,
+                &locality,
+            );
+            let name = new_name(rng, fs, &path, case_collisions, &mut gen_name);
+            fs.mkdir(path.join(name), random_mode(rng, special_mode_bits))
+                .unwrap();
         }
         OperationKind::CREATE => {
-            let path = alive.dirs.choose(rng).unwrap().to_owned();
-            fs.create(path.join(gen_name()), mode.clone()).unwrap();
+            let path = choose_parent(
+                rng,
+                &alive_parent_dirs,
+                root_bias,
+                deep_path_bias,
+                &locality,
+            );
+            let name = new_name(rng, fs, &path, case_collisions, &mut gen_name);
+            fs.create(path.join(name), random_mode(rng, special_mode_bits))
+                .unwrap();
         }
         OperationKind::REMOVE => {
-            let path = [
+            let paths = [
                 alive_dirs_except_root,
                 alive.files.iter().map(|(_, path)| path.clone()).collect(),
+                alive.sockets.clone(),
+                alive.specials.clone(),
+                alive.symlinks.clone(),
             ]
-            .concat()
-            .choose(rng)
-            .unwrap()
-            .to_owned();
+            .concat();
+            let path = choose_path(rng, &paths, root_bias, &locality);
             fs.remove(path).unwrap();
         }
+        OperationKind::MKSOCKET => {
+            let path = choose_parent(
+                rng,
+                &alive_parent_dirs,
+                root_bias,
+                deep_path_bias,
+                &locality,
+            );
+            let name = new_name(rng, fs, &path, case_collisions, &mut gen_name);
+            fs.mksocket(path.join(name)).unwrap();
+        }
+        OperationKind::SYMLINK => {
+            let path = choose_parent(
+                rng,
+                &alive_parent_dirs,
+                root_bias,
+                deep_path_bias,
+                &locality,
+            );
+            let name = new_name(rng, fs, &path, case_collisions, &mut gen_name);
+            let target = random_symlink_target(rng, &fs.alive_paths(), &mut gen_name);
+            fs.symlink(target, path.join(name)).unwrap();
+        }
+        OperationKind::READLINK => {
+            let path = choose_any(rng, &alive.symlinks, &locality);
+            fs.readlink(path).unwrap();
+        }
+        OperationKind::HARDLINK if rng.gen_bool(DIR_HARDLINK_RATE) => {
+            let dir_path = choose_path(rng, &alive.dirs, root_bias, &locality);
+            let parent_path = choose_parent(
+                rng,
+                &alive_parent_dirs,
+                root_bias,
+                deep_path_bias,
+                &locality,
+            );
+            fs.hardlink_dir(dir_path, parent_path.join(gen_name()))
+                .unwrap();
+        }
+        OperationKind::HARDLINK
+            if !alive.symlinks.is_empty() && rng.gen_bool(SYMLINK_HARDLINK_RATE) =>
+        {
+            // directory symlink leads to has to hold one more level, unless link fails
+            let links: Vec<PathName> = alive
+                .symlinks
+                .iter()
+                .filter(|link| fits(link.segments().len() + 1, 0))
+                .filter(|link| {
+                    match fs
+                        .follow_symlinks((*link).clone())
+                        .and_then(|target| fs.resolve_dir(target.clone()).map(|_| target))
+                    {
+                        Ok(target) => alive_parent_dirs.contains(&target),
+                        Err(FsError::NotFound(_) | FsError::NotADir(_)) => true,
+                        Err(_) => false,
+                    }
+                })
+                .cloned()
+                .collect();
+            let file_path = choose_any(rng, &alive_file_paths, &locality);
+            match links.choose(rng) {
+                Some(link) => {
+                    fs.hardlink_through_symlink(file_path, link.join(gen_name()))
+                        .unwrap();
+                }
+                None => {
+                    let dir_path = choose_parent(
+                        rng,
+                        &alive_parent_dirs,
+                        root_bias,
+                        deep_path_bias,
+                        &locality,
+                    );
+                    fs.hardlink(file_path, dir_path.join(gen_name())).unwrap();
+                }
+            }
+        }
         OperationKind::HARDLINK => {
-            let file_path = alive.files.choose(rng).unwrap().to_owned().1;
-            let dir_path = alive.dirs.choose(rng).unwrap().to_owned();
+            let file_path = choose_any(rng, &alive_file_paths, &locality);
+            let dir_path = choose_parent(
+                rng,
+                &alive_parent_dirs,
+                root_bias,
+                deep_path_bias,
+                &locality,
+            );
             fs.hardlink(file_path, dir_path.join(gen_name())).unwrap();
         }
         OperationKind::RENAME => {
-            let old_path = [
-                alive_dirs_except_root,
-                alive.files.iter().map(|(_, path)| path.clone()).collect(),
-            ]
-            .concat()
-            .choose(rng)
-            .unwrap()
-            .to_owned();
-            let alive_non_subdirectories: Vec<PathName> = alive
+            let paths =
+                if !alive_dirs_except_root.is_empty() && rng.gen_bool(SUBDIRECTORY_RENAME_RATE) {
+                    alive_dirs_except_root
+                } else {
+                    [
+                        alive_dirs_except_root,
+                        alive.files.iter().map(|(_, path)| path.clone()).collect(),
+                        alive.sockets.clone(),
+                        alive.specials.clone(),
+                        alive.symlinks.clone(),
+                    ]
+                    .concat()
+                };
+            let old_path = choose_path(rng, &paths, root_bias, &locality);
+            let alive_paths = fs.alive_paths();
+            let height = subtree_height(&alive_paths, &old_path);
+            let mut alive_non_subdirectories: Vec<PathName> = alive
                 .dirs
                 .iter()
-                .filter(|p| !old_path.is_prefix_of(p))
+                .filter(|p| !old_path.is_prefix_of(p) && fits(p.segments().len() + 1, height))
                 .map(|p| p.clone())
                 .collect();
-            let new_path = alive_non_subdirectories.choose(rng).unwrap().to_owned();
-            fs.rename(old_path, new_path.join(gen_name())).unwrap();
+            if alive_non_subdirectories.is_empty() {
+                // tree is already deeper than limit (e.g. input was generated without it)
+                alive_non_subdirectories.push(old_path.split().0);
+            }
+            if rng.gen_bool(BAD_PARENT_RENAME_RATE) {
+                // parent is a file (ENOTDIR) or does not exist (ENOENT)
+                let bad_parent = match alive.files.choose(rng) {
+                    Some((_, file_path)) if rng.gen_bool(0.5) => file_path.clone(),
+                    _ => choose_path(rng, &alive.dirs, root_bias, &locality).join(gen_name()),
+                };
+                fs.rename_to_bad_parent(old_path, bad_parent.join(gen_name()), vec![])
+                    .unwrap();
+            } else if let Some(other) = rng
+                .gen_bool(EXCHANGE_RENAME_RATE)
+                .then(|| {
+                    // neither can be inside the other, but they can be of different kinds
+                    paths
+                        .iter()
+                        .filter(|p| !old_path.is_prefix_of(p) && !p.is_prefix_of(&old_path))
+                        .filter(|p| {
+                            fits(p.segments().len(), height)
+                                && fits(old_path.segments().len(), subtree_height(&alive_paths, p))
+                        })
+                        .choose(rng)
+                })
+                .flatten()
+            {
+                fs.rename_with_flags(old_path, other.clone(), vec![RenameFlag::RENAME_EXCHANGE])
+                    .unwrap();
+            } else {
+                let new_path = choose_parent(
+                    rng,
+                    &alive_non_subdirectories,
+                    root_bias,
+                    deep_path_bias,
+                    &locality,
+                );
+                let noreplace = rng.gen_bool(NOREPLACE_RENAME_RATE);
+                let existing = if noreplace && rng.gen_bool(EXISTING_NOREPLACE_RENAME_RATE) {
+                    let mut names = fs.child_names(new_path.clone()).unwrap();
+                    // names are not ordered in directory
+                    names.sort();
+                    names.choose(rng).map(|name| new_path.join(name.clone()))
+                } else {
+                    None
+                };
+                if let Some(existing) = existing {
+                    fs.rename_noreplace_existing(old_path, existing).unwrap();
+                } else {
+                    let flags = if noreplace {
+                        vec![RenameFlag::RENAME_NOREPLACE]
+                    } else {
+                        vec![]
+                    };
+                    fs.rename_with_flags(old_path, new_path.join(gen_name()), flags)
+                        .unwrap();
+                }
+            }
+        }
+        OperationKind::OPEN if can_create && rng.gen_bool(CREATE_OPEN_RATE) => {
+            let path = choose_parent(
+                rng,
+                &alive_parent_dirs,
+                root_bias,
+                deep_path_bias,
+                &locality,
+            );
+            let name = new_name(rng, fs, &path, case_collisions, &mut gen_name);
+            let flags = if rng.gen_bool(EXCLUSIVE_OPEN_RATE) {
+                vec![OpenFlag::O_CREAT, OpenFlag::O_EXCL]
+            } else {
+                vec![OpenFlag::O_CREAT]
+            };
+            fs.open(path.join(name), flags).unwrap();
+        }
+        OperationKind::OPEN if rng.gen_bool(EXISTING_EXCLUSIVE_OPEN_RATE) => {
+            let paths = [
+                alive_dirs_except_root,
+                alive_file_paths,
+                alive.sockets.clone(),
+                alive.specials.clone(),
+                alive.symlinks.clone(),
+            ]
+            .concat();
+            let path = choose_any(rng, &paths, &locality);
+            let flags = if rng.gen_bool(NOFOLLOW_OPEN_RATE) {
+                vec![OpenFlag::O_CREAT, OpenFlag::O_EXCL, OpenFlag::O_NOFOLLOW]
+            } else {
+                vec![OpenFlag::O_CREAT, OpenFlag::O_EXCL]
+            };
+            fs.open_failing(path, flags).unwrap();
+        }
+        OperationKind::OPEN if !alive.symlinks.is_empty() && rng.gen_bool(NOFOLLOW_OPEN_RATE) => {
+            let path = choose_any(rng, &alive.symlinks, &locality);
+            fs.open_failing(path, vec![OpenFlag::O_NOFOLLOW]).unwrap();
         }
         OperationKind::OPEN => {
-            let path = alive_closed_files.choose(rng).unwrap().to_owned();
-            fs.open(path).unwrap();
+            // symlinks to files are followed
+            let followed: Vec<PathName> = alive
+                .symlinks
+                .iter()
+                .filter(|path| {
+                    fs.follow_symlinks((*path).clone())
+                        .and_then(|target| fs.resolve_file(target))
+                        .is_ok()
+                })
+                .cloned()
+                .collect();
+            let paths = [alive_file_paths, followed].concat();
+            let path = choose_any(rng, &paths, &locality);
+            let flags = if rng.gen_bool(APPEND_RATE) {
+                vec![OpenFlag::O_APPEND]
+            } else {
+                vec![]
+            };
+            fs.open(path, flags).unwrap();
         }
         OperationKind::CLOSE => {
-            let des = alive_open_files.choose(rng).unwrap().to_owned();
+            let des = choose_descriptor(rng, &alive_open_files, &locality);
             fs.close(des).unwrap();
         }
         OperationKind::WRITE => {
-            let des = alive_open_files.choose(rng).unwrap().to_owned();
+            let des = choose_descriptor(rng, &alive_open_files, &locality);
             fs.read(des, random_interesting_unsigned(rng)).unwrap();
         }
         OperationKind::READ => {
-            let des = alive_open_files.choose(rng).unwrap().to_owned();
+            let des = choose_descriptor(rng, &alive_open_files, &locality);
             fs.write(
                 des,
                 random_interesting_unsigned(rng),
@@ -158,9 +951,181 @@ pub fn append_one(
             .unwrap();
         }
         OperationKind::FSYNC => {
-            let des = alive_open_files.choose(rng).unwrap().to_owned();
+            let des = choose_descriptor(rng, &alive_open_files, &locality);
             fs.fsync(des).unwrap();
         }
+        OperationKind::FDATASYNC => {
+            let des = choose_descriptor(rng, &alive_open_files, &locality);
+            fs.fdatasync(des).unwrap();
+        }
+        OperationKind::SYNCFILERANGE => {
+            let des = choose_descriptor(rng, &alive_open_files, &locality);
+            let flags = [
+                SyncFileRangeFlag::SYNC_FILE_RANGE_WAIT_BEFORE,
+                SyncFileRangeFlag::SYNC_FILE_RANGE_WRITE,
+                SyncFileRangeFlag::SYNC_FILE_RANGE_WAIT_AFTER,
+            ]
+            .into_iter()
+            .filter(|_| rng.gen_bool(0.5))
+            .collect();
+            fs.sync_file_range(
+                des,
+                random_interesting_unsigned(rng),
+                random_interesting_unsigned(rng),
+                flags,
+            )
+            .unwrap();
+        }
+        OperationKind::LSEEK => {
+            let des = choose_descriptor(rng, &alive_open_files, &locality);
+            let whence = *[Whence::SEEK_SET, Whence::SEEK_CUR, Whence::SEEK_END]
+                .choose(rng)
+                .unwrap();
+            let descriptor = &fs.descriptors[des.0];
+            let base = match whence {
+                Whence::SEEK_SET => 0,
+                Whence::SEEK_CUR => descriptor.offset,
+                Whence::SEEK_END => fs.files[descriptor.file.0].content.size(),
+            };
+            // backwards, but never before the start of file
+            let offset = if base > 0 && rng.gen_bool(0.5) {
+                -(rng.gen_range(1..=base) as i64)
+            } else {
+                random_interesting_unsigned(rng) as i64
+            };
+            fs.lseek(des, offset, whence).unwrap();
+        }
+        OperationKind::OPENDIR => {
+            let path = choose_path(rng, &alive.dirs, root_bias, &locality);
+            fs.opendir(path).unwrap();
+        }
+        OperationKind::READDIR => {
+            let stream = open_dir_streams.choose(rng).unwrap().to_owned();
+            fs.readdir(stream, rng.gen_range(1..=4)).unwrap();
+        }
+        OperationKind::CLOSEDIR => {
+            let stream = open_dir_streams.choose(rng).unwrap().to_owned();
+            fs.closedir(stream).unwrap();
+        }
+        OperationKind::LISTDIR => {
+            let path = choose_path(rng, &alive.dirs, root_bias, &locality);
+            fs.listdir(path).unwrap();
+        }
+        OperationKind::NAMETOHANDLE => {
+            let paths = [
+                alive.dirs.clone(),
+                alive.files.iter().map(|(_, path)| path.clone()).collect(),
+                alive.sockets.clone(),
+                alive.specials.clone(),
+            ]
+            .concat();
+            let path = choose_path(rng, &paths, root_bias, &locality);
+            fs.name_to_handle(path).unwrap();
+        }
+        OperationKind::OPENBYHANDLE => {
+            let handle = FileHandleIndex(rng.gen_range(0..fs.file_handles.len()));
+            fs.open_by_handle(handle).unwrap();
+        }
+        OperationKind::PREALLOC => {
+            let path = choose_any(rng, &alive_closed_files, &locality);
+            fs.prealloc(path, *PREALLOC_SIZES.choose(rng).unwrap())
+                .unwrap();
+        }
+        OperationKind::CHMOD => {
+            let paths: Vec<PathName> = alive
+                .dirs
+                .iter()
+                .chain(alive.files.iter().map(|(_, path)| path))
+                .chain(alive.specials.iter())
+                .cloned()
+                .collect();
+            let path = choose_path(rng, &paths, root_bias, &locality);
+            // permission bits are always random, otherwise chmod would not change anything
+            let mode = random_mode(rng, Some(special_mode_bits.unwrap_or(0.0)));
+            fs.chmod(path, mode).unwrap();
+        }
+        OperationKind::TRUNCATE => {
+            let path = choose_any(rng, &alive_file_paths, &locality);
+            fs.truncate(path, random_interesting_unsigned(rng)).unwrap();
+        }
+        OperationKind::SETXATTR => {
+            let paths: Vec<PathName> = alive
+                .dirs
+                .iter()
+                .chain(alive.files.iter().map(|(_, path)| path))
+                .cloned()
+                .collect();
+            let path = choose_path(rng, &paths, root_bias, &locality);
+            // existing attribute can always be replaced, so some name fits
+            let names: Vec<(&str, Vec<u64>)> = XATTR_NAMES
+                .iter()
+                .map(|name| (*name, xattr_sizes(fs, &path, name)))
+                .filter(|(_, sizes)| !sizes.is_empty())
+                .collect();
+            let (name, sizes) = names.choose(rng).unwrap();
+            fs.setxattr(path, name.to_string(), *sizes.choose(rng).unwrap())
+                .unwrap();
+        }
+        OperationKind::REMOVEXATTR => {
+            let (path, name) = alive_xattrs.choose(rng).unwrap().to_owned();
+            fs.removexattr(path, name).unwrap();
+        }
+        OperationKind::MKNOD => {
+            let path = choose_parent(
+                rng,
+                &alive_parent_dirs,
+                root_bias,
+                deep_path_bias,
+                &locality,
+            );
+            let name = new_name(rng, fs, &path, case_collisions, &mut gen_name);
+            let kind = *[
+                SpecialKind::S_IFIFO,
+                SpecialKind::S_IFCHR,
+                SpecialKind::S_IFBLK,
+            ]
+            .choose(rng)
+            .unwrap();
+            fs.mknod(path.join(name), kind, random_mode(rng, special_mode_bits))
+                .unwrap();
+        }
+        OperationKind::UTIMENS => {
+            let paths = [
+                alive.dirs.clone(),
+                alive.files.iter().map(|(_, path)| path.clone()).collect(),
+                alive.sockets.clone(),
+                alive.specials.clone(),
+                alive.symlinks.clone(),
+            ]
+            .concat();
+            let path = choose_path(rng, &paths, root_bias, &locality);
+            let atime = *INTERESTING_TIMES.choose(rng).unwrap();
+            let mtime = *INTERESTING_TIMES.choose(rng).unwrap();
+            fs.utimens(path, atime, mtime).unwrap();
+        }
+        OperationKind::STAT => {
+            let paths = [
+                alive.dirs.clone(),
+                alive.files.iter().map(|(_, path)| path.clone()).collect(),
+                alive.sockets.clone(),
+                alive.specials.clone(),
+                alive.symlinks.clone(),
+            ]
+            .concat();
+            let path = choose_path(rng, &paths, root_bias, &locality);
+            fs.stat(path).unwrap();
+        }
+        OperationKind::FALLOCATE => {
+            let des = choose_descriptor(rng, &alive_open_files, &locality);
+            let mode = config.fallocate_modes.choose(rng).unwrap().clone();
+            // empty range is rejected
+            let len = random_interesting_unsigned(rng).max(1);
+            fs.fallocate(des, random_interesting_unsigned(rng), len, mode)
+                .unwrap();
+        }
+        OperationKind::CRASHPOINT => {
+            fs.crashpoint().unwrap();
+        }
     }
 }
 
@@ -168,13 +1133,643 @@ pub fn append_one(
 mod tests {
     use rand::{rngs::StdRng, SeedableRng};
 
+    use crate::abstract_fs::operation::Operation;
+
     use super::*;
 
+    #[test]
+    fn test_generate_rename_loops_replay() {
+        for i in 0..100 {
+            let mut rng = StdRng::seed_from_u64(i);
+            let w = generate_rename_loops(&mut rng, 5, 20);
+            assert!(w
+                .ops
+                .iter()
+                .any(|op| matches!(op, Operation::RENAME { .. })));
+            let mut fs = AbstractFS::new();
+            fs.replay(&w).unwrap();
+            assert_eq!(w, fs.recording);
+        }
+    }
+
+    #[test]
+    fn test_rename_loop_rejected() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/0".into(), vec![]).unwrap();
+        fs.mkdir("/1".into(), vec![]).unwrap();
+        fs.mkdir("/2".into(), vec![]).unwrap();
+        fs.rename("/0".into(), "/1/3".into()).unwrap();
+        fs.rename("/1".into(), "/2/4".into()).unwrap();
+        assert_eq!(
+            Err(FsError::RenameToSubdirectoryError(
+                "/2".into(),
+                "/2/4/3/5".into()
+            )),
+            fs.rename("/2".into(), "/2/4/3/5".into())
+        );
+        fs.rename("/2/4/3".into(), "/5".into()).unwrap();
+        let dirs: Vec<PathName> = vec!["/".into(), "/2".into(), "/2/4".into(), "/5".into()];
+        assert_eq!(dirs, fs.alive().dirs);
+    }
+
+    #[test]
+    fn test_generate_new_node_limit() {
+        for i in 0..100 {
+            let mut rng = StdRng::seed_from_u64(i);
+            let w = generate_new(
+                &mut rng,
+                1000,
+                &GeneratorConfig {
+                    max_nodes: Some(10),
+                    ..Default::default()
+                },
+            );
+            let mut fs = AbstractFS::new();
+            fs.replay(&w).unwrap();
+            assert!(fs.node_count() <= 10);
+        }
+    }
+
+    #[test]
+    fn test_generate_new_alive_node_limit() {
+        let mut reached_limit = 0;
+        for i in 0..100 {
+            let mut rng = StdRng::seed_from_u64(i);
+            let w = generate_new(
+                &mut rng,
+                300,
+                &GeneratorConfig {
+                    max_alive_nodes: Some(8),
+                    ..Default::default()
+                },
+            );
+            let mut fs = AbstractFS::new();
+            for op in w.ops {
+                fs.replay(&Workload {
+                    ops: vec![op],
+                    seed: 0,
+                })
+                .unwrap();
+                let alive_nodes = fs.alive().node_count();
+                assert!(alive_nodes <= 8);
+                if alive_nodes == 8 {
+                    reached_limit += 1;
+                }
+            }
+        }
+        assert!(reached_limit > 0);
+    }
+
+    #[test]
+    fn test_generate_case_collisions() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut generate = |case_collisions| {
+            generate_new(
+                &mut rng,
+                1000,
+                &GeneratorConfig {
+                    weights: OperationWeights::new(vec![
+                        (OperationKind::MKDIR, 100),
+                        (OperationKind::MKSOCKET, 100),
+                    ]),
+                    root_bias: 0.5,
+                    case_collisions,
+                    ..Default::default()
+                },
+            )
+        };
+        let collide = |w: &Workload| {
+            let names: Vec<Name> = w
+                .ops
+                .iter()
+                .filter_map(|op| match op {
+                    Operation::MKDIR { path, .. } | Operation::MKSOCKET { path } => {
+                        Some(path.split().1)
+                    }
+                    _ => None,
+                })
+                .collect();
+            names
+                .iter()
+                .any(|a| names.iter().any(|b| a != b && a.eq_ignore_ascii_case(b)))
+        };
+        let w = generate(true);
+        assert!(collide(&w));
+        let mut fs = AbstractFS::new();
+        fs.replay(&w).unwrap();
+        assert!(!collide(&generate(false)));
+    }
+
+    #[test]
+    fn test_generate_symlinks() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let w = generate_new(
+            &mut rng,
+            200,
+            &GeneratorConfig {
+                weights: OperationWeights::new(vec![
+                    (OperationKind::MKDIR, 100),
+                    (OperationKind::SYMLINK, 100),
+                    (OperationKind::READLINK, 100),
+                    (OperationKind::REMOVE, 50),
+                ]),
+                ..Default::default()
+            },
+        );
+        let targets: Vec<&String> = w
+            .ops
+            .iter()
+            .filter_map(|op| match op {
+                Operation::SYMLINK { target, .. } => Some(target),
+                _ => None,
+            })
+            .collect();
+        assert!(targets.iter().any(|t| t.ends_with('/')));
+        assert!(targets.iter().any(|t| !t.starts_with('/')));
+        assert!(w
+            .ops
+            .iter()
+            .any(|op| matches!(op, Operation::READLINK { .. })));
+        let mut fs = AbstractFS::new();
+        fs.replay(&w).unwrap();
+    }
+
+    #[test]
+    fn test_generate_nofollow_opens() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let w = generate_new(
+            &mut rng,
+            1000,
+            &GeneratorConfig {
+                weights: OperationWeights::new(vec![
+                    (OperationKind::CREATE, 100),
+                    (OperationKind::SYMLINK, 100),
+                    (OperationKind::OPEN, 100),
+                    (OperationKind::CLOSE, 100),
+                ]),
+                ..Default::default()
+            },
+        );
+        let mut fs = AbstractFS::new();
+        fs.replay(&w).unwrap();
+        assert!(!fs.expected_failures.is_empty());
+        assert!(fs.expected_failures.iter().any(|index| matches!(
+            &w.ops[*index as usize],
+            Operation::OPEN { flags, .. } if *flags == vec![OpenFlag::O_NOFOLLOW]
+        )));
+        // without flag symlinks to files are opened too
+        let symlinks: Vec<&PathName> = w
+            .ops
+            .iter()
+            .filter_map(|op| match op {
+                Operation::SYMLINK { path, .. } => Some(path),
+                _ => None,
+            })
+            .collect();
+        assert!(w.ops.iter().enumerate().any(|(index, op)| matches!(
+            op,
+            Operation::OPEN { path, .. }
+                if symlinks.contains(&path) && !fs.expected_failures.contains(&(index as u32))
+        )));
+    }
+
+    #[test]
+    fn test_generate_hardlinks_through_symlinks() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let config = GeneratorConfig {
+            weights: OperationWeights::new(vec![(OperationKind::HARDLINK, 100)]),
+            ..Default::default()
+        };
+        let mut fs = config.model();
+        fs.create("/foo".into(), vec![]).unwrap();
+        fs.mkdir("/bar".into(), vec![]).unwrap();
+        fs.symlink("bar".into(), "/dir".into()).unwrap();
+        fs.symlink("baz".into(), "/dangling".into()).unwrap();
+        let mut name_idx: usize = 0;
+        for _ in 0..200 {
+            append_one(&mut rng, &mut fs, &config, || {
+                name_idx += 1;
+                format!("link{}", name_idx)
+            });
+        }
+        let through = |link: &str, failing: bool| {
+            fs.recording.ops.iter().enumerate().any(|(index, op)| {
+                matches!(op, Operation::HARDLINK { new_path, .. } if new_path.split().0 == link.into())
+                    && fs.expected_failures.contains(&(index as u32)) == failing
+            })
+        };
+        assert!(through("/dir", false));
+        assert!(through("/dangling", true));
+        AbstractFS::new().replay(&fs.recording).unwrap();
+    }
+
+    #[test]
+    fn test_generate_noreplace_renames_of_existing() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let w = generate_new(
+            &mut rng,
+            1000,
+            &GeneratorConfig {
+                weights: OperationWeights::new(vec![
+                    (OperationKind::CREATE, 100),
+                    (OperationKind::MKDIR, 100),
+                    (OperationKind::RENAME, 100),
+                ]),
+                ..Default::default()
+            },
+        );
+        let mut fs = AbstractFS::new();
+        fs.replay(&w).unwrap();
+        assert!(fs.expected_failures.iter().any(|index| matches!(
+            &w.ops[*index as usize],
+            Operation::RENAME { flags, .. } if *flags == vec![RenameFlag::RENAME_NOREPLACE]
+        )));
+    }
+
+    #[test]
+    fn test_generate_exclusive_opens_of_existing() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let w = generate_new(
+            &mut rng,
+            500,
+            &GeneratorConfig {
+                weights: OperationWeights::new(vec![
+                    (OperationKind::CREATE, 100),
+                    (OperationKind::MKDIR, 100),
+                    (OperationKind::SYMLINK, 100),
+                    (OperationKind::OPEN, 100),
+                    (OperationKind::CLOSE, 100),
+                ]),
+                ..Default::default()
+            },
+        );
+        // replay checks that every failing open targets existing node
+        let mut fs = AbstractFS::new();
+        fs.replay(&w).unwrap();
+        assert!(fs.expected_failures.iter().any(|index| matches!(
+            &w.ops[*index as usize],
+            Operation::OPEN { flags, .. } if flags.contains(&OpenFlag::O_EXCL)
+        )));
+    }
+
     #[test]
     fn smoke_test_generate_new() {
         for i in 0..100 {
             let mut rng = StdRng::seed_from_u64(i);
-            generate_new(&mut rng, 1000, &OperationWeights::uniform());
+            generate_new(&mut rng, 1000, &GeneratorConfig::default());
+        }
+    }
+
+    #[test]
+    fn test_random_mode_fixed() {
+        let mut rng = StdRng::seed_from_u64(123);
+        for _ in 0..100 {
+            assert_eq!(
+                vec![
+                    ModeFlag::S_IRWXU,
+                    ModeFlag::S_IRWXG,
+                    ModeFlag::S_IROTH,
+                    ModeFlag::S_IXOTH,
+                ],
+                random_mode(&mut rng, None)
+            );
+        }
+    }
+
+    #[test]
+    fn test_random_mode_special_bits_rate() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let n = 10000;
+        let modes: Vec<Mode> = (0..n).map(|_| random_mode(&mut rng, Some(0.2))).collect();
+        for bit in SPECIAL_BITS.iter().chain(PERMISSION_BITS) {
+            let rate = modes.iter().filter(|m| m.contains(bit)).count() as f64 / n as f64;
+            let expected = if SPECIAL_BITS.contains(bit) { 0.2 } else { 0.5 };
+            assert!((rate - expected).abs() < 0.03, "{}: {}", bit, rate);
+        }
+        assert!(random_mode(&mut rng, Some(0.0))
+            .iter()
+            .all(|bit| !SPECIAL_BITS.contains(bit)));
+    }
+
+    #[test]
+    fn test_special_mode_bits_round_trip() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let w = generate_new(
+            &mut rng,
+            100,
+            &GeneratorConfig {
+                weights: OperationWeights::new(vec![
+                    (OperationKind::MKDIR, 100),
+                    (OperationKind::CREATE, 100),
+                ]),
+                special_mode_bits: Some(1.0),
+                ..Default::default()
+            },
+        );
+        let mut fs = AbstractFS::new();
+        fs.replay(&w).unwrap();
+        assert_eq!(w, fs.recording);
+        let encoded = w.encode_c(None);
+        for bit in SPECIAL_BITS {
+            assert!(encoded.contains(&bit.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_root_bias() {
+        let weights = OperationWeights::new(vec![
+            (OperationKind::MKDIR, 100),
+            (OperationKind::CREATE, 100),
+        ]);
+        let max_depth = |w: &Workload| {
+            w.ops
+                .iter()
+                .map(|op| match op {
+                    Operation::MKDIR { path, .. } | Operation::CREATE { path, .. } => {
+                        path.segments().len()
+                    }
+                    _ => 0,
+                })
+                .max()
+                .unwrap()
+        };
+        let mut rng = StdRng::seed_from_u64(123);
+        let w = generate_new(
+            &mut rng,
+            200,
+            &GeneratorConfig {
+                weights: weights.clone(),
+                root_bias: 1.0,
+                ..Default::default()
+            },
+        );
+        assert_eq!(2, max_depth(&w));
+        let w = generate_new(
+            &mut rng,
+            200,
+            &GeneratorConfig {
+                weights: weights.clone(),
+                ..Default::default()
+            },
+        );
+        assert!(max_depth(&w) > 2);
+    }
+
+    #[test]
+    fn test_max_path_depth() {
+        for i in 0..100 {
+            let mut rng = StdRng::seed_from_u64(i);
+            let w = generate_new(
+                &mut rng,
+                100,
+                &GeneratorConfig {
+                    max_path_depth: Some(3),
+                    root_bias: 0.2,
+                    deep_path_bias: 0.5,
+                    ..Default::default()
+                },
+            );
+            let mut fs = AbstractFS::new();
+            for op in w.ops.iter() {
+                fs.replay(&Workload {
+                    ops: vec![op.clone()],
+                    seed: w.seed,
+                })
+                .unwrap();
+                for path in fs.alive_paths() {
+                    assert!(path.segments().len() <= 3, "{} after {:?}", path, op);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_max_xattr_space() {
+        let weights = OperationWeights::new(vec![
+            (OperationKind::MKDIR, 10),
+            (OperationKind::CREATE, 10),
+            (OperationKind::SETXATTR, 100),
+            (OperationKind::REMOVEXATTR, 10),
+        ]);
+        for i in 0..100 {
+            let mut rng = StdRng::seed_from_u64(i);
+            let w = generate_new(
+                &mut rng,
+                100,
+                &GeneratorConfig {
+                    weights: weights.clone(),
+                    max_xattr_space: Some(4060),
+                    ..Default::default()
+                },
+            );
+            let mut fs = AbstractFS::new();
+            fs.replay(&w).unwrap();
+            let alive = fs.alive();
+            for path in alive
+                .dirs
+                .iter()
+                .chain(alive.files.iter().map(|(_, path)| path))
+            {
+                let space: u64 = fs
+                    .xattrs(path.clone())
+                    .unwrap()
+                    .values()
+                    .map(|size| size + XATTR_ENTRY_OVERHEAD)
+                    .sum();
+                assert!(space <= 4060, "{} takes {}", path, space);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fallocate_modes() {
+        let weights = OperationWeights::new(vec![
+            (OperationKind::CREATE, 100),
+            (OperationKind::OPEN, 100),
+            (OperationKind::FALLOCATE, 100),
+        ]);
+        let punch_hole = vec![
+            FallocateFlag::FALLOC_FL_PUNCH_HOLE,
+            FallocateFlag::FALLOC_FL_KEEP_SIZE,
+        ];
+        for fallocate_modes in [vec![], vec![punch_hole.clone()]] {
+            let mut rng = StdRng::seed_from_u64(1);
+            let w = generate_new(
+                &mut rng,
+                100,
+                &GeneratorConfig {
+                    weights: weights.clone(),
+                    fallocate_modes: fallocate_modes.clone(),
+                    ..Default::default()
+                },
+            );
+            let modes: Vec<&Vec<FallocateFlag>> = w
+                .ops
+                .iter()
+                .filter_map(|op| match op {
+                    Operation::FALLOCATE { mode, .. } => Some(mode),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(fallocate_modes.is_empty(), modes.is_empty());
+            assert!(modes.iter().all(|mode| **mode == punch_hole));
+        }
+    }
+
+    #[test]
+    fn test_deep_path_bias() {
+        let weights = OperationWeights {
+            weights: vec![
+                (OperationKind::MKDIR, 100),
+                (OperationKind::CREATE, 100),
+                (OperationKind::REMOVE, 30),
+            ],
+        };
+        let average_depth = |deep_path_bias: f64| {
+            let (mut depth, mut count) = (0, 0);
+            for i in 0..100 {
+                let mut rng = StdRng::seed_from_u64(i);
+                let w = generate_new(
+                    &mut rng,
+                    50,
+                    &GeneratorConfig {
+                        weights: weights.clone(),
+                        max_path_depth: Some(10),
+                        root_bias: 0.2,
+                        deep_path_bias,
+                        ..Default::default()
+                    },
+                );
+                let mut fs = AbstractFS::new();
+                fs.replay(&w).unwrap();
+                for path in fs.alive_paths() {
+                    depth += path.segments().len();
+                    count += 1;
+                }
+            }
+            depth as f64 / count as f64
+        };
+        assert!(average_depth(0.8) > average_depth(0.0) * 1.5);
+    }
+
+    #[test]
+    fn test_root_bias_replay() {
+        for i in 0..100 {
+            let mut rng = StdRng::seed_from_u64(i);
+            let w = generate_new(
+                &mut rng,
+                100,
+                &GeneratorConfig {
+                    root_bias: 0.5,
+                    ..Default::default()
+                },
+            );
+            let mut fs = AbstractFS::new();
+            fs.replay(&w).unwrap();
+            assert_eq!(w, fs.recording);
+        }
+    }
+
+    #[test]
+    fn test_zero_locality_bias_unchanged() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        let paths = vec![PathName::from("/foo")];
+        let descriptors = vec![des];
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut expected_rng = rng.clone();
+        // without bias recent targets are never preferred and no random draw is taken,
+        // so workloads are the same as generated before locality bias was introduced
+        let locality = Locality::new(&fs, 0.0);
+        assert!(locality.paths.contains(&paths[0]));
+        assert_eq!(None, locality.choose(&mut rng, &locality.paths, &paths));
+        assert_eq!(
+            None,
+            locality.choose(&mut rng, &locality.descriptors, &descriptors)
+        );
+        assert_eq!(expected_rng.gen::<u64>(), rng.gen::<u64>());
+        // with full bias recent target is always chosen
+        let locality = Locality::new(&fs, 1.0);
+        assert_eq!(
+            Some(PathName::from("/foo")),
+            locality.choose(&mut rng, &locality.paths, &paths)
+        );
+        assert_eq!(
+            Some(des),
+            locality.choose(&mut rng, &locality.descriptors, &descriptors)
+        );
+    }
+
+    #[test]
+    fn test_locality_bias_chains() {
+        let weights = OperationWeights::new(vec![
+            (OperationKind::MKDIR, 100),
+            (OperationKind::CREATE, 100),
+            (OperationKind::OPEN, 100),
+            (OperationKind::WRITE, 100),
+        ]);
+        let target = |op: &Operation| match op {
+            Operation::MKDIR { path, .. }
+            | Operation::CREATE { path, .. }
+            | Operation::OPEN { path, .. } => Some(path.clone()),
+            _ => None,
+        };
+        // operations targeting node created or opened by the previous operation
+        let chains = |w: &Workload| {
+            w.ops
+                .windows(2)
+                .filter(|ops| match (target(&ops[0]), target(&ops[1])) {
+                    (Some(prev), Some(next)) => {
+                        next == prev || (!next.is_root() && next.split().0 == prev)
+                    }
+                    _ => false,
+                })
+                .count()
+        };
+        let (mut biased, mut unbiased) = (0, 0);
+        for i in 0..20 {
+            let mut rng = StdRng::seed_from_u64(i);
+            biased += chains(&generate_new(
+                &mut rng,
+                100,
+                &GeneratorConfig {
+                    weights: weights.clone(),
+                    root_bias: 0.2,
+                    locality_bias: 1.0,
+                    ..Default::default()
+                },
+            ));
+            let mut rng = StdRng::seed_from_u64(i);
+            unbiased += chains(&generate_new(
+                &mut rng,
+                100,
+                &GeneratorConfig {
+                    weights: weights.clone(),
+                    root_bias: 0.2,
+                    ..Default::default()
+                },
+            ));
+        }
+        assert!(biased > 2 * unbiased, "{} vs {}", biased, unbiased);
+    }
+
+    #[test]
+    fn test_locality_bias_replay() {
+        for i in 0..100 {
+            let mut rng = StdRng::seed_from_u64(i);
+            let w = generate_new(
+                &mut rng,
+                100,
+                &GeneratorConfig {
+                    root_bias: 0.2,
+                    locality_bias: 1.0,
+                    ..Default::default()
+                },
+            );
+            let mut fs = AbstractFS::new();
+            fs.replay(&w).unwrap();
+            assert_eq!(w, fs.recording);
         }
     }
 }
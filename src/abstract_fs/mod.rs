@@ -1,12 +1,16 @@
 pub mod compile;
 pub mod content;
+pub mod decode;
 pub mod encode;
 pub mod flags;
 pub mod fs;
+pub mod fstest;
 pub mod generator;
 pub mod mutator;
 pub mod node;
 pub mod operation;
 pub mod pathname;
+pub mod reproducer;
 pub mod trace;
+pub mod validate;
 pub mod workload;
@@ -1,12 +1,14 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 use super::{
     fs::AbstractFS,
-    generator::append_one,
-    operation::{Operation, OperationWeights},
+    generator::{append_one, GeneratorConfig},
+    node::{DirStreamIndex, FileDescriptorIndex, FileHandleIndex, Node},
+    operation::Operation,
+    pathname::PathName,
     workload::Workload,
 };
 
@@ -14,6 +16,8 @@ use super::{
 pub enum MutationKind {
     INSERT,
     REMOVE,
+    REORDER,
+    SPLICE,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -32,18 +36,604 @@ pub fn remove(workload: &Workload, index: usize) -> Option<Workload> {
     let mut ops = workload.ops.clone();
     ops.remove(index);
     let mut fs = AbstractFS::new();
-    if !fs.replay(&Workload { ops }).is_ok() {
+    if fs
+        .replay(&Workload {
+            ops,
+            seed: workload.seed,
+        })
+        .is_err()
+    {
+        None
+    } else {
+        Some(fs.recording)
+    }
+}
+
+/// Swaps operations at `index` and `index + 1` if they are independent.
+///
+/// Model state after reordered workload must stay the same, only real filesystems may notice the order.
+pub fn reorder(workload: &Workload, index: usize) -> Option<Workload> {
+    let (fst, snd) = (workload.ops.get(index)?, workload.ops.get(index + 1)?);
+    let mut before = AbstractFS::new();
+    before
+        .replay(&Workload {
+            ops: workload.ops[..index].to_vec(),
+            seed: workload.seed,
+        })
+        .ok()?;
+    if !independent(&before, &workload.ops[..index], fst, snd) {
+        return None;
+    }
+    let mut expected = AbstractFS::new();
+    expected.replay(workload).ok()?;
+    let mut ops = workload.ops.clone();
+    ops.swap(index, index + 1);
+    let mut fs = AbstractFS::new();
+    fs.replay(&Workload {
+        ops,
+        seed: workload.seed,
+    })
+    .ok()?;
+    let swapped = |idx: &u32| match *idx as usize {
+        i if i == index => index as u32 + 1,
+        i if i == index + 1 => index as u32,
+        _ => *idx,
+    };
+    let mut expected_failures: Vec<u32> = expected.expected_failures.iter().map(swapped).collect();
+    expected_failures.sort();
+    let mut crash_dependent: Vec<u32> = expected.crash_dependent.iter().map(swapped).collect();
+    crash_dependent.sort();
+    if model_state(&fs) != model_state(&expected)
+        || fs.expected_failures != expected_failures
+        || fs.crash_dependent != crash_dependent
+    {
         None
     } else {
         Some(fs.recording)
     }
 }
 
+/// Everything model knows about alive nodes, keyed by path so it does not depend on creation order.
+fn model_state(fs: &AbstractFS) -> Vec<(PathName, String)> {
+    fs.alive_paths()
+        .into_iter()
+        .map(|path| {
+            let state = match fs.resolve_node(path.clone()) {
+                Ok(Node::FILE(idx)) => format!("{:?}", fs.files[idx.0]),
+                Ok(Node::DIR(idx)) => {
+                    let dir = &fs.dirs[idx.0];
+                    format!("{:?} {:?} {:?}", dir.mode, dir.xattrs, dir.times)
+                }
+                Ok(Node::SOCKET(_)) => "socket".to_owned(),
+                Ok(Node::SPECIAL(idx)) => format!("{:?}", fs.specials[idx.0]),
+                Ok(Node::SYMLINK(idx)) => format!("{:?}", fs.symlinks[idx.0]),
+                Err(err) => err.to_string(),
+            };
+            (path, state)
+        })
+        .collect()
+}
+
+/// Prefix of `fst` (first `fst_len` operations) followed by suffix of `snd` (starting at `snd_start`).
+///
+/// Descriptors, directory streams and handles of suffix are renumbered to match combined workload.
+/// Operations of suffix that fail in the model (e.g. use descriptor opened before `snd_start`) are dropped,
+/// `None` if none of them is left.
+pub fn splice(
+    fst: &Workload,
+    fst_len: usize,
+    snd: &Workload,
+    snd_start: usize,
+) -> Option<Workload> {
+    let mut fs = AbstractFS::new();
+    if fs
+        .replay(&Workload {
+            ops: fst.ops.get(..fst_len)?.to_vec(),
+            seed: fst.seed,
+        })
+        .is_err()
+    {
+        return None;
+    }
+    let suffix = snd.ops.get(snd_start..)?;
+    let dropped = replay_renumbered(&mut fs, suffix);
+    (dropped < suffix.len()).then_some(fs.recording)
+}
+
+/// Workload without operations that fail in the model, along with number of dropped operations.
+///
+/// Operations are replayed one by one and dropped on error, indices are renumbered the same way as in [`splice`],
+/// so operations that use descriptor of dropped `OPEN` are dropped too.
+pub fn repair(workload: &Workload) -> (Workload, usize) {
+    let mut fs = AbstractFS::new();
+    fs.recording.seed = workload.seed;
+    let dropped = replay_renumbered(&mut fs, &workload.ops);
+    (fs.recording, dropped)
+}
+
+/// Replays operations that succeed in the model, returns number of operations that were skipped.
+fn replay_renumbered(fs: &mut AbstractFS, ops: &[Operation]) -> usize {
+    let mut descriptors = HashMap::new();
+    let mut dir_streams = HashMap::new();
+    let mut file_handles = HashMap::new();
+    let mut dropped = 0;
+    for op in ops {
+        let Some(mapped) = map_indices(op, &descriptors, &dir_streams, &file_handles) else {
+            dropped += 1;
+            continue;
+        };
+        let opened = fs.descriptors.len();
+        if fs
+            .replay(&Workload {
+                ops: vec![mapped],
+                seed: fs.recording.seed,
+            })
+            .is_err()
+        {
+            dropped += 1;
+            continue;
+        }
+        match op {
+            // open that must fail makes no descriptor
+            Operation::OPEN { des, .. } if fs.descriptors.len() > opened => {
+                descriptors.insert(*des, FileDescriptorIndex(opened));
+            }
+            Operation::OPENDIR { stream, .. } => {
+                dir_streams.insert(*stream, DirStreamIndex(fs.dir_streams.len() - 1));
+            }
+            Operation::NAMETOHANDLE { handle, .. } => {
+                file_handles.insert(*handle, FileHandleIndex(fs.file_handles.len() - 1));
+            }
+            _ => {}
+        }
+    }
+    dropped
+}
+
+/// Variants of workload with simpler arguments of operation at `index`.
+///
+/// Sizes are halved, mode and open flags lose one flag, created nodes are moved one directory up
+/// (along with every later path under them). Only variants valid for the model are returned.
+pub fn shrink(workload: &Workload, index: usize) -> Vec<Workload> {
+    let Some(op) = workload.ops.get(index) else {
+        return vec![];
+    };
+    let halve = |size: &u64| (*size > 0).then(|| *size / 2);
+    let mut ops: Vec<Operation> = vec![];
+    match op {
+        Operation::WRITE {
+            des,
+            src_offset,
+            size,
+        } => {
+            ops.extend(halve(size).map(|size| Operation::WRITE {
+                des: *des,
+                src_offset: *src_offset,
+                size,
+            }));
+            if *src_offset > 0 {
+                ops.push(Operation::WRITE {
+                    des: *des,
+                    src_offset: 0,
+                    size: *size,
+                });
+            }
+        }
+        Operation::READ { des, size } => {
+            ops.extend(halve(size).map(|size| Operation::READ { des: *des, size }));
+        }
+        Operation::READDIR { stream, count } => {
+            ops.extend(halve(count).map(|count| Operation::READDIR {
+                stream: *stream,
+                count,
+            }));
+        }
+        Operation::PREALLOC { path, size } => {
+            ops.extend(halve(size).map(|size| Operation::PREALLOC {
+                path: path.clone(),
+                size,
+            }));
+        }
+        Operation::TRUNCATE { path, size } => {
+            ops.extend(halve(size).map(|size| Operation::TRUNCATE {
+                path: path.clone(),
+                size,
+            }));
+        }
+        Operation::SETXATTR {
+            path,
+            name,
+            value_size,
+        } => {
+            ops.extend(halve(value_size).map(|value_size| Operation::SETXATTR {
+                path: path.clone(),
+                name: name.clone(),
+                value_size,
+            }));
+        }
+        Operation::MKDIR { path, mode } => {
+            ops.extend(without_each(mode).into_iter().map(|mode| Operation::MKDIR {
+                path: path.clone(),
+                mode,
+            }));
+        }
+        Operation::CREATE { path, mode } => {
+            ops.extend(
+                without_each(mode)
+                    .into_iter()
+                    .map(|mode| Operation::CREATE {
+                        path: path.clone(),
+                        mode,
+                    }),
+            );
+        }
+        Operation::CHMOD { path, mode } => {
+            ops.extend(without_each(mode).into_iter().map(|mode| Operation::CHMOD {
+                path: path.clone(),
+                mode,
+            }));
+        }
+        Operation::MKNOD { path, kind, mode } => {
+            ops.extend(without_each(mode).into_iter().map(|mode| Operation::MKNOD {
+                path: path.clone(),
+                kind: *kind,
+                mode,
+            }));
+        }
+        Operation::OPEN { path, flags, des } => {
+            ops.extend(
+                without_each(flags)
+                    .into_iter()
+                    .map(|flags| Operation::OPEN {
+                        path: path.clone(),
+                        flags,
+                        des: *des,
+                    }),
+            );
+        }
+        _ => {}
+    }
+    let mut candidates: Vec<Workload> = ops
+        .into_iter()
+        .map(|op| {
+            let mut ops = workload.ops.clone();
+            ops[index] = op;
+            Workload {
+                ops,
+                seed: workload.seed,
+            }
+        })
+        .collect();
+    match op {
+        Operation::MKDIR { path, .. }
+        | Operation::CREATE { path, .. }
+        | Operation::MKSOCKET { path }
+        | Operation::SYMLINK { path, .. }
+        | Operation::MKNOD { path, .. }
+            if path.segments().len() > 1 =>
+        {
+            let (parent, name) = path.split();
+            let moved = parent.split().0.join(name);
+            let mut ops = workload.ops.clone();
+            for op in ops[index..].iter_mut() {
+                *op = map_paths(op, |p| p.replace_prefix(path, &moved).unwrap_or(p.clone()));
+            }
+            candidates.push(Workload {
+                ops,
+                seed: workload.seed,
+            });
+        }
+        _ => {}
+    }
+    candidates
+        .into_iter()
+        .filter(|candidate| AbstractFS::new().replay(candidate).is_ok())
+        .collect()
+}
+
+/// Every way to drop one flag.
+fn without_each<T: Clone>(flags: &[T]) -> Vec<Vec<T>> {
+    (0..flags.len())
+        .map(|i| {
+            let mut flags = flags.to_vec();
+            flags.remove(i);
+            flags
+        })
+        .collect()
+}
+
+fn map_paths(op: &Operation, f: impl Fn(&PathName) -> PathName) -> Operation {
+    let mut op = op.clone();
+    match &mut op {
+        Operation::MKDIR { path, .. }
+        | Operation::CREATE { path, .. }
+        | Operation::REMOVE { path }
+        | Operation::MKSOCKET { path }
+        | Operation::SYMLINK { path, .. }
+        | Operation::READLINK { path }
+        | Operation::OPEN { path, .. }
+        | Operation::OPENDIR { path, .. }
+        | Operation::LISTDIR { path }
+        | Operation::NAMETOHANDLE { path, .. }
+        | Operation::PREALLOC { path, .. }
+        | Operation::TRUNCATE { path, .. }
+        | Operation::CHMOD { path, .. }
+        | Operation::MKNOD { path, .. }
+        | Operation::SETXATTR { path, .. }
+        | Operation::REMOVEXATTR { path, .. }
+        | Operation::UTIMENS { path, .. }
+        | Operation::STAT { path } => *path = f(path),
+        Operation::HARDLINK { old_path, new_path }
+        | Operation::RENAME {
+            old_path, new_path, ..
+        } => {
+            *old_path = f(old_path);
+            *new_path = f(new_path);
+        }
+        Operation::CLOSE { .. }
+        | Operation::READ { .. }
+        | Operation::WRITE { .. }
+        | Operation::FSYNC { .. }
+        | Operation::FDATASYNC { .. }
+        | Operation::SYNCFILERANGE { .. }
+        | Operation::LSEEK { .. }
+        | Operation::FALLOCATE { .. }
+        | Operation::READDIR { .. }
+        | Operation::CLOSEDIR { .. }
+        | Operation::OPENBYHANDLE { .. }
+        | Operation::CRASHPOINT => {}
+    }
+    op
+}
+
+/// Operation with indices replaced according to maps, `None` if any of them is missing.
+///
+/// Operations that create index (e.g. `OPEN`) are returned as is, model assigns index on replay.
+fn map_indices(
+    op: &Operation,
+    descriptors: &HashMap<FileDescriptorIndex, FileDescriptorIndex>,
+    dir_streams: &HashMap<DirStreamIndex, DirStreamIndex>,
+    file_handles: &HashMap<FileHandleIndex, FileHandleIndex>,
+) -> Option<Operation> {
+    let mut op = op.clone();
+    match &mut op {
+        Operation::CLOSE { des }
+        | Operation::READ { des, .. }
+        | Operation::WRITE { des, .. }
+        | Operation::FSYNC { des }
+        | Operation::FDATASYNC { des }
+        | Operation::SYNCFILERANGE { des, .. }
+        | Operation::LSEEK { des, .. }
+        | Operation::FALLOCATE { des, .. } => *des = *descriptors.get(des)?,
+        Operation::READDIR { stream, .. } | Operation::CLOSEDIR { stream } => {
+            *stream = *dir_streams.get(stream)?
+        }
+        Operation::OPENBYHANDLE { handle } => *handle = *file_handles.get(handle)?,
+        Operation::MKDIR { .. }
+        | Operation::CREATE { .. }
+        | Operation::REMOVE { .. }
+        | Operation::MKSOCKET { .. }
+        | Operation::SYMLINK { .. }
+        | Operation::READLINK { .. }
+        | Operation::HARDLINK { .. }
+        | Operation::RENAME { .. }
+        | Operation::OPEN { .. }
+        | Operation::OPENDIR { .. }
+        | Operation::LISTDIR { .. }
+        | Operation::NAMETOHANDLE { .. }
+        | Operation::PREALLOC { .. }
+        | Operation::TRUNCATE { .. }
+        | Operation::CHMOD { .. }
+        | Operation::MKNOD { .. }
+        | Operation::SETXATTR { .. }
+        | Operation::REMOVEXATTR { .. }
+        | Operation::UTIMENS { .. }
+        | Operation::STAT { .. }
+        | Operation::CRASHPOINT => {}
+    }
+    Some(op)
+}
+
+#[derive(Debug, PartialEq)]
+enum Resource {
+    Path(PathName),
+    /// Hardlinked file is reachable by any of its names, so paths alone do not tell if nodes are shared.
+    Node(Node),
+    Descriptor(FileDescriptorIndex),
+    DirStream(DirStreamIndex),
+    FileHandle(FileHandleIndex),
+}
+
+impl Resource {
+    fn overlaps(&self, other: &Resource) -> bool {
+        match (self, other) {
+            (Resource::Path(a), Resource::Path(b)) => a.is_prefix_of(b) || b.is_prefix_of(a),
+            (a, b) => a == b,
+        }
+    }
+}
+
+/// Paths, nodes and descriptors operation depends on, descriptors also touch the path they were opened with.
+///
+/// Nodes are resolved in `fs`, the model state before operation.
+fn touched(fs: &AbstractFS, before: &[Operation], op: &Operation) -> Vec<Resource> {
+    let mut resources = touched_paths(before, op);
+    let mut nodes = vec![];
+    for resource in resources.iter() {
+        match resource {
+            Resource::Path(path) => {
+                if let Ok(node) = fs.resolve_node(path.clone()) {
+                    subtree_nodes(fs, node, &mut nodes);
+                }
+            }
+            Resource::Descriptor(des) => {
+                if let Some(des) = fs.descriptors.get(des.0) {
+                    nodes.push(Node::FILE(des.file));
+                }
+            }
+            Resource::DirStream(stream) => {
+                if let Some(stream) = fs.dir_streams.get(stream.0) {
+                    nodes.push(Node::DIR(stream.dir));
+                }
+            }
+            Resource::FileHandle(handle) => {
+                if let Some(handle) = fs.file_handles.get(handle.0) {
+                    subtree_nodes(fs, handle.node.clone(), &mut nodes);
+                }
+            }
+            Resource::Node(_) => {}
+        }
+    }
+    resources.extend(nodes.into_iter().map(Resource::Node));
+    resources
+}
+
+/// Node itself and every node below it, removing or renaming directory affects all of them.
+fn subtree_nodes(fs: &AbstractFS, node: Node, nodes: &mut Vec<Node>) {
+    if let Node::DIR(idx) = &node {
+        if let Some(dir) = fs.dirs.get(idx.0) {
+            for child in dir.children.values() {
+                subtree_nodes(fs, child.clone(), nodes);
+            }
+        }
+    }
+    nodes.push(node);
+}
+
+fn touched_paths(before: &[Operation], op: &Operation) -> Vec<Resource> {
+    let opened_with = |des: &FileDescriptorIndex| {
+        before.iter().rev().find_map(|op| match op {
+            Operation::OPEN {
+                path, des: opened, ..
+            } if opened == des => Some(Resource::Path(path.clone())),
+            _ => None,
+        })
+    };
+    let opened_dir_with = |stream: &DirStreamIndex| {
+        before.iter().rev().find_map(|op| match op {
+            Operation::OPENDIR {
+                path,
+                stream: opened,
+            } if opened == stream => Some(Resource::Path(path.clone())),
+            _ => None,
+        })
+    };
+    let obtained_with = |handle: &FileHandleIndex| {
+        before.iter().rev().find_map(|op| match op {
+            Operation::NAMETOHANDLE {
+                path,
+                handle: obtained,
+            } if obtained == handle => Some(Resource::Path(path.clone())),
+            _ => None,
+        })
+    };
+    match op {
+        Operation::MKDIR { path, mode: _ }
+        | Operation::CREATE { path, mode: _ }
+        | Operation::REMOVE { path }
+        | Operation::MKSOCKET { path }
+        | Operation::SYMLINK { path, .. }
+        | Operation::READLINK { path }
+        | Operation::LISTDIR { path }
+        | Operation::PREALLOC { path, size: _ }
+        | Operation::TRUNCATE { path, size: _ }
+        | Operation::CHMOD { path, mode: _ }
+        | Operation::MKNOD { path, .. }
+        | Operation::SETXATTR { path, .. }
+        | Operation::REMOVEXATTR { path, .. }
+        | Operation::UTIMENS { path, .. }
+        | Operation::STAT { path } => vec![Resource::Path(path.clone())],
+        Operation::HARDLINK { old_path, new_path }
+        | Operation::RENAME {
+            old_path, new_path, ..
+        } => {
+            vec![
+                Resource::Path(old_path.clone()),
+                Resource::Path(new_path.clone()),
+            ]
+        }
+        Operation::OPEN { path, des, .. } => vec![
+            Resource::Path(path.clone()),
+            Resource::Descriptor(des.clone()),
+        ],
+        Operation::CLOSE { des }
+        | Operation::READ { des, size: _ }
+        | Operation::WRITE {
+            des,
+            src_offset: _,
+            size: _,
+        }
+        | Operation::FSYNC { des }
+        | Operation::FDATASYNC { des }
+        | Operation::SYNCFILERANGE { des, .. }
+        | Operation::LSEEK { des, .. }
+        | Operation::FALLOCATE { des, .. } => {
+            [Some(Resource::Descriptor(des.clone())), opened_with(des)]
+                .into_iter()
+                .flatten()
+                .collect()
+        }
+        Operation::OPENDIR { path, stream } => vec![
+            Resource::Path(path.clone()),
+            Resource::DirStream(stream.clone()),
+        ],
+        Operation::READDIR { stream, count: _ } | Operation::CLOSEDIR { stream } => [
+            Some(Resource::DirStream(stream.clone())),
+            opened_dir_with(stream),
+        ]
+        .into_iter()
+        .flatten()
+        .collect(),
+        Operation::NAMETOHANDLE { path, handle } => vec![
+            Resource::Path(path.clone()),
+            Resource::FileHandle(handle.clone()),
+        ],
+        Operation::OPENBYHANDLE { handle } => [
+            Some(Resource::FileHandle(handle.clone())),
+            obtained_with(handle),
+        ]
+        .into_iter()
+        .flatten()
+        .collect(),
+        // closes every descriptor and stream, so nothing can be moved across it
+        Operation::CRASHPOINT => vec![Resource::Path("/".into())],
+    }
+}
+
+fn independent(fs: &AbstractFS, before: &[Operation], fst: &Operation, snd: &Operation) -> bool {
+    // descriptors are allocated in order, swapping two opens would swap their indices
+    match (fst, snd) {
+        (Operation::OPEN { .. }, Operation::OPEN { .. })
+        | (Operation::OPENDIR { .. }, Operation::OPENDIR { .. })
+        | (Operation::NAMETOHANDLE { .. }, Operation::NAMETOHANDLE { .. }) => return false,
+        _ => {}
+    }
+    // handle goes stale when node is removed under any of its names
+    match (fst, snd) {
+        (Operation::REMOVE { .. }, Operation::OPENBYHANDLE { .. })
+        | (Operation::OPENBYHANDLE { .. }, Operation::REMOVE { .. }) => return false,
+        _ => {}
+    }
+    // renamed directory moves every descriptor opened under it
+    match (fst, snd) {
+        (Operation::RENAME { .. }, op) | (op, Operation::RENAME { .. })
+            if touched(fs, before, op)
+                .iter()
+                .any(|r| !matches!(r, Resource::Path(_))) =>
+        {
+            return false
+        }
+        _ => {}
+    }
+    let (fst, snd) = (touched(fs, before, fst), touched(fs, before, snd));
+    !fst.iter().any(|a| snd.iter().any(|b| a.overlaps(b)))
+}
+
 pub fn insert(
     rng: &mut impl Rng,
     workload: &Workload,
     index: usize,
-    weights: &OperationWeights,
+    config: &GeneratorConfig,
 ) -> Option<Workload> {
     let mut used_names = HashSet::new();
     for op in workload.ops.iter() {
@@ -59,6 +649,13 @@ pub fn insert(
                 }
             }
             Operation::REMOVE { path: _ } => {}
+            Operation::MKSOCKET { path }
+            | Operation::SYMLINK { path, .. }
+            | Operation::READLINK { path } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
             Operation::HARDLINK { old_path, new_path } => {
                 for segment in old_path.segments() {
                     used_names.insert(segment);
@@ -67,7 +664,9 @@ pub fn insert(
                     used_names.insert(segment);
                 }
             }
-            Operation::RENAME { old_path, new_path } => {
+            Operation::RENAME {
+                old_path, new_path, ..
+            } => {
                 for segment in old_path.segments() {
                     used_names.insert(segment);
                 }
@@ -75,7 +674,7 @@ pub fn insert(
                     used_names.insert(segment);
                 }
             }
-            Operation::OPEN { path, des: _ } => {
+            Operation::OPEN { path, .. } => {
                 for segment in path.segments() {
                     used_names.insert(segment);
                 }
@@ -88,16 +687,55 @@ pub fn insert(
                 size: _,
             } => {}
             Operation::FSYNC { des: _ } => {}
+            Operation::FDATASYNC { des: _ } => {}
+            Operation::SYNCFILERANGE { .. } => {}
+            Operation::LSEEK { .. } => {}
+            Operation::FALLOCATE { .. } => {}
+            Operation::CRASHPOINT => {}
+            Operation::OPENDIR { path, stream: _ } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::READDIR {
+                stream: _,
+                count: _,
+            } => {}
+            Operation::CLOSEDIR { stream: _ } => {}
+            Operation::LISTDIR { path } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::NAMETOHANDLE { path, handle: _ } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::OPENBYHANDLE { handle: _ } => {}
+            Operation::PREALLOC { path, size: _ }
+            | Operation::TRUNCATE { path, size: _ }
+            | Operation::CHMOD { path, mode: _ }
+            | Operation::MKNOD { path, .. }
+            | Operation::SETXATTR { path, .. }
+            | Operation::REMOVEXATTR { path, .. }
+            | Operation::UTIMENS { path, .. }
+            | Operation::STAT { path } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
         }
     }
 
     let (before, after) = workload.ops.split_at(index);
-    let mut fs = AbstractFS::new();
-    if !fs
+    let mut fs = config.model();
+    if fs
         .replay(&Workload {
             ops: before.to_vec(),
+            seed: workload.seed,
         })
-        .is_ok()
+        .is_err()
     {
         return None;
     }
@@ -110,12 +748,13 @@ pub fn insert(
             break name;
         }
     };
-    append_one(rng, &mut fs, weights, &mut gen_name);
-    if !fs
+    append_one(rng, &mut fs, config, &mut gen_name);
+    if fs
         .replay(&Workload {
             ops: after.to_vec(),
+            seed: workload.seed,
         })
-        .is_ok()
+        .is_err()
     {
         None
     } else {
@@ -127,7 +766,11 @@ pub fn insert(
 mod tests {
     use rand::{rngs::StdRng, SeedableRng};
 
-    use crate::abstract_fs::{generator::generate_new, operation::OperationKind};
+    use crate::abstract_fs::{
+        flags::{ModeFlag, OpenFlag},
+        generator::generate_new,
+        operation::{OperationKind, OperationWeights},
+    };
 
     use super::*;
 
@@ -148,6 +791,7 @@ mod tests {
                     mode: vec![],
                 },
             ],
+            seed: 0,
         };
         assert_eq!(None, remove(&w, 0));
         assert_eq!(
@@ -162,6 +806,7 @@ mod tests {
                         mode: vec![],
                     },
                 ],
+                seed: 0,
             }),
             remove(&w, 1)
         );
@@ -184,6 +829,7 @@ mod tests {
                     path: "/foobar/boo".into(),
                 },
             ],
+            seed: 0,
         };
         assert_eq!(
             None,
@@ -191,7 +837,10 @@ mod tests {
                 &mut rng,
                 &w,
                 1,
-                &OperationWeights::new(vec![(OperationKind::REMOVE, 100)])
+                &GeneratorConfig {
+                    weights: OperationWeights::new(vec![(OperationKind::REMOVE, 100)]),
+                    ..Default::default()
+                }
             )
         );
         assert_eq!(
@@ -212,25 +861,296 @@ mod tests {
                         path: "/foobar".into(),
                     },
                 ],
+                seed: 0,
             }),
             insert(
                 &mut rng,
                 &w,
                 3,
-                &OperationWeights::new(vec![(OperationKind::REMOVE, 100)])
+                &GeneratorConfig {
+                    weights: OperationWeights::new(vec![(OperationKind::REMOVE, 100)]),
+                    ..Default::default()
+                }
             )
         );
     }
 
+    #[test]
+    fn test_reorder_independent() {
+        let w = Workload {
+            ops: vec![
+                Operation::MKDIR {
+                    path: "/foo".into(),
+                    mode: vec![],
+                },
+                Operation::CREATE {
+                    path: "/bar".into(),
+                    mode: vec![],
+                },
+                Operation::CREATE {
+                    path: "/foo/boo".into(),
+                    mode: vec![],
+                },
+            ],
+            seed: 0,
+        };
+        assert_eq!(
+            Some(Workload {
+                ops: vec![
+                    Operation::CREATE {
+                        path: "/bar".into(),
+                        mode: vec![],
+                    },
+                    Operation::MKDIR {
+                        path: "/foo".into(),
+                        mode: vec![],
+                    },
+                    Operation::CREATE {
+                        path: "/foo/boo".into(),
+                        mode: vec![],
+                    },
+                ],
+                seed: 0,
+            }),
+            reorder(&w, 0)
+        );
+        assert_eq!(
+            Some(Workload {
+                ops: vec![
+                    Operation::MKDIR {
+                        path: "/foo".into(),
+                        mode: vec![],
+                    },
+                    Operation::CREATE {
+                        path: "/foo/boo".into(),
+                        mode: vec![],
+                    },
+                    Operation::CREATE {
+                        path: "/bar".into(),
+                        mode: vec![],
+                    },
+                ],
+                seed: 0,
+            }),
+            reorder(&w, 1)
+        );
+        assert_eq!(None, reorder(&w, 2));
+    }
+
+    #[test]
+    fn test_reorder_dependent() {
+        let w = Workload {
+            ops: vec![
+                Operation::MKDIR {
+                    path: "/foo".into(),
+                    mode: vec![],
+                },
+                Operation::CREATE {
+                    path: "/foo/bar".into(),
+                    mode: vec![],
+                },
+                Operation::OPEN {
+                    path: "/foo/bar".into(),
+                    flags: vec![],
+                    des: FileDescriptorIndex(0),
+                },
+                Operation::CREATE {
+                    path: "/boo".into(),
+                    mode: vec![],
+                },
+                Operation::OPEN {
+                    path: "/boo".into(),
+                    flags: vec![],
+                    des: FileDescriptorIndex(1),
+                },
+                Operation::WRITE {
+                    des: FileDescriptorIndex(0),
+                    src_offset: 0,
+                    size: 10,
+                },
+                Operation::REMOVE {
+                    path: "/foo".into(),
+                },
+            ],
+            seed: 0,
+        };
+        // parent and child
+        assert_eq!(None, reorder(&w, 0));
+        // same path
+        assert_eq!(None, reorder(&w, 1));
+        // two opens
+        assert_eq!(None, reorder(&w, 3));
+        // write and remove of directory containing written file
+        assert_eq!(None, reorder(&w, 5));
+        // open and write to different files
+        assert!(reorder(&w, 4).is_some());
+        // open and create on different paths
+        assert!(reorder(&w, 2).is_some());
+    }
+
+    #[test]
+    fn test_reorder_hardlink_alias() {
+        let w = Workload {
+            ops: vec![
+                Operation::CREATE {
+                    path: "/foo".into(),
+                    mode: vec![],
+                },
+                Operation::HARDLINK {
+                    old_path: "/foo".into(),
+                    new_path: "/bar".into(),
+                },
+                Operation::OPEN {
+                    path: "/foo".into(),
+                    flags: vec![],
+                    des: FileDescriptorIndex(0),
+                },
+                Operation::TRUNCATE {
+                    path: "/bar".into(),
+                    size: 10,
+                },
+                Operation::CHMOD {
+                    path: "/foo".into(),
+                    mode: vec![ModeFlag::S_IRWXU],
+                },
+                Operation::WRITE {
+                    des: FileDescriptorIndex(0),
+                    src_offset: 0,
+                    size: 10,
+                },
+                Operation::CREATE {
+                    path: "/boo".into(),
+                    mode: vec![],
+                },
+            ],
+            seed: 0,
+        };
+        // truncate and chmod of the same file under different names
+        assert_eq!(None, reorder(&w, 3));
+        // chmod by one name and write by descriptor opened with it
+        assert_eq!(None, reorder(&w, 4));
+        // write by descriptor and create of unrelated file
+        assert!(reorder(&w, 5).is_some());
+    }
+
+    #[test]
+    fn test_shrink() {
+        let w = Workload {
+            ops: vec![
+                Operation::MKDIR {
+                    path: "/0".into(),
+                    mode: vec![],
+                },
+                Operation::CREATE {
+                    path: "/0/1".into(),
+                    mode: vec![ModeFlag::S_IRWXU, ModeFlag::S_ISVTX],
+                },
+                Operation::OPEN {
+                    path: "/0/1".into(),
+                    flags: vec![],
+                    des: FileDescriptorIndex(0),
+                },
+                Operation::WRITE {
+                    des: FileDescriptorIndex(0),
+                    src_offset: 10,
+                    size: 1000,
+                },
+                Operation::CLOSE {
+                    des: FileDescriptorIndex(0),
+                },
+            ],
+            seed: 0,
+        };
+        assert_eq!(Vec::<Workload>::new(), shrink(&w, 0));
+        let shrunk = shrink(&w, 1);
+        assert_eq!(3, shrunk.len());
+        assert_eq!(
+            Operation::CREATE {
+                path: "/0/1".into(),
+                mode: vec![ModeFlag::S_ISVTX],
+            },
+            shrunk[0].ops[1]
+        );
+        // file and every later path is moved to root
+        assert_eq!(
+            Operation::CREATE {
+                path: "/1".into(),
+                mode: vec![ModeFlag::S_IRWXU, ModeFlag::S_ISVTX],
+            },
+            shrunk[2].ops[1]
+        );
+        assert_eq!(
+            Operation::OPEN {
+                path: "/1".into(),
+                flags: vec![],
+                des: FileDescriptorIndex(0),
+            },
+            shrunk[2].ops[2]
+        );
+        assert_eq!(
+            vec![
+                Operation::WRITE {
+                    des: FileDescriptorIndex(0),
+                    src_offset: 10,
+                    size: 500,
+                },
+                Operation::WRITE {
+                    des: FileDescriptorIndex(0),
+                    src_offset: 0,
+                    size: 1000,
+                },
+            ],
+            shrink(&w, 3)
+                .into_iter()
+                .map(|w| w.ops[3].clone())
+                .collect::<Vec<_>>()
+        );
+        assert!(shrink(&w, 4).is_empty());
+        assert!(shrink(&w, 5).is_empty());
+    }
+
+    #[test]
+    fn smoke_test_shrink() {
+        let mut rng = StdRng::seed_from_u64(123);
+        for _ in 0..100 {
+            let w = generate_new(&mut rng, 30, &GeneratorConfig::default());
+            for index in 0..w.ops.len() {
+                for shrunk in shrink(&w, index) {
+                    assert_ne!(w, shrunk);
+                    assert_eq!(w.ops.len(), shrunk.ops.len());
+                    AbstractFS::new().replay(&shrunk).unwrap();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn smoke_test_reorder() {
+        let mut rng = StdRng::seed_from_u64(123);
+        for _ in 0..100 {
+            let w = generate_new(&mut rng, 30, &GeneratorConfig::default());
+            let mut expected = AbstractFS::new();
+            expected.replay(&w).unwrap();
+            for index in 0..w.ops.len() {
+                if let Some(reordered) = reorder(&w, index) {
+                    assert_ne!(w, reordered);
+                    let mut fs = AbstractFS::new();
+                    fs.replay(&reordered).unwrap();
+                    assert_eq!(expected.alive_paths(), fs.alive_paths());
+                }
+            }
+        }
+    }
+
     #[test]
     fn smoke_test_mutate() {
         let mut rng = StdRng::seed_from_u64(123);
-        let mut w = generate_new(&mut rng, 100, &OperationWeights::uniform());
+        let mut w = generate_new(&mut rng, 100, &GeneratorConfig::default());
         for _ in 0..1000 {
             let p: f64 = rng.gen();
             if w.ops.is_empty() || p >= 0.5 {
                 let index = rng.gen_range(0..=w.ops.len());
-                if let Some(workload) = insert(&mut rng, &w, index, &OperationWeights::uniform()) {
+                if let Some(workload) = insert(&mut rng, &w, index, &GeneratorConfig::default()) {
                     w = workload;
                 }
             } else {
@@ -241,4 +1161,150 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_splice_renumbers_descriptors() {
+        let mut fst = AbstractFS::new();
+        fst.create("/foo".into(), vec![]).unwrap();
+        let des = fst.open("/foo".into(), vec![]).unwrap();
+        fst.write(des, 0, 1024).unwrap();
+        let mut snd = AbstractFS::new();
+        snd.create("/bar".into(), vec![]).unwrap();
+        let des = snd.open("/bar".into(), vec![]).unwrap();
+        snd.write(des, 0, 512).unwrap();
+        snd.close(des).unwrap();
+
+        let mut expected = AbstractFS::new();
+        expected.create("/foo".into(), vec![]).unwrap();
+        let fst_des = expected.open("/foo".into(), vec![]).unwrap();
+        expected.write(fst_des, 0, 1024).unwrap();
+        expected.create("/bar".into(), vec![]).unwrap();
+        let snd_des = expected.open("/bar".into(), vec![]).unwrap();
+        expected.write(snd_des, 0, 512).unwrap();
+        expected.close(snd_des).unwrap();
+        assert_eq!(FileDescriptorIndex(1), snd_des);
+        assert_eq!(
+            Some(expected.recording),
+            splice(&fst.recording, 3, &snd.recording, 0)
+        );
+
+        // descriptor opened before the suffix is unknown, even if prefix has one with same index
+        assert_eq!(None, splice(&fst.recording, 3, &snd.recording, 2));
+        // file is not created in prefix
+        assert_eq!(None, splice(&fst.recording, 3, &snd.recording, 1));
+        assert_eq!(None, splice(&fst.recording, 4, &snd.recording, 0));
+    }
+
+    #[test]
+    fn smoke_test_splice() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let generate = |rng: &mut StdRng| generate_new(rng, 30, &GeneratorConfig::default());
+        for _ in 0..100 {
+            let fst = generate(&mut rng);
+            let snd = generate(&mut rng);
+            let fst_len = rng.gen_range(0..=fst.ops.len());
+            let snd_start = rng.gen_range(0..snd.ops.len());
+            if let Some(spliced) = splice(&fst, fst_len, &snd, snd_start) {
+                assert_eq!(fst.ops[..fst_len], spliced.ops[..fst_len]);
+                assert!(spliced.ops.len() <= fst_len + snd.ops.len() - snd_start);
+                let mut fs = AbstractFS::new();
+                fs.replay(&spliced).unwrap();
+                assert_eq!(spliced, fs.recording);
+            }
+        }
+    }
+
+    #[test]
+    fn test_repair() {
+        let broken = Workload {
+            ops: vec![
+                Operation::MKDIR {
+                    path: "/foo".into(),
+                    mode: vec![],
+                },
+                Operation::CREATE {
+                    path: "/foo/bar".into(),
+                    mode: vec![],
+                },
+                Operation::REMOVE {
+                    path: "/foo".into(),
+                },
+                Operation::OPEN {
+                    path: "/foo/bar".into(),
+                    flags: vec![],
+                    des: FileDescriptorIndex(0),
+                },
+                Operation::WRITE {
+                    des: FileDescriptorIndex(0),
+                    src_offset: 0,
+                    size: 1024,
+                },
+                Operation::CREATE {
+                    path: "/baz".into(),
+                    mode: vec![],
+                },
+                Operation::OPEN {
+                    path: "/baz".into(),
+                    flags: vec![],
+                    des: FileDescriptorIndex(1),
+                },
+                Operation::WRITE {
+                    des: FileDescriptorIndex(1),
+                    src_offset: 0,
+                    size: 1024,
+                },
+                Operation::CLOSE {
+                    des: FileDescriptorIndex(1),
+                },
+                Operation::CLOSE {
+                    des: FileDescriptorIndex(1),
+                },
+            ],
+            seed: 0,
+        };
+        assert!(AbstractFS::new().replay(&broken).is_err());
+        let mut expected = AbstractFS::new();
+        expected.mkdir("/foo".into(), vec![]).unwrap();
+        expected.create("/foo/bar".into(), vec![]).unwrap();
+        expected.remove("/foo".into()).unwrap();
+        expected.create("/baz".into(), vec![]).unwrap();
+        let des = expected.open("/baz".into(), vec![]).unwrap();
+        expected.write(des, 0, 1024).unwrap();
+        expected.close(des).unwrap();
+        assert_eq!((expected.recording, 3), repair(&broken));
+    }
+
+    #[test]
+    fn test_repair_failed_open() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        fs.symlink("/foo".into(), "/bar".into()).unwrap();
+        fs.open_failing("/bar".into(), vec![OpenFlag::O_NOFOLLOW])
+            .unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        fs.write(des, 0, 1024).unwrap();
+        fs.close(des).unwrap();
+        assert_eq!((fs.recording.clone(), 0), repair(&fs.recording));
+    }
+
+    #[test]
+    fn smoke_test_repair() {
+        let mut rng = StdRng::seed_from_u64(123);
+        for _ in 0..100 {
+            let w = generate_new(&mut rng, 30, &GeneratorConfig::default());
+            assert_eq!((w.clone(), 0), repair(&w));
+            // removing random operations usually breaks workload
+            let mut broken = w.clone();
+            for _ in 0..5 {
+                if !broken.ops.is_empty() {
+                    broken.ops.remove(rng.gen_range(0..broken.ops.len()));
+                }
+            }
+            let (repaired, dropped) = repair(&broken);
+            assert_eq!(broken.ops.len(), repaired.ops.len() + dropped);
+            let mut fs = AbstractFS::new();
+            fs.replay(&repaired).unwrap();
+            assert_eq!(repaired, fs.recording);
+        }
+    }
 }
@@ -1,8 +1,15 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+};
 
 use serde::{Deserialize, Serialize};
 
-use super::{content::Content, pathname::Name};
+use super::{
+    content::Content,
+    flags::{Mode, OpenFlags, SpecialKind},
+    pathname::Name,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct FileIndex(pub usize);
@@ -10,6 +17,15 @@ pub struct FileIndex(pub usize);
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct DirIndex(pub usize);
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SocketIndex(pub usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SpecialIndex(pub usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SymlinkIndex(pub usize);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct FileDescriptorIndex(pub usize);
 
@@ -19,25 +35,140 @@ impl Display for FileDescriptorIndex {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct DirStreamIndex(pub usize);
+
+impl Display for DirStreamIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct FileHandleIndex(pub usize);
+
+impl Display for FileHandleIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Extended attribute names with sizes of their values.
+pub type Xattrs = BTreeMap<String, u64>;
+
+/// Access and modification times in seconds since epoch.
+/// `None` means time was last updated by filesystem itself, so model does not know it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Times {
+    pub atime: Option<i64>,
+    pub mtime: Option<i64>,
+}
+
 #[derive(Debug, Clone)]
 pub struct File {
-    pub descriptor: Option<FileDescriptorIndex>,
+    /// Open descriptors of file, each one has its own offset.
+    pub descriptors: Vec<FileDescriptorIndex>,
     pub content: Content,
+    pub mode: Mode,
+    /// Number of names file has.
+    pub nlink: u64,
+    pub xattrs: Xattrs,
+    pub times: Times,
+    /// Sorted disjoint byte ranges changed since file was last synced, they may be lost by crash.
+    pub unsynced: Vec<(u64, u64)>,
+    /// Content as of last sync.
+    pub synced: Content,
+    /// Content file has if unsynced data was lost by crash point, changed the same way as
+    /// [`File::content`] afterwards. `None` if there was nothing to lose.
+    pub crashed: Option<Content>,
+}
+
+impl File {
+    /// Marks bytes in `start..end` as changed since file was last synced.
+    pub fn mark_unsynced(&mut self, start: u64, end: u64) {
+        if start >= end {
+            return;
+        }
+        let (mut start, mut end) = (start, end);
+        let mut merged = vec![];
+        for (from, to) in self.unsynced.drain(..) {
+            if to < start || end < from {
+                merged.push((from, to));
+            } else {
+                start = start.min(from);
+                end = end.max(to);
+            }
+        }
+        merged.push((start, end));
+        merged.sort();
+        self.unsynced = merged;
+    }
+
+    /// Both states file can be in after crash are synced.
+    pub fn sync(&mut self) {
+        self.unsynced.clear();
+        self.synced = self.content.clone();
+        self.crashed = None;
+    }
+
+    /// No byte in `start..end` was changed since file was last synced.
+    pub fn is_synced(&self, start: u64, end: u64) -> bool {
+        self.unsynced
+            .iter()
+            .all(|(from, to)| end <= *from || *to <= start)
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct FileDescriptor {
     pub file: FileIndex,
     pub offset: u64,
+    pub flags: OpenFlags,
+}
+
+#[derive(Debug, Clone)]
+pub struct DirStream {
+    pub dir: DirIndex,
+    pub is_open: bool,
+}
+
+/// Reference to a node independent of its name, obtained with `name_to_handle_at`.
+#[derive(Debug, Clone)]
+pub struct FileHandle {
+    pub node: Node,
+}
+
+/// Unix domain socket has no data, only a name.
+#[derive(Debug, Clone)]
+pub struct Socket {}
+
+/// FIFO or device node, it is never opened, so it has no data.
+#[derive(Debug, Clone)]
+pub struct Special {
+    pub kind: SpecialKind,
+    pub mode: Mode,
+}
+
+/// Symbolic link is never followed in model, target is kept as is and may point nowhere.
+#[derive(Debug, Clone)]
+pub struct Symlink {
+    pub target: String,
 }
 
 #[derive(Debug, Clone)]
 pub struct Dir {
     pub children: HashMap<Name, Node>,
+    /// Mode of root is unknown until it is changed.
+    pub mode: Mode,
+    pub xattrs: Xattrs,
+    pub times: Times,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Node {
     FILE(FileIndex),
     DIR(DirIndex),
+    SOCKET(SocketIndex),
+    SPECIAL(SpecialIndex),
+    SYMLINK(SymlinkIndex),
 }
@@ -1,6 +1,12 @@
 use serde::{Deserialize, Serialize};
 
-use super::{flags::Mode, node::FileDescriptorIndex, pathname::PathName};
+use super::{
+    flags::{
+        FallocateFlags, Mode, OpenFlags, RenameFlags, SpecialKind, SyncFileRangeFlags, Whence,
+    },
+    node::{DirStreamIndex, FileDescriptorIndex, FileHandleIndex},
+    pathname::PathName,
+};
 
 #[derive(Clone, Debug, Hash, PartialEq, Serialize, Deserialize)]
 pub enum Operation {
@@ -15,6 +21,18 @@ pub enum Operation {
     REMOVE {
         path: PathName,
     },
+    MKSOCKET {
+        path: PathName,
+    },
+    /// Creates symbolic link at `path`, `target` is stored as is and never resolved.
+    SYMLINK {
+        target: String,
+        path: PathName,
+    },
+    /// Reads target of symbolic link, it is reported in trace to compare between filesystems.
+    READLINK {
+        path: PathName,
+    },
     HARDLINK {
         old_path: PathName,
         new_path: PathName,
@@ -22,9 +40,14 @@ pub enum Operation {
     RENAME {
         old_path: PathName,
         new_path: PathName,
+        /// Plain `rename` if empty, `renameat2` otherwise.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        flags: RenameFlags,
     },
     OPEN {
         path: PathName,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        flags: OpenFlags,
         des: FileDescriptorIndex,
     },
     CLOSE {
@@ -42,13 +65,100 @@ pub enum Operation {
     FSYNC {
         des: FileDescriptorIndex,
     },
+    FDATASYNC {
+        des: FileDescriptorIndex,
+    },
+    SYNCFILERANGE {
+        des: FileDescriptorIndex,
+        offset: u64,
+        nbytes: u64,
+        flags: SyncFileRangeFlags,
+    },
+    LSEEK {
+        des: FileDescriptorIndex,
+        offset: i64,
+        whence: Whence,
+    },
+    OPENDIR {
+        path: PathName,
+        stream: DirStreamIndex,
+    },
+    READDIR {
+        stream: DirStreamIndex,
+        count: u64,
+    },
+    CLOSEDIR {
+        stream: DirStreamIndex,
+    },
+    /// Reads the whole directory at once (`opendir`, `readdir` until the end, `closedir`).
+    LISTDIR {
+        path: PathName,
+    },
+    NAMETOHANDLE {
+        path: PathName,
+        handle: FileHandleIndex,
+    },
+    OPENBYHANDLE {
+        handle: FileHandleIndex,
+    },
+    PREALLOC {
+        path: PathName,
+        size: u64,
+    },
+    TRUNCATE {
+        path: PathName,
+        size: u64,
+    },
+    CHMOD {
+        path: PathName,
+        mode: Mode,
+    },
+    MKNOD {
+        path: PathName,
+        kind: SpecialKind,
+        mode: Mode,
+    },
+    SETXATTR {
+        path: PathName,
+        name: String,
+        value_size: u64,
+    },
+    REMOVEXATTR {
+        path: PathName,
+        name: String,
+    },
+    /// Sets access and modification times (in seconds since epoch) without following symlinks.
+    UTIMENS {
+        path: PathName,
+        atime: i64,
+        mtime: i64,
+    },
+    /// Reports size, allocated blocks, mode and link count of node without following symlinks.
+    STAT {
+        path: PathName,
+    },
+    /// Allocates, zeroes or punches hole in `len` bytes at `offset` of file opened with descriptor.
+    FALLOCATE {
+        des: FileDescriptorIndex,
+        offset: u64,
+        len: u64,
+        mode: FallocateFlags,
+    },
+    /// Simulated power cut: filesystem is shut down without writing back data, every descriptor and
+    /// directory stream is closed and filesystem is remounted.
+    ///
+    /// Data changed since file was last synced is not guaranteed to survive it.
+    CRASHPOINT,
 }
 
-#[derive(PartialEq, Eq, Hash, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Clone, Copy)]
 pub enum OperationKind {
     MKDIR,
     CREATE,
     REMOVE,
+    MKSOCKET,
+    SYMLINK,
+    READLINK,
     HARDLINK,
     RENAME,
     OPEN,
@@ -56,6 +166,65 @@ pub enum OperationKind {
     READ,
     WRITE,
     FSYNC,
+    FDATASYNC,
+    SYNCFILERANGE,
+    LSEEK,
+    OPENDIR,
+    READDIR,
+    CLOSEDIR,
+    LISTDIR,
+    NAMETOHANDLE,
+    OPENBYHANDLE,
+    PREALLOC,
+    TRUNCATE,
+    CHMOD,
+    MKNOD,
+    SETXATTR,
+    REMOVEXATTR,
+    UTIMENS,
+    STAT,
+    FALLOCATE,
+    CRASHPOINT,
+}
+
+#[allow(dead_code)]
+impl Operation {
+    pub fn kind(&self) -> OperationKind {
+        match self {
+            Operation::MKDIR { .. } => OperationKind::MKDIR,
+            Operation::CREATE { .. } => OperationKind::CREATE,
+            Operation::REMOVE { .. } => OperationKind::REMOVE,
+            Operation::MKSOCKET { .. } => OperationKind::MKSOCKET,
+            Operation::SYMLINK { .. } => OperationKind::SYMLINK,
+            Operation::READLINK { .. } => OperationKind::READLINK,
+            Operation::HARDLINK { .. } => OperationKind::HARDLINK,
+            Operation::RENAME { .. } => OperationKind::RENAME,
+            Operation::OPEN { .. } => OperationKind::OPEN,
+            Operation::CLOSE { .. } => OperationKind::CLOSE,
+            Operation::READ { .. } => OperationKind::READ,
+            Operation::WRITE { .. } => OperationKind::WRITE,
+            Operation::FSYNC { .. } => OperationKind::FSYNC,
+            Operation::FDATASYNC { .. } => OperationKind::FDATASYNC,
+            Operation::SYNCFILERANGE { .. } => OperationKind::SYNCFILERANGE,
+            Operation::LSEEK { .. } => OperationKind::LSEEK,
+            Operation::OPENDIR { .. } => OperationKind::OPENDIR,
+            Operation::READDIR { .. } => OperationKind::READDIR,
+            Operation::CLOSEDIR { .. } => OperationKind::CLOSEDIR,
+            Operation::LISTDIR { .. } => OperationKind::LISTDIR,
+            Operation::NAMETOHANDLE { .. } => OperationKind::NAMETOHANDLE,
+            Operation::OPENBYHANDLE { .. } => OperationKind::OPENBYHANDLE,
+            Operation::PREALLOC { .. } => OperationKind::PREALLOC,
+            Operation::TRUNCATE { .. } => OperationKind::TRUNCATE,
+            Operation::CHMOD { .. } => OperationKind::CHMOD,
+            Operation::MKNOD { .. } => OperationKind::MKNOD,
+            Operation::SETXATTR { .. } => OperationKind::SETXATTR,
+            Operation::REMOVEXATTR { .. } => OperationKind::REMOVEXATTR,
+            Operation::UTIMENS { .. } => OperationKind::UTIMENS,
+            Operation::STAT { .. } => OperationKind::STAT,
+            Operation::FALLOCATE { .. } => OperationKind::FALLOCATE,
+            Operation::CRASHPOINT => OperationKind::CRASHPOINT,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -75,6 +244,9 @@ impl OperationWeights {
                 (OperationKind::CREATE, 100),
                 (OperationKind::MKDIR, 100),
                 (OperationKind::REMOVE, 100),
+                (OperationKind::MKSOCKET, 100),
+                (OperationKind::SYMLINK, 100),
+                (OperationKind::READLINK, 100),
                 (OperationKind::HARDLINK, 100),
                 (OperationKind::RENAME, 100),
                 (OperationKind::OPEN, 100),
@@ -82,6 +254,26 @@ impl OperationWeights {
                 (OperationKind::READ, 100),
                 (OperationKind::WRITE, 100),
                 (OperationKind::FSYNC, 100),
+                (OperationKind::OPENDIR, 100),
+                (OperationKind::READDIR, 100),
+                (OperationKind::CLOSEDIR, 100),
+                (OperationKind::LISTDIR, 100),
+                (OperationKind::NAMETOHANDLE, 100),
+                (OperationKind::OPENBYHANDLE, 100),
+                (OperationKind::PREALLOC, 100),
+                (OperationKind::TRUNCATE, 100),
+                (OperationKind::CHMOD, 100),
+                (OperationKind::MKNOD, 100),
+                (OperationKind::SETXATTR, 100),
+                (OperationKind::REMOVEXATTR, 100),
+                (OperationKind::FDATASYNC, 100),
+                (OperationKind::SYNCFILERANGE, 100),
+                (OperationKind::LSEEK, 100),
+                (OperationKind::UTIMENS, 100),
+                (OperationKind::STAT, 100),
+                (OperationKind::FALLOCATE, 100),
+                // crash closes every descriptor and remounts filesystem, so it is rare
+                (OperationKind::CRASHPOINT, 5),
             ],
         }
     }
@@ -70,6 +70,18 @@ impl PathName {
             return true;
         }
     }
+
+    /// Path with `prefix` replaced by `with`, `None` if path does not start with `prefix`.
+    pub fn replace_prefix(&self, prefix: &PathName, with: &PathName) -> Option<PathName> {
+        if !prefix.is_prefix_of(self) {
+            return None;
+        }
+        let rest = &self.segments()[prefix.segments().len()..];
+        Some(
+            rest.iter()
+                .fold(with.clone(), |path, name| path.join(name.to_string())),
+        )
+    }
 }
 
 pub type Name = String;
@@ -86,4 +98,15 @@ mod tests {
         assert!(PathName::from("/").is_prefix_of(&PathName::from("/1")));
         assert!(PathName::from("/1").is_prefix_of(&PathName::from("/1")));
     }
+
+    #[test]
+    fn test_replace_prefix() {
+        let path = PathName::from("/1/2/3");
+        assert_eq!(
+            Some("/2/3".into()),
+            path.replace_prefix(&"/1/2".into(), &"/2".into())
+        );
+        assert_eq!(Some("/".into()), path.replace_prefix(&path, &"/".into()));
+        assert_eq!(None, path.replace_prefix(&"/1/3".into(), &"/3".into()));
+    }
 }
@@ -0,0 +1,103 @@
+//! Export of workload as a single C++ source file that does not depend on the rest of executor.
+//!
+//! Executor header and source are pasted before the workload, so file can be attached to bug
+//! report and built with `g++ -std=c++20 reproducer.c -o reproducer`.
+
+use super::workload::Workload;
+
+const EXECUTOR_INCLUDE: &str = "#include \"executor.h\"";
+
+impl Workload {
+    /// Renders workload along with executor sources it needs to be built.
+    pub fn encode_reproducer(&self, executor_h: &str, executor_cpp: &str) -> String {
+        let mut result = String::new();
+        result.push_str("// Reproducer generated by DIFFuzzer.\n");
+        result.push_str("//\n");
+        result.push_str("// Build: g++ -std=c++20 reproducer.c -o reproducer\n");
+        result.push_str("// Run:   ./reproducer <workspace> (directory on mounted filesystem)\n");
+        result.push_str("\n// executor.h\n\n");
+        for line in executor_h.lines().filter(|line| *line != "#pragma once") {
+            result.push_str(&format!("{}\n", line));
+        }
+        result.push_str("\n// executor.cpp\n\n");
+        for line in executor_cpp
+            .lines()
+            .filter(|line| *line != EXECUTOR_INCLUDE)
+        {
+            result.push_str(&format!("{}\n", line));
+        }
+        result.push_str("\n// workload\n");
+        for line in self
+            .encode_c(None)
+            .lines()
+            .filter(|line| *line != EXECUTOR_INCLUDE)
+        {
+            result.push_str(&format!("{}\n", line));
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::Path, process::Command};
+
+    use crate::abstract_fs::fs::AbstractFS;
+
+    use super::*;
+
+    fn executor(name: &str) -> String {
+        fs::read_to_string(Path::new("executor").join(name)).unwrap()
+    }
+
+    fn example() -> Workload {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.create("/foo/bar".into(), vec![]).unwrap();
+        let des = fs.open("/foo/bar".into(), vec![]).unwrap();
+        fs.write(des, 0, 1024).unwrap();
+        fs.close(des).unwrap();
+        fs.rename("/foo/bar".into(), "/baz".into()).unwrap();
+        fs.recording
+    }
+
+    #[test]
+    fn test_reproducer_contents() {
+        let reproducer =
+            example().encode_reproducer(&executor("executor.h"), &executor("executor.cpp"));
+        assert!(!reproducer.contains(EXECUTOR_INCLUDE));
+        assert!(!reproducer.contains("#pragma once"));
+        assert!(reproducer.contains("int do_mkdir(const char *path, mode_t param);"));
+        assert!(reproducer.contains("int main(int argc, char *argv[]) {"));
+        assert!(reproducer.contains("\ndo_mkdir(\"/foo\", 0);\n"));
+        assert!(reproducer.contains("\ndo_rename(\"/foo/bar\", \"/baz\");\n"));
+        assert!(reproducer.trim_end().ends_with('}'));
+        // workload can be recovered from reproducer
+        let workload = &reproducer[reproducer.find("// workload").unwrap()..];
+        assert_eq!(example(), Workload::decode_c(workload));
+    }
+
+    #[test]
+    fn test_reproducer_compiles() {
+        let dir = env::temp_dir().join("DIFFuzzer-reproducer-test");
+        fs::remove_dir_all(&dir).unwrap_or(());
+        fs::create_dir(&dir).unwrap();
+        let source = dir.join("reproducer.c");
+        fs::write(
+            &source,
+            example().encode_reproducer(&executor("executor.h"), &executor("executor.cpp")),
+        )
+        .unwrap();
+        let output = Command::new("g++")
+            .args(["-std=c++20", "-fsyntax-only"])
+            .arg(&source)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
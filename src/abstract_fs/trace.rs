@@ -1,4 +1,8 @@
-use std::num::ParseIntError;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt::Display,
+    num::ParseIntError,
+};
 
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -15,6 +19,140 @@ pub struct TraceRow {
     return_code: i32,
     errno: Errno,
     extra: String,
+    /// Wall-clock time of operation in microseconds, absent in traces of older executors.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duration: Option<u64>,
+}
+
+impl TraceRow {
+    /// Extra fields with key in `ignored` are not compared.
+    fn same_as(&self, other: &TraceRow, ignored: &[String]) -> bool {
+        if self.command == READDIR && other.command == READDIR {
+            self.index == other.index && self.errno_class() == other.errno_class()
+        } else if self.command == LISTDIR && other.command == LISTDIR {
+            self.index == other.index
+                && self.return_code == other.return_code
+                && self.errno_class() == other.errno_class()
+                && (ignored.iter().any(|key| key == ENTRIES)
+                    || self.listed_entries() == other.listed_entries())
+        } else {
+            self.index == other.index
+                && self.command == other.command
+                && self.return_code == other.return_code
+                && self.errno_class() == other.errno_class()
+                && self
+                    .comparable_extra(ignored)
+                    .eq(other.comparable_extra(ignored))
+        }
+    }
+
+    /// Errno code, with codes that are equally valid for the operation merged into one.
+    pub fn errno_class(&self) -> i32 {
+        let code = self.errno.code;
+        EQUIVALENT_ERRNOS
+            .iter()
+            .find(|(command, from, _)| *command == self.command && *from == code)
+            .map(|(_, _, to)| *to)
+            .unwrap_or(code)
+    }
+
+    /// Extra fields without inode numbers and timestamps, which are filesystem-specific.
+    fn comparable_extra<'a>(&'a self, ignored: &'a [String]) -> impl Iterator<Item = &'a str> {
+        self.extra.split_whitespace().filter(|field| {
+            !UNCOMPARABLE_FIELDS
+                .iter()
+                .copied()
+                .chain(ignored.iter().map(String::as_str))
+                .any(|key| field.split_once('=').is_some_and(|(k, _)| k == key))
+        })
+    }
+
+    /// Names returned by `LISTDIR`, order they were returned in is not kept.
+    pub fn listed_entries(&self) -> BTreeSet<&str> {
+        self.field(ENTRIES)
+            .map(|entries| entries.split(':').filter(|name| !name.is_empty()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Value of `key=value` field in extra.
+    pub fn field(&self, key: &str) -> Option<&str> {
+        self.extra
+            .split_whitespace()
+            .find_map(|field| field.split_once('=').filter(|(k, _)| *k == key))
+            .map(|(_, value)| value)
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    pub fn return_code(&self) -> i32 {
+        self.return_code
+    }
+
+    pub fn errno_code(&self) -> i32 {
+        self.errno.code
+    }
+
+    pub fn errno_name(&self) -> &str {
+        &self.errno.name
+    }
+
+    pub fn duration(&self) -> Option<u64> {
+        self.duration
+    }
+
+    /// Attributes reported by successful `STAT`.
+    pub fn stat(&self) -> Option<Stat> {
+        if self.command != STAT || self.return_code != 0 {
+            return None;
+        }
+        Some(Stat {
+            size: self.field("st_size")?.parse().ok()?,
+            blocks: self.field("st_blocks")?.parse().ok()?,
+            mode: u32::from_str_radix(self.field("st_mode")?, 8).ok()?,
+            nlink: self.field("st_nlink")?.parse().ok()?,
+        })
+    }
+
+    /// Symlink target returned by successful `READLINK`, bytes executor can not print as is are escaped.
+    pub fn readlink_target(&self) -> Option<&str> {
+        if self.command != READLINK || self.return_code != 0 {
+            return None;
+        }
+        self.field("target")
+    }
+
+    /// Name of fatal signal executor died by during operation, e.g. `SIGSEGV`.
+    pub fn signal(&self) -> Option<&str> {
+        if self.command != SIGNAL {
+            return None;
+        }
+        self.field("signal")
+    }
+
+    fn inode(&self) -> Option<u64> {
+        self.field("ino").and_then(|ino| ino.parse().ok())
+    }
+}
+
+/// Attributes of node reported by `STAT`, blocks are 512-byte units.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Stat {
+    pub size: u64,
+    pub blocks: u64,
+    pub mode: u32,
+    pub nlink: u64,
+}
+
+impl Stat {
+    pub fn is_dir(&self) -> bool {
+        self.mode & 0o170000 == 0o040000
+    }
 }
 
 #[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
@@ -23,6 +161,62 @@ pub struct Errno {
     code: i32,
 }
 
+/// How inode numbers of created files relate to inodes seen earlier in the test.
+///
+/// Filesystems differ in whether they reuse inodes of removed files, so only the pattern is comparable.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct InodeReuse {
+    /// Creates that got inode number seen before (e.g. of a removed file).
+    pub reused: Vec<u32>,
+    /// Creates that got inode number still referenced by an open descriptor.
+    pub unsafe_reused: Vec<u32>,
+}
+
+/// Entries seen by single directory stream during the whole test.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct DirStreamEntries {
+    pub entries: Vec<String>,
+    /// Stream was read until the end.
+    pub eof: bool,
+}
+
+const READDIR: &str = "READDIR";
+const LISTDIR: &str = "LISTDIR";
+const WRITE: &str = "WRITE";
+const CREATE: &str = "CREATE";
+const OPEN: &str = "OPEN";
+const CLOSE: &str = "CLOSE";
+const STAT: &str = "STAT";
+const READLINK: &str = "READLINK";
+const ENTRIES: &str = "entries";
+/// Row executor writes from signal handler, instead of row of operation it died at.
+const SIGNAL: &str = "SIGNAL";
+const EEXIST: i32 = 17;
+const ENOTEMPTY: i32 = 39;
+/// POSIX allows either `EEXIST` or `ENOTEMPTY` when directory is not empty (command, errno, same as errno).
+const EQUIVALENT_ERRNOS: &[(&str, i32, i32)] =
+    &[("RENAME", EEXIST, ENOTEMPTY), ("RMDIR", EEXIST, ENOTEMPTY)];
+/// Directory link counts are not tracked by some filesystems (e.g. always 1 on Btrfs).
+const UNCOMPARABLE_FIELDS: &[&str] = &[
+    "ino",
+    "atime0",
+    "atime",
+    "mtime",
+    "ctime",
+    "old_parent_nlink",
+    "new_parent_nlink",
+    // attributes of `STAT` are compared by stat objective, some of them differ between filesystems
+    "st_size",
+    "st_blocks",
+    "st_mode",
+    "st_nlink",
+    // compared by readlink objective
+    "target",
+];
+
+const HEADER: &str = "Index,Command,ReturnCode,Errno,Extra";
+const HEADER_WITH_DURATION: &str = "Index,Command,ReturnCode,Errno,Extra,Duration";
+
 pub const TRACE_FILENAME: &str = "trace.csv";
 
 type Result<T> = std::result::Result<T, TraceError>;
@@ -45,6 +239,64 @@ impl From<ParseIntError> for TraceError {
     }
 }
 
+impl Display for TraceRow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:4},{:>12},{:>8},{}({}),{}",
+            self.index,
+            self.command,
+            self.return_code,
+            self.errno.name,
+            self.errno.code,
+            self.extra
+        )?;
+        if let Some(duration) = self.duration {
+            write!(f, ",{}", duration)?;
+        }
+        Ok(())
+    }
+}
+
+impl TraceRow {
+    fn try_parse(line: &str) -> Result<TraceRow> {
+        let columns: Vec<&str> = line.split(",").collect();
+        if columns.len() != 5 && columns.len() != 6 {
+            return Err(TraceError::InvalidColumnNumber);
+        }
+        let index = columns[0].trim().parse()?;
+        let command = columns[1].trim().to_owned();
+        let return_code = columns[2].trim().parse()?;
+        let errno_string = columns[3].trim().to_owned();
+        let extra = columns[4].trim().to_owned();
+        let duration = match columns.get(5) {
+            Some(duration) => Some(duration.trim().parse()?),
+            None => None,
+        };
+        let errno_parts: Vec<String> = errno_string
+            .split(&['(', ')'])
+            .map(|s| s.to_owned())
+            .collect();
+        let name = errno_parts
+            .get(0)
+            .ok_or(TraceError::InvalidErrno(errno_string.clone()))?
+            .clone();
+        let code: i32 = errno_parts
+            .get(1)
+            .ok_or(TraceError::InvalidErrno(errno_string.clone()))?
+            .parse()?;
+
+        Ok(TraceRow {
+            index,
+            command,
+            return_code,
+            errno: Errno { name, code },
+            extra,
+            duration,
+        })
+    }
+}
+
 impl Trace {
     pub fn try_parse(trace: String) -> Result<Trace> {
         let lines: Vec<&str> = trace.split('\n').collect();
@@ -56,43 +308,199 @@ impl Trace {
             if line.trim().is_empty() {
                 break;
             }
-            let columns: Vec<&str> = line.split(",").collect();
-            if columns.len() != 5 {
-                return Err(TraceError::InvalidColumnNumber);
-            }
-            let index = columns[0].trim().parse()?;
-            let command = columns[1].trim().to_owned();
-            let return_code = columns[2].trim().parse()?;
-            let errno_string = columns[3].trim().to_owned();
-            let extra = columns[4].trim().to_owned();
-            let errno_parts: Vec<String> = errno_string
-                .split(&['(', ')'])
-                .map(|s| s.to_owned())
-                .collect();
-            let name = errno_parts
-                .get(0)
-                .ok_or(TraceError::InvalidErrno(errno_string.clone()))?
-                .clone();
-            let code: i32 = errno_parts
-                .get(1)
-                .ok_or(TraceError::InvalidErrno(errno_string.clone()))?
-                .parse()?;
-
-            trace.rows.push(TraceRow {
-                index,
-                command,
-                return_code,
-                errno: Errno { name, code },
-                extra,
-            });
+            trace.rows.push(TraceRow::try_parse(line)?);
         }
         Ok(trace)
     }
+
+    /// Same as [`Trace::try_parse`], but drops last row if it is cut off (executor died while writing it).
+    pub fn try_parse_lenient(trace: String) -> Result<Trace> {
+        match trace.rsplit_once('\n') {
+            Some((complete, last)) if !last.trim().is_empty() => match TraceRow::try_parse(last) {
+                Ok(_) => Trace::try_parse(trace),
+                Err(_) => Trace::try_parse(format!("{}\n", complete)),
+            },
+            _ => Trace::try_parse(trace),
+        }
+    }
+
+    /// Index of operation executor was running when it died, if trace has less rows than workload operations.
+    ///
+    /// Every operation writes exactly one row. Death by signal is recorded in trace (see [`Trace::signal`]),
+    /// so it is not counted.
+    pub fn crashed_at(&self, workload_len: usize) -> Option<usize> {
+        if self.signal().is_some() {
+            return None;
+        }
+        let completed = self
+            .rows
+            .last()
+            .map(|row| row.index as usize + 1)
+            .unwrap_or(0);
+        if completed < workload_len {
+            Some(completed)
+        } else {
+            None
+        }
+    }
+
+    /// Last row, if executor was killed by fatal signal (index of row is operation it died at).
+    pub fn signal(&self) -> Option<&TraceRow> {
+        self.rows.last().filter(|row| row.signal().is_some())
+    }
+
+    /// Renders trace back in the same format executor writes it.
+    pub fn render(&self) -> String {
+        let header = if self.rows.iter().any(|row| row.duration.is_some()) {
+            HEADER_WITH_DURATION
+        } else {
+            HEADER
+        };
+        let mut trace = format!("{}\n", header);
+        for row in self.rows.iter() {
+            trace.push_str(&format!("{}\n", row));
+        }
+        trace
+    }
+
+    /// Compares traces, entries returned by `READDIR` and `LISTDIR` are compared as sets (order may differ).
     pub fn same_as(&self, other: &Trace) -> bool {
-        self == other
+        self.same_as_ignoring(other, &[])
+    }
+
+    /// Same as [`Trace::same_as`], but extra fields with key in `ignored` (e.g. `entries`) are not compared.
+    pub fn same_as_ignoring(&self, other: &Trace, ignored: &[String]) -> bool {
+        self.rows.len() == other.rows.len()
+            && self
+                .rows
+                .iter()
+                .zip(other.rows.iter())
+                .all(|(fst, snd)| fst.same_as(snd, ignored))
+            && (ignored.iter().any(|key| key == ENTRIES) || self.same_dir_entries_as(other))
+    }
+
+    /// Trace without rows of operations at `indices`.
+    pub fn without(&self, indices: &[u32]) -> Trace {
+        Trace {
+            rows: self
+                .rows
+                .iter()
+                .filter(|row| !indices.contains(&row.index))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Position of the first row that differs (or is missing in one of traces), `None` if rows are the same.
+    ///
+    /// Extra fields with key in `ignored` are not compared, see [`Trace::same_as_ignoring`].
+    pub fn first_difference(&self, other: &Trace, ignored: &[String]) -> Option<usize> {
+        self.rows
+            .iter()
+            .zip(other.rows.iter())
+            .position(|(fst, snd)| !fst.same_as(snd, ignored))
+            .or_else(|| {
+                (self.rows.len() != other.rows.len()).then(|| self.rows.len().min(other.rows.len()))
+            })
+    }
+
+    /// Compares entries of directory streams that were read until the end in both traces.
+    pub fn same_dir_entries_as(&self, other: &Trace) -> bool {
+        let fst_streams = self.dir_streams();
+        let snd_streams = other.dir_streams();
+        fst_streams
+            .iter()
+            .all(|(stream, fst)| match snd_streams.get(stream) {
+                Some(snd) if fst.eof && snd.eof => fst.entries == snd.entries,
+                _ => true,
+            })
+    }
+
+    /// Collects (sorted) entries for each directory stream.
+    pub fn dir_streams(&self) -> BTreeMap<String, DirStreamEntries> {
+        let mut streams: BTreeMap<String, DirStreamEntries> = BTreeMap::new();
+        for row in self.rows.iter().filter(|row| row.command == READDIR) {
+            let mut stream = None;
+            let mut entries = vec![];
+            let mut eof = false;
+            for field in row.extra.split_whitespace() {
+                match field.split_once('=') {
+                    Some(("stream", value)) => stream = Some(value.to_owned()),
+                    Some(("entries", value)) => {
+                        entries = value
+                            .split(':')
+                            .filter(|name| !name.is_empty())
+                            .map(|name| name.to_owned())
+                            .collect()
+                    }
+                    _ if field == "eof" => eof = true,
+                    _ => {}
+                }
+            }
+            if let Some(stream) = stream {
+                let stream = streams.entry(stream).or_default();
+                stream.entries.append(&mut entries);
+                stream.eof |= eof;
+            }
+        }
+        for stream in streams.values_mut() {
+            stream.entries.sort();
+        }
+        streams
+    }
+    /// Indices of writes which data did not match when read back (only if verification enabled).
+    pub fn write_verification_failures(&self) -> Vec<u32> {
+        self.rows
+            .iter()
+            .filter(|row| row.command == WRITE)
+            .filter(|row| {
+                row.extra
+                    .split_whitespace()
+                    .any(|field| field.starts_with("verify=") && field != "verify=ok")
+            })
+            .map(|row| row.index)
+            .collect()
+    }
+
+    /// Tracks inode numbers of open descriptors (captured on `OPEN` and `CLOSE`) and checks inodes of created files.
+    ///
+    /// Creating an existing file opens it instead, so workloads are expected to create new names (as generator does).
+    pub fn inode_reuse(&self) -> InodeReuse {
+        let mut reuse = InodeReuse::default();
+        let mut seen: HashSet<u64> = HashSet::new();
+        let mut open: HashMap<u64, usize> = HashMap::new();
+        for row in self.rows.iter() {
+            let Some(ino) = row.inode() else {
+                continue;
+            };
+            match row.command.as_str() {
+                OPEN => {
+                    *open.entry(ino).or_default() += 1;
+                }
+                CLOSE => {
+                    if let Some(count) = open.get_mut(&ino) {
+                        *count = count.saturating_sub(1);
+                    }
+                }
+                CREATE => {
+                    if open.get(&ino).is_some_and(|count| *count > 0) {
+                        reuse.unsafe_reused.push(row.index);
+                    } else if seen.contains(&ino) {
+                        reuse.reused.push(row.index);
+                    }
+                }
+                _ => {}
+            }
+            seen.insert(ino);
+        }
+        reuse
     }
-    pub fn has_errors(&self) -> bool {
-        self.rows.iter().any(|row| row.errno.code != 0)
+
+    /// Failures of operations at `expected` indices (see [`crate::abstract_fs::fs::AbstractFS::expected_failures`]) are not errors.
+    pub fn has_errors(&self, expected: &[u32]) -> bool {
+        self.rows
+            .iter()
+            .any(|row| row.errno.code != 0 && !expected.contains(&row.index))
     }
 }
 
@@ -117,7 +525,7 @@ mod tests {
     fn test_invalid_columns_count() {
         let trace = r#"
 Index,Command,ReturnCode,Errno,Extra
-    1,    Foo,        42,Success(0),a=1, ???
+    1,    Foo,        42,Success(0),a=1,5, ???
     2,    Bar,        -1,Error(42),b=2
 "#
         .trim();
@@ -146,7 +554,8 @@ Index,Command,ReturnCode,Errno,Extra
                             name: "Success".to_owned(),
                             code: 0
                         },
-                        extra: "a=1".to_owned()
+                        extra: "a=1".to_owned(),
+                        duration: None,
                     },
                     TraceRow {
                         index: 2,
@@ -156,7 +565,8 @@ Index,Command,ReturnCode,Errno,Extra
                             name: "Error".to_owned(),
                             code: 42
                         },
-                        extra: "b=2".to_owned()
+                        extra: "b=2".to_owned(),
+                        duration: None,
                     },
                 ]
             }),
@@ -164,6 +574,232 @@ Index,Command,ReturnCode,Errno,Extra
         )
     }
 
+    #[test]
+    fn test_duration_column() {
+        let trace = r#"
+Index,Command,ReturnCode,Errno,Extra,Duration
+    0,      MKDIR,         0,Success(0),,55
+    1,     CREATE,         4,Success(0),ino=12,1200
+"#
+        .trim();
+        let trace = Trace::try_parse(trace.to_owned()).unwrap();
+        assert_eq!(Some(55), trace.rows[0].duration());
+        assert_eq!(Some(1200), trace.rows[1].duration());
+        assert_eq!("ino=12", trace.rows[1].extra);
+        assert_eq!(Ok(trace.clone()), Trace::try_parse(trace.render()));
+        assert!(trace.render().starts_with(HEADER_WITH_DURATION));
+        assert_eq!(
+            Err(TraceError::IntParse("x".parse::<u64>().unwrap_err())),
+            Trace::try_parse(format!("{}\n0,MKDIR,0,Success(0),,x", HEADER_WITH_DURATION))
+        );
+    }
+
+    #[test]
+    fn test_without_duration_column() {
+        let trace = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,      MKDIR,         0,Success(0),
+"#
+        .trim();
+        let trace = Trace::try_parse(trace.to_owned()).unwrap();
+        assert_eq!(None, trace.rows[0].duration());
+        assert!(trace.render().starts_with(&format!("{}\n", HEADER)));
+        // older traces are comparable with new ones
+        let timed = Trace::try_parse(format!(
+            "{}\n0,MKDIR,0,Success(0),,10",
+            HEADER_WITH_DURATION
+        ))
+        .unwrap();
+        assert!(trace.same_as(&timed));
+    }
+
+    #[test]
+    fn test_readdir_different_order() {
+        let fst = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,    OPENDIR,         0,Success(0),
+    1,    READDIR,         1,Success(0),stream=0 entries=1
+    2,     CREATE,         3,Success(0),
+    3,    READDIR,         2,Success(0),stream=0 entries=0:2 eof
+"#
+        .trim();
+        let snd = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,    OPENDIR,         0,Success(0),
+    1,    READDIR,         1,Success(0),stream=0 entries=0
+    2,     CREATE,         3,Success(0),
+    3,    READDIR,         1,Success(0),stream=0 entries=1 eof
+"#
+        .trim();
+        let fst = Trace::try_parse(fst.to_owned()).unwrap();
+        let snd = Trace::try_parse(snd.to_owned()).unwrap();
+        assert_eq!(
+            DirStreamEntries {
+                entries: vec!["0".to_owned(), "1".to_owned(), "2".to_owned()],
+                eof: true
+            },
+            fst.dir_streams()["0"]
+        );
+        assert!(fst.same_as(&fst));
+        assert!(!fst.same_as(&snd));
+        assert!(!snd.same_as(&fst));
+    }
+
+    #[test]
+    fn test_readdir_dropped_or_duplicated() {
+        let fst = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,    OPENDIR,         0,Success(0),
+    1,    READDIR,         2,Success(0),stream=0 entries=0:1
+    2,     RENAME,         0,Success(0),
+    3,    READDIR,         2,Success(0),stream=0 entries=2 eof
+"#
+        .trim();
+        let dropped = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,    OPENDIR,         0,Success(0),
+    1,    READDIR,         2,Success(0),stream=0 entries=2:0
+    2,     RENAME,         0,Success(0),
+    3,    READDIR,         0,Success(0),stream=0 entries= eof
+"#
+        .trim();
+        let duplicated = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,    OPENDIR,         0,Success(0),
+    1,    READDIR,         2,Success(0),stream=0 entries=1:0
+    2,     RENAME,         0,Success(0),
+    3,    READDIR,         2,Success(0),stream=0 entries=0:2 eof
+"#
+        .trim();
+        let reordered = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,    OPENDIR,         0,Success(0),
+    1,    READDIR,         2,Success(0),stream=0 entries=2:1
+    2,     RENAME,         0,Success(0),
+    3,    READDIR,         1,Success(0),stream=0 entries=0 eof
+"#
+        .trim();
+        let fst = Trace::try_parse(fst.to_owned()).unwrap();
+        let dropped = Trace::try_parse(dropped.to_owned()).unwrap();
+        let duplicated = Trace::try_parse(duplicated.to_owned()).unwrap();
+        let reordered = Trace::try_parse(reordered.to_owned()).unwrap();
+        assert!(!fst.same_as(&dropped));
+        assert!(!fst.same_as(&duplicated));
+        assert!(fst.same_as(&reordered));
+    }
+
+    #[test]
+    fn test_listdir_mutated_between_reads() {
+        let fst = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,    LISTDIR,         2,Success(0),entries=foo:bar
+    1,     RENAME,         0,Success(0),
+    2,    LISTDIR,         2,Success(0),entries=baz:foo
+"#
+        .trim();
+        let reordered = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,    LISTDIR,         2,Success(0),entries=bar:foo
+    1,     RENAME,         0,Success(0),
+    2,    LISTDIR,         2,Success(0),entries=foo:baz
+"#
+        .trim();
+        let stale = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,    LISTDIR,         2,Success(0),entries=bar:foo
+    1,     RENAME,         0,Success(0),
+    2,    LISTDIR,         2,Success(0),entries=bar:foo
+"#
+        .trim();
+        let dropped = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,    LISTDIR,         2,Success(0),entries=bar:foo
+    1,     RENAME,         0,Success(0),
+    2,    LISTDIR,         1,Success(0),entries=foo
+"#
+        .trim();
+        let fst = Trace::try_parse(fst.to_owned()).unwrap();
+        let reordered = Trace::try_parse(reordered.to_owned()).unwrap();
+        let stale = Trace::try_parse(stale.to_owned()).unwrap();
+        let dropped = Trace::try_parse(dropped.to_owned()).unwrap();
+        assert_eq!(BTreeSet::from(["bar", "foo"]), fst.rows[0].listed_entries());
+        assert!(fst.same_as(&reordered));
+        assert!(!fst.same_as(&stale));
+        assert_eq!(Some(2), fst.first_difference(&stale, &[]));
+        assert!(!fst.same_as(&dropped));
+        assert_eq!(Some(2), fst.first_difference(&dropped, &[]));
+    }
+
+    #[test]
+    fn test_listdir_empty() {
+        let empty = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,    LISTDIR,         0,Success(0),entries=
+"#
+        .trim();
+        let failed = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,    LISTDIR,        -1,Not a directory(20),
+"#
+        .trim();
+        let empty = Trace::try_parse(empty.to_owned()).unwrap();
+        let failed = Trace::try_parse(failed.to_owned()).unwrap();
+        assert!(empty.rows[0].listed_entries().is_empty());
+        assert!(failed.rows[0].listed_entries().is_empty());
+        assert!(!empty.same_as(&failed));
+    }
+
+    #[test]
+    fn test_readdir_not_finished() {
+        let fst = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,    OPENDIR,         0,Success(0),
+    1,    READDIR,         1,Success(0),stream=0 entries=0
+"#
+        .trim();
+        let snd = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,    OPENDIR,         0,Success(0),
+    1,    READDIR,         1,Success(0),stream=0 entries=1
+"#
+        .trim();
+        let fst = Trace::try_parse(fst.to_owned()).unwrap();
+        let snd = Trace::try_parse(snd.to_owned()).unwrap();
+        assert!(fst.same_as(&snd));
+    }
+
+    #[test]
+    fn test_write_verification_failures() {
+        let trace = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,     CREATE,         3,Success(0),
+    1,       OPEN,         3,Success(0),
+    2,      WRITE,       100,Success(0),verify=ok
+    3,      WRITE,         5,Success(0),verify=mismatch@2
+    4,      WRITE,        10,Success(0),verify=short@4
+    5,      WRITE,        10,Success(0),
+    6,      CLOSE,         0,Success(0),
+"#
+        .trim();
+        let trace = Trace::try_parse(trace.to_owned()).unwrap();
+        assert_eq!(vec![3, 4], trace.write_verification_failures());
+        assert!(!trace.has_errors(&[]));
+    }
+
+    #[test]
+    fn test_expected_errors() {
+        let trace = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,      MKDIR,         0,Success(0),
+    1,     RENAME,        -1,Error(20),
+"#
+        .trim();
+        let trace = Trace::try_parse(trace.to_owned()).unwrap();
+        assert!(trace.has_errors(&[]));
+        assert!(trace.has_errors(&[0]));
+        assert!(!trace.has_errors(&[1]));
+    }
+
     #[test]
     fn test_invalid_errno_no_brackets() {
         let trace = r#"
@@ -177,4 +813,240 @@ Index,Command,ReturnCode,Errno,Extra
             Trace::try_parse(trace.to_owned())
         )
     }
+
+    #[test]
+    fn test_inode_reuse() {
+        let never = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,     CREATE,         3,Success(0),ino=12
+    1,       OPEN,         3,Success(0),ino=12
+    2,      CLOSE,         0,Success(0),ino=12
+    3,     UNLINK,         0,Success(0),
+    4,     CREATE,         3,Success(0),ino=13
+"#
+        .trim();
+        let after_close = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,     CREATE,         3,Success(0),ino=257
+    1,       OPEN,         3,Success(0),ino=257
+    2,      CLOSE,         0,Success(0),ino=257
+    3,     UNLINK,         0,Success(0),
+    4,     CREATE,         3,Success(0),ino=257
+"#
+        .trim();
+        let while_open = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,     CREATE,         3,Success(0),ino=257
+    1,       OPEN,         3,Success(0),ino=257
+    2,     UNLINK,         0,Success(0),
+    3,     CREATE,         4,Success(0),ino=257
+    4,      CLOSE,         0,Success(0),ino=257
+"#
+        .trim();
+        let never = Trace::try_parse(never.to_owned()).unwrap();
+        let after_close = Trace::try_parse(after_close.to_owned()).unwrap();
+        let while_open = Trace::try_parse(while_open.to_owned()).unwrap();
+        assert_eq!(InodeReuse::default(), never.inode_reuse());
+        assert_eq!(
+            InodeReuse {
+                reused: vec![4],
+                unsafe_reused: vec![]
+            },
+            after_close.inode_reuse()
+        );
+        assert_eq!(
+            InodeReuse {
+                reused: vec![],
+                unsafe_reused: vec![3]
+            },
+            while_open.inode_reuse()
+        );
+        assert!(never.same_as(&after_close));
+    }
+
+    #[test]
+    fn test_lenient_cut_off_row() {
+        let trace = "Index,Command,ReturnCode,Errno,Extra\n    0,    MKDIR,         0,Success(0),\n    1,    CRE";
+        assert_eq!(
+            Err(TraceError::InvalidColumnNumber),
+            Trace::try_parse(trace.to_owned())
+        );
+        let parsed = Trace::try_parse_lenient(trace.to_owned()).unwrap();
+        assert_eq!(1, parsed.rows.len());
+        assert_eq!("MKDIR", parsed.rows[0].command);
+        let complete = format!("{}ATE,         3,Success(0),\n", trace);
+        assert_eq!(
+            Trace::try_parse(complete.clone()),
+            Trace::try_parse_lenient(complete)
+        );
+    }
+
+    #[test]
+    fn test_crashed_at() {
+        let rows = [
+            "    0,    MKDIR,         0,Success(0),",
+            "    1,   CREATE,         3,Success(0),",
+            "    2,     OPEN,         4,Success(0),",
+            "    3,    WRITE,        10,Success(0),",
+        ];
+        for completed in 0..=rows.len() {
+            let trace = format!(
+                "Index,Command,ReturnCode,Errno,Extra\n{}",
+                rows[..completed]
+                    .iter()
+                    .map(|row| format!("{}\n", row))
+                    .collect::<String>()
+            );
+            let trace = Trace::try_parse_lenient(trace).unwrap();
+            if completed < rows.len() {
+                assert_eq!(Some(completed), trace.crashed_at(rows.len()));
+            } else {
+                assert_eq!(None, trace.crashed_at(rows.len()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_signal_row() {
+        let trace = Trace::try_parse(
+            "Index,Command,ReturnCode,Errno,Extra,Duration\n   0,       MKDIR,       0,Success(0),,12\n   1,SIGNAL,-1,Success(0),signal=SIGBUS,7\n"
+                .to_owned(),
+        )
+        .unwrap();
+        let row = trace.signal().unwrap();
+        assert_eq!(1, row.index());
+        assert_eq!(Some("SIGBUS"), row.signal());
+        assert_eq!(Some(7), row.duration());
+        assert_eq!(None, trace.rows[0].signal());
+        assert!(!trace.has_errors(&[]));
+        // executor died, but it is not truncation
+        assert_eq!(None, trace.crashed_at(3));
+        assert_eq!(
+            Trace::try_parse(trace.render()).unwrap().signal(),
+            trace.signal()
+        );
+
+        let completed = Trace::try_parse(
+            "Index,Command,ReturnCode,Errno,Extra\n   0,       MKDIR,       0,Success(0),\n"
+                .to_owned(),
+        )
+        .unwrap();
+        assert_eq!(None, completed.signal());
+        assert!(!completed.same_as(&trace));
+    }
+
+    #[test]
+    fn test_render_round_trip() {
+        let trace = r#"
+Index,Command,ReturnCode,Errno,Extra
+   0,       MKDIR,       0,Success(0),
+   1,      CREATE,      -1,File exists(17),
+   2,        READ,      10,Success(0),hash=1f atime0=1 atime=2 mtime=1
+"#
+        .trim_start();
+        let parsed = Trace::try_parse(trace.to_owned()).unwrap();
+        assert_eq!(3, parsed.rows.len());
+        assert_eq!(-1, parsed.rows[1].return_code);
+        assert_eq!(
+            Errno {
+                name: "File exists".to_owned(),
+                code: 17
+            },
+            parsed.rows[1].errno
+        );
+        assert_eq!(trace, parsed.render());
+        assert_eq!(Ok(parsed.clone()), Trace::try_parse(parsed.render()));
+    }
+
+    #[test]
+    fn test_different_errno() {
+        let fst = r#"
+Index,Command,ReturnCode,Errno,Extra
+   0,      RENAME,      -1,Not a directory(20),
+"#
+        .trim();
+        let snd = r#"
+Index,Command,ReturnCode,Errno,Extra
+   0,      RENAME,      -1,No such file or directory(2),
+"#
+        .trim();
+        let fst = Trace::try_parse(fst.to_owned()).unwrap();
+        let snd = Trace::try_parse(snd.to_owned()).unwrap();
+        assert!(fst.same_as(&fst));
+        assert!(!fst.same_as(&snd));
+    }
+
+    #[test]
+    fn test_stat_row() {
+        let trace = r#"
+Index,Command,ReturnCode,Errno,Extra
+   0,      CREATE,       3,Success(0),ino=12
+   1,        STAT,       0,Success(0),ino=12 st_size=4096 st_blocks=8 st_mode=100644 st_nlink=1
+   2,        STAT,       0,Success(0),ino=2 st_size=60 st_blocks=0 st_mode=40755 st_nlink=3
+   3,        STAT,      -1,No such file or directory(2),
+"#
+        .trim();
+        let trace = Trace::try_parse(trace.to_owned()).unwrap();
+        assert_eq!(None, trace.rows[0].stat());
+        assert_eq!(
+            Some(Stat {
+                size: 4096,
+                blocks: 8,
+                mode: 0o100644,
+                nlink: 1
+            }),
+            trace.rows[1].stat()
+        );
+        assert!(!trace.rows[1].stat().unwrap().is_dir());
+        assert!(trace.rows[2].stat().unwrap().is_dir());
+        assert_eq!(None, trace.rows[3].stat());
+        assert_eq!(
+            trace.render(),
+            Trace::try_parse(trace.render()).unwrap().render()
+        );
+    }
+
+    #[test]
+    fn test_stat_fields_not_compared() {
+        let fst = r#"
+Index,Command,ReturnCode,Errno,Extra
+   0,        STAT,       0,Success(0),ino=12 st_size=4096 st_blocks=8 st_mode=40755 st_nlink=2
+"#
+        .trim();
+        let snd = r#"
+Index,Command,ReturnCode,Errno,Extra
+   0,        STAT,       0,Success(0),ino=257 st_size=0 st_blocks=0 st_mode=40755 st_nlink=1
+"#
+        .trim();
+        let fst = Trace::try_parse(fst.to_owned()).unwrap();
+        let snd = Trace::try_parse(snd.to_owned()).unwrap();
+        assert!(fst.same_as(&snd));
+    }
+
+    #[test]
+    fn test_readlink_row() {
+        let trace = r#"
+Index,Command,ReturnCode,Errno,Extra
+   0,     SYMLINK,       0,Success(0),
+   1,    READLINK,       0,Success(0),target=../foo/
+   2,    READLINK,       0,Success(0),target=a\x20b
+   3,    READLINK,      -1,Invalid argument(22),
+"#
+        .trim();
+        let trace = Trace::try_parse(trace.to_owned()).unwrap();
+        assert_eq!(None, trace.rows[0].readlink_target());
+        assert_eq!(Some("../foo/"), trace.rows[1].readlink_target());
+        assert_eq!(Some("a\\x20b"), trace.rows[2].readlink_target());
+        assert_eq!(None, trace.rows[3].readlink_target());
+        let other = r#"
+Index,Command,ReturnCode,Errno,Extra
+   0,     SYMLINK,       0,Success(0),
+   1,    READLINK,       0,Success(0),target=../foo
+   2,    READLINK,       0,Success(0),target=a\x20b
+   3,    READLINK,      -1,Invalid argument(22),
+"#
+        .trim();
+        // targets are left to readlink objective
+        assert!(trace.same_as(&Trace::try_parse(other.to_owned()).unwrap()));
+    }
 }
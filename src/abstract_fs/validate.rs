@@ -0,0 +1,163 @@
+//! Offline check of workload against the model, nothing is mounted or compiled.
+
+use thiserror::Error;
+
+use super::{
+    fs::{AbstractFS, AliveNodes, FsError},
+    operation::Operation,
+    pathname::PathName,
+    workload::Workload,
+};
+
+/// First operation of workload that fails in the model.
+#[derive(Error, Debug, PartialEq)]
+#[error("operation {index} ({op:?}) failed: {err}")]
+pub struct InvalidOperation {
+    pub index: usize,
+    pub op: Operation,
+    pub err: FsError,
+}
+
+/// Replays workload one operation at a time, returns model after the last one.
+pub fn validate(workload: &Workload) -> Result<AbstractFS, Box<InvalidOperation>> {
+    validate_compacting(workload, None)
+}
+
+/// Same as [`validate`], but once model has `compact_at` nodes, unreachable ones are dropped
+/// (see [`AbstractFS::compact`]). Operation fails with [`FsError::NodeLimitReached`] if that is not enough.
+pub fn validate_compacting(
+    workload: &Workload,
+    compact_at: Option<usize>,
+) -> Result<AbstractFS, Box<InvalidOperation>> {
+    let mut fs = AbstractFS::new();
+    fs.max_nodes = compact_at;
+    for (index, op) in workload.ops.iter().enumerate() {
+        let op_workload = Workload {
+            ops: vec![op.clone()],
+            seed: workload.seed,
+        };
+        let mut result = fs.replay(&op_workload);
+        if let Err(FsError::NodeLimitReached(_)) = result {
+            fs.compact();
+            result = fs.replay(&op_workload);
+        }
+        result.map_err(|err| {
+            Box::new(InvalidOperation {
+                index,
+                op: op.clone(),
+                err,
+            })
+        })?;
+    }
+    Ok(fs)
+}
+
+/// Alive nodes as indented tree, one node per line, directories end with `/`.
+pub fn render_alive(alive: &AliveNodes) -> String {
+    let mut nodes: Vec<(&PathName, &str)> = alive
+        .dirs
+        .iter()
+        .map(|path| (path, "/"))
+        .chain(alive.files.iter().map(|(_, path)| (path, "")))
+        .chain(alive.sockets.iter().map(|path| (path, " (socket)")))
+        .chain(alive.specials.iter().map(|path| (path, " (special)")))
+        .chain(alive.symlinks.iter().map(|path| (path, " (symlink)")))
+        .collect();
+    // by segments, so that children are right after their parent
+    nodes.sort_by(|(a, _), (b, _)| a.segments().cmp(&b.segments()));
+    let mut result = String::new();
+    for (path, suffix) in nodes {
+        if path.is_root() {
+            result.push_str("/\n");
+            continue;
+        }
+        let segments = path.segments();
+        result.push_str(&format!(
+            "{}{}{}\n",
+            "  ".repeat(segments.len()),
+            segments.last().unwrap(),
+            suffix
+        ));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.create("/foo/bar".into(), vec![]).unwrap();
+        fs.mkdir("/foo-baz".into(), vec![]).unwrap();
+        fs.mksocket("/foo/sock".into()).unwrap();
+        fs.hardlink("/foo/bar".into(), "/boo".into()).unwrap();
+        fs.symlink("../boo".into(), "/foo/link".into()).unwrap();
+        let model = validate(&fs.recording).unwrap();
+        assert_eq!(fs.recording, model.recording);
+        assert_eq!(
+            "/\n  boo\n  foo/\n    bar\n    link (symlink)\n    sock (socket)\n  foo-baz/\n",
+            render_alive(&model.alive())
+        );
+    }
+
+    #[test]
+    fn test_remove_nonexistent() {
+        let workload = Workload {
+            ops: vec![
+                Operation::MKDIR {
+                    path: "/foo".into(),
+                    mode: vec![],
+                },
+                Operation::REMOVE {
+                    path: "/bar".into(),
+                },
+                Operation::REMOVE {
+                    path: "/foo".into(),
+                },
+            ],
+            seed: 0,
+        };
+        let Err(err) = validate(&workload) else {
+            panic!("workload must be invalid");
+        };
+        assert_eq!(1, err.index);
+        assert_eq!(workload.ops[1], err.op);
+        assert!(err.to_string().starts_with("operation 1 (REMOVE"));
+    }
+
+    #[test]
+    fn test_compacting() {
+        let create = |path: &str| Operation::CREATE {
+            path: path.into(),
+            mode: vec![],
+        };
+        let remove = |path: &str| Operation::REMOVE { path: path.into() };
+        let mut ops = vec![];
+        for _ in 0..10 {
+            ops.push(create("/foo"));
+            ops.push(remove("/foo"));
+        }
+        ops.push(create("/bar"));
+        let workload = Workload { ops, seed: 0 };
+        assert_eq!(12, validate(&workload).unwrap().node_count());
+
+        let model = validate_compacting(&workload, Some(3)).unwrap();
+        assert!(model.node_count() <= 3);
+        assert_eq!(workload, model.recording);
+        assert_eq!("/\n  bar\n", render_alive(&model.alive()));
+
+        // alive nodes are never dropped
+        let workload = Workload {
+            ops: vec![create("/foo"), create("/bar"), create("/baz")],
+            seed: 0,
+        };
+        let Err(err) = validate_compacting(&workload, Some(3)) else {
+            panic!("workload must not fit");
+        };
+        assert_eq!(2, err.index);
+        assert_eq!(FsError::NodeLimitReached(3), err.err);
+    }
+}
@@ -7,11 +7,17 @@ use super::operation::Operation;
 #[derive(Clone, Debug, Hash, PartialEq, Serialize, Deserialize)]
 pub struct Workload {
     pub ops: Vec<Operation>,
+    /// Seed of executor write buffer, so the same workload writes the same bytes.
+    #[serde(default)]
+    pub seed: u64,
 }
 
 impl Workload {
     pub fn new() -> Workload {
-        Workload { ops: vec![] }
+        Workload {
+            ops: vec![],
+            seed: 0,
+        }
     }
     pub fn push(&mut self, op: Operation) {
         self.ops.push(op);
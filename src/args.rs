@@ -1,5 +1,7 @@
-use crate::filesystems::filesystems_available;
+use std::time::Duration;
+
 use clap::{builder::PossibleValuesParser, Parser, Subcommand};
+use dif_fuzzer::filesystems::filesystems_available;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -9,8 +11,42 @@ pub struct Args {
     #[arg(long,default_value_t = String::from("./config.toml"))]
     pub config_path: String,
 
+    /// Only check that tools and modules for selected filesystems (or every one, if mode is not given)
+    /// are available on host
+    #[arg(long, default_value_t = false)]
+    pub check: bool,
+
+    /// Do not check host before running mode (e.g. if tools are not found in `PATH`)
+    #[arg(long, default_value_t = false, conflicts_with = "check")]
+    pub skip_probe: bool,
+
+    /// Features passed to `mkfs -O` of filesystem instead of its own, e.g. `ext4=^has_journal`
+    /// ('+' prefix appends to them, can be repeated for different filesystems)
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_fs_opts)]
+    pub mkfs_opts: Vec<(String, String)>,
+
+    /// Options passed to `mount -o` of filesystem instead of its own, e.g. `btrfs=+compress`
+    /// ('+' prefix appends to them, can be repeated for different filesystems)
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_fs_opts)]
+    pub mount_opts: Vec<(String, String)>,
+
+    /// Required unless only host is checked
     #[clap(subcommand)]
-    pub mode: Mode,
+    pub mode: Option<Mode>,
+}
+
+impl Args {
+    /// Filesystems host is checked for, `None` if it is not checked at all
+    /// (offline modes and `--skip-probe`).
+    pub fn probed_filesystems(&self) -> Option<Vec<String>> {
+        if self.skip_probe {
+            return None;
+        }
+        match &self.mode {
+            Some(mode) => mode.needs_host().then(|| mode.filesystems()),
+            None => Some(filesystems_available()),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Subcommand)]
@@ -29,6 +65,21 @@ pub enum Mode {
         /// Test count
         #[arg(short, long)]
         test_count: Option<u64>,
+        /// Stop after running that long, e.g. `90s`, `30m`, `12h` or `7d` (seconds if no unit)
+        #[arg(long, value_parser = parse_duration)]
+        max_runtime: Option<Duration>,
+        /// Stop and exit with non-zero status on the first crash
+        #[arg(long, default_value_t = false)]
+        fail_fast: bool,
+        /// Seed of random generators (overrides config)
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Append every saved crash as a line of JSON to file, `-` for stdout (overrides config)
+        #[arg(long)]
+        ndjson_out: Option<String>,
+        /// Continue campaign from checkpoint directory instead of initial seeds
+        #[arg(long)]
+        resume: Option<String>,
     },
     /// Run blackbox fuzzing
     Blackbox {
@@ -40,9 +91,26 @@ pub enum Mode {
         #[arg(short, long)]
         #[clap(value_parser = PossibleValuesParser::new(filesystems_available()))]
         second_filesystem: String,
+        /// More filesystems to test, every pair of filesystems is compared by the same objectives,
+        /// data modes and mount options only apply to first two (can be repeated)
+        #[arg(short, long)]
+        #[clap(value_parser = PossibleValuesParser::new(filesystems_available()))]
+        other_filesystem: Vec<String>,
         /// Test count
         #[arg(short, long)]
         test_count: Option<u64>,
+        /// Stop after running that long, e.g. `90s`, `30m`, `12h` or `7d` (seconds if no unit)
+        #[arg(long, value_parser = parse_duration)]
+        max_runtime: Option<Duration>,
+        /// Stop and exit with non-zero status on the first crash
+        #[arg(long, default_value_t = false)]
+        fail_fast: bool,
+        /// Seed of random generators (overrides config)
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Append every saved crash as a line of JSON to file, `-` for stdout (overrides config)
+        #[arg(long)]
+        ndjson_out: Option<String>,
     },
     /// Run single test
     Single {
@@ -60,6 +128,24 @@ pub enum Mode {
         #[clap(value_parser = PossibleValuesParser::new(filesystems_available()))]
         filesystem: String,
     },
+    /// Export testcase as standalone C++ source file that can be attached to bug report
+    Reproducer {
+        /// Path to testcase in JSON format
+        #[arg(short, long)]
+        path_to_test: String,
+        /// Where reproducer will be saved
+        #[arg(short, long, default_value_t = String::from("./reproducer.c"))]
+        output: String,
+        /// Also save shell script of the same workload next to reproducer (with '.sh' extension)
+        #[arg(long, default_value_t = false)]
+        shell: bool,
+    },
+    /// Check that testcase replays in the model and print resulting tree, nothing is mounted
+    Validate {
+        /// Path to testcase in JSON format
+        #[arg(short, long)]
+        path_to_test: String,
+    },
     /// Reduce testcase
     Reduce {
         /// Place where results will be saved
@@ -77,4 +163,233 @@ pub enum Mode {
         #[clap(value_parser = PossibleValuesParser::new(filesystems_available()))]
         second_filesystem: String,
     },
+    /// Replay saved crashes and check which of them still reproduce
+    Triage {
+        /// Directory with saved crashes
+        #[arg(short, long, default_value_t = String::from("./crashes"))]
+        crashes_dir: String,
+        /// First filesystem to test
+        #[arg(short, long)]
+        #[clap(value_parser = PossibleValuesParser::new(filesystems_available()))]
+        first_filesystem: String,
+        /// Second filesystem to test
+        #[arg(short, long)]
+        #[clap(value_parser = PossibleValuesParser::new(filesystems_available()))]
+        second_filesystem: String,
+    },
+    /// Compare runs of the same testcase (saved in single mode) made on two kernels, e.g. with different configs
+    KernelDiff {
+        /// Directory with results of run on the first kernel
+        #[arg(short, long)]
+        first_run: String,
+        /// Directory with results of run on the second kernel
+        #[arg(short, long)]
+        second_run: String,
+        /// Filesystem that was tested
+        #[arg(long)]
+        #[clap(value_parser = PossibleValuesParser::new(filesystems_available()))]
+        filesystem: String,
+    },
+    /// Compare coverage saved by two greybox campaigns
+    CorpusDiff {
+        /// First corpus directory
+        #[arg(short, long)]
+        first_corpus: String,
+        /// Second corpus directory
+        #[arg(short, long)]
+        second_corpus: String,
+        /// Filesystem which coverage is compared
+        #[arg(long)]
+        #[clap(value_parser = PossibleValuesParser::new(filesystems_available()))]
+        filesystem: String,
+        /// Side of comparison filesystem was on in both campaigns
+        #[arg(long, default_value_t = String::from("fst"))]
+        #[clap(value_parser = PossibleValuesParser::new(["fst", "snd"]))]
+        side: String,
+    },
+    /// Pack saved inputs of corpus into single tar archive
+    ExportCorpus {
+        /// Corpus directory
+        #[arg(short, long, default_value_t = String::from("./corpus"))]
+        corpus_path: String,
+        /// Where archive will be saved
+        #[arg(short, long, default_value_t = String::from("./corpus.tar"))]
+        output: String,
+    },
+    /// Unpack inputs from tar archive into corpus, inputs that do not pass the model are skipped
+    ImportCorpus {
+        /// Archive made by export
+        #[arg(short, long)]
+        archive: String,
+        /// Corpus directory
+        #[arg(short, long, default_value_t = String::from("./corpus"))]
+        corpus_path: String,
+    },
+}
+
+impl Mode {
+    pub fn filesystems(&self) -> Vec<String> {
+        match self {
+            Mode::Blackbox {
+                first_filesystem,
+                second_filesystem,
+                other_filesystem,
+                ..
+            } => [first_filesystem, second_filesystem]
+                .into_iter()
+                .chain(other_filesystem)
+                .cloned()
+                .collect(),
+            Mode::Greybox {
+                first_filesystem,
+                second_filesystem,
+                ..
+            }
+            | Mode::Reduce {
+                first_filesystem,
+                second_filesystem,
+                ..
+            }
+            | Mode::Triage {
+                first_filesystem,
+                second_filesystem,
+                ..
+            } => vec![first_filesystem.clone(), second_filesystem.clone()],
+            Mode::Single { filesystem, .. } => vec![filesystem.clone()],
+            Mode::CorpusDiff { .. }
+            | Mode::KernelDiff { .. }
+            | Mode::ExportCorpus { .. }
+            | Mode::ImportCorpus { .. }
+            | Mode::Reproducer { .. }
+            | Mode::Validate { .. } => vec![],
+        }
+    }
+
+    /// Offline modes do not mount anything.
+    pub fn needs_host(&self) -> bool {
+        match self {
+            Mode::CorpusDiff { .. }
+            | Mode::KernelDiff { .. }
+            | Mode::ExportCorpus { .. }
+            | Mode::ImportCorpus { .. }
+            | Mode::Reproducer { .. }
+            | Mode::Validate { .. } => false,
+            _ => true,
+        }
+    }
+}
+
+/// Options given for filesystem with `--mkfs-opts` or `--mount-opts`.
+pub fn opts_of(opts: &[(String, String)], fs_name: &str) -> Option<String> {
+    opts.iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(fs_name))
+        .map(|(_, opts)| opts.clone())
+}
+
+/// Options of one filesystem in `<filesystem>=<options>` form.
+fn parse_fs_opts(arg: &str) -> Result<(String, String), String> {
+    let Some((fs_name, opts)) = arg.split_once('=') else {
+        return Err(format!("expected '<filesystem>=<options>', got '{}'", arg));
+    };
+    let fs_name = fs_name.to_lowercase();
+    if !filesystems_available().contains(&fs_name) {
+        return Err(format!("unknown filesystem '{}'", fs_name));
+    }
+    Ok((fs_name, opts.to_owned()))
+}
+
+fn parse_duration(arg: &str) -> Result<Duration, String> {
+    let (number, unit) = match arg.find(|c: char| !c.is_ascii_digit()) {
+        Some(idx) => arg.split_at(idx),
+        None => (arg, "s"),
+    };
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("bad duration '{}'", arg))?;
+    let secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(format!("unknown unit of duration '{}'", arg)),
+    };
+    number
+        .checked_mul(secs)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("bad duration '{}'", arg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fs_opts() {
+        assert_eq!(
+            Ok(("ext4".to_owned(), "^has_journal".to_owned())),
+            parse_fs_opts("Ext4=^has_journal")
+        );
+        assert_eq!(
+            Ok(("btrfs".to_owned(), "+compress=zstd".to_owned())),
+            parse_fs_opts("btrfs=+compress=zstd")
+        );
+        assert_eq!(Ok(("xfs".to_owned(), "".to_owned())), parse_fs_opts("xfs="));
+        assert!(parse_fs_opts("noatime").is_err());
+        assert!(parse_fs_opts("zfs=noatime").is_err());
+    }
+
+    #[test]
+    fn test_probed_filesystems() {
+        let parse = |args: &[&str]| Args::try_parse_from([&["diffuzzer"], args].concat());
+        let args = parse(&["--check"]).unwrap();
+        assert_eq!(None, args.mode);
+        assert_eq!(Some(filesystems_available()), args.probed_filesystems());
+        let args = parse(&[
+            "--check",
+            "single",
+            "-s",
+            ".",
+            "-p",
+            "test.json",
+            "-f",
+            "btrfs",
+        ])
+        .unwrap();
+        assert_eq!(Some(vec!["btrfs".to_owned()]), args.probed_filesystems());
+        let args = parse(&[
+            "--skip-probe",
+            "single",
+            "-s",
+            ".",
+            "-p",
+            "test.json",
+            "-f",
+            "btrfs",
+        ])
+        .unwrap();
+        assert_eq!(None, args.probed_filesystems());
+        assert!(parse(&["--check", "--skip-probe"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(Ok(Duration::from_secs(90)), parse_duration("90"));
+        assert_eq!(Ok(Duration::from_secs(2 * 60 * 60)), parse_duration("2h"));
+        assert!(parse_duration("3w").is_err());
+        assert_eq!(
+            Err("bad duration '18446744073709551615d'".to_owned()),
+            parse_duration("18446744073709551615d")
+        );
+    }
+
+    #[test]
+    fn test_opts_of() {
+        let opts: Vec<(String, String)> = ["btrfs=+compress", "ext4=noatime"]
+            .iter()
+            .map(|arg| parse_fs_opts(arg).unwrap())
+            .collect();
+        assert_eq!(Some("noatime".to_owned()), opts_of(&opts, "Ext4"));
+        assert_eq!(Some("+compress".to_owned()), opts_of(&opts, "btrfs"));
+        assert_eq!(None, opts_of(&opts, "xfs"));
+    }
 }
@@ -1,6 +1,19 @@
+use anyhow::{bail, Context};
 use serde::{Deserialize, Serialize};
 
-use crate::abstract_fs::{mutator::MutationWeights, operation::OperationWeights};
+use crate::abstract_fs::{
+    encode::Delays,
+    generator::{GeneratorConfig, FALLOCATE_MODES},
+    mutator::MutationWeights,
+    operation::OperationWeights,
+};
+use crate::fuzzing::crash_dir::CrashDirTemplate;
+use crate::fuzzing::greybox::feedback::lcov::LCovConfig;
+use crate::fuzzing::greybox::schedule::Scheduler;
+use crate::fuzzing::greybox::shard::ShardConfig;
+use crate::fuzzing::objective::allowlist::KnownDifference;
+use crate::mount::atime::AtimePolicy;
+use crate::mount::options::MountOptionsConfig;
 
 #[derive(Serialize, Deserialize)]
 pub struct Config {
@@ -8,14 +21,148 @@ pub struct Config {
     pub operation_weights: OperationWeights,
     pub mutation_weights: MutationWeights,
     pub max_workload_length: u16,
+    pub compile_jobs: u8,
     pub fs_name: String,
     pub hashing_enabled: bool,
+    /// Contents of regular files up to this size (in bytes) are hashed, not hashed at all if not set.
+    pub hash_content_limit: Option<u64>,
+    /// Paths (relative to test directory) matching any of these regexes are not hashed.
+    pub hash_exclude: Vec<String>,
+    /// Access and modification times are hashed (recent ones in hours, see [`crate::hasher::hasher::TIMES_GRANULARITY`])
+    /// and times set explicitly are compared with model.
+    pub hash_times: bool,
+    /// Space allocated to regular files is hashed, it depends on layout, so it usually needs allowlist
+    /// (see [`KnownDifference`]) for filesystems that allocate differently.
+    pub hash_allocated: bool,
     pub heartbeat_interval: u16,
     pub timeout: u8,
+    /// Operation that took longer (in milliseconds) is reported even if the whole run finished.
+    pub per_op_timeout: Option<u64>,
+    pub inconclusive_retries: u8,
+    pub max_alive_nodes: Option<usize>,
+    pub max_model_nodes: Option<usize>,
+    /// Standalone replays (`validate`, `single`) drop unreachable model nodes once `max_model_nodes` is reached.
+    pub compact_model: bool,
+    /// Number of path segments new nodes may have (root has none).
+    pub max_path_depth: Option<usize>,
+    pub min_free_space: u64,
+    /// Size of each block ram device in KiB, also capacity of filesystems without one (e.g. tmpfs).
+    pub ram_disk_size: usize,
+    pub special_mode_bits_rate: Option<f64>,
+    pub root_bias: f64,
+    /// Chance that new node is created in one of the deepest directories allowed.
+    pub deep_path_bias: f64,
+    /// Chance that operation targets a node used by one of the last few operations.
+    pub locality_bias: f64,
+    pub verify_writes: bool,
+    /// Data returned by reads is compared with bytes model expects.
+    pub verify_reads: bool,
+    /// Pairs of `data=<mode>` options of first and second filesystem, cycled through on each execution.
+    /// Empty mode keeps options of filesystem (e.g. for one without data modes).
+    pub data_modes: Vec<(String, String)>,
+    pub dir_nlink_enabled: bool,
+    pub fstest_export: bool,
+    /// Crash is replayed and both filesystems are checked on device (e.g. with `fsck`).
+    pub check_after_crash: bool,
+    pub delays: Option<Delays>,
+    pub control_socket: Option<String>,
+    pub crash_dir_template: Option<CrashDirTemplate>,
+    pub crash_report_url: Option<String>,
+    /// File (or `-` for stdout) where every saved crash is appended as a line of JSON.
+    pub ndjson_out: Option<String>,
+    pub atime_policy: Option<AtimePolicy>,
+    /// Relative difference allowed between space (and inodes) used on both filesystems.
+    pub statfs_tolerance: Option<f64>,
+    /// Relative difference allowed between blocks allocated for the same file (`STAT`), not compared if not set.
+    pub stat_blocks_tolerance: Option<f64>,
+    pub persistent_runs: Option<u32>,
+    pub mount_options: Option<MountOptionsConfig>,
+    /// Differences expected between filesystems, they are not reported.
+    pub known_differences: Option<Vec<KnownDifference>>,
+    /// Seed of random generators, picked from current time if not set.
+    pub rng_seed: Option<u64>,
+}
+
+impl Config {
+    /// Checks settings that can not be checked by parsing alone.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let rates = [
+            ("special_mode_bits_rate", self.special_mode_bits_rate),
+            ("root_bias", Some(self.root_bias)),
+            ("locality_bias", Some(self.locality_bias)),
+            ("deep_path_bias", Some(self.deep_path_bias)),
+        ];
+        for (name, rate) in rates {
+            if let Some(rate) = rate.filter(|rate| !(0.0..=1.0).contains(rate)) {
+                bail!("'{}' must be in [0, 1], got {}", name, rate);
+            }
+        }
+        if let Some(options) = &self.mount_options {
+            options
+                .validate()
+                .with_context(|| format!("bad 'mount_options'"))?;
+        }
+        Ok(())
+    }
+
+    /// Number of nodes model of standalone replay is compacted at, see [`crate::abstract_fs::validate::validate_compacting`].
+    pub fn compact_model_at(&self) -> Option<usize> {
+        self.max_model_nodes.filter(|_| self.compact_model)
+    }
+
+    pub fn generator(&self) -> GeneratorConfig {
+        GeneratorConfig {
+            weights: self.operation_weights.clone(),
+            max_nodes: self.max_model_nodes,
+            max_alive_nodes: self.max_alive_nodes,
+            max_path_depth: self.max_path_depth,
+            // depend on compared filesystems, see `Runner::generator`
+            max_xattr_space: None,
+            case_collisions: true,
+            fallocate_modes: FALLOCATE_MODES.iter().map(|mode| mode.to_vec()).collect(),
+            special_mode_bits: self.special_mode_bits_rate,
+            root_bias: self.root_bias,
+            deep_path_bias: self.deep_path_bias,
+            locality_bias: self.locality_bias,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct GreyboxConfig {
     pub max_mutations: u16,
     pub save_corpus: bool,
+    pub rename_loop_seed: bool,
+    pub scheduler: Scheduler,
+    pub seeds_dir: Option<String>,
+    pub targets: Option<String>,
+    pub shard: Option<ShardConfig>,
+    pub sync_dir: Option<String>,
+    pub checkpoint_dir: Option<String>,
+    /// Corpus entries are trimmed every that many executions, not trimmed at all if not set.
+    pub trim_interval: Option<usize>,
+    /// Diverging input is reduced (with the same reason) before crash is saved.
+    pub reduce_on_crash: bool,
+    /// Line coverage of user-space filesystems, used as feedback along with kernel coverage.
+    pub lcov: Option<LCovConfig>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_rates() {
+        let config = || -> Config { toml::from_str(include_str!("../config.toml")).unwrap() };
+        assert!(config().validate().is_ok());
+        let mut bad = config();
+        bad.root_bias = 1.5;
+        assert!(bad.validate().is_err());
+        let mut bad = config();
+        bad.special_mode_bits_rate = Some(-0.1);
+        assert!(bad.validate().is_err());
+        let mut bad = config();
+        bad.locality_bias = f64::NAN;
+        assert!(bad.validate().is_err());
+    }
 }
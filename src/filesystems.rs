@@ -1,10 +1,18 @@
-use crate::mount::{btrfs::Btrfs, ext4::Ext4, f2fs::F2FS, mount::FileSystemMount, xfs::XFS};
+use crate::mount::{
+    btrfs::Btrfs, ext2::Ext2, ext3::Ext3, ext4::Ext4, ext4_casefold::Ext4Casefold, f2fs::F2FS,
+    mount::FileSystemMount, overlayfs::Overlayfs, tmpfs::Tmpfs, xfs::XFS,
+};
 
 pub const FILESYSTEMS: &[&dyn FileSystemMount] = &[
     &Ext4::new(),
     &Btrfs::new(),
     &F2FS::new(),
     &XFS::new(),
+    &Ext2::new(),
+    &Ext3::new(),
+    &Tmpfs::new(),
+    &Overlayfs::new(),
+    &Ext4Casefold::new(),
     // your filesystem here
 ];
 
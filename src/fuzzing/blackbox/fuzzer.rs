@@ -1,12 +1,24 @@
 use anyhow::{Context, Ok};
-use log::{debug, info};
+use log::{debug, error, info, warn};
 use rand::prelude::StdRng;
 use rand::SeedableRng;
-use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::path::Path;
+use std::time::Instant;
 
+use crate::abstract_fs::fs::AbstractFS;
 use crate::abstract_fs::generator::generate_new;
+use crate::abstract_fs::trace::Trace;
+use crate::abstract_fs::workload::Workload;
 use crate::config::Config;
-use crate::fuzzing::common::{parse_trace, Fuzzer, Runner};
+use crate::fuzzing::common::{
+    campaign_seed, crashed_during, parse_trace, Fuzzer, Runner, ACCIDENT_REASON,
+};
+use crate::fuzzing::crash_dir::CrashKind;
+use crate::fuzzing::objective::hash::crashed_patterns;
+use crate::fuzzing::observer::ObserverVerdict;
+use crate::fuzzing::reason::DivergedPair;
+
+use super::pairwise::{diverging_pairs, FsRun, PairObjectives, PAIRWISE_REASON};
 
 use crate::mount::mount::FileSystemMount;
 
@@ -17,19 +29,175 @@ pub struct BlackBoxFuzzer {
 
 impl BlackBoxFuzzer {
     pub fn new(
-        config: Config,
+        mut config: Config,
         fst_mount: &'static dyn FileSystemMount,
         snd_mount: &'static dyn FileSystemMount,
     ) -> Self {
+        let rng = StdRng::seed_from_u64(campaign_seed(&mut config));
         Self {
             runner: Runner::new(fst_mount, snd_mount, config),
-            rng: StdRng::seed_from_u64(
-                SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis() as u64,
+            rng,
+        }
+    }
+
+    /// Compares every pair of filesystems, first two are the ones passed to [`BlackBoxFuzzer::new`].
+    pub fn with_others(mut self, others: &[&'static dyn FileSystemMount]) -> anyhow::Result<Self> {
+        self.runner = self.runner.with_others(others)?;
+        Ok(self)
+    }
+
+    /// Same objectives as for two filesystems are run for every pair of filesystems.
+    ///
+    /// Data modes and mount options are only chosen for the first two filesystems,
+    /// others are always mounted with their defaults.
+    fn compare_all(&mut self, input: &Workload, input_path: &Path) -> anyhow::Result<()> {
+        let runner = &mut self.runner;
+        let mut traces: Vec<(String, Trace)> = vec![
+            (
+                runner.fst_fs_name.clone(),
+                parse_trace(&runner.fst_trace_path)
+                    .with_context(|| format!("failed to parse first trace"))?,
             ),
+            (
+                runner.snd_fs_name.clone(),
+                parse_trace(&runner.snd_trace_path)
+                    .with_context(|| format!("failed to parse second trace"))?,
+            ),
+        ];
+        for other in runner.others.iter() {
+            traces.push((
+                other.fs_name.clone(),
+                parse_trace(&other.trace_path)
+                    .with_context(|| format!("failed to parse trace of '{}'", other.fs_name))?,
+            ));
+        }
+
+        let truncated: Vec<String> = traces
+            .iter()
+            .filter_map(|(fs_name, trace)| {
+                trace
+                    .crashed_at(input.ops.len())
+                    .map(|index| crashed_during(fs_name, input, index))
+            })
+            .collect();
+        if !truncated.is_empty() {
+            let reason = truncated.join("\n");
+            error!("{}", reason);
+            runner
+                .report_crash(
+                    input,
+                    input_path,
+                    runner.crashes_path.clone(),
+                    vec![],
+                    CrashKind::Truncated,
+                    &reason,
+                )
+                .with_context(|| format!("failed to report crash"))?;
+            self.runner.stats.crashes += 1;
+            self.show_stats();
+            return Ok(());
+        }
+
+        let expected = AbstractFS::expected_failures_of(input);
+        if traces.iter().all(|(_, trace)| trace.has_errors(&expected)) {
+            warn!("all traces contain errors, potential bug in model");
+            runner
+                .report_crash(
+                    input,
+                    input_path,
+                    runner.accidents_path.clone(),
+                    vec![],
+                    CrashKind::Accident,
+                    ACCIDENT_REASON,
+                )
+                .with_context(|| format!("failed to report accident"))?;
+            return Ok(());
         }
+
+        let mut hashes = vec![&runner.hash_objective.fst_fs, &runner.hash_objective.snd_fs];
+        hashes.extend(runner.others.iter().map(|other| &other.hash));
+        let hashing_enabled = runner.hash_objective.enabled();
+        let mut usages = match &runner.statfs_objective {
+            Some(objective) => vec![Some(&objective.fst_fs.usage), Some(&objective.snd_fs.usage)],
+            None => vec![None, None],
+        };
+        usages.extend(
+            runner
+                .others
+                .iter()
+                .map(|other| other.statfs.as_ref().map(|statfs| &statfs.usage)),
+        );
+        let mut case_insensitive = vec![
+            runner.fst_harness.fs_mount().case_insensitive(),
+            runner.snd_harness.fs_mount().case_insensitive(),
+        ];
+        case_insensitive.extend(
+            runner
+                .others
+                .iter()
+                .map(|other| other.harness.fs_mount().case_insensitive()),
+        );
+        let crashed = crashed_patterns(&AbstractFS::crashed_paths_of(input));
+        let runs: Vec<FsRun> = traces
+            .iter()
+            .zip(hashes)
+            .zip(usages)
+            .zip(case_insensitive)
+            .map(
+                |((((fs_name, trace), hash), usage), case_insensitive)| FsRun {
+                    fs_name,
+                    trace,
+                    hash: hashing_enabled.then_some(hash),
+                    content: hash.fs_content(),
+                    usage,
+                    case_insensitive,
+                },
+            )
+            .collect();
+        let pairs = diverging_pairs(
+            input,
+            &runs,
+            runner
+                .config
+                .known_differences
+                .as_deref()
+                .unwrap_or_default(),
+            &crashed,
+            &PairObjectives {
+                atime: runner.atime_objective.as_ref(),
+                dir_nlink: runner.dir_nlink_objective.as_ref(),
+                content: runner.content_objective.as_ref(),
+                slow_op: runner.slow_op_objective.as_ref(),
+                times: runner.times_objective.as_ref(),
+                statfs_tolerance: runner.config.statfs_tolerance,
+                stat_blocks_tolerance: runner.config.stat_blocks_tolerance,
+            },
+        )?;
+        if pairs.is_empty() {
+            return Ok(());
+        }
+        debug!("diverged pairs: {:?}", pairs);
+        runner.diverged_pairs = pairs
+            .iter()
+            .map(|(i, j, detected_by)| DivergedPair {
+                fst: runs[*i].fs_name.to_owned(),
+                snd: runs[*j].fs_name.to_owned(),
+                detected_by: detected_by.iter().map(|name| name.to_string()).collect(),
+            })
+            .collect();
+        runner
+            .report_crash(
+                input,
+                input_path,
+                runner.crashes_path.clone(),
+                vec![],
+                CrashKind::Divergence,
+                PAIRWISE_REASON,
+            )
+            .with_context(|| format!("failed to report crash"))?;
+        self.runner.stats.crashes += 1;
+        self.show_stats();
+        Ok(())
     }
 }
 
@@ -39,23 +207,21 @@ impl Fuzzer for BlackBoxFuzzer {
         let input = generate_new(
             &mut self.rng,
             self.runner.config.max_workload_length.into(),
-            &self.runner.config.operation_weights,
+            &self.runner.generator(),
         );
+        self.runner().update_model_stats(&input);
 
         let input_path = self.runner().compile_test(&input)?;
 
-        self.runner().run_harness(&input_path)?;
-
-        let fst_trace = parse_trace(&self.runner().fst_trace_path)
-            .with_context(|| format!("failed to parse first trace"))?;
-        let snd_trace = parse_trace(&self.runner().snd_trace_path)
-            .with_context(|| format!("failed to parse second trace"))?;
-
-        if self.detect_errors(&input, &input_path, &fst_trace, &snd_trace)? {
-            return Ok(());
+        if !self.runner.others.is_empty() {
+            if let ObserverVerdict::Inconclusive(reason) = self.runner().run_harness(&input_path)? {
+                debug!("skipping objectives, run is inconclusive: {}", reason);
+                return Ok(());
+            }
+            return self.compare_all(&input, &input_path);
         }
 
-        self.do_objective(&input, &input_path, &fst_trace, &snd_trace)?;
+        self.run_objectives(&input, &input_path)?;
 
         Ok(())
     }
@@ -65,13 +231,16 @@ impl Fuzzer for BlackBoxFuzzer {
         let since_start = Instant::now().duration_since(self.runner.stats.start);
         let secs = since_start.as_secs();
         info!(
-            "crashes: {}, executions: {}, exec/s: {:.2}, time: {:02}h:{:02}m:{:02}s",
+            "crashes: {} ({} unique), executions: {}, model nodes (peak): {}, exec/s: {:.2}, time: {:02}h:{:02}m:{:02}s{}",
             self.runner.stats.crashes,
+            self.runner.stats.unique_crashes,
             self.runner.stats.executions,
-            (self.runner.stats.executions as f64) / (secs as f64),
+            self.runner.stats.peak_model_nodes,
+            self.runner.stats.rate.per_sec(),
             secs / (60 * 60),
             (secs / (60)) % 60,
             secs % 60,
+            self.runner.stats.progress_summary(),
         );
     }
 
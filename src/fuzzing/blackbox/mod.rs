@@ -1 +1,2 @@
 pub mod fuzzer;
+pub mod pairwise;
@@ -0,0 +1,444 @@
+use anyhow::Context;
+
+use crate::{
+    abstract_fs::{fs::AbstractFS, trace::Trace, workload::Workload},
+    fuzzing::{
+        common::Detected,
+        objective::{
+            allowlist::{Allowlist, KnownDifference},
+            atime::AtimeObjective,
+            case::CaseObjective,
+            content::ContentObjective,
+            durability::DurabilityObjective,
+            failure::FailureObjective,
+            hash::HashHolder,
+            nlink::DirNlinkObjective,
+            readlink::ReadlinkObjective,
+            signal::SignalObjective,
+            slow_op::SlowOpObjective,
+            stat::StatObjective,
+            statfs,
+            times::TimesObjective,
+            trace::TraceObjective,
+        },
+        observer::statfs::FsUsage,
+    },
+    hasher::hasher::FileInfo,
+};
+
+pub const PAIRWISE_REASON: &str = "detected by pairwise objective";
+
+/// Results of workload on one of compared filesystems.
+pub struct FsRun<'a> {
+    pub fs_name: &'a str,
+    pub trace: &'a Trace,
+    /// Hashed filesystem contents, `None` if hashing is disabled.
+    pub hash: Option<&'a HashHolder>,
+    /// Files and their attributes (see [`HashHolder::fs_content`]), compared with model by times objective.
+    pub content: &'a [FileInfo],
+    /// Space and inodes used by workload, `None` if statfs objective is disabled.
+    pub usage: Option<&'a FsUsage>,
+    pub case_insensitive: bool,
+}
+
+/// Optional objectives compared for every pair, the same as enabled for the first two filesystems.
+///
+/// Objectives that are always enabled (traces, failures, stat, durability, signals and symlinks)
+/// are compared too, along with hashes if filesystems were hashed.
+#[derive(Default)]
+pub struct PairObjectives<'a> {
+    pub atime: Option<&'a AtimeObjective>,
+    pub dir_nlink: Option<&'a DirNlinkObjective>,
+    pub content: Option<&'a ContentObjective>,
+    pub slow_op: Option<&'a SlowOpObjective>,
+    pub times: Option<&'a TimesObjective>,
+    pub statfs_tolerance: Option<f64>,
+    pub stat_blocks_tolerance: Option<f64>,
+}
+
+/// Every pair of filesystems (as indices, first one is lower) that diverged, with objectives that detected it.
+///
+/// Pair is compared like the first two filesystems are (see [`crate::fuzzing::common::Runner::detect`]).
+/// Trace fields and attributes known to differ for pair are not compared,
+/// neither are files matching `crashed` patterns (see [`HashHolder::diff`]).
+pub fn diverging_pairs(
+    input: &Workload,
+    runs: &[FsRun],
+    known_differences: &[KnownDifference],
+    crashed: &[String],
+    objectives: &PairObjectives,
+) -> anyhow::Result<Vec<(usize, usize, Vec<&'static str>)>> {
+    // outcomes of these operations depend on data that survived crash point
+    let crash_dependent = AbstractFS::crash_dependent_of(input);
+    let mut pairs = vec![];
+    for (i, fst) in runs.iter().enumerate() {
+        for (j, snd) in runs.iter().enumerate().skip(i + 1) {
+            let detected = detect_pair(
+                input,
+                fst,
+                snd,
+                &Allowlist::for_pair(known_differences, fst.fs_name, snd.fs_name),
+                &crash_dependent,
+                crashed,
+                objectives,
+            )?;
+            let detected_by: Vec<&'static str> = detected
+                .objectives()
+                .into_iter()
+                .filter_map(|(name, detected)| detected.then_some(name))
+                .collect();
+            if !detected_by.is_empty() {
+                pairs.push((i, j, detected_by));
+            }
+        }
+    }
+    Ok(pairs)
+}
+
+fn detect_pair(
+    input: &Workload,
+    fst: &FsRun,
+    snd: &FsRun,
+    allowlist: &Allowlist,
+    crash_dependent: &[u32],
+    crashed: &[String],
+    objectives: &PairObjectives,
+) -> anyhow::Result<Detected> {
+    let case_objective = (fst.case_insensitive || snd.case_insensitive)
+        .then(|| CaseObjective::new(fst.case_insensitive, snd.case_insensitive));
+    // only one of colliding names is added on filesystem that folds case
+    let (colliding, crashed) = match &case_objective {
+        Some(objective) => (
+            objective.expected_differences(input),
+            [crashed, &objective.colliding_patterns(input)].concat(),
+        ),
+        None => (vec![], crashed.to_vec()),
+    };
+    let fst_certain = fst.trace.without(crash_dependent).without(&colliding);
+    let snd_certain = snd.trace.without(crash_dependent).without(&colliding);
+    let mut detected = Detected {
+        trace: TraceObjective::new()
+            .with_ignored_fields(allowlist.trace_fields.clone())
+            .is_interesting(&fst_certain, &snd_certain)
+            .with_context(|| {
+                format!(
+                    "failed to do trace objective for '{}' and '{}'",
+                    fst.fs_name, snd.fs_name
+                )
+            })?,
+        hash: match (fst.hash, snd.hash) {
+            (Some(fst_hash), Some(snd_hash)) => {
+                fst_hash.differs_from(snd_hash, &allowlist.hash_attributes, &crashed)
+            }
+            _ => false,
+        },
+        failure: FailureObjective::new().is_interesting(
+            input,
+            &fst.trace.without(&colliding),
+            &snd.trace.without(&colliding),
+        ),
+        ..Detected::default()
+    };
+    detected.atime = objectives
+        .atime
+        .is_some_and(|objective| objective.is_interesting(input, fst.trace, snd.trace));
+    detected.dir_nlink = objectives
+        .dir_nlink
+        .is_some_and(|objective| objective.is_interesting(input, fst.trace, snd.trace));
+    detected.content = objectives
+        .content
+        .is_some_and(|objective| objective.is_interesting(input, fst.trace, snd.trace));
+    detected.case = case_objective
+        .is_some_and(|objective| objective.is_interesting(input, fst.trace, snd.trace));
+    detected.statfs = match (objectives.statfs_tolerance, fst.usage, snd.usage) {
+        (Some(tolerance), Some(fst_usage), Some(snd_usage)) => {
+            !statfs::diff(fst_usage, snd_usage, tolerance).is_empty()
+        }
+        _ => false,
+    };
+    detected.slow_op = objectives
+        .slow_op
+        .is_some_and(|objective| objective.is_interesting(fst.trace, snd.trace));
+    detected.stat = StatObjective::new(objectives.stat_blocks_tolerance)
+        .with_ignored_fields(allowlist.trace_fields.clone())
+        .is_interesting(&fst_certain, &snd_certain);
+    detected.durability = DurabilityObjective::new().is_interesting(input, fst.trace, snd.trace);
+    detected.signal = SignalObjective::new().is_interesting(fst.trace, snd.trace);
+    detected.readlink = ReadlinkObjective::new()
+        .with_ignored_fields(&allowlist.trace_fields)
+        .is_interesting(fst.trace, snd.trace);
+    detected.times = objectives
+        .times
+        .is_some_and(|objective| objective.is_interesting(input, fst.content, snd.content));
+    Ok(detected)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, os::unix::fs::PermissionsExt};
+
+    use crate::{
+        fuzzing::objective::hash::crashed_patterns,
+        hasher::hasher::{Attribute, HasherOptions},
+        mount::{atime::AtimePolicy, ext4::Ext4, mount::FileSystemMount},
+    };
+
+    use super::*;
+
+    /// Indices of diverging pairs, optional objectives are disabled.
+    fn pairs(
+        runs: &[FsRun],
+        known_differences: &[KnownDifference],
+        crashed: &[String],
+    ) -> anyhow::Result<Vec<(usize, usize)>> {
+        Ok(diverging_pairs(
+            &Workload::new(),
+            runs,
+            known_differences,
+            crashed,
+            &PairObjectives::default(),
+        )?
+        .into_iter()
+        .map(|(i, j, _)| (i, j))
+        .collect())
+    }
+
+    fn trace(remove: &str) -> Trace {
+        Trace::try_parse(format!(
+            "Index,Command,ReturnCode,Errno,Extra\n0,MKDIR,0,Success(0),\n1,REMOVE,{},\n",
+            remove
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_only_diverging_pairs_reported() {
+        let ok = trace("0,Success(0)");
+        let failed = trace("-1,Directory not empty(39)");
+        let runs = [
+            FsRun {
+                fs_name: "Ext4",
+                trace: &ok,
+                hash: None,
+                usage: None,
+                content: &[],
+                case_insensitive: false,
+            },
+            FsRun {
+                fs_name: "Xfs",
+                trace: &ok,
+                hash: None,
+                usage: None,
+                content: &[],
+                case_insensitive: false,
+            },
+            FsRun {
+                fs_name: "Btrfs",
+                trace: &failed,
+                hash: None,
+                usage: None,
+                content: &[],
+                case_insensitive: false,
+            },
+        ];
+        let pairs = diverging_pairs(
+            &Workload::new(),
+            &runs,
+            &[],
+            &[],
+            &PairObjectives::default(),
+        )
+        .unwrap();
+        assert_eq!(vec![(0, 2, vec!["trace"]), (1, 2, vec!["trace"])], pairs);
+        let names: Vec<(&str, &str)> = pairs
+            .iter()
+            .map(|(i, j, _)| (runs[*i].fs_name, runs[*j].fs_name))
+            .collect();
+        assert_eq!(vec![("Ext4", "Btrfs"), ("Xfs", "Btrfs")], names);
+    }
+
+    #[test]
+    fn test_hash_divergence() {
+        let temp_dir = env::temp_dir().join("DIFFuzzer-pairwise-hash-test");
+        fs::remove_dir_all(temp_dir.as_path()).unwrap_or(());
+        let holder = |name: &str, mode: u32, data: &str| {
+            let dir = temp_dir.join(name);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("0"), data).unwrap();
+            fs::set_permissions(dir.join("0"), fs::Permissions::from_mode(mode)).unwrap();
+            let mut holder = HashHolder::new(
+                dir.into_boxed_path(),
+                Ext4::new().get_internal_dirs(),
+                HasherOptions::all(),
+            );
+            holder.calc_and_save_hash();
+            holder
+        };
+        let (ext4, xfs, btrfs) = (
+            holder("ext4", 0o644, "data"),
+            holder("xfs", 0o600, "data"),
+            holder("btrfs", 0o644, "data"),
+        );
+        let ok = trace("0,Success(0)");
+        let run = |fs_name, hash| FsRun {
+            fs_name,
+            trace: &ok,
+            hash: Some(hash),
+            usage: None,
+            content: &[],
+            case_insensitive: false,
+        };
+        let runs = [run("Ext4", &ext4), run("XFS", &xfs), run("Btrfs", &btrfs)];
+        assert_eq!(vec![(0, 1), (1, 2)], pairs(&runs, &[], &[]).unwrap());
+        let known = [KnownDifference {
+            filesystems: ["xfs".to_owned(), "ext4".to_owned()],
+            hash_attributes: vec![Attribute::Mode],
+            trace_fields: vec![],
+        }];
+        assert_eq!(vec![(1, 2)], pairs(&runs, &known, &[]).unwrap());
+
+        // file could have lost data at crash point
+        let truncated = holder("truncated", 0o644, "");
+        let runs = [run("Ext4", &ext4), run("Btrfs", &truncated)];
+        assert_eq!(vec![(0, 1)], pairs(&runs, &[], &[]).unwrap());
+        let crashed = crashed_patterns(&["/0".into()]);
+        assert!(pairs(&runs, &[], &crashed).unwrap().is_empty());
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn test_known_differences() {
+        let trace = |offset: u32| {
+            Trace::try_parse(format!(
+                "Index,Command,ReturnCode,Errno,Extra\n0,LSEEK,0,Success(0),offset={}\n",
+                offset
+            ))
+            .unwrap()
+        };
+        let (ext4, xfs, btrfs) = (trace(4096), trace(0), trace(8192));
+        let runs = [
+            FsRun {
+                fs_name: "Ext4",
+                trace: &ext4,
+                hash: None,
+                usage: None,
+                content: &[],
+                case_insensitive: false,
+            },
+            FsRun {
+                fs_name: "XFS",
+                trace: &xfs,
+                hash: None,
+                usage: None,
+                content: &[],
+                case_insensitive: false,
+            },
+            FsRun {
+                fs_name: "Btrfs",
+                trace: &btrfs,
+                hash: None,
+                usage: None,
+                content: &[],
+                case_insensitive: false,
+            },
+        ];
+        let known = [KnownDifference {
+            filesystems: ["xfs".to_owned(), "ext4".to_owned()],
+            hash_attributes: vec![],
+            trace_fields: vec!["offset".to_owned()],
+        }];
+        assert_eq!(vec![(0, 2), (1, 2)], pairs(&runs, &known, &[]).unwrap());
+    }
+
+    #[test]
+    fn test_atime_and_statfs_divergence() {
+        let read = |atime: i64| {
+            Trace::try_parse(format!(
+                "Index,Command,ReturnCode,Errno,Extra\n0,READ,0,Success(0),atime0=1 atime={} mtime=1 ctime=1\n",
+                atime
+            ))
+            .unwrap()
+        };
+        let (updated, unchanged) = (read(2), read(1));
+        let usage = |bytes| FsUsage {
+            bytes,
+            files: Some(1),
+        };
+        let (small, large) = (usage(4096), usage(65536));
+        let run = |fs_name, trace, usage| FsRun {
+            fs_name,
+            trace,
+            hash: None,
+            usage: Some(usage),
+            content: &[],
+            case_insensitive: false,
+        };
+        let runs = [
+            run("Ext4", &unchanged, &small),
+            run("XFS", &unchanged, &large),
+            run("Btrfs", &updated, &small),
+        ];
+        let atime = AtimeObjective::new(AtimePolicy::Noatime);
+        let objectives = PairObjectives {
+            atime: Some(&atime),
+            statfs_tolerance: Some(0.5),
+            ..PairObjectives::default()
+        };
+        assert_eq!(
+            vec![
+                (0, 1, vec!["statfs"]),
+                (0, 2, vec!["atime"]),
+                (1, 2, vec!["atime", "statfs"])
+            ],
+            diverging_pairs(&Workload::new(), &runs, &[], &[], &objectives).unwrap()
+        );
+        assert!(pairs(&runs, &[], &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_every_objective_compared() {
+        let rename = |duration: u64| {
+            Trace::try_parse(format!(
+                "Index,Command,ReturnCode,Errno,Extra,Duration\n0,RENAME,0,Success(0),,{}\n",
+                duration
+            ))
+            .unwrap()
+        };
+        let (fast, slow) = (rename(10), rename(5_000_000));
+        let killed = Trace::try_parse(
+            "Index,Command,ReturnCode,Errno,Extra\n0,SIGNAL,-1,Success(0),signal=SIGBUS\n"
+                .to_owned(),
+        )
+        .unwrap();
+        let run = |fs_name, trace| FsRun {
+            fs_name,
+            trace,
+            hash: None,
+            content: &[],
+            usage: None,
+            case_insensitive: false,
+        };
+        let runs = [run("Ext4", &fast), run("XFS", &slow), run("Btrfs", &fast)];
+        let slow_op = SlowOpObjective::new(100);
+        let objectives = PairObjectives {
+            slow_op: Some(&slow_op),
+            ..PairObjectives::default()
+        };
+        assert_eq!(
+            vec![
+                (0, 1, vec!["slow operation"]),
+                (1, 2, vec!["slow operation"])
+            ],
+            diverging_pairs(&Workload::new(), &runs, &[], &[], &objectives).unwrap()
+        );
+        assert!(pairs(&runs, &[], &[]).unwrap().is_empty());
+
+        let runs = [run("Ext4", &fast), run("XFS", &fast), run("Btrfs", &killed)];
+        let detected = diverging_pairs(&Workload::new(), &runs, &[], &[], &objectives).unwrap();
+        assert_eq!(2, detected.len());
+        assert!(detected
+            .iter()
+            .all(|(_, j, detected_by)| *j == 2 && detected_by.contains(&"signal")));
+    }
+}
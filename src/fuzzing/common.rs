@@ -1,26 +1,69 @@
+use crate::abstract_fs::fs::AbstractFS;
+use crate::abstract_fs::generator::GeneratorConfig;
+use crate::abstract_fs::mutator::repair;
 use crate::abstract_fs::trace::{Trace, TRACE_FILENAME};
 
 use crate::abstract_fs::workload::Workload;
 use crate::config::Config;
-use crate::fuzzing::objective::trace::TraceObjective;
-use crate::harness::{ConsolePipe, Harness};
-use crate::hasher::hasher::FileDiff;
-use crate::mount::mount::FileSystemMount;
-use crate::save::{save_diff, save_output, save_testcase};
-use crate::temp_dir::setup_temp_dir;
-use anyhow::{Context, Ok};
+use crate::fuzzing::objective::allowlist::Allowlist;
+use crate::fuzzing::objective::hash::HashHolder;
+use crate::fuzzing::objective::trace::{first_divergence, TraceObjective};
+use crate::harness::{ConsolePipe, Harness, HarnessOptions};
+use crate::hasher::hasher::{with_excluded, Attribute, FileDiff, HasherOptions};
+use crate::mount::atime::with_atime_policy;
+use crate::mount::data_mode::with_data_modes;
+use crate::mount::mount::{devices, FileSystemMount, DEVICES_PER_INSTANCE};
+use crate::mount::options::{choose_options, with_mount_options};
+use crate::save::{
+    save_check, save_diff, save_fstest, save_output, save_statfs_diff, save_testcase, side_name,
+};
+use crate::temp_dir::Instance;
+use anyhow::{bail, Context, Ok};
 use log::{debug, error, info, warn};
+use rand::{rngs::StdRng, SeedableRng};
+use signal_hook::{
+    consts::{SIGINT, SIGTERM},
+    flag,
+};
 use std::cell::RefCell;
+use std::cmp::max;
+use std::collections::{HashSet, VecDeque};
 use std::fs::read_to_string;
 use std::path::Path;
 use std::rc::Rc;
-use std::time::Instant;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::{fs, io};
 
+use super::control::{ControlCommand, ControlResponse, ControlSocket};
+use super::crash_dir::{CrashInfo, CrashKind};
+use super::objective::atime::AtimeObjective;
+use super::objective::case::CaseObjective;
+use super::objective::content::ContentObjective;
+use super::objective::durability::DurabilityObjective;
+use super::objective::failure::FailureObjective;
 use super::objective::hash::HashObjective;
+use super::objective::nlink::DirNlinkObjective;
+use super::objective::readlink::ReadlinkObjective;
+use super::objective::signal::SignalObjective;
+use super::objective::slow_op::SlowOpObjective;
+use super::objective::stat::StatObjective;
+use super::objective::statfs::StatfsObjective;
+use super::objective::times::TimesObjective;
+use super::observer::statfs::StatfsObserver;
+use super::observer::{Observer, ObserverVerdict};
+use super::rate::{RollingRate, RATE_WINDOW};
+use super::reason::{DivergedPair, Reason};
+use super::reducer::reduce_divergence;
+use super::reporter::{
+    report_remote, CrashReport, CrashReporter, FileSystemReporter, HttpReporter, NdjsonReporter,
+};
 
 pub struct Runner {
     pub config: Config,
+    /// Slot of this runner among instances on the host, temporary directory, devices and mountpoints are its own.
+    pub instance: Instance,
 
     pub fst_exec_dir: Box<Path>,
     pub snd_exec_dir: Box<Path>,
@@ -38,55 +81,225 @@ pub struct Runner {
 
     pub trace_objective: TraceObjective,
     pub hash_objective: HashObjective,
+    pub atime_objective: Option<AtimeObjective>,
+    pub dir_nlink_objective: Option<DirNlinkObjective>,
+    pub content_objective: Option<ContentObjective>,
+    pub case_objective: Option<CaseObjective>,
+    pub statfs_objective: Option<StatfsObjective>,
+    pub slow_op_objective: Option<SlowOpObjective>,
+    pub stat_objective: StatObjective,
+    pub durability_objective: DurabilityObjective,
+    pub signal_objective: SignalObjective,
+    pub readlink_objective: ReadlinkObjective,
+    pub times_objective: Option<TimesObjective>,
+    pub failure_objective: FailureObjective,
 
     pub fst_fs_name: String,
     pub snd_fs_name: String,
     pub fst_harness: Harness,
     pub snd_harness: Harness,
+    /// Observers run on the first and second harness besides the built-in ones.
+    pub fst_observers: Vec<Box<dyn Observer>>,
+    pub snd_observers: Vec<Box<dyn Observer>>,
+
+    pub fst_data_modes: Vec<&'static dyn FileSystemMount>,
+    pub snd_data_modes: Vec<&'static dyn FileSystemMount>,
+    /// Index of data mode pair used for current run.
+    pub data_mode: Option<usize>,
+
+    pub fst_option_mounts: Vec<&'static dyn FileSystemMount>,
+    pub snd_option_mounts: Vec<&'static dyn FileSystemMount>,
+    /// Indices of option sets picked for current run.
+    pub mount_options: Option<(usize, usize)>,
+    pub mount_options_rng: StdRng,
 
     pub stats: Stats,
+    pub recent_crashes: VecDeque<String>,
+    /// Signatures of crashes saved so far, see [`Reason::signature`].
+    crash_signatures: HashSet<String>,
+
+    /// Bound once fuzzing loop starts, see [`Runner::bind_control`].
+    pub control: Option<ControlSocket>,
+    /// Set to stop fuzzing loop after the current test, see [`stop_on_signals`].
+    pub stop: Arc<AtomicBool>,
+    /// Remote sinks notified after crash is saved to disk.
+    pub reporters: Vec<Box<dyn CrashReporter>>,
+
+    /// Filesystems compared besides the first two, see [`Runner::with_others`].
+    pub others: Vec<OtherFs>,
+    /// Pairs of filesystems that diverged, recorded in reason of the next reported crash.
+    pub diverged_pairs: Vec<DivergedPair>,
+}
+
+/// Filesystem that is run along with the first two in N-way comparison.
+///
+/// Data modes and mount options are only applied to the first two filesystems.
+pub struct OtherFs {
+    pub fs_name: String,
+    pub exec_dir: Box<Path>,
+    pub trace_path: Box<Path>,
+    pub stdout: ConsolePipe,
+    pub stderr: ConsolePipe,
+    pub harness: Harness,
+    pub hash: HashHolder,
+    /// Usage of filesystem, `None` if statfs objective is disabled.
+    pub statfs: Option<StatfsObserver>,
+}
+
+const RECENT_CRASHES: usize = 16;
+
+/// Reason fuzzing loop stopped.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LoopExit {
+    /// All tests were run.
+    Finished,
+    Error,
+    /// Crash was found in fail-fast mode.
+    Crash,
+    /// Time budget was used up before all tests were run.
+    OutOfTime,
+    /// Stop was requested (e.g. by `SIGINT`) before all tests were run.
+    Interrupted,
+}
+
+/// First `SIGINT` or `SIGTERM` sets `stop`, so fuzzing loop finishes the current test and returns,
+/// filesystems are torn down once fuzzer is dropped. The second one exits immediately.
+pub fn stop_on_signals(stop: &Arc<AtomicBool>) -> anyhow::Result<()> {
+    for signal in [SIGINT, SIGTERM] {
+        flag::register_conditional_shutdown(signal, 1, stop.clone())
+            .with_context(|| format!("failed to register handler of signal {}", signal))?;
+        flag::register(signal, stop.clone())
+            .with_context(|| format!("failed to register handler of signal {}", signal))?;
+    }
+    Ok(())
 }
 
 pub trait Fuzzer {
-    fn run(&mut self, test_count: Option<u64>) {
+    /// With `fail_fast` loop stops after the first crash is saved (accidents are not counted).
+    ///
+    /// With `max_runtime` loop stops once that much time passed, whether all tests were run or not,
+    /// test that is already running is not interrupted. Same goes for stop requested by setting [`Runner::stop`].
+    fn run(
+        &mut self,
+        test_count: Option<u64>,
+        max_runtime: Option<Duration>,
+        fail_fast: bool,
+    ) -> LoopExit {
         info!("starting fuzzing loop");
-        self.runner().stats.start = Instant::now();
-        match test_count {
-            None => loop {
-                if self.runs() {
-                    return;
+        self.runner().bind_control();
+        let stats = &mut self.runner().stats;
+        stats.start = Instant::now();
+        stats.test_count = test_count;
+        stats.rate.record(stats.start, stats.executions);
+        let mut remaining = test_count;
+        while remaining != Some(0) {
+            if max_runtime.is_some_and(|limit| self.runner().stats.start.elapsed() >= limit) {
+                info!("stopping after running out of time");
+                self.show_stats();
+                if let Err(err) = self.save_checkpoint() {
+                    warn!("failed to save checkpoint: {:?}", err);
                 }
-            },
-            Some(count) => {
-                for _ in 0..count {
-                    if self.runs() {
-                        return;
-                    }
+                return LoopExit::OutOfTime;
+            }
+            if self.runner().stop.load(Ordering::Relaxed) {
+                info!("stopping on request");
+                self.show_stats();
+                if let Err(err) = self.save_checkpoint() {
+                    warn!("failed to save checkpoint: {:?}", err);
                 }
+                return LoopExit::Interrupted;
+            }
+            if let Some(exit) = self.runs(fail_fast) {
+                return exit;
             }
+            remaining = remaining.map(|count| count - 1);
         }
+        LoopExit::Finished
     }
 
-    fn runs(&mut self) -> bool {
+    fn runs(&mut self, fail_fast: bool) -> Option<LoopExit> {
+        let executions = self.runner().stats.executions;
+        self.runner().select_data_modes(executions);
+        self.runner().select_mount_options(executions);
         match self.fuzz_one() {
             Err(err) => {
                 error!("{:?}", err);
-                return true;
+                return Some(LoopExit::Error);
             }
-            _ => self.runner().stats.executions += 1,
+            _ => {
+                let stats = &mut self.runner().stats;
+                stats.executions += 1;
+                stats.rate.record(Instant::now(), stats.executions);
+            }
+        }
+        if fail_fast && self.runner().stats.crashes > 0 {
+            info!("stopping on first crash (fail-fast)");
+            return Some(LoopExit::Crash);
         }
+        self.serve_control();
         if Instant::now()
             .duration_since(self.runner().stats.last_time_showed)
             .as_secs()
             > self.runner().config.heartbeat_interval.into()
         {
             self.show_stats();
+            if let Err(err) = self.save_checkpoint() {
+                warn!("failed to save checkpoint: {:?}", err);
+            }
         }
-        false
+        None
     }
 
     fn fuzz_one(&mut self) -> anyhow::Result<()>;
 
+    /// Saves state needed to resume fuzzing after restart, if fuzzer supports it.
+    fn save_checkpoint(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn serve_control(&mut self) {
+        let Some(control) = self.runner().control.take() else {
+            return;
+        };
+        if let Err(err) = control.serve(|cmd| self.control_response(cmd)) {
+            warn!("control socket failure: {:?}", err);
+        }
+        self.runner().control = Some(control);
+    }
+
+    fn control_response(&mut self, cmd: ControlCommand) -> ControlResponse {
+        match cmd {
+            ControlCommand::Stats => {
+                let stats = &self.runner().stats;
+                ControlResponse::Stats {
+                    executions: stats.executions,
+                    crashes: stats.crashes,
+                    unique_crashes: stats.unique_crashes,
+                    inconclusive: stats.inconclusive,
+                    peak_model_nodes: stats.peak_model_nodes,
+                    repaired_ops: stats.repaired_ops,
+                    uptime_secs: stats.start.elapsed().as_secs(),
+                    exec_per_sec: stats.rate.per_sec(),
+                    progress: stats.progress(),
+                }
+            }
+            ControlCommand::ListCrashes => {
+                ControlResponse::Crashes(self.runner().recent_crashes.iter().cloned().collect())
+            }
+            ControlCommand::DumpCorpus => ControlResponse::Corpus(self.corpus()),
+        }
+    }
+
+    /// Inputs kept by fuzzer, if any.
+    fn corpus(&self) -> Vec<Workload> {
+        vec![]
+    }
+
+    /// Diverging inputs are reduced before they are saved, if fuzzer supports it.
+    fn reduces_crashes(&mut self) -> bool {
+        false
+    }
+
     fn do_objective(
         &mut self,
         input: &Workload,
@@ -94,34 +307,134 @@ pub trait Fuzzer {
         fst_trace: &Trace,
         snd_trace: &Trace,
     ) -> anyhow::Result<bool> {
+        let Some((mut reason, mut diff)) = self.divergence(input, fst_trace, snd_trace)? else {
+            return Ok(false);
+        };
+        let mut input = input.clone();
+        let mut input_path = input_path.to_path_buf().into_boxed_path();
+        if self.reduces_crashes() {
+            let reduced = reduce_divergence(&input, &reason, |workload| {
+                Ok(self
+                    .rerun_divergence(workload)?
+                    .map(|(_, reason, _)| reason))
+            })?;
+            // outputs saved with crash must be of the reduced input, so it is run once more
+            match self.rerun_divergence(&reduced)? {
+                Some((path, new_reason, new_diff)) => {
+                    (input, input_path, reason, diff) = (reduced, path, new_reason, new_diff);
+                }
+                None => {
+                    warn!("reduced input does not diverge again, saving original one");
+                    if let Some((path, new_reason, new_diff)) = self.rerun_divergence(&input)? {
+                        (input_path, reason, diff) = (path, new_reason, new_diff);
+                    }
+                }
+            }
+        }
+        let runner = self.runner();
+        runner
+            .report_crash(
+                &input,
+                &input_path,
+                runner.crashes_path.clone(),
+                diff,
+                CrashKind::Divergence,
+                &reason,
+            )
+            .with_context(|| format!("failed to report crash"))?;
+        self.runner().stats.crashes += 1;
+        self.show_stats();
+        Ok(true)
+    }
+
+    /// Runs input on both harnesses and checks objectives.
+    ///
+    /// Returns `true` if run needs no further processing: it is inconclusive, accident or crash.
+    fn run_objectives(&mut self, input: &Workload, input_path: &Path) -> anyhow::Result<bool> {
+        if let ObserverVerdict::Inconclusive(reason) = self.runner().run_harness(input_path)? {
+            debug!("skipping objectives, run is inconclusive: {}", reason);
+            return Ok(true);
+        }
+
+        let fst_trace = parse_trace(&self.runner().fst_trace_path)
+            .with_context(|| format!("failed to parse first trace"))?;
+        let snd_trace = parse_trace(&self.runner().snd_trace_path)
+            .with_context(|| format!("failed to parse second trace"))?;
+
+        if self.detect_truncation(input, input_path, &fst_trace, &snd_trace)? {
+            return Ok(true);
+        }
+
+        if self.detect_errors(input, input_path, &fst_trace, &snd_trace)? {
+            return Ok(true);
+        }
+
+        self.do_objective(input, input_path, &fst_trace, &snd_trace)
+    }
+
+    /// Runs input again with fresh harnesses, returns path to its executable, `None` if run is inconclusive.
+    fn rerun(&mut self, input: &Workload) -> anyhow::Result<Option<Box<Path>>> {
+        // previous run diverged, its state is not reused
+        self.runner().expire_harnesses();
+        let input_path = self.runner().compile_test(input)?;
+        if let ObserverVerdict::Inconclusive(_) = self.runner().run_harness(&input_path)? {
+            return Ok(None);
+        }
+        Ok(Some(input_path))
+    }
+
+    /// Runs input again, returns path to its executable, reason of divergence and hash differences.
+    ///
+    /// Runs that would be reported as accident or truncated trace are not divergences,
+    /// so reduction never turns crash into one of them.
+    fn rerun_divergence(
+        &mut self,
+        input: &Workload,
+    ) -> anyhow::Result<Option<(Box<Path>, String, Vec<FileDiff>)>> {
+        let Some(input_path) = self.rerun(input)? else {
+            return Ok(None);
+        };
+        let fst_trace = parse_trace(&self.runner().fst_trace_path)
+            .with_context(|| format!("failed to parse first trace"))?;
+        let snd_trace = parse_trace(&self.runner().snd_trace_path)
+            .with_context(|| format!("failed to parse second trace"))?;
+        let expected = AbstractFS::expected_failures_of(input);
+        if fst_trace.has_errors(&expected) && snd_trace.has_errors(&expected) {
+            debug!("both traces of rerun contain errors");
+            return Ok(None);
+        }
+        if let Some(reason) = self
+            .runner()
+            .truncation_reason(input, &fst_trace, &snd_trace)
+        {
+            debug!("rerun is truncated: {}", reason);
+            return Ok(None);
+        }
+        Ok(self
+            .divergence(input, &fst_trace, &snd_trace)?
+            .map(|(reason, diff)| (input_path, reason, diff)))
+    }
+
+    /// Reason of divergence detected by objectives and hash differences, `None` if there is none.
+    fn divergence(
+        &mut self,
+        input: &Workload,
+        fst_trace: &Trace,
+        snd_trace: &Trace,
+    ) -> anyhow::Result<Option<(String, Vec<FileDiff>)>> {
         let runner = self.runner();
         debug!("doing objectives");
-        let hash_diff_interesting = runner
-            .hash_objective
-            .is_interesting()
-            .with_context(|| format!("failed to do hash objective"))?;
-        let trace_is_interesting = runner
-            .trace_objective
-            .is_interesting(fst_trace, snd_trace)
-            .with_context(|| format!("failed to do trace objective"))?;
-        if trace_is_interesting || hash_diff_interesting {
-            debug!(
-                "Error detected by: trace?: {}, hash?: {}",
-                trace_is_interesting, hash_diff_interesting
-            );
-            let mut diff: Vec<FileDiff> = vec![];
-            if hash_diff_interesting {
-                diff = runner.hash_objective.get_diff();
-            }
-            runner
-                .report_crash(&input, input_path, runner.crashes_path.clone(), diff)
-                .with_context(|| format!("failed to report crash"))?;
-            self.runner().stats.crashes += 1;
-            self.show_stats();
-            Ok(true)
-        } else {
-            Ok(false)
+        let detected = runner.detect(input, fst_trace, snd_trace)?;
+        if !detected.any() {
+            return Ok(None);
         }
+        debug!("error detected by: {:?}", detected);
+        let diff = if detected.hash {
+            runner.hash_objective.get_diff()
+        } else {
+            vec![]
+        };
+        Ok(Some((detected.to_string(), diff)))
     }
 
     fn detect_errors(
@@ -132,11 +445,19 @@ pub trait Fuzzer {
         snd_trace: &Trace,
     ) -> anyhow::Result<bool> {
         debug!("detecting errors");
-        if fst_trace.has_errors() && snd_trace.has_errors() {
+        let expected = AbstractFS::expected_failures_of(input);
+        if fst_trace.has_errors(&expected) && snd_trace.has_errors(&expected) {
             warn!("both traces contain errors, potential bug in model");
             let accidents_path = self.runner().accidents_path.clone();
             self.runner()
-                .report_crash(&input, &input_path, accidents_path, vec![])
+                .report_crash(
+                    &input,
+                    &input_path,
+                    accidents_path,
+                    vec![],
+                    CrashKind::Accident,
+                    ACCIDENT_REASON,
+                )
                 .with_context(|| format!("failed to report accident"))?;
             Ok(true)
         } else {
@@ -144,6 +465,35 @@ pub trait Fuzzer {
         }
     }
 
+    /// Executor that died mid-workload leaves truncated trace, crash is reported with operation it died at.
+    fn detect_truncation(
+        &mut self,
+        input: &Workload,
+        input_path: &Path,
+        fst_trace: &Trace,
+        snd_trace: &Trace,
+    ) -> anyhow::Result<bool> {
+        debug!("detecting truncated traces");
+        let runner = self.runner();
+        let Some(reason) = runner.truncation_reason(input, fst_trace, snd_trace) else {
+            return Ok(false);
+        };
+        error!("{}", reason);
+        runner
+            .report_crash(
+                &input,
+                input_path,
+                runner.crashes_path.clone(),
+                vec![],
+                CrashKind::Truncated,
+                &reason,
+            )
+            .with_context(|| format!("failed to report crash"))?;
+        self.runner().stats.crashes += 1;
+        self.show_stats();
+        Ok(true)
+    }
+
     fn show_stats(&mut self);
 
     fn runner(&mut self) -> &mut Runner;
@@ -157,7 +507,12 @@ impl Runner {
     ) -> Self {
         info!("new fuzzer");
 
-        let temp_dir = setup_temp_dir();
+        let fst_mount = with_atime_policy(fst_mount, config.atime_policy);
+        let snd_mount = with_atime_policy(snd_mount, config.atime_policy);
+
+        let instance = Instance::claim().unwrap();
+        let temp_dir = instance.setup_temp_dir();
+        let devices = devices(instance.index);
 
         info!("setting up fuzzing components");
         let test_dir = temp_dir.clone();
@@ -181,23 +536,54 @@ impl Runner {
         let fst_fs_name = fst_mount.to_string();
         let snd_fs_name = snd_mount.to_string();
 
-        let fst_fs_dir = Path::new("/mnt")
-            .join(fst_fs_name.to_lowercase())
+        let fst_fs_dir = instance
+            .mount_dir()
+            .join(side_name("fst", &fst_fs_name.to_lowercase()))
             .join(&config.fs_name)
             .into_boxed_path();
-        let snd_fs_dir = Path::new("/mnt")
-            .join(snd_fs_name.to_lowercase())
+        let snd_fs_dir = instance
+            .mount_dir()
+            .join(side_name("snd", &snd_fs_name.to_lowercase()))
             .join(&config.fs_name)
             .into_boxed_path();
 
+        let allowlist = Allowlist::for_pair(
+            config.known_differences.as_deref().unwrap_or_default(),
+            &fst_fs_name,
+            &snd_fs_name,
+        );
         let hash_objective = HashObjective::new(
             fst_fs_dir.clone(),
             snd_fs_dir.clone(),
-            fst_mount.get_internal_dirs(),
-            snd_mount.get_internal_dirs(),
+            with_excluded(&fst_mount.get_internal_dirs(), &config.hash_exclude)
+                .expect("bad hash exclude pattern"),
+            with_excluded(&snd_mount.get_internal_dirs(), &config.hash_exclude)
+                .expect("bad hash exclude pattern"),
+            hasher_options(&config),
             config.hashing_enabled,
+            allowlist.hash_attributes,
         );
-        let trace_objective = TraceObjective::new();
+        let trace_objective =
+            TraceObjective::new().with_ignored_fields(allowlist.trace_fields.clone());
+        let readlink_objective =
+            ReadlinkObjective::new().with_ignored_fields(&allowlist.trace_fields);
+        let stat_objective = StatObjective::new(config.stat_blocks_tolerance)
+            .with_ignored_fields(allowlist.trace_fields.clone());
+        let durability_objective = DurabilityObjective::new();
+        let signal_objective = SignalObjective::new();
+        let failure_objective = FailureObjective::new();
+        let atime_objective = config.atime_policy.map(AtimeObjective::new);
+        let dir_nlink_objective = config.dir_nlink_enabled.then(DirNlinkObjective::new);
+        let content_objective = config.verify_reads.then(ContentObjective::new);
+        let times_objective = config.hash_times.then(TimesObjective::new);
+        let slow_op_objective = config.per_op_timeout.map(SlowOpObjective::new);
+        let statfs_objective = config.statfs_tolerance.map(|tolerance| {
+            StatfsObjective::new(fst_fs_dir.clone(), snd_fs_dir.clone(), tolerance)
+        });
+        let case_objective =
+            (fst_mount.case_insensitive() || snd_mount.case_insensitive()).then(|| {
+                CaseObjective::new(fst_mount.case_insensitive(), snd_mount.case_insensitive())
+            });
 
         let fst_harness = Harness::new(
             fst_mount,
@@ -205,6 +591,8 @@ impl Runner {
             fst_exec_dir.clone().into_boxed_path(),
             fst_stdout.clone(),
             fst_stderr.clone(),
+            devices[0],
+            HarnessOptions::from(&config),
         );
         let snd_harness = Harness::new(
             snd_mount,
@@ -212,10 +600,48 @@ impl Runner {
             snd_exec_dir.clone().into_boxed_path(),
             snd_stdout.clone(),
             snd_stderr.clone(),
+            devices[1],
+            HarnessOptions::from(&config),
         );
 
+        let (fst_option_mounts, snd_option_mounts) = match &config.mount_options {
+            Some(options) => (
+                with_mount_options(fst_mount, &options.fst),
+                with_mount_options(snd_mount, &options.snd),
+            ),
+            None => (vec![], vec![]),
+        };
+        let data_modes = if config.mount_options.is_some() && !config.data_modes.is_empty() {
+            warn!("data modes are ignored with mount options, add 'data=<mode>' to mount options instead");
+            vec![]
+        } else {
+            config.data_modes.clone()
+        };
+        let fst_data_modes = with_data_modes(fst_mount, data_modes.iter().map(|(fst, _)| fst));
+        let snd_data_modes = with_data_modes(snd_mount, data_modes.iter().map(|(_, snd)| snd));
+
+        let mut reporters: Vec<Box<dyn CrashReporter>> = config
+            .crash_report_url
+            .iter()
+            .filter_map(|url| {
+                HttpReporter::new(url)
+                    .inspect_err(|err| warn!("crash reporting disabled: {:?}", err))
+                    .ok()
+            })
+            .map(|reporter| Box::new(reporter) as Box<dyn CrashReporter>)
+            .collect();
+        if let Some(path) = &config.ndjson_out {
+            match NdjsonReporter::open(path) {
+                Result::Ok(reporter) => reporters.push(Box::new(reporter)),
+                Err(err) => warn!("crash event stream disabled: {:?}", err),
+            }
+        }
+
+        let mount_options_rng = StdRng::seed_from_u64(config.rng_seed.unwrap_or_else(time_seed));
+
         Self {
             config,
+            instance,
 
             fst_exec_dir: fst_exec_dir.into_boxed_path(),
             snd_exec_dir: snd_exec_dir.into_boxed_path(),
@@ -233,39 +659,452 @@ impl Runner {
 
             hash_objective,
             trace_objective,
+            atime_objective,
+            dir_nlink_objective,
+            content_objective,
+            case_objective,
+            statfs_objective,
+            slow_op_objective,
+            stat_objective,
+            durability_objective,
+            signal_objective,
+            readlink_objective,
+            failure_objective,
+            times_objective,
 
             fst_fs_name,
             snd_fs_name,
             fst_harness,
             snd_harness,
+            fst_observers: vec![],
+            snd_observers: vec![],
+
+            fst_data_modes,
+            snd_data_modes,
+            data_mode: None,
+
+            fst_option_mounts,
+            snd_option_mounts,
+            mount_options: None,
+            mount_options_rng,
 
             stats: Stats::new(),
+            recent_crashes: VecDeque::new(),
+            crash_signatures: HashSet::new(),
+
+            control: None,
+            stop: Arc::new(AtomicBool::new(false)),
+            reporters,
+
+            others: vec![],
+            diverged_pairs: vec![],
+        }
+    }
+
+    /// Adds filesystems that are run and compared pairwise with the first two.
+    pub fn with_others(mut self, mounts: &[&'static dyn FileSystemMount]) -> anyhow::Result<Self> {
+        if mounts.len() + 2 > DEVICES_PER_INSTANCE {
+            bail!(
+                "at most {} filesystems can be compared, got {}",
+                DEVICES_PER_INSTANCE,
+                mounts.len() + 2
+            );
+        }
+        for (i, mount) in mounts.iter().enumerate() {
+            let mount = with_atime_policy(*mount, self.config.atime_policy);
+            let fs_name = mount.to_string();
+            let exec_dir = self
+                .test_dir
+                .join(format!("other{}_exec", i))
+                .into_boxed_path();
+            let fs_dir = self
+                .instance
+                .mount_dir()
+                .join(side_name(&format!("other{}", i), &fs_name.to_lowercase()))
+                .join(&self.config.fs_name)
+                .into_boxed_path();
+            let stdout = Rc::new(RefCell::new("".to_owned()));
+            let stderr = Rc::new(RefCell::new("".to_owned()));
+            let harness = Harness::new(
+                mount,
+                fs_dir.clone(),
+                exec_dir.clone(),
+                stdout.clone(),
+                stderr.clone(),
+                devices(self.instance.index)[i + 2],
+                HarnessOptions::from(&self.config),
+            );
+            info!("comparing with '{}' too", fs_name);
+            self.others.push(OtherFs {
+                fs_name,
+                trace_path: exec_dir.join(TRACE_FILENAME).into_boxed_path(),
+                exec_dir,
+                stdout,
+                stderr,
+                harness,
+                hash: HashHolder::new(
+                    fs_dir.clone(),
+                    with_excluded(&mount.get_internal_dirs(), &self.config.hash_exclude)
+                        .with_context(|| format!("bad hash exclude pattern"))?,
+                    hasher_options(&self.config),
+                ),
+                statfs: self
+                    .config
+                    .statfs_tolerance
+                    .map(|_| StatfsObserver::new(fs_dir)),
+            });
+        }
+        Ok(self)
+    }
+
+    /// Generator settings from config, extended attributes and `fallocate` modes are limited
+    /// to what every compared filesystem supports, so that none of them fails with
+    /// `E2BIG`, `ENOSPC` or `EOPNOTSUPP`.
+    ///
+    /// Names only collide if some filesystems fold case and others do not, model does not know
+    /// colliding operations fail when every filesystem folds case.
+    pub fn generator(&self) -> GeneratorConfig {
+        let mounts: Vec<&dyn FileSystemMount> = [&self.fst_harness, &self.snd_harness]
+            .into_iter()
+            .chain(self.others.iter().map(|other| &other.harness))
+            .map(|harness| harness.fs_mount())
+            .collect();
+        let config = self.config.generator();
+        GeneratorConfig {
+            max_xattr_space: mounts.iter().filter_map(|m| m.xattr_space()).min(),
+            fallocate_modes: config
+                .fallocate_modes
+                .into_iter()
+                .filter(|mode| mounts.iter().all(|m| m.fallocate_supported(mode)))
+                .collect(),
+            case_collisions: mounts.iter().any(|m| m.case_insensitive())
+                && !mounts.iter().all(|m| m.case_insensitive()),
+            ..config
         }
     }
 
     pub fn compile_test(&mut self, input: &Workload) -> anyhow::Result<Box<Path>> {
         debug!("compiling test at '{}'", self.test_dir.display());
         let input_path = input
-            .compile(&self.test_dir)
+            .compile(
+                &self.test_dir,
+                self.config.delays.as_ref(),
+                self.config.compile_jobs,
+            )
             .with_context(|| format!("failed to compile test"))?;
         Ok(input_path)
     }
 
-    pub fn run_harness(&mut self, input_path: &Path) -> anyhow::Result<()> {
+    pub fn run_harness(&mut self, input_path: &Path) -> anyhow::Result<ObserverVerdict> {
         debug!("running harness at '{}'", input_path.display());
 
-        setup_dir(self.fst_exec_dir.as_ref())
-            .with_context(|| format!("failed to setup dir at '{}'", input_path.display()))?;
-        setup_dir(self.snd_exec_dir.as_ref())
-            .with_context(|| format!("failed to setup dir at '{}'", input_path.display()))?;
-
-        self.fst_harness
-            .run(&input_path, false, Some(&mut self.hash_objective.fst_fs))
-            .with_context(|| format!("failed to run first harness '{}'", self.fst_fs_name))?;
-        self.snd_harness
-            .run(&input_path, false, Some(&mut self.hash_objective.snd_fs))
-            .with_context(|| format!("failed to run second harness '{}'", self.snd_fs_name))?;
-        Ok(())
+        let mut verdict = ObserverVerdict::Conclusive;
+        for attempt in 0..=self.config.inconclusive_retries {
+            if attempt > 0 {
+                debug!("retrying inconclusive run (attempt {})", attempt);
+            }
+
+            setup_dir(self.fst_exec_dir.as_ref())
+                .with_context(|| format!("failed to setup dir at '{}'", input_path.display()))?;
+            setup_dir(self.snd_exec_dir.as_ref())
+                .with_context(|| format!("failed to setup dir at '{}'", input_path.display()))?;
+
+            let mut fst_observers: Vec<&mut dyn Observer> = vec![&mut self.hash_objective.fst_fs];
+            let mut snd_observers: Vec<&mut dyn Observer> = vec![&mut self.hash_objective.snd_fs];
+            if let Some(objective) = self.statfs_objective.as_mut() {
+                fst_observers.push(&mut objective.fst_fs);
+                snd_observers.push(&mut objective.snd_fs);
+            }
+            for observer in self.fst_observers.iter_mut() {
+                fst_observers.push(observer.as_mut());
+            }
+            for observer in self.snd_observers.iter_mut() {
+                snd_observers.push(observer.as_mut());
+            }
+            let fst_verdict = self
+                .fst_harness
+                .run(&input_path, false, &mut fst_observers)
+                .with_context(|| format!("failed to run first harness '{}'", self.fst_fs_name))?;
+            let snd_verdict = self
+                .snd_harness
+                .run(&input_path, false, &mut snd_observers)
+                .with_context(|| format!("failed to run second harness '{}'", self.snd_fs_name))?;
+
+            let mut other_verdicts = vec![];
+            for other in self.others.iter_mut() {
+                setup_dir(other.exec_dir.as_ref()).with_context(|| {
+                    format!("failed to setup dir at '{}'", other.exec_dir.display())
+                })?;
+                let mut observers: Vec<&mut dyn Observer> = vec![&mut other.hash];
+                if let Some(statfs) = other.statfs.as_mut() {
+                    observers.push(statfs);
+                }
+                other_verdicts.push(
+                    other
+                        .harness
+                        .run(&input_path, false, &mut observers)
+                        .with_context(|| format!("failed to run harness '{}'", other.fs_name))?,
+                );
+            }
+
+            verdict = match (fst_verdict, snd_verdict) {
+                (ObserverVerdict::Conclusive, ObserverVerdict::Conclusive) => {
+                    match other_verdicts
+                        .into_iter()
+                        .find(|verdict| *verdict != ObserverVerdict::Conclusive)
+                    {
+                        Some(verdict) => verdict,
+                        None => return Ok(ObserverVerdict::Conclusive),
+                    }
+                }
+                (ObserverVerdict::Inconclusive(reason), _) => ObserverVerdict::Inconclusive(reason),
+                (_, ObserverVerdict::Inconclusive(reason)) => ObserverVerdict::Inconclusive(reason),
+            };
+        }
+        self.stats.inconclusive += 1;
+        Ok(verdict)
+    }
+
+    /// Binds control socket (if configured and not bound yet). Only fuzzing loop does so, so runners
+    /// that only replay (e.g. reducer or triage) do not take over socket of running fuzzer.
+    pub fn bind_control(&mut self) {
+        if self.control.is_some() {
+            return;
+        }
+        self.control = self.config.control_socket.as_ref().and_then(|path| {
+            ControlSocket::bind(Path::new(path))
+                .inspect_err(|err| warn!("control socket disabled: {:?}", err))
+                .ok()
+        });
+    }
+
+    /// Switches harnesses to option sets picked at random (if any configured), in persistent mode
+    /// only when filesystems are recreated.
+    ///
+    /// Filesystem names are updated too, so saved crashes are labeled with active options.
+    pub fn select_mount_options(&mut self, execution: usize) {
+        let Some(options) = &self.config.mount_options else {
+            return;
+        };
+        if self.mount_options.is_some()
+            && self
+                .config
+                .persistent_runs
+                .is_some_and(|runs| !execution.is_multiple_of(runs.max(1) as usize))
+        {
+            return;
+        }
+        let fst = choose_options(&mut self.mount_options_rng, &options.fst);
+        let snd = choose_options(&mut self.mount_options_rng, &options.snd);
+        let fst_mount = self.fst_option_mounts[fst];
+        let snd_mount = self.snd_option_mounts[snd];
+        debug!("using mount options '{}' and '{}'", fst_mount, snd_mount);
+        self.fst_harness.set_mount(fst_mount);
+        self.snd_harness.set_mount(snd_mount);
+        self.fst_fs_name = fst_mount.to_string();
+        self.snd_fs_name = snd_mount.to_string();
+        self.mount_options = Some((fst, snd));
+    }
+
+    /// Switches harnesses to the next configured pair of data modes.
+    ///
+    /// Filesystem names stay the same, active modes are recorded in reason of saved crashes.
+    pub fn select_data_modes(&mut self, execution: usize) {
+        if self.fst_data_modes.is_empty() {
+            return;
+        }
+        let pair = execution % self.fst_data_modes.len();
+        let fst_mount = self.fst_data_modes[pair];
+        let snd_mount = self.snd_data_modes[pair];
+        debug!("using data modes '{}' and '{}'", fst_mount, snd_mount);
+        self.fst_harness.set_mount(fst_mount);
+        self.snd_harness.set_mount(snd_mount);
+        self.data_mode = Some(pair);
+    }
+
+    /// Drops operations that fail in the model, so that execution is not wasted on invalid workload.
+    pub fn repair(&mut self, input: Workload) -> Workload {
+        let (repaired, dropped) = repair(&input);
+        if dropped > 0 {
+            debug!("repaired workload, {} operations dropped", dropped);
+            self.stats.repaired_ops += dropped;
+        }
+        repaired
+    }
+
+    pub fn update_model_stats(&mut self, input: &Workload) {
+        let mut fs = AbstractFS::new();
+        if let Err(err) = fs.replay(input) {
+            warn!("failed to replay workload in model: {}", err);
+            return;
+        }
+        let nodes = fs.node_count();
+        if self
+            .config
+            .max_model_nodes
+            .is_some_and(|limit| nodes >= limit)
+        {
+            warn!("workload reached model node limit ({} nodes)", nodes);
+        }
+        self.stats.peak_model_nodes = max(self.stats.peak_model_nodes, nodes);
+    }
+
+    /// Every filesystem whose executor died mid-workload, with operation it died at.
+    pub fn truncation_reason(
+        &self,
+        input: &Workload,
+        fst_trace: &Trace,
+        snd_trace: &Trace,
+    ) -> Option<String> {
+        let reasons: Vec<String> = [
+            (&self.fst_fs_name, fst_trace),
+            (&self.snd_fs_name, snd_trace),
+        ]
+        .into_iter()
+        .filter_map(|(fs_name, trace)| {
+            trace
+                .crashed_at(input.ops.len())
+                .map(|index| crashed_during(fs_name, input, index))
+        })
+        .collect();
+        if reasons.is_empty() {
+            None
+        } else {
+            Some(reasons.join("\n"))
+        }
+    }
+
+    /// Traces without operations that may legitimately differ after crash point, files that may
+    /// have lost data are not compared by hash objective either.
+    fn without_crash_dependent(
+        &mut self,
+        input: &Workload,
+        fst_trace: &Trace,
+        snd_trace: &Trace,
+    ) -> (Trace, Trace) {
+        self.hash_objective
+            .set_crashed_paths(&AbstractFS::crashed_paths_of(input));
+        let crash_dependent = AbstractFS::crash_dependent_of(input);
+        (
+            fst_trace.without(&crash_dependent),
+            snd_trace.without(&crash_dependent),
+        )
+    }
+
+    /// Runs input on both filesystems and returns reason fuzzer would report, `None` if they agree.
+    pub fn replay_reason(&mut self, input: &Workload) -> anyhow::Result<Option<String>> {
+        let input_path = self.compile_test(input)?;
+        if let ObserverVerdict::Inconclusive(reason) = self.run_harness(&input_path)? {
+            bail!("run is inconclusive: {}", reason);
+        }
+        let fst_trace = parse_trace(&self.fst_trace_path)
+            .with_context(|| format!("failed to parse first trace"))?;
+        let snd_trace = parse_trace(&self.snd_trace_path)
+            .with_context(|| format!("failed to parse second trace"))?;
+        if let Some(reason) = self.truncation_reason(input, &fst_trace, &snd_trace) {
+            return Ok(Some(reason));
+        }
+        let expected = AbstractFS::expected_failures_of(input);
+        if fst_trace.has_errors(&expected) && snd_trace.has_errors(&expected) {
+            return Ok(Some(ACCIDENT_REASON.to_owned()));
+        }
+        let detected = self.detect(input, &fst_trace, &snd_trace)?;
+        Ok(detected.any().then(|| detected.to_string()))
+    }
+
+    /// Runs every objective on traces and states of both filesystems after input.
+    pub fn detect(
+        &mut self,
+        input: &Workload,
+        fst_trace: &Trace,
+        snd_trace: &Trace,
+    ) -> anyhow::Result<Detected> {
+        let (fst_certain, snd_certain) = self.without_crash_dependent(input, fst_trace, snd_trace);
+        // only one of colliding names is added on filesystem that folds case
+        let colliding = match &self.case_objective {
+            Some(objective) => {
+                let colliding = objective.expected_differences(input);
+                if !colliding.is_empty() {
+                    debug!(
+                        "names collide on case-insensitive filesystem at operations {:?}, they are not compared",
+                        colliding
+                    );
+                    self.hash_objective
+                        .skip_colliding(objective.colliding_patterns(input));
+                }
+                colliding
+            }
+            None => vec![],
+        };
+        let (fst_certain, snd_certain) = (
+            fst_certain.without(&colliding),
+            snd_certain.without(&colliding),
+        );
+        let mut detected = Detected {
+            hash: self
+                .hash_objective
+                .is_interesting()
+                .with_context(|| format!("failed to do hash objective"))?,
+            trace: self
+                .trace_objective
+                .is_interesting(&fst_certain, &snd_certain)
+                .with_context(|| format!("failed to do trace objective"))?,
+            failure: self.failure_objective.is_interesting(
+                input,
+                &fst_trace.without(&colliding),
+                &snd_trace.without(&colliding),
+            ),
+            ..Detected::default()
+        };
+        detected.atime = self
+            .atime_objective
+            .as_ref()
+            .is_some_and(|objective| objective.is_interesting(input, fst_trace, snd_trace));
+        detected.dir_nlink = self
+            .dir_nlink_objective
+            .as_ref()
+            .is_some_and(|objective| objective.is_interesting(input, fst_trace, snd_trace));
+        detected.content = self
+            .content_objective
+            .as_ref()
+            .is_some_and(|objective| objective.is_interesting(input, fst_trace, snd_trace));
+        detected.case = self
+            .case_objective
+            .as_ref()
+            .is_some_and(|objective| objective.is_interesting(input, fst_trace, snd_trace));
+        detected.statfs = self
+            .statfs_objective
+            .as_ref()
+            .is_some_and(|objective| objective.is_interesting());
+        detected.slow_op = self
+            .slow_op_objective
+            .as_ref()
+            .is_some_and(|objective| objective.is_interesting(fst_trace, snd_trace));
+        detected.stat = self
+            .stat_objective
+            .is_interesting(&fst_certain, &snd_certain);
+        detected.durability = self
+            .durability_objective
+            .is_interesting(input, fst_trace, snd_trace);
+        detected.signal = self.signal_objective.is_interesting(fst_trace, snd_trace);
+        detected.readlink = self.readlink_objective.is_interesting(fst_trace, snd_trace);
+        detected.times = self.times_objective.as_ref().is_some_and(|objective| {
+            objective.is_interesting(
+                input,
+                self.hash_objective.fst_fs.fs_content(),
+                self.hash_objective.snd_fs.fs_content(),
+            )
+        });
+        Ok(detected)
+    }
+
+    /// Filesystems kept mounted in persistent mode are recreated before the next run.
+    pub fn expire_harnesses(&self) {
+        self.fst_harness.expire();
+        self.snd_harness.expire();
+        for other in self.others.iter() {
+            other.harness.expire();
+        }
     }
 
     pub fn report_crash(
@@ -274,20 +1113,91 @@ impl Runner {
         input_path: &Path,
         crash_dir: Box<Path>,
         hash_diff: Vec<FileDiff>,
+        kind: CrashKind,
+        reason: &str,
     ) -> anyhow::Result<()> {
         let name = input.generate_name();
         debug!("report crash '{}'", name);
 
-        let crash_dir = crash_dir.join(name);
+        let known_differences = self.config.known_differences.as_deref().unwrap_or_default();
+        let mut full_reason = Reason::new(reason).with_known_differences(known_differences);
+        if let (Some(options), Some((fst, snd))) = (&self.config.mount_options, self.mount_options)
+        {
+            full_reason = full_reason
+                .with_mount_options(&self.fst_fs_name, &options.fst[fst].to_string())
+                .with_mount_options(&self.snd_fs_name, &options.snd[snd].to_string());
+        }
+        if let Some(pair) = self.data_mode {
+            let (fst, snd) = &self.config.data_modes[pair];
+            full_reason = full_reason
+                .with_mount_options(&self.fst_fs_name, &data_mode_options(fst))
+                .with_mount_options(&self.snd_fs_name, &data_mode_options(snd));
+        }
+        for pair in std::mem::take(&mut self.diverged_pairs) {
+            full_reason = full_reason.with_diverged(pair);
+        }
+        let fst_trace = parse_trace(&self.fst_trace_path).ok();
+        let snd_trace = parse_trace(&self.snd_trace_path).ok();
+        if let (Some(fst), Some(snd)) = (&fst_trace, &snd_trace) {
+            let allowlist =
+                Allowlist::for_pair(known_differences, &self.fst_fs_name, &self.snd_fs_name);
+            if let Some(index) = first_divergence(fst, snd, &allowlist.trace_fields) {
+                if let Some(op) = input.ops.get(index as usize) {
+                    full_reason = full_reason.with_divergence(index, op);
+                }
+            }
+        }
+        let mut full_reason = full_reason
+            .with_differences(self.hash_objective.describe_diff(&hash_diff))
+            .with_results(&self.fst_fs_name, fst_trace)
+            .with_results(&self.snd_fs_name, snd_trace);
+        for other in self.others.iter() {
+            full_reason =
+                full_reason.with_results(&other.fs_name, parse_trace(&other.trace_path).ok());
+        }
+        // reducer saves every smaller workload with the same difference
+        if kind != CrashKind::Reduced {
+            if !self.crash_signatures.insert(full_reason.signature()) {
+                info!(
+                    "skipping crash '{}', crash with the same signature was already saved",
+                    name
+                );
+                return anyhow::Ok(());
+            }
+            if kind != CrashKind::Accident {
+                self.stats.unique_crashes += 1;
+            }
+        }
+
+        let crash_dir = match &self.config.crash_dir_template {
+            Some(template) => crash_dir.join(template.render(&CrashInfo {
+                kind,
+                fst_fs_name: &self.fst_fs_name,
+                snd_fs_name: &self.snd_fs_name,
+                reason,
+                time: SystemTime::now(),
+            })),
+            None => crash_dir.to_path_buf(),
+        }
+        .join(&name);
         if fs::exists(crash_dir.as_path()).with_context(|| {
             format!(
                 "failed to determine existence of crash directory at '{}'",
                 crash_dir.display()
             )
         })? {
-            return anyhow::Ok(());
+            // reducer saves the last reduced workload again, once divergence is isolated
+            if kind != CrashKind::Reduced {
+                return anyhow::Ok(());
+            }
+            fs::remove_dir_all(crash_dir.as_path()).with_context(|| {
+                format!(
+                    "failed to remove previous crash directory at '{}'",
+                    crash_dir.display()
+                )
+            })?;
         }
-        fs::create_dir(crash_dir.as_path()).with_context(|| {
+        fs::create_dir_all(crash_dir.as_path()).with_context(|| {
             format!(
                 "failed to create crash directory at '{}'",
                 crash_dir.display()
@@ -298,23 +1208,70 @@ impl Runner {
         save_output(
             &crash_dir,
             &self.fst_trace_path,
-            &self.fst_fs_name,
+            &side_name("fst", &self.fst_fs_name),
             self.fst_stdout.borrow().clone(),
             self.fst_stderr.borrow().clone(),
         )
-            .with_context(|| format!("failed to save output for first harness"))?;
+        .with_context(|| format!("failed to save output for first harness"))?;
         save_output(
             &crash_dir,
             &self.snd_trace_path,
-            &self.snd_fs_name,
+            &side_name("snd", &self.snd_fs_name),
             self.snd_stdout.borrow().clone(),
             self.snd_stderr.borrow().clone(),
         )
-            .with_context(|| format!("failed to save output for first harness"))?;
+        .with_context(|| format!("failed to save output for second harness"))?;
+        for (i, other) in self.others.iter().enumerate() {
+            save_output(
+                &crash_dir,
+                &other.trace_path,
+                &side_name(&format!("other{}", i), &other.fs_name),
+                other.stdout.borrow().clone(),
+                other.stderr.borrow().clone(),
+            )
+            .with_context(|| format!("failed to save output for harness '{}'", other.fs_name))?;
+        }
 
         save_diff(&crash_dir, hash_diff)
             .with_context(|| format!("failed to save hash differences"))?;
+        // replay overwrites outputs of harnesses, so it is done after they are saved
+        if self.config.check_after_crash {
+            for (side, harness, fs_name) in [
+                ("fst", &self.fst_harness, &self.fst_fs_name),
+                ("snd", &self.snd_harness, &self.snd_fs_name),
+            ] {
+                match harness.check(input_path) {
+                    Result::Ok(Some(output)) => {
+                        save_check(&crash_dir, &side_name(side, fs_name), &output)?
+                    }
+                    Result::Ok(None) => {}
+                    Err(err) => warn!("failed to check '{}': {:?}", fs_name, err),
+                }
+            }
+        }
+        if let Some(objective) = &self.statfs_objective {
+            let statfs_diff = objective.get_diff();
+            if !statfs_diff.is_empty() {
+                save_statfs_diff(&crash_dir, &statfs_diff)?;
+            }
+        }
+        if self.config.fstest_export {
+            save_fstest(&crash_dir, input, reason)?;
+        }
+        let report = CrashReport::new(&name, kind, &crash_dir, &full_reason, input);
+        FileSystemReporter
+            .report(&report)
+            .with_context(|| format!("failed to save reason"))?;
         info!("crash saved at '{}'", crash_dir.display());
+        report_remote(&self.reporters, &report);
+
+        // filesystems diverged, accumulated state is no longer comparable
+        self.expire_harnesses();
+
+        if self.recent_crashes.len() >= RECENT_CRASHES {
+            self.recent_crashes.pop_front();
+        }
+        self.recent_crashes.push_back(name);
 
         anyhow::Ok(())
     }
@@ -323,8 +1280,18 @@ impl Runner {
 pub struct Stats {
     pub executions: usize,
     pub crashes: usize,
+    /// Crashes (not accidents) with distinct signatures, duplicates are not saved.
+    pub unique_crashes: usize,
+    pub inconclusive: usize,
+    pub peak_model_nodes: usize,
+    /// Operations dropped from workloads that failed in the model.
+    pub repaired_ops: usize,
     pub start: Instant,
     pub last_time_showed: Instant,
+    /// Executions per second over the last minute.
+    pub rate: RollingRate,
+    /// Number of tests to run, if it was limited.
+    pub test_count: Option<u64>,
 }
 
 impl Stats {
@@ -332,19 +1299,744 @@ impl Stats {
         Stats {
             executions: 0,
             crashes: 0,
+            unique_crashes: 0,
+            inconclusive: 0,
+            peak_model_nodes: 0,
+            repaired_ops: 0,
             start: Instant::now(),
             last_time_showed: Instant::now(),
+            rate: RollingRate::new(RATE_WINDOW),
+            test_count: None,
         }
     }
+
+    /// Tests completed out of total, when number of tests is limited.
+    pub fn progress(&self) -> Option<(u64, u64)> {
+        self.test_count
+            .map(|total| ((self.executions as u64).min(total), total))
+    }
+
+    /// Progress appended to stats line, empty if number of tests is not limited.
+    pub fn progress_summary(&self) -> String {
+        self.progress()
+            .map(|(completed, total)| format!(", progress: {}/{}", completed, total))
+            .unwrap_or_default()
+    }
+}
+
+/// How data mode is recorded in reason, empty mode keeps options of filesystem.
+fn data_mode_options(mode: &str) -> String {
+    if mode.is_empty() {
+        "filesystem defaults".to_owned()
+    } else {
+        format!("data={}", mode)
+    }
+}
+
+pub fn hasher_options(config: &Config) -> HasherOptions {
+    let mut options = match config.hash_content_limit {
+        Some(limit) => HasherOptions::default().with_content_limit(limit),
+        None => HasherOptions::default(),
+    };
+    if config.hash_allocated {
+        options = options.with(Attribute::Allocated);
+    }
+    if config.hash_times {
+        options = options.with(Attribute::Times);
+    }
+    options
+}
+
+/// Seed of the campaign, picked from current time if not set in config.
+///
+/// Chosen seed is written back to config and logged, so campaign can be reproduced.
+pub fn campaign_seed(config: &mut Config) -> u64 {
+    let seed = *config.rng_seed.get_or_insert_with(time_seed);
+    info!("rng seed: {}", seed);
+    seed
+}
+
+fn time_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
 }
 
 pub fn parse_trace(path: &Path) -> anyhow::Result<Trace> {
     let trace = read_to_string(&path)
         .with_context(|| format!("failed to read trace at '{}'", path.display()))?;
-    anyhow::Ok(Trace::try_parse(trace).with_context(|| format!("failed to parse trace"))?)
+    anyhow::Ok(Trace::try_parse_lenient(trace).with_context(|| format!("failed to parse trace"))?)
+}
+
+/// Reason of accidents: both filesystems fail where model expects success, likely a bug in model.
+pub const ACCIDENT_REASON: &str = "both traces contain errors";
+
+/// Objectives that detected divergence, see [`Runner::detect`].
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Detected {
+    pub trace: bool,
+    pub hash: bool,
+    pub atime: bool,
+    pub dir_nlink: bool,
+    pub content: bool,
+    pub case: bool,
+    pub statfs: bool,
+    pub slow_op: bool,
+    pub stat: bool,
+    pub durability: bool,
+    pub signal: bool,
+    pub readlink: bool,
+    pub times: bool,
+    pub failure: bool,
+}
+
+impl Detected {
+    pub(crate) fn objectives(&self) -> [(&'static str, bool); 14] {
+        [
+            ("trace", self.trace),
+            ("hash", self.hash),
+            ("atime", self.atime),
+            ("dir nlink", self.dir_nlink),
+            ("content", self.content),
+            ("case", self.case),
+            ("statfs", self.statfs),
+            ("slow operation", self.slow_op),
+            ("stat", self.stat),
+            ("durability", self.durability),
+            ("signal", self.signal),
+            ("readlink", self.readlink),
+            ("times", self.times),
+            ("failure", self.failure),
+        ]
+    }
+
+    pub fn any(&self) -> bool {
+        self.objectives().iter().any(|(_, detected)| *detected)
+    }
+}
+
+impl std::fmt::Display for Detected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let objectives: Vec<String> = self
+            .objectives()
+            .iter()
+            .map(|(name, detected)| format!("{} objective: {}", name, detected))
+            .collect();
+        write!(f, "detected by {}", objectives.join(", "))
+    }
+}
+
+/// Describes operation executor died at (and the last one completed).
+pub fn crashed_during(fs_name: &str, input: &Workload, index: usize) -> String {
+    let describe = |index: usize| match input.ops.get(index) {
+        Some(op) => format!("{} ({:?})", index, op.kind()),
+        None => format!("{}", index),
+    };
+    let last_completed = match index.checked_sub(1) {
+        Some(last) => describe(last),
+        None => "none".to_owned(),
+    };
+    format!(
+        "'{}' crashed during operation {}, last completed operation: {}",
+        fs_name,
+        describe(index),
+        last_completed
+    )
 }
 
 pub fn setup_dir(path: &Path) -> io::Result<()> {
     fs::remove_dir_all(path).unwrap_or(());
     fs::create_dir(path)
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::abstract_fs::compile::{TEST_EXE_FILENAME, TEST_SOURCE_FILENAME};
+    use crate::abstract_fs::operation::Operation;
+    use crate::fuzzing::observer::tests::MockObserver;
+    use crate::harness::tests::MockMount;
+    use crate::mount::ext4::Ext4;
+    use crate::save::{REASON_FILENAME, TESTCASE_JSON_FILENAME};
+    use regex::RegexSet;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::PathBuf;
+
+    use super::*;
+
+    static EXT4: Ext4 = Ext4::new();
+
+    enum Outcome {
+        Pass,
+        Crash,
+        Accident,
+        /// Stop is requested while test is running.
+        Interrupt,
+    }
+
+    /// Counts crashes like objectives do, without running harness, accidents are detected from traces.
+    struct MockFuzzer {
+        runner: Runner,
+        outcomes: VecDeque<Outcome>,
+        /// Traces (first and second) rerun of workload leaves, crashes are reduced if set.
+        rerun_traces: Option<fn(&Workload) -> (String, String)>,
+    }
+
+    impl Fuzzer for MockFuzzer {
+        fn fuzz_one(&mut self) -> anyhow::Result<()> {
+            match self.outcomes.pop_front().unwrap_or(Outcome::Pass) {
+                Outcome::Pass => {}
+                Outcome::Crash => self.runner.stats.crashes += 1,
+                Outcome::Accident => {
+                    let input = accident_input();
+                    let failed =
+                        "Index,Command,ReturnCode,Errno,Extra\n0,MKDIR,-1,Input/output error(5),\n";
+                    fs::write(&self.runner.fst_trace_path, failed)?;
+                    fs::write(&self.runner.snd_trace_path, failed)?;
+                    let fst_trace = parse_trace(&self.runner.fst_trace_path)?;
+                    let snd_trace = parse_trace(&self.runner.snd_trace_path)?;
+                    let input_path = self.runner.fst_trace_path.with_file_name(TEST_EXE_FILENAME);
+                    assert!(self.detect_errors(&input, &input_path, &fst_trace, &snd_trace)?);
+                }
+                Outcome::Interrupt => self.runner.stop.store(true, Ordering::Relaxed),
+            }
+            Ok(())
+        }
+
+        fn reduces_crashes(&mut self) -> bool {
+            self.rerun_traces.is_some()
+        }
+
+        fn rerun(&mut self, input: &Workload) -> anyhow::Result<Option<Box<Path>>> {
+            let (fst, snd) = (self.rerun_traces.unwrap())(input);
+            fs::write(&self.runner.fst_trace_path, fst)?;
+            fs::write(&self.runner.snd_trace_path, snd)?;
+            Ok(Some(
+                self.runner
+                    .fst_trace_path
+                    .with_file_name(TEST_EXE_FILENAME)
+                    .into_boxed_path(),
+            ))
+        }
+
+        fn show_stats(&mut self) {}
+
+        fn runner(&mut self) -> &mut Runner {
+            &mut self.runner
+        }
+    }
+
+    /// Workload which only operation fails on both filesystems in [`Outcome::Accident`].
+    fn accident_input() -> Workload {
+        Workload {
+            ops: vec![Operation::MKDIR {
+                path: "/a".into(),
+                mode: vec![],
+            }],
+            seed: 0,
+        }
+    }
+
+    fn mock_fuzzer() -> MockFuzzer {
+        mock_fuzzer_with(|_| {})
+    }
+
+    /// Fuzzer of two ext4 filesystems with default configuration changed by `configure`.
+    fn mock_fuzzer_with(configure: impl FnOnce(&mut Config)) -> MockFuzzer {
+        let mut config: Config = toml::from_str(include_str!("../../config.toml")).unwrap();
+        configure(&mut config);
+        MockFuzzer {
+            runner: Runner::new(&EXT4, &EXT4, config),
+            outcomes: VecDeque::new(),
+            rerun_traces: None,
+        }
+    }
+
+    /// Fresh temporary directory with (empty) test executable and its source, runner keeps traces,
+    /// crashes and accidents there. Directory is removed once dropped.
+    struct TestDir {
+        path: PathBuf,
+        input_path: PathBuf,
+    }
+
+    impl TestDir {
+        fn new(name: &str, runner: &mut Runner) -> Self {
+            let path = std::env::temp_dir().join(format!("DIFFuzzer-{}-test", name));
+            fs::remove_dir_all(&path).unwrap_or(());
+            fs::create_dir_all(&path).unwrap();
+            let input_path = path.join(TEST_EXE_FILENAME);
+            fs::write(&input_path, "").unwrap();
+            fs::write(path.join(TEST_SOURCE_FILENAME), "").unwrap();
+            runner.fst_trace_path = path.join("fst.trace.csv").into_boxed_path();
+            runner.snd_trace_path = path.join("snd.trace.csv").into_boxed_path();
+            runner.crashes_path = path.join("crashes").into_boxed_path();
+            runner.accidents_path = path.join("accidents").into_boxed_path();
+            Self { path, input_path }
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            fs::remove_dir_all(&self.path).unwrap_or(());
+        }
+    }
+
+    #[test]
+    fn test_fail_fast() {
+        let mut fuzzer = mock_fuzzer();
+        let _dir = TestDir::new("fail-fast", &mut fuzzer.runner);
+        let mut run = |outcomes: Vec<Outcome>, fail_fast: bool| {
+            fuzzer.runner.stats = Stats::new();
+            fuzzer.outcomes = outcomes.into();
+            let exit = fuzzer.run(Some(5), None, fail_fast);
+            (exit, fuzzer.runner.stats.executions)
+        };
+
+        use Outcome::*;
+        assert_eq!(
+            (LoopExit::Crash, 2),
+            run(vec![Pass, Crash, Pass, Crash], true)
+        );
+        assert_eq!(
+            (LoopExit::Finished, 5),
+            run(vec![Pass, Crash, Pass, Crash], false)
+        );
+        assert_eq!(
+            (LoopExit::Finished, 5),
+            run(vec![Accident, Accident, Pass], true)
+        );
+        // accidents are saved, but are not crashes to stop at
+        assert_eq!(0, fuzzer.runner.stats.crashes);
+        assert!(fuzzer
+            .runner
+            .accidents_path
+            .join(accident_input().generate_name())
+            .exists());
+    }
+
+    #[test]
+    fn test_stop() {
+        let mut fuzzer = mock_fuzzer();
+        let _dir = TestDir::new("stop", &mut fuzzer.runner);
+        use Outcome::*;
+        fuzzer.outcomes = vec![Pass, Interrupt, Pass].into();
+        // current test is finished before loop returns
+        assert_eq!(LoopExit::Interrupted, fuzzer.run(Some(5), None, false));
+        assert_eq!(2, fuzzer.runner.stats.executions);
+        assert_eq!(1, fuzzer.outcomes.len());
+
+        fuzzer.runner.stats = Stats::new();
+        assert_eq!(LoopExit::Interrupted, fuzzer.run(None, None, false));
+        assert_eq!(0, fuzzer.runner.stats.executions);
+    }
+
+    #[test]
+    fn test_max_runtime() {
+        let mut fuzzer = mock_fuzzer();
+        let _dir = TestDir::new("max-runtime", &mut fuzzer.runner);
+        let start = Instant::now();
+        let exit = fuzzer.run(None, Some(Duration::from_millis(10)), false);
+        assert_eq!(LoopExit::OutOfTime, exit);
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(fuzzer.runner.stats.executions > 0);
+
+        // test count is still respected
+        fuzzer.runner.stats = Stats::new();
+        let exit = fuzzer.run(Some(3), Some(Duration::from_secs(60 * 60)), false);
+        assert_eq!(
+            (LoopExit::Finished, 3),
+            (exit, fuzzer.runner.stats.executions)
+        );
+
+        fuzzer.runner.stats = Stats::new();
+        let exit = fuzzer.run(Some(3), Some(Duration::ZERO), false);
+        assert_eq!(
+            (LoopExit::OutOfTime, 0),
+            (exit, fuzzer.runner.stats.executions)
+        );
+    }
+
+    #[test]
+    fn test_expected_failure_is_not_accident() {
+        let mut fuzzer = mock_fuzzer();
+        let dir = TestDir::new("expected-failure", &mut fuzzer.runner);
+        let input_path = dir.input_path.clone();
+
+        // rename into file fails on every filesystem
+        let input = Workload {
+            ops: vec![
+                Operation::MKDIR {
+                    path: "/foo".into(),
+                    mode: vec![],
+                },
+                Operation::CREATE {
+                    path: "/bar".into(),
+                    mode: vec![],
+                },
+                Operation::RENAME {
+                    old_path: "/foo".into(),
+                    new_path: "/bar/foo".into(),
+                    flags: vec![],
+                },
+            ],
+            seed: 0,
+        };
+        let mut run = |fst: &str, snd: &str| {
+            let header = "Index,Command,ReturnCode,Errno,Extra\n    0,MKDIR,0,Success(0),\n    1,CREATE,3,Success(0),\n";
+            fs::write(&fuzzer.runner.fst_trace_path, format!("{}{}", header, fst)).unwrap();
+            fs::write(&fuzzer.runner.snd_trace_path, format!("{}{}", header, snd)).unwrap();
+            let fst_trace = parse_trace(&fuzzer.runner.fst_trace_path).unwrap();
+            let snd_trace = parse_trace(&fuzzer.runner.snd_trace_path).unwrap();
+            let accident = fuzzer
+                .detect_errors(&input, &input_path, &fst_trace, &snd_trace)
+                .unwrap();
+            let crash = !accident
+                && fuzzer
+                    .do_objective(&input, &input_path, &fst_trace, &snd_trace)
+                    .unwrap();
+            (accident, crash)
+        };
+
+        let failed = "    2,RENAME,-1,Error(20),\n";
+        assert_eq!((false, false), run(failed, failed));
+        assert!(!fs::exists(dir.path.join("accidents").join(input.generate_name())).unwrap());
+
+        let renamed = "    2,RENAME,0,Success(0),\n";
+        assert_eq!((false, true), run(failed, renamed));
+        assert!(fs::exists(dir.path.join("crashes").join(input.generate_name())).unwrap());
+
+        // unexpected failures on both sides are still accidents
+        let created = "Index,Command,ReturnCode,Errno,Extra\n    0,MKDIR,-1,Error(17),\n";
+        fs::write(&fuzzer.runner.fst_trace_path, created).unwrap();
+        let trace = parse_trace(&fuzzer.runner.fst_trace_path).unwrap();
+        assert!(fuzzer
+            .detect_errors(&input, &input_path, &trace, &trace)
+            .unwrap());
+        // accidents are not counted as crashes
+        assert_eq!(1, fuzzer.runner.stats.crashes);
+        assert_eq!(1, fuzzer.runner.stats.unique_crashes);
+    }
+
+    #[test]
+    fn test_reduced_crash_saved() {
+        // removing '/b' fails only on second filesystem, and both fail first operation without '/c'
+        let rerun_traces = |input: &Workload| {
+            let has_c = input
+                .ops
+                .iter()
+                .any(|op| matches!(op, Operation::MKDIR { path, .. } if *path == "/c".into()));
+            let mut fst = "Index,Command,ReturnCode,Errno,Extra\n".to_owned();
+            let mut snd = fst.clone();
+            for (i, op) in input.ops.iter().enumerate() {
+                let command = format!("{:?}", op.kind());
+                let (fst_row, snd_row) = match op {
+                    _ if i == 0 && !has_c => {
+                        ("-1,Input/output error(5)", "-1,Input/output error(5)")
+                    }
+                    Operation::REMOVE { .. } => ("0,Success(0)", "-1,Input/output error(5)"),
+                    _ => ("0,Success(0)", "0,Success(0)"),
+                };
+                fst.push_str(&format!("{},{},{},\n", i, command, fst_row));
+                snd.push_str(&format!("{},{},{},\n", i, command, snd_row));
+            }
+            (fst, snd)
+        };
+        let mut fuzzer = mock_fuzzer();
+        fuzzer.rerun_traces = Some(rerun_traces);
+        let dir = TestDir::new("reduced-crash", &mut fuzzer.runner);
+
+        let create = |path: &str| Operation::CREATE {
+            path: path.into(),
+            mode: vec![],
+        };
+        let mkdir_c = Operation::MKDIR {
+            path: "/c".into(),
+            mode: vec![],
+        };
+        let remove_b = Operation::REMOVE { path: "/b".into() };
+        let input = Workload {
+            ops: vec![
+                create("/a"),
+                create("/b"),
+                mkdir_c.clone(),
+                remove_b.clone(),
+            ],
+            seed: 0,
+        };
+        fuzzer.rerun(&input).unwrap();
+        let fst_trace = parse_trace(&fuzzer.runner.fst_trace_path).unwrap();
+        let snd_trace = parse_trace(&fuzzer.runner.snd_trace_path).unwrap();
+        assert!(fuzzer
+            .do_objective(&input, &dir.input_path, &fst_trace, &snd_trace)
+            .unwrap());
+
+        // '/c' is kept, without it both traces contain errors
+        let reduced = Workload {
+            ops: vec![create("/b"), mkdir_c, remove_b],
+            seed: 0,
+        };
+        let crash_dir = dir.path.join("crashes").join(reduced.generate_name());
+        let saved: Workload = serde_json::from_str(
+            &fs::read_to_string(crash_dir.join(TESTCASE_JSON_FILENAME)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(reduced, saved);
+        assert!(!dir
+            .path
+            .join("crashes")
+            .join(input.generate_name())
+            .exists());
+    }
+
+    static DIR: MockMount = MockMount::new();
+
+    #[test]
+    fn test_inconclusive_run_not_reported() {
+        let mut fuzzer = mock_fuzzer();
+        let dir = TestDir::new("inconclusive-run", &mut fuzzer.runner);
+        // only the second filesystem fails the operation
+        let input_path = dir.input_path.clone();
+        fs::write(
+            &input_path,
+            format!(
+                "#!/bin/sh\ncase \"$1\" in\n*fst*) rc='0,Success(0)' ;;\n*) rc='-1,Input/output error(5)' ;;\nesac\nprintf 'Index,Command,ReturnCode,Errno,Extra\\n0,MKDIR,%s,\\n' \"$rc\" > {}\n",
+                TRACE_FILENAME
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&input_path, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let runner = &mut fuzzer.runner;
+        for (side, exec_dir, trace_path, harness) in [
+            (
+                "fst",
+                &mut runner.fst_exec_dir,
+                &mut runner.fst_trace_path,
+                &mut runner.fst_harness,
+            ),
+            (
+                "snd",
+                &mut runner.snd_exec_dir,
+                &mut runner.snd_trace_path,
+                &mut runner.snd_harness,
+            ),
+        ] {
+            *exec_dir = dir.path.join(format!("{}_exec", side)).into_boxed_path();
+            *trace_path = exec_dir.join(TRACE_FILENAME).into_boxed_path();
+            *harness = Harness::new(
+                &DIR,
+                dir.path.join(format!("{}_fs", side)).into_boxed_path(),
+                exec_dir.clone(),
+                Rc::new(RefCell::new(String::new())),
+                Rc::new(RefCell::new(String::new())),
+                "/dev/null",
+                HarnessOptions::default(),
+            );
+        }
+        runner.hash_objective = HashObjective::new(
+            dir.path.join("fst_fs").into_boxed_path(),
+            dir.path.join("snd_fs").into_boxed_path(),
+            RegexSet::empty(),
+            RegexSet::empty(),
+            HasherOptions::default(),
+            true,
+            vec![],
+        );
+        runner
+            .snd_observers
+            .push(Box::new(MockObserver::new(ObserverVerdict::Inconclusive(
+                "noise".to_owned(),
+            ))));
+
+        let input = Workload {
+            ops: vec![Operation::MKDIR {
+                path: "/a".into(),
+                mode: vec![],
+            }],
+            seed: 0,
+        };
+        assert!(fuzzer.run_objectives(&input, &input_path).unwrap());
+        assert_eq!(0, fuzzer.runner.stats.crashes);
+        assert_eq!(1, fuzzer.runner.stats.inconclusive);
+        assert!(!fuzzer.runner.crashes_path.exists());
+
+        // the same run is a crash once observer agrees
+        fuzzer.runner.snd_observers =
+            vec![Box::new(MockObserver::new(ObserverVerdict::Conclusive))];
+        assert!(fuzzer.run_objectives(&input, &input_path).unwrap());
+        assert_eq!(1, fuzzer.runner.stats.crashes);
+        assert!(fuzzer
+            .runner
+            .crashes_path
+            .join(input.generate_name())
+            .exists());
+    }
+
+    #[test]
+    fn test_data_modes_recorded() {
+        let mut fuzzer = mock_fuzzer_with(|config| {
+            config.data_modes = vec![
+                ("ordered".to_owned(), "journal".to_owned()),
+                ("writeback".to_owned(), "".to_owned()),
+            ];
+        });
+        let dir = TestDir::new("data-modes", &mut fuzzer.runner);
+        let input_path = dir.input_path.clone();
+        let runner = &mut fuzzer.runner;
+        let header = "Index,Command,ReturnCode,Errno,Extra\n";
+        fs::write(&runner.fst_trace_path, header).unwrap();
+        fs::write(&runner.snd_trace_path, header).unwrap();
+
+        let mounts: Vec<(String, String)> = (0..3)
+            .map(|execution| {
+                runner.select_data_modes(execution);
+                (
+                    runner.fst_harness.fs_mount().to_string(),
+                    runner.snd_harness.fs_mount().to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("Ext4-ordered".to_owned(), "Ext4-journal".to_owned()),
+                ("Ext4-writeback".to_owned(), "Ext4".to_owned()),
+                ("Ext4-ordered".to_owned(), "Ext4-journal".to_owned()),
+            ],
+            mounts
+        );
+        assert_eq!(
+            ("Ext4", "Ext4"),
+            (&*runner.fst_fs_name, &*runner.snd_fs_name)
+        );
+
+        let input = Workload::new();
+        runner
+            .report_crash(
+                &input,
+                &input_path,
+                runner.crashes_path.clone(),
+                vec![],
+                CrashKind::Divergence,
+                "summary",
+            )
+            .unwrap();
+        let reason = fs::read_to_string(
+            dir.path
+                .join("crashes")
+                .join(input.generate_name())
+                .join(REASON_FILENAME),
+        )
+        .unwrap();
+        assert!(
+            reason.starts_with("summary\n'Ext4': data=ordered\n'Ext4': data=journal\n"),
+            "{}",
+            reason
+        );
+        // the same filesystem on both sides is saved twice
+        let crash_dir = dir.path.join("crashes").join(input.generate_name());
+        for side in ["fst", "snd"] {
+            assert!(crash_dir.join(format!("{}.Ext4.trace.csv", side)).exists());
+            assert!(crash_dir.join(format!("{}.Ext4.stdout.txt", side)).exists());
+        }
+
+        // reduced crash is saved again with isolated divergence
+        for reason in ["same hash difference", "only mode of '0' differs"] {
+            runner
+                .report_crash(
+                    &input,
+                    &input_path,
+                    runner.crashes_path.clone(),
+                    vec![],
+                    CrashKind::Reduced,
+                    reason,
+                )
+                .unwrap();
+        }
+        let reason = fs::read_to_string(
+            dir.path
+                .join("crashes")
+                .join(input.generate_name())
+                .join(REASON_FILENAME),
+        )
+        .unwrap();
+        assert!(
+            reason.starts_with("only mode of '0' differs\n"),
+            "{}",
+            reason
+        );
+    }
+
+    #[test]
+    fn test_control_bound_by_loop() {
+        let path = std::env::temp_dir().join("DIFFuzzer-control-bind-test.sock");
+        fs::remove_file(&path).unwrap_or(());
+        let mut fuzzer = mock_fuzzer_with(|config| {
+            config.control_socket = Some(path.to_string_lossy().into_owned());
+        });
+        let _dir = TestDir::new("control-bind", &mut fuzzer.runner);
+        // reducer and triage only make runner
+        assert!(fuzzer.runner.control.is_none());
+        assert!(!path.exists());
+
+        assert_eq!(LoopExit::Finished, fuzzer.run(Some(1), None, false));
+        assert!(fuzzer.runner.control.is_some());
+        assert!(path.exists());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_hasher_options() {
+        let mut config: Config = toml::from_str(include_str!("../../config.toml")).unwrap();
+        assert!(!hasher_options(&config)
+            .enabled()
+            .contains(&Attribute::Allocated));
+        config.hash_allocated = true;
+        config.hash_times = true;
+        let enabled = hasher_options(&config).enabled();
+        assert!(enabled.contains(&Attribute::Allocated));
+        assert!(enabled.contains(&Attribute::Times));
+    }
+
+    #[test]
+    fn test_crashed_during() {
+        let input = Workload {
+            ops: vec![
+                Operation::MKDIR {
+                    path: "/foo".into(),
+                    mode: vec![],
+                },
+                Operation::REMOVE {
+                    path: "/foo".into(),
+                },
+            ],
+            seed: 0,
+        };
+        assert_eq!(
+            "'Ext4' crashed during operation 0 (MKDIR), last completed operation: none",
+            crashed_during("Ext4", &input, 0)
+        );
+        assert_eq!(
+            "'Ext4' crashed during operation 1 (REMOVE), last completed operation: 0 (MKDIR)",
+            crashed_during("Ext4", &input, 1)
+        );
+    }
+
+    #[test]
+    fn test_detected() {
+        assert!(!Detected::default().any());
+        let detected = Detected {
+            hash: true,
+            slow_op: true,
+            ..Detected::default()
+        };
+        assert!(detected.any());
+        let reason = detected.to_string();
+        assert!(
+            reason.starts_with("detected by trace objective: false, hash objective: true, "),
+            "{}",
+            reason
+        );
+        assert!(reason.contains(", slow operation objective: true, "));
+        assert!(reason.ends_with(", failure objective: false"));
+    }
+}
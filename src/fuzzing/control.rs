@@ -0,0 +1,208 @@
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::Path,
+    time::Duration,
+};
+
+use anyhow::Context;
+use log::{debug, info, warn};
+use serde::Serialize;
+
+use crate::abstract_fs::workload::Workload;
+
+/// Client that does not send request (or read response) in time is dropped, so fuzzer is not stalled.
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Read-only requests accepted by control socket, one per line.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ControlCommand {
+    Stats,
+    ListCrashes,
+    DumpCorpus,
+}
+
+impl TryFrom<&str> for ControlCommand {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim() {
+            "stats" => Ok(ControlCommand::Stats),
+            "list-crashes" => Ok(ControlCommand::ListCrashes),
+            "dump-corpus" => Ok(ControlCommand::DumpCorpus),
+            cmd => Err(format!("unknown command '{}'", cmd)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum ControlResponse {
+    Stats {
+        executions: usize,
+        crashes: usize,
+        unique_crashes: usize,
+        inconclusive: usize,
+        peak_model_nodes: usize,
+        repaired_ops: usize,
+        uptime_secs: u64,
+        /// Executions per second over the last minute.
+        exec_per_sec: f64,
+        /// Tests completed out of total, if number of tests is limited.
+        progress: Option<(u64, u64)>,
+    },
+    Crashes(Vec<String>),
+    Corpus(Vec<Workload>),
+    Error(String),
+}
+
+/// Unix socket polled by fuzzer between executions, so requests never run concurrently with a test.
+pub struct ControlSocket {
+    listener: UnixListener,
+}
+
+impl ControlSocket {
+    pub fn bind(path: &Path) -> anyhow::Result<Self> {
+        fs::remove_file(path).unwrap_or(());
+        let listener = UnixListener::bind(path)
+            .with_context(|| format!("failed to bind control socket at '{}'", path.display()))?;
+        listener
+            .set_nonblocking(true)
+            .with_context(|| format!("failed to make control socket non-blocking"))?;
+        info!("control socket listening at '{}'", path.display());
+        Ok(Self { listener })
+    }
+
+    /// Answers all pending requests without blocking, returns number of requests served.
+    pub fn serve(
+        &self,
+        mut respond: impl FnMut(ControlCommand) -> ControlResponse,
+    ) -> anyhow::Result<usize> {
+        let mut served = 0;
+        loop {
+            let stream = match self.listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(served),
+                Err(err) => return Err(err).with_context(|| format!("failed to accept client")),
+            };
+            if let Err(err) = serve_client(stream, &mut respond) {
+                warn!("failed to serve control client: {:?}", err);
+            }
+            served += 1;
+        }
+    }
+}
+
+fn serve_client(
+    stream: UnixStream,
+    respond: &mut impl FnMut(ControlCommand) -> ControlResponse,
+) -> anyhow::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut line)
+        .with_context(|| format!("failed to read request"))?;
+    debug!("control request '{}'", line.trim());
+    let response = match ControlCommand::try_from(line.as_str()) {
+        Ok(cmd) => respond(cmd),
+        Err(err) => ControlResponse::Error(err),
+    };
+    let mut response =
+        serde_json::to_string(&response).with_context(|| format!("failed to encode response"))?;
+    response.push('\n');
+    (&stream)
+        .write_all(response.as_bytes())
+        .with_context(|| format!("failed to write response"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, io::Read, thread};
+
+    use crate::abstract_fs::{node::FileDescriptorIndex, operation::Operation};
+
+    use super::*;
+
+    fn request(socket: &ControlSocket, path: &Path, cmd: &str) -> serde_json::Value {
+        let mut client = UnixStream::connect(path).unwrap();
+        client.write_all(format!("{}\n", cmd).as_bytes()).unwrap();
+        let handle = thread::spawn(move || {
+            let mut response = String::new();
+            client.read_to_string(&mut response).unwrap();
+            response
+        });
+        let served = socket
+            .serve(|cmd| match cmd {
+                ControlCommand::Stats => ControlResponse::Stats {
+                    executions: 42,
+                    crashes: 1,
+                    unique_crashes: 1,
+                    inconclusive: 0,
+                    peak_model_nodes: 10,
+                    repaired_ops: 0,
+                    uptime_secs: 5,
+                    exec_per_sec: 8.5,
+                    progress: Some((42, 100)),
+                },
+                ControlCommand::ListCrashes => ControlResponse::Crashes(vec!["abc".to_owned()]),
+                ControlCommand::DumpCorpus => ControlResponse::Corpus(vec![Workload {
+                    ops: vec![Operation::FSYNC {
+                        des: FileDescriptorIndex(0),
+                    }],
+                    seed: 0,
+                }]),
+            })
+            .unwrap();
+        assert_eq!(1, served);
+        serde_json::from_str(&handle.join().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_control_commands() {
+        let dir = env::temp_dir().join("DIFFuzzer-control-test");
+        fs::remove_dir_all(&dir).unwrap_or(());
+        fs::create_dir(&dir).unwrap();
+        let path = dir.join("control.sock");
+        let socket = ControlSocket::bind(&path).unwrap();
+        assert_eq!(0, socket.serve(|_| unreachable!()).unwrap());
+
+        let stats = request(&socket, &path, "stats");
+        assert_eq!(42, stats["Stats"]["executions"]);
+        assert_eq!(5, stats["Stats"]["uptime_secs"]);
+        assert_eq!(8.5, stats["Stats"]["exec_per_sec"]);
+        assert_eq!(serde_json::json!([42, 100]), stats["Stats"]["progress"]);
+
+        let crashes = request(&socket, &path, "list-crashes");
+        assert_eq!(serde_json::json!({"Crashes": ["abc"]}), crashes);
+
+        let corpus = request(&socket, &path, "dump-corpus");
+        assert_eq!(
+            serde_json::json!({"Corpus": [{"ops": [{"FSYNC": {"des": 0}}], "seed": 0}]}),
+            corpus
+        );
+
+        let error = request(&socket, &path, "rm -rf");
+        assert_eq!(
+            serde_json::json!({"Error": "unknown command 'rm -rf'"}),
+            error
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_silent_client_dropped() {
+        let dir = env::temp_dir().join("DIFFuzzer-control-silent-test");
+        fs::remove_dir_all(&dir).unwrap_or(());
+        fs::create_dir(&dir).unwrap();
+        let path = dir.join("control.sock");
+        let socket = ControlSocket::bind(&path).unwrap();
+        let _client = UnixStream::connect(&path).unwrap();
+        assert_eq!(1, socket.serve(|_| unreachable!()).unwrap());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
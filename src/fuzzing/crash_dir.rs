@@ -0,0 +1,246 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use siphasher::sip128::{Hasher128, SipHasher13};
+use std::hash::Hasher;
+use thiserror::Error;
+
+const TOKENS: &[&str] = &["date", "signature", "fst", "snd", "kind"];
+
+#[derive(Error, Debug, PartialEq)]
+pub enum TemplateError {
+    #[error("unknown token '{{{0}}}'")]
+    UnknownToken(String),
+    #[error("unbalanced braces")]
+    UnbalancedBraces,
+    #[error("template must be a relative path without '..'")]
+    InvalidPath,
+}
+
+/// What kind of problem the saved testcase shows.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CrashKind {
+    /// Traces or hashes differ.
+    Divergence,
+    /// Executor died before finishing workload.
+    Truncated,
+    /// Both traces contain errors.
+    Accident,
+    /// Reduced testcase with the same difference.
+    Reduced,
+}
+
+impl Display for CrashKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CrashKind::Divergence => write!(f, "divergence"),
+            CrashKind::Truncated => write!(f, "truncated"),
+            CrashKind::Accident => write!(f, "accident"),
+            CrashKind::Reduced => write!(f, "reduced"),
+        }
+    }
+}
+
+/// Everything that can be substituted into [`CrashDirTemplate`].
+pub struct CrashInfo<'a> {
+    pub kind: CrashKind,
+    pub fst_fs_name: &'a str,
+    pub snd_fs_name: &'a str,
+    pub reason: &'a str,
+    pub time: SystemTime,
+}
+
+/// Relative path with `{token}` placeholders, e.g. `{date}/{fst}-{snd}/{signature}`.
+///
+/// Testcase is saved at `<crashes>/<rendered template>/<name>`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CrashDirTemplate(String);
+
+impl TryFrom<String> for CrashDirTemplate {
+    type Error = TemplateError;
+
+    fn try_from(template: String) -> Result<Self, Self::Error> {
+        let mut rest = template.as_str();
+        while let Some(start) = rest.find(|c| c == '{' || c == '}') {
+            let Some(token) = rest[start..]
+                .strip_prefix('{')
+                .and_then(|after| after.split_once('}'))
+                .map(|(token, _)| token)
+            else {
+                return Err(TemplateError::UnbalancedBraces);
+            };
+            if token.contains('{') {
+                return Err(TemplateError::UnbalancedBraces);
+            }
+            if !TOKENS.contains(&token) {
+                return Err(TemplateError::UnknownToken(token.to_owned()));
+            }
+            rest = &rest[start + token.len() + 2..];
+        }
+        let path = Path::new(&template);
+        if path.is_absolute() || path.components().any(|c| c.as_os_str() == "..") {
+            return Err(TemplateError::InvalidPath);
+        }
+        Ok(Self(template))
+    }
+}
+
+impl From<CrashDirTemplate> for String {
+    fn from(template: CrashDirTemplate) -> Self {
+        template.0
+    }
+}
+
+impl CrashDirTemplate {
+    pub fn render(&self, info: &CrashInfo) -> PathBuf {
+        let path = self
+            .0
+            .replace("{date}", &utc_date(info.time))
+            .replace("{signature}", &signature(info.reason))
+            .replace("{fst}", info.fst_fs_name)
+            .replace("{snd}", info.snd_fs_name)
+            .replace("{kind}", &info.kind.to_string());
+        PathBuf::from(path)
+    }
+}
+
+/// Hash of reason with numbers erased, so crashes at different operation indices share it.
+pub fn signature(reason: &str) -> String {
+    let normalized = Regex::new(r"\d+").unwrap().replace_all(reason, "N");
+    let mut hasher = SipHasher13::new();
+    hasher.write(normalized.as_bytes());
+    format!("{:016x}", hasher.finish128().h1)
+}
+
+/// Date as `YYYY-MM-DD` in UTC.
+fn utc_date(time: SystemTime) -> String {
+    let days = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86400)
+        .unwrap_or(0) as i64;
+    // civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn info(kind: CrashKind, reason: &'static str) -> CrashInfo<'static> {
+        CrashInfo {
+            kind,
+            fst_fs_name: "Ext4",
+            snd_fs_name: "Btrfs",
+            reason,
+            time: UNIX_EPOCH + Duration::from_secs(1729036800),
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let template = CrashDirTemplate::try_from("{date}/{fst}-{snd}/{kind}".to_owned()).unwrap();
+        assert_eq!(
+            PathBuf::from("2024-10-16/Ext4-Btrfs/divergence"),
+            template.render(&info(CrashKind::Divergence, "trace"))
+        );
+        assert_eq!(
+            PathBuf::from("2024-10-16/Ext4-Btrfs/truncated"),
+            template.render(&info(CrashKind::Truncated, "crashed"))
+        );
+        assert_eq!(
+            PathBuf::from(""),
+            CrashDirTemplate::try_from("".to_owned())
+                .unwrap()
+                .render(&info(CrashKind::Accident, "errors"))
+        );
+    }
+
+    #[test]
+    fn test_signature() {
+        let template = CrashDirTemplate::try_from("by-signature/{signature}".to_owned()).unwrap();
+        let at_3 = template.render(&info(
+            CrashKind::Truncated,
+            "'Ext4' crashed during operation 3 (WRITE)",
+        ));
+        let at_7 = template.render(&info(
+            CrashKind::Truncated,
+            "'Ext4' crashed during operation 7 (WRITE)",
+        ));
+        let other = template.render(&info(
+            CrashKind::Truncated,
+            "'Ext4' crashed during operation 3 (READ)",
+        ));
+        assert_eq!(at_3, at_7);
+        assert_ne!(at_3, other);
+        assert!(at_3.starts_with("by-signature"));
+    }
+
+    #[test]
+    fn test_utc_date() {
+        assert_eq!("1970-01-01", utc_date(UNIX_EPOCH));
+        assert_eq!(
+            "2000-02-29",
+            utc_date(UNIX_EPOCH + Duration::from_secs(951782400))
+        );
+        assert_eq!(
+            "2024-12-31",
+            utc_date(UNIX_EPOCH + Duration::from_secs(1735689599))
+        );
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        assert_eq!(
+            Err(TemplateError::UnknownToken("time".to_owned())),
+            CrashDirTemplate::try_from("{date}/{time}".to_owned())
+        );
+        assert_eq!(
+            Err(TemplateError::UnbalancedBraces),
+            CrashDirTemplate::try_from("{date".to_owned())
+        );
+        assert_eq!(
+            Err(TemplateError::UnbalancedBraces),
+            CrashDirTemplate::try_from("date}".to_owned())
+        );
+        assert_eq!(
+            Err(TemplateError::UnbalancedBraces),
+            CrashDirTemplate::try_from("{{date}}".to_owned())
+        );
+        assert_eq!(
+            Err(TemplateError::InvalidPath),
+            CrashDirTemplate::try_from("/tmp/{date}".to_owned())
+        );
+        assert_eq!(
+            Err(TemplateError::InvalidPath),
+            CrashDirTemplate::try_from("{kind}/../..".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_config_validation() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            #[allow(dead_code)]
+            template: CrashDirTemplate,
+        }
+        assert!(toml::from_str::<Wrapper>("template = \"{kind}/{signature}\"").is_ok());
+        assert!(toml::from_str::<Wrapper>("template = \"{oops}\"").is_err());
+    }
+}
@@ -0,0 +1,279 @@
+//! Corpus packed into single tar archive, so it can be moved between machines.
+//!
+//! Archive keeps saved inputs as is, one directory per input with `test.json` inside.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::Read,
+    os::unix::fs::PermissionsExt,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{bail, Context};
+use log::{info, warn};
+
+use crate::{
+    abstract_fs::{operation::OperationWeights, validate::validate, workload::Workload},
+    save::TESTCASE_JSON_FILENAME,
+};
+
+/// Packs every saved input of corpus into archive, returns number of inputs packed.
+pub fn export(corpus_dir: &Path, output: &Path) -> anyhow::Result<usize> {
+    let mut inputs: Vec<PathBuf> = fs::read_dir(corpus_dir)
+        .with_context(|| format!("failed to read corpus at '{}'", corpus_dir.display()))?
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("failed to read corpus at '{}'", corpus_dir.display()))?
+        .into_iter()
+        .map(|entry| entry.path())
+        .filter(|path| path.join(TESTCASE_JSON_FILENAME).is_file())
+        .collect();
+    inputs.sort();
+    let file = File::create(output)
+        .with_context(|| format!("failed to create archive at '{}'", output.display()))?;
+    let mut builder = tar::Builder::new(file);
+    for input in inputs.iter() {
+        builder
+            .append_dir_all(input.file_name().unwrap(), input)
+            .with_context(|| format!("failed to pack input '{}'", input.display()))?;
+    }
+    builder
+        .into_inner()
+        .with_context(|| format!("failed to finish archive at '{}'", output.display()))?;
+    info!(
+        "exported {} inputs from '{}' to '{}'",
+        inputs.len(),
+        corpus_dir.display(),
+        output.display()
+    );
+    Ok(inputs.len())
+}
+
+/// Unpacks inputs from archive into corpus, returns number of inputs imported.
+///
+/// Inputs that already exist in corpus are kept, invalid ones (see [`check`]) are skipped.
+pub fn import(
+    archive: &Path,
+    corpus_dir: &Path,
+    weights: &OperationWeights,
+) -> anyhow::Result<usize> {
+    let file = File::open(archive)
+        .with_context(|| format!("failed to open archive at '{}'", archive.display()))?;
+    // files of every input, so that input is checked before anything is written
+    let mut inputs: BTreeMap<String, Vec<(PathBuf, Vec<u8>, u32)>> = BTreeMap::new();
+    let mut tar = tar::Archive::new(file);
+    for entry in tar
+        .entries()
+        .with_context(|| format!("failed to read archive at '{}'", archive.display()))?
+    {
+        let mut entry =
+            entry.with_context(|| format!("failed to read archive at '{}'", archive.display()))?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path()?.into_owned();
+        let components: Vec<&str> = path
+            .components()
+            .map(|component| match component {
+                Component::Normal(name) => name.to_str(),
+                _ => None,
+            })
+            .collect::<Option<_>>()
+            .unwrap_or_default();
+        let [name, file_name] = components[..] else {
+            warn!("skipping unexpected archive entry '{}'", path.display());
+            continue;
+        };
+        let mode = entry.header().mode()?;
+        let mut data = vec![];
+        entry
+            .read_to_end(&mut data)
+            .with_context(|| format!("failed to unpack '{}'", path.display()))?;
+        inputs
+            .entry(name.to_owned())
+            .or_default()
+            .push((PathBuf::from(file_name), data, mode));
+    }
+
+    fs::create_dir_all(corpus_dir)
+        .with_context(|| format!("failed to create corpus at '{}'", corpus_dir.display()))?;
+    let mut imported = 0;
+    for (name, files) in inputs {
+        let Some((_, json, _)) = files
+            .iter()
+            .find(|(path, _, _)| path == Path::new(TESTCASE_JSON_FILENAME))
+        else {
+            warn!(
+                "skipping input '{}' without {}",
+                name, TESTCASE_JSON_FILENAME
+            );
+            continue;
+        };
+        if let Err(err) = serde_json::from_slice(json)
+            .map_err(anyhow::Error::from)
+            .and_then(|workload| check(&workload, weights))
+        {
+            warn!("skipping input '{}': {:#}", name, err);
+            continue;
+        }
+        let input_dir = corpus_dir.join(&name);
+        if input_dir.exists() {
+            continue;
+        }
+        fs::create_dir(&input_dir).with_context(|| {
+            format!(
+                "failed to create input directory at '{}'",
+                input_dir.display()
+            )
+        })?;
+        for (file_name, data, mode) in files {
+            let path = input_dir.join(file_name);
+            fs::write(&path, data)
+                .with_context(|| format!("failed to write '{}'", path.display()))?;
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode))
+                .with_context(|| format!("failed to set permissions of '{}'", path.display()))?;
+        }
+        imported += 1;
+    }
+    info!(
+        "imported {} inputs from '{}' to '{}'",
+        imported,
+        archive.display(),
+        corpus_dir.display()
+    );
+    Ok(imported)
+}
+
+/// Input has to replay in the model and use only operations enabled in weights,
+/// otherwise fuzzer would mutate workloads it could never generate itself.
+pub fn check(workload: &Workload, weights: &OperationWeights) -> anyhow::Result<()> {
+    validate(workload)?;
+    for op in workload.ops.iter() {
+        if !weights
+            .weights
+            .iter()
+            .any(|(kind, weight)| *kind == op.kind() && *weight > 0)
+        {
+            bail!("operation {:?} is disabled", op.kind());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, env};
+
+    use crate::abstract_fs::operation::{Operation, OperationKind};
+
+    use super::*;
+
+    fn save(corpus_dir: &Path, workload: &Workload) {
+        let dir = corpus_dir.join(workload.generate_name());
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join(TESTCASE_JSON_FILENAME),
+            serde_json::to_string_pretty(workload).unwrap(),
+        )
+        .unwrap();
+        fs::write(dir.join("test.out"), "").unwrap();
+    }
+
+    fn load(corpus_dir: &Path) -> HashSet<String> {
+        fs::read_dir(corpus_dir)
+            .unwrap()
+            .map(|entry| {
+                let json =
+                    fs::read_to_string(entry.unwrap().path().join(TESTCASE_JSON_FILENAME)).unwrap();
+                serde_json::from_str::<Workload>(&json)
+                    .unwrap()
+                    .generate_name()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_export_import() {
+        let dir = env::temp_dir().join("DIFFuzzer-archive-test");
+        fs::remove_dir_all(&dir).unwrap_or(());
+        let (corpus, archive, imported) = (
+            dir.join("corpus"),
+            dir.join("corpus.tar"),
+            dir.join("imported"),
+        );
+        fs::create_dir_all(&corpus).unwrap();
+
+        let valid = Workload {
+            ops: vec![Operation::MKDIR {
+                path: "/foo".into(),
+                mode: vec![],
+            }],
+            seed: 42,
+        };
+        let also_valid = Workload {
+            ops: vec![
+                Operation::CREATE {
+                    path: "/bar".into(),
+                    mode: vec![],
+                },
+                Operation::STAT {
+                    path: "/bar".into(),
+                },
+            ],
+            seed: 0,
+        };
+        let invalid = Workload {
+            ops: vec![Operation::REMOVE {
+                path: "/foo".into(),
+            }],
+            seed: 0,
+        };
+        let disabled = Workload {
+            ops: vec![Operation::LISTDIR { path: "/".into() }],
+            seed: 0,
+        };
+        for workload in [&valid, &also_valid, &invalid, &disabled] {
+            save(&corpus, workload);
+        }
+        // coverage is saved next to inputs, it is not packed
+        fs::write(corpus.join("ext4.coverage"), "").unwrap();
+
+        let mut weights = OperationWeights::uniform();
+        weights
+            .weights
+            .retain(|(kind, _)| *kind != OperationKind::LISTDIR);
+        assert_eq!(4, export(&corpus, &archive).unwrap());
+        assert_eq!(2, import(&archive, &imported, &weights).unwrap());
+        assert_eq!(
+            HashSet::from([valid.generate_name(), also_valid.generate_name()]),
+            load(&imported)
+        );
+        assert!(imported
+            .join(valid.generate_name())
+            .join("test.out")
+            .exists());
+        // inputs already in corpus are kept
+        assert_eq!(0, import(&archive, &imported, &weights).unwrap());
+        assert_eq!(2, load(&imported).len());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_check() {
+        let weights = OperationWeights::uniform();
+        assert!(check(&Workload::new(), &weights).is_ok());
+        let workload = Workload {
+            ops: vec![Operation::MKDIR {
+                path: "/foo".into(),
+                mode: vec![],
+            }],
+            seed: 0,
+        };
+        assert!(check(&workload, &weights).is_ok());
+        let weights = OperationWeights {
+            weights: vec![(OperationKind::MKDIR, 0)],
+        };
+        assert!(check(&workload, &weights).is_err());
+    }
+}
@@ -0,0 +1,121 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use anyhow::{bail, Context};
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::abstract_fs::workload::Workload;
+
+use super::schedule::CoverageRarity;
+
+pub const CHECKPOINT_FILENAME: &str = "checkpoint.json";
+
+/// Greybox fuzzer state needed to continue campaign after restart.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub corpus: Vec<Workload>,
+    /// Corpus entry that is picked next.
+    pub next_seed: usize,
+    /// Distance to target of each corpus entry, `None` if infinite (JSON has no infinity).
+    pub distances: Vec<Option<f64>>,
+    pub rarity: Option<CoverageRarity>,
+    pub fst_coverage: HashSet<u64>,
+    pub snd_coverage: HashSet<u64>,
+}
+
+impl Checkpoint {
+    pub fn save(&self, dir: &Path) -> anyhow::Result<()> {
+        fs::create_dir_all(dir).with_context(|| {
+            format!(
+                "failed to create checkpoint directory at '{}'",
+                dir.display()
+            )
+        })?;
+        let path = dir.join(CHECKPOINT_FILENAME);
+        debug!("saving checkpoint to '{}'", path.display());
+        let json = serde_json::to_string(self)
+            .with_context(|| format!("failed to serialize checkpoint"))?;
+        // previous checkpoint stays intact if fuzzer is killed while writing
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, json)
+            .with_context(|| format!("failed to write checkpoint at '{}'", tmp_path.display()))?;
+        fs::rename(&tmp_path, &path)
+            .with_context(|| format!("failed to move checkpoint to '{}'", path.display()))?;
+        Ok(())
+    }
+
+    pub fn load(dir: &Path) -> anyhow::Result<Self> {
+        let path = dir.join(CHECKPOINT_FILENAME);
+        let json = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read checkpoint at '{}'", path.display()))?;
+        let checkpoint: Checkpoint = serde_json::from_str(&json)
+            .with_context(|| format!("failed to parse checkpoint at '{}'", path.display()))?;
+        if checkpoint.corpus.len() != checkpoint.distances.len()
+            || checkpoint.next_seed > checkpoint.corpus.len()
+        {
+            bail!("inconsistent checkpoint at '{}'", path.display());
+        }
+        Ok(checkpoint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use crate::abstract_fs::fs::AbstractFS;
+
+    use super::*;
+
+    #[test]
+    fn test_save_load_round_trip() {
+        let dir = env::temp_dir().join("DIFFuzzer-checkpoint-test");
+        fs::remove_dir_all(&dir).unwrap_or(());
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        let mut rarity = CoverageRarity::new(1);
+        rarity.add(HashSet::from([0xffffffff81460712, 0x10]));
+        let checkpoint = Checkpoint {
+            corpus: vec![Workload::new(), fs.recording],
+            next_seed: 1,
+            distances: vec![None, Some(2.5)],
+            rarity: Some(rarity),
+            fst_coverage: HashSet::from([0xffffffff81460712, 0x10]),
+            snd_coverage: HashSet::from([0x20]),
+        };
+        checkpoint.save(&dir).unwrap();
+        assert!(!dir
+            .join(CHECKPOINT_FILENAME)
+            .with_extension("json.tmp")
+            .exists());
+        assert_eq!(checkpoint, Checkpoint::load(&dir).unwrap());
+
+        // saving again replaces previous checkpoint
+        let checkpoint = Checkpoint {
+            next_seed: 2,
+            ..checkpoint
+        };
+        checkpoint.save(&dir).unwrap();
+        assert_eq!(checkpoint, Checkpoint::load(&dir).unwrap());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_inconsistent() {
+        let dir = env::temp_dir().join("DIFFuzzer-checkpoint-inconsistent-test");
+        fs::remove_dir_all(&dir).unwrap_or(());
+        assert!(Checkpoint::load(&dir).is_err());
+        Checkpoint {
+            corpus: vec![Workload::new()],
+            next_seed: 0,
+            distances: vec![],
+            rarity: None,
+            fst_coverage: HashSet::new(),
+            snd_coverage: HashSet::new(),
+        }
+        .save(&dir)
+        .unwrap();
+        assert!(Checkpoint::load(&dir).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
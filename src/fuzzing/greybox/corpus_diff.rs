@@ -0,0 +1,98 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use anyhow::Context;
+use log::info;
+
+use crate::save::side_name;
+
+use super::feedback::kcov::{load_coverage, COVERAGE_FILENAME};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CoverageDiff {
+    pub fst_only: HashSet<u64>,
+    pub snd_only: HashSet<u64>,
+    pub shared: HashSet<u64>,
+}
+
+impl CoverageDiff {
+    pub fn new(fst: &HashSet<u64>, snd: &HashSet<u64>) -> Self {
+        Self {
+            fst_only: fst.difference(snd).copied().collect(),
+            snd_only: snd.difference(fst).copied().collect(),
+            shared: fst.intersection(snd).copied().collect(),
+        }
+    }
+}
+
+/// Number of saved inputs (each one is a directory) in corpus.
+fn corpus_size(corpus_dir: &Path) -> anyhow::Result<usize> {
+    let entries = fs::read_dir(corpus_dir)
+        .with_context(|| format!("failed to read corpus at '{}'", corpus_dir.display()))?;
+    let mut size = 0;
+    for entry in entries {
+        if entry?.file_type()?.is_dir() {
+            size += 1;
+        }
+    }
+    Ok(size)
+}
+
+/// Compares coverage of the same filesystem saved by two greybox campaigns, on the same `side`
+/// (`fst` or `snd`) in both.
+pub fn run(fst_corpus: &Path, snd_corpus: &Path, fs_name: &str, side: &str) -> anyhow::Result<()> {
+    let coverage_filename = format!("{}.{}", side_name(side, fs_name), COVERAGE_FILENAME);
+    let fst = load_coverage(&fst_corpus.join(&coverage_filename))
+        .with_context(|| format!("failed to load first coverage"))?;
+    let snd = load_coverage(&snd_corpus.join(&coverage_filename))
+        .with_context(|| format!("failed to load second coverage"))?;
+    let diff = CoverageDiff::new(&fst, &snd);
+    let fst_size = corpus_size(fst_corpus)?;
+    let snd_size = corpus_size(snd_corpus)?;
+    info!(
+        "coverage of '{}': first {}, second {}, shared {}, only first {}, only second {}",
+        fs_name,
+        fst.len(),
+        snd.len(),
+        diff.shared.len(),
+        diff.fst_only.len(),
+        diff.snd_only.len(),
+    );
+    info!(
+        "corpus size: first {}, second {} (delta {:+})",
+        fst_size,
+        snd_size,
+        snd_size as i64 - fst_size as i64,
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coverage_diff() {
+        let fst = HashSet::from([1, 2, 3, 4]);
+        let snd = HashSet::from([3, 4, 5]);
+        assert_eq!(
+            CoverageDiff {
+                fst_only: HashSet::from([1, 2]),
+                snd_only: HashSet::from([5]),
+                shared: HashSet::from([3, 4]),
+            },
+            CoverageDiff::new(&fst, &snd)
+        );
+    }
+
+    #[test]
+    fn test_coverage_diff_same() {
+        let fst = HashSet::from([1, 2]);
+        let diff = CoverageDiff::new(&fst, &fst);
+        assert!(diff.fst_only.is_empty());
+        assert!(diff.snd_only.is_empty());
+        assert_eq!(fst, diff.shared);
+        let diff = CoverageDiff::new(&fst, &HashSet::new());
+        assert_eq!(fst, diff.fst_only);
+        assert!(diff.shared.is_empty());
+    }
+}
@@ -0,0 +1,156 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use anyhow::{bail, Context};
+
+const KALLSYMS_PATH: &str = "/proc/kallsyms";
+
+/// Corpus entries closest to target are picked this many times in a row.
+pub const MAX_ENERGY: usize = 16;
+
+/// Kernel addresses to steer fuzzing to.
+///
+/// Without control flow graph distance is measured in address space,
+/// code of the same subsystem is usually placed close together.
+pub struct DirectedTarget {
+    targets: Vec<u64>,
+}
+
+impl DirectedTarget {
+    /// Reads targets file, each line is either an address (`0xffffffff81460712`) or a kernel symbol.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let targets = fs::read_to_string(path)
+            .with_context(|| format!("failed to read targets at '{}'", path.display()))?;
+        let needs_symbols = targets
+            .lines()
+            .any(|line| !line.trim().is_empty() && parse_addr(line.trim()).is_none());
+        let kallsyms = if needs_symbols {
+            fs::read_to_string(KALLSYMS_PATH)
+                .with_context(|| format!("failed to read symbols at '{}'", KALLSYMS_PATH))?
+        } else {
+            String::new()
+        };
+        Self::parse(&targets, &kallsyms)
+    }
+
+    pub fn parse(targets: &str, kallsyms: &str) -> anyhow::Result<Self> {
+        let mut addrs = vec![];
+        for target in targets.lines().map(|line| line.trim()) {
+            if target.is_empty() {
+                continue;
+            }
+            match parse_addr(target).or_else(|| resolve_symbol(kallsyms, target)) {
+                Some(addr) => addrs.push(addr),
+                None => bail!("unknown target '{}'", target),
+            }
+        }
+        if addrs.is_empty() {
+            bail!("no targets");
+        }
+        Ok(Self { targets: addrs })
+    }
+
+    /// Mean over targets of log distance to the closest covered address, zero if every target is hit.
+    pub fn distance(&self, coverage: &HashSet<u64>) -> f64 {
+        if coverage.is_empty() {
+            return f64::INFINITY;
+        }
+        let total: f64 = self
+            .targets
+            .iter()
+            .map(|target| {
+                let closest = coverage
+                    .iter()
+                    .map(|addr| addr.abs_diff(*target))
+                    .min()
+                    .unwrap();
+                (closest as f64 + 1.0).log2()
+            })
+            .sum();
+        total / self.targets.len() as f64
+    }
+}
+
+/// Number of times input is picked in a row, inputs closest to target (among `distances`) get [`MAX_ENERGY`].
+pub fn energy(distance: f64, distances: &[f64]) -> usize {
+    if !distance.is_finite() {
+        return 1;
+    }
+    let finite = distances.iter().copied().filter(|d| d.is_finite());
+    let min = finite.clone().fold(f64::INFINITY, f64::min);
+    let max = finite.fold(f64::NEG_INFINITY, f64::max);
+    if max - min <= f64::EPSILON {
+        return MAX_ENERGY;
+    }
+    let closeness = (max - distance) / (max - min);
+    1 + (closeness * (MAX_ENERGY - 1) as f64).round() as usize
+}
+
+fn parse_addr(addr: &str) -> Option<u64> {
+    addr.strip_prefix("0x")
+        .and_then(|addr| u64::from_str_radix(addr, 16).ok())
+}
+
+/// Looks up symbol in `/proc/kallsyms` format (`ffffffff81000000 T _stext`).
+fn resolve_symbol(kallsyms: &str, symbol: &str) -> Option<u64> {
+    kallsyms.lines().find_map(|line| {
+        let mut columns = line.split_whitespace();
+        let addr = columns.next()?;
+        let name = columns.nth(1)?;
+        if name == symbol {
+            u64::from_str_radix(addr, 16).ok()
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KALLSYMS: &str = "ffffffff81000000 T _stext
+ffffffff81460700 T ext4_rename
+ffffffff81460800 t ext4_rename2 [ext4]
+";
+
+    #[test]
+    fn test_parse_targets() {
+        let target = DirectedTarget::parse("0x10\n\next4_rename\n", KALLSYMS).unwrap();
+        assert_eq!(vec![0x10, 0xffffffff81460700], target.targets);
+        assert!(DirectedTarget::parse("ext4_unknown", KALLSYMS).is_err());
+        assert!(DirectedTarget::parse("\n", KALLSYMS).is_err());
+    }
+
+    #[test]
+    fn test_distance() {
+        let target = DirectedTarget::parse("0x1000\n0x2000", "").unwrap();
+        let hit = HashSet::from([0x1000, 0x2000]);
+        let near = HashSet::from([0x1010, 0x2010]);
+        let far = HashSet::from([0x100000]);
+        assert_eq!(0.0, target.distance(&hit));
+        assert!(target.distance(&near) < target.distance(&far));
+        assert!(target.distance(&far).is_finite());
+        assert_eq!(f64::INFINITY, target.distance(&HashSet::new()));
+    }
+
+    #[test]
+    fn test_closer_inputs_get_more_energy() {
+        let target = DirectedTarget::parse("0x1000", "").unwrap();
+        let distances: Vec<f64> = [
+            HashSet::from([0x1000]),
+            HashSet::from([0x1100]),
+            HashSet::from([0x10000]),
+            HashSet::new(),
+        ]
+        .iter()
+        .map(|coverage| target.distance(coverage))
+        .collect();
+        let energies: Vec<usize> = distances.iter().map(|d| energy(*d, &distances)).collect();
+        assert_eq!(MAX_ENERGY, energies[0]);
+        assert!(energies[0] > energies[1]);
+        assert!(energies[1] > energies[2]);
+        assert_eq!(1, energies[2]);
+        assert_eq!(1, energies[3]);
+        assert_eq!(MAX_ENERGY, energy(1.0, &[1.0, f64::INFINITY]));
+    }
+}
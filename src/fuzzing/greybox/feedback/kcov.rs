@@ -1,6 +1,6 @@
 use std::{
     collections::HashSet,
-    fs::File,
+    fs::{self, File},
     io::{BufRead, BufReader},
     path::Path,
 };
@@ -9,9 +9,17 @@ use anyhow::Context;
 use log::debug;
 
 pub const KCOV_FILENAME: &str = "kcov.dat";
+pub const COVERAGE_FILENAME: &str = "coverage.dat";
 
+/// Kernel coverage feedback, executor dumps PCs hit during workload (`KCOV_TRACE_PC`) to [`KCOV_FILENAME`].
+///
+/// Raw PCs are tracked instead of AFL-style bucketed edge hit counts: part of filesystem work
+/// (writeback, journal commits) runs asynchronously, so hit counts vary between identical runs
+/// and would make almost every input interesting.
+/// Input is interesting if it hits at least one PC never seen before.
 pub struct KCovFeedback {
     all_coverage: HashSet<u64>,
+    last_coverage: HashSet<u64>,
     kcov_path: Box<Path>,
 }
 
@@ -19,35 +27,63 @@ impl KCovFeedback {
     pub fn new(kcov_path: Box<Path>) -> Self {
         Self {
             all_coverage: HashSet::new(),
+            last_coverage: HashSet::new(),
             kcov_path,
         }
     }
     pub fn is_interesting(&mut self) -> anyhow::Result<bool> {
         debug!("do kcov feedback");
-        let kcov = File::open(&self.kcov_path).with_context(|| {
-            format!("failed to open kcov file at '{}'", self.kcov_path.display())
-        })?;
-        let reader = BufReader::new(kcov);
-        let mut new_coverage = HashSet::new();
-        for line in reader.lines() {
-            let addr = line.with_context(|| format!("failed to read lines from kcov file"))?;
-            let addr = parse_addr(&addr)
-                .with_context(|| format!("failed to parse addr from kcov line '{}'", addr))?;
-            new_coverage.insert(addr);
-        }
-        let c = self.all_coverage.clone();
-        let diff: Vec<&u64> = new_coverage.difference(&c).collect();
-        if diff.is_empty() {
-            Ok(false)
-        } else {
-            for v in diff {
-                self.all_coverage.insert(v.clone());
-            }
-            Ok(true)
-        }
+        let new_coverage = load_coverage(&self.kcov_path)?;
+        let before = self.all_coverage.len();
+        self.all_coverage.extend(new_coverage.iter());
+        self.last_coverage = new_coverage;
+        Ok(self.all_coverage.len() > before)
+    }
+
+    /// Number of distinct PCs seen so far.
+    pub fn total_coverage(&self) -> usize {
+        self.all_coverage.len()
+    }
+
+    /// All distinct PCs seen so far.
+    pub fn all_coverage(&self) -> &HashSet<u64> {
+        &self.all_coverage
+    }
+
+    /// Continues with coverage seen before restart, so that it is not reported as new.
+    pub fn restore_coverage(&mut self, coverage: HashSet<u64>) {
+        self.all_coverage = coverage;
+    }
+
+    /// Coverage of the last run.
+    pub fn last_coverage(&self) -> &HashSet<u64> {
+        &self.last_coverage
+    }
+
+    /// Saves all coverage seen so far in the same format as kcov output.
+    pub fn save_coverage(&self, path: &Path) -> anyhow::Result<()> {
+        let mut addrs: Vec<&u64> = self.all_coverage.iter().collect();
+        addrs.sort();
+        let lines: Vec<String> = addrs.iter().map(|addr| format!("0x{:x}", addr)).collect();
+        fs::write(path, lines.join("\n"))
+            .with_context(|| format!("failed to save coverage at '{}'", path.display()))
     }
 }
 
+pub fn load_coverage(path: &Path) -> anyhow::Result<HashSet<u64>> {
+    let kcov = File::open(path)
+        .with_context(|| format!("failed to open kcov file at '{}'", path.display()))?;
+    let reader = BufReader::new(kcov);
+    let mut coverage = HashSet::new();
+    for line in reader.lines() {
+        let addr = line.with_context(|| format!("failed to read lines from kcov file"))?;
+        let addr = parse_addr(&addr)
+            .with_context(|| format!("failed to parse addr from kcov line '{}'", addr))?;
+        coverage.insert(addr);
+    }
+    Ok(coverage)
+}
+
 fn parse_addr(addr: &str) -> Result<u64, std::num::ParseIntError> {
     let prefix_removed = addr.trim_start_matches("0x");
     u64::from_str_radix(prefix_removed, 16)
@@ -64,4 +100,48 @@ mod tests {
             parse_addr("0xffffffff81460712").unwrap()
         );
     }
+
+    #[test]
+    fn test_only_new_pcs_are_interesting() {
+        let dir = std::env::temp_dir().join("DIFFuzzer-kcov-feedback-test");
+        fs::remove_dir_all(&dir).unwrap_or(());
+        fs::create_dir(&dir).unwrap();
+        let kcov_path = dir.join(KCOV_FILENAME);
+        let mut feedback = KCovFeedback::new(kcov_path.clone().into_boxed_path());
+        let runs: &[(&str, bool, usize)] = &[
+            ("0x10\n0x20\n", true, 2),
+            ("0x20\n0x10\n", false, 2),
+            ("0x20\n", false, 2),
+            ("0x20\n0x30\n", true, 3),
+            ("", false, 3),
+        ];
+        for (kcov, interesting, total) in runs {
+            fs::write(&kcov_path, kcov).unwrap();
+            assert_eq!(*interesting, feedback.is_interesting().unwrap());
+            assert_eq!(*total, feedback.total_coverage());
+            assert_eq!(
+                &load_coverage(&kcov_path).unwrap(),
+                feedback.last_coverage()
+            );
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_save_load_coverage() {
+        let dir = std::env::temp_dir().join("DIFFuzzer-kcov-test");
+        fs::remove_dir_all(&dir).unwrap_or(());
+        fs::create_dir(&dir).unwrap();
+        let kcov_path = dir.join(KCOV_FILENAME);
+        fs::write(&kcov_path, "0xffffffff81460712\n0x10\n").unwrap();
+        let mut feedback = KCovFeedback::new(kcov_path.into_boxed_path());
+        assert!(feedback.is_interesting().unwrap());
+        let coverage_path = dir.join(COVERAGE_FILENAME);
+        feedback.save_coverage(&coverage_path).unwrap();
+        assert_eq!(
+            HashSet::from([0x10, 0xffffffff81460712]),
+            load_coverage(&coverage_path).unwrap()
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
 }
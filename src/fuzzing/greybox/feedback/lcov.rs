@@ -0,0 +1,148 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::{bail, Context};
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+pub const LCOV_FILENAME: &str = "coverage.info";
+
+/// Tracefiles of filesystems built with line coverage, filesystem without one only has kernel coverage.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LCovConfig {
+    pub fst: Option<String>,
+    pub snd: Option<String>,
+}
+
+/// Hit counts of source lines, keyed by source file and line number.
+pub type LineCoverage = HashMap<(String, u32), u64>;
+
+/// Line coverage feedback for user-space filesystems (e.g. FUSE) built with gcov/llvm-cov.
+///
+/// Expects `.info` tracefile (as produced by `lcov --capture`) to be rewritten after each run.
+pub struct LCovFeedback {
+    all_coverage: LineCoverage,
+    lcov_path: Box<Path>,
+}
+
+impl LCovFeedback {
+    pub fn new(lcov_path: Box<Path>) -> Self {
+        Self {
+            all_coverage: HashMap::new(),
+            lcov_path,
+        }
+    }
+
+    /// Interesting if the last run hit at least one line that was never hit before.
+    pub fn is_interesting(&mut self) -> anyhow::Result<bool> {
+        debug!("do lcov feedback");
+        let new_coverage = load_lcov(&self.lcov_path)?;
+        let mut interesting = false;
+        for (line, hits) in new_coverage {
+            let total = self.all_coverage.entry(line).or_insert(0);
+            if *total == 0 && hits > 0 {
+                interesting = true;
+            }
+            *total += hits;
+        }
+        Ok(interesting)
+    }
+
+    /// Number of distinct lines hit so far.
+    pub fn covered_lines(&self) -> usize {
+        self.all_coverage.values().filter(|hits| **hits > 0).count()
+    }
+}
+
+pub fn load_lcov(path: &Path) -> anyhow::Result<LineCoverage> {
+    let info = fs::read_to_string(path)
+        .with_context(|| format!("failed to read lcov file at '{}'", path.display()))?;
+    parse_lcov(&info)
+}
+
+/// Parses `SF:<source>` and `DA:<line>,<hits>[,<checksum>]` records, other records are ignored.
+fn parse_lcov(info: &str) -> anyhow::Result<LineCoverage> {
+    let mut coverage = HashMap::new();
+    let mut source: Option<&str> = None;
+    for line in info.lines().map(|line| line.trim()) {
+        if let Some(sf) = line.strip_prefix("SF:") {
+            source = Some(sf);
+        } else if line == "end_of_record" {
+            source = None;
+        } else if let Some(da) = line.strip_prefix("DA:") {
+            let Some(source) = source else {
+                bail!("line data '{}' outside of source file record", line);
+            };
+            let mut fields = da.split(',');
+            let (Some(number), Some(hits)) = (fields.next(), fields.next()) else {
+                bail!("invalid line data '{}'", line);
+            };
+            let number: u32 = number
+                .parse()
+                .with_context(|| format!("failed to parse line number from '{}'", line))?;
+            let hits: u64 = hits
+                .parse()
+                .with_context(|| format!("failed to parse hit count from '{}'", line))?;
+            *coverage.entry((source.to_owned(), number)).or_insert(0) += hits;
+        }
+    }
+    Ok(coverage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FST_SNAPSHOT: &str = "TN:
+SF:/src/fuse/inode.c
+FN:10,fuse_create
+FNDA:1,fuse_create
+DA:10,1
+DA:11,1
+DA:12,0
+LF:3
+LH:2
+end_of_record
+";
+
+    #[test]
+    fn test_parse_lcov() {
+        let coverage = parse_lcov(FST_SNAPSHOT).unwrap();
+        assert_eq!(3, coverage.len());
+        assert_eq!(
+            Some(&1),
+            coverage.get(&("/src/fuse/inode.c".to_owned(), 11))
+        );
+        assert_eq!(
+            Some(&0),
+            coverage.get(&("/src/fuse/inode.c".to_owned(), 12))
+        );
+        assert!(parse_lcov("DA:1,1\n").is_err());
+        assert!(parse_lcov("SF:/a.c\nDA:1\n").is_err());
+        assert!(parse_lcov("SF:/a.c\nDA:x,1\n").is_err());
+    }
+
+    #[test]
+    fn test_only_new_lines_are_interesting() {
+        let dir = std::env::temp_dir().join("DIFFuzzer-lcov-test");
+        fs::remove_dir_all(&dir).unwrap_or(());
+        fs::create_dir(&dir).unwrap();
+        let lcov_path = dir.join(LCOV_FILENAME);
+        let mut feedback = LCovFeedback::new(lcov_path.clone().into_boxed_path());
+
+        fs::write(&lcov_path, FST_SNAPSHOT).unwrap();
+        assert!(feedback.is_interesting().unwrap());
+        assert_eq!(2, feedback.covered_lines());
+
+        // same lines hit more times
+        fs::write(&lcov_path, FST_SNAPSHOT.replace("DA:11,1", "DA:11,5")).unwrap();
+        assert!(!feedback.is_interesting().unwrap());
+        assert_eq!(2, feedback.covered_lines());
+
+        // previously uncovered line is hit
+        fs::write(&lcov_path, FST_SNAPSHOT.replace("DA:12,0", "DA:12,3")).unwrap();
+        assert!(feedback.is_interesting().unwrap());
+        assert_eq!(3, feedback.covered_lines());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1 +1,2 @@
 pub mod kcov;
+pub mod lcov;
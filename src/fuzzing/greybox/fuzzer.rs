@@ -1,50 +1,108 @@
+use std::collections::HashSet;
 use std::fs;
 use std::path::Path;
-use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::time::Instant;
 
 use anyhow::{Context, Ok};
-use log::{debug, info};
+use log::{debug, info, warn};
 use rand::{rngs::StdRng, SeedableRng};
 
-use crate::fuzzing::common::{parse_trace, Fuzzer, Runner};
-use crate::fuzzing::greybox::feedback::kcov::KCOV_FILENAME;
-use crate::save::{save_output, save_testcase};
+use crate::abstract_fs::generator::generate_rename_loops;
+use crate::fuzzing::common::{campaign_seed, Fuzzer, Runner};
+use crate::fuzzing::greybox::feedback::kcov::{load_coverage, COVERAGE_FILENAME, KCOV_FILENAME};
+use crate::fuzzing::observer::ObserverVerdict;
+use crate::save::{save_output, save_testcase, side_name};
 use crate::{abstract_fs::workload::Workload, config::Config, mount::mount::FileSystemMount};
 
-use super::{feedback::kcov::KCovFeedback, mutator::Mutator};
+use super::{
+    checkpoint::Checkpoint,
+    directed::{energy, DirectedTarget},
+    feedback::{kcov::KCovFeedback, lcov::LCovFeedback},
+    mutator::Mutator,
+    schedule::{CoverageRarity, Scheduler},
+    seeds::load_seeds,
+    shard::Shard,
+    sync::CorpusSync,
+    trim::trim,
+};
 
 pub struct GreyBoxFuzzer {
     runner: Runner,
 
     corpus: Vec<Workload>,
     next_seed: usize,
+    current_seed: usize,
+
+    target: Option<DirectedTarget>,
+    /// Distance to target of each corpus entry.
+    distances: Vec<f64>,
+    /// Coverage of each corpus entry, only tracked with [`Scheduler::Rarity`].
+    rarity: Option<CoverageRarity>,
+    energy_left: usize,
 
     fst_kcov_feedback: KCovFeedback,
     snd_kcov_feedback: KCovFeedback,
+    fst_lcov_feedback: Option<LCovFeedback>,
+    snd_lcov_feedback: Option<LCovFeedback>,
 
     mutator: Mutator,
 
     corpus_path: Option<Box<Path>>,
+
+    shard: Option<Shard>,
+    sync: Option<CorpusSync>,
+
+    checkpoint_dir: Option<Box<Path>>,
+
+    trim_interval: Option<usize>,
+    /// Corpus entries before that index were already trimmed.
+    trimmed: usize,
 }
 
 impl GreyBoxFuzzer {
     pub fn new(
-        config: Config,
+        mut config: Config,
         fst_mount: &'static dyn FileSystemMount,
         snd_mount: &'static dyn FileSystemMount,
-    ) -> Self {
-        let mutator = Mutator::new(
-            StdRng::seed_from_u64(
-                SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis() as u64,
-            ),
-            config.operation_weights.clone(),
-            config.mutation_weights.clone(),
-            config.max_workload_length,
-            config.greybox.max_mutations,
-        );
+    ) -> anyhow::Result<Self> {
+        let mut rng = StdRng::seed_from_u64(campaign_seed(&mut config));
+
+        let mut corpus = vec![Workload::new()];
+        if config.greybox.rename_loop_seed {
+            corpus.push(generate_rename_loops(&mut rng, 5, 10));
+        }
+        let shard = config.greybox.shard.as_ref().map(Shard::new).transpose()?;
+        let sync = config
+            .greybox
+            .sync_dir
+            .as_ref()
+            .map(|dir| CorpusSync::new(Path::new(dir)))
+            .transpose()?;
+        if let Some(dir) = &config.greybox.seeds_dir {
+            let seeds = load_seeds(Path::new(dir))
+                .with_context(|| format!("failed to load seeds from '{}'", dir))?;
+            corpus.extend(
+                seeds
+                    .into_iter()
+                    .filter(|seed| shard.as_ref().is_none_or(|shard| shard.owns(seed))),
+            );
+        }
+
+        let target = config
+            .greybox
+            .targets
+            .as_ref()
+            .map(|path| DirectedTarget::load(Path::new(path)))
+            .transpose()?;
+        let distances = vec![f64::INFINITY; corpus.len()];
+        let rarity = match config.greybox.scheduler {
+            Scheduler::Rarity if target.is_some() => {
+                warn!("rarity scheduler is ignored when fuzzing is directed at targets");
+                None
+            }
+            Scheduler::Rarity => Some(CoverageRarity::new(corpus.len())),
+            Scheduler::Queue => None,
+        };
 
         let corpus_path = if config.greybox.save_corpus {
             let path = Path::new("./corpus");
@@ -54,42 +112,266 @@ impl GreyBoxFuzzer {
             None
         };
 
+        let checkpoint_dir = config
+            .greybox
+            .checkpoint_dir
+            .as_ref()
+            .map(|dir| Path::new(dir).to_path_buf().into_boxed_path());
+
+        let trim_interval = config.greybox.trim_interval;
+
         let runner = Runner::new(fst_mount, snd_mount, config);
 
+        let mutator = Mutator::new(
+            rng,
+            runner.generator(),
+            runner.config.mutation_weights.clone(),
+            runner.config.max_workload_length,
+            runner.config.greybox.max_mutations,
+        );
+
         let fst_kcov_path = runner.fst_exec_dir.join(KCOV_FILENAME);
         let snd_kcov_path = runner.snd_exec_dir.join(KCOV_FILENAME);
 
         let fst_kcov_feedback = KCovFeedback::new(fst_kcov_path.clone().into_boxed_path());
         let snd_kcov_feedback = KCovFeedback::new(snd_kcov_path.clone().into_boxed_path());
 
-        Self {
+        let lcov_feedback = |path: &Option<String>| {
+            path.as_ref()
+                .map(|path| LCovFeedback::new(Path::new(path).to_path_buf().into_boxed_path()))
+        };
+        let (fst_lcov_feedback, snd_lcov_feedback) = match &runner.config.greybox.lcov {
+            Some(lcov) => (lcov_feedback(&lcov.fst), lcov_feedback(&lcov.snd)),
+            None => (None, None),
+        };
+
+        Ok(Self {
             runner,
-            corpus: vec![Workload::new()],
+            corpus,
             next_seed: 0,
+            current_seed: 0,
+
+            target,
+            distances,
+            rarity,
+            energy_left: 0,
 
             fst_kcov_feedback,
             snd_kcov_feedback,
+            fst_lcov_feedback,
+            snd_lcov_feedback,
 
             mutator,
 
             corpus_path,
+
+            shard,
+            sync,
+
+            checkpoint_dir,
+
+            trim_interval,
+            trimmed: 0,
+        })
+    }
+
+    /// Continues campaign from checkpoint instead of initial seeds.
+    pub fn resume(mut self, dir: &Path) -> anyhow::Result<Self> {
+        let checkpoint = Checkpoint::load(dir)?;
+        info!(
+            "resuming with {} corpus entries from '{}'",
+            checkpoint.corpus.len(),
+            dir.display()
+        );
+        self.distances = checkpoint
+            .distances
+            .iter()
+            .map(|distance| distance.unwrap_or(f64::INFINITY))
+            .collect();
+        if self.rarity.is_some() {
+            // checkpoint made with another scheduler has no coverage of entries
+            self.rarity = Some(
+                checkpoint
+                    .rarity
+                    .unwrap_or_else(|| CoverageRarity::new(checkpoint.corpus.len())),
+            );
+        }
+        self.fst_kcov_feedback
+            .restore_coverage(checkpoint.fst_coverage);
+        self.snd_kcov_feedback
+            .restore_coverage(checkpoint.snd_coverage);
+        self.corpus = checkpoint.corpus;
+        self.next_seed = checkpoint.next_seed;
+        self.current_seed = 0;
+        self.energy_left = 0;
+        self.trimmed = 0;
+        Ok(self)
+    }
+
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            corpus: self.corpus.clone(),
+            next_seed: self.next_seed,
+            distances: self
+                .distances
+                .iter()
+                .map(|distance| distance.is_finite().then_some(*distance))
+                .collect(),
+            rarity: self.rarity.clone(),
+            fst_coverage: self.fst_kcov_feedback.all_coverage().clone(),
+            snd_coverage: self.snd_kcov_feedback.all_coverage().clone(),
         }
     }
 
     fn pick_input(&mut self) -> Workload {
-        if self.next_seed >= self.corpus.len() {
-            self.next_seed = 0
+        if self.energy_left == 0 {
+            if self.next_seed >= self.corpus.len() {
+                self.next_seed = 0;
+                self.import_shared();
+            }
+            self.current_seed = self.next_seed;
+            self.next_seed += 1;
+            self.energy_left = match (&self.target, &self.rarity) {
+                (Some(_), _) => energy(self.distances[self.current_seed], &self.distances),
+                (None, Some(rarity)) => rarity.energy(self.current_seed),
+                (None, None) => 1,
+            };
+        }
+        self.energy_left -= 1;
+        self.corpus.get(self.current_seed).unwrap().clone()
+    }
+
+    /// Picks up inputs found by other instances for this shard (or whole corpus if synced).
+    fn import_shared(&mut self) {
+        let mut inputs = vec![];
+        if let Some(shard) = self.shard.as_mut() {
+            inputs.extend(shard.import().unwrap_or_else(|err| {
+                warn!("failed to import shared inputs: {:?}", err);
+                vec![]
+            }));
+        }
+        if let Some(sync) = self.sync.as_mut() {
+            inputs.extend(sync.import().unwrap_or_else(|err| {
+                warn!("failed to import synced inputs: {:?}", err);
+                vec![]
+            }));
+        }
+        self.distances.extend(inputs.iter().map(|_| f64::INFINITY));
+        if let Some(rarity) = self.rarity.as_mut() {
+            for _ in inputs.iter() {
+                rarity.add(HashSet::new());
+            }
         }
-        let workload = self.corpus.get(self.next_seed).unwrap().clone();
-        self.next_seed += 1;
-        workload
+        self.corpus.extend(inputs);
     }
 
     fn add_to_corpus(&mut self, input: Workload) {
         debug!("adding new input to corpus");
+        let distance = match &self.target {
+            Some(target) => f64::min(
+                target.distance(self.fst_kcov_feedback.last_coverage()),
+                target.distance(self.snd_kcov_feedback.last_coverage()),
+            ),
+            None => f64::INFINITY,
+        };
+        self.distances.push(distance);
+        if let Some(rarity) = self.rarity.as_mut() {
+            let coverage = self
+                .fst_kcov_feedback
+                .last_coverage()
+                .union(self.snd_kcov_feedback.last_coverage())
+                .copied()
+                .collect();
+            rarity.add(coverage);
+        }
         self.corpus.push(input);
     }
 
+    /// Adds interesting input to corpus (of shard owning it) and shares it with other instances.
+    fn keep_input(&mut self, input: Workload, input_path: &Path) -> anyhow::Result<()> {
+        if let Some(shard) = self.shard.as_ref().filter(|shard| !shard.owns(&input)) {
+            debug!("input belongs to another shard");
+            shard
+                .export(&input)
+                .with_context(|| format!("failed to export input"))?;
+            return Ok(());
+        }
+        if let Some(sync) = self.sync.as_mut() {
+            sync.export(&input)
+                .with_context(|| format!("failed to sync input"))?;
+        }
+        self.add_to_corpus(input.clone());
+        self.show_stats();
+        if self.corpus_path.is_some() {
+            self.save_input(input, input_path)
+                .with_context(|| format!("failed to save input"))?;
+            self.save_coverage()
+                .with_context(|| format!("failed to save coverage"))?;
+        }
+        Ok(())
+    }
+
+    /// Trims corpus entries added since the last time, see [`trim`].
+    ///
+    /// Coverage of entry (on both filesystems) is taken from its own run, so it must be kept whole.
+    fn trim_corpus(&mut self) -> anyhow::Result<()> {
+        let mut removed_ops = 0;
+        for idx in self.trimmed..self.corpus.len() {
+            let input = self.corpus[idx].clone();
+            if input.ops.is_empty() {
+                continue;
+            }
+            let Some(required) = self.run_for_coverage(&input)? else {
+                continue;
+            };
+            let trimmed = trim(&input, &required, |candidate| {
+                self.run_for_coverage(candidate)
+            })
+            .with_context(|| format!("failed to trim corpus entry {}", idx))?;
+            removed_ops += input.ops.len() - trimmed.ops.len();
+            self.corpus[idx] = trimmed;
+        }
+        if self.trimmed < self.corpus.len() {
+            info!(
+                "trimmed {} corpus entries, {} operations removed",
+                self.corpus.len() - self.trimmed,
+                removed_ops
+            );
+        }
+        self.trimmed = self.corpus.len();
+        Ok(())
+    }
+
+    /// Coverage of input on both filesystems, objectives are not checked.
+    fn run_for_coverage(&mut self, input: &Workload) -> anyhow::Result<Option<HashSet<u64>>> {
+        let input_path = self.runner.compile_test(input)?;
+        if let ObserverVerdict::Inconclusive(_) = self.runner.run_harness(&input_path)? {
+            return Ok(None);
+        }
+        let mut coverage = load_coverage(&self.runner.fst_exec_dir.join(KCOV_FILENAME))?;
+        coverage.extend(load_coverage(
+            &self.runner.snd_exec_dir.join(KCOV_FILENAME),
+        )?);
+        Ok(Some(coverage))
+    }
+
+    fn save_coverage(&self) -> anyhow::Result<()> {
+        let corpus_path = self.corpus_path.clone().unwrap();
+        self.fst_kcov_feedback
+            .save_coverage(&corpus_path.join(format!(
+                "{}.{}",
+                side_name("fst", &self.runner.fst_fs_name),
+                COVERAGE_FILENAME
+            )))?;
+        self.snd_kcov_feedback
+            .save_coverage(&corpus_path.join(format!(
+                "{}.{}",
+                side_name("snd", &self.runner.snd_fs_name),
+                COVERAGE_FILENAME
+            )))?;
+        Ok(())
+    }
+
     fn save_input(&mut self, input: Workload, input_path: &Path) -> anyhow::Result<()> {
         let name = input.generate_name();
         debug!("save corpus input '{}'", name);
@@ -114,7 +396,7 @@ impl GreyBoxFuzzer {
         save_output(
             &corpus_dir,
             &self.runner.fst_trace_path,
-            &self.runner.fst_fs_name,
+            &side_name("fst", &self.runner.fst_fs_name),
             self.runner.fst_stdout.borrow().clone(),
             self.runner.fst_stderr.borrow().clone(),
         )
@@ -122,37 +404,50 @@ impl GreyBoxFuzzer {
         save_output(
             &corpus_dir,
             &self.runner.snd_trace_path,
-            &self.runner.snd_fs_name,
+            &side_name("snd", &self.runner.snd_fs_name),
             self.runner.snd_stdout.borrow().clone(),
             self.runner.snd_stderr.borrow().clone(),
         )
-        .with_context(|| format!("failed to save output for first harness"))?;
+        .with_context(|| format!("failed to save output for second harness"))?;
         Ok(())
     }
+
+    /// Lines covered so far by each filesystem with line coverage (`-` for one without),
+    /// empty if neither has it.
+    fn lcov_summary(&self) -> String {
+        let lines = |feedback: &Option<LCovFeedback>| match feedback {
+            Some(feedback) => feedback.covered_lines().to_string(),
+            None => "-".to_owned(),
+        };
+        match (&self.fst_lcov_feedback, &self.snd_lcov_feedback) {
+            (None, None) => String::new(),
+            (fst, snd) => format!(", line coverage: {}/{}", lines(fst), lines(snd)),
+        }
+    }
 }
 
 impl Fuzzer for GreyBoxFuzzer {
     fn fuzz_one(&mut self) -> anyhow::Result<()> {
+        let executions = self.runner.stats.executions;
+        if self
+            .trim_interval
+            .is_some_and(|interval| executions > 0 && executions.is_multiple_of(interval.max(1)))
+        {
+            self.trim_corpus()
+                .with_context(|| format!("failed to trim corpus"))?;
+        }
+
         debug!("picking input");
         let input = self.pick_input();
 
         debug!("mutating input");
-        let input = self.mutator.mutate(input);
+        let input = self.mutator.mutate(input, &self.corpus);
+        let input = self.runner().repair(input);
+        self.runner().update_model_stats(&input);
 
         let input_path = self.runner().compile_test(&input)?;
 
-        self.runner().run_harness(&input_path)?;
-
-        let fst_trace = parse_trace(&self.runner().fst_trace_path)
-            .with_context(|| format!("failed to parse first trace"))?;
-        let snd_trace = parse_trace(&self.runner().snd_trace_path)
-            .with_context(|| format!("failed to parse second trace"))?;
-
-        if self.detect_errors(&input, &input_path, &fst_trace, &snd_trace)? {
-            return Ok(());
-        }
-
-        if self.do_objective(&input, &input_path, &fst_trace, &snd_trace)? {
+        if self.run_objectives(&input, &input_path)? {
             return Ok(());
         }
 
@@ -171,14 +466,30 @@ impl Fuzzer for GreyBoxFuzzer {
                     self.runner.snd_fs_name
                 )
             })?;
-        if fst_kcov_is_interesting || snd_kcov_is_interesting {
-            self.add_to_corpus(input.clone());
-            self.show_stats();
-            if self.corpus_path.is_some() {
-                self.save_input(input, &input_path)
-                    .with_context(|| format!("failed to save input"))?;
-            }
-            return Ok(());
+        let fst_lcov_is_interesting = match self.fst_lcov_feedback.as_mut() {
+            Some(feedback) => feedback.is_interesting().with_context(|| {
+                format!(
+                    "failed to get first lcov feedback for '{}'",
+                    self.runner.fst_fs_name
+                )
+            })?,
+            None => false,
+        };
+        let snd_lcov_is_interesting = match self.snd_lcov_feedback.as_mut() {
+            Some(feedback) => feedback.is_interesting().with_context(|| {
+                format!(
+                    "failed to get second lcov feedback for '{}'",
+                    self.runner.snd_fs_name
+                )
+            })?,
+            None => false,
+        };
+        if fst_kcov_is_interesting
+            || snd_kcov_is_interesting
+            || fst_lcov_is_interesting
+            || snd_lcov_is_interesting
+        {
+            return self.keep_input(input, &input_path);
         }
 
         Ok(())
@@ -189,18 +500,168 @@ impl Fuzzer for GreyBoxFuzzer {
         let since_start = Instant::now().duration_since(self.runner.stats.start);
         let secs = since_start.as_secs();
         info!(
-            "corpus: {}, crashes: {}, executions: {}, exec/s: {:.2}, time: {:02}h:{:02}m:{:02}s",
+            "corpus: {}, coverage: {}/{}{}, crashes: {} ({} unique), executions: {}, model nodes (peak): {}, repaired ops: {}, exec/s: {:.2}, time: {:02}h:{:02}m:{:02}s{}",
             self.corpus.len(),
+            self.fst_kcov_feedback.total_coverage(),
+            self.snd_kcov_feedback.total_coverage(),
+            self.lcov_summary(),
             self.runner.stats.crashes,
+            self.runner.stats.unique_crashes,
             self.runner.stats.executions,
-            (self.runner.stats.executions as f64) / (secs as f64),
+            self.runner.stats.peak_model_nodes,
+            self.runner.stats.repaired_ops,
+            self.runner.stats.rate.per_sec(),
             secs / (60 * 60),
             (secs / (60)) % 60,
             secs % 60,
+            self.runner.stats.progress_summary(),
         );
     }
 
+    fn reduces_crashes(&mut self) -> bool {
+        self.runner.config.greybox.reduce_on_crash
+    }
+
+    fn save_checkpoint(&mut self) -> anyhow::Result<()> {
+        match &self.checkpoint_dir {
+            Some(dir) => self.checkpoint().save(dir),
+            None => Ok(()),
+        }
+    }
+
+    fn corpus(&self) -> Vec<Workload> {
+        self.corpus.clone()
+    }
+
     fn runner(&mut self) -> &mut Runner {
         &mut self.runner
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::abstract_fs::operation::Operation;
+    use crate::fuzzing::greybox::feedback::lcov::LCovConfig;
+    use crate::mount::ext4::Ext4;
+
+    use super::*;
+
+    static EXT4: Ext4 = Ext4::new();
+
+    #[test]
+    fn test_same_seed_same_mutations() {
+        let mutations = |seed| {
+            let mut config: Config = toml::from_str(include_str!("../../../config.toml")).unwrap();
+            config.rng_seed = Some(seed);
+            let mut fuzzer = GreyBoxFuzzer::new(config, &EXT4, &EXT4).unwrap();
+            (0..10)
+                .map(|_| {
+                    let input = fuzzer.pick_input();
+                    fuzzer.mutator.mutate(input, &fuzzer.corpus)
+                })
+                .collect::<Vec<Workload>>()
+        };
+        assert_eq!(mutations(42), mutations(42));
+        assert_ne!(mutations(42), mutations(43));
+    }
+
+    #[test]
+    fn test_lcov_feedback_from_config() {
+        let mut config: Config = toml::from_str(include_str!("../../../config.toml")).unwrap();
+        assert!(config.greybox.lcov.is_none());
+        config.greybox.lcov = Some(LCovConfig {
+            fst: Some("./fst.coverage.info".to_owned()),
+            snd: None,
+        });
+        let fuzzer = GreyBoxFuzzer::new(config, &EXT4, &EXT4).unwrap();
+        assert!(fuzzer.fst_lcov_feedback.is_some());
+        assert!(fuzzer.snd_lcov_feedback.is_none());
+        assert_eq!(", line coverage: 0/-", fuzzer.lcov_summary());
+    }
+
+    #[test]
+    fn test_missing_seeds_dir() {
+        let mut config: Config = toml::from_str(include_str!("../../../config.toml")).unwrap();
+        config.greybox.seeds_dir = Some("./DIFFuzzer-missing-seeds".to_owned());
+        assert!(GreyBoxFuzzer::new(config, &EXT4, &EXT4).is_err());
+    }
+
+    #[test]
+    fn test_synced_instances_share_corpus() {
+        let dir = std::env::temp_dir().join("DIFFuzzer-greybox-sync-test");
+        fs::remove_dir_all(&dir).unwrap_or(());
+        let config = |seed| {
+            let mut config: Config = toml::from_str(include_str!("../../../config.toml")).unwrap();
+            config.rng_seed = Some(seed);
+            config.greybox.sync_dir = Some(dir.to_string_lossy().into_owned());
+            config
+        };
+        let mut fst = GreyBoxFuzzer::new(config(1), &EXT4, &EXT4).unwrap();
+        let mut snd = GreyBoxFuzzer::new(config(2), &EXT4, &EXT4).unwrap();
+        let mkdir = |path: &str| Workload {
+            ops: vec![Operation::MKDIR {
+                path: path.into(),
+                mode: vec![],
+            }],
+            seed: 0,
+        };
+        fst.keep_input(mkdir("/fst"), Path::new("")).unwrap();
+        snd.keep_input(mkdir("/snd"), Path::new("")).unwrap();
+
+        // inputs of other instance are imported once corpus is cycled through
+        for fuzzer in [&mut fst, &mut snd] {
+            for _ in 0..3 {
+                fuzzer.pick_input();
+            }
+        }
+        assert_eq!(
+            vec![Workload::new(), mkdir("/fst"), mkdir("/snd")],
+            fst.corpus
+        );
+        assert_eq!(
+            vec![Workload::new(), mkdir("/snd"), mkdir("/fst")],
+            snd.corpus
+        );
+        assert_eq!(fst.corpus.len(), fst.distances.len());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_resume_from_checkpoint() {
+        let dir = std::env::temp_dir().join("DIFFuzzer-resume-test");
+        fs::remove_dir_all(&dir).unwrap_or(());
+        let config = || {
+            let mut config: Config = toml::from_str(include_str!("../../../config.toml")).unwrap();
+            config.greybox.scheduler = Scheduler::Rarity;
+            config.greybox.checkpoint_dir = Some(dir.to_string_lossy().into_owned());
+            config
+        };
+        let mut fuzzer = GreyBoxFuzzer::new(config(), &EXT4, &EXT4).unwrap();
+        fuzzer
+            .fst_kcov_feedback
+            .restore_coverage(HashSet::from([0x10]));
+        fuzzer
+            .snd_kcov_feedback
+            .restore_coverage(HashSet::from([0x20]));
+        for _ in 0..3 {
+            let input = fuzzer.pick_input();
+            let input = fuzzer.mutator.mutate(input, &fuzzer.corpus);
+            fuzzer.add_to_corpus(input);
+        }
+        fuzzer.pick_input();
+        fuzzer.save_checkpoint().unwrap();
+
+        let resumed = GreyBoxFuzzer::new(config(), &EXT4, &EXT4)
+            .unwrap()
+            .resume(&dir)
+            .unwrap();
+        assert_eq!(fuzzer.corpus, resumed.corpus);
+        assert_eq!(fuzzer.next_seed, resumed.next_seed);
+        assert_eq!(fuzzer.checkpoint(), resumed.checkpoint());
+        assert_eq!(
+            &HashSet::from([0x10]),
+            resumed.fst_kcov_feedback.all_coverage()
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
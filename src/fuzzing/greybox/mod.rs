@@ -1,3 +1,12 @@
+pub mod archive;
+pub mod checkpoint;
+pub mod corpus_diff;
+pub mod directed;
 pub mod feedback;
 pub mod fuzzer;
 pub mod mutator;
+pub mod schedule;
+pub mod seeds;
+pub mod shard;
+pub mod sync;
+pub mod trim;
@@ -1,14 +1,14 @@
 use rand::{rngs::StdRng, seq::SliceRandom, Rng};
 
 use crate::abstract_fs::{
-    mutator::{insert, remove, MutationKind, MutationWeights},
-    operation::OperationWeights,
+    generator::GeneratorConfig,
+    mutator::{insert, remove, reorder, splice, MutationKind, MutationWeights},
     workload::Workload,
 };
 
 pub struct Mutator {
     rng: StdRng,
-    operation_weights: OperationWeights,
+    generator: GeneratorConfig,
     mutation_weights: MutationWeights,
     max_length: u16,
     max_mutations: u16,
@@ -17,14 +17,14 @@ pub struct Mutator {
 impl Mutator {
     pub fn new(
         rng: StdRng,
-        operation_weights: OperationWeights,
+        generator: GeneratorConfig,
         mutation_weights: MutationWeights,
         max_length: u16,
         max_mutations: u16,
     ) -> Self {
         Self {
             rng,
-            operation_weights,
+            generator,
             mutation_weights,
             max_length,
             max_mutations,
@@ -33,29 +33,40 @@ impl Mutator {
 }
 
 impl Mutator {
-    pub fn mutate(&mut self, input: Workload) -> Workload {
+    /// Corpus is only used as a source of other inputs for splicing.
+    pub fn mutate(&mut self, input: Workload, corpus: &[Workload]) -> Workload {
         let mut input = input;
         let mut count = 0;
         let n = self.rng.gen_range(1..=self.max_mutations);
         while count < n {
-            if self.mutate_once(&mut input) {
+            if self.mutate_once(&mut input, corpus) {
                 count += 1;
             }
         }
         input
     }
-    fn mutate_once(&mut self, input: &mut Workload) -> bool {
+    fn mutate_once(&mut self, input: &mut Workload, corpus: &[Workload]) -> bool {
         let mut mutations = self.mutation_weights.clone();
         if input.ops.is_empty() {
             mutations
                 .weights
                 .retain(|(op, _)| *op != MutationKind::REMOVE);
         }
+        if input.ops.len() < 2 {
+            mutations
+                .weights
+                .retain(|(op, _)| *op != MutationKind::REORDER);
+        }
         if input.ops.len() >= self.max_length.into() {
             mutations
                 .weights
                 .retain(|(op, _)| *op != MutationKind::INSERT);
         }
+        if corpus.len() < 2 {
+            mutations
+                .weights
+                .retain(|(op, _)| *op != MutationKind::SPLICE);
+        }
         match mutations
             .weights
             .choose_weighted(&mut self.rng, |item| item.1)
@@ -64,9 +75,7 @@ impl Mutator {
         {
             MutationKind::INSERT => {
                 let index = self.rng.gen_range(0..=input.ops.len());
-                if let Some(workload) =
-                    insert(&mut self.rng, &input, index, &self.operation_weights)
-                {
+                if let Some(workload) = insert(&mut self.rng, &input, index, &self.generator) {
                     *input = workload;
                     true
                 } else {
@@ -82,6 +91,30 @@ impl Mutator {
                     false
                 }
             }
+            MutationKind::REORDER => {
+                let index = self.rng.gen_range(0..input.ops.len() - 1);
+                if let Some(workload) = reorder(&input, index) {
+                    *input = workload;
+                    true
+                } else {
+                    false
+                }
+            }
+            MutationKind::SPLICE => {
+                let other = corpus.choose(&mut self.rng).unwrap();
+                if other.ops.is_empty() || other == input {
+                    return false;
+                }
+                let fst_len = self.rng.gen_range(0..=input.ops.len());
+                let snd_start = self.rng.gen_range(0..other.ops.len());
+                match splice(&input, fst_len, other, snd_start) {
+                    Some(workload) if workload.ops.len() <= self.max_length.into() => {
+                        *input = workload;
+                        true
+                    }
+                    _ => false,
+                }
+            }
         }
     }
 }
@@ -0,0 +1,97 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use super::directed::energy;
+
+/// How many times in a row corpus entry is picked, ignored when fuzzing is directed at targets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scheduler {
+    /// Every entry is picked once per cycle.
+    Queue,
+    /// Entries that hit PCs covered by few other entries are picked more often (like AFL rare branches).
+    Rarity,
+}
+
+/// Number of corpus entries hitting each PC, along with PCs hit by each entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoverageRarity {
+    hits: HashMap<u64, usize>,
+    entries: Vec<Vec<u64>>,
+}
+
+impl CoverageRarity {
+    /// Existing entries (e.g. seeds) have no coverage and always get the least energy.
+    pub fn new(entries: usize) -> Self {
+        Self {
+            hits: HashMap::new(),
+            entries: vec![vec![]; entries],
+        }
+    }
+
+    pub fn add(&mut self, coverage: HashSet<u64>) {
+        for pc in coverage.iter() {
+            *self.hits.entry(*pc).or_default() += 1;
+        }
+        self.entries.push(coverage.into_iter().collect());
+    }
+
+    /// How many entries hit the rarest PC of entry, `None` if it has no coverage.
+    pub fn rarest_hits(&self, entry: usize) -> Option<usize> {
+        self.entries[entry].iter().map(|pc| self.hits[pc]).min()
+    }
+
+    /// Entries hitting the rarest PCs (among whole corpus) get [`super::directed::MAX_ENERGY`].
+    pub fn energy(&self, entry: usize) -> usize {
+        // log scale, so that a few very common PCs do not squash the rest
+        let distance = |entry| {
+            self.rarest_hits(entry)
+                .map_or(f64::INFINITY, |hits| (hits as f64).log2())
+        };
+        let distances: Vec<f64> = (0..self.entries.len()).map(distance).collect();
+        energy(distances[entry], &distances)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fuzzing::greybox::directed::MAX_ENERGY;
+
+    use super::*;
+
+    #[test]
+    fn test_rarest_edge_gets_most_energy() {
+        let mut rarity = CoverageRarity::new(1);
+        for coverage in [
+            HashSet::from([1, 2]),
+            HashSet::from([1, 2, 3]),
+            HashSet::from([1, 4]),
+            HashSet::from([1, 2, 3]),
+        ] {
+            rarity.add(coverage);
+        }
+        let hits: Vec<Option<usize>> = (0..5).map(|i| rarity.rarest_hits(i)).collect();
+        assert_eq!(vec![None, Some(3), Some(2), Some(1), Some(2)], hits);
+        let energies: Vec<usize> = (0..5).map(|i| rarity.energy(i)).collect();
+        assert_eq!(MAX_ENERGY, energies[3]);
+        assert!(energies[3] > energies[2]);
+        assert_eq!(energies[2], energies[4]);
+        assert!(energies[2] > energies[1]);
+        assert_eq!(1, energies[1]);
+        assert_eq!(1, energies[0]);
+    }
+
+    #[test]
+    fn test_rarity_changes_with_corpus() {
+        let mut rarity = CoverageRarity::new(0);
+        rarity.add(HashSet::from([1, 2]));
+        rarity.add(HashSet::from([1]));
+        assert_eq!(MAX_ENERGY, rarity.energy(0));
+        assert_eq!(1, rarity.energy(1));
+        rarity.add(HashSet::from([2, 3]));
+        assert_eq!(Some(2), rarity.rarest_hits(0));
+        assert_eq!(MAX_ENERGY, rarity.energy(2));
+        assert!(rarity.energy(0) < MAX_ENERGY);
+    }
+}
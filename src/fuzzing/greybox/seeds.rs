@@ -0,0 +1,131 @@
+use std::{fs, path::Path};
+
+use anyhow::Context;
+use log::{debug, info, warn};
+
+use crate::{abstract_fs::workload::Workload, save::FSTEST_FILENAME};
+
+/// Loads seed workloads from directory (recursively).
+///
+/// Workloads saved as `.json` are read as is, `.c` reproducers are decoded from source
+/// unless `.json` with the same name is next to them (as in saved corpus and crashes).
+/// Shell scripts are decoded too, unless `.json` or `.c` reproducer with the same name is next to them
+/// (as exported with reproducer); fstests of saved crashes are not.
+pub fn load_seeds(dir: &Path) -> anyhow::Result<Vec<Workload>> {
+    let mut seeds = vec![];
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("failed to read seeds directory '{}'", dir.display()))?
+        .collect::<Result<_, _>>()
+        .with_context(|| format!("failed to read seeds directory '{}'", dir.display()))?;
+    entries.sort_by_key(|entry| entry.path());
+    for entry in entries {
+        let path = entry.path();
+        if path.is_dir() {
+            seeds.append(&mut load_seeds(&path)?);
+            continue;
+        }
+        let read = || {
+            fs::read_to_string(&path)
+                .with_context(|| format!("failed to read seed '{}'", path.display()))
+        };
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => match serde_json::from_str(&read()?) {
+                Ok(workload) => seeds.push(workload),
+                Err(err) => warn!("skipping seed '{}': {}", path.display(), err),
+            },
+            Some("c") => {
+                if path.with_extension("json").exists() {
+                    debug!("skipping seed '{}', json exists", path.display());
+                    continue;
+                }
+                seeds.push(Workload::decode_c(&read()?));
+            }
+            Some("sh") => {
+                if path.ends_with(FSTEST_FILENAME) {
+                    debug!("skipping fstest '{}'", path.display());
+                    continue;
+                }
+                if path.with_extension("json").exists() || path.with_extension("c").exists() {
+                    debug!(
+                        "skipping seed '{}', json or reproducer exists",
+                        path.display()
+                    );
+                    continue;
+                }
+                seeds.push(Workload::decode_shell(&read()?));
+            }
+            _ => debug!("skipping '{}'", path.display()),
+        }
+    }
+    info!("loaded {} seeds from '{}'", seeds.len(), dir.display());
+    Ok(seeds)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use crate::abstract_fs::{flags::ModeFlag, node::FileDescriptorIndex, operation::Operation};
+
+    use super::*;
+
+    #[test]
+    fn test_load_seeds() {
+        let dir = env::temp_dir().join("DIFFuzzer-seeds-test");
+        fs::remove_dir_all(&dir).unwrap_or(());
+        fs::create_dir_all(dir.join("saved")).unwrap();
+
+        let fst = Workload {
+            ops: vec![
+                Operation::MKDIR {
+                    path: "/foo".into(),
+                    mode: vec![ModeFlag::S_IRWXU],
+                },
+                Operation::OPEN {
+                    path: "/foo".into(),
+                    flags: vec![],
+                    des: FileDescriptorIndex(0),
+                },
+            ],
+            seed: 0,
+        };
+        let snd = Workload {
+            ops: vec![Operation::REMOVE {
+                path: "/bar".into(),
+            }],
+            seed: 0,
+        };
+        let trd = Workload {
+            ops: vec![Operation::MKDIR {
+                path: "/baz".into(),
+                mode: vec![ModeFlag::S_IRUSR],
+            }],
+            seed: 42,
+        };
+        fs::write(dir.join("a.c"), fst.encode_c(None)).unwrap();
+        fs::write(
+            dir.join("saved").join("test.json"),
+            serde_json::to_string(&snd).unwrap(),
+        )
+        .unwrap();
+        fs::write(dir.join("saved").join("test.c"), fst.encode_c(None)).unwrap();
+        fs::write(
+            dir.join("saved").join("test.sh"),
+            snd.encode_shell().unwrap(),
+        )
+        .unwrap();
+        fs::write(
+            dir.join("saved").join(FSTEST_FILENAME),
+            snd.encode_fstest(""),
+        )
+        .unwrap();
+        fs::write(dir.join("a.sh"), fst.encode_shell().unwrap()).unwrap();
+        fs::write(dir.join("b.sh"), trd.encode_shell().unwrap()).unwrap();
+        fs::write(dir.join("c.json"), "{}").unwrap();
+        fs::write(dir.join("d.txt"), "").unwrap();
+
+        assert_eq!(vec![fst, trd, snd], load_seeds(&dir).unwrap());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
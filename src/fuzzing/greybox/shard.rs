@@ -0,0 +1,212 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use anyhow::{bail, Context};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use siphasher::sip128::SipHasher13;
+
+use crate::abstract_fs::workload::Workload;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ShardConfig {
+    /// Index of this instance, from `0` to `count - 1`.
+    pub id: usize,
+    pub count: usize,
+    /// Directory shared by all instances, inputs found for other shards are passed through it.
+    pub shared_dir: Option<String>,
+}
+
+/// Shard that owns workload, the same on every host (hash is not randomized).
+pub fn shard_of(workload: &Workload, count: usize) -> usize {
+    let bytes = bincode::serialize(workload).unwrap();
+    let hash = SipHasher13::new().hash(&bytes).as_u128();
+    (hash % count as u128) as usize
+}
+
+/// Part of corpus mutated and saved by this instance.
+pub struct Shard {
+    id: usize,
+    count: usize,
+    shared_dir: Option<Box<Path>>,
+    imported: HashSet<String>,
+}
+
+impl Shard {
+    pub fn new(config: &ShardConfig) -> anyhow::Result<Self> {
+        if config.id >= config.count {
+            bail!(
+                "shard id must be less than shard count ({} >= {})",
+                config.id,
+                config.count
+            );
+        }
+        let shared_dir = match &config.shared_dir {
+            Some(dir) => {
+                let dir = Path::new(dir);
+                fs::create_dir_all(dir).with_context(|| {
+                    format!("failed to create shared directory at '{}'", dir.display())
+                })?;
+                Some(dir.to_path_buf().into_boxed_path())
+            }
+            None => None,
+        };
+        info!("fuzzing shard {} of {}", config.id, config.count);
+        Ok(Self {
+            id: config.id,
+            count: config.count,
+            shared_dir,
+            imported: HashSet::new(),
+        })
+    }
+
+    pub fn owns(&self, workload: &Workload) -> bool {
+        shard_of(workload, self.count) == self.id
+    }
+
+    /// Passes input to its owner through shared directory (if any).
+    pub fn export(&self, workload: &Workload) -> anyhow::Result<()> {
+        let Some(dir) = &self.shared_dir else {
+            return Ok(());
+        };
+        let path = dir.join(workload.generate_name()).with_extension("json");
+        if path.exists() {
+            return Ok(());
+        }
+        debug!("export input to '{}'", path.display());
+        let json = serde_json::to_string(workload)
+            .with_context(|| format!("failed to serialize input"))?;
+        // written under temporary name, so other instances never read partial file
+        let tmp_path = path.with_extension(format!("{}.tmp", self.id));
+        fs::write(&tmp_path, json)
+            .with_context(|| format!("failed to write input at '{}'", tmp_path.display()))?;
+        fs::rename(&tmp_path, &path)
+            .with_context(|| format!("failed to move input to '{}'", path.display()))?;
+        Ok(())
+    }
+
+    /// Reads inputs exported by other instances for this shard, each input is returned once.
+    pub fn import(&mut self) -> anyhow::Result<Vec<Workload>> {
+        let Some(dir) = &self.shared_dir else {
+            return Ok(vec![]);
+        };
+        let mut imported = vec![];
+        let mut paths: Vec<_> = fs::read_dir(dir)
+            .with_context(|| format!("failed to read shared directory '{}'", dir.display()))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            let name = path.file_stem().unwrap().to_string_lossy().to_string();
+            if self.imported.contains(&name) {
+                continue;
+            }
+            let json = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read input at '{}'", path.display()))?;
+            match serde_json::from_str::<Workload>(&json) {
+                Ok(workload) if self.owns(&workload) => {
+                    self.imported.insert(name);
+                    imported.push(workload);
+                }
+                Ok(_) => {
+                    self.imported.insert(name);
+                }
+                Err(err) => warn!("skipping shared input '{}': {}", path.display(), err),
+            }
+        }
+        if !imported.is_empty() {
+            debug!("imported {} inputs from shared directory", imported.len());
+        }
+        Ok(imported)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::abstract_fs::generator::{generate_new, GeneratorConfig};
+
+    use super::*;
+
+    fn seeds() -> Vec<Workload> {
+        (0..200)
+            .map(|i| {
+                let mut rng = StdRng::seed_from_u64(i);
+                generate_new(
+                    &mut rng,
+                    10,
+                    &GeneratorConfig {
+                        root_bias: 0.2,
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_shards_partition_seeds() {
+        let seeds = seeds();
+        let count = 4;
+        let shards: Vec<Shard> = (0..count)
+            .map(|id| {
+                Shard::new(&ShardConfig {
+                    id,
+                    count,
+                    shared_dir: None,
+                })
+                .unwrap()
+            })
+            .collect();
+        for seed in seeds.iter() {
+            let owners = shards.iter().filter(|shard| shard.owns(seed)).count();
+            assert_eq!(1, owners);
+            assert_eq!(shard_of(seed, count), shard_of(&seed.clone(), count));
+        }
+        for shard in shards.iter() {
+            assert!(seeds.iter().any(|seed| shard.owns(seed)));
+        }
+    }
+
+    #[test]
+    fn test_invalid_shard() {
+        assert!(Shard::new(&ShardConfig {
+            id: 2,
+            count: 2,
+            shared_dir: None,
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_shared_dir() {
+        let dir = env::temp_dir().join("DIFFuzzer-shard-test");
+        fs::remove_dir_all(&dir).unwrap_or(());
+        let config = |id| ShardConfig {
+            id,
+            count: 2,
+            shared_dir: Some(dir.to_str().unwrap().to_owned()),
+        };
+        let mut fst = Shard::new(&config(0)).unwrap();
+        let mut snd = Shard::new(&config(1)).unwrap();
+        let seeds = seeds();
+        for seed in seeds.iter() {
+            if fst.owns(seed) {
+                snd.export(seed).unwrap();
+            } else {
+                fst.export(seed).unwrap();
+            }
+        }
+        let fst_imported = fst.import().unwrap();
+        let snd_imported = snd.import().unwrap();
+        let unique: HashSet<String> = seeds.iter().map(|seed| seed.generate_name()).collect();
+        assert_eq!(unique.len(), fst_imported.len() + snd_imported.len());
+        assert!(fst_imported.iter().all(|seed| fst.owns(seed)));
+        assert!(snd_imported.iter().all(|seed| snd.owns(seed)));
+        assert!(fst.import().unwrap().is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
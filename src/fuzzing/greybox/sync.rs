@@ -0,0 +1,138 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use anyhow::Context;
+use log::{debug, info, warn};
+
+use crate::abstract_fs::workload::Workload;
+
+/// Corpus replicated between instances through shared directory.
+///
+/// Unlike [`super::shard::Shard`] every instance keeps all inputs, so coverage found by one
+/// instance is not rediscovered by others. Instances may run on the same host,
+/// each of them takes its own slot (see [`crate::temp_dir::Instance`]).
+pub struct CorpusSync {
+    dir: Box<Path>,
+    /// Names of inputs already exported or imported by this instance.
+    seen: HashSet<String>,
+}
+
+impl CorpusSync {
+    pub fn new(dir: &Path) -> anyhow::Result<Self> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create sync directory at '{}'", dir.display()))?;
+        info!("syncing corpus through '{}'", dir.display());
+        Ok(Self {
+            dir: dir.to_path_buf().into_boxed_path(),
+            seen: HashSet::new(),
+        })
+    }
+
+    /// Publishes interesting input to other instances.
+    pub fn export(&mut self, workload: &Workload) -> anyhow::Result<()> {
+        let name = workload.generate_name();
+        self.seen.insert(name.clone());
+        let path = self.dir.join(name).with_extension("json");
+        if path.exists() {
+            return Ok(());
+        }
+        debug!("export input to '{}'", path.display());
+        let json = serde_json::to_string(workload)
+            .with_context(|| format!("failed to serialize input"))?;
+        // written under temporary name, so other instances never read partial file
+        let tmp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&tmp_path, json)
+            .with_context(|| format!("failed to write input at '{}'", tmp_path.display()))?;
+        fs::rename(&tmp_path, &path)
+            .with_context(|| format!("failed to move input to '{}'", path.display()))?;
+        Ok(())
+    }
+
+    /// Reads inputs published by other instances, each input is returned once.
+    pub fn import(&mut self) -> anyhow::Result<Vec<Workload>> {
+        let mut imported = vec![];
+        let mut paths: Vec<_> = fs::read_dir(&self.dir)
+            .with_context(|| format!("failed to read sync directory '{}'", self.dir.display()))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            let name = path.file_stem().unwrap().to_string_lossy().to_string();
+            if self.seen.contains(&name) {
+                continue;
+            }
+            let json = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read input at '{}'", path.display()))?;
+            match serde_json::from_str::<Workload>(&json) {
+                Ok(workload) => {
+                    self.seen.insert(name);
+                    imported.push(workload);
+                }
+                Err(err) => warn!("skipping synced input '{}': {}", path.display(), err),
+            }
+        }
+        if !imported.is_empty() {
+            debug!("imported {} inputs from sync directory", imported.len());
+        }
+        Ok(imported)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::abstract_fs::generator::{generate_new, GeneratorConfig};
+
+    use super::*;
+
+    fn input(seed: u64) -> Workload {
+        let mut rng = StdRng::seed_from_u64(seed);
+        generate_new(
+            &mut rng,
+            10,
+            &GeneratorConfig {
+                root_bias: 0.2,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_workers_converge() {
+        let dir = env::temp_dir().join("DIFFuzzer-sync-test");
+        fs::remove_dir_all(&dir).unwrap_or(());
+        let mut fst = CorpusSync::new(&dir).unwrap();
+        let mut snd = CorpusSync::new(&dir).unwrap();
+        let mut fst_corpus: Vec<Workload> = (0..10).map(input).collect();
+        let mut snd_corpus: Vec<Workload> = (10..25).map(input).collect();
+        let names = |corpus: &[Workload]| -> HashSet<String> {
+            corpus.iter().map(|input| input.generate_name()).collect()
+        };
+        let union: HashSet<String> = names(&fst_corpus)
+            .union(&names(&snd_corpus))
+            .cloned()
+            .collect();
+
+        // workers find inputs in turns, syncing in between
+        for i in 0..20 {
+            if let Some(input) = fst_corpus.get(i).cloned() {
+                fst.export(&input).unwrap();
+            }
+            if let Some(input) = snd_corpus.get(i).cloned() {
+                snd.export(&input).unwrap();
+            }
+            fst_corpus.extend(fst.import().unwrap());
+            snd_corpus.extend(snd.import().unwrap());
+        }
+
+        assert_eq!(union, names(&fst_corpus));
+        assert_eq!(union, names(&snd_corpus));
+        assert_eq!(union.len(), fst_corpus.len());
+        assert_eq!(union.len(), snd_corpus.len());
+        assert!(fst.import().unwrap().is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::HashSet;
+
+use crate::abstract_fs::{mutator::remove, workload::Workload};
+
+/// Removes operations of input one by one (from the last one) as long as coverage is not lost.
+///
+/// `run` returns coverage of workload, `None` if run can not be trusted (removal is rejected then).
+/// Operations that can not be removed without others (e.g. open used by later write) are kept.
+pub fn trim(
+    input: &Workload,
+    required: &HashSet<u64>,
+    mut run: impl FnMut(&Workload) -> anyhow::Result<Option<HashSet<u64>>>,
+) -> anyhow::Result<Workload> {
+    let mut trimmed = input.clone();
+    // going from the end, indices of operations not tried yet stay the same after removal
+    for index in (0..input.ops.len()).rev() {
+        let Some(candidate) = remove(&trimmed, index) else {
+            continue;
+        };
+        if run(&candidate)?.is_some_and(|coverage| coverage.is_superset(required)) {
+            trimmed = candidate;
+        }
+    }
+    Ok(trimmed)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::abstract_fs::{fs::AbstractFS, operation::Operation};
+
+    use super::*;
+
+    /// Every kind of operation hits its own PC, repeated operations add nothing.
+    fn coverage(workload: &Workload) -> HashSet<u64> {
+        workload.ops.iter().map(|op| op.kind() as u64).collect()
+    }
+
+    #[test]
+    fn test_trailing_operations_removed() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.create("/foo/bar".into(), vec![]).unwrap();
+        let des = fs.open("/foo/bar".into(), vec![]).unwrap();
+        fs.write(des, 0, 1024).unwrap();
+        fs.write(des, 0, 1024).unwrap();
+        fs.mkdir("/baz".into(), vec![]).unwrap();
+        fs.create("/baz/boo".into(), vec![]).unwrap();
+        let input = fs.recording;
+        let required = coverage(&input);
+
+        let mut runs = 0;
+        let trimmed = trim(&input, &required, |workload| {
+            runs += 1;
+            Ok(Some(coverage(workload)))
+        })
+        .unwrap();
+        assert!(runs <= input.ops.len());
+        assert_eq!(required, coverage(&trimmed));
+        assert_eq!(
+            vec![
+                Operation::MKDIR {
+                    path: "/foo".into(),
+                    mode: vec![],
+                },
+                Operation::CREATE {
+                    path: "/foo/bar".into(),
+                    mode: vec![],
+                },
+                Operation::OPEN {
+                    path: "/foo/bar".into(),
+                    flags: vec![],
+                    des,
+                },
+                Operation::WRITE {
+                    des,
+                    src_offset: 0,
+                    size: 1024,
+                },
+            ],
+            trimmed.ops
+        );
+    }
+
+    #[test]
+    fn test_untrusted_runs_keep_input() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.mkdir("/bar".into(), vec![]).unwrap();
+        let input = fs.recording;
+        let trimmed = trim(&input, &coverage(&input), |_| Ok(None)).unwrap();
+        assert_eq!(input, trimmed);
+        assert!(trim(&input, &HashSet::new(), |_| anyhow::bail!("failed")).is_err());
+    }
+}
@@ -1,5 +1,12 @@
 pub mod blackbox;
 pub mod common;
+pub mod control;
+pub mod crash_dir;
 pub mod greybox;
 pub mod objective;
+pub mod observer;
+pub mod rate;
+pub mod reason;
 pub mod reducer;
+pub mod reporter;
+pub mod triage;
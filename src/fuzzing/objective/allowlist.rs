@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+
+use crate::hasher::hasher::Attribute;
+
+/// Differences expected between two filesystems (e.g. allocated size on XFS and Ext4), they are never reported.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KnownDifference {
+    /// Names of both filesystems in any order, case is ignored.
+    /// Name without suffix (e.g. `ext4`) also matches filesystem with options or data mode (e.g. `Ext4-journal`).
+    pub filesystems: [String; 2],
+    /// Attributes not compared by hash objective.
+    pub hash_attributes: Vec<Attribute>,
+    /// Keys of extra fields (e.g. `st_blocks`) not compared by trace, stat and readlink objectives.
+    pub trace_fields: Vec<String>,
+}
+
+/// Known differences of single pair of filesystems merged together.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Allowlist {
+    pub hash_attributes: Vec<Attribute>,
+    pub trace_fields: Vec<String>,
+}
+
+impl Allowlist {
+    pub fn for_pair(known: &[KnownDifference], fst_fs_name: &str, snd_fs_name: &str) -> Self {
+        let mut allowlist = Self::default();
+        for difference in known.iter().filter(|difference| {
+            let [fst, snd] = &difference.filesystems;
+            (same_fs(fst, fst_fs_name) && same_fs(snd, snd_fs_name))
+                || (same_fs(fst, snd_fs_name) && same_fs(snd, fst_fs_name))
+        }) {
+            for attribute in difference.hash_attributes.iter() {
+                if !allowlist.hash_attributes.contains(attribute) {
+                    allowlist.hash_attributes.push(*attribute);
+                }
+            }
+            for field in difference.trace_fields.iter() {
+                if !allowlist.trace_fields.contains(field) {
+                    allowlist.trace_fields.push(field.clone());
+                }
+            }
+        }
+        allowlist
+    }
+}
+
+fn same_fs(name: &str, fs_name: &str) -> bool {
+    fs_name.eq_ignore_ascii_case(name)
+        || fs_name
+            .split_once('-')
+            .is_some_and(|(base, _)| base.eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known() -> Vec<KnownDifference> {
+        vec![
+            KnownDifference {
+                filesystems: ["xfs".to_owned(), "ext4".to_owned()],
+                hash_attributes: vec![Attribute::Allocated],
+                trace_fields: vec!["st_blocks".to_owned()],
+            },
+            KnownDifference {
+                filesystems: ["Ext4".to_owned(), "XFS".to_owned()],
+                hash_attributes: vec![Attribute::Allocated, Attribute::Nlink],
+                trace_fields: vec![],
+            },
+            KnownDifference {
+                filesystems: ["btrfs".to_owned(), "ext4".to_owned()],
+                hash_attributes: vec![Attribute::Nlink],
+                trace_fields: vec!["entries".to_owned()],
+            },
+        ]
+    }
+
+    #[test]
+    fn test_for_pair() {
+        let expected = Allowlist {
+            hash_attributes: vec![Attribute::Allocated, Attribute::Nlink],
+            trace_fields: vec!["st_blocks".to_owned()],
+        };
+        assert_eq!(expected, Allowlist::for_pair(&known(), "XFS", "Ext4"));
+        assert_eq!(expected, Allowlist::for_pair(&known(), "Ext4", "XFS"));
+        assert_eq!(
+            expected,
+            Allowlist::for_pair(&known(), "Ext4-journal", "XFS")
+        );
+        assert_eq!(
+            Allowlist::default(),
+            Allowlist::for_pair(&known(), "Ext4", "F2FS")
+        );
+        assert_eq!(
+            Allowlist::default(),
+            Allowlist::for_pair(&known(), "Ext4", "Ext4")
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let known: KnownDifference = toml::from_str(
+            r#"
+filesystems = ["xfs", "ext4"]
+hash_attributes = ["allocated", "times"]
+trace_fields = ["st_blocks"]
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            vec![Attribute::Allocated, Attribute::Times],
+            known.hash_attributes
+        );
+    }
+}
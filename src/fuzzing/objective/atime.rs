@@ -0,0 +1,217 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::debug;
+
+use crate::{
+    abstract_fs::{fs::AbstractFS, trace::Trace, workload::Workload},
+    mount::atime::AtimePolicy,
+};
+
+/// Day in nanoseconds, `relatime` also updates atime older than that.
+const RELATIME_PERIOD: i64 = 24 * 60 * 60 * 1_000_000_000;
+
+/// Checks that every read updated access time according to policy.
+///
+/// Timestamps are coarse, so update that set atime to the same value can not be told apart from no update,
+/// instead updated atime is only expected to be not older than ctime (which is never in the future).
+/// Reads of files with times set (by `UTIMENS`) to the future are not checked.
+pub struct AtimeObjective {
+    policy: AtimePolicy,
+}
+
+#[derive(Debug, PartialEq)]
+struct AccessTimes {
+    before: i64,
+    after: i64,
+    mtime: i64,
+    ctime: i64,
+}
+
+impl AtimeObjective {
+    pub fn new(policy: AtimePolicy) -> Self {
+        Self { policy }
+    }
+
+    pub fn is_interesting(&self, input: &Workload, fst_trace: &Trace, snd_trace: &Trace) -> bool {
+        debug!("do atime objective");
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs() as i64);
+        let skipped = AbstractFS::future_time_reads_of(input, now);
+        let fst_violations = self.violations(&skipped, fst_trace);
+        let snd_violations = self.violations(&skipped, snd_trace);
+        if !fst_violations.is_empty() || !snd_violations.is_empty() {
+            debug!(
+                "atime policy '{}' violated at operations {:?} (first) and {:?} (second)",
+                self.policy, fst_violations, snd_violations
+            );
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Indices of reads (except `skipped` ones) that did not follow policy.
+    pub fn violations(&self, skipped: &[u32], trace: &Trace) -> Vec<u32> {
+        trace
+            .rows
+            .iter()
+            .filter(|row| row.command() == "READ" && !skipped.contains(&row.index()))
+            .filter_map(|row| {
+                let times = AccessTimes {
+                    before: row.field("atime0")?.parse().ok()?,
+                    after: row.field("atime")?.parse().ok()?,
+                    mtime: row.field("mtime")?.parse().ok()?,
+                    ctime: row.field("ctime")?.parse().ok()?,
+                };
+                (!self.expected(&times)).then_some(row.index())
+            })
+            .collect()
+    }
+
+    /// Same rules as kernel (`relatime_need_update`), updated atime is current time.
+    fn expected(&self, times: &AccessTimes) -> bool {
+        // every change sets ctime to current time, modification time can only be newer if set explicitly
+        let updated = times.after >= times.ctime && times.after >= times.before;
+        let unchanged = times.after == times.before;
+        match self.policy {
+            AtimePolicy::Noatime => unchanged,
+            AtimePolicy::Strictatime => updated,
+            AtimePolicy::Relatime => {
+                let stale = times.before <= times.mtime
+                    || times.before <= times.ctime
+                    || times.after - times.before >= RELATIME_PERIOD;
+                if stale {
+                    updated
+                } else {
+                    unchanged
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::abstract_fs::{node::FileDescriptorIndex, operation::Operation};
+
+    use super::*;
+
+    /// Access time before and after read, modification and change time.
+    type Read = (i64, i64, i64, i64);
+
+    fn trace(reads: &[Read]) -> Trace {
+        let mut trace = "Index,Command,ReturnCode,Errno,Extra\n".to_owned();
+        for (i, (before, after, mtime, ctime)) in reads.iter().enumerate() {
+            trace.push_str(&format!(
+                "{},READ,10,Success(0),hash=1 atime0={} atime={} mtime={} ctime={}\n",
+                i, before, after, mtime, ctime
+            ));
+        }
+        Trace::try_parse(trace).unwrap()
+    }
+
+    /// Read right after write (atime older than mtime), then read again.
+    const FIRST_READ_UPDATED: Read = (100, 300, 200, 200);
+    const FIRST_READ_UNCHANGED: Read = (100, 100, 200, 200);
+    const SECOND_READ_UPDATED: Read = (300, 400, 200, 200);
+    const SECOND_READ_UNCHANGED: Read = (300, 300, 200, 200);
+    /// Read after attributes changed (e.g. `CHMOD`), atime is older than ctime only.
+    const CHANGED_READ_UPDATED: Read = (300, 400, 200, 350);
+    const CHANGED_READ_UNCHANGED: Read = (300, 300, 200, 350);
+
+    #[test]
+    fn test_noatime() {
+        let objective = AtimeObjective::new(AtimePolicy::Noatime);
+        let ok = trace(&[FIRST_READ_UNCHANGED, SECOND_READ_UNCHANGED]);
+        let bad = trace(&[FIRST_READ_UPDATED, SECOND_READ_UNCHANGED]);
+        assert!(objective.violations(&[], &ok).is_empty());
+        assert_eq!(vec![0], objective.violations(&[], &bad));
+        assert!(objective.is_interesting(&Workload::new(), &ok, &bad));
+        assert!(!objective.is_interesting(&Workload::new(), &ok, &ok));
+    }
+
+    #[test]
+    fn test_strictatime() {
+        let objective = AtimeObjective::new(AtimePolicy::Strictatime);
+        let ok = trace(&[
+            FIRST_READ_UPDATED,
+            SECOND_READ_UPDATED,
+            CHANGED_READ_UPDATED,
+        ]);
+        let coarse = trace(&[FIRST_READ_UPDATED, SECOND_READ_UNCHANGED]);
+        let bad = trace(&[FIRST_READ_UNCHANGED, CHANGED_READ_UNCHANGED]);
+        // modification time set to the future explicitly
+        let future_mtime = trace(&[(100, 300, 4_294_967_295_000_000_000, 200)]);
+        assert!(objective.violations(&[], &ok).is_empty());
+        assert!(objective.violations(&[], &coarse).is_empty());
+        assert!(objective.violations(&[], &future_mtime).is_empty());
+        assert_eq!(vec![0, 1], objective.violations(&[], &bad));
+    }
+
+    #[test]
+    fn test_relatime() {
+        let objective = AtimeObjective::new(AtimePolicy::Relatime);
+        let ok = trace(&[FIRST_READ_UPDATED, SECOND_READ_UNCHANGED]);
+        let not_updated = trace(&[FIRST_READ_UNCHANGED, SECOND_READ_UNCHANGED]);
+        let updated_twice = trace(&[FIRST_READ_UPDATED, SECOND_READ_UPDATED]);
+        let day_old = trace(&[(100, 100 + RELATIME_PERIOD, 50, 50)]);
+        let changed = trace(&[FIRST_READ_UPDATED, CHANGED_READ_UPDATED]);
+        let changed_not_updated = trace(&[FIRST_READ_UPDATED, CHANGED_READ_UNCHANGED]);
+        assert!(objective.violations(&[], &ok).is_empty());
+        assert_eq!(vec![0], objective.violations(&[], &not_updated));
+        assert_eq!(vec![1], objective.violations(&[], &updated_twice));
+        assert!(objective.violations(&[], &day_old).is_empty());
+        assert!(objective.violations(&[], &changed).is_empty());
+        assert_eq!(vec![1], objective.violations(&[], &changed_not_updated));
+    }
+
+    #[test]
+    fn test_future_times_skipped() {
+        let objective = AtimeObjective::new(AtimePolicy::Strictatime);
+        let utimens = |atime| Operation::UTIMENS {
+            path: "/foo".into(),
+            atime,
+            mtime: 0,
+        };
+        let input = |atime| Workload {
+            ops: vec![
+                Operation::CREATE {
+                    path: "/foo".into(),
+                    mode: vec![],
+                },
+                utimens(atime),
+                Operation::OPEN {
+                    path: "/foo".into(),
+                    flags: vec![],
+                    des: FileDescriptorIndex(0),
+                },
+                Operation::READ {
+                    des: FileDescriptorIndex(0),
+                    size: 10,
+                },
+            ],
+            seed: 0,
+        };
+        // atime goes back to current time
+        let future = 4_294_967_295;
+        let read = trace(&[
+            (0, 0, 0, 0),
+            (0, 0, 0, 0),
+            (0, 0, 0, 0),
+            (future * 1_000_000_000, 300, 0, 200),
+        ]);
+        assert!(!objective.is_interesting(&input(future), &read, &read));
+        assert!(objective.is_interesting(&input(1), &read, &read));
+    }
+
+    #[test]
+    fn test_reads_without_times_ignored() {
+        let objective = AtimeObjective::new(AtimePolicy::Noatime);
+        let trace = Trace::try_parse(
+            "Index,Command,ReturnCode,Errno,Extra\n0,READ,10,Success(0),hash=1\n".to_owned(),
+        )
+        .unwrap();
+        assert!(objective.violations(&[], &trace).is_empty());
+    }
+}
@@ -0,0 +1,258 @@
+use log::debug;
+
+use crate::abstract_fs::{
+    fs::AbstractFS, operation::Operation, pathname::PathName, trace::Trace, workload::Workload,
+};
+
+/// Aware of which filesystem folds case of names (model never does).
+///
+/// New entry whose name differs from existing sibling only in case must be rejected
+/// with `EEXIST` by case-insensitive filesystem, otherwise it holds two colliding names.
+/// `CREATE` is not checked, `creat` opens existing file under folded name.
+/// If only one filesystem folds case, colliding operations are expected to differ.
+pub struct CaseObjective {
+    fst_case_insensitive: bool,
+    snd_case_insensitive: bool,
+}
+
+const EEXIST: i32 = 17;
+
+impl CaseObjective {
+    pub fn new(fst_case_insensitive: bool, snd_case_insensitive: bool) -> Self {
+        Self {
+            fst_case_insensitive,
+            snd_case_insensitive,
+        }
+    }
+
+    pub fn is_interesting(&self, input: &Workload, fst_trace: &Trace, snd_trace: &Trace) -> bool {
+        debug!("do case objective");
+        let collisions = collisions(input);
+        let violations = |case_insensitive: bool, trace: &Trace| {
+            if case_insensitive {
+                violations(input, &collisions, trace)
+            } else {
+                vec![]
+            }
+        };
+        let fst_violations = violations(self.fst_case_insensitive, fst_trace);
+        let snd_violations = violations(self.snd_case_insensitive, snd_trace);
+        if !fst_violations.is_empty() || !snd_violations.is_empty() {
+            debug!(
+                "colliding names accepted at operations {:?} (first) and {:?} (second)",
+                fst_violations, snd_violations
+            );
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Indices of operations adding colliding names, their outcome differs if only one filesystem folds case.
+    pub fn expected_differences(&self, input: &Workload) -> Vec<u32> {
+        if self.fst_case_insensitive == self.snd_case_insensitive {
+            return vec![];
+        }
+        collisions(input)
+    }
+
+    /// Patterns matching colliding names (in any case) and files under them, see [`crate::fuzzing::objective::hash::crashed_patterns`].
+    ///
+    /// Only one of colliding names exists on filesystem that folds case, so they are not compared.
+    pub fn colliding_patterns(&self, input: &Workload) -> Vec<String> {
+        self.expected_differences(input)
+            .into_iter()
+            .filter_map(|index| input.ops.get(index as usize).and_then(new_path))
+            .map(|path| {
+                format!(
+                    "(?i)^{}(/.*)?$",
+                    regex::escape(path.to_string().trim_start_matches('/'))
+                )
+            })
+            .collect()
+    }
+}
+
+/// Indices of operations adding a name that collides with a different existing sibling if case is folded.
+pub fn collisions(input: &Workload) -> Vec<u32> {
+    let mut fs = AbstractFS::new();
+    let mut collisions = vec![];
+    for (index, op) in input.ops.iter().enumerate() {
+        if new_path(op).is_some_and(|path| collides(&fs, path)) {
+            collisions.push(index as u32);
+        }
+        if fs
+            .replay(&Workload {
+                ops: vec![op.clone()],
+                seed: input.seed,
+            })
+            .is_err()
+        {
+            break;
+        }
+    }
+    collisions
+}
+
+fn new_path(op: &Operation) -> Option<&PathName> {
+    match op {
+        Operation::MKDIR { path, .. }
+        | Operation::CREATE { path, .. }
+        | Operation::MKSOCKET { path }
+        | Operation::SYMLINK { path, .. }
+        | Operation::MKNOD { path, .. } => Some(path),
+        Operation::HARDLINK { new_path, .. } | Operation::RENAME { new_path, .. } => Some(new_path),
+        _ => None,
+    }
+}
+
+fn collides(fs: &AbstractFS, path: &PathName) -> bool {
+    let (parent, name) = path.split();
+    fs.child_names(parent).is_ok_and(|siblings| {
+        siblings
+            .iter()
+            .any(|sibling| *sibling != name && sibling.eq_ignore_ascii_case(&name))
+    })
+}
+
+/// Collisions accepted by case-insensitive filesystem.
+fn violations(input: &Workload, collisions: &[u32], trace: &Trace) -> Vec<u32> {
+    collisions
+        .iter()
+        .copied()
+        .filter(|index| {
+            matches!(
+                input.ops.get(*index as usize),
+                Some(
+                    Operation::MKDIR { .. }
+                        | Operation::MKSOCKET { .. }
+                        | Operation::SYMLINK { .. }
+                        | Operation::MKNOD { .. }
+                        | Operation::HARDLINK { .. }
+                )
+            )
+        })
+        .filter(|index| {
+            trace
+                .rows
+                .iter()
+                .find(|row| row.index() == *index)
+                .is_some_and(|row| row.errno_code() != EEXIST)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::RegexSet;
+
+    use super::*;
+
+    fn workload() -> Workload {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/n0".into(), vec![]).unwrap();
+        fs.mkdir("/N0".into(), vec![]).unwrap();
+        fs.mksocket("/n0/s1".into()).unwrap();
+        fs.mksocket("/n0/S1".into()).unwrap();
+        fs.recording
+    }
+
+    fn trace(rows: &[&str]) -> Trace {
+        let mut trace = "Index,Command,ReturnCode,Errno,Extra\n".to_owned();
+        for (index, row) in rows.iter().enumerate() {
+            trace.push_str(&format!("{},{}\n", index, row));
+        }
+        Trace::try_parse(trace).unwrap()
+    }
+
+    fn sensitive() -> Trace {
+        trace(&[
+            "MKDIR,0,Success(0),",
+            "MKDIR,0,Success(0),",
+            "MKSOCKET,0,Success(0),",
+            "MKSOCKET,0,Success(0),",
+        ])
+    }
+
+    fn insensitive() -> Trace {
+        trace(&[
+            "MKDIR,0,Success(0),",
+            "MKDIR,-1,File exists(17),",
+            "MKSOCKET,0,Success(0),",
+            "MKSOCKET,-1,File exists(17),",
+        ])
+    }
+
+    #[test]
+    fn test_collisions() {
+        assert_eq!(vec![1, 3], collisions(&workload()));
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/n0".into(), vec![]).unwrap();
+        fs.mkdir("/n1".into(), vec![]).unwrap();
+        fs.mkdir("/n1/N0".into(), vec![]).unwrap();
+        fs.rename("/n1".into(), "/N1".into()).unwrap();
+        assert_eq!(vec![3], collisions(&fs.recording));
+    }
+
+    #[test]
+    fn test_expected_case_behavior() {
+        let input = workload();
+        let objective = CaseObjective::new(true, false);
+        assert!(!objective.is_interesting(&input, &insensitive(), &sensitive()));
+        let expected = objective.expected_differences(&input);
+        assert_eq!(vec![1, 3], expected);
+        assert_eq!(
+            None,
+            insensitive()
+                .without(&expected)
+                .first_difference(&sensitive().without(&expected), &[])
+        );
+
+        let early = trace(&[
+            "MKDIR,-1,No space left on device(28),",
+            "MKDIR,-1,File exists(17),",
+            "MKSOCKET,-1,No such file or directory(2),",
+            "MKSOCKET,-1,No such file or directory(2),",
+        ]);
+        assert_eq!(
+            Some(0),
+            early
+                .without(&expected)
+                .first_difference(&sensitive().without(&expected), &[])
+        );
+        let objective = CaseObjective::new(true, true);
+        assert!(objective.expected_differences(&input).is_empty());
+        assert!(objective.colliding_patterns(&input).is_empty());
+    }
+
+    #[test]
+    fn test_colliding_patterns() {
+        let objective = CaseObjective::new(false, true);
+        let patterns = RegexSet::new(objective.colliding_patterns(&workload())).unwrap();
+        assert!(patterns.is_match("N0"));
+        assert!(patterns.is_match("n0"));
+        assert!(patterns.is_match("n0/s1"));
+        assert!(patterns.is_match("N0/x"));
+        assert!(!patterns.is_match("n00"));
+    }
+
+    #[test]
+    fn test_collision_bug() {
+        let input = workload();
+        let objective = CaseObjective::new(true, false);
+        assert!(objective.is_interesting(&input, &sensitive(), &sensitive()));
+        let objective = CaseObjective::new(false, true);
+        let accepted_socket = trace(&[
+            "MKDIR,0,Success(0),",
+            "MKDIR,-1,File exists(17),",
+            "MKSOCKET,0,Success(0),",
+            "MKSOCKET,0,Success(0),",
+        ]);
+        assert!(objective.is_interesting(&input, &sensitive(), &accepted_socket));
+        assert_eq!(
+            vec![3],
+            violations(&input, &collisions(&input), &accepted_socket)
+        );
+        assert!(!objective.is_interesting(&input, &sensitive(), &insensitive()));
+    }
+}
@@ -0,0 +1,235 @@
+use log::debug;
+
+use crate::abstract_fs::{
+    content::Content, fs::AbstractFS, operation::Operation, trace::Trace, workload::Workload,
+};
+
+/// Checks hash of data returned by every read against bytes model expects.
+///
+/// Executor fills write buffer from seed of workload, so model knows exact bytes of every file.
+/// Reads that returned different number of bytes are left to trace objective.
+/// After crash point, file that had unsynced data may also return what it had when it was last synced.
+pub struct ContentObjective {}
+
+/// Bytes model predicts for read at `index`, hashed the same way as in executor.
+#[derive(Debug, PartialEq)]
+pub struct ExpectedRead {
+    pub index: u32,
+    pub size: u64,
+    pub hash: u64,
+    /// Size and hash of read if unsynced data was lost by crash point.
+    pub after_crash: Option<(u64, u64)>,
+}
+
+impl ContentObjective {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn is_interesting(&self, input: &Workload, fst_trace: &Trace, snd_trace: &Trace) -> bool {
+        debug!("do content objective");
+        let expected = self.expected(input);
+        let fst_violations = self.violations(&expected, fst_trace);
+        let snd_violations = self.violations(&expected, snd_trace);
+        if !fst_violations.is_empty() || !snd_violations.is_empty() {
+            debug!(
+                "read data differs from model at operations {:?} (first) and {:?} (second)",
+                fst_violations, snd_violations
+            );
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn expected(&self, input: &Workload) -> Vec<ExpectedRead> {
+        let mut fs = AbstractFS::new();
+        let mut expected = vec![];
+        for (index, op) in input.ops.iter().enumerate() {
+            if let Operation::READ { des, size } = op {
+                let after_crash = fs
+                    .descriptors
+                    .get(des.0)
+                    .and_then(|des| {
+                        let crashed = fs.file(&des.file).ok()?.crashed.as_ref()?;
+                        Some(if des.offset > crashed.size() {
+                            Content::new()
+                        } else {
+                            crashed.read(des.offset, *size).ok()?
+                        })
+                    })
+                    .map(|content| (content.size(), hashcode(&content.bytes(input.seed))));
+                let Ok(content) = fs.read(*des, *size) else {
+                    break;
+                };
+                expected.push(ExpectedRead {
+                    index: index as u32,
+                    size: content.size(),
+                    hash: hashcode(&content.bytes(input.seed)),
+                    after_crash,
+                });
+                continue;
+            }
+            if fs
+                .replay(&Workload {
+                    ops: vec![op.clone()],
+                    seed: input.seed,
+                })
+                .is_err()
+            {
+                break;
+            }
+        }
+        expected
+    }
+
+    /// Indices of reads that returned expected number of bytes, but not the expected ones.
+    pub fn violations(&self, expected: &[ExpectedRead], trace: &Trace) -> Vec<u32> {
+        expected
+            .iter()
+            .filter(|expected| {
+                let outcomes: Vec<(u64, u64)> = [(expected.size, expected.hash)]
+                    .into_iter()
+                    .chain(expected.after_crash)
+                    .collect();
+                trace
+                    .rows
+                    .iter()
+                    .find(|row| row.index() == expected.index && row.command() == "READ")
+                    .filter(|row| {
+                        outcomes
+                            .iter()
+                            .any(|(size, _)| row.return_code() as i64 == *size as i64)
+                    })
+                    .and_then(|row| {
+                        let hash = u64::from_str_radix(row.field("hash")?, 16).ok()?;
+                        Some((row.return_code() as u64, hash))
+                    })
+                    .is_some_and(|outcome| !outcomes.contains(&outcome))
+            })
+            .map(|expected| expected.index)
+            .collect()
+    }
+}
+
+/// Same as `buffer_hashcode` in executor.
+pub fn hashcode(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(1u64, |h, byte| {
+        h.wrapping_mul(31).wrapping_add(*byte as u64)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::abstract_fs::{content::source_byte, flags::Whence};
+
+    use super::*;
+
+    fn workload(seed: u64) -> Workload {
+        let mut fs = AbstractFS::new();
+        fs.recording.seed = seed;
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        fs.write(des, 100, 10).unwrap();
+        fs.lseek(des, 0, Whence::SEEK_SET).unwrap();
+        fs.read(des, 1024).unwrap();
+        fs.recording
+    }
+
+    fn trace(reads: &[(u32, i32, &str)]) -> Trace {
+        let mut trace = "Index,Command,ReturnCode,Errno,Extra\n".to_owned();
+        for (index, return_code, extra) in reads {
+            trace.push_str(&format!(
+                "{},READ,{},Success(0),{}\n",
+                index, return_code, extra
+            ));
+        }
+        Trace::try_parse(trace).unwrap()
+    }
+
+    #[test]
+    fn test_hashcode() {
+        assert_eq!(1, hashcode(&[]));
+        assert_eq!(31 + 0xff, hashcode(&[0xff]));
+        assert_eq!((31 + 1) * 31 + 2, hashcode(&[1, 2]));
+    }
+
+    #[test]
+    fn test_expected_read() {
+        let objective = ContentObjective::new();
+        let input = workload(42);
+        let bytes: Vec<u8> = (100..110).map(|offset| source_byte(42, offset)).collect();
+        assert_eq!(
+            vec![ExpectedRead {
+                index: 4,
+                size: 10,
+                hash: hashcode(&bytes),
+                after_crash: None,
+            }],
+            objective.expected(&input)
+        );
+        assert_ne!(
+            objective.expected(&input),
+            objective.expected(&workload(43))
+        );
+    }
+
+    #[test]
+    fn test_violations() {
+        let objective = ContentObjective::new();
+        let expected = objective.expected(&workload(42));
+        let hash = format!("hash={:x}", expected[0].hash);
+        assert!(objective
+            .violations(&expected, &trace(&[(4, 10, &hash)]))
+            .is_empty());
+        assert_eq!(
+            vec![4],
+            objective.violations(&expected, &trace(&[(4, 10, "hash=1")]))
+        );
+        // different size is divergence of trace
+        assert!(objective
+            .violations(&expected, &trace(&[(4, 5, "hash=1")]))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_read_after_crash() {
+        let mut fs = AbstractFS::new();
+        fs.recording.seed = 42;
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        fs.write(des, 0, 10).unwrap();
+        fs.fsync(des).unwrap();
+        fs.write(des, 10, 10).unwrap();
+        fs.crashpoint().unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        fs.read(des, 100).unwrap();
+
+        let objective = ContentObjective::new();
+        let expected = objective.expected(&fs.recording);
+        let synced: Vec<u8> = (0..10).map(|offset| source_byte(42, offset)).collect();
+        let latest: Vec<u8> = (0..20).map(|offset| source_byte(42, offset)).collect();
+        assert_eq!(Some((10, hashcode(&synced))), expected[0].after_crash);
+        assert_eq!(
+            (20, hashcode(&latest)),
+            (expected[0].size, expected[0].hash)
+        );
+
+        let synced = format!("hash={:x}", hashcode(&synced));
+        let latest = format!("hash={:x}", hashcode(&latest));
+        assert!(objective
+            .violations(&expected, &trace(&[(7, 10, &synced)]))
+            .is_empty());
+        assert!(objective
+            .violations(&expected, &trace(&[(7, 20, &latest)]))
+            .is_empty());
+        assert_eq!(
+            vec![7],
+            objective.violations(&expected, &trace(&[(7, 20, &synced)]))
+        );
+        assert_eq!(
+            vec![7],
+            objective.violations(&expected, &trace(&[(7, 10, "hash=1")]))
+        );
+    }
+}
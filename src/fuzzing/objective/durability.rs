@@ -0,0 +1,144 @@
+use log::debug;
+
+use crate::abstract_fs::{fs::AbstractFS, operation::Operation, trace::Trace, workload::Workload};
+
+/// Compares data read after crash point, when it was synced before the crash.
+///
+/// Model tracks byte ranges changed since every file was last synced, read of range
+/// without such bytes must return the same data on both filesystems.
+/// Unsynced data may legitimately be lost (or kept) by either of them.
+pub struct DurabilityObjective {}
+
+impl DurabilityObjective {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn is_interesting(&self, input: &Workload, fst_trace: &Trace, snd_trace: &Trace) -> bool {
+        debug!("do durability objective");
+        let violations = self.violations(&self.durable_reads(input), fst_trace, snd_trace);
+        if !violations.is_empty() {
+            debug!(
+                "synced data differs after crash at operations {:?}",
+                violations
+            );
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Indices of reads done after crash point that touch only synced bytes.
+    pub fn durable_reads(&self, input: &Workload) -> Vec<u32> {
+        let mut fs = AbstractFS::new();
+        let mut crashed = false;
+        let mut reads = vec![];
+        for (index, op) in input.ops.iter().enumerate() {
+            match op {
+                Operation::CRASHPOINT => crashed = true,
+                Operation::READ { des, size } if crashed => {
+                    let synced = fs.descriptors.get(des.0).is_some_and(|des| {
+                        fs.file(&des.file)
+                            .is_ok_and(|file| file.is_synced(des.offset, des.offset + size))
+                    });
+                    if synced {
+                        reads.push(index as u32);
+                    }
+                }
+                _ => {}
+            }
+            if fs
+                .replay(&Workload {
+                    ops: vec![op.clone()],
+                    seed: input.seed,
+                })
+                .is_err()
+            {
+                break;
+            }
+        }
+        reads
+    }
+
+    /// Indices of durable reads that returned different data (or failed only on one filesystem).
+    pub fn violations(&self, reads: &[u32], fst_trace: &Trace, snd_trace: &Trace) -> Vec<u32> {
+        let read = |trace: &Trace, index: u32| {
+            trace
+                .rows
+                .iter()
+                .find(|row| row.index() == index && row.command() == "READ")
+                .map(|row| (row.return_code(), row.field("hash").map(str::to_owned)))
+        };
+        reads
+            .iter()
+            .filter(|index| read(fst_trace, **index) != read(snd_trace, **index))
+            .copied()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::abstract_fs::flags::Whence;
+
+    use super::*;
+
+    fn workload() -> Workload {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        fs.write(des, 0, 100).unwrap();
+        fs.fsync(des).unwrap();
+        fs.lseek(des, 0, Whence::SEEK_SET).unwrap();
+        fs.write(des, 0, 10).unwrap();
+        fs.crashpoint().unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        fs.read(des, 10).unwrap();
+        fs.read(des, 90).unwrap();
+        fs.recording
+    }
+
+    fn trace(reads: &[(u32, i32, &str)]) -> Trace {
+        let mut trace = "Index,Command,ReturnCode,Errno,Extra\n".to_owned();
+        for (index, return_code, extra) in reads {
+            trace.push_str(&format!(
+                "{},READ,{},Success(0),{}\n",
+                index, return_code, extra
+            ));
+        }
+        Trace::try_parse(trace).unwrap()
+    }
+
+    #[test]
+    fn test_durable_reads() {
+        let objective = DurabilityObjective::new();
+        // first read overlaps bytes written after fsync
+        assert_eq!(vec![9], objective.durable_reads(&workload()));
+
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs.open("/foo".into(), vec![]).unwrap();
+        fs.write(des, 0, 100).unwrap();
+        fs.read(des, 10).unwrap();
+        // reads before crash are never durable
+        assert!(objective.durable_reads(&fs.recording).is_empty());
+    }
+
+    #[test]
+    fn test_violations() {
+        let objective = DurabilityObjective::new();
+        let reads = objective.durable_reads(&workload());
+        let fst = trace(&[(8, 10, "hash=1"), (9, 90, "hash=2")]);
+        // unsynced data lost on one filesystem only
+        let snd = trace(&[(8, 10, "hash=3"), (9, 90, "hash=2")]);
+        assert!(objective.violations(&reads, &fst, &snd).is_empty());
+        assert!(!objective.is_interesting(&workload(), &fst, &snd));
+
+        let snd = trace(&[(8, 10, "hash=1"), (9, 90, "hash=4")]);
+        assert_eq!(vec![9], objective.violations(&reads, &fst, &snd));
+        let snd = trace(&[(8, 10, "hash=1"), (9, 0, "hash=1")]);
+        assert_eq!(vec![9], objective.violations(&reads, &fst, &snd));
+        let snd = trace(&[(8, 10, "hash=1")]);
+        assert!(objective.is_interesting(&workload(), &fst, &snd));
+    }
+}
@@ -0,0 +1,106 @@
+use log::debug;
+
+use crate::abstract_fs::{fs::AbstractFS, operation::Operation, trace::Trace, workload::Workload};
+
+/// Flags operations model expects to fail (e.g. `O_NOFOLLOW` open of symlink) that succeeded.
+///
+/// Filesystems that get it wrong the same way do not diverge, so each of them is checked on its own.
+/// Removed nodes may come back after crash point, so workloads with crash points are not checked.
+#[derive(Default)]
+pub struct FailureObjective {}
+
+impl FailureObjective {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn is_interesting(&self, input: &Workload, fst_trace: &Trace, snd_trace: &Trace) -> bool {
+        debug!("do failure objective");
+        let expected = self.expected(input);
+        let fst_violations = self.violations(&expected, fst_trace);
+        let snd_violations = self.violations(&expected, snd_trace);
+        if !fst_violations.is_empty() || !snd_violations.is_empty() {
+            debug!(
+                "operations expected to fail succeeded at {:?} (first) and {:?} (second)",
+                fst_violations, snd_violations
+            );
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Indices of operations model expects to fail.
+    pub fn expected(&self, input: &Workload) -> Vec<u32> {
+        if input.ops.contains(&Operation::CRASHPOINT) {
+            return vec![];
+        }
+        AbstractFS::expected_failures_of(input)
+    }
+
+    /// Indices of expected failures that succeeded.
+    pub fn violations(&self, expected: &[u32], trace: &Trace) -> Vec<u32> {
+        trace
+            .rows
+            .iter()
+            .filter(|row| expected.contains(&row.index()) && row.errno_code() == 0)
+            .map(|row| row.index())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::abstract_fs::flags::OpenFlag;
+
+    use super::*;
+
+    fn trace(rows: &[&str]) -> Trace {
+        let mut trace = "Index,Command,ReturnCode,Errno,Extra\n".to_owned();
+        for row in rows {
+            trace.push_str(&format!("{}\n", row));
+        }
+        Trace::try_parse(trace).unwrap()
+    }
+
+    #[test]
+    fn test_symlink_followed() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        fs.symlink("/foo".to_owned(), "/bar".into()).unwrap();
+        fs.open_failing("/bar".into(), vec![OpenFlag::O_NOFOLLOW])
+            .unwrap();
+        let input = fs.recording;
+        let objective = FailureObjective::new();
+        assert_eq!(vec![2], objective.expected(&input));
+
+        let rejected = trace(&[
+            "0,CREATE,3,Success(0),",
+            "1,SYMLINK,0,Success(0),",
+            "2,OPEN,-1,Error(40),",
+        ]);
+        let followed = trace(&[
+            "0,CREATE,3,Success(0),",
+            "1,SYMLINK,0,Success(0),",
+            "2,OPEN,3,Success(0),",
+        ]);
+        assert!(!objective.is_interesting(&input, &rejected, &rejected));
+        assert!(objective.is_interesting(&input, &rejected, &followed));
+        // both filesystems follow symlink, so traces do not diverge
+        assert!(objective.is_interesting(&input, &followed, &followed));
+        assert_eq!(
+            vec![2],
+            objective.violations(&objective.expected(&input), &followed)
+        );
+    }
+
+    #[test]
+    fn test_crashpoint_not_checked() {
+        let mut fs = AbstractFS::new();
+        fs.symlink("/foo".to_owned(), "/bar".into()).unwrap();
+        fs.crashpoint().unwrap();
+        fs.open_failing("/bar".into(), vec![OpenFlag::O_NOFOLLOW])
+            .unwrap();
+        assert!(FailureObjective::new().expected(&fs.recording).is_empty());
+    }
+}
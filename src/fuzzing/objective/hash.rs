@@ -3,7 +3,12 @@ use std::path::Path;
 use log::debug;
 use regex::RegexSet;
 
-use crate::hasher::hasher::{calc_dir_hash, get_diff, FileDiff, FileInfo, HasherOptions};
+use crate::abstract_fs::pathname::PathName;
+use crate::fuzzing::observer::{Observer, ObserverVerdict};
+use crate::hasher::hasher::{
+    calc_dir_hash, describe_diff, get_diff, isolate_divergence, Attribute, FileDiff, FileInfo,
+    HasherOptions,
+};
 
 pub struct HashHolder {
     fs_dir: Box<Path>,
@@ -14,18 +19,118 @@ pub struct HashHolder {
 }
 
 impl HashHolder {
+    pub fn new(fs_dir: Box<Path>, fs_internal: RegexSet, hasher_options: HasherOptions) -> Self {
+        Self {
+            fs_dir,
+            fs_internal,
+            fs_content: vec![],
+            hash: 0,
+            hasher_options,
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn fs_content(&self) -> &[FileInfo] {
+        &self.fs_content
+    }
+
     pub fn calc_and_save_hash(&mut self) {
         let (hash, fs_content) =
             calc_dir_hash(&self.fs_dir, &self.fs_internal, &self.hasher_options);
         self.fs_content = fs_content;
         self.hash = hash;
     }
+
+    /// Differences from contents of other filesystem.
+    ///
+    /// `suppressed` attributes and files matching `crashed` patterns (see [`crashed_patterns`]) are not compared.
+    pub fn diff(
+        &self,
+        other: &HashHolder,
+        suppressed: &[Attribute],
+        crashed: &[String],
+    ) -> Vec<FileDiff> {
+        get_diff(
+            &self.fs_content,
+            &other.fs_content,
+            &skipped(&self.fs_internal, crashed),
+            &skipped(&other.fs_internal, crashed),
+            &compared_options(&self.hasher_options, suppressed),
+        )
+    }
+
+    /// Same as checking [`HashHolder::diff`] is not empty, but contents are only compared if hashes differ.
+    pub fn differs_from(
+        &self,
+        other: &HashHolder,
+        suppressed: &[Attribute],
+        crashed: &[String],
+    ) -> bool {
+        if self.hash == other.hash {
+            return false;
+        }
+        if suppressed.is_empty() && crashed.is_empty() {
+            return true;
+        }
+        let differs = !self.diff(other, suppressed, crashed).is_empty();
+        if !differs {
+            debug!(
+                "hashes differ only in suppressed attributes {:?} or files changed by crash {:?}",
+                suppressed, crashed
+            );
+        }
+        differs
+    }
+}
+
+/// Patterns matching files that may have lost data at crash point.
+pub fn crashed_patterns(paths: &[PathName]) -> Vec<String> {
+    paths
+        .iter()
+        .map(|path| {
+            format!(
+                "^{}$",
+                regex::escape(path.to_string().trim_start_matches('/'))
+            )
+        })
+        .collect()
+}
+
+fn skipped(internal: &RegexSet, crashed: &[String]) -> RegexSet {
+    if crashed.is_empty() {
+        return internal.clone();
+    }
+    RegexSet::new(internal.patterns().iter().chain(crashed.iter()))
+        .expect("escaped paths are valid patterns")
+}
+
+fn compared_options(options: &HasherOptions, suppressed: &[Attribute]) -> HasherOptions {
+    suppressed
+        .iter()
+        .fold(options.clone(), |options, attribute| {
+            options.without(*attribute)
+        })
+}
+
+impl Observer for HashHolder {
+    fn post_exec(&mut self) -> anyhow::Result<ObserverVerdict> {
+        self.calc_and_save_hash();
+        Ok(ObserverVerdict::Conclusive)
+    }
 }
 
 pub struct HashObjective {
     pub fst_fs: HashHolder,
     pub snd_fs: HashHolder,
     enabled: bool,
+    /// Attributes known to differ between filesystems, they are hashed but not compared.
+    suppressed: Vec<Attribute>,
+    /// Patterns of files that may have lost data at crash point of the last input
+    /// or collided on case-insensitive filesystem, they are not compared.
+    crashed: Vec<String>,
 }
 
 impl HashObjective {
@@ -34,43 +139,135 @@ impl HashObjective {
         snd_fs_dir: Box<Path>,
         fst_fs_internal: RegexSet,
         snd_fs_internal: RegexSet,
+        hasher_options: HasherOptions,
         enabled: bool,
+        suppressed: Vec<Attribute>,
     ) -> Self {
         Self {
-            fst_fs: HashHolder {
-                fs_dir: fst_fs_dir,
-                fs_internal: fst_fs_internal,
-                fs_content: vec![],
-                hash: 0,
-                hasher_options: Default::default(),
-            },
-            snd_fs: HashHolder {
-                fs_dir: snd_fs_dir,
-                fs_internal: snd_fs_internal,
-                fs_content: vec![],
-                hash: 0,
-                hasher_options: Default::default(),
-            },
+            fst_fs: HashHolder::new(fst_fs_dir, fst_fs_internal, hasher_options.clone()),
+            snd_fs: HashHolder::new(snd_fs_dir, snd_fs_internal, hasher_options),
             enabled,
+            suppressed,
+            crashed: vec![],
         }
     }
 
+    pub fn set_crashed_paths(&mut self, paths: &[PathName]) {
+        self.crashed = crashed_patterns(paths);
+    }
+
+    /// Extends patterns set by [`HashObjective::set_crashed_paths`], see [`crate::fuzzing::objective::case::CaseObjective::colliding_patterns`].
+    pub fn skip_colliding(&mut self, patterns: Vec<String>) {
+        self.crashed.extend(patterns);
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
     pub fn is_interesting(&self) -> anyhow::Result<bool> {
         debug!("do hash objective");
         if !self.enabled {
             return Ok(false);
         }
 
-        Ok(self.fst_fs.hash != self.snd_fs.hash)
+        Ok(self
+            .fst_fs
+            .differs_from(&self.snd_fs, &self.suppressed, &self.crashed))
     }
 
-    pub fn get_diff(&mut self) -> Vec<FileDiff> {
-        get_diff(
+    /// Differences of filesystem contents, suppressed attributes are not compared.
+    pub fn get_diff(&self) -> Vec<FileDiff> {
+        self.fst_fs
+            .diff(&self.snd_fs, &self.suppressed, &self.crashed)
+    }
+
+    /// Paths and attributes of files that differ, see [`describe_diff`].
+    pub fn describe_diff(&self, diff: &[FileDiff]) -> Vec<String> {
+        let options = compared_options(&self.fst_fs.hasher_options, &self.suppressed);
+        diff.iter()
+            .filter_map(|file_diff| describe_diff(file_diff, &options))
+            .collect()
+    }
+
+    /// Minimal set of attributes that still constitute a divergence, see [`isolate_divergence`].
+    ///
+    /// Starts with every attribute, so it works regardless of options used for hashing
+    /// (contents are only compared if they were read).
+    /// Times are only included if they were hashed, otherwise they almost always differ.
+    pub fn isolate_divergence(&self) -> Option<(HasherOptions, String)> {
+        let mut options = HasherOptions::all().with(Attribute::Content);
+        if self
+            .fst_fs
+            .hasher_options
+            .enabled()
+            .contains(&Attribute::Times)
+        {
+            options = options.with(Attribute::Times);
+        }
+        isolate_divergence(
             &self.fst_fs.fs_content,
             &self.snd_fs.fs_content,
-            &self.fst_fs.fs_internal,
-            &self.snd_fs.fs_internal,
-            &self.fst_fs.hasher_options,
+            &skipped(&self.fst_fs.fs_internal, &self.crashed),
+            &skipped(&self.snd_fs.fs_internal, &self.crashed),
+            &compared_options(&options, &self.suppressed),
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, os::unix::fs::PermissionsExt};
+
+    use crate::mount::{ext4::Ext4, mount::FileSystemMount};
+
+    use super::*;
+
+    #[test]
+    fn test_suppressed_attributes() {
+        let temp_dir = env::temp_dir().join("DIFFuzzer-hash-objective-test");
+        fs::remove_dir_all(temp_dir.as_path()).unwrap_or(());
+        let fst = temp_dir.join("fst");
+        let snd = temp_dir.join("snd");
+        for dir in [&fst, &snd] {
+            fs::create_dir_all(dir).unwrap();
+            fs::write(dir.join("0"), "data").unwrap();
+        }
+        fs::set_permissions(snd.join("0"), fs::Permissions::from_mode(0o600)).unwrap();
+        let skip = Ext4::new().get_internal_dirs();
+        let objective = |suppressed: Vec<Attribute>| {
+            let mut objective = HashObjective::new(
+                fst.clone().into_boxed_path(),
+                snd.clone().into_boxed_path(),
+                skip.clone(),
+                skip.clone(),
+                HasherOptions::all(),
+                true,
+                suppressed,
+            );
+            objective.fst_fs.calc_and_save_hash();
+            objective.snd_fs.calc_and_save_hash();
+            objective
+        };
+
+        assert!(objective(vec![]).is_interesting().unwrap());
+        let suppressed = objective(vec![Attribute::Mode]);
+        assert!(!suppressed.is_interesting().unwrap());
+        assert!(suppressed.get_diff().is_empty());
+        assert_eq!(None, suppressed.isolate_divergence());
+
+        fs::write(snd.join("0"), "more data").unwrap();
+        let suppressed = objective(vec![Attribute::Mode]);
+        assert!(suppressed.is_interesting().unwrap());
+        let (options, _) = suppressed.isolate_divergence().unwrap();
+        assert_eq!(vec![Attribute::Size], options.enabled());
+
+        // file could have lost data at crash point
+        let mut crashed = objective(vec![]);
+        crashed.set_crashed_paths(&["/0".into()]);
+        assert!(!crashed.is_interesting().unwrap());
+        assert!(crashed.get_diff().is_empty());
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+}
@@ -1,2 +1,15 @@
+pub mod allowlist;
+pub mod atime;
+pub mod case;
+pub mod content;
+pub mod durability;
+pub mod failure;
 pub mod hash;
+pub mod nlink;
+pub mod readlink;
+pub mod signal;
+pub mod slow_op;
+pub mod stat;
+pub mod statfs;
+pub mod times;
 pub mod trace;
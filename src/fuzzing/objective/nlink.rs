@@ -0,0 +1,173 @@
+use log::debug;
+
+use crate::abstract_fs::{
+    fs::AbstractFS, operation::Operation, pathname::PathName, trace::Trace, workload::Workload,
+};
+
+/// Checks link counts of parent directories after every rename against model.
+///
+/// Moved subdirectory takes its `..` entry along, so old parent loses a link and new parent gains one.
+/// Root is skipped because it can hold filesystem internal directories (e.g. `lost+found`),
+/// as are link counts of 1, which filesystems report when they do not count subdirectories.
+pub struct DirNlinkObjective {}
+
+/// Link counts model predicts for parents after rename at `index`, `None` for root.
+#[derive(Debug, PartialEq)]
+pub struct ExpectedNlink {
+    pub index: u32,
+    pub old_parent: Option<u64>,
+    pub new_parent: Option<u64>,
+}
+
+impl DirNlinkObjective {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn is_interesting(&self, input: &Workload, fst_trace: &Trace, snd_trace: &Trace) -> bool {
+        debug!("do dir nlink objective");
+        let expected = self.expected(input);
+        let fst_violations = self.violations(&expected, fst_trace);
+        let snd_violations = self.violations(&expected, snd_trace);
+        if !fst_violations.is_empty() || !snd_violations.is_empty() {
+            debug!(
+                "parent link counts differ from model at operations {:?} (first) and {:?} (second)",
+                fst_violations, snd_violations
+            );
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn expected(&self, input: &Workload) -> Vec<ExpectedNlink> {
+        let mut fs = AbstractFS::new();
+        let mut expected = vec![];
+        for (index, op) in input.ops.iter().enumerate() {
+            if fs
+                .replay(&Workload {
+                    ops: vec![op.clone()],
+                    seed: input.seed,
+                })
+                .is_err()
+            {
+                break;
+            }
+            let Operation::RENAME {
+                old_path, new_path, ..
+            } = op
+            else {
+                continue;
+            };
+            // rename into bad parent is recorded too, but changes nothing
+            if fs.resolve_node(new_path.clone()).is_err() {
+                continue;
+            }
+            let nlink = |path: &PathName| {
+                let (parent, _) = path.split();
+                if parent.is_root() {
+                    None
+                } else {
+                    fs.dir_nlink(parent).ok()
+                }
+            };
+            expected.push(ExpectedNlink {
+                index: index as u32,
+                old_parent: nlink(old_path),
+                new_parent: nlink(new_path),
+            });
+        }
+        expected
+    }
+
+    /// Indices of renames after which parent link counts differ from model.
+    pub fn violations(&self, expected: &[ExpectedNlink], trace: &Trace) -> Vec<u32> {
+        let differs = |expected: Option<u64>, actual: Option<&str>| match (
+            expected,
+            actual.and_then(|nlink| nlink.parse::<u64>().ok()),
+        ) {
+            (Some(expected), Some(actual)) => actual != 1 && actual != expected,
+            _ => false,
+        };
+        expected
+            .iter()
+            .filter(|expected| {
+                trace
+                    .rows
+                    .iter()
+                    .find(|row| row.index() == expected.index && row.command() == "RENAME")
+                    .is_some_and(|row| {
+                        differs(expected.old_parent, row.field("old_parent_nlink"))
+                            || differs(expected.new_parent, row.field("new_parent_nlink"))
+                    })
+            })
+            .map(|expected| expected.index)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workload() -> Workload {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.mkdir("/bar".into(), vec![]).unwrap();
+        fs.mkdir("/foo/baz".into(), vec![]).unwrap();
+        fs.rename("/foo/baz".into(), "/bar/baz".into()).unwrap();
+        fs.rename("/bar/baz".into(), "/baz".into()).unwrap();
+        fs.rename_to_bad_parent("/baz".into(), "/boo/baz".into(), vec![])
+            .unwrap();
+        fs.recording
+    }
+
+    fn trace(renames: &[(u32, &str)]) -> Trace {
+        let mut trace = "Index,Command,ReturnCode,Errno,Extra\n".to_owned();
+        for (index, extra) in renames {
+            trace.push_str(&format!("{},RENAME,0,Success(0),{}\n", index, extra));
+        }
+        Trace::try_parse(trace).unwrap()
+    }
+
+    #[test]
+    fn test_expected_parent_nlink() {
+        let objective = DirNlinkObjective::new();
+        assert_eq!(
+            vec![
+                ExpectedNlink {
+                    index: 3,
+                    old_parent: Some(2),
+                    new_parent: Some(3),
+                },
+                ExpectedNlink {
+                    index: 4,
+                    old_parent: Some(2),
+                    new_parent: None,
+                },
+            ],
+            objective.expected(&workload())
+        );
+    }
+
+    #[test]
+    fn test_nlink_mismatch() {
+        let objective = DirNlinkObjective::new();
+        let input = workload();
+        let ok = trace(&[
+            (3, "old_parent_nlink=2 new_parent_nlink=3"),
+            (4, "old_parent_nlink=2 new_parent_nlink=5"),
+        ]);
+        let not_counted = trace(&[(3, "old_parent_nlink=1 new_parent_nlink=1")]);
+        let miscounted = trace(&[
+            (3, "old_parent_nlink=3 new_parent_nlink=3"),
+            (4, "old_parent_nlink=2 new_parent_nlink=5"),
+        ]);
+        let expected = objective.expected(&input);
+        assert!(objective.violations(&expected, &ok).is_empty());
+        assert!(objective.violations(&expected, &not_counted).is_empty());
+        assert_eq!(vec![3], objective.violations(&expected, &miscounted));
+        assert!(objective.is_interesting(&input, &ok, &miscounted));
+        assert!(!objective.is_interesting(&input, &ok, &not_counted));
+    }
+}
@@ -0,0 +1,114 @@
+use log::debug;
+
+use crate::abstract_fs::trace::Trace;
+
+/// Compares targets returned by `READLINK` on both filesystems.
+///
+/// Target is stored as given to `symlink`, so any normalization (e.g. of trailing or doubled slashes)
+/// is a difference even if both targets resolve to the same node.
+#[derive(Default)]
+pub struct ReadlinkObjective {
+    /// Set if `target` is known to differ between filesystems, then nothing is compared.
+    ignored: bool,
+}
+
+impl ReadlinkObjective {
+    pub fn new() -> Self {
+        Self { ignored: false }
+    }
+
+    pub fn with_ignored_fields(mut self, ignored_fields: &[String]) -> Self {
+        self.ignored = ignored_fields.iter().any(|field| field == "target");
+        self
+    }
+
+    pub fn is_interesting(&self, fst_trace: &Trace, snd_trace: &Trace) -> bool {
+        debug!("do readlink objective");
+        let violations = self.violations(fst_trace, snd_trace);
+        if !violations.is_empty() {
+            debug!("symlink target differs at operations {:?}", violations);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Indices of `READLINK` operations that succeeded on both filesystems with different targets.
+    pub fn violations(&self, fst_trace: &Trace, snd_trace: &Trace) -> Vec<u32> {
+        if self.ignored {
+            return vec![];
+        }
+        fst_trace
+            .rows
+            .iter()
+            .filter_map(|fst| {
+                let snd = snd_trace
+                    .rows
+                    .iter()
+                    .find(|snd| snd.index() == fst.index())?;
+                (fst.readlink_target()? != snd.readlink_target()?).then_some(fst.index())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace(rows: &[&str]) -> Trace {
+        let mut trace = "Index,Command,ReturnCode,Errno,Extra\n".to_owned();
+        for row in rows {
+            trace.push_str(&format!("{}\n", row));
+        }
+        Trace::try_parse(trace).unwrap()
+    }
+
+    #[test]
+    fn test_same_target() {
+        let objective = ReadlinkObjective::new();
+        let fst = trace(&[
+            "0,SYMLINK,0,Success(0),",
+            "1,READLINK,0,Success(0),target=../foo/",
+            "2,READLINK,-1,Invalid argument(22),",
+        ]);
+        let snd = trace(&[
+            "0,SYMLINK,0,Success(0),",
+            "1,READLINK,0,Success(0),target=../foo/",
+            "2,READLINK,-1,Invalid argument(22),",
+        ]);
+        assert!(objective.violations(&fst, &snd).is_empty());
+        assert!(!objective.is_interesting(&fst, &snd));
+    }
+
+    #[test]
+    fn test_different_target() {
+        let objective = ReadlinkObjective::new();
+        let fst = trace(&[
+            "0,SYMLINK,0,Success(0),",
+            "1,READLINK,0,Success(0),target=../foo/",
+            "2,READLINK,0,Success(0),target=//foo",
+            "3,READLINK,0,Success(0),target=bar",
+        ]);
+        // trailing and doubled slashes normalized
+        let snd = trace(&[
+            "0,SYMLINK,0,Success(0),",
+            "1,READLINK,0,Success(0),target=../foo",
+            "2,READLINK,0,Success(0),target=/foo",
+            "3,READLINK,0,Success(0),target=bar",
+        ]);
+        assert_eq!(vec![1, 2], objective.violations(&fst, &snd));
+        assert!(objective.is_interesting(&fst, &snd));
+        let objective = ReadlinkObjective::new().with_ignored_fields(&["target".to_owned()]);
+        assert!(!objective.is_interesting(&fst, &snd));
+    }
+
+    #[test]
+    fn test_failed_on_one_side() {
+        // left to trace objective, it compares errors
+        let objective = ReadlinkObjective::new();
+        let fst = trace(&["0,READLINK,0,Success(0),target=foo"]);
+        let snd = trace(&["0,READLINK,-1,No such file or directory(2),"]);
+        assert!(!objective.is_interesting(&fst, &snd));
+    }
+}
@@ -0,0 +1,67 @@
+use log::debug;
+
+use crate::abstract_fs::trace::Trace;
+
+/// Flags executor killed by fatal signal (e.g. `SIGBUS`) on one filesystem only.
+///
+/// Executor reports signal in trace with index of operation it died at,
+/// death on both filesystems points at executor itself rather than filesystem.
+pub struct SignalObjective {}
+
+impl SignalObjective {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn is_interesting(&self, fst_trace: &Trace, snd_trace: &Trace) -> bool {
+        debug!("do signal objective");
+        match (fst_trace.signal(), snd_trace.signal()) {
+            (Some(row), None) | (None, Some(row)) => {
+                debug!(
+                    "executor killed by {} at operation {} on one filesystem only",
+                    row.signal().unwrap_or_default(),
+                    row.index()
+                );
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace(rows: &[&str]) -> Trace {
+        let mut trace = "Index,Command,ReturnCode,Errno,Extra\n".to_owned();
+        for row in rows {
+            trace.push_str(&format!("{}\n", row));
+        }
+        Trace::try_parse(trace).unwrap()
+    }
+
+    #[test]
+    fn test_signal_on_one_side() {
+        let objective = SignalObjective::new();
+        let completed = trace(&["0,MKDIR,0,Success(0),", "1,CREATE,3,Success(0),"]);
+        let killed = trace(&[
+            "0,MKDIR,0,Success(0),",
+            "1,SIGNAL,-1,Success(0),signal=SIGSEGV",
+        ]);
+        assert!(objective.is_interesting(&completed, &killed));
+        assert!(objective.is_interesting(&killed, &completed));
+        assert!(!objective.is_interesting(&completed, &completed));
+    }
+
+    #[test]
+    fn test_signal_on_both_sides() {
+        let objective = SignalObjective::new();
+        let fst = trace(&["0,SIGNAL,-1,Success(0),signal=SIGBUS"]);
+        let snd = trace(&[
+            "0,MKDIR,0,Success(0),",
+            "1,SIGNAL,-1,Success(0),signal=SIGSEGV",
+        ]);
+        assert!(!objective.is_interesting(&fst, &snd));
+    }
+}
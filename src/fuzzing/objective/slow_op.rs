@@ -0,0 +1,75 @@
+use log::debug;
+
+use crate::abstract_fs::trace::Trace;
+
+/// Flags operations that took longer than per-operation timeout, even if the whole run finished in time.
+///
+/// Some operations (e.g. huge write) legitimately take long, so timeout should be set well above them.
+pub struct SlowOpObjective {
+    /// In milliseconds.
+    per_op_timeout: u64,
+}
+
+impl SlowOpObjective {
+    pub fn new(per_op_timeout: u64) -> Self {
+        Self { per_op_timeout }
+    }
+
+    pub fn is_interesting(&self, fst_trace: &Trace, snd_trace: &Trace) -> bool {
+        debug!("do slow operation objective");
+        let fst_slow = self.slow_operations(fst_trace);
+        let snd_slow = self.slow_operations(snd_trace);
+        if !fst_slow.is_empty() || !snd_slow.is_empty() {
+            debug!(
+                "operations {:?} (first) and {:?} (second) took longer than {}ms",
+                fst_slow, snd_slow, self.per_op_timeout
+            );
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Indices of operations that exceeded timeout, rows without duration are skipped.
+    pub fn slow_operations(&self, trace: &Trace) -> Vec<u32> {
+        trace
+            .rows
+            .iter()
+            .filter(|row| {
+                row.duration()
+                    .is_some_and(|duration| duration > self.per_op_timeout * 1000)
+            })
+            .map(|row| row.index())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace(durations: &[Option<u64>]) -> Trace {
+        let mut trace = "Index,Command,ReturnCode,Errno,Extra,Duration\n".to_owned();
+        for (i, duration) in durations.iter().enumerate() {
+            match duration {
+                Some(duration) => {
+                    trace.push_str(&format!("{},RENAME,0,Success(0),,{}\n", i, duration))
+                }
+                None => trace.push_str(&format!("{},RENAME,0,Success(0),\n", i)),
+            }
+        }
+        Trace::try_parse(trace).unwrap()
+    }
+
+    #[test]
+    fn test_slow_operations() {
+        let objective = SlowOpObjective::new(100);
+        let fast = trace(&[Some(10), Some(100_000), None]);
+        let slow = trace(&[Some(10), Some(100_001), Some(5_000_000)]);
+        assert!(objective.slow_operations(&fast).is_empty());
+        assert_eq!(vec![1, 2], objective.slow_operations(&slow));
+        assert!(objective.is_interesting(&fast, &slow));
+        assert!(objective.is_interesting(&slow, &fast));
+        assert!(!objective.is_interesting(&fast, &fast));
+    }
+}
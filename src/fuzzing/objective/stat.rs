@@ -0,0 +1,156 @@
+use log::debug;
+
+use crate::abstract_fs::trace::{Stat, Trace};
+
+use super::statfs::within_tolerance;
+
+/// Compares attributes reported by `STAT` operations on both filesystems in the middle of run.
+///
+/// Mode is always compared, size and link count only for non-directories (they depend on directory layout).
+/// Allocated blocks of regular files depend on layout too, so they may differ by `blocks_tolerance` share
+/// of the larger one, and are not compared if it is not set.
+pub struct StatObjective {
+    blocks_tolerance: Option<f64>,
+    /// Attributes known to differ between filesystems (e.g. `st_blocks`), they are not compared.
+    ignored_fields: Vec<String>,
+}
+
+impl StatObjective {
+    pub fn new(blocks_tolerance: Option<f64>) -> Self {
+        Self {
+            blocks_tolerance,
+            ignored_fields: vec![],
+        }
+    }
+
+    pub fn with_ignored_fields(mut self, ignored_fields: Vec<String>) -> Self {
+        self.ignored_fields = ignored_fields;
+        self
+    }
+
+    pub fn is_interesting(&self, fst_trace: &Trace, snd_trace: &Trace) -> bool {
+        debug!("do stat objective");
+        let violations = self.violations(fst_trace, snd_trace);
+        if !violations.is_empty() {
+            debug!("stat differs at operations {:?}", violations);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Indices of `STAT` operations that succeeded on both filesystems with different attributes.
+    pub fn violations(&self, fst_trace: &Trace, snd_trace: &Trace) -> Vec<u32> {
+        fst_trace
+            .rows
+            .iter()
+            .filter_map(|fst| {
+                let snd = snd_trace
+                    .rows
+                    .iter()
+                    .find(|snd| snd.index() == fst.index())?;
+                (!self.same(&fst.stat()?, &snd.stat()?)).then_some(fst.index())
+            })
+            .collect()
+    }
+
+    fn same(&self, fst: &Stat, snd: &Stat) -> bool {
+        if fst.mode != snd.mode && !self.ignored("st_mode") {
+            return false;
+        }
+        if fst.is_dir() {
+            return true;
+        }
+        (fst.size == snd.size || self.ignored("st_size"))
+            && (fst.nlink == snd.nlink || self.ignored("st_nlink"))
+            && (self.ignored("st_blocks")
+                || self.blocks_tolerance.is_none_or(|tolerance| {
+                    within_tolerance(fst.blocks as i64, snd.blocks as i64, tolerance)
+                }))
+    }
+
+    fn ignored(&self, key: &str) -> bool {
+        self.ignored_fields.iter().any(|field| field == key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace(stats: &[(u32, &str)]) -> Trace {
+        let mut trace = "Index,Command,ReturnCode,Errno,Extra\n".to_owned();
+        for (index, extra) in stats {
+            trace.push_str(&format!("{},STAT,0,Success(0),{}\n", index, extra));
+        }
+        Trace::try_parse(trace).unwrap()
+    }
+
+    #[test]
+    fn test_same_stat() {
+        let objective = StatObjective::new(Some(0.5));
+        let fst = trace(&[
+            (1, "st_size=4096 st_blocks=8 st_mode=100644 st_nlink=1"),
+            (3, "st_size=4096 st_blocks=8 st_mode=40755 st_nlink=3"),
+        ]);
+        let snd = trace(&[
+            (1, "st_size=4096 st_blocks=12 st_mode=100644 st_nlink=1"),
+            (3, "st_size=60 st_blocks=0 st_mode=40755 st_nlink=1"),
+        ]);
+        assert!(objective.violations(&fst, &snd).is_empty());
+        assert!(!objective.is_interesting(&fst, &snd));
+    }
+
+    #[test]
+    fn test_different_stat() {
+        let objective = StatObjective::new(Some(0.5));
+        let fst = trace(&[
+            (1, "st_size=4096 st_blocks=8 st_mode=100644 st_nlink=1"),
+            (2, "st_size=0 st_blocks=0 st_mode=100644 st_nlink=2"),
+            (3, "st_size=4096 st_blocks=8 st_mode=40755 st_nlink=2"),
+            // sparse write
+            (4, "st_size=1048577 st_blocks=8 st_mode=100644 st_nlink=1"),
+        ]);
+        let snd = trace(&[
+            (1, "st_size=4095 st_blocks=8 st_mode=100644 st_nlink=1"),
+            (2, "st_size=0 st_blocks=0 st_mode=100644 st_nlink=1"),
+            (3, "st_size=4096 st_blocks=8 st_mode=40700 st_nlink=2"),
+            (
+                4,
+                "st_size=1048577 st_blocks=2056 st_mode=100644 st_nlink=1",
+            ),
+        ]);
+        assert_eq!(vec![1, 2, 3, 4], objective.violations(&fst, &snd));
+        assert_eq!(
+            vec![1, 2, 3],
+            StatObjective::new(None).violations(&fst, &snd)
+        );
+    }
+
+    #[test]
+    fn test_ignored_fields() {
+        let objective =
+            StatObjective::new(Some(0.5)).with_ignored_fields(vec!["st_blocks".to_owned()]);
+        let fst = trace(&[
+            (1, "st_size=4096 st_blocks=8 st_mode=100644 st_nlink=1"),
+            (2, "st_size=4096 st_blocks=8 st_mode=100644 st_nlink=1"),
+        ]);
+        let snd = trace(&[
+            (1, "st_size=4096 st_blocks=2056 st_mode=100644 st_nlink=1"),
+            (2, "st_size=4095 st_blocks=2056 st_mode=100644 st_nlink=1"),
+        ]);
+        assert_eq!(vec![2], objective.violations(&fst, &snd));
+    }
+
+    #[test]
+    fn test_failed_stat_skipped() {
+        let objective = StatObjective::new(None);
+        let fst = trace(&[(0, "st_size=1 st_blocks=8 st_mode=100644 st_nlink=1")]);
+        let snd = Trace::try_parse(
+            "Index,Command,ReturnCode,Errno,Extra\n0,STAT,-1,No such file or directory(2),\n"
+                .to_owned(),
+        )
+        .unwrap();
+        assert!(objective.violations(&fst, &snd).is_empty());
+    }
+}
@@ -0,0 +1,122 @@
+use std::{fmt::Display, path::Path};
+
+use log::debug;
+
+use crate::fuzzing::observer::statfs::{FsUsage, StatfsObserver};
+
+/// Counter that changed differently on two filesystems.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct StatfsDiff {
+    pub field: &'static str,
+    pub fst: i64,
+    pub snd: i64,
+}
+
+impl Display for StatfsDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} used: {} vs {}", self.field, self.fst, self.snd)
+    }
+}
+
+/// Compares space and inodes used by workload on both filesystems.
+///
+/// Filesystems lay out data differently, so usage may differ by `tolerance` share of the larger one.
+pub struct StatfsObjective {
+    pub fst_fs: StatfsObserver,
+    pub snd_fs: StatfsObserver,
+    tolerance: f64,
+}
+
+impl StatfsObjective {
+    pub fn new(fst_fs_dir: Box<Path>, snd_fs_dir: Box<Path>, tolerance: f64) -> Self {
+        Self {
+            fst_fs: StatfsObserver::new(fst_fs_dir),
+            snd_fs: StatfsObserver::new(snd_fs_dir),
+            tolerance,
+        }
+    }
+
+    pub fn is_interesting(&self) -> bool {
+        debug!("do statfs objective");
+        !self.get_diff().is_empty()
+    }
+
+    pub fn get_diff(&self) -> Vec<StatfsDiff> {
+        diff(&self.fst_fs.usage, &self.snd_fs.usage, self.tolerance)
+    }
+}
+
+/// Counters that differ by more than `tolerance`.
+pub fn diff(fst: &FsUsage, snd: &FsUsage, tolerance: f64) -> Vec<StatfsDiff> {
+    let mut diff = vec![];
+    if !within_tolerance(fst.bytes, snd.bytes, tolerance) {
+        diff.push(StatfsDiff {
+            field: "bytes",
+            fst: fst.bytes,
+            snd: snd.bytes,
+        });
+    }
+    if let (Some(fst_files), Some(snd_files)) = (fst.files, snd.files) {
+        if !within_tolerance(fst_files, snd_files, tolerance) {
+            diff.push(StatfsDiff {
+                field: "inodes",
+                fst: fst_files,
+                snd: snd_files,
+            });
+        }
+    }
+    diff
+}
+
+/// Values differ by at most `tolerance` share of the larger one.
+pub fn within_tolerance(fst: i64, snd: i64, tolerance: f64) -> bool {
+    let largest = fst.unsigned_abs().max(snd.unsigned_abs());
+    fst.abs_diff(snd) as f64 <= tolerance * largest as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_within_tolerance() {
+        assert!(within_tolerance(0, 0, 0.0));
+        assert!(within_tolerance(100, 100, 0.0));
+        assert!(!within_tolerance(100, 101, 0.0));
+        assert!(within_tolerance(100, 110, 0.1));
+        assert!(!within_tolerance(100, 112, 0.1));
+        assert!(within_tolerance(-100, -90, 0.1));
+        assert!(!within_tolerance(-10, 10, 0.5));
+        assert!(within_tolerance(-10, 10, 2.0));
+    }
+
+    #[test]
+    fn test_diff() {
+        let fst = FsUsage {
+            bytes: 40960,
+            files: Some(10),
+        };
+        let snd = FsUsage {
+            bytes: 45056,
+            files: Some(3),
+        };
+        assert_eq!(
+            vec![StatfsDiff {
+                field: "inodes",
+                fst: 10,
+                snd: 3,
+            }],
+            diff(&fst, &snd, 0.5)
+        );
+        assert_eq!(2, diff(&fst, &snd, 0.0).len());
+        let snd = FsUsage {
+            bytes: 45056,
+            files: None,
+        };
+        assert!(diff(&fst, &snd, 0.5).is_empty());
+        assert_eq!(
+            "bytes used: 40960 vs 45056",
+            diff(&fst, &snd, 0.0)[0].to_string()
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use log::debug;
+
+use crate::{
+    abstract_fs::{fs::AbstractFS, operation::Operation, pathname::PathName, workload::Workload},
+    hasher::hasher::FileInfo,
+};
+
+/// Compares access and modification times of files and directories with times model knows
+/// (set explicitly by `UTIMENS` and not updated by filesystem since).
+///
+/// Unlike hash objective times are compared exactly, so clamping or truncation is found even if
+/// both filesystems do the same.
+/// Times may be lost with unsynced metadata at crash point, so workloads with crash points are not checked.
+/// In persistent mode workload runs in its own subdirectory, so model paths are not found and nothing is checked.
+/// Hasher reads directory before it takes its metadata, which updates atime (unless mounted with `noatime`),
+/// so only modification time of directories is compared.
+#[derive(Default)]
+pub struct TimesObjective {}
+
+impl TimesObjective {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn is_interesting(&self, input: &Workload, fst: &[FileInfo], snd: &[FileInfo]) -> bool {
+        debug!("do times objective");
+        let expected = self.expected(input);
+        let fst_violations = self.violations(&expected, fst);
+        let snd_violations = self.violations(&expected, snd);
+        if !fst_violations.is_empty() || !snd_violations.is_empty() {
+            debug!(
+                "times differ from model at {:?} (first) and {:?} (second)",
+                fst_violations, snd_violations
+            );
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Paths with times known to model (access and modification time), `None` if one is unknown.
+    pub fn expected(&self, input: &Workload) -> Vec<(PathName, Option<i64>, Option<i64>)> {
+        if input.ops.contains(&Operation::CRASHPOINT) {
+            return vec![];
+        }
+        let mut fs = AbstractFS::new();
+        if fs.replay(input).is_err() {
+            return vec![];
+        }
+        fs.alive_paths()
+            .into_iter()
+            .filter_map(|path| {
+                let times = fs.times(path.clone()).ok()??;
+                (times.atime.is_some() || times.mtime.is_some()).then_some((
+                    path,
+                    times.atime,
+                    times.mtime,
+                ))
+            })
+            .collect()
+    }
+
+    /// Paths which times differ from ones model knows.
+    pub fn violations(
+        &self,
+        expected: &[(PathName, Option<i64>, Option<i64>)],
+        content: &[FileInfo],
+    ) -> Vec<PathName> {
+        expected
+            .iter()
+            .filter(|(path, atime, mtime)| {
+                let rel_path = path.to_string();
+                let rel_path = rel_path.trim_start_matches('/');
+                content
+                    .iter()
+                    .find(|info| info.rel_path() == rel_path)
+                    .is_some_and(|info| {
+                        let (actual_atime, actual_mtime) = info.times();
+                        (atime.is_some_and(|atime| atime != actual_atime) && !info.is_dir())
+                            || mtime.is_some_and(|mtime| mtime != actual_mtime)
+                    })
+            })
+            .map(|(path, _, _)| path.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env,
+        fs::{self, File, FileTimes},
+        time::{Duration, UNIX_EPOCH},
+    };
+
+    use crate::hasher::hasher::{calc_dir_hash, HasherOptions};
+    use crate::mount::{ext4::Ext4, mount::FileSystemMount};
+
+    use super::*;
+
+    #[test]
+    fn test_times_compared_with_model() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.create("/foo/bar".into(), vec![]).unwrap();
+        fs.utimens("/foo/bar".into(), 0, 4294967296).unwrap();
+        fs.create("/baz".into(), vec![]).unwrap();
+        let input = fs.recording;
+        let objective = TimesObjective::new();
+        let expected = objective.expected(&input);
+        assert_eq!(
+            vec![(PathName::from("/foo/bar"), Some(0), Some(4294967296))],
+            expected
+        );
+
+        let temp_dir = env::temp_dir().join("DIFFuzzer-times-objective-test");
+        fs::remove_dir_all(temp_dir.as_path()).unwrap_or(());
+        fs::create_dir_all(temp_dir.join("foo")).unwrap();
+        fs::write(temp_dir.join("foo").join("bar"), "").unwrap();
+        fs::write(temp_dir.join("baz"), "").unwrap();
+        let set_mtime = |secs: u64| {
+            File::options()
+                .write(true)
+                .open(temp_dir.join("foo").join("bar"))
+                .unwrap()
+                .set_times(
+                    FileTimes::new()
+                        .set_accessed(UNIX_EPOCH)
+                        .set_modified(UNIX_EPOCH + Duration::from_secs(secs)),
+                )
+                .unwrap();
+        };
+        let content = |options: &HasherOptions| {
+            calc_dir_hash(&temp_dir, &Ext4::new().get_internal_dirs(), options).1
+        };
+        let options = HasherOptions::default();
+
+        set_mtime(4294967296);
+        assert!(objective
+            .violations(&expected, &content(&options))
+            .is_empty());
+        // 32-bit time overflowed
+        set_mtime(0);
+        assert_eq!(
+            vec![PathName::from("/foo/bar")],
+            objective.violations(&expected, &content(&options))
+        );
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn test_dir_atime_not_compared() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.utimens("/foo".into(), 0, 0).unwrap();
+        let objective = TimesObjective::new();
+        let expected = objective.expected(&fs.recording);
+
+        let temp_dir = env::temp_dir().join("DIFFuzzer-times-dir-test");
+        fs::remove_dir_all(temp_dir.as_path()).unwrap_or(());
+        fs::create_dir_all(temp_dir.join("foo")).unwrap();
+        let set_times = |atime: u64, mtime: u64| {
+            File::open(temp_dir.join("foo"))
+                .unwrap()
+                .set_times(
+                    FileTimes::new()
+                        .set_accessed(UNIX_EPOCH + Duration::from_secs(atime))
+                        .set_modified(UNIX_EPOCH + Duration::from_secs(mtime)),
+                )
+                .unwrap();
+        };
+        let content = || {
+            calc_dir_hash(
+                &temp_dir,
+                &Ext4::new().get_internal_dirs(),
+                &HasherOptions::default(),
+            )
+            .1
+        };
+
+        // atime updated by reading directory
+        set_times(100, 0);
+        assert!(objective.violations(&expected, &content()).is_empty());
+        set_times(0, 100);
+        assert_eq!(
+            vec![PathName::from("/foo")],
+            objective.violations(&expected, &content())
+        );
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn test_crashpoint_not_checked() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        fs.utimens("/foo".into(), 0, 0).unwrap();
+        fs.crashpoint().unwrap();
+        assert!(TimesObjective::new().expected(&fs.recording).is_empty());
+    }
+}
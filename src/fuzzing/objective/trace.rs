@@ -2,17 +2,238 @@ use log::debug;
 
 use crate::abstract_fs::trace::Trace;
 
-pub struct TraceObjective {}
+pub struct TraceObjective {
+    /// Keys of extra fields known to differ between filesystems, see [`Trace::same_as_ignoring`].
+    ignored_fields: Vec<String>,
+}
 
 impl TraceObjective {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            ignored_fields: vec![],
+        }
+    }
+
+    pub fn with_ignored_fields(mut self, ignored_fields: Vec<String>) -> Self {
+        self.ignored_fields = ignored_fields;
+        self
     }
 }
 
 impl TraceObjective {
     pub fn is_interesting(&mut self, fst_trace: &Trace, snd_trace: &Trace) -> anyhow::Result<bool> {
         debug!("do trace objective");
-        Ok(!fst_trace.same_as(&snd_trace))
+        let fst_failures = fst_trace.write_verification_failures();
+        let snd_failures = snd_trace.write_verification_failures();
+        if !fst_failures.is_empty() || !snd_failures.is_empty() {
+            debug!(
+                "write verification failed at operations {:?} (first) and {:?} (second)",
+                fst_failures, snd_failures
+            );
+            return Ok(true);
+        }
+        let fst_reuse = fst_trace.inode_reuse();
+        let snd_reuse = snd_trace.inode_reuse();
+        if !fst_reuse.unsafe_reused.is_empty() || !snd_reuse.unsafe_reused.is_empty() {
+            debug!(
+                "inode of open file reused at operations {:?} (first) and {:?} (second)",
+                fst_reuse.unsafe_reused, snd_reuse.unsafe_reused
+            );
+            return Ok(true);
+        }
+        if fst_reuse.reused != snd_reuse.reused {
+            debug!(
+                "inode reuse differs (benign): {:?} (first) and {:?} (second)",
+                fst_reuse.reused, snd_reuse.reused
+            );
+        }
+        Ok(!fst_trace.same_as_ignoring(snd_trace, &self.ignored_fields))
+    }
+}
+
+/// Index of the first operation with different results on filesystems,
+/// or of the first operation missing in one of traces (e.g. executor crashed).
+///
+/// Rows are compared the same way trace objective does, so equally valid errnos are considered the same
+/// and extra fields with key in `ignored` (known to differ for the pair) are not counted.
+pub fn first_divergence(fst_trace: &Trace, snd_trace: &Trace, ignored: &[String]) -> Option<u32> {
+    let position = fst_trace.first_difference(snd_trace, ignored)?;
+    fst_trace
+        .rows
+        .get(position)
+        .or(snd_trace.rows.get(position))
+        .map(|row| row.index())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_verification_failure_is_interesting() {
+        let ok = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,      WRITE,       100,Success(0),verify=ok
+"#
+        .trim();
+        let failed = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,      WRITE,       100,Success(0),verify=mismatch@0
+"#
+        .trim();
+        let ok = Trace::try_parse(ok.to_owned()).unwrap();
+        let failed = Trace::try_parse(failed.to_owned()).unwrap();
+        let mut objective = TraceObjective::new();
+        assert!(!objective.is_interesting(&ok, &ok).unwrap());
+        assert!(objective.is_interesting(&ok, &failed).unwrap());
+        assert!(objective.is_interesting(&failed, &failed).unwrap());
+    }
+
+    #[test]
+    fn test_unsafe_inode_reuse_is_interesting() {
+        let never = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,     CREATE,         3,Success(0),ino=12
+    1,       OPEN,         3,Success(0),ino=12
+    2,     UNLINK,         0,Success(0),
+    3,     CREATE,         4,Success(0),ino=13
+"#
+        .trim();
+        let while_open = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,     CREATE,         3,Success(0),ino=257
+    1,       OPEN,         3,Success(0),ino=257
+    2,     UNLINK,         0,Success(0),
+    3,     CREATE,         4,Success(0),ino=257
+"#
+        .trim();
+        let after_close = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,     CREATE,         3,Success(0),ino=257
+    1,       OPEN,         3,Success(0),ino=257
+    2,      CLOSE,         0,Success(0),ino=257
+    3,     CREATE,         3,Success(0),ino=257
+"#
+        .trim();
+        let after_close_other = r#"
+Index,Command,ReturnCode,Errno,Extra
+    0,     CREATE,         3,Success(0),ino=12
+    1,       OPEN,         3,Success(0),ino=12
+    2,      CLOSE,         0,Success(0),ino=12
+    3,     CREATE,         3,Success(0),ino=13
+"#
+        .trim();
+        let never = Trace::try_parse(never.to_owned()).unwrap();
+        let while_open = Trace::try_parse(while_open.to_owned()).unwrap();
+        let after_close = Trace::try_parse(after_close.to_owned()).unwrap();
+        let after_close_other = Trace::try_parse(after_close_other.to_owned()).unwrap();
+        let mut objective = TraceObjective::new();
+        assert!(objective.is_interesting(&never, &while_open).unwrap());
+        assert!(!objective
+            .is_interesting(&after_close, &after_close_other)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_first_divergence() {
+        let fst = Trace::try_parse(
+            "Index,Command,ReturnCode,Errno,Extra
+    0,      MKDIR,         0,Success(0),
+    1,     CREATE,         3,Success(0),ino=12
+    2,     RENAME,        -1,Directory not empty(39),
+    3,     REMOVE,         0,Success(0),
+"
+            .to_owned(),
+        )
+        .unwrap();
+        let snd = Trace::try_parse(
+            "Index,Command,ReturnCode,Errno,Extra
+    0,      MKDIR,         0,Success(0),
+    1,     CREATE,         3,Success(0),ino=257
+    2,     RENAME,        -1,File exists(17),
+    3,     REMOVE,        -1,No such file or directory(2),
+"
+            .to_owned(),
+        )
+        .unwrap();
+        // inode numbers and equally valid errnos do not count
+        assert_eq!(Some(3), first_divergence(&fst, &snd, &[]));
+        assert_eq!(None, first_divergence(&fst, &fst, &[]));
+
+        let died = Trace::try_parse(
+            "Index,Command,ReturnCode,Errno,Extra
+    0,      MKDIR,         0,Success(0),
+"
+            .to_owned(),
+        )
+        .unwrap();
+        assert_eq!(Some(1), first_divergence(&fst, &died, &[]));
+        assert_eq!(Some(1), first_divergence(&died, &snd, &[]));
+
+        let lseek = |offset: u32| {
+            Trace::try_parse(format!(
+                "Index,Command,ReturnCode,Errno,Extra\n0,LSEEK,0,Success(0),offset={}\n",
+                offset
+            ))
+            .unwrap()
+        };
+        assert_eq!(Some(0), first_divergence(&lseek(0), &lseek(4096), &[]));
+        assert_eq!(
+            None,
+            first_divergence(&lseek(0), &lseek(4096), &["offset".to_owned()])
+        );
+    }
+
+    #[test]
+    fn test_ignored_fields() {
+        let trace = |read: &str, entries: &str| {
+            Trace::try_parse(format!(
+                "Index,Command,ReturnCode,Errno,Extra
+    0,     CREATE,         3,Success(0),
+    1,       READ,{}
+    2,    LISTDIR,         1,Success(0),entries={}
+",
+                read, entries
+            ))
+            .unwrap()
+        };
+        let fst = trace("10,Success(0),hash=1f", "foo");
+        let other_hash = trace("10,Success(0),hash=2f", "foo");
+        let other_entries = trace("10,Success(0),hash=1f", "bar");
+        let failed = trace("-1,Input/output error(5),", "foo");
+        let mut objective = TraceObjective::new();
+        assert!(objective.is_interesting(&fst, &other_hash).unwrap());
+        assert!(objective.is_interesting(&fst, &other_entries).unwrap());
+        let mut objective = TraceObjective::new().with_ignored_fields(vec!["hash".to_owned()]);
+        assert!(!objective.is_interesting(&fst, &other_hash).unwrap());
+        assert!(objective.is_interesting(&fst, &other_entries).unwrap());
+        assert!(objective.is_interesting(&fst, &failed).unwrap());
+        let mut objective = TraceObjective::new().with_ignored_fields(vec!["entries".to_owned()]);
+        assert!(!objective.is_interesting(&fst, &other_entries).unwrap());
+        assert!(objective.is_interesting(&fst, &other_hash).unwrap());
+    }
+
+    #[test]
+    fn test_different_errno_is_interesting() {
+        let trace = |errno: &str| {
+            Trace::try_parse(format!(
+                "Index,Command,ReturnCode,Errno,Extra
+    0,      MKDIR,         0,Success(0),
+    1,      MKDIR,         0,Success(0),
+    2,     RENAME,        -1,{},
+",
+                errno
+            ))
+            .unwrap()
+        };
+        let einval = trace("Invalid argument(22)");
+        let enotempty = trace("Directory not empty(39)");
+        let eexist = trace("File exists(17)");
+        let mut objective = TraceObjective::new();
+        assert!(objective.is_interesting(&einval, &enotempty).unwrap());
+        assert!(objective.is_interesting(&eexist, &einval).unwrap());
+        assert!(!objective.is_interesting(&enotempty, &enotempty).unwrap());
+        // both are valid for non-empty target directory
+        assert!(!objective.is_interesting(&enotempty, &eexist).unwrap());
     }
 }
@@ -0,0 +1,83 @@
+use log::warn;
+
+pub mod statfs;
+
+/// Opinion of an observer about the last execution.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ObserverVerdict {
+    Conclusive,
+    /// Result can not be trusted (e.g. environmental noise), objectives must be skipped.
+    Inconclusive(String),
+}
+
+pub trait Observer {
+    /// Called right before workload is executed, filesystem is already mounted.
+    fn pre_exec(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Called after workload is executed, while filesystem is still mounted.
+    fn post_exec(&mut self) -> anyhow::Result<ObserverVerdict>;
+}
+
+/// Runs every observer (even if some already vetoed the run) and merges their verdicts.
+pub fn observe_all(observers: &mut [&mut dyn Observer]) -> anyhow::Result<ObserverVerdict> {
+    let mut verdict = ObserverVerdict::Conclusive;
+    for observer in observers.iter_mut() {
+        if let ObserverVerdict::Inconclusive(reason) = observer.post_exec()? {
+            warn!("run marked inconclusive: {}", reason);
+            if verdict == ObserverVerdict::Conclusive {
+                verdict = ObserverVerdict::Inconclusive(reason);
+            }
+        }
+    }
+    Ok(verdict)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Gives the same verdict every time, shared with tests of runner.
+    pub(crate) struct MockObserver {
+        verdict: ObserverVerdict,
+        calls: usize,
+    }
+
+    impl MockObserver {
+        pub(crate) fn new(verdict: ObserverVerdict) -> Self {
+            Self { verdict, calls: 0 }
+        }
+    }
+
+    impl Observer for MockObserver {
+        fn post_exec(&mut self) -> anyhow::Result<ObserverVerdict> {
+            self.calls += 1;
+            Ok(self.verdict.clone())
+        }
+    }
+
+    #[test]
+    fn test_all_conclusive() {
+        let mut fst = MockObserver::new(ObserverVerdict::Conclusive);
+        let mut snd = MockObserver::new(ObserverVerdict::Conclusive);
+        assert_eq!(
+            ObserverVerdict::Conclusive,
+            observe_all(&mut [&mut fst, &mut snd]).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_inconclusive_vetoes() {
+        let mut fst = MockObserver::new(ObserverVerdict::Inconclusive("oom".to_owned()));
+        let mut snd = MockObserver::new(ObserverVerdict::Inconclusive("mount".to_owned()));
+        let mut trd = MockObserver::new(ObserverVerdict::Conclusive);
+        assert_eq!(
+            ObserverVerdict::Inconclusive("oom".to_owned()),
+            observe_all(&mut [&mut fst, &mut snd, &mut trd]).unwrap()
+        );
+        assert_eq!(1, fst.calls);
+        assert_eq!(1, snd.calls);
+        assert_eq!(1, trd.calls);
+    }
+}
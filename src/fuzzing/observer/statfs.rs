@@ -0,0 +1,155 @@
+use std::{path::Path, process::Command};
+
+use anyhow::{bail, Context};
+
+use super::{Observer, ObserverVerdict};
+
+/// Filesystem-wide counters reported by `statfs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FsStats {
+    pub block_size: u64,
+    pub blocks: u64,
+    pub blocks_free: u64,
+    /// Zero on filesystems that allocate inodes dynamically (e.g. Btrfs).
+    pub files: u64,
+    pub files_free: u64,
+}
+
+impl FsStats {
+    pub fn used_bytes(&self) -> i64 {
+        ((self.blocks - self.blocks_free) * self.block_size) as i64
+    }
+
+    pub fn used_files(&self) -> i64 {
+        (self.files - self.files_free) as i64
+    }
+}
+
+/// How much space and inodes workload used, filled after execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FsUsage {
+    pub bytes: i64,
+    /// `None` if filesystem does not report inode counts.
+    pub files: Option<i64>,
+}
+
+/// Takes `statfs` of mounted filesystem before and after workload.
+pub struct StatfsObserver {
+    fs_dir: Box<Path>,
+    before: Option<FsStats>,
+    pub usage: FsUsage,
+}
+
+impl StatfsObserver {
+    pub fn new(fs_dir: Box<Path>) -> Self {
+        Self {
+            fs_dir,
+            before: None,
+            usage: FsUsage::default(),
+        }
+    }
+}
+
+impl Observer for StatfsObserver {
+    fn pre_exec(&mut self) -> anyhow::Result<()> {
+        self.before = Some(statfs(&self.fs_dir)?);
+        Ok(())
+    }
+
+    fn post_exec(&mut self) -> anyhow::Result<ObserverVerdict> {
+        let Some(before) = self.before.take() else {
+            bail!("statfs was not taken before execution");
+        };
+        let after = statfs(&self.fs_dir)?;
+        self.usage = usage(&before, &after);
+        Ok(ObserverVerdict::Conclusive)
+    }
+}
+
+pub fn usage(before: &FsStats, after: &FsStats) -> FsUsage {
+    FsUsage {
+        bytes: after.used_bytes() - before.used_bytes(),
+        files: (before.files > 0 && after.files > 0)
+            .then(|| after.used_files() - before.used_files()),
+    }
+}
+
+fn statfs(path: &Path) -> anyhow::Result<FsStats> {
+    let mut stat = Command::new("stat");
+    stat.arg("-f").arg("-c").arg("%S %b %f %c %d").arg(path);
+    let output = stat.output()?;
+    if !output.status.success() {
+        bail!(
+            "failed to get fs stats: {:?}\n{}",
+            stat,
+            String::from_utf8(output.stderr)
+                .with_context(|| format!("failed to read stderr (stat)"))?,
+        );
+    }
+    let stdout = String::from_utf8(output.stdout)
+        .with_context(|| format!("failed to read stdout (stat)"))?;
+    parse_statfs(&stdout)
+}
+
+fn parse_statfs(output: &str) -> anyhow::Result<FsStats> {
+    let parts: Vec<u64> = output
+        .split_whitespace()
+        .map(|part| {
+            part.parse()
+                .with_context(|| format!("failed to parse fs stat '{}'", part))
+        })
+        .collect::<anyhow::Result<_>>()?;
+    let [block_size, blocks, blocks_free, files, files_free] = parts[..] else {
+        bail!("unexpected fs stats output '{}'", output.trim());
+    };
+    Ok(FsStats {
+        block_size,
+        blocks,
+        blocks_free,
+        files,
+        files_free,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_statfs() {
+        assert_eq!(
+            FsStats {
+                block_size: 4096,
+                blocks: 1000,
+                blocks_free: 900,
+                files: 256,
+                files_free: 245,
+            },
+            parse_statfs("4096 1000 900 256 245\n").unwrap()
+        );
+        assert!(parse_statfs("4096 1000 900\n").is_err());
+        assert!(parse_statfs("4096 1000 900 256 x\n").is_err());
+    }
+
+    #[test]
+    fn test_usage() {
+        let before = parse_statfs("4096 1000 900 256 245").unwrap();
+        let after = parse_statfs("4096 1000 890 256 240").unwrap();
+        assert_eq!(
+            FsUsage {
+                bytes: 10 * 4096,
+                files: Some(5),
+            },
+            usage(&before, &after)
+        );
+        let before = parse_statfs("4096 1000 900 0 0").unwrap();
+        let after = parse_statfs("4096 1000 905 0 0").unwrap();
+        assert_eq!(
+            FsUsage {
+                bytes: -5 * 4096,
+                files: None,
+            },
+            usage(&before, &after)
+        );
+    }
+}
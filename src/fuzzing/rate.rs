@@ -0,0 +1,70 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Window of executions rate shown in stats.
+pub const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Rate of events over the last `window`, so that it reflects current speed rather than average since start.
+pub struct RollingRate {
+    window: Duration,
+    /// Total count of events at the moment, oldest first.
+    samples: VecDeque<(Instant, usize)>,
+}
+
+impl RollingRate {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Only the last sample older than window is kept, so rate covers the whole window.
+    pub fn record(&mut self, now: Instant, total: usize) {
+        self.samples.push_back((now, total));
+        while self
+            .samples
+            .get(1)
+            .is_some_and(|(time, _)| now.duration_since(*time) >= self.window)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Events per second between the oldest and the latest samples, zero if there are not enough of them.
+    pub fn per_sec(&self) -> f64 {
+        match (self.samples.front(), self.samples.back()) {
+            (Some((start, from)), Some((end, to))) if end > start => {
+                (to - from) as f64 / end.duration_since(*start).as_secs_f64()
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rolling_rate() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut rate = RollingRate::new(Duration::from_secs(10));
+        assert_eq!(0.0, rate.per_sec());
+        rate.record(at(0), 0);
+        assert_eq!(0.0, rate.per_sec());
+        rate.record(at(5), 50);
+        assert_eq!(10.0, rate.per_sec());
+        rate.record(at(10), 100);
+        assert_eq!(10.0, rate.per_sec());
+        // slowed down, samples before window are dropped
+        rate.record(at(15), 110);
+        assert_eq!(6.0, rate.per_sec());
+        rate.record(at(20), 120);
+        assert_eq!(2.0, rate.per_sec());
+        assert_eq!(3, rate.samples.len());
+    }
+}
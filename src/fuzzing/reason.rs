@@ -0,0 +1,474 @@
+use serde::Serialize;
+
+use crate::abstract_fs::{operation::Operation, trace::Trace};
+
+use super::objective::allowlist::{Allowlist, KnownDifference};
+
+use super::crash_dir::signature;
+
+/// Why testcase was saved, along with results of every operation on each filesystem.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Reason {
+    pub summary: String,
+    /// Option sets filesystems were created and mounted with, if they are picked at random.
+    pub mount_options: Vec<String>,
+    /// Pairs of filesystems with different results, if more than two were compared.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub diverged: Vec<DivergedPair>,
+    /// Operation results first differed at, if traces of the first two filesystems differ.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub divergence: Option<Divergence>,
+    /// Files with different contents or attributes, if hashes of the first two filesystems differ.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub differences: Vec<String>,
+    pub results: Vec<FsResults>,
+    /// Differences expected between compared filesystems, they are not counted when comparing traces.
+    #[serde(skip)]
+    pub known_differences: Vec<KnownDifference>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DivergedPair {
+    pub fst: String,
+    pub snd: String,
+    /// Objectives that detected divergence of the pair (e.g. `trace`, `statfs`).
+    pub detected_by: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Divergence {
+    pub index: u32,
+    pub command: String,
+    /// Operation as it is encoded in C test.
+    pub operation: String,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FsResults {
+    pub fs_name: String,
+    /// Missing if trace could not be read or parsed.
+    pub trace: Option<Trace>,
+}
+
+impl Reason {
+    pub fn new(summary: &str) -> Self {
+        Self {
+            summary: summary.to_owned(),
+            mount_options: vec![],
+            diverged: vec![],
+            divergence: None,
+            differences: vec![],
+            results: vec![],
+            known_differences: vec![],
+        }
+    }
+
+    pub fn with_known_differences(mut self, known_differences: &[KnownDifference]) -> Self {
+        self.known_differences = known_differences.to_vec();
+        self
+    }
+
+    pub fn with_results(mut self, fs_name: &str, trace: Option<Trace>) -> Self {
+        self.results.push(FsResults {
+            fs_name: fs_name.to_owned(),
+            trace,
+        });
+        self
+    }
+
+    pub fn with_mount_options(mut self, fs_name: &str, options: &str) -> Self {
+        self.mount_options
+            .push(format!("'{}': {}", fs_name, options));
+        self
+    }
+
+    pub fn with_diverged(mut self, pair: DivergedPair) -> Self {
+        self.diverged.push(pair);
+        self
+    }
+
+    pub fn with_divergence(mut self, index: u32, op: &Operation) -> Self {
+        self.divergence = Some(Divergence {
+            index,
+            command: format!("{:?}", op.kind()),
+            operation: op.encode_c(),
+        });
+        self
+    }
+
+    pub fn with_differences(mut self, differences: Vec<String>) -> Self {
+        self.differences.extend(differences);
+        self
+    }
+
+    /// Summary followed by a table of operation results for each filesystem.
+    pub fn render(&self) -> String {
+        let mut reason = format!("{}\n", self.summary);
+        for options in self.mount_options.iter() {
+            reason.push_str(&format!("{}\n", options));
+        }
+        for pair in self.diverged.iter() {
+            reason.push_str(&format!(
+                "'{}' and '{}' diverged ({})\n",
+                pair.fst,
+                pair.snd,
+                pair.detected_by.join(", ")
+            ));
+        }
+        if let Some(divergence) = &self.divergence {
+            reason.push_str(&format!(
+                "divergence first observed at operation {} ({}): {}\n",
+                divergence.index, divergence.command, divergence.operation
+            ));
+        }
+        for difference in self.differences.iter() {
+            reason.push_str(&format!("{}\n", difference));
+        }
+        for results in self.results.iter() {
+            reason.push_str(&format!("\n'{}':\n", results.fs_name));
+            match &results.trace {
+                Some(trace) => reason.push_str(&trace.render()),
+                None => reason.push_str("<no trace>\n"),
+            }
+        }
+        reason
+    }
+
+    /// Index of the first operation with different results, if there are exactly two traces.
+    pub fn first_difference(&self) -> Option<usize> {
+        let [fst, snd] = self.results.as_slice() else {
+            return None;
+        };
+        fst.trace
+            .as_ref()?
+            .first_difference(snd.trace.as_ref()?, &self.ignored_fields(fst, snd))
+    }
+
+    /// Signature shared by crashes that are likely caused by the same bug.
+    ///
+    /// Made of summary, differences of files (paths and attributes) and, for each pair of
+    /// compared filesystems, command of the first diverging operation and errors it returned.
+    /// Operation indices are not included, mutated inputs usually differ in them.
+    pub fn signature(&self) -> String {
+        let mut normalized = self.summary.clone();
+        for difference in self.differences.iter() {
+            normalized.push_str(&format!("\n{}", difference));
+        }
+        for pair in self.diverged.iter() {
+            normalized.push_str(&format!(
+                "\n{} {} {}",
+                pair.fst,
+                pair.snd,
+                pair.detected_by.join(" ")
+            ));
+        }
+        for (fst_results, snd_results) in self.compared_pairs() {
+            let (Some(fst), Some(snd)) = (&fst_results.trace, &snd_results.trace) else {
+                continue;
+            };
+            let ignored = self.ignored_fields(fst_results, snd_results);
+            if let Some(i) = fst.first_difference(snd, &ignored) {
+                let describe = |trace: &Trace| match trace.rows.get(i) {
+                    Some(row) => format!("{} {}", row.command(), row.errno_name()),
+                    None => "<missing>".to_owned(),
+                };
+                normalized.push_str(&format!("\n{}\n{}", describe(fst), describe(snd)));
+            }
+        }
+        signature(&normalized)
+    }
+
+    /// Keys of extra fields known to differ between the pair of filesystems.
+    fn ignored_fields(&self, fst: &FsResults, snd: &FsResults) -> Vec<String> {
+        Allowlist::for_pair(&self.known_differences, &fst.fs_name, &snd.fs_name).trace_fields
+    }
+
+    /// Diverged pairs if more than two filesystems were compared, otherwise the only pair.
+    fn compared_pairs(&self) -> Vec<(&FsResults, &FsResults)> {
+        let results = |fs_name: &str| self.results.iter().find(|r| r.fs_name == fs_name);
+        if !self.diverged.is_empty() {
+            return self
+                .diverged
+                .iter()
+                .filter_map(|pair| Some((results(&pair.fst)?, results(&pair.snd)?)))
+                .collect();
+        }
+        match self.results.as_slice() {
+            [fst, snd] => vec![(fst, snd)],
+            _ => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let trace = r#"
+Index,Command,ReturnCode,Errno,Extra
+   0,       MKDIR,       0,Success(0),
+   1,      REMOVE,      -1,Directory not empty(39),
+"#
+        .trim_start();
+        let reason = Reason::new("detected by trace objective: true")
+            .with_results("Ext4", Some(Trace::try_parse(trace.to_owned()).unwrap()))
+            .with_results("Btrfs", None);
+        assert_eq!(
+            format!(
+                "detected by trace objective: true\n\n'Ext4':\n{}\n'Btrfs':\n<no trace>\n",
+                trace
+            ),
+            reason.render()
+        );
+        let json = serde_json::to_value(&reason).unwrap();
+        assert_eq!("Ext4", json["results"][0]["fs_name"]);
+        assert_eq!(
+            serde_json::json!({
+                "index": 1,
+                "command": "REMOVE",
+                "return_code": -1,
+                "errno": {"name": "Directory not empty", "code": 39},
+                "extra": ""
+            }),
+            json["results"][0]["trace"]["rows"][1]
+        );
+        assert!(json["results"][1]["trace"].is_null());
+    }
+
+    fn trace(rows: &[(&str, i32, &str, i32)]) -> Option<Trace> {
+        let mut trace = "Index,Command,ReturnCode,Errno,Extra\n".to_owned();
+        for (i, (command, return_code, errno, code)) in rows.iter().enumerate() {
+            trace.push_str(&format!(
+                "{},{},{},{}({}),\n",
+                i, command, return_code, errno, code
+            ));
+        }
+        Some(Trace::try_parse(trace).unwrap())
+    }
+
+    #[test]
+    fn test_signature() {
+        let summary = "detected by trace objective: true";
+        let remove_diverged = |fst_prefix: &[(&str, i32, &str, i32)]| {
+            let mut fst = fst_prefix.to_vec();
+            let mut snd = fst_prefix.to_vec();
+            fst.push(("REMOVE", -1, "Directory not empty", 39));
+            snd.push(("REMOVE", 0, "Success", 0));
+            Reason::new(summary)
+                .with_results("Ext4", trace(&fst))
+                .with_results("Btrfs", trace(&snd))
+        };
+        let mkdir = ("MKDIR", 0, "Success", 0);
+        let create = ("CREATE", 0, "Success", 0);
+        // same bug at different operation index
+        assert_eq!(
+            remove_diverged(&[mkdir]).signature(),
+            remove_diverged(&[mkdir, create, mkdir]).signature()
+        );
+        // different errno
+        let other_errno = Reason::new(summary)
+            .with_results(
+                "Ext4",
+                trace(&[mkdir, ("REMOVE", -1, "No such file or directory", 2)]),
+            )
+            .with_results("Btrfs", trace(&[mkdir, ("REMOVE", 0, "Success", 0)]));
+        assert_ne!(
+            remove_diverged(&[mkdir]).signature(),
+            other_errno.signature()
+        );
+        // different operation
+        let other_command = Reason::new(summary)
+            .with_results("Ext4", trace(&[("MKDIR", -1, "File exists", 17)]))
+            .with_results("Btrfs", trace(&[mkdir]));
+        assert_ne!(
+            remove_diverged(&[mkdir]).signature(),
+            other_command.signature()
+        );
+        // executor died on one filesystem
+        let truncated = Reason::new(summary)
+            .with_results("Ext4", trace(&[mkdir]))
+            .with_results("Btrfs", trace(&[mkdir, create]));
+        assert_ne!(truncated.signature(), other_command.signature());
+        // no traces, only summary counts
+        let hash_only = Reason::new("detected by hash objective: true")
+            .with_results("Ext4", None)
+            .with_results("Btrfs", None);
+        assert_eq!(
+            hash_only.signature(),
+            Reason::new("detected by hash objective: true").signature()
+        );
+        assert_ne!(hash_only.signature(), Reason::new(summary).signature());
+    }
+
+    #[test]
+    fn test_known_differences_not_in_signature() {
+        let summary = "detected by trace objective: true";
+        let lseek = |offset: u32| {
+            Some(
+                Trace::try_parse(format!(
+                    "Index,Command,ReturnCode,Errno,Extra\n0,LSEEK,0,Success(0),offset={}\n",
+                    offset
+                ))
+                .unwrap(),
+            )
+        };
+        let reason = |known: &[KnownDifference]| {
+            Reason::new(summary)
+                .with_known_differences(known)
+                .with_results("Ext4", lseek(4096))
+                .with_results("XFS", lseek(0))
+        };
+        let known = [KnownDifference {
+            filesystems: ["xfs".to_owned(), "ext4".to_owned()],
+            hash_attributes: vec![],
+            trace_fields: vec!["offset".to_owned()],
+        }];
+        assert_eq!(Some(0), reason(&[]).first_difference());
+        assert_eq!(None, reason(&known).first_difference());
+        assert_eq!(Reason::new(summary).signature(), reason(&known).signature());
+    }
+
+    #[test]
+    fn test_mount_options_recorded() {
+        let reason = Reason::new("summary")
+            .with_mount_options("Ext4-opts1", "mkfs -O '', mount -o 'noatime'")
+            .with_results("Ext4-opts1", None);
+        assert_eq!(
+            "summary\n'Ext4-opts1': mkfs -O '', mount -o 'noatime'\n\n'Ext4-opts1':\n<no trace>\n",
+            reason.render()
+        );
+        let json = serde_json::to_value(&reason).unwrap();
+        assert_eq!(
+            serde_json::json!(["'Ext4-opts1': mkfs -O '', mount -o 'noatime'"]),
+            json["mount_options"]
+        );
+    }
+
+    #[test]
+    fn test_divergence() {
+        let op = Operation::RENAME {
+            old_path: "/0".into(),
+            new_path: "/1/2".into(),
+            flags: vec![],
+        };
+        let reason = Reason::new("detected by trace objective: true").with_divergence(2, &op);
+        assert_eq!(
+            "detected by trace objective: true\ndivergence first observed at operation 2 (RENAME): do_rename(\"/0\", \"/1/2\");\n",
+            reason.render()
+        );
+        assert_eq!(
+            serde_json::json!({
+                "index": 2,
+                "command": "RENAME",
+                "operation": "do_rename(\"/0\", \"/1/2\");"
+            }),
+            serde_json::to_value(&reason).unwrap()["divergence"]
+        );
+        // signature does not depend on index
+        assert_eq!(
+            reason.signature(),
+            Reason::new("detected by trace objective: true").signature()
+        );
+    }
+
+    #[test]
+    fn test_signature_differences() {
+        let reason = |differences: &[&str]| {
+            Reason::new("detected by hash objective: true")
+                .with_differences(differences.iter().map(|d| d.to_string()).collect())
+        };
+        assert_eq!(
+            "detected by hash objective: true\nonly mode of '0' differs\n",
+            reason(&["only mode of '0' differs"]).render()
+        );
+        assert_eq!(
+            reason(&["only mode of '0' differs"]).signature(),
+            reason(&["only mode of '0' differs"]).signature()
+        );
+        assert_ne!(
+            reason(&["only mode of '0' differs"]).signature(),
+            reason(&["only size of '0' differs"]).signature()
+        );
+        // names are numbers, so only path depth is kept
+        assert_eq!(
+            reason(&["only mode of '0' differs"]).signature(),
+            reason(&["only mode of '1' differs"]).signature()
+        );
+        assert_ne!(
+            reason(&["only mode of '0' differs"]).signature(),
+            reason(&["only mode of '0/1' differs"]).signature()
+        );
+        assert_ne!(
+            reason(&[]).signature(),
+            reason(&["only one filesystem has '0'"]).signature()
+        );
+    }
+
+    fn diverged(fst: &str, snd: &str, detected_by: &[&str]) -> DivergedPair {
+        DivergedPair {
+            fst: fst.to_owned(),
+            snd: snd.to_owned(),
+            detected_by: detected_by.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_signature_diverged_pairs() {
+        let mkdir = ("MKDIR", 0, "Success", 0);
+        let remove = ("REMOVE", 0, "Success", 0);
+        let failed = |command| (command, -1, "Directory not empty", 39);
+        let reason = |xfs: Option<Trace>| {
+            Reason::new("detected by pairwise objective")
+                .with_diverged(diverged("Ext4", "Xfs", &["trace"]))
+                .with_results("Ext4", trace(&[mkdir, remove]))
+                .with_results("Btrfs", trace(&[mkdir, remove]))
+                .with_results("Xfs", xfs)
+        };
+        assert_ne!(
+            reason(trace(&[mkdir, failed("REMOVE")])).signature(),
+            reason(trace(&[failed("MKDIR"), remove])).signature()
+        );
+        assert_eq!(
+            reason(trace(&[mkdir, failed("REMOVE")])).signature(),
+            reason(trace(&[mkdir, failed("REMOVE")])).signature()
+        );
+        // the same pair diverged in usage only
+        let statfs = |fs_name| {
+            Reason::new("detected by pairwise objective")
+                .with_diverged(diverged("Ext4", fs_name, &["statfs"]))
+                .with_results("Ext4", trace(&[mkdir, remove]))
+                .with_results(fs_name, trace(&[mkdir, remove]))
+        };
+        assert_ne!(
+            reason(trace(&[mkdir, remove])).signature(),
+            statfs("Xfs").signature()
+        );
+        assert_ne!(statfs("Xfs").signature(), statfs("Btrfs").signature());
+    }
+
+    #[test]
+    fn test_diverged_pairs() {
+        let reason = Reason::new("detected by pairwise objective")
+            .with_diverged(diverged("Ext4", "Btrfs", &["trace"]))
+            .with_diverged(diverged("Xfs", "Btrfs", &["trace", "hash"]));
+        assert_eq!(
+            "detected by pairwise objective\n'Ext4' and 'Btrfs' diverged (trace)\n'Xfs' and 'Btrfs' diverged (trace, hash)\n",
+            reason.render()
+        );
+        assert_eq!(
+            serde_json::json!([
+                {"fst": "Ext4", "snd": "Btrfs", "detected_by": ["trace"]},
+                {"fst": "Xfs", "snd": "Btrfs", "detected_by": ["trace", "hash"]}
+            ]),
+            serde_json::to_value(&reason).unwrap()["diverged"]
+        );
+        assert_ne!(
+            reason.signature(),
+            Reason::new("detected by pairwise objective")
+                .with_diverged(diverged("Ext4", "Btrfs", &["trace"]))
+                .signature()
+        );
+    }
+}
@@ -4,14 +4,17 @@ use anyhow::{Context, Ok};
 use log::{info, warn};
 
 use crate::{
-    abstract_fs::{mutator::remove, workload::Workload},
+    abstract_fs::{
+        mutator::{remove, shrink},
+        workload::Workload,
+    },
     config::Config,
-    fuzzing::common::parse_trace,
+    fuzzing::{common::parse_trace, observer::ObserverVerdict},
     hasher::hasher::FileDiff,
     mount::mount::FileSystemMount,
 };
 
-use super::common::Runner;
+use super::{common::Runner, crash_dir::CrashKind};
 
 pub struct Reducer {
     runner: Runner,
@@ -40,7 +43,10 @@ impl Reducer {
 
         let input_path = self.runner.compile_test(&input)?;
 
-        self.runner.run_harness(&input_path)?;
+        if let ObserverVerdict::Inconclusive(reason) = self.runner.run_harness(&input_path)? {
+            warn!("run is inconclusive, can't reduce: {}", reason);
+            return Ok(());
+        }
 
         let fst_trace = parse_trace(&self.runner.fst_trace_path)
             .with_context(|| format!("failed to parse first trace"))?;
@@ -75,36 +81,328 @@ impl Reducer {
         save_to_dir: &Path,
     ) -> anyhow::Result<()> {
         info!("reducing using hash difference");
-        let mut index = input.ops.len() - 1;
-        let mut workload = input;
-        loop {
-            if let Some(reduced) = remove(&workload, index) {
-                let input_path = self.runner.compile_test(&workload)?;
-                self.runner.run_harness(&input_path)?;
-                let hash_diff_interesting = self
-                    .runner
-                    .hash_objective
-                    .is_interesting()
-                    .with_context(|| format!("failed to do hash objective"))?;
-                if hash_diff_interesting {
-                    let new_diff = self.runner.hash_objective.get_diff();
-                    if old_diff == new_diff {
-                        workload = reduced;
-                        info!("reduced workload (length = {})", workload.ops.len());
-                        self.runner.report_crash(
-                            &workload,
-                            &input_path,
-                            save_to_dir.to_path_buf().into_boxed_path(),
-                            new_diff,
-                        )?;
-                    }
-                }
+        let mut reproduces = |workload: &Workload| -> anyhow::Result<bool> {
+            let input_path = self.runner.compile_test(workload)?;
+            let verdict = self.runner.run_harness(&input_path)?;
+            let hash_diff_interesting = self
+                .runner
+                .hash_objective
+                .is_interesting()
+                .with_context(|| format!("failed to do hash objective"))?;
+            if verdict != ObserverVerdict::Conclusive || !hash_diff_interesting {
+                return Ok(false);
+            }
+            let new_diff = self.runner.hash_objective.get_diff();
+            if old_diff != new_diff {
+                return Ok(false);
             }
-            if index == 0 {
-                break;
+            info!("reduced workload (length = {})", workload.ops.len());
+            self.runner.report_crash(
+                workload,
+                &input_path,
+                save_to_dir.to_path_buf().into_boxed_path(),
+                new_diff,
+                CrashKind::Reduced,
+                "same hash difference as original testcase",
+            )?;
+            Ok(true)
+        };
+        let workload = reduce(input, &mut reproduces)?;
+        self.report_divergence(&workload, save_to_dir)
+    }
+
+    /// Reruns reduced workload to find which attributes of which file differ.
+    fn report_divergence(&mut self, workload: &Workload, save_to_dir: &Path) -> anyhow::Result<()> {
+        let input_path = self.runner.compile_test(workload)?;
+        if let ObserverVerdict::Inconclusive(reason) = self.runner.run_harness(&input_path)? {
+            warn!("run is inconclusive, can't isolate divergence: {}", reason);
+            return Ok(());
+        }
+        match self.runner.hash_objective.isolate_divergence() {
+            Some((options, description)) => {
+                info!("divergence isolated: {} ({:?})", description, options);
+                let diff = self.runner.hash_objective.get_diff();
+                self.runner.report_crash(
+                    workload,
+                    &input_path,
+                    save_to_dir.to_path_buf().into_boxed_path(),
+                    diff,
+                    CrashKind::Reduced,
+                    &description,
+                )?;
             }
-            index -= 1
+            None => warn!("divergence not reproduced by reduced workload"),
         }
         Ok(())
     }
 }
+
+/// Removes operations, then minimizes arguments of the rest while crash still reproduces.
+pub fn reduce(
+    workload: Workload,
+    reproduces: &mut impl FnMut(&Workload) -> anyhow::Result<bool>,
+) -> anyhow::Result<Workload> {
+    let workload = remove_ops(workload, reproduces)?;
+    info!("minimizing operation arguments");
+    minimize_args(workload, reproduces)
+}
+
+/// Reduces input that diverged with `reason`, `diverges` reruns workload and returns its reason.
+///
+/// Candidates diverging for another reason (e.g. other objective) are rejected, so saved crash
+/// is the same one that was found.
+pub fn reduce_divergence(
+    input: &Workload,
+    reason: &str,
+    mut diverges: impl FnMut(&Workload) -> anyhow::Result<Option<String>>,
+) -> anyhow::Result<Workload> {
+    info!("reducing diverging input (length = {})", input.ops.len());
+    let reduced = reduce(input.clone(), &mut |workload| {
+        Ok(diverges(workload)?.is_some_and(|candidate| candidate == reason))
+    })?;
+    info!("input reduced (length = {})", reduced.ops.len());
+    Ok(reduced)
+}
+
+/// Removes operations one by one (from the end), keeping removals after which crash still reproduces.
+fn remove_ops(
+    workload: Workload,
+    reproduces: &mut impl FnMut(&Workload) -> anyhow::Result<bool>,
+) -> anyhow::Result<Workload> {
+    let mut workload = workload;
+    for index in (0..workload.ops.len()).rev() {
+        if let Some(reduced) = remove(&workload, index) {
+            if reproduces(&reduced)? {
+                workload = reduced;
+            }
+        }
+    }
+    Ok(workload)
+}
+
+/// Shrinks arguments of each operation (see [`shrink`]) while crash still reproduces.
+///
+/// Every accepted change makes workload strictly simpler, so it stops once no change is accepted.
+fn minimize_args(
+    workload: Workload,
+    reproduces: &mut impl FnMut(&Workload) -> anyhow::Result<bool>,
+) -> anyhow::Result<Workload> {
+    let mut workload = workload;
+    for index in 0..workload.ops.len() {
+        'shrinking: loop {
+            for candidate in shrink(&workload, index) {
+                if reproduces(&candidate)? {
+                    workload = candidate;
+                    continue 'shrinking;
+                }
+            }
+            break;
+        }
+    }
+    Ok(workload)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::abstract_fs::{
+        flags::{ModeFlag, OpenFlag},
+        node::FileDescriptorIndex,
+        operation::Operation,
+    };
+
+    use super::*;
+
+    fn workload() -> Workload {
+        Workload {
+            ops: vec![
+                Operation::MKDIR {
+                    path: "/0".into(),
+                    mode: vec![ModeFlag::S_IRWXU],
+                },
+                Operation::MKDIR {
+                    path: "/0/1".into(),
+                    mode: vec![ModeFlag::S_IRWXU, ModeFlag::S_ISGID],
+                },
+                Operation::CREATE {
+                    path: "/0/1/2".into(),
+                    mode: vec![ModeFlag::S_IRUSR, ModeFlag::S_IWUSR],
+                },
+                Operation::OPEN {
+                    path: "/0/1/2".into(),
+                    flags: vec![OpenFlag::O_APPEND],
+                    des: FileDescriptorIndex(0),
+                },
+                Operation::WRITE {
+                    des: FileDescriptorIndex(0),
+                    src_offset: 42,
+                    size: 4096,
+                },
+                Operation::CLOSE {
+                    des: FileDescriptorIndex(0),
+                },
+            ],
+            seed: 0,
+        }
+    }
+
+    #[test]
+    fn test_minimize_args_always_crashing() {
+        let mut runs = 0;
+        let minimized = minimize_args(workload(), &mut |_| {
+            runs += 1;
+            Ok(true)
+        })
+        .unwrap();
+        assert_eq!(
+            vec![
+                Operation::MKDIR {
+                    path: "/0".into(),
+                    mode: vec![],
+                },
+                Operation::MKDIR {
+                    path: "/1".into(),
+                    mode: vec![],
+                },
+                Operation::CREATE {
+                    path: "/2".into(),
+                    mode: vec![],
+                },
+                Operation::OPEN {
+                    path: "/2".into(),
+                    flags: vec![],
+                    des: FileDescriptorIndex(0),
+                },
+                Operation::WRITE {
+                    des: FileDescriptorIndex(0),
+                    src_offset: 0,
+                    size: 0,
+                },
+                Operation::CLOSE {
+                    des: FileDescriptorIndex(0),
+                },
+            ],
+            minimized.ops
+        );
+        // every accepted candidate is a single simplification
+        assert!(runs < 30);
+    }
+
+    #[test]
+    fn test_minimize_args_keeps_crash() {
+        // crash needs big enough write into file nested in directory with setgid bit
+        let crashes = |workload: &Workload| {
+            let nested = workload.ops.iter().any(
+                |op| matches!(op, Operation::CREATE { path, .. } if path.segments().len() > 1),
+            );
+            let setgid = workload.ops.iter().any(|op| {
+                matches!(op, Operation::MKDIR { mode, .. } if mode.contains(&ModeFlag::S_ISGID))
+            });
+            let big_write = workload
+                .ops
+                .iter()
+                .any(|op| matches!(op, Operation::WRITE { size, .. } if *size >= 100));
+            nested && setgid && big_write
+        };
+        let minimized = minimize_args(workload(), &mut |w| Ok(crashes(w))).unwrap();
+        assert!(crashes(&minimized));
+        assert_eq!(
+            Operation::MKDIR {
+                path: "/1".into(),
+                mode: vec![ModeFlag::S_ISGID],
+            },
+            minimized.ops[1]
+        );
+        assert_eq!(
+            Operation::CREATE {
+                path: "/1/2".into(),
+                mode: vec![],
+            },
+            minimized.ops[2]
+        );
+        assert_eq!(
+            Operation::WRITE {
+                des: FileDescriptorIndex(0),
+                src_offset: 0,
+                size: 128,
+            },
+            minimized.ops[4]
+        );
+    }
+
+    #[test]
+    fn test_reduce_divergence_always_diverging() {
+        let mut runs = 0;
+        let reduced = reduce_divergence(&workload(), "trace", |_| {
+            runs += 1;
+            Ok(Some("trace".to_owned()))
+        })
+        .unwrap();
+        // every operation can be removed, last ones go first so that nothing depends on them
+        assert_eq!(Workload::new(), reduced);
+        assert!(runs <= workload().ops.len());
+    }
+
+    #[test]
+    fn test_reduce_divergence_same_reason() {
+        // only workloads writing anything diverge for the same reason
+        let reduced = reduce_divergence(&workload(), "hash", |workload| {
+            let writes = workload
+                .ops
+                .iter()
+                .any(|op| matches!(op, Operation::WRITE { size, .. } if *size > 0));
+            Ok(Some(if writes { "hash" } else { "trace" }.to_owned()))
+        })
+        .unwrap();
+        // directories are needed until file is moved up, they are not removed again after that
+        assert_eq!(
+            vec![
+                Operation::MKDIR {
+                    path: "/0".into(),
+                    mode: vec![],
+                },
+                Operation::MKDIR {
+                    path: "/1".into(),
+                    mode: vec![],
+                },
+                Operation::CREATE {
+                    path: "/2".into(),
+                    mode: vec![],
+                },
+                Operation::OPEN {
+                    path: "/2".into(),
+                    flags: vec![],
+                    des: FileDescriptorIndex(0),
+                },
+                Operation::WRITE {
+                    des: FileDescriptorIndex(0),
+                    src_offset: 0,
+                    size: 1,
+                },
+            ],
+            reduced.ops
+        );
+        let unchanged = reduce_divergence(&workload(), "hash", |_| Ok(None)).unwrap();
+        assert_eq!(workload(), unchanged);
+    }
+
+    #[test]
+    fn test_remove_ops_then_minimize() {
+        let crashes = |workload: &Workload| {
+            workload
+                .ops
+                .iter()
+                .any(|op| matches!(op, Operation::WRITE { size, .. } if *size > 0))
+        };
+        let reduced = remove_ops(workload(), &mut |w| Ok(crashes(w))).unwrap();
+        assert_eq!(5, reduced.ops.len());
+        let minimized = minimize_args(reduced, &mut |w| Ok(crashes(w))).unwrap();
+        assert_eq!(
+            Operation::WRITE {
+                des: FileDescriptorIndex(0),
+                src_offset: 0,
+                size: 1,
+            },
+            minimized.ops[4]
+        );
+    }
+}
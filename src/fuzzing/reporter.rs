@@ -0,0 +1,463 @@
+use std::{
+    cell::RefCell,
+    fs::OpenOptions,
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpStream, ToSocketAddrs},
+    path::Path,
+    time::Duration,
+};
+
+use anyhow::{bail, Context};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{abstract_fs::workload::Workload, save::save_reason};
+
+use super::{crash_dir::CrashKind, reason::Reason};
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Crash that was just saved to disk.
+#[derive(Debug, Serialize)]
+pub struct CrashReport<'a> {
+    pub name: &'a str,
+    pub kind: String,
+    #[serde(skip)]
+    pub dir: &'a Path,
+    pub reason: &'a Reason,
+    #[serde(skip)]
+    pub input: &'a Workload,
+}
+
+impl<'a> CrashReport<'a> {
+    pub fn new(
+        name: &'a str,
+        kind: CrashKind,
+        dir: &'a Path,
+        reason: &'a Reason,
+        input: &'a Workload,
+    ) -> Self {
+        Self {
+            name,
+            kind: kind.to_string(),
+            dir,
+            reason,
+            input,
+        }
+    }
+}
+
+/// Sink for saved crashes.
+pub trait CrashReporter {
+    fn report(&self, crash: &CrashReport) -> anyhow::Result<()>;
+}
+
+/// Writes reason next to saved testcase.
+pub struct FileSystemReporter;
+
+impl CrashReporter for FileSystemReporter {
+    fn report(&self, crash: &CrashReport) -> anyhow::Result<()> {
+        save_reason(crash.dir, crash.reason)
+    }
+}
+
+/// Posts crash as JSON to `http://` URL (TLS is not supported).
+pub struct HttpReporter {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl HttpReporter {
+    pub fn new(url: &str) -> anyhow::Result<Self> {
+        let Some(rest) = url.strip_prefix("http://") else {
+            bail!("only 'http://' URLs are supported, got '{}'", url);
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse()
+                    .with_context(|| format!("invalid port in '{}'", url))?,
+            ),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            bail!("no host in '{}'", url);
+        }
+        Ok(Self {
+            host: host.to_owned(),
+            port,
+            path: path.to_owned(),
+        })
+    }
+}
+
+impl CrashReporter for HttpReporter {
+    fn report(&self, crash: &CrashReport) -> anyhow::Result<()> {
+        let body =
+            serde_json::to_string(crash).with_context(|| format!("failed to encode crash"))?;
+        let addr = (self.host.as_str(), self.port)
+            .to_socket_addrs()
+            .with_context(|| format!("failed to resolve '{}'", self.host))?
+            .next()
+            .with_context(|| format!("no address for '{}'", self.host))?;
+        let mut stream = TcpStream::connect_timeout(&addr, HTTP_TIMEOUT)
+            .with_context(|| format!("failed to connect to '{}'", addr))?;
+        stream.set_read_timeout(Some(HTTP_TIMEOUT))?;
+        stream.set_write_timeout(Some(HTTP_TIMEOUT))?;
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.path,
+            self.host,
+            body.len(),
+            body
+        )
+        .with_context(|| format!("failed to send crash"))?;
+        let mut status = String::new();
+        BufReader::new(&stream)
+            .read_line(&mut status)
+            .with_context(|| format!("failed to read response"))?;
+        match status.split_whitespace().nth(1) {
+            Some(code) if code.starts_with('2') => {
+                debug!("crash '{}' posted to '{}'", crash.name, addr);
+                Ok(())
+            }
+            _ => bail!("unexpected response '{}'", status.trim()),
+        }
+    }
+}
+
+/// Line of event stream written by [`NdjsonReporter`].
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct CrashEvent {
+    pub name: String,
+    pub kind: String,
+    pub summary: String,
+    /// Index of the first operation with different results, missing if traces are the same.
+    pub divergence_index: Option<usize>,
+    pub workload: Workload,
+}
+
+impl From<&CrashReport<'_>> for CrashEvent {
+    fn from(crash: &CrashReport) -> Self {
+        Self {
+            name: crash.name.to_owned(),
+            kind: crash.kind.clone(),
+            summary: crash.reason.summary.clone(),
+            divergence_index: crash.reason.first_difference(),
+            workload: crash.input.clone(),
+        }
+    }
+}
+
+/// Appends each crash as a single line of JSON, for CI to follow.
+pub struct NdjsonReporter {
+    out: RefCell<Box<dyn Write>>,
+}
+
+impl NdjsonReporter {
+    /// Writes to stdout if path is `-`, otherwise appends to file.
+    pub fn open(path: &str) -> anyhow::Result<Self> {
+        let out: Box<dyn Write> = if path == "-" {
+            Box::new(io::stdout())
+        } else {
+            Box::new(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|| format!("failed to open event stream at '{}'", path))?,
+            )
+        };
+        info!("streaming crash events to '{}'", path);
+        Ok(Self::new(out))
+    }
+
+    pub fn new(out: Box<dyn Write>) -> Self {
+        Self {
+            out: RefCell::new(out),
+        }
+    }
+}
+
+impl CrashReporter for NdjsonReporter {
+    fn report(&self, crash: &CrashReport) -> anyhow::Result<()> {
+        let line = serde_json::to_string(&CrashEvent::from(crash))
+            .with_context(|| format!("failed to encode crash event"))?;
+        let mut out = self.out.borrow_mut();
+        writeln!(out, "{}", line).with_context(|| format!("failed to write crash event"))?;
+        out.flush()
+            .with_context(|| format!("failed to flush crash event"))?;
+        Ok(())
+    }
+}
+
+/// Failures of remote sinks are only logged, crash is already on disk.
+pub fn report_remote(reporters: &[Box<dyn CrashReporter>], crash: &CrashReport) {
+    for reporter in reporters {
+        if let Err(err) = reporter.report(crash) {
+            warn!("failed to report crash '{}': {:#}", crash.name, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, io::Read, net::TcpListener, rc::Rc, thread};
+
+    use crate::abstract_fs::{operation::Operation, trace::Trace};
+
+    use super::*;
+
+    struct MockReporter {
+        payloads: Rc<RefCell<Vec<serde_json::Value>>>,
+        fail: bool,
+    }
+
+    impl CrashReporter for MockReporter {
+        fn report(&self, crash: &CrashReport) -> anyhow::Result<()> {
+            self.payloads
+                .borrow_mut()
+                .push(serde_json::to_value(crash).unwrap());
+            if self.fail {
+                bail!("sink is down");
+            }
+            Ok(())
+        }
+    }
+
+    fn reason() -> Reason {
+        Reason::new("detected by trace objective: true").with_results("Ext4", None)
+    }
+
+    #[test]
+    fn test_report_remote() {
+        let payloads = Rc::new(RefCell::new(vec![]));
+        let reporters: Vec<Box<dyn CrashReporter>> = vec![
+            Box::new(MockReporter {
+                payloads: payloads.clone(),
+                fail: true,
+            }),
+            Box::new(MockReporter {
+                payloads: payloads.clone(),
+                fail: false,
+            }),
+        ];
+        let reason = reason();
+        let input = Workload::new();
+        let crash = CrashReport::new(
+            "abc",
+            CrashKind::Divergence,
+            Path::new("/tmp"),
+            &reason,
+            &input,
+        );
+        report_remote(&reporters, &crash);
+        let expected = serde_json::json!({
+            "name": "abc",
+            "kind": "divergence",
+            "reason": {
+                "summary": "detected by trace objective: true",
+                "mount_options": [],
+                "results": [{"fs_name": "Ext4", "trace": null}],
+            },
+        });
+        assert_eq!(vec![expected.clone(), expected], *payloads.borrow());
+    }
+
+    #[test]
+    fn test_http_reporter() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![];
+            let mut buf = [0; 4096];
+            loop {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length: usize = head
+                        .lines()
+                        .find_map(|l| l.strip_prefix("Content-Length: "))
+                        .unwrap()
+                        .parse()
+                        .unwrap();
+                    if body.len() >= length {
+                        break;
+                    }
+                }
+            }
+            stream.write_all(b"HTTP/1.1 201 Created\r\n\r\n").unwrap();
+            String::from_utf8(request).unwrap()
+        });
+        let reporter = HttpReporter::new(&format!("http://127.0.0.1:{}/crashes", port)).unwrap();
+        let reason = reason();
+        let input = Workload::new();
+        let crash = CrashReport::new(
+            "abc",
+            CrashKind::Truncated,
+            Path::new("/tmp"),
+            &reason,
+            &input,
+        );
+        reporter.report(&crash).unwrap();
+        let request = server.join().unwrap();
+        let (head, body) = request.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("POST /crashes HTTP/1.1\r\n"));
+        assert!(head.contains("Content-Type: application/json"));
+        assert_eq!(
+            serde_json::to_value(&crash).unwrap(),
+            serde_json::from_str::<serde_json::Value>(body).unwrap()
+        );
+        assert_eq!("truncated", serde_json::to_value(&crash).unwrap()["kind"]);
+    }
+
+    #[test]
+    fn test_http_reporter_url() {
+        let reporter = HttpReporter::new("http://dashboard/").unwrap();
+        assert_eq!(
+            ("dashboard", 80, "/"),
+            (
+                reporter.host.as_str(),
+                reporter.port,
+                reporter.path.as_str()
+            )
+        );
+        let reporter = HttpReporter::new("http://dashboard:8080").unwrap();
+        assert_eq!(
+            ("dashboard", 8080, "/"),
+            (
+                reporter.host.as_str(),
+                reporter.port,
+                reporter.path.as_str()
+            )
+        );
+        assert!(HttpReporter::new("https://dashboard/").is_err());
+        assert!(HttpReporter::new("http://:80/").is_err());
+        assert!(HttpReporter::new("http://dashboard:port/").is_err());
+    }
+
+    #[test]
+    fn test_http_reporter_unreachable() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+        let reporter = HttpReporter::new(&format!("http://127.0.0.1:{}/", port)).unwrap();
+        let reason = reason();
+        let input = Workload::new();
+        let crash = CrashReport::new(
+            "abc",
+            CrashKind::Accident,
+            Path::new("/tmp"),
+            &reason,
+            &input,
+        );
+        assert!(reporter.report(&crash).is_err());
+    }
+
+    fn diverged_reason() -> Reason {
+        let trace = |errno: &str| {
+            Trace::try_parse(format!(
+                "Index,Command,ReturnCode,Errno,Extra\n0,MKDIR,0,Success(0),\n1,REMOVE,{}\n",
+                errno
+            ))
+            .unwrap()
+        };
+        Reason::new("detected by trace objective: true")
+            .with_results("Ext4", Some(trace("-1,Directory not empty(39),")))
+            .with_results("Btrfs", Some(trace("0,Success(0),")))
+    }
+
+    #[test]
+    fn test_crash_event_json() {
+        let reason = diverged_reason();
+        let mut input = Workload::new();
+        input.push(Operation::MKDIR {
+            path: "/foobar".into(),
+            mode: vec![],
+        });
+        let crash = CrashReport::new(
+            "abc",
+            CrashKind::Divergence,
+            Path::new("/tmp"),
+            &reason,
+            &input,
+        );
+        let json = serde_json::to_value(CrashEvent::from(&crash)).unwrap();
+        assert_eq!("abc", json["name"]);
+        assert_eq!("divergence", json["kind"]);
+        assert_eq!("detected by trace objective: true", json["summary"]);
+        assert_eq!(1, json["divergence_index"]);
+        assert_eq!(serde_json::to_value(&input).unwrap(), json["workload"]);
+        let reason = Reason::new("detected by hash objective: true");
+        let crash = CrashReport::new(
+            "abc",
+            CrashKind::Divergence,
+            Path::new("/tmp"),
+            &reason,
+            &input,
+        );
+        assert!(
+            serde_json::to_value(CrashEvent::from(&crash)).unwrap()["divergence_index"].is_null()
+        );
+    }
+
+    #[test]
+    fn test_ndjson_reporter_round_trip() {
+        let dir = env::temp_dir().join("DIFFuzzer-ndjson-test");
+        fs::remove_dir_all(&dir).unwrap_or(());
+        fs::create_dir(&dir).unwrap();
+        let path = dir.join("crashes.ndjson");
+        let reporter = NdjsonReporter::open(path.to_str().unwrap()).unwrap();
+        let reason = diverged_reason();
+        let mut input = Workload::new();
+        input.push(Operation::CREATE {
+            path: "/foobar".into(),
+            mode: vec![],
+        });
+        let fst = CrashReport::new(
+            "abc",
+            CrashKind::Divergence,
+            Path::new("/tmp"),
+            &reason,
+            &input,
+        );
+        let snd = CrashReport::new(
+            "def",
+            CrashKind::Accident,
+            Path::new("/tmp"),
+            &reason,
+            &input,
+        );
+        reporter.report(&fst).unwrap();
+        reporter.report(&snd).unwrap();
+        drop(reporter);
+        // stream is appended to, not overwritten
+        NdjsonReporter::open(path.to_str().unwrap())
+            .unwrap()
+            .report(&fst)
+            .unwrap();
+        let events: Vec<CrashEvent> = fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            vec![
+                CrashEvent::from(&fst),
+                CrashEvent::from(&snd),
+                CrashEvent::from(&fst)
+            ],
+            events
+        );
+        assert_eq!(input, events[1].workload);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
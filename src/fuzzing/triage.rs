@@ -0,0 +1,224 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use log::{info, warn};
+use walkdir::WalkDir;
+
+use crate::{
+    abstract_fs::workload::Workload,
+    save::{REASON_JSON_FILENAME, TESTCASE_JSON_FILENAME},
+};
+
+use super::{common::Runner, crash_dir::signature};
+
+pub const TRIAGE_FILENAME: &str = "triage.txt";
+
+/// Testcase saved by fuzzer along with the reason it was saved for.
+pub struct SavedCrash {
+    pub dir: PathBuf,
+    pub input: Workload,
+    pub reason: String,
+}
+
+/// Finds every saved testcase in crashes directory, crash directory template can nest them.
+pub fn load_crashes(crashes_dir: &Path) -> anyhow::Result<Vec<SavedCrash>> {
+    let mut crashes = vec![];
+    for entry in WalkDir::new(crashes_dir).sort_by_file_name() {
+        let entry = entry
+            .with_context(|| format!("failed to walk crashes at '{}'", crashes_dir.display()))?;
+        if entry.file_name() != TESTCASE_JSON_FILENAME {
+            continue;
+        }
+        let dir = entry.path().parent().unwrap().to_path_buf();
+        match load_crash(&dir) {
+            Ok(crash) => crashes.push(crash),
+            Err(err) => warn!("skipping crash at '{}': {:#}", dir.display(), err),
+        }
+    }
+    Ok(crashes)
+}
+
+fn load_crash(dir: &Path) -> anyhow::Result<SavedCrash> {
+    let input = fs::read_to_string(dir.join(TESTCASE_JSON_FILENAME))
+        .with_context(|| format!("failed to read testcase"))?;
+    let input: Workload =
+        serde_json::from_str(&input).with_context(|| format!("failed to parse testcase"))?;
+    let reason = fs::read_to_string(dir.join(REASON_JSON_FILENAME))
+        .with_context(|| format!("failed to read reason"))?;
+    let reason: serde_json::Value =
+        serde_json::from_str(&reason).with_context(|| format!("failed to parse reason"))?;
+    let reason = reason["summary"]
+        .as_str()
+        .with_context(|| format!("reason has no summary"))?
+        .to_owned();
+    Ok(SavedCrash {
+        dir: dir.to_path_buf(),
+        input,
+        reason,
+    })
+}
+
+/// Saved crashes bucketed by what happens when they are replayed now.
+#[derive(Debug, Default, PartialEq)]
+pub struct Triage {
+    /// Same signature as saved reason.
+    pub reproducing: Vec<PathBuf>,
+    /// Filesystems agree.
+    pub fixed: Vec<PathBuf>,
+    /// Still interesting, but for a different reason.
+    pub changed: Vec<(PathBuf, String)>,
+    /// Replay failed.
+    pub skipped: Vec<PathBuf>,
+}
+
+impl Triage {
+    /// `replay` gives reason crash would be reported for now, `None` if it is not reported.
+    pub fn classify(
+        crashes: Vec<SavedCrash>,
+        mut replay: impl FnMut(&Workload) -> anyhow::Result<Option<String>>,
+    ) -> Self {
+        let mut triage = Self::default();
+        for crash in crashes {
+            info!("replaying crash at '{}'", crash.dir.display());
+            match replay(&crash.input) {
+                Ok(None) => triage.fixed.push(crash.dir),
+                Ok(Some(reason)) if signature(&reason) == signature(&crash.reason) => {
+                    triage.reproducing.push(crash.dir)
+                }
+                Ok(Some(reason)) => triage.changed.push((crash.dir, reason)),
+                Err(err) => {
+                    warn!(
+                        "failed to replay crash at '{}': {:#}",
+                        crash.dir.display(),
+                        err
+                    );
+                    triage.skipped.push(crash.dir)
+                }
+            }
+        }
+        triage
+    }
+
+    pub fn render(&self) -> String {
+        let mut summary = format!(
+            "reproducing: {}, no longer reproducing: {}, changed signature: {}, skipped: {}\n",
+            self.reproducing.len(),
+            self.fixed.len(),
+            self.changed.len(),
+            self.skipped.len()
+        );
+        let sections = [
+            ("reproducing", &self.reproducing),
+            ("no longer reproducing", &self.fixed),
+            ("skipped", &self.skipped),
+        ];
+        for (title, dirs) in sections {
+            if !dirs.is_empty() {
+                summary.push_str(&format!("\n{}:\n", title));
+                for dir in dirs {
+                    summary.push_str(&format!("{}\n", dir.display()));
+                }
+            }
+        }
+        if !self.changed.is_empty() {
+            summary.push_str("\nchanged signature:\n");
+            for (dir, reason) in self.changed.iter() {
+                summary.push_str(&format!("{}: {}\n", dir.display(), reason));
+            }
+        }
+        summary
+    }
+}
+
+/// Replays every saved crash and writes summary to crashes directory.
+pub fn run(runner: &mut Runner, crashes_dir: &Path) -> anyhow::Result<()> {
+    info!("running triage of crashes at '{}'", crashes_dir.display());
+    let crashes = load_crashes(crashes_dir)?;
+    let triage = Triage::classify(crashes, |input| runner.replay_reason(input));
+    let summary = triage.render();
+    let summary_path = crashes_dir.join(TRIAGE_FILENAME);
+    fs::write(&summary_path, &summary)
+        .with_context(|| format!("failed to save triage at '{}'", summary_path.display()))?;
+    info!("{}", summary.lines().next().unwrap_or_default());
+    info!("triage saved at '{}'", summary_path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use anyhow::bail;
+
+    use crate::abstract_fs::operation::Operation;
+
+    use super::*;
+
+    fn save_crash(dir: &Path, path: &str, reason: &str) {
+        fs::create_dir_all(dir).unwrap();
+        let input = Workload {
+            ops: vec![Operation::MKDIR {
+                path: path.into(),
+                mode: vec![],
+            }],
+            seed: 0,
+        };
+        fs::write(
+            dir.join(TESTCASE_JSON_FILENAME),
+            serde_json::to_string(&input).unwrap(),
+        )
+        .unwrap();
+        fs::write(
+            dir.join(REASON_JSON_FILENAME),
+            serde_json::json!({"summary": reason}).to_string(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_triage() {
+        let dir = env::temp_dir().join("DIFFuzzer-triage-test");
+        fs::remove_dir_all(&dir).unwrap_or(());
+        let truncated = "'Ext4' crashed during operation 3 (WRITE)";
+        save_crash(&dir.join("a"), "/reproducing", truncated);
+        save_crash(&dir.join("2024-10-16/b"), "/fixed", truncated);
+        save_crash(&dir.join("c"), "/changed", truncated);
+        save_crash(&dir.join("d"), "/skipped", truncated);
+        fs::create_dir_all(dir.join("broken")).unwrap();
+        fs::write(dir.join("broken").join(TESTCASE_JSON_FILENAME), "{").unwrap();
+
+        let crashes = load_crashes(&dir).unwrap();
+        assert_eq!(4, crashes.len());
+        let triage = Triage::classify(crashes, |input| {
+            let Operation::MKDIR { path, .. } = &input.ops[0] else {
+                unreachable!()
+            };
+            match path.to_string().as_str() {
+                "/reproducing" => Ok(Some("'Ext4' crashed during operation 7 (WRITE)".into())),
+                "/fixed" => Ok(None),
+                "/changed" => Ok(Some("'Ext4' crashed during operation 3 (READ)".into())),
+                _ => bail!("run is inconclusive"),
+            }
+        });
+        assert_eq!(
+            Triage {
+                reproducing: vec![dir.join("a")],
+                fixed: vec![dir.join("2024-10-16/b")],
+                changed: vec![(
+                    dir.join("c"),
+                    "'Ext4' crashed during operation 3 (READ)".to_owned()
+                )],
+                skipped: vec![dir.join("d")],
+            },
+            triage
+        );
+        assert!(triage.render().starts_with(
+            "reproducing: 1, no longer reproducing: 1, changed signature: 1, skipped: 1\n"
+        ));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
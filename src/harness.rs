@@ -1,18 +1,53 @@
-use std::{cell::RefCell, path::Path, process::Command, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    os::unix::process::CommandExt,
+    path::Path,
+    process::Command,
+    rc::Rc,
+};
 
-use anyhow::Context;
+use anyhow::{bail, Context};
+use log::warn;
 
-use crate::fuzzing::objective::hash::HashHolder;
+use crate::config::Config;
+use crate::fuzzing::observer::{observe_all, Observer, ObserverVerdict};
 use crate::mount::mount::FileSystemMount;
 
 pub type ConsolePipe = Rc<RefCell<String>>;
 
+/// Settings shared by harnesses of every tested filesystem.
+#[derive(Clone, Default)]
+pub struct HarnessOptions {
+    /// In bytes, checked before each run (0 to disable).
+    pub min_free_space: u64,
+    pub verify_writes: bool,
+    /// In KiB.
+    pub ram_disk_size: usize,
+    /// Filesystem is kept mounted (not recreated) for that many runs.
+    pub persistent_runs: Option<u32>,
+}
+
+impl From<&Config> for HarnessOptions {
+    fn from(config: &Config) -> Self {
+        Self {
+            min_free_space: config.min_free_space,
+            verify_writes: config.verify_writes,
+            ram_disk_size: config.ram_disk_size,
+            persistent_runs: config.persistent_runs,
+        }
+    }
+}
+
 pub struct Harness {
     fs_mount: &'static dyn FileSystemMount,
     fs_dir: Box<Path>,
     exec_dir: Box<Path>,
     stdout: ConsolePipe,
     stderr: ConsolePipe,
+    device: &'static str,
+    options: HarnessOptions,
+    /// Runs done since filesystem was created, `None` if it is not mounted.
+    mounted_runs: Cell<Option<u32>>,
 }
 
 impl Harness {
@@ -22,6 +57,8 @@ impl Harness {
         exec_dir: Box<Path>,
         stdout: ConsolePipe,
         stderr: ConsolePipe,
+        device: &'static str,
+        options: HarnessOptions,
     ) -> Self {
         Self {
             fs_mount,
@@ -29,14 +66,17 @@ impl Harness {
             exec_dir,
             stdout,
             stderr,
+            device,
+            options,
+            mounted_runs: Cell::new(None),
         }
     }
     pub fn run(
         &self,
         input_path: &Path,
         keep_fs: bool,
-        hash_holder: Option<&mut HashHolder>,
-    ) -> anyhow::Result<bool> {
+        observers: &mut [&mut dyn Observer],
+    ) -> anyhow::Result<ObserverVerdict> {
         let test_exec_copy = self.exec_dir.join("test.out");
         std::fs::copy(input_path, &test_exec_copy).with_context(|| {
             format!(
@@ -46,27 +86,67 @@ impl Harness {
             )
         })?;
 
-        self.fs_mount.setup(&self.fs_dir).with_context(|| {
-            format!(
-                "failed to setup fs '{}' at '{}'",
-                self.fs_mount,
-                self.fs_dir.display()
-            )
-        })?;
+        if let Some(runs) = self.mounted_runs.get() {
+            if self
+                .options
+                .persistent_runs
+                .is_none_or(|max_runs| runs >= max_runs)
+            {
+                self.teardown()?;
+            }
+        }
+        if self.mounted_runs.get().is_none() {
+            self.setup()?;
+        }
+
+        if self.options.min_free_space > 0 {
+            ensure_free_space(
+                self.options.min_free_space,
+                || self.fs_mount.free_space(&self.fs_dir),
+                || {
+                    self.teardown()?;
+                    self.setup()
+                },
+            )?;
+        }
 
+        // in persistent mode every run gets its own workspace, files of previous runs stay around
+        let workspace = match self.options.persistent_runs {
+            Some(_) => self
+                .fs_dir
+                .join(format!("run-{}", self.mounted_runs.get().unwrap_or(0))),
+            None => self.fs_dir.to_path_buf(),
+        };
+        for observer in observers.iter_mut() {
+            observer
+                .pre_exec()
+                .with_context(|| format!("failed to prepare observers"))?;
+        }
         let mut exec = Command::new(test_exec_copy);
-        exec.arg(self.fs_dir.as_os_str());
+        exec.arg(workspace.as_os_str());
+        if self.options.verify_writes {
+            exec.arg("--verify-writes");
+        }
         exec.current_dir(&self.exec_dir);
+        // `SIGINT` from terminal only stops fuzzer after the test, executor is not killed by it
+        exec.process_group(0);
+        exec.env(
+            "DIFFUZZER_REMOUNT",
+            self.fs_mount.remount_cmd(&self.fs_dir, self.device),
+        );
+        exec.env(
+            "DIFFUZZER_INTERNAL_DIRS",
+            self.fs_mount.get_internal_dirs().patterns().join("\n"),
+        );
         let output = exec
             .output()
             .with_context(|| format!("failed to run executable '{:?}'", exec))?;
 
-        match hash_holder {
-            Some(holder) => { holder.calc_and_save_hash() }
-            _ => {}
-        }
+        let verdict = observe_all(observers).with_context(|| format!("failed to run observers"))?;
 
-        if !keep_fs {
+        self.mounted_runs
+            .set(self.mounted_runs.get().map(|runs| runs + 1));
+        if !keep_fs && self.options.persistent_runs.is_none() {
             self.teardown()?;
         }
 
@@ -79,7 +159,70 @@ impl Harness {
                 .with_context(|| format!("failed to convert stderr to string"))?,
         );
 
-        Ok(output.status.success())
+        Ok(verdict)
+    }
+
+    /// Replays input keeping filesystem mounted, then unmounts and checks it on device.
+    ///
+    /// Filesystem kept mounted in persistent mode is checked as is, without replaying.
+    /// Returns `None` if filesystem has no check.
+    pub fn check(&self, input_path: &Path) -> anyhow::Result<Option<String>> {
+        if self.fs_mount.check_cmd(self.device).is_none() {
+            return Ok(None);
+        }
+        if self.mounted_runs.get().is_none() {
+            self.run(input_path, true, &mut [])
+                .with_context(|| format!("failed to replay input before check"))?;
+        }
+        self.fs_mount.unmount(&self.fs_dir).with_context(|| {
+            format!(
+                "failed to unmount fs '{}' at '{}'",
+                self.fs_mount,
+                self.fs_dir.display()
+            )
+        })?;
+        self.mounted_runs.set(None);
+        let output = self.fs_mount.check(self.device);
+        self.fs_mount.release(&self.fs_dir).with_context(|| {
+            format!(
+                "failed to release fs '{}' at '{}'",
+                self.fs_mount,
+                self.fs_dir.display()
+            )
+        })?;
+        output
+    }
+
+    pub fn fs_mount(&self) -> &'static dyn FileSystemMount {
+        self.fs_mount
+    }
+
+    pub fn set_mount(&mut self, fs_mount: &'static dyn FileSystemMount) {
+        if !std::ptr::addr_eq(self.fs_mount, fs_mount) {
+            self.expire();
+        }
+        self.fs_mount = fs_mount;
+    }
+
+    /// Filesystem kept mounted in persistent mode is recreated before the next run.
+    pub fn expire(&self) {
+        if self.mounted_runs.get().is_some() {
+            self.mounted_runs.set(Some(u32::MAX));
+        }
+    }
+
+    fn setup(&self) -> anyhow::Result<()> {
+        self.fs_mount
+            .setup(&self.fs_dir, self.device, self.options.ram_disk_size)
+            .with_context(|| {
+                format!(
+                    "failed to setup fs '{}' at '{}'",
+                    self.fs_mount,
+                    self.fs_dir.display()
+                )
+            })?;
+        self.mounted_runs.set(Some(0));
+        Ok(())
     }
 
     pub fn teardown(&self) -> anyhow::Result<()> {
@@ -90,6 +233,198 @@ impl Harness {
                 self.fs_dir.display()
             )
         })?;
+        self.mounted_runs.set(None);
         Ok(())
     }
 }
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        if self.options.persistent_runs.is_some() && self.mounted_runs.get().is_some() {
+            if let Err(err) = self.teardown() {
+                warn!("failed to teardown persistent filesystem: {:?}", err);
+            }
+        }
+    }
+}
+
+/// Checks free space and resets filesystem (once) if there is not enough of it.
+///
+/// Returns `true` if filesystem was reset.
+fn ensure_free_space(
+    min_free_space: u64,
+    mut probe: impl FnMut() -> anyhow::Result<u64>,
+    mut reset: impl FnMut() -> anyhow::Result<()>,
+) -> anyhow::Result<bool> {
+    let free_space = probe().with_context(|| format!("failed to probe free space"))?;
+    if free_space >= min_free_space {
+        return Ok(false);
+    }
+    warn!(
+        "not enough free space ({} < {} bytes), resetting filesystem",
+        free_space, min_free_space
+    );
+    reset().with_context(|| format!("failed to reset filesystem"))?;
+    let free_space = probe().with_context(|| format!("failed to probe free space"))?;
+    if free_space < min_free_space {
+        bail!(
+            "not enough free space after reset ({} < {} bytes)",
+            free_space,
+            min_free_space
+        );
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{
+        env, fmt, fs,
+        os::unix::fs::PermissionsExt,
+        path::PathBuf,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+
+    /// Plain directory instead of real filesystem, shared with tests of runner.
+    pub(crate) struct MockMount {
+        setups: AtomicUsize,
+    }
+
+    impl MockMount {
+        pub(crate) const fn new() -> Self {
+            Self {
+                setups: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    impl fmt::Display for MockMount {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "Mock")
+        }
+    }
+
+    impl FileSystemMount for MockMount {
+        fn setup(&self, path: &Path, _device: &str, _ram_disk_size: usize) -> anyhow::Result<()> {
+            self.setups.fetch_add(1, Ordering::SeqCst);
+            fs::create_dir_all(path)?;
+            Ok(())
+        }
+
+        fn teardown(&self, path: &Path) -> anyhow::Result<()> {
+            fs::remove_dir_all(path)?;
+            Ok(())
+        }
+
+        fn remount_cmd(&self, _path: &Path, _device: &str) -> String {
+            "true".to_owned()
+        }
+    }
+
+    static PERSISTENT: MockMount = MockMount::new();
+    static FRESH: MockMount = MockMount::new();
+
+    /// Harness with executable that creates a file in workspace.
+    fn mock_harness(
+        name: &str,
+        mount: &'static MockMount,
+        persistent_runs: Option<u32>,
+    ) -> (Harness, PathBuf, PathBuf) {
+        let dir = env::temp_dir().join(format!("DIFFuzzer-harness-{}-test", name));
+        fs::remove_dir_all(&dir).unwrap_or(());
+        let exec_dir = dir.join("exec");
+        fs::create_dir_all(&exec_dir).unwrap();
+        let input_path = dir.join("input.sh");
+        fs::write(
+            &input_path,
+            "#!/bin/sh\nmkdir -p \"$1\" && touch \"$1/file\"\n",
+        )
+        .unwrap();
+        fs::set_permissions(&input_path, fs::Permissions::from_mode(0o755)).unwrap();
+        let fs_dir = dir.join("fs");
+        let harness = Harness::new(
+            mount,
+            fs_dir.clone().into_boxed_path(),
+            exec_dir.into_boxed_path(),
+            Rc::new(RefCell::new(String::new())),
+            Rc::new(RefCell::new(String::new())),
+            "/dev/null",
+            HarnessOptions {
+                persistent_runs,
+                ..Default::default()
+            },
+        );
+        (harness, input_path, fs_dir)
+    }
+
+    #[test]
+    fn test_persistent_mount() {
+        let (harness, input_path, fs_dir) = mock_harness("persistent", &PERSISTENT, Some(3));
+        for run in 0..3 {
+            harness.run(&input_path, false, &mut []).unwrap();
+            assert_eq!(1, PERSISTENT.setups.load(Ordering::SeqCst));
+            assert!(fs_dir.join(format!("run-{}", run)).join("file").exists());
+        }
+        assert!(fs_dir.join("run-0").exists());
+
+        harness.run(&input_path, false, &mut []).unwrap();
+        assert_eq!(2, PERSISTENT.setups.load(Ordering::SeqCst));
+        assert!(fs_dir.join("run-0").exists());
+        assert!(!fs_dir.join("run-1").exists());
+
+        harness.expire();
+        harness.run(&input_path, false, &mut []).unwrap();
+        assert_eq!(3, PERSISTENT.setups.load(Ordering::SeqCst));
+
+        drop(harness);
+        assert!(!fs_dir.exists());
+    }
+
+    #[test]
+    fn test_fresh_mount() {
+        let (harness, input_path, fs_dir) = mock_harness("fresh", &FRESH, None);
+        for run in 1..=3 {
+            harness.run(&input_path, true, &mut []).unwrap();
+            assert_eq!(run, FRESH.setups.load(Ordering::SeqCst));
+            assert!(fs_dir.join("file").exists());
+        }
+    }
+
+    #[test]
+    fn test_enough_free_space() {
+        let mut resets = 0;
+        assert!(!ensure_free_space(
+            100,
+            || Ok(100),
+            || {
+                resets += 1;
+                Ok(())
+            }
+        )
+        .unwrap());
+        assert_eq!(0, resets);
+    }
+
+    #[test]
+    fn test_not_enough_free_space() {
+        let mut resets = 0;
+        let mut free_space = vec![1000, 10];
+        assert!(ensure_free_space(
+            100,
+            || Ok(free_space.pop().unwrap()),
+            || {
+                resets += 1;
+                Ok(())
+            }
+        )
+        .unwrap());
+        assert_eq!(1, resets);
+    }
+
+    #[test]
+    fn test_not_enough_free_space_after_reset() {
+        assert!(ensure_free_space(100, || Ok(10), || Ok(())).is_err());
+    }
+}
@@ -1,10 +1,13 @@
 use std::cmp::Ordering;
 use std::fmt::{Display, Formatter};
+use std::fs::{self, FileType};
 use std::hash::Hasher;
-use std::os::unix::fs::MetadataExt;
+use std::ops::Range;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
 use std::path::Path;
 
 use regex::RegexSet;
+use serde::{Deserialize, Serialize};
 use twox_hash::XxHash64;
 use walkdir::WalkDir;
 
@@ -13,32 +16,141 @@ use crate::hasher::hasher::FileDiff::OneExists;
 
 pub const DIFF_HASH_FILENAME: &str = "diff_hash.txt";
 
+/// Larger files are not read when contents are hashed, unless limit is set in [`HasherOptions`].
+pub const DEFAULT_CONTENT_LIMIT: u64 = 1024 * 1024;
+
+/// Recent times (in seconds) are compared in units of that, since filesystems run at different moments.
+///
+/// Times set explicitly (e.g. with `utimensat`) are whole units apart, so they are still told apart.
+pub const TIMES_GRANULARITY: i64 = 60 * 60;
+
+/// Times filesystems may set themselves, from year 2000 until 32-bit `time_t` overflows.
+/// Others (e.g. around epoch or overflows) can only be set explicitly, so they are compared exactly,
+/// otherwise clamping or truncation would go unnoticed.
+pub const RECENT_TIMES: Range<i64> = 946684800..2147483647;
+
+/// Type of node, special files are handled differently by filesystems.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileKind {
+    Dir,
+    File,
+    Symlink,
+    Socket,
+    Fifo,
+    Other,
+}
+
+impl From<FileType> for FileKind {
+    fn from(file_type: FileType) -> Self {
+        if file_type.is_dir() {
+            FileKind::Dir
+        } else if file_type.is_file() {
+            FileKind::File
+        } else if file_type.is_symlink() {
+            FileKind::Symlink
+        } else if file_type.is_socket() {
+            FileKind::Socket
+        } else if file_type.is_fifo() {
+            FileKind::Fifo
+        } else {
+            FileKind::Other
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct FileInfo {
     abs_path: String,
-    rel_path: String,
+    pub(super) rel_path: String,
+
+    pub(super) kind: FileKind,
 
     gid: u32,
     uid: u32,
+    /// Apparent size, holes included.
     size: u64,
+    /// Size of blocks allocated for data, holes excluded.
+    allocated: u64,
     nlink: u64,
     mode: u32,
+    /// Link target as stored, without resolving it (target may not exist).
+    symlink_target: Option<String>,
+    /// Extended attributes sorted by name, only collected if enabled in [`HasherOptions`].
+    xattrs: Vec<(String, Vec<u8>)>,
+    /// Hash of regular file data, only collected if enabled in [`HasherOptions`] and file is not too large.
+    content_hash: Option<u64>,
+    /// Access time in seconds.
+    atime: i64,
+    /// Modification time in seconds.
+    mtime: i64,
 }
 
 impl FileInfo {
     fn add_to_hasher(&self, hasher: &mut dyn Hasher, hasher_options: &HasherOptions) {
         hasher.write(self.rel_path.as_bytes());
+        hasher.write_u8(self.kind as u8);
         hasher.write_u32(self.gid);
         hasher.write_u32(self.uid);
         if hasher_options.size {
             hasher.write_u64(self.size);
         }
+        if hasher_options.allocated {
+            hasher.write_u64(self.allocated);
+        }
         if hasher_options.nlink {
             hasher.write_u64(self.nlink);
         }
         if hasher_options.mode {
             hasher.write_u32(self.mode);
         }
+        if hasher_options.symlink {
+            if let Some(target) = &self.symlink_target {
+                hasher.write(target.as_bytes());
+            }
+        }
+        if hasher_options.xattr {
+            for (name, value) in self.xattrs.iter() {
+                hasher.write(name.as_bytes());
+                hasher.write(value);
+            }
+        }
+        if hasher_options.content {
+            if let Some(content_hash) = self.content_hash {
+                hasher.write_u64(content_hash);
+            }
+        }
+        if hasher_options.times {
+            let (atime, mtime) = self.coarse_times();
+            hasher.write_i64(atime);
+            hasher.write_i64(mtime);
+        }
+    }
+
+    /// Recent times are rounded down to [`TIMES_GRANULARITY`], which keeps them in [`RECENT_TIMES`],
+    /// so they never collide with times compared exactly.
+    fn coarse_times(&self) -> (i64, i64) {
+        let coarse = |time: i64| {
+            if RECENT_TIMES.contains(&time) {
+                time - time.rem_euclid(TIMES_GRANULARITY)
+            } else {
+                time
+            }
+        };
+        (coarse(self.atime), coarse(self.mtime))
+    }
+
+    /// Path relative to hashed directory, empty for directory itself.
+    pub fn rel_path(&self) -> &str {
+        &self.rel_path
+    }
+
+    /// Access and modification times in seconds.
+    pub fn times(&self) -> (i64, i64) {
+        (self.atime, self.mtime)
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.kind == FileKind::Dir
     }
 }
 
@@ -48,18 +160,114 @@ pub enum FileDiff {
     OneExists(FileInfo),
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct HasherOptions {
     size: bool,
+    allocated: bool,
     nlink: bool,
     mode: bool,
+    symlink: bool,
+    xattr: bool,
+    content: bool,
+    /// Files larger than that (in bytes) are not read.
+    content_limit: u64,
+    /// Access and modification times, recent ones with [`TIMES_GRANULARITY`].
+    times: bool,
+}
+
+/// Attributes that can be toggled in [`HasherOptions`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Attribute {
+    Size,
+    Allocated,
+    Nlink,
+    Mode,
+    Symlink,
+    Xattr,
+    Content,
+    Times,
+}
+
+impl HasherOptions {
+    /// Every attribute except allocated size, which depends on block allocation policy of filesystem
+    /// (e.g. inline data or preallocation) and has to be enabled explicitly.
+    /// Contents are not read either, because it is expensive.
+    /// Times are not included too, since they are updated by filesystem and depend on mount options.
+    pub fn all() -> Self {
+        Self {
+            size: true,
+            allocated: false,
+            nlink: true,
+            mode: true,
+            symlink: true,
+            xattr: true,
+            content: false,
+            content_limit: DEFAULT_CONTENT_LIMIT,
+            times: false,
+        }
+    }
+
+    /// Contents of regular files up to `limit` bytes are hashed too.
+    pub fn with_content_limit(&self, limit: u64) -> Self {
+        let mut options = self.with(Attribute::Content);
+        options.content_limit = limit;
+        options
+    }
+
+    pub fn enabled(&self) -> Vec<Attribute> {
+        [
+            (Attribute::Size, self.size),
+            (Attribute::Allocated, self.allocated),
+            (Attribute::Nlink, self.nlink),
+            (Attribute::Mode, self.mode),
+            (Attribute::Symlink, self.symlink),
+            (Attribute::Xattr, self.xattr),
+            (Attribute::Content, self.content),
+            (Attribute::Times, self.times),
+        ]
+        .into_iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(attribute, _)| attribute)
+        .collect()
+    }
+
+    pub fn without(&self, attribute: Attribute) -> Self {
+        self.set(attribute, false)
+    }
+
+    pub fn with(&self, attribute: Attribute) -> Self {
+        self.set(attribute, true)
+    }
+
+    fn set(&self, attribute: Attribute, enabled: bool) -> Self {
+        let mut options = self.clone();
+        match attribute {
+            Attribute::Size => options.size = enabled,
+            Attribute::Allocated => options.allocated = enabled,
+            Attribute::Nlink => options.nlink = enabled,
+            Attribute::Mode => options.mode = enabled,
+            Attribute::Symlink => options.symlink = enabled,
+            Attribute::Xattr => options.xattr = enabled,
+            Attribute::Content => options.content = enabled,
+            Attribute::Times => options.times = enabled,
+        }
+        options
+    }
 }
 
 impl Default for HasherOptions {
     fn default() -> Self {
         Self {
             size: false,
+            allocated: false,
             nlink: false,
             mode: false,
+            symlink: false,
+            xattr: false,
+            content: false,
+            content_limit: DEFAULT_CONTENT_LIMIT,
+            times: false,
         }
     }
 }
@@ -70,6 +278,11 @@ impl Display for FileInfo {
     }
 }
 
+/// Internal directories of filesystem along with patterns excluded by user.
+pub fn with_excluded(internal: &RegexSet, exclude: &[String]) -> Result<RegexSet, regex::Error> {
+    RegexSet::new(internal.patterns().iter().chain(exclude))
+}
+
 pub fn calc_dir_hash(
     path: &Path,
     skip: &RegexSet,
@@ -86,15 +299,41 @@ pub fn calc_dir_hash(
             continue;
         }
 
+        // links are not followed, so metadata is of the link itself
         let metadata = entry.metadata().unwrap();
+        let symlink_target = if entry.path_is_symlink() {
+            let target = fs::read_link(entry.path()).unwrap();
+            Some(target.to_string_lossy().into_owned())
+        } else {
+            None
+        };
         let file_info = FileInfo {
             abs_path: entry.path().to_str().unwrap().to_owned(),
             rel_path: rel_path.to_owned(),
+            kind: metadata.file_type().into(),
             gid: metadata.gid(),
             uid: metadata.uid(),
             size: metadata.size(),
+            // `st_blocks` is always in 512-byte units
+            allocated: metadata.blocks() * 512,
             nlink: metadata.nlink(),
             mode: metadata.mode(),
+            symlink_target,
+            xattrs: if hasher_options.xattr {
+                read_xattrs(entry.path())
+            } else {
+                vec![]
+            },
+            content_hash: if hasher_options.content
+                && metadata.is_file()
+                && metadata.size() <= hasher_options.content_limit
+            {
+                hash_content(entry.path())
+            } else {
+                None
+            },
+            atime: metadata.atime(),
+            mtime: metadata.mtime(),
         };
         file_info.add_to_hasher(&mut hasher, hasher_options);
         res.push(file_info);
@@ -103,14 +342,42 @@ pub fn calc_dir_hash(
     return (hasher.finish(), res);
 }
 
+/// `None` if file can't be read.
+fn hash_content(path: &Path) -> Option<u64> {
+    let data = fs::read(path).ok()?;
+    let mut hasher = XxHash64::default();
+    hasher.write(&data);
+    Some(hasher.finish())
+}
+
+/// User attributes of node itself (symlinks are not followed), filesystem without xattr support has none.
+///
+/// Other namespaces are set by filesystem or kernel (e.g. `security.selinux`), not by workload.
+fn read_xattrs(path: &Path) -> Vec<(String, Vec<u8>)> {
+    let Ok(names) = xattr::list(path) else {
+        return vec![];
+    };
+    let mut xattrs: Vec<(String, Vec<u8>)> = names
+        .filter(|name| name.as_encoded_bytes().starts_with(b"user."))
+        .filter_map(|name| {
+            let value = xattr::get(path, &name).ok().flatten()?;
+            Some((name.to_string_lossy().into_owned(), value))
+        })
+        .collect();
+    xattrs.sort();
+    xattrs
+}
+
 pub fn calc_fileinfo_hash(
     vec: &Vec<FileInfo>,
     rel_path: &String,
+    skip: &RegexSet,
     hasher_options: &HasherOptions,
 ) -> u64 {
     let mut hasher = XxHash64::default();
     for file_info in vec {
-        if file_info.rel_path.starts_with(rel_path.as_str()) {
+        if file_info.rel_path.starts_with(rel_path.as_str()) && !skip.is_match(&file_info.rel_path)
+        {
             file_info.add_to_hasher(&mut hasher, hasher_options);
         }
     }
@@ -149,9 +416,9 @@ pub fn get_diff(
         match cmp_res {
             Ordering::Equal => {
                 let hash_fst =
-                    calc_fileinfo_hash(vec_fst, &vec_fst[i_fst].rel_path, hasher_options);
+                    calc_fileinfo_hash(vec_fst, &vec_fst[i_fst].rel_path, fst_skip, hasher_options);
                 let hash_snd =
-                    calc_fileinfo_hash(vec_snd, &vec_snd[i_snd].rel_path, hasher_options);
+                    calc_fileinfo_hash(vec_snd, &vec_snd[i_snd].rel_path, snd_skip, hasher_options);
                 if hash_fst != hash_snd {
                     res.push(DifferentHash {
                         fst: vec_fst[i_fst].clone(),
@@ -187,6 +454,84 @@ pub fn get_diff(
     res
 }
 
+/// Disables attributes one by one while filesystems still differ,
+/// then describes single file difference left (e.g. "only mode of '0/1' differs").
+pub fn isolate_divergence(
+    vec_fst: &Vec<FileInfo>,
+    vec_snd: &Vec<FileInfo>,
+    fst_skip: &RegexSet,
+    snd_skip: &RegexSet,
+    hasher_options: &HasherOptions,
+) -> Option<(HasherOptions, String)> {
+    let diff = |options: &HasherOptions| get_diff(vec_fst, vec_snd, fst_skip, snd_skip, options);
+    if diff(hasher_options).is_empty() {
+        return None;
+    }
+    let mut options = hasher_options.clone();
+    for attribute in hasher_options.enabled() {
+        let reduced = options.without(attribute);
+        if !diff(&reduced).is_empty() {
+            options = reduced;
+        }
+    }
+    let description = diff(&options)
+        .iter()
+        .find_map(|file_diff| describe_diff(file_diff, &options))?;
+    Some((options, description))
+}
+
+/// Parent directories differ only because their children do, so they are not described.
+pub fn describe_diff(diff: &FileDiff, hasher_options: &HasherOptions) -> Option<String> {
+    match diff {
+        OneExists(info) => Some(format!("only one filesystem has '{}'", info.rel_path)),
+        DifferentHash { fst, snd } => {
+            let mut attributes = vec![];
+            if fst.kind != snd.kind {
+                attributes.push("kind");
+            }
+            if fst.gid != snd.gid {
+                attributes.push("gid");
+            }
+            if fst.uid != snd.uid {
+                attributes.push("uid");
+            }
+            for attribute in hasher_options.enabled() {
+                match attribute {
+                    Attribute::Size if fst.size != snd.size => attributes.push("size"),
+                    Attribute::Allocated if fst.allocated != snd.allocated => {
+                        attributes.push("allocated size")
+                    }
+                    Attribute::Nlink if fst.nlink != snd.nlink => attributes.push("nlink"),
+                    Attribute::Mode if fst.mode != snd.mode => attributes.push("mode"),
+                    Attribute::Symlink if fst.symlink_target != snd.symlink_target => {
+                        attributes.push("symlink target")
+                    }
+                    Attribute::Xattr if fst.xattrs != snd.xattrs => attributes.push("xattrs"),
+                    Attribute::Content if fst.content_hash != snd.content_hash => {
+                        attributes.push("content")
+                    }
+                    Attribute::Times if fst.coarse_times() != snd.coarse_times() => {
+                        attributes.push("times")
+                    }
+                    _ => {}
+                }
+            }
+            match attributes.len() {
+                0 => None,
+                1 => Some(format!(
+                    "only {} of '{}' differs",
+                    attributes[0], fst.rel_path
+                )),
+                _ => Some(format!(
+                    "{} of '{}' differ",
+                    attributes.join(", "),
+                    fst.rel_path
+                )),
+            }
+        }
+    }
+}
+
 fn handle_last_diff(mut i: usize, vec_data: &Vec<FileInfo>, res: &mut Vec<FileDiff>) {
     if i > 0 {
         loop {
@@ -1,11 +1,18 @@
-use std::fs::File;
+use std::fs::{File, FileTimes};
 use std::io::Write;
-use std::path::PathBuf;
+use std::os::unix::fs::{symlink, PermissionsExt};
+use std::os::unix::net::UnixListener;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::{env, fs};
 
 use anyhow::Context;
 
-use crate::hasher::hasher::{calc_dir_hash, get_diff};
+use crate::hasher::hasher::{
+    calc_dir_hash, describe_diff, get_diff, isolate_divergence, with_excluded, Attribute, FileDiff,
+    FileKind, HasherOptions, TIMES_GRANULARITY,
+};
 use crate::mount::ext4::Ext4;
 use crate::mount::mount::FileSystemMount;
 
@@ -92,6 +99,42 @@ fn test_hash_eq_skip() {
     assert_eq!(diff.len(), 0);
 }
 
+#[test]
+fn test_file_kind() {
+    let temp_dir = env::temp_dir().join("DIFFuzzer-hash-kind-test");
+    fs::remove_dir_all(temp_dir.as_path()).unwrap_or(());
+    let kinds = ["socket", "fifo", "file"];
+    for kind in kinds {
+        fs::create_dir_all(temp_dir.join(kind)).unwrap();
+    }
+    let _socket = UnixListener::bind(temp_dir.join("socket").join("node")).unwrap();
+    assert!(Command::new("mkfifo")
+        .arg(temp_dir.join("fifo").join("node"))
+        .status()
+        .unwrap()
+        .success());
+    File::create(temp_dir.join("file").join("node")).unwrap();
+
+    let skip = Ext4::new().get_internal_dirs();
+    let hash_options = Default::default();
+    let hashed: Vec<(u64, FileKind)> = kinds
+        .iter()
+        .map(|kind| {
+            let (hash, content) = calc_dir_hash(&temp_dir.join(kind), &skip, &hash_options);
+            let node = content.iter().find(|info| info.rel_path == "node").unwrap();
+            (hash, node.kind)
+        })
+        .collect();
+    assert_eq!(FileKind::Socket, hashed[0].1);
+    assert_eq!(FileKind::Fifo, hashed[1].1);
+    assert_eq!(FileKind::File, hashed[2].1);
+    assert_ne!(hashed[0].0, hashed[1].0);
+    assert_ne!(hashed[0].0, hashed[2].0);
+    assert_ne!(hashed[1].0, hashed[2].0);
+
+    fs::remove_dir_all(temp_dir).unwrap();
+}
+
 fn create_data_for_test(dirs: Vec<&str>, files: Vec<&str>, data: Vec<&str>) -> Vec<PathBuf> {
     let temp_dir = env::temp_dir().join("DIFFuzzer-hash-test");
     fs::remove_dir_all(temp_dir.as_path()).unwrap_or(());
@@ -129,3 +172,339 @@ fn create_data_for_test(dirs: Vec<&str>, files: Vec<&str>, data: Vec<&str>) -> V
 
     cmp_dirs
 }
+
+#[test]
+fn test_isolate_single_attribute() {
+    let temp_dir = env::temp_dir().join("DIFFuzzer-hash-isolate-test");
+    fs::remove_dir_all(temp_dir.as_path()).unwrap_or(());
+    let fst = temp_dir.join("fst");
+    let snd = temp_dir.join("snd");
+    for dir in [&fst, &snd] {
+        fs::create_dir_all(dir.join("0")).unwrap();
+        fs::write(dir.join("0").join("1"), "data").unwrap();
+        fs::write(dir.join("0").join("2"), "data").unwrap();
+    }
+    let skip = Ext4::new().get_internal_dirs();
+    let all = HasherOptions::all();
+    let isolate = || {
+        let (_, fst_content) = calc_dir_hash(&fst, &skip, &all);
+        let (_, snd_content) = calc_dir_hash(&snd, &skip, &all);
+        isolate_divergence(&fst_content, &snd_content, &skip, &skip, &all)
+    };
+
+    assert_eq!(None, isolate());
+
+    fs::set_permissions(snd.join("0").join("1"), fs::Permissions::from_mode(0o600)).unwrap();
+    let (options, description) = isolate().unwrap();
+    assert_eq!(vec![Attribute::Mode], options.enabled());
+    assert_eq!("only mode of '0/1' differs", description);
+
+    fs::set_permissions(snd.join("0").join("1"), fs::Permissions::from_mode(0o644)).unwrap();
+    fs::set_permissions(fst.join("0").join("1"), fs::Permissions::from_mode(0o644)).unwrap();
+    fs::write(snd.join("0").join("2"), "more data").unwrap();
+    let (options, description) = isolate().unwrap();
+    assert_eq!(vec![Attribute::Size], options.enabled());
+    assert_eq!("only size of '0/2' differs", description);
+
+    fs::write(snd.join("0").join("2"), "data").unwrap();
+    fs::hard_link(snd.join("0").join("1"), snd.join("1")).unwrap();
+    fs::hard_link(fst.join("0").join("1"), fst.join("1")).unwrap();
+    fs::remove_file(fst.join("1")).unwrap();
+    let (options, description) = isolate().unwrap();
+    assert!(options.enabled().is_empty());
+    assert_eq!("only one filesystem has '1'", description);
+
+    fs::remove_dir_all(temp_dir).unwrap();
+}
+
+#[test]
+fn test_dangling_symlink() {
+    let temp_dir = env::temp_dir().join("DIFFuzzer-hash-symlink-test");
+    fs::remove_dir_all(temp_dir.as_path()).unwrap_or(());
+    let fst = temp_dir.join("fst");
+    let snd = temp_dir.join("snd");
+    for dir in [&fst, &snd] {
+        fs::create_dir_all(dir.join("0")).unwrap();
+        fs::write(dir.join("1"), "data").unwrap();
+    }
+    symlink("missing", fst.join("0").join("link")).unwrap();
+    symlink("other", snd.join("0").join("link")).unwrap();
+
+    let skip = Ext4::new().get_internal_dirs();
+    let symlink_options = HasherOptions::all()
+        .without(Attribute::Size)
+        .without(Attribute::Nlink)
+        .without(Attribute::Mode);
+    let (fst_hash, fst_content) = calc_dir_hash(&fst, &skip, &symlink_options);
+    let (snd_hash, snd_content) = calc_dir_hash(&snd, &skip, &symlink_options);
+    let link = fst_content
+        .iter()
+        .find(|info| info.rel_path == "0/link")
+        .unwrap();
+    assert_eq!(FileKind::Symlink, link.kind);
+    assert_ne!(fst_hash, snd_hash);
+    let diff = get_diff(&fst_content, &snd_content, &skip, &skip, &symlink_options);
+    assert_eq!(3, diff.len());
+    assert!(diff
+        .iter()
+        .all(|d| matches!(d, FileDiff::DifferentHash { .. })));
+    assert_eq!(
+        Some("only symlink target of '0/link' differs".to_owned()),
+        describe_diff(&diff[0], &symlink_options)
+    );
+
+    let default_options = Default::default();
+    let (fst_hash, _) = calc_dir_hash(&fst, &skip, &default_options);
+    let (snd_hash, _) = calc_dir_hash(&snd, &skip, &default_options);
+    assert_eq!(fst_hash, snd_hash);
+
+    // dangling link is not the same as the file it would point to
+    fs::remove_file(snd.join("0").join("link")).unwrap();
+    fs::write(snd.join("0").join("link"), "").unwrap();
+    let (snd_hash, snd_content) = calc_dir_hash(&snd, &skip, &default_options);
+    let (fst_hash, fst_content) = calc_dir_hash(&fst, &skip, &default_options);
+    assert_ne!(fst_hash, snd_hash);
+    assert_eq!(
+        Some("only kind of '0/link' differs".to_owned()),
+        describe_diff(
+            &get_diff(&fst_content, &snd_content, &skip, &skip, &default_options)[0],
+            &default_options
+        )
+    );
+
+    fs::remove_dir_all(temp_dir).unwrap();
+}
+
+#[test]
+fn test_xattr() {
+    let temp_dir = env::temp_dir().join("DIFFuzzer-hash-xattr-test");
+    fs::remove_dir_all(temp_dir.as_path()).unwrap_or(());
+    let fst = temp_dir.join("fst");
+    let snd = temp_dir.join("snd");
+    for dir in [&fst, &snd] {
+        fs::create_dir_all(dir.join("0")).unwrap();
+        fs::write(dir.join("0").join("1"), "data").unwrap();
+    }
+    if xattr::set(fst.join("0").join("1"), "user.0", b"value").is_err() {
+        // temporary directory is on filesystem without user xattrs (e.g. tmpfs on older kernels)
+        fs::remove_dir_all(temp_dir).unwrap();
+        return;
+    }
+    xattr::set(snd.join("0").join("1"), "user.0", b"other").unwrap();
+
+    let skip = Ext4::new().get_internal_dirs();
+    let xattr_options = HasherOptions::all()
+        .without(Attribute::Size)
+        .without(Attribute::Nlink)
+        .without(Attribute::Mode)
+        .without(Attribute::Symlink);
+    let (fst_hash, fst_content) = calc_dir_hash(&fst, &skip, &xattr_options);
+    let (snd_hash, snd_content) = calc_dir_hash(&snd, &skip, &xattr_options);
+    assert_ne!(fst_hash, snd_hash);
+    let diff = get_diff(&fst_content, &snd_content, &skip, &skip, &xattr_options);
+    assert_eq!(
+        Some("only xattrs of '0/1' differs".to_owned()),
+        describe_diff(&diff[0], &xattr_options)
+    );
+
+    let default_options = Default::default();
+    let (fst_hash, _) = calc_dir_hash(&fst, &skip, &default_options);
+    let (snd_hash, _) = calc_dir_hash(&snd, &skip, &default_options);
+    assert_eq!(fst_hash, snd_hash);
+
+    xattr::set(snd.join("0").join("1"), "user.0", b"value").unwrap();
+    let (fst_hash, _) = calc_dir_hash(&fst, &skip, &xattr_options);
+    let (snd_hash, _) = calc_dir_hash(&snd, &skip, &xattr_options);
+    assert_eq!(fst_hash, snd_hash);
+
+    xattr::remove(snd.join("0").join("1"), "user.0").unwrap();
+    let (fst_hash, _) = calc_dir_hash(&fst, &skip, &xattr_options);
+    let (snd_hash, _) = calc_dir_hash(&snd, &skip, &xattr_options);
+    assert_ne!(fst_hash, snd_hash);
+
+    fs::remove_dir_all(temp_dir).unwrap();
+}
+
+#[test]
+fn test_allocated_size() {
+    let temp_dir = env::temp_dir().join("DIFFuzzer-hash-allocated-test");
+    fs::remove_dir_all(temp_dir.as_path()).unwrap_or(());
+    let fst = temp_dir.join("fst");
+    let snd = temp_dir.join("snd");
+    for dir in [&fst, &snd] {
+        fs::create_dir_all(dir.join("0")).unwrap();
+    }
+    let size = 1024 * 1024;
+    // same apparent size, but only one of them is backed by data
+    File::create(fst.join("0").join("1"))
+        .unwrap()
+        .set_len(size)
+        .unwrap();
+    fs::write(snd.join("0").join("1"), vec![1u8; size as usize]).unwrap();
+
+    let skip = Ext4::new().get_internal_dirs();
+    let all = HasherOptions::all();
+    let (fst_hash, _) = calc_dir_hash(&fst, &skip, &all);
+    let (snd_hash, _) = calc_dir_hash(&snd, &skip, &all);
+    assert_eq!(fst_hash, snd_hash);
+
+    let allocated_options = HasherOptions::default().with(Attribute::Allocated);
+    assert_eq!(vec![Attribute::Allocated], allocated_options.enabled());
+    let (fst_hash, fst_content) = calc_dir_hash(&fst, &skip, &allocated_options);
+    let (snd_hash, snd_content) = calc_dir_hash(&snd, &skip, &allocated_options);
+    if fst_hash == snd_hash {
+        // temporary directory is on filesystem without sparse files
+        fs::remove_dir_all(temp_dir).unwrap();
+        return;
+    }
+    let diff = get_diff(&fst_content, &snd_content, &skip, &skip, &allocated_options);
+    assert_eq!(
+        Some("only allocated size of '0/1' differs".to_owned()),
+        describe_diff(&diff[0], &allocated_options)
+    );
+
+    fs::remove_dir_all(temp_dir).unwrap();
+}
+
+#[test]
+fn test_user_excluded_paths() {
+    let temp_dir = env::temp_dir().join("DIFFuzzer-hash-exclude-test");
+    fs::remove_dir_all(temp_dir.as_path()).unwrap_or(());
+    let fst = temp_dir.join("fst");
+    let snd = temp_dir.join("snd");
+    for dir in [&fst, &snd] {
+        fs::create_dir_all(dir.join("0")).unwrap();
+        fs::write(dir.join("0").join("1"), "data").unwrap();
+    }
+    // e.g. log that is written differently by each filesystem
+    fs::write(fst.join("noise"), "a").unwrap();
+    fs::write(snd.join("noise"), "bb").unwrap();
+
+    let internal = Ext4::new().get_internal_dirs();
+    let options = HasherOptions::all();
+    let (_, fst_content) = calc_dir_hash(&fst, &internal, &options);
+    let (_, snd_content) = calc_dir_hash(&snd, &internal, &options);
+    assert!(!get_diff(&fst_content, &snd_content, &internal, &internal, &options).is_empty());
+
+    let skip = with_excluded(&internal, &[r"^noise($|/)".to_owned()]).unwrap();
+    assert!(skip.is_match("lost+found"));
+    assert!(get_diff(&fst_content, &snd_content, &skip, &skip, &options).is_empty());
+    let (fst_hash, _) = calc_dir_hash(&fst, &skip, &options);
+    let (snd_hash, _) = calc_dir_hash(&snd, &skip, &options);
+    assert_eq!(fst_hash, snd_hash);
+
+    assert!(with_excluded(&internal, &["(".to_owned()]).is_err());
+
+    fs::remove_dir_all(temp_dir).unwrap();
+}
+
+#[test]
+fn test_content() {
+    let temp_dir = env::temp_dir().join("DIFFuzzer-hash-content-test");
+    fs::remove_dir_all(temp_dir.as_path()).unwrap_or(());
+    let fst = temp_dir.join("fst");
+    let snd = temp_dir.join("snd");
+    for dir in [&fst, &snd] {
+        fs::create_dir_all(dir.join("0")).unwrap();
+    }
+    // same metadata, different data
+    fs::write(fst.join("0").join("1"), "aaaa").unwrap();
+    fs::write(snd.join("0").join("1"), "bbbb").unwrap();
+
+    let skip = Ext4::new().get_internal_dirs();
+    let all = HasherOptions::all();
+    let (fst_hash, _) = calc_dir_hash(&fst, &skip, &all);
+    let (snd_hash, _) = calc_dir_hash(&snd, &skip, &all);
+    assert_eq!(fst_hash, snd_hash);
+
+    let content_options = HasherOptions::default().with_content_limit(1024);
+    assert_eq!(vec![Attribute::Content], content_options.enabled());
+    let (fst_hash, fst_content) = calc_dir_hash(&fst, &skip, &content_options);
+    let (snd_hash, snd_content) = calc_dir_hash(&snd, &skip, &content_options);
+    assert_ne!(fst_hash, snd_hash);
+    let diff = get_diff(&fst_content, &snd_content, &skip, &skip, &content_options);
+    assert_eq!(
+        Some("only content of '0/1' differs".to_owned()),
+        describe_diff(&diff[0], &content_options)
+    );
+
+    // files above limit are not read
+    let small_limit = HasherOptions::default().with_content_limit(2);
+    let (fst_hash, _) = calc_dir_hash(&fst, &skip, &small_limit);
+    let (snd_hash, _) = calc_dir_hash(&snd, &skip, &small_limit);
+    assert_eq!(fst_hash, snd_hash);
+
+    fs::remove_dir_all(temp_dir).unwrap();
+}
+
+#[test]
+fn test_times() {
+    let temp_dir = env::temp_dir().join("DIFFuzzer-hash-times-test");
+    fs::remove_dir_all(temp_dir.as_path()).unwrap_or(());
+    let fst = temp_dir.join("fst");
+    let snd = temp_dir.join("snd");
+    // in the future, so that walking directories does not update atime (relatime),
+    // and at the start of granularity unit, so that times a minute apart are in the same one
+    let future = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        + 10 * 24 * 60 * 60;
+    let granularity = TIMES_GRANULARITY as u64;
+    let base_secs = (future / granularity * granularity) as i64;
+    let base = UNIX_EPOCH + Duration::from_secs(base_secs as u64);
+    let set_times = |path: &Path, atime: SystemTime, mtime: SystemTime| {
+        File::open(path)
+            .unwrap()
+            .set_times(FileTimes::new().set_accessed(atime).set_modified(mtime))
+            .unwrap();
+    };
+    for (dir, mtime) in [(&fst, base), (&snd, base + Duration::from_secs(1))] {
+        fs::create_dir_all(dir.join("0")).unwrap();
+        fs::write(dir.join("0").join("1"), "aaaa").unwrap();
+        for path in [dir.join("0").join("1"), dir.join("0"), dir.to_path_buf()] {
+            set_times(&path, base + Duration::from_secs(60), base);
+        }
+        // off by a second is within granularity
+        set_times(&dir.join("0").join("1"), base, mtime);
+    }
+
+    let skip = Ext4::new().get_internal_dirs();
+    let times = HasherOptions::default().with(Attribute::Times);
+    let (fst_hash, _) = calc_dir_hash(&fst, &skip, &times);
+    let (snd_hash, _) = calc_dir_hash(&snd, &skip, &times);
+    assert_eq!(fst_hash, snd_hash);
+
+    set_times(
+        &snd.join("0").join("1"),
+        base,
+        base + Duration::from_secs(10 * 60 * 60),
+    );
+    let all = HasherOptions::all();
+    let (fst_hash, _) = calc_dir_hash(&fst, &skip, &all);
+    let (snd_hash, _) = calc_dir_hash(&snd, &skip, &all);
+    assert_eq!(fst_hash, snd_hash);
+    let (fst_hash, fst_content) = calc_dir_hash(&fst, &skip, &times);
+    let (snd_hash, snd_content) = calc_dir_hash(&snd, &skip, &times);
+    assert_ne!(fst_hash, snd_hash);
+    let diff = get_diff(&fst_content, &snd_content, &skip, &skip, &times);
+    assert_eq!(
+        Some("only times of '0/1' differs".to_owned()),
+        describe_diff(&diff[0], &times)
+    );
+
+    // times around epoch can only be set explicitly, so they are compared exactly
+    set_times(&fst.join("0").join("1"), base, UNIX_EPOCH);
+    set_times(
+        &snd.join("0").join("1"),
+        base,
+        UNIX_EPOCH + Duration::from_secs(1),
+    );
+    let (fst_hash, fst_content) = calc_dir_hash(&fst, &skip, &times);
+    let (snd_hash, snd_content) = calc_dir_hash(&snd, &skip, &times);
+    assert_ne!(fst_hash, snd_hash);
+    assert_eq!((base_secs, 0), fst_content[2].times());
+    assert_eq!((base_secs, 1), snd_content[2].times());
+
+    fs::remove_dir_all(temp_dir).unwrap();
+}
@@ -0,0 +1,209 @@
+use std::{fs, path::Path};
+
+use anyhow::{bail, Context};
+use log::info;
+
+use dif_fuzzer::{
+    abstract_fs::{
+        fs::AbstractFS,
+        trace::{Trace, TRACE_FILENAME},
+        workload::Workload,
+    },
+    fuzzing::common::parse_trace,
+    save::{KERNEL_FILENAME, TESTCASE_JSON_FILENAME},
+};
+
+/// Outputs of testcase run by filesystem in single mode, see [`crate::single::run`].
+struct KernelRun {
+    /// Kernel run was made on, as in `/proc/version`.
+    kernel: String,
+    input: Workload,
+    trace: Trace,
+    /// `None` if run was saved without hash.
+    hash: Option<u64>,
+}
+
+impl KernelRun {
+    fn load(dir: &Path, fs_name: &str) -> anyhow::Result<Self> {
+        let read = |filename: &str| {
+            let path = dir.join(filename);
+            fs::read_to_string(&path)
+                .with_context(|| format!("failed to read '{}'", path.display()))
+        };
+        let input = serde_json::from_str(&read(TESTCASE_JSON_FILENAME)?)
+            .with_context(|| format!("failed to parse testcase"))?;
+        let hash = match read(&format!("{}.hash.txt", fs_name)) {
+            Ok(hash) => Some(
+                hash.trim()
+                    .parse()
+                    .with_context(|| format!("failed to parse hash '{}'", hash.trim()))?,
+            ),
+            Err(_) => None,
+        };
+        Ok(Self {
+            kernel: read(KERNEL_FILENAME)?.trim().to_owned(),
+            input,
+            trace: parse_trace(&dir.join(format!("{}.{}", fs_name, TRACE_FILENAME)))?,
+            hash,
+        })
+    }
+}
+
+/// Compares the same testcase run by filesystem (in single mode) on two kernels,
+/// e.g. built with different configs. Booting each kernel is left to user.
+///
+/// Returns `true` if runs diverged, divergence is config-sensitive, since only kernel differs.
+pub fn run(fst_dir: &Path, snd_dir: &Path, fs_name: &str) -> anyhow::Result<bool> {
+    info!(
+        "comparing runs of '{}' at '{}' and '{}'",
+        fs_name,
+        fst_dir.display(),
+        snd_dir.display()
+    );
+    let fst = KernelRun::load(fst_dir, fs_name)
+        .with_context(|| format!("failed to load run at '{}'", fst_dir.display()))?;
+    let snd = KernelRun::load(snd_dir, fs_name)
+        .with_context(|| format!("failed to load run at '{}'", snd_dir.display()))?;
+    match divergence(fs_name, &fst, &snd)? {
+        Some(reason) => {
+            print!("{}", reason);
+            Ok(true)
+        }
+        None => {
+            println!("no divergence");
+            Ok(false)
+        }
+    }
+}
+
+/// Reason noting kernels and which of them failed where model expects success, `None` if runs agree.
+fn divergence(fs_name: &str, fst: &KernelRun, snd: &KernelRun) -> anyhow::Result<Option<String>> {
+    if fst.input != snd.input {
+        bail!("runs are of different testcases");
+    }
+    let mut reasons = vec![];
+    if let Some(index) = fst.trace.first_difference(&snd.trace, &[]) {
+        let describe = match fst.input.ops.get(index) {
+            Some(op) => format!("{} ({:?})", index, op.kind()),
+            None => format!("{}", index),
+        };
+        reasons.push(format!("traces differ at operation {}", describe));
+    }
+    if let (Some(fst_hash), Some(snd_hash)) = (fst.hash, snd.hash) {
+        if fst_hash != snd_hash {
+            reasons.push("filesystem contents differ".to_owned());
+        }
+    }
+    if reasons.is_empty() {
+        return Ok(None);
+    }
+    let expected = AbstractFS::expected_failures_of(&fst.input);
+    let diverged: Vec<&str> = [("first", fst), ("second", snd)]
+        .into_iter()
+        .filter(|(_, run)| run.trace.has_errors(&expected))
+        .map(|(side, _)| side)
+        .collect();
+    let diverged = match diverged.as_slice() {
+        [] => "none".to_owned(),
+        sides => sides.join(", "),
+    };
+    Ok(Some(format!(
+        "config-sensitive divergence of '{}'\nfirst kernel: {}\nsecond kernel: {}\n{}\nkernels with unexpected errors: {}\n",
+        fs_name,
+        fst.kernel,
+        snd.kernel,
+        reasons.join("\n"),
+        diverged
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use dif_fuzzer::{abstract_fs::operation::Operation, save::save_hash};
+
+    use super::*;
+
+    fn input() -> Workload {
+        Workload {
+            ops: vec![
+                Operation::MKDIR {
+                    path: "/0".into(),
+                    mode: vec![],
+                },
+                Operation::REMOVE { path: "/0".into() },
+            ],
+            seed: 0,
+        }
+    }
+
+    fn save_run(dir: &Path, kernel: &str, remove: &str, hash: u64) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(
+            dir.join(TESTCASE_JSON_FILENAME),
+            serde_json::to_string(&input()).unwrap(),
+        )
+        .unwrap();
+        fs::write(dir.join(KERNEL_FILENAME), format!("{}\n", kernel)).unwrap();
+        fs::write(
+            dir.join(format!("Ext4.{}", TRACE_FILENAME)),
+            format!(
+                "Index,Command,ReturnCode,Errno,Extra\n0,MKDIR,0,Success(0),\n1,REMOVE,{},\n",
+                remove
+            ),
+        )
+        .unwrap();
+        save_hash(dir, "Ext4", hash).unwrap();
+    }
+
+    #[test]
+    fn test_kernels_noted() {
+        let temp_dir = env::temp_dir().join("DIFFuzzer-kernel-diff-test");
+        fs::remove_dir_all(&temp_dir).unwrap_or(());
+        let (fst, snd) = (temp_dir.join("fst"), temp_dir.join("snd"));
+        save_run(&fst, "Linux version 6.1.0", "0,Success(0)", 1);
+        save_run(&snd, "Linux version 6.1.0-debug", "0,Success(0)", 1);
+        let load = |dir: &Path| KernelRun::load(dir, "Ext4").unwrap();
+        assert_eq!(None, divergence("Ext4", &load(&fst), &load(&snd)).unwrap());
+        assert!(!run(&fst, &snd, "Ext4").unwrap());
+
+        save_run(
+            &snd,
+            "Linux version 6.1.0-debug",
+            "-1,Directory not empty(39)",
+            2,
+        );
+        assert_eq!(
+            Some(
+                "config-sensitive divergence of 'Ext4'\n\
+                 first kernel: Linux version 6.1.0\n\
+                 second kernel: Linux version 6.1.0-debug\n\
+                 traces differ at operation 1 (REMOVE)\n\
+                 filesystem contents differ\n\
+                 kernels with unexpected errors: second\n"
+                    .to_owned()
+            ),
+            divergence("Ext4", &load(&fst), &load(&snd)).unwrap()
+        );
+        assert!(run(&fst, &snd, "Ext4").unwrap());
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn test_different_testcases_rejected() {
+        let temp_dir = env::temp_dir().join("DIFFuzzer-kernel-diff-testcase-test");
+        fs::remove_dir_all(&temp_dir).unwrap_or(());
+        let (fst, snd) = (temp_dir.join("fst"), temp_dir.join("snd"));
+        save_run(&fst, "Linux version 6.1.0", "0,Success(0)", 1);
+        save_run(&snd, "Linux version 6.6.0", "0,Success(0)", 1);
+        fs::write(
+            snd.join(TESTCASE_JSON_FILENAME),
+            serde_json::to_string(&Workload::new()).unwrap(),
+        )
+        .unwrap();
+        assert!(run(&fst, &snd, "Ext4").is_err());
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+}
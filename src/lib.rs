@@ -0,0 +1,49 @@
+//! Differential fuzzer for Linux filesystems.
+//!
+//! Library part exposes filesystem model, workload encoders and hashing of filesystem state,
+//! so custom harnesses can be built on top of them.
+//! Everything else is used by `dif-fuzzer` binary and is not considered stable.
+//!
+//! ```
+//! use dif_fuzzer::{AbstractFS, ModeFlag, Workload};
+//!
+//! let mut fs = AbstractFS::new();
+//! fs.mkdir("/foo".into(), vec![ModeFlag::S_IRWXU]).unwrap();
+//! let des = fs.open("/foo".into(), vec![]);
+//! assert!(des.is_err());
+//! fs.create("/foo/bar".into(), vec![]).unwrap();
+//!
+//! let workload: Workload = fs.recording.clone();
+//! let source = workload.encode_c(None);
+//! assert!(source.contains("do_create(\"/foo/bar\", 0);"));
+//! assert_eq!(workload, Workload::decode_c(&source));
+//! ```
+
+pub mod abstract_fs;
+pub mod hasher;
+
+#[doc(hidden)]
+pub mod config;
+#[doc(hidden)]
+pub mod filesystems;
+#[doc(hidden)]
+pub mod fuzzing;
+#[doc(hidden)]
+pub mod harness;
+#[doc(hidden)]
+pub mod mount;
+#[doc(hidden)]
+pub mod save;
+#[doc(hidden)]
+pub mod temp_dir;
+
+pub use abstract_fs::{
+    flags::{Mode, ModeFlag},
+    fs::{AbstractFS, FsError},
+    generator::{generate_new, GeneratorConfig},
+    operation::{Operation, OperationKind, OperationWeights},
+    pathname::PathName,
+    trace::Trace,
+    workload::Workload,
+};
+pub use hasher::hasher::{calc_dir_hash, get_diff, FileDiff, FileInfo, HasherOptions};
@@ -1,59 +1,148 @@
 use std::{fs, path::Path};
 
-use crate::fuzzing::blackbox::fuzzer::BlackBoxFuzzer;
-use crate::fuzzing::greybox::fuzzer::GreyBoxFuzzer;
-use args::Args;
-use clap::Parser;
-use config::Config;
-use fuzzing::common::Fuzzer;
-use fuzzing::reducer::Reducer;
-use log::info;
+use args::{opts_of, Args};
+use clap::{error::ErrorKind, CommandFactory, Parser};
+use dif_fuzzer::config::Config;
+use dif_fuzzer::fuzzing::blackbox::fuzzer::BlackBoxFuzzer;
+use dif_fuzzer::fuzzing::common::{stop_on_signals, Fuzzer, LoopExit, Runner};
+use dif_fuzzer::fuzzing::greybox::fuzzer::GreyBoxFuzzer;
+use dif_fuzzer::fuzzing::greybox::{archive, corpus_diff};
+use dif_fuzzer::fuzzing::reducer::Reducer;
+use dif_fuzzer::fuzzing::triage;
+use dif_fuzzer::mount::{
+    custom::with_custom_options,
+    mount::FileSystemMount,
+    probe::{check_host, SystemProbe},
+};
+use log::{error, info, warn};
 
-mod abstract_fs;
 mod args;
-mod config;
-mod filesystems;
-mod fuzzing;
-mod harness;
-mod hasher;
-mod mount;
-mod save;
+mod kernel_diff;
+mod reproducer;
 mod single;
-mod temp_dir;
+mod validate;
 
 fn main() {
     let args = Args::parse();
+    if args.mode.is_none() && !args.check {
+        Args::command()
+            .error(
+                ErrorKind::MissingSubcommand,
+                "mode is required unless '--check' is given",
+            )
+            .exit()
+    }
 
     log4rs::init_file("log4rs.yml", Default::default()).unwrap();
     info!("logger initialized");
     info!("reading configuration");
-    let config = fs::read_to_string(args.config_path).expect("failed to read configuration file");
-    let config: Config = toml::from_str(&config).expect("bad configuration");
+    let config = fs::read_to_string(&args.config_path).expect("failed to read configuration file");
+    let mut config: Config = toml::from_str(&config).expect("bad configuration");
+    if let Err(err) = config.validate() {
+        error!("bad configuration: {:#}", err);
+        std::process::exit(1);
+    }
+
+    if let Some(filesystems) = args.probed_filesystems() {
+        info!("checking host");
+        let filesystems: Vec<&dyn FileSystemMount> = filesystems
+            .into_iter()
+            .map(|fs| fs.try_into().unwrap())
+            .collect();
+        if let Err(err) = check_host(&filesystems, &SystemProbe) {
+            error!("{:#}", err);
+            std::process::exit(1);
+        }
+    }
+    if args.check {
+        return;
+    }
+    let mode = args.mode.expect("mode is given without '--check'");
+
+    let tested = mode.filesystems();
+    for (fs, _) in args.mkfs_opts.iter().chain(args.mount_opts.iter()) {
+        if !tested.contains(fs) {
+            warn!("options of '{}' are ignored, it is not tested", fs);
+        }
+    }
+    let mount = |fs: String| -> &'static dyn FileSystemMount {
+        with_custom_options(
+            fs.clone().try_into().unwrap(),
+            opts_of(&args.mkfs_opts, &fs),
+            opts_of(&args.mount_opts, &fs),
+        )
+    };
 
-    match args.mode {
+    match mode {
         args::Mode::Greybox {
             first_filesystem,
             second_filesystem,
             test_count,
+            max_runtime,
+            fail_fast,
+            seed,
+            ndjson_out,
+            resume,
         } => {
-            GreyBoxFuzzer::new(
-                config,
-                first_filesystem.try_into().unwrap(),
-                second_filesystem.try_into().unwrap(),
-            )
-            .run(test_count);
+            config.rng_seed = seed.or(config.rng_seed);
+            config.ndjson_out = ndjson_out.or(config.ndjson_out);
+            let fuzzer =
+                GreyBoxFuzzer::new(config, mount(first_filesystem), mount(second_filesystem))
+                    .and_then(|fuzzer| match resume {
+                        Some(dir) => fuzzer.resume(Path::new(&dir)),
+                        None => Ok(fuzzer),
+                    });
+            // fuzzer is dropped before exit, so teardown is not skipped
+            let exit = match fuzzer {
+                Ok(mut fuzzer) => {
+                    if let Err(err) = stop_on_signals(&fuzzer.runner().stop) {
+                        warn!("{:#}", err);
+                    }
+                    fuzzer.run(test_count, max_runtime, fail_fast)
+                }
+                Err(err) => {
+                    error!("{:#}", err);
+                    std::process::exit(1);
+                }
+            };
+            // fuzzing error is a failure too, not only crash in fail-fast mode
+            if matches!(exit, LoopExit::Crash | LoopExit::Error) {
+                std::process::exit(1);
+            }
         }
         args::Mode::Blackbox {
             first_filesystem,
             second_filesystem,
+            other_filesystem,
             test_count,
+            max_runtime,
+            fail_fast,
+            seed,
+            ndjson_out,
         } => {
-            BlackBoxFuzzer::new(
-                config,
-                first_filesystem.try_into().unwrap(),
-                second_filesystem.try_into().unwrap(),
-            )
-            .run(test_count);
+            config.rng_seed = seed.or(config.rng_seed);
+            config.ndjson_out = ndjson_out.or(config.ndjson_out);
+            let others: Vec<&'static dyn FileSystemMount> =
+                other_filesystem.into_iter().map(mount).collect();
+            let fuzzer =
+                BlackBoxFuzzer::new(config, mount(first_filesystem), mount(second_filesystem))
+                    .with_others(&others);
+            let exit = match fuzzer {
+                Ok(mut fuzzer) => {
+                    if let Err(err) = stop_on_signals(&fuzzer.runner().stop) {
+                        warn!("{:#}", err);
+                    }
+                    fuzzer.run(test_count, max_runtime, fail_fast)
+                }
+                Err(err) => {
+                    error!("{:#}", err);
+                    std::process::exit(1);
+                }
+            };
+            // fuzzing error is a failure too, not only crash in fail-fast mode
+            if matches!(exit, LoopExit::Crash | LoopExit::Error) {
+                std::process::exit(1);
+            }
         }
         args::Mode::Single {
             save_to_dir,
@@ -64,21 +153,92 @@ fn main() {
             Path::new(&path_to_test),
             Path::new(&save_to_dir),
             keep_fs,
-            filesystem.try_into().unwrap(),
-            config.fs_name,
+            mount(filesystem),
+            single::Settings::from(&config),
         ),
+        args::Mode::Reproducer {
+            path_to_test,
+            output,
+            shell,
+        } => {
+            if let Err(err) = reproducer::run(Path::new(&path_to_test), Path::new(&output), shell) {
+                error!("{:#}", err);
+                std::process::exit(1);
+            }
+        }
+        args::Mode::Validate { path_to_test } => {
+            if let Err(err) = validate::run(Path::new(&path_to_test), config.compact_model_at()) {
+                error!("{:#}", err);
+                std::process::exit(1);
+            }
+        }
         args::Mode::Reduce {
             output_dir,
             path_to_test,
             first_filesystem,
             second_filesystem,
         } => {
-            Reducer::new(
-                config,
-                first_filesystem.try_into().unwrap(),
-                second_filesystem.try_into().unwrap(),
+            Reducer::new(config, mount(first_filesystem), mount(second_filesystem))
+                .run(Path::new(&path_to_test), Path::new(&output_dir))
+                .unwrap();
+        }
+        args::Mode::Triage {
+            crashes_dir,
+            first_filesystem,
+            second_filesystem,
+        } => {
+            let mut runner = Runner::new(mount(first_filesystem), mount(second_filesystem), config);
+            triage::run(&mut runner, Path::new(&crashes_dir)).unwrap();
+        }
+        args::Mode::CorpusDiff {
+            first_corpus,
+            second_corpus,
+            filesystem,
+            side,
+        } => {
+            let mount: &dyn FileSystemMount = mount(filesystem);
+            corpus_diff::run(
+                Path::new(&first_corpus),
+                Path::new(&second_corpus),
+                &mount.to_string(),
+                &side,
+            )
+            .unwrap();
+        }
+        args::Mode::KernelDiff {
+            first_run,
+            second_run,
+            filesystem,
+        } => {
+            let mount: &dyn FileSystemMount = mount(filesystem);
+            match kernel_diff::run(
+                Path::new(&first_run),
+                Path::new(&second_run),
+                &mount.to_string(),
+            ) {
+                Ok(false) => {}
+                Ok(true) => std::process::exit(1),
+                Err(err) => {
+                    error!("{:#}", err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        args::Mode::ExportCorpus {
+            corpus_path,
+            output,
+        } => {
+            archive::export(Path::new(&corpus_path), Path::new(&output)).unwrap();
+        }
+        args::Mode::ImportCorpus {
+            archive,
+            corpus_path,
+        } => {
+            archive::import(
+                Path::new(&archive),
+                Path::new(&corpus_path),
+                &config.operation_weights,
             )
-            .run(Path::new(&path_to_test), Path::new(&output_dir))
             .unwrap();
         }
     }
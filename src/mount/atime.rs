@@ -0,0 +1,164 @@
+use std::{fmt::Display, path::Path, process::Command};
+
+use regex::RegexSet;
+use serde::{Deserialize, Serialize};
+
+use crate::abstract_fs::flags::FallocateFlag;
+
+use super::mount::{run_setup_commands, FileSystemMount};
+
+/// Access time update policy, selected with mount option of the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AtimePolicy {
+    /// Atime is updated only if it is not newer than mtime (or older than a day).
+    Relatime,
+    /// Atime is never updated.
+    Noatime,
+    /// Atime is updated on every access.
+    Strictatime,
+}
+
+impl Display for AtimePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AtimePolicy::Relatime => write!(f, "relatime"),
+            AtimePolicy::Noatime => write!(f, "noatime"),
+            AtimePolicy::Strictatime => write!(f, "strictatime"),
+        }
+    }
+}
+
+/// Same filesystem mounted with atime policy option.
+///
+/// Policy does not apply to filesystems with their own setup (e.g. overlay), they are set up as is.
+pub struct AtimeMount {
+    inner: &'static dyn FileSystemMount,
+    policy: AtimePolicy,
+}
+
+impl Display for AtimeMount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.inner, self.policy)
+    }
+}
+
+impl FileSystemMount for AtimeMount {
+    fn setup(&self, path: &Path, device: &str, ram_disk_size: usize) -> anyhow::Result<()> {
+        if self.inner.default_setup() {
+            run_setup_commands(self, path, device, ram_disk_size)
+        } else {
+            self.inner.setup(path, device, ram_disk_size)
+        }
+    }
+
+    fn default_setup(&self) -> bool {
+        self.inner.default_setup()
+    }
+
+    fn teardown(&self, path: &Path) -> anyhow::Result<()> {
+        self.inner.teardown(path)
+    }
+
+    fn unmount(&self, path: &Path) -> anyhow::Result<()> {
+        self.inner.unmount(path)
+    }
+
+    fn release(&self, path: &Path) -> anyhow::Result<()> {
+        self.inner.release(path)
+    }
+
+    fn mkfs_cmd(&self) -> String {
+        self.inner.mkfs_cmd()
+    }
+
+    fn mkfs_opts(&self) -> Option<String> {
+        self.inner.mkfs_opts()
+    }
+
+    fn mount_t(&self) -> String {
+        self.inner.mount_t()
+    }
+
+    fn mount_opts(&self) -> Option<String> {
+        match self.inner.mount_opts() {
+            Some(opts) => Some(format!("{},{}", opts, self.policy)),
+            None => Some(self.policy.to_string()),
+        }
+    }
+
+    fn get_internal_dirs(&self) -> RegexSet {
+        self.inner.get_internal_dirs()
+    }
+
+    fn uses_ram_disk(&self) -> bool {
+        self.inner.uses_ram_disk()
+    }
+
+    fn case_insensitive(&self) -> bool {
+        self.inner.case_insensitive()
+    }
+
+    fn prepare_cmds(&self, path: &Path) -> Vec<(&'static str, Command)> {
+        self.inner.prepare_cmds(path)
+    }
+
+    fn xattr_space(&self) -> Option<u64> {
+        self.inner.xattr_space()
+    }
+
+    fn fallocate_supported(&self, mode: &[FallocateFlag]) -> bool {
+        self.inner.fallocate_supported(mode)
+    }
+
+    fn check_cmd(&self, device: &str) -> Option<Command> {
+        self.inner.check_cmd(device)
+    }
+
+    fn capacity_opt(&self, size: usize) -> Option<String> {
+        self.inner.capacity_opt(size)
+    }
+}
+
+impl AtimeMount {
+    pub fn new(inner: &'static dyn FileSystemMount, policy: AtimePolicy) -> Self {
+        Self { inner, policy }
+    }
+}
+
+/// Wraps mount if policy is set, leaked like data mode mounts.
+pub fn with_atime_policy(
+    inner: &'static dyn FileSystemMount,
+    policy: Option<AtimePolicy>,
+) -> &'static dyn FileSystemMount {
+    match policy {
+        Some(policy) => Box::leak(Box::new(AtimeMount::new(inner, policy))),
+        None => inner,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mount::{ext4::Ext4, overlayfs::Overlayfs};
+
+    use super::*;
+
+    static EXT4: Ext4 = Ext4::new();
+
+    #[test]
+    fn test_mount_opts() {
+        let mount = with_atime_policy(&EXT4, Some(AtimePolicy::Noatime));
+        assert_eq!(Some("noatime".to_owned()), mount.mount_opts());
+        assert_eq!("Ext4-noatime", mount.to_string());
+        let mount = with_atime_policy(&EXT4, None);
+        assert_eq!("Ext4", mount.to_string());
+    }
+
+    #[test]
+    fn test_own_setup_forwarded() {
+        static OVERLAY: Overlayfs = Overlayfs::new();
+        let mount = with_atime_policy(&OVERLAY, Some(AtimePolicy::Strictatime));
+        assert!(!mount.default_setup());
+        assert!(with_atime_policy(&EXT4, Some(AtimePolicy::Strictatime)).default_setup());
+    }
+}
@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{fmt::Display, process::Command};
 
 use super::mount::FileSystemMount;
 
@@ -17,6 +17,18 @@ impl FileSystemMount for Btrfs {
     fn mount_t(&self) -> String {
         "btrfs".to_owned()
     }
+
+    /// Each attribute is an item that has to fit into one leaf (of default 16 KiB node size),
+    /// so it is also the bound for all of them.
+    fn xattr_space(&self) -> Option<u64> {
+        Some(16000)
+    }
+
+    fn check_cmd(&self, device: &str) -> Option<Command> {
+        let mut cmd = Command::new("btrfs");
+        cmd.arg("check").arg("--readonly").arg(device);
+        Some(cmd)
+    }
 }
 
 impl Btrfs {
@@ -0,0 +1,225 @@
+use std::{fmt::Display, path::Path, process::Command};
+
+use regex::RegexSet;
+
+use crate::abstract_fs::flags::FallocateFlag;
+
+use super::mount::{run_setup_commands, FileSystemMount};
+
+/// Same filesystem created and mounted with options given on command line.
+///
+/// Options replace the ones of filesystem, unless they start with `+`, then they are appended.
+/// Empty options remove the ones of filesystem. Filesystems with their own setup (e.g. overlay)
+/// are set up and torn down by themselves.
+///
+/// Name is the same as of filesystem, so crashes and saved files are not renamed.
+pub struct CustomMount {
+    inner: &'static dyn FileSystemMount,
+    mkfs_opts: Option<String>,
+    mount_opts: Option<String>,
+}
+
+impl Display for CustomMount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.inner)
+    }
+}
+
+impl FileSystemMount for CustomMount {
+    fn setup(&self, path: &Path, device: &str, ram_disk_size: usize) -> anyhow::Result<()> {
+        if self.inner.default_setup() {
+            run_setup_commands(self, path, device, ram_disk_size)
+        } else {
+            self.inner.setup(path, device, ram_disk_size)
+        }
+    }
+
+    fn default_setup(&self) -> bool {
+        self.inner.default_setup()
+    }
+
+    fn teardown(&self, path: &Path) -> anyhow::Result<()> {
+        self.inner.teardown(path)
+    }
+
+    fn unmount(&self, path: &Path) -> anyhow::Result<()> {
+        self.inner.unmount(path)
+    }
+
+    fn release(&self, path: &Path) -> anyhow::Result<()> {
+        self.inner.release(path)
+    }
+
+    fn mkfs_cmd(&self) -> String {
+        self.inner.mkfs_cmd()
+    }
+
+    fn mkfs_opts(&self) -> Option<String> {
+        combine(self.inner.mkfs_opts(), self.mkfs_opts.as_deref())
+    }
+
+    fn mount_t(&self) -> String {
+        self.inner.mount_t()
+    }
+
+    fn mount_opts(&self) -> Option<String> {
+        combine(self.inner.mount_opts(), self.mount_opts.as_deref())
+    }
+
+    fn get_internal_dirs(&self) -> RegexSet {
+        self.inner.get_internal_dirs()
+    }
+
+    fn uses_ram_disk(&self) -> bool {
+        self.inner.uses_ram_disk()
+    }
+
+    fn case_insensitive(&self) -> bool {
+        self.inner.case_insensitive()
+    }
+
+    fn prepare_cmds(&self, path: &Path) -> Vec<(&'static str, Command)> {
+        self.inner.prepare_cmds(path)
+    }
+
+    fn xattr_space(&self) -> Option<u64> {
+        self.inner.xattr_space()
+    }
+
+    fn fallocate_supported(&self, mode: &[FallocateFlag]) -> bool {
+        self.inner.fallocate_supported(mode)
+    }
+
+    fn check_cmd(&self, device: &str) -> Option<Command> {
+        self.inner.check_cmd(device)
+    }
+
+    fn capacity_opt(&self, size: usize) -> Option<String> {
+        self.inner.capacity_opt(size)
+    }
+}
+
+impl CustomMount {
+    pub fn new(
+        inner: &'static dyn FileSystemMount,
+        mkfs_opts: Option<String>,
+        mount_opts: Option<String>,
+    ) -> Self {
+        Self {
+            inner,
+            mkfs_opts,
+            mount_opts,
+        }
+    }
+}
+
+/// Mount lives for the whole campaign, so it is leaked to match static filesystems.
+///
+/// Filesystem is returned as is if no options are given.
+pub fn with_custom_options(
+    inner: &'static dyn FileSystemMount,
+    mkfs_opts: Option<String>,
+    mount_opts: Option<String>,
+) -> &'static dyn FileSystemMount {
+    if mkfs_opts.is_none() && mount_opts.is_none() {
+        return inner;
+    }
+    Box::leak(Box::new(CustomMount::new(inner, mkfs_opts, mount_opts)))
+}
+
+fn combine(default: Option<String>, custom: Option<&str>) -> Option<String> {
+    match (default, custom) {
+        (default, None) => default,
+        (Some(default), Some(custom)) if custom.starts_with('+') => {
+            Some(format!("{},{}", default, &custom[1..]))
+        }
+        (None, Some(custom)) if custom.starts_with('+') => Some(custom[1..].to_owned()),
+        (_, Some("")) => None,
+        (_, Some(custom)) => Some(custom.to_owned()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::mount::{
+        ext4::Ext4,
+        f2fs::F2FS,
+        mount::{devices, setup_commands},
+        overlayfs::Overlayfs,
+    };
+
+    use super::*;
+
+    static EXT4: Ext4 = Ext4::new();
+    static F2FS_MOUNT: F2FS = F2FS::new();
+
+    fn commands(mount: &dyn FileSystemMount) -> Vec<String> {
+        setup_commands(mount, Path::new("/mnt/ext4"), devices(0)[0], 1_000_000)
+            .iter()
+            .map(|(_, cmd)| format!("{:?}", cmd))
+            .collect()
+    }
+
+    #[test]
+    fn test_custom_options_in_commands() {
+        let mount = CustomMount::new(
+            &EXT4,
+            Some("^has_journal".to_owned()),
+            Some("noatime,nodelalloc".to_owned()),
+        );
+        assert_eq!("Ext4", mount.to_string());
+        let commands = commands(&mount);
+        assert_eq!(
+            "\"mkfs.ext4\" \"-O\" \"^has_journal\" \"/dev/ram0\"",
+            commands[1]
+        );
+        assert_eq!(
+            "\"mount\" \"-t\" \"ext4\" \"-o\" \"noatime,nodelalloc\" \"/dev/ram0\" \"/mnt/ext4\"",
+            commands[2]
+        );
+    }
+
+    #[test]
+    fn test_custom_options_appended() {
+        let defaults = (
+            F2FS_MOUNT.mkfs_opts().unwrap(),
+            F2FS_MOUNT.mount_opts().unwrap(),
+        );
+        let mount = CustomMount::new(
+            &F2FS_MOUNT,
+            Some("+quota".to_owned()),
+            Some("+noatime".to_owned()),
+        );
+        assert_eq!(Some(format!("{},quota", defaults.0)), mount.mkfs_opts());
+        assert_eq!(Some(format!("{},noatime", defaults.1)), mount.mount_opts());
+        assert!(commands(&mount)[2].contains(&format!("\"{},noatime\"", defaults.1)));
+
+        let mount = CustomMount::new(&EXT4, Some("+^has_journal".to_owned()), None);
+        assert_eq!(Some("^has_journal".to_owned()), mount.mkfs_opts());
+        assert_eq!(EXT4.mount_opts(), mount.mount_opts());
+
+        let mount = CustomMount::new(&F2FS_MOUNT, None, Some("".to_owned()));
+        assert_eq!(F2FS_MOUNT.mkfs_opts(), mount.mkfs_opts());
+        assert_eq!(None, mount.mount_opts());
+    }
+
+    #[test]
+    fn test_without_custom_options() {
+        let mount = with_custom_options(&EXT4, None, None);
+        assert!(std::ptr::addr_eq(mount, &EXT4 as &dyn FileSystemMount));
+        let mount = with_custom_options(&EXT4, None, Some("noatime".to_owned()));
+        assert!(!std::ptr::addr_eq(mount, &EXT4 as &dyn FileSystemMount));
+        assert_eq!("Ext4", mount.to_string());
+    }
+
+    #[test]
+    fn test_own_setup_forwarded() {
+        static OVERLAY: Overlayfs = Overlayfs::new();
+        assert!(EXT4.default_setup());
+        let mount = CustomMount::new(&OVERLAY, None, Some("+redirect_dir=on".to_owned()));
+        assert!(!mount.default_setup());
+        assert_eq!("Overlayfs", mount.to_string());
+    }
+}
@@ -0,0 +1,161 @@
+use std::{fmt::Display, path::Path, process::Command};
+
+use regex::RegexSet;
+
+use crate::abstract_fs::flags::FallocateFlag;
+
+use super::mount::{run_setup_commands, FileSystemMount};
+
+/// Same filesystem mounted with `data=<mode>` option (e.g. `ordered`, `journal` or `writeback` for ext4).
+///
+/// Setup and teardown are left to filesystem if it has its own (see [`FileSystemMount::default_setup`]).
+pub struct DataModeMount {
+    inner: &'static dyn FileSystemMount,
+    mode: String,
+}
+
+impl Display for DataModeMount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.inner, self.mode)
+    }
+}
+
+impl FileSystemMount for DataModeMount {
+    fn setup(&self, path: &Path, device: &str, ram_disk_size: usize) -> anyhow::Result<()> {
+        if self.inner.default_setup() {
+            run_setup_commands(self, path, device, ram_disk_size)
+        } else {
+            self.inner.setup(path, device, ram_disk_size)
+        }
+    }
+
+    fn default_setup(&self) -> bool {
+        self.inner.default_setup()
+    }
+
+    fn teardown(&self, path: &Path) -> anyhow::Result<()> {
+        self.inner.teardown(path)
+    }
+
+    fn unmount(&self, path: &Path) -> anyhow::Result<()> {
+        self.inner.unmount(path)
+    }
+
+    fn release(&self, path: &Path) -> anyhow::Result<()> {
+        self.inner.release(path)
+    }
+
+    fn mkfs_cmd(&self) -> String {
+        self.inner.mkfs_cmd()
+    }
+
+    fn mkfs_opts(&self) -> Option<String> {
+        self.inner.mkfs_opts()
+    }
+
+    fn mount_t(&self) -> String {
+        self.inner.mount_t()
+    }
+
+    fn mount_opts(&self) -> Option<String> {
+        let data = format!("data={}", self.mode);
+        match self.inner.mount_opts() {
+            Some(opts) => Some(format!("{},{}", opts, data)),
+            None => Some(data),
+        }
+    }
+
+    fn get_internal_dirs(&self) -> RegexSet {
+        self.inner.get_internal_dirs()
+    }
+
+    fn uses_ram_disk(&self) -> bool {
+        self.inner.uses_ram_disk()
+    }
+
+    fn case_insensitive(&self) -> bool {
+        self.inner.case_insensitive()
+    }
+
+    fn prepare_cmds(&self, path: &Path) -> Vec<(&'static str, Command)> {
+        self.inner.prepare_cmds(path)
+    }
+
+    fn xattr_space(&self) -> Option<u64> {
+        self.inner.xattr_space()
+    }
+
+    fn fallocate_supported(&self, mode: &[FallocateFlag]) -> bool {
+        self.inner.fallocate_supported(mode)
+    }
+
+    fn check_cmd(&self, device: &str) -> Option<Command> {
+        self.inner.check_cmd(device)
+    }
+
+    fn capacity_opt(&self, size: usize) -> Option<String> {
+        self.inner.capacity_opt(size)
+    }
+}
+
+impl DataModeMount {
+    pub fn new(inner: &'static dyn FileSystemMount, mode: String) -> Self {
+        Self { inner, mode }
+    }
+}
+
+/// Mounts live for the whole campaign, so they are leaked to match static filesystems.
+///
+/// Filesystem is used as is for empty mode.
+pub fn with_data_modes<'a>(
+    inner: &'static dyn FileSystemMount,
+    modes: impl Iterator<Item = &'a String>,
+) -> Vec<&'static dyn FileSystemMount> {
+    modes
+        .map(|mode| {
+            if mode.is_empty() {
+                return inner;
+            }
+            let mount: &'static dyn FileSystemMount =
+                Box::leak(Box::new(DataModeMount::new(inner, mode.clone())));
+            mount
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mount::{ext4::Ext4, f2fs::F2FS, overlayfs::Overlayfs};
+
+    use super::*;
+
+    static EXT4: Ext4 = Ext4::new();
+    static F2FS_MOUNT: F2FS = F2FS::new();
+
+    #[test]
+    fn test_mount_opts() {
+        let mount = DataModeMount::new(&EXT4, "journal".to_owned());
+        assert_eq!(Some("data=journal".to_owned()), mount.mount_opts());
+        assert_eq!("Ext4-journal", mount.to_string());
+        assert_eq!("ext4", mount.mount_t());
+        let mount = DataModeMount::new(&F2FS_MOUNT, "ordered".to_owned());
+        assert!(mount.mount_opts().unwrap().ends_with(",data=ordered"));
+    }
+
+    #[test]
+    fn test_with_data_modes_labels() {
+        let modes = vec!["ordered".to_owned(), "".to_owned(), "writeback".to_owned()];
+        let mounts = with_data_modes(&EXT4, modes.iter());
+        let labels: Vec<String> = mounts.iter().map(|m| m.to_string()).collect();
+        assert_eq!(vec!["Ext4-ordered", "Ext4", "Ext4-writeback"], labels);
+        assert_eq!(None, mounts[1].mount_opts());
+    }
+
+    #[test]
+    fn test_own_setup_forwarded() {
+        static OVERLAY: Overlayfs = Overlayfs::new();
+        let mount = DataModeMount::new(&OVERLAY, "ordered".to_owned());
+        assert!(!mount.default_setup());
+        assert!(DataModeMount::new(&EXT4, "ordered".to_owned()).default_setup());
+    }
+}
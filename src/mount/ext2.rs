@@ -0,0 +1,50 @@
+use std::{fmt::Display, process::Command};
+
+use regex::RegexSet;
+
+use crate::abstract_fs::flags::FallocateFlag;
+
+use super::mount::FileSystemMount;
+
+pub struct Ext2;
+
+impl Display for Ext2 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Ext2")
+    }
+}
+
+impl FileSystemMount for Ext2 {
+    fn mkfs_cmd(&self) -> String {
+        "mkfs.ext2".to_owned()
+    }
+    fn mount_t(&self) -> String {
+        "ext2".to_owned()
+    }
+
+    fn get_internal_dirs(&self) -> RegexSet {
+        RegexSet::new([r"^/?lost\+found($|/)"]).unwrap()
+    }
+
+    /// Attributes that do not fit into inode share one block (without 32 byte header and end marker).
+    fn xattr_space(&self) -> Option<u64> {
+        Some(4060)
+    }
+
+    /// Files are mapped with indirect blocks, which can only have holes punched.
+    fn fallocate_supported(&self, mode: &[FallocateFlag]) -> bool {
+        mode.contains(&FallocateFlag::FALLOC_FL_PUNCH_HOLE)
+    }
+
+    fn check_cmd(&self, device: &str) -> Option<Command> {
+        let mut cmd = Command::new("fsck.ext2");
+        cmd.arg("-n").arg("-f").arg(device);
+        Some(cmd)
+    }
+}
+
+impl Ext2 {
+    pub const fn new() -> Self {
+        Self {}
+    }
+}
@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{fmt::Display, process::Command};
 
 use regex::RegexSet;
 
@@ -23,6 +23,17 @@ impl FileSystemMount for Ext4 {
     fn get_internal_dirs(&self) -> RegexSet {
         RegexSet::new([r"^/?lost\+found($|/)"]).unwrap()
     }
+
+    /// Attributes that do not fit into inode share one block (without 32 byte header and end marker).
+    fn xattr_space(&self) -> Option<u64> {
+        Some(4060)
+    }
+
+    fn check_cmd(&self, device: &str) -> Option<Command> {
+        let mut cmd = Command::new("fsck.ext4");
+        cmd.arg("-n").arg("-f").arg(device);
+        Some(cmd)
+    }
 }
 
 impl Ext4 {
@@ -0,0 +1,101 @@
+use std::{fmt::Display, path::Path, process::Command};
+
+use regex::RegexSet;
+
+use super::mount::FileSystemMount;
+
+/// Ext4 with case-insensitive root directory.
+///
+/// Casefold flag can only be set on empty directory, so `lost+found` is removed and made again
+/// inside casefolded root.
+pub struct Ext4Casefold;
+
+impl Display for Ext4Casefold {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Ext4Casefold")
+    }
+}
+
+impl FileSystemMount for Ext4Casefold {
+    fn mkfs_cmd(&self) -> String {
+        "mkfs.ext4".to_owned()
+    }
+    fn mkfs_opts(&self) -> Option<String> {
+        Some("casefold".to_owned())
+    }
+    fn mount_t(&self) -> String {
+        "ext4".to_owned()
+    }
+
+    fn get_internal_dirs(&self) -> RegexSet {
+        RegexSet::new([r"^/?lost\+found($|/)"]).unwrap()
+    }
+
+    fn case_insensitive(&self) -> bool {
+        true
+    }
+
+    fn prepare_cmds(&self, path: &Path) -> Vec<(&'static str, Command)> {
+        let lost_found = path.join("lost+found");
+        let mut rmdir = Command::new("rmdir");
+        rmdir.arg(&lost_found);
+        let mut chattr = Command::new("chattr");
+        chattr.arg("+F").arg(path);
+        let mut mkdir = Command::new("mkdir");
+        mkdir.arg(&lost_found);
+        vec![
+            ("empty root", rmdir),
+            ("casefold root", chattr),
+            ("restore lost+found", mkdir),
+        ]
+    }
+
+    /// Attributes that do not fit into inode share one block (without 32 byte header and end marker).
+    fn xattr_space(&self) -> Option<u64> {
+        Some(4060)
+    }
+
+    fn check_cmd(&self, device: &str) -> Option<Command> {
+        let mut cmd = Command::new("fsck.ext4");
+        cmd.arg("-n").arg("-f").arg(device);
+        Some(cmd)
+    }
+}
+
+impl Ext4Casefold {
+    pub const fn new() -> Self {
+        Self {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mount::mount::{devices, setup_commands};
+
+    use super::*;
+
+    #[test]
+    fn test_root_casefolded() {
+        let fs = Ext4Casefold::new();
+        assert!(fs.case_insensitive());
+        let commands: Vec<String> = setup_commands(
+            &fs,
+            Path::new("/mnt/ext4casefold"),
+            devices(0)[0],
+            1_000_000,
+        )
+        .iter()
+        .map(|(_, cmd)| format!("{:?}", cmd))
+        .collect();
+        assert_eq!(
+            vec![
+                "\"mkfs.ext4\" \"-O\" \"casefold\" \"/dev/ram0\"",
+                "\"mount\" \"-t\" \"ext4\" \"/dev/ram0\" \"/mnt/ext4casefold\"",
+                "\"rmdir\" \"/mnt/ext4casefold/lost+found\"",
+                "\"chattr\" \"+F\" \"/mnt/ext4casefold\"",
+                "\"mkdir\" \"/mnt/ext4casefold/lost+found\"",
+            ],
+            commands[1..]
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{fmt::Display, process::Command};
 
 use regex::RegexSet;
 
@@ -29,6 +29,17 @@ impl FileSystemMount for F2FS {
     fn get_internal_dirs(&self) -> RegexSet {
         RegexSet::new([r"^/?lost\+found($|/)"]).unwrap()
     }
+
+    /// Attributes that are not inline share one node block (without its footer and header).
+    fn xattr_space(&self) -> Option<u64> {
+        Some(4000)
+    }
+
+    fn check_cmd(&self, device: &str) -> Option<Command> {
+        let mut cmd = Command::new("fsck.f2fs");
+        cmd.arg("--dry-run").arg(device);
+        Some(cmd)
+    }
 }
 
 impl F2FS {
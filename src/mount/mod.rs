@@ -1,5 +1,15 @@
+pub mod atime;
 pub mod btrfs;
+pub mod custom;
+pub mod data_mode;
+pub mod ext2;
+pub mod ext3;
 pub mod ext4;
+pub mod ext4_casefold;
 pub mod f2fs;
 pub mod mount;
+pub mod options;
+pub mod overlayfs;
+pub mod probe;
+pub mod tmpfs;
 pub mod xfs;
@@ -1,74 +1,53 @@
-use std::{fmt::Display, fs, path::Path, process::Command};
+use std::{fmt::Display, fs, path::Path, process::Command, sync::LazyLock};
 
 use anyhow::{bail, Context};
 use log::debug;
 use regex::RegexSet;
 
-const RAM_DISK_SIZE: usize = 1_000_000;
-const DEVICE: &str = "/dev/ram0";
+use crate::{abstract_fs::flags::FallocateFlag, temp_dir::MAX_INSTANCES};
 
-pub trait FileSystemMount: Display {
-    fn setup(&self, path: &Path) -> anyhow::Result<()> {
-        debug!("setting up '{}' filesystem at '{}'", self, path.display());
+/// Each harness of instance has its own device, so all filesystems can stay mounted at the same time.
+pub const DEVICES_PER_INSTANCE: usize = 4;
 
-        fs::create_dir_all(path)
-            .with_context(|| format!("failed to create mountpoint at '{}'", path.display()))?;
+/// Ram devices of every instance, see [`devices`].
+static DEVICES: LazyLock<Vec<String>> = LazyLock::new(|| {
+    (0..DEVICES_PER_INSTANCE * MAX_INSTANCES)
+        .map(|index| format!("/dev/ram{}", index))
+        .collect()
+});
 
-        let mut modprobe = Command::new("modprobe");
-        modprobe
-            .arg("brd")
-            .arg("rd_nr=1")
-            .arg(format!("rd_size={RAM_DISK_SIZE}"));
-        let output = modprobe
-            .output()
-            .with_context(|| format!("failed to load block ram device module: {:?}", modprobe))?;
-        if !output.status.success() {
-            bail!(
-                "failed to load block ram device module: {:?}\n{}",
-                modprobe,
-                String::from_utf8(output.stderr)
-                    .with_context(|| format!("failed to read stderr (brd)"))?,
-            );
-        }
+/// Devices of instance (see [`crate::temp_dir::Instance`]), the first one has `/dev/ram0` to `/dev/ram3`.
+pub fn devices(instance: usize) -> Vec<&'static str> {
+    DEVICES[instance * DEVICES_PER_INSTANCE..(instance + 1) * DEVICES_PER_INSTANCE]
+        .iter()
+        .map(String::as_str)
+        .collect()
+}
 
-        let mut mkfs = Command::new(self.mkfs_cmd());
-        if let Some(opts) = self.mkfs_opts() {
-            mkfs.arg("-O");
-            mkfs.arg(opts);
-        }
-        mkfs.arg(DEVICE);
-        let output = mkfs.output()?;
-        if !output.status.success() {
-            bail!(
-                "failed to create fs: {:?}\n{}",
-                mkfs,
-                String::from_utf8(output.stderr)
-                    .with_context(|| format!("failed to read stderr (mkfs)"))?,
-            );
-        }
+/// Size (in KiB) of ram disks block ram device module is loaded with.
+const RAM_DISK_SIZE_PARAM: &str = "/sys/module/brd/parameters/rd_size";
 
-        let mut mount = Command::new("mount");
-        mount.arg("-t").arg(self.mount_t());
-        if let Some(opts) = self.mount_opts() {
-            mount.arg("-o");
-            mount.arg(opts);
-        }
-        mount.arg(DEVICE).arg(path);
-        let output = mount.output()?;
-        if !output.status.success() {
-            bail!(
-                "failed to mount fs: {:?}\n{}",
-                mount,
-                String::from_utf8(output.stderr)
-                    .with_context(|| format!("failed to read stderr (mount)"))?,
-            );
-        }
-        Ok(())
+pub trait FileSystemMount: Display {
+    /// Device of size `ram_disk_size` (in KiB) is made if filesystem uses ram disk,
+    /// otherwise it is the capacity of filesystem (see [`FileSystemMount::capacity_opt`]).
+    fn setup(&self, path: &Path, device: &str, ram_disk_size: usize) -> anyhow::Result<()> {
+        run_setup_commands(self, path, device, ram_disk_size)
+    }
+
+    /// Filesystem is set up with [`setup_commands`] (default [`FileSystemMount::setup`]), so options
+    /// of mount wrapping it apply. Filesystems with their own setup (e.g. overlay) are set up as is.
+    fn default_setup(&self) -> bool {
+        true
     }
 
     fn teardown(&self, path: &Path) -> anyhow::Result<()> {
         debug!("tearing down '{}' filesystem at '{}'", self, path.display());
+        self.unmount(path)?;
+        self.release(path)
+    }
 
+    /// First part of default teardown, device is kept, so it can be checked afterwards.
+    fn unmount(&self, path: &Path) -> anyhow::Result<()> {
         let mut umount = Command::new("umount");
         umount.arg("-fl").arg(path);
         let output = umount.output()?;
@@ -80,17 +59,26 @@ pub trait FileSystemMount: Display {
                     .with_context(|| format!("failed to read stderr (umount)"))?,
             );
         }
+        Ok(())
+    }
 
-        let mut rmmod = Command::new("rmmod");
-        rmmod.arg("brd");
-        let output = rmmod.output()?;
-        if !output.status.success() {
-            bail!(
-                "failed to remove block ram device module fs: {:?}\n{}",
-                rmmod,
-                String::from_utf8(output.stderr)
-                    .with_context(|| format!("failed to read stderr (umount)"))?,
-            );
+    /// Second part of default teardown, removes device (if no other uses it) and mountpoint.
+    fn release(&self, path: &Path) -> anyhow::Result<()> {
+        // module is in use while other filesystem stays mounted
+        let mounts = fs::read_to_string("/proc/mounts")
+            .with_context(|| format!("failed to read mounted filesystems"))?;
+        if self.uses_ram_disk() && !ram_device_mounted(&mounts) {
+            let mut rmmod = Command::new("rmmod");
+            rmmod.arg("brd");
+            let output = rmmod.output()?;
+            if !output.status.success() {
+                bail!(
+                    "failed to remove block ram device module fs: {:?}\n{}",
+                    rmmod,
+                    String::from_utf8(output.stderr)
+                        .with_context(|| format!("failed to read stderr (umount)"))?,
+                );
+            }
         }
 
         fs::remove_dir_all(path)
@@ -99,6 +87,24 @@ pub trait FileSystemMount: Display {
         Ok(())
     }
 
+    /// Free space (in bytes) available to unprivileged user at mounted FS.
+    fn free_space(&self, path: &Path) -> anyhow::Result<u64> {
+        let mut stat = Command::new("stat");
+        stat.arg("-f").arg("-c").arg("%a %S").arg(path);
+        let output = stat.output()?;
+        if !output.status.success() {
+            bail!(
+                "failed to get fs stats: {:?}\n{}",
+                stat,
+                String::from_utf8(output.stderr)
+                    .with_context(|| format!("failed to read stderr (stat)"))?,
+            );
+        }
+        let stdout = String::from_utf8(output.stdout)
+            .with_context(|| format!("failed to read stdout (stat)"))?;
+        parse_free_space(&stdout)
+    }
+
     /// Used in default implementation: `mkfs` command to make new FS.
     /// Example: `"mkfs.ext4"` or `"mkfs.btrfs"`
     fn mkfs_cmd(&self) -> String {
@@ -126,4 +132,395 @@ pub trait FileSystemMount: Display {
     fn get_internal_dirs(&self) -> RegexSet {
         RegexSet::new::<_, &str>([]).unwrap()
     }
+
+    /// Used in default implementation: `mount -o` argument that limits capacity (in KiB) of filesystem
+    /// without ram disk, so free space is comparable.
+    /// Example: `size=1000000k` for tmpfs
+    fn capacity_opt(&self, _size: usize) -> Option<String> {
+        None
+    }
+
+    /// Filesystem is made with `mkfs` on block ram device.
+    /// Otherwise (e.g. tmpfs) it is mounted without backing device and `mkfs` is never called.
+    fn uses_ram_disk(&self) -> bool {
+        true
+    }
+
+    /// Names that differ only in case refer to the same entry (e.g. vfat or ext4 with casefold).
+    fn case_insensitive(&self) -> bool {
+        false
+    }
+
+    /// Used in default implementation: commands (with description) run after filesystem
+    /// is mounted at `path`, before any workload.
+    /// Example: `chattr +F` to make root casefolded
+    fn prepare_cmds(&self, _path: &Path) -> Vec<(&'static str, Command)> {
+        vec![]
+    }
+
+    /// Bytes extended attributes of one node may take in total (values and entries), `None` if only
+    /// each value is limited (to `XATTR_SIZE_MAX`).
+    fn xattr_space(&self) -> Option<u64> {
+        None
+    }
+
+    /// `fallocate` with these flags does not fail with `EOPNOTSUPP`.
+    fn fallocate_supported(&self, _mode: &[FallocateFlag]) -> bool {
+        true
+    }
+
+    /// Command that checks unmounted FS on device without repairing it, `None` if there is none.
+    /// Example: `fsck.ext4 -n -f /dev/ram0` or `btrfs check --readonly /dev/ram0`
+    fn check_cmd(&self, _device: &str) -> Option<Command> {
+        None
+    }
+
+    /// Shell command run by executor at crash point, filesystem is unmounted and mounted again
+    /// from the same device. Filesystems without ram disk are only remounted, since their data
+    /// would be lost otherwise.
+    fn remount_cmd(&self, path: &Path, device: &str) -> String {
+        if !self.uses_ram_disk() {
+            return format!("mount -o remount {}", quote(&path.to_string_lossy()));
+        }
+        let mount = mount_command(self, path, device, 0);
+        let mount: Vec<String> = [mount.get_program()]
+            .into_iter()
+            .chain(mount.get_args())
+            .map(|arg| quote(&arg.to_string_lossy()))
+            .collect();
+        format!(
+            "umount {} && {}",
+            quote(&path.to_string_lossy()),
+            mount.join(" ")
+        )
+    }
+
+    /// Runs [`FileSystemMount::check_cmd`], returns its exit status and output.
+    ///
+    /// Failed check is not an error, since it is what check is run for.
+    fn check(&self, device: &str) -> anyhow::Result<Option<String>> {
+        let Some(mut cmd) = self.check_cmd(device) else {
+            return Ok(None);
+        };
+        debug!("checking '{}' filesystem on '{}'", self, device);
+        let output = cmd
+            .output()
+            .with_context(|| format!("failed to check fs: {:?}", cmd))?;
+        Ok(Some(format!(
+            "{:?}\n{}\n\n{}{}",
+            cmd,
+            output.status,
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )))
+    }
+}
+
+pub(super) fn run_setup_commands<FS: FileSystemMount + ?Sized>(
+    fs: &FS,
+    path: &Path,
+    device: &str,
+    ram_disk_size: usize,
+) -> anyhow::Result<()> {
+    debug!(
+        "setting up '{}' filesystem at '{}' on '{}'",
+        fs,
+        path.display(),
+        device
+    );
+
+    fs::create_dir_all(path)
+        .with_context(|| format!("failed to create mountpoint at '{}'", path.display()))?;
+
+    if fs.uses_ram_disk() {
+        unload_resized_ram_disk(ram_disk_size)?;
+    }
+
+    for (what, mut cmd) in setup_commands(fs, path, device, ram_disk_size) {
+        let output = cmd
+            .output()
+            .with_context(|| format!("failed to {}: {:?}", what, cmd))?;
+        if !output.status.success() {
+            bail!(
+                "failed to {}: {:?}\n{}",
+                what,
+                cmd,
+                String::from_utf8(output.stderr)
+                    .with_context(|| format!("failed to read stderr ({})", what))?,
+            );
+        }
+    }
+    Ok(())
+}
+
+/// `modprobe` keeps module that is already loaded as is, so it is removed if its ram disks are of other size.
+fn unload_resized_ram_disk(ram_disk_size: usize) -> anyhow::Result<()> {
+    let Ok(loaded_size) = fs::read_to_string(RAM_DISK_SIZE_PARAM) else {
+        // module is not loaded
+        return Ok(());
+    };
+    let mounts = fs::read_to_string("/proc/mounts")
+        .with_context(|| format!("failed to read mounted filesystems"))?;
+    if !ram_disk_resized(&loaded_size, ram_disk_size, &mounts)? {
+        return Ok(());
+    }
+    debug!(
+        "reloading block ram device module with ram disk size {} KiB",
+        ram_disk_size
+    );
+    let mut rmmod = Command::new("rmmod");
+    rmmod.arg("brd");
+    let output = rmmod.output()?;
+    if !output.status.success() {
+        bail!(
+            "failed to remove block ram device module: {:?}\n{}",
+            rmmod,
+            String::from_utf8(output.stderr)
+                .with_context(|| format!("failed to read stderr (rmmod)"))?,
+        );
+    }
+    Ok(())
+}
+
+/// Ram disks of loaded module differ in size from requested one, error if module can't be reloaded.
+fn ram_disk_resized(loaded_size: &str, ram_disk_size: usize, mounts: &str) -> anyhow::Result<bool> {
+    let loaded_size: usize = loaded_size
+        .trim()
+        .parse()
+        .with_context(|| format!("failed to parse ram disk size '{}'", loaded_size.trim()))?;
+    if loaded_size == ram_disk_size {
+        return Ok(false);
+    }
+    if ram_device_mounted(mounts) {
+        bail!(
+            "block ram device module is loaded with ram disk size {} KiB instead of {} KiB and is in use",
+            loaded_size,
+            ram_disk_size
+        );
+    }
+    Ok(true)
+}
+
+/// Commands (with description) run in order to make and mount filesystem.
+pub(super) fn setup_commands<FS: FileSystemMount + ?Sized>(
+    fs: &FS,
+    path: &Path,
+    device: &str,
+    ram_disk_size: usize,
+) -> Vec<(&'static str, Command)> {
+    let mut commands = vec![];
+    if fs.uses_ram_disk() {
+        let mut modprobe = Command::new("modprobe");
+        modprobe
+            .arg("brd")
+            .arg(format!("rd_nr={}", DEVICES.len()))
+            .arg(format!("rd_size={ram_disk_size}"));
+        commands.push(("load block ram device module", modprobe));
+
+        let mut mkfs = Command::new(fs.mkfs_cmd());
+        if let Some(opts) = fs.mkfs_opts() {
+            mkfs.arg("-O");
+            mkfs.arg(opts);
+        }
+        mkfs.arg(device);
+        commands.push(("create fs", mkfs));
+    }
+
+    commands.push(("mount fs", mount_command(fs, path, device, ram_disk_size)));
+    commands.extend(fs.prepare_cmds(path));
+    commands
+}
+
+fn mount_command<FS: FileSystemMount + ?Sized>(
+    fs: &FS,
+    path: &Path,
+    device: &str,
+    ram_disk_size: usize,
+) -> Command {
+    let mut mount = Command::new("mount");
+    mount.arg("-t").arg(fs.mount_t());
+    let capacity = if fs.uses_ram_disk() {
+        None
+    } else {
+        fs.capacity_opt(ram_disk_size)
+    };
+    let opts: Vec<String> = fs.mount_opts().into_iter().chain(capacity).collect();
+    if !opts.is_empty() {
+        mount.arg("-o");
+        mount.arg(opts.join(","));
+    }
+    if fs.uses_ram_disk() {
+        mount.arg(device);
+    } else {
+        mount.arg(fs.mount_t());
+    }
+    mount.arg(path);
+    mount
+}
+
+fn quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+fn ram_device_mounted(mounts: &str) -> bool {
+    mounts.lines().any(|line| {
+        DEVICES
+            .iter()
+            .any(|device| line.starts_with(&format!("{} ", device)))
+    })
+}
+
+/// Parses output of `stat -f -c '%a %S'` (available blocks and block size).
+fn parse_free_space(output: &str) -> anyhow::Result<u64> {
+    let parts: Vec<&str> = output.split_whitespace().collect();
+    if parts.len() != 2 {
+        bail!("unexpected fs stats output '{}'", output.trim());
+    }
+    let blocks: u64 = parts[0]
+        .parse()
+        .with_context(|| format!("failed to parse available blocks '{}'", parts[0]))?;
+    let block_size: u64 = parts[1]
+        .parse()
+        .with_context(|| format!("failed to parse block size '{}'", parts[1]))?;
+    Ok(blocks * block_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mount::ext4::Ext4;
+    use crate::mount::tmpfs::Tmpfs;
+    use crate::save::save_check;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_free_space() {
+        assert_eq!(1024 * 4096, parse_free_space("1024 4096\n").unwrap());
+        assert!(parse_free_space("1024").is_err());
+        assert!(parse_free_space("foo 4096").is_err());
+    }
+
+    #[test]
+    fn test_setup_commands() {
+        let commands: Vec<String> = setup_commands(
+            &Ext4::new(),
+            Path::new("/mnt/ext4"),
+            devices(0)[0],
+            1_000_000,
+        )
+        .iter()
+        .map(|(_, cmd)| format!("{:?}", cmd))
+        .collect();
+        assert_eq!(3, commands.len());
+        assert_eq!(
+            "\"modprobe\" \"brd\" \"rd_nr=32\" \"rd_size=1000000\"",
+            commands[0]
+        );
+        assert_eq!("\"mkfs.ext4\" \"/dev/ram0\"", commands[1]);
+        assert_eq!(
+            "\"mount\" \"-t\" \"ext4\" \"/dev/ram0\" \"/mnt/ext4\"",
+            commands[2]
+        );
+    }
+
+    #[test]
+    fn test_remount_cmd() {
+        assert_eq!(
+            "umount '/mnt/ext4' && 'mount' '-t' 'ext4' '/dev/ram1' '/mnt/ext4'",
+            Ext4::new().remount_cmd(Path::new("/mnt/ext4"), devices(0)[1])
+        );
+        assert_eq!(
+            "mount -o remount '/mnt/it'\\''s'",
+            Tmpfs::new().remount_cmd(Path::new("/mnt/it's"), devices(0)[0])
+        );
+    }
+
+    #[test]
+    fn test_setup_commands_per_device() {
+        let commands = |device| -> Vec<String> {
+            setup_commands(&Ext4::new(), Path::new("/mnt/ext4"), device, 4_000_000)
+                .iter()
+                .map(|(_, cmd)| format!("{:?}", cmd))
+                .collect()
+        };
+        // module is loaded once with every device, whichever harness is set up first
+        assert_eq!(
+            vec![
+                "\"modprobe\" \"brd\" \"rd_nr=32\" \"rd_size=4000000\"",
+                "\"mkfs.ext4\" \"/dev/ram0\"",
+                "\"mount\" \"-t\" \"ext4\" \"/dev/ram0\" \"/mnt/ext4\"",
+            ],
+            commands(devices(0)[0])
+        );
+        assert_eq!(
+            vec![
+                "\"modprobe\" \"brd\" \"rd_nr=32\" \"rd_size=4000000\"",
+                "\"mkfs.ext4\" \"/dev/ram1\"",
+                "\"mount\" \"-t\" \"ext4\" \"/dev/ram1\" \"/mnt/ext4\"",
+            ],
+            commands(devices(0)[1])
+        );
+    }
+
+    #[test]
+    fn test_ram_disk_resized() {
+        let unmounted = "proc /proc proc rw 0 0\n";
+        let mounted = "/dev/ram1 /mnt/snd.ext4 ext4 rw 0 0\n";
+        assert!(!ram_disk_resized("4000000\n", 4_000_000, mounted).unwrap());
+        assert!(ram_disk_resized("1000000\n", 4_000_000, unmounted).unwrap());
+        assert!(ram_disk_resized("1000000\n", 4_000_000, mounted).is_err());
+        assert!(ram_disk_resized("", 4_000_000, unmounted).is_err());
+    }
+
+    struct Checked;
+
+    impl Display for Checked {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Checked")
+        }
+    }
+
+    impl FileSystemMount for Checked {
+        fn check_cmd(&self, device: &str) -> Option<Command> {
+            let mut cmd = Command::new("sh");
+            cmd.arg("-c").arg(format!(
+                "echo '{}: bad inode'; echo 'errors found' >&2; exit 4",
+                device
+            ));
+            Some(cmd)
+        }
+    }
+
+    #[test]
+    fn test_check_output_saved() {
+        assert_eq!(None, Tmpfs::new().check(devices(0)[0]).unwrap());
+
+        let output = Checked.check(devices(0)[0]).unwrap().unwrap();
+        assert!(output.starts_with("\"sh\" \"-c\""));
+        assert!(output.contains("exit status: 4"));
+        assert!(output.ends_with("/dev/ram0: bad inode\nerrors found\n"));
+
+        let dir = std::env::temp_dir().join("DIFFuzzer-check-output-test");
+        fs::remove_dir_all(&dir).unwrap_or(());
+        fs::create_dir_all(&dir).unwrap();
+        save_check(&dir, &Checked.to_string(), &output).unwrap();
+        assert_eq!(
+            output,
+            fs::read_to_string(dir.join("Checked.check.txt")).unwrap()
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_ram_device_mounted() {
+        let mounts = "proc /proc proc rw 0 0\n/dev/sda1 / ext4 rw 0 0\n";
+        assert!(!ram_device_mounted(mounts));
+        let mounts = format!("{}/dev/ram1 /mnt/btrfs/fstest btrfs rw 0 0\n", mounts);
+        assert!(ram_device_mounted(&mounts));
+        // device of other instance
+        assert!(ram_device_mounted(
+            "/dev/ram10 /mnt/DIFFuzzer-2 ext4 rw 0 0\n"
+        ));
+        assert!(!ram_device_mounted("/dev/ram100 /mnt ext4 rw 0 0\n"));
+    }
 }
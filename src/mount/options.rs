@@ -0,0 +1,264 @@
+use std::{fmt::Display, path::Path, process::Command};
+
+use anyhow::bail;
+use rand::{seq::SliceRandom, Rng};
+use regex::RegexSet;
+use serde::{Deserialize, Serialize};
+
+use crate::abstract_fs::flags::FallocateFlag;
+
+use super::mount::{run_setup_commands, FileSystemMount};
+
+/// One candidate set of options, the ones not set are taken from the filesystem itself.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MountOptions {
+    pub mkfs_opts: Option<String>,
+    pub mount_opts: Option<String>,
+    pub weight: u32,
+}
+
+impl Display for MountOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "mkfs -O '{}', mount -o '{}'",
+            self.mkfs_opts.as_deref().unwrap_or(""),
+            self.mount_opts.as_deref().unwrap_or("")
+        )
+    }
+}
+
+/// Candidate option sets for first and second filesystem.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MountOptionsConfig {
+    pub fst: Vec<MountOptions>,
+    pub snd: Vec<MountOptions>,
+}
+
+impl MountOptionsConfig {
+    /// Each filesystem needs a candidate that can be picked, see [`choose_options`].
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for (fs, candidates) in [("first", &self.fst), ("second", &self.snd)] {
+            if candidates.iter().all(|options| options.weight == 0) {
+                bail!(
+                    "no mount options of {} filesystem can be picked, every weight is zero or none given",
+                    fs
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Same filesystem created and mounted with one of candidate option sets,
+/// filesystems with their own setup (e.g. overlay) set up and tear down themselves.
+pub struct OptionsMount {
+    inner: &'static dyn FileSystemMount,
+    options: MountOptions,
+    index: usize,
+}
+
+impl Display for OptionsMount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-opts{}", self.inner, self.index)
+    }
+}
+
+impl FileSystemMount for OptionsMount {
+    fn setup(&self, path: &Path, device: &str, ram_disk_size: usize) -> anyhow::Result<()> {
+        if self.inner.default_setup() {
+            run_setup_commands(self, path, device, ram_disk_size)
+        } else {
+            self.inner.setup(path, device, ram_disk_size)
+        }
+    }
+
+    fn default_setup(&self) -> bool {
+        self.inner.default_setup()
+    }
+
+    fn teardown(&self, path: &Path) -> anyhow::Result<()> {
+        self.inner.teardown(path)
+    }
+
+    fn unmount(&self, path: &Path) -> anyhow::Result<()> {
+        self.inner.unmount(path)
+    }
+
+    fn release(&self, path: &Path) -> anyhow::Result<()> {
+        self.inner.release(path)
+    }
+
+    fn mkfs_cmd(&self) -> String {
+        self.inner.mkfs_cmd()
+    }
+
+    fn mkfs_opts(&self) -> Option<String> {
+        self.options.mkfs_opts.clone().or(self.inner.mkfs_opts())
+    }
+
+    fn mount_t(&self) -> String {
+        self.inner.mount_t()
+    }
+
+    fn mount_opts(&self) -> Option<String> {
+        self.options.mount_opts.clone().or(self.inner.mount_opts())
+    }
+
+    fn get_internal_dirs(&self) -> RegexSet {
+        self.inner.get_internal_dirs()
+    }
+
+    fn uses_ram_disk(&self) -> bool {
+        self.inner.uses_ram_disk()
+    }
+
+    fn case_insensitive(&self) -> bool {
+        self.inner.case_insensitive()
+    }
+
+    fn prepare_cmds(&self, path: &Path) -> Vec<(&'static str, Command)> {
+        self.inner.prepare_cmds(path)
+    }
+
+    fn xattr_space(&self) -> Option<u64> {
+        self.inner.xattr_space()
+    }
+
+    fn fallocate_supported(&self, mode: &[FallocateFlag]) -> bool {
+        self.inner.fallocate_supported(mode)
+    }
+
+    fn check_cmd(&self, device: &str) -> Option<Command> {
+        self.inner.check_cmd(device)
+    }
+
+    fn capacity_opt(&self, size: usize) -> Option<String> {
+        self.inner.capacity_opt(size)
+    }
+}
+
+impl OptionsMount {
+    pub fn new(inner: &'static dyn FileSystemMount, options: MountOptions, index: usize) -> Self {
+        Self {
+            inner,
+            options,
+            index,
+        }
+    }
+}
+
+/// Mounts live for the whole campaign, so they are leaked to match static filesystems.
+pub fn with_mount_options(
+    inner: &'static dyn FileSystemMount,
+    candidates: &[MountOptions],
+) -> Vec<&'static dyn FileSystemMount> {
+    candidates
+        .iter()
+        .enumerate()
+        .map(|(index, options)| {
+            let mount: &'static dyn FileSystemMount =
+                Box::leak(Box::new(OptionsMount::new(inner, options.clone(), index)));
+            mount
+        })
+        .collect()
+}
+
+/// Index of candidate picked according to weights, candidates are checked by [`MountOptionsConfig::validate`].
+pub fn choose_options(rng: &mut impl Rng, candidates: &[MountOptions]) -> usize {
+    let indices: Vec<usize> = (0..candidates.len()).collect();
+    *indices
+        .choose_weighted(rng, |index| candidates[*index].weight)
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::mount::{ext4::Ext4, overlayfs::Overlayfs};
+
+    use super::*;
+
+    static EXT4: Ext4 = Ext4::new();
+
+    fn candidates() -> Vec<MountOptions> {
+        vec![
+            MountOptions {
+                mkfs_opts: Some("^has_journal".to_owned()),
+                mount_opts: None,
+                weight: 1,
+            },
+            MountOptions {
+                mkfs_opts: None,
+                mount_opts: Some("data=journal,noatime".to_owned()),
+                weight: 3,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_validate() {
+        let config = |fst, snd| MountOptionsConfig { fst, snd };
+        assert!(config(candidates(), candidates()).validate().is_ok());
+        assert!(config(candidates(), vec![]).validate().is_err());
+        let mut unweighted = candidates();
+        unweighted.iter_mut().for_each(|options| options.weight = 0);
+        assert!(config(unweighted, candidates()).validate().is_err());
+    }
+
+    #[test]
+    fn test_options_mount() {
+        let mounts = with_mount_options(&EXT4, &candidates());
+        assert_eq!("Ext4-opts0", mounts[0].to_string());
+        assert_eq!(Some("^has_journal".to_owned()), mounts[0].mkfs_opts());
+        assert_eq!(EXT4.mount_opts(), mounts[0].mount_opts());
+        assert_eq!(EXT4.mkfs_opts(), mounts[1].mkfs_opts());
+        assert_eq!(
+            Some("data=journal,noatime".to_owned()),
+            mounts[1].mount_opts()
+        );
+        assert_eq!(
+            "mkfs -O '', mount -o 'data=journal,noatime'",
+            candidates()[1].to_string()
+        );
+    }
+
+    #[test]
+    fn test_distribution_follows_weights() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let candidates = candidates();
+        let mut picked = [0; 2];
+        for _ in 0..4000 {
+            picked[choose_options(&mut rng, &candidates)] += 1;
+        }
+        assert!((800..1200).contains(&picked[0]), "{:?}", picked);
+        assert!((2800..3200).contains(&picked[1]), "{:?}", picked);
+    }
+
+    #[test]
+    fn test_config() {
+        let config: MountOptionsConfig = toml::from_str(
+            r#"
+[[fst]]
+mkfs_opts = "^has_journal"
+weight = 1
+
+[[snd]]
+mount_opts = "compress=zstd"
+weight = 2
+"#,
+        )
+        .unwrap();
+        assert_eq!(None, config.fst[0].mount_opts);
+        assert_eq!(Some("compress=zstd".to_owned()), config.snd[0].mount_opts);
+    }
+
+    #[test]
+    fn test_own_setup_forwarded() {
+        static OVERLAY: Overlayfs = Overlayfs::new();
+        let mounts = with_mount_options(&OVERLAY, &candidates());
+        assert!(mounts.iter().all(|mount| !mount.default_setup()));
+        assert!(with_mount_options(&EXT4, &candidates())[0].default_setup());
+    }
+}
@@ -0,0 +1,193 @@
+use std::{
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{bail, Context};
+use log::debug;
+use regex::RegexSet;
+
+use super::mount::FileSystemMount;
+
+/// Overlay of upper directory over lower one, both kept in backing directory next to mountpoint.
+///
+/// Overlay does not live on a single block device, so it has its own setup and teardown.
+/// Lower layer is seeded with [`SEED_DIR`], so root is merged from both layers and every lookup and
+/// listing in it goes through overlay. Seeded nodes are internal, they are not compared.
+pub struct Overlayfs {
+    lower: &'static str,
+    upper: &'static str,
+    work: &'static str,
+}
+
+impl Display for Overlayfs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Overlayfs")
+    }
+}
+
+impl FileSystemMount for Overlayfs {
+    fn setup(&self, path: &Path, _device: &str, _ram_disk_size: usize) -> anyhow::Result<()> {
+        debug!("setting up '{}' filesystem at '{}'", self, path.display());
+
+        fs::create_dir_all(path)
+            .with_context(|| format!("failed to create mountpoint at '{}'", path.display()))?;
+        let backing = backing_dir(path);
+        for dir in [self.lower, self.upper, self.work] {
+            let dir = backing.join(dir);
+            fs::create_dir_all(&dir)
+                .with_context(|| format!("failed to create overlay dir at '{}'", dir.display()))?;
+        }
+        let seed = backing.join(self.lower).join(SEED_DIR);
+        seed_lower(&seed)
+            .with_context(|| format!("failed to seed lower layer at '{}'", seed.display()))?;
+
+        let mut mount = self.mount_cmd(path);
+        let output = mount.output()?;
+        if !output.status.success() {
+            bail!(
+                "failed to mount fs: {:?}\n{}",
+                mount,
+                String::from_utf8(output.stderr)
+                    .with_context(|| format!("failed to read stderr (mount)"))?,
+            );
+        }
+        Ok(())
+    }
+
+    fn teardown(&self, path: &Path) -> anyhow::Result<()> {
+        debug!("tearing down '{}' filesystem at '{}'", self, path.display());
+
+        let mut umount = Command::new("umount");
+        umount.arg("-fl").arg(path);
+        let output = umount.output()?;
+        if !output.status.success() {
+            bail!(
+                "failed to unmount fs: {:?}\n{}",
+                umount,
+                String::from_utf8(output.stderr)
+                    .with_context(|| format!("failed to read stderr (umount)"))?,
+            );
+        }
+
+        fs::remove_dir_all(path)
+            .with_context(|| format!("failed to remove mountpoint at '{}'", path.display()))?;
+        let backing = backing_dir(path);
+        fs::remove_dir_all(&backing)
+            .with_context(|| format!("failed to remove overlay dirs at '{}'", backing.display()))?;
+
+        Ok(())
+    }
+
+    fn mount_t(&self) -> String {
+        "overlay".to_owned()
+    }
+
+    /// Work directory is outside of mountpoint, only seeded nodes of lower layer are visible.
+    fn get_internal_dirs(&self) -> RegexSet {
+        RegexSet::new([format!(r"^/?{}($|/)", regex::escape(SEED_DIR))]).unwrap()
+    }
+
+    fn uses_ram_disk(&self) -> bool {
+        false
+    }
+
+    fn default_setup(&self) -> bool {
+        false
+    }
+}
+
+impl Overlayfs {
+    pub const fn new() -> Self {
+        Self::with_dirs("lower", "upper", "work")
+    }
+
+    /// Directories are relative to backing directory.
+    pub const fn with_dirs(lower: &'static str, upper: &'static str, work: &'static str) -> Self {
+        Self { lower, upper, work }
+    }
+
+    fn mount_cmd(&self, path: &Path) -> Command {
+        let backing = backing_dir(path);
+        let mut mount = Command::new("mount");
+        mount
+            .arg("-t")
+            .arg(self.mount_t())
+            .arg("-o")
+            .arg(format!(
+                "lowerdir={},upperdir={},workdir={}",
+                backing.join(self.lower).display(),
+                backing.join(self.upper).display(),
+                backing.join(self.work).display()
+            ))
+            .arg(self.mount_t())
+            .arg(path);
+        mount
+    }
+}
+
+/// Directory with overlay layers, e.g. `/mnt/overlayfs/fstest.overlay` for `/mnt/overlayfs/fstest`.
+fn backing_dir(path: &Path) -> PathBuf {
+    path.with_extension("overlay")
+}
+
+/// Directory in root of lower layer, its name never clashes with names of generated nodes.
+pub const SEED_DIR: &str = ".lower";
+
+/// Nodes overlay handles differently when they are copied up: directory, file with data and symlink.
+fn seed_lower(seed: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(seed.join("dir"))?;
+    fs::write(seed.join("dir").join("file"), "lower layer data")?;
+    fs::write(seed.join("file"), vec![0xAB; 8192])?;
+    let link = seed.join("link");
+    if !link.is_symlink() {
+        std::os::unix::fs::symlink("dir/file", link)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mount_cmd() {
+        let overlay = Overlayfs::new();
+        assert_eq!(
+            "\"mount\" \"-t\" \"overlay\" \"-o\" \
+            \"lowerdir=/mnt/fstest.overlay/lower,upperdir=/mnt/fstest.overlay/upper,workdir=/mnt/fstest.overlay/work\" \
+            \"overlay\" \"/mnt/fstest\"",
+            format!("{:?}", overlay.mount_cmd(Path::new("/mnt/fstest")))
+        );
+    }
+
+    #[test]
+    fn test_seed_is_internal() {
+        let overlay = Overlayfs::with_dirs("l", "u", "w");
+        let internal = overlay.get_internal_dirs();
+        assert!(internal.is_match("/.lower"));
+        assert!(internal.is_match(".lower/dir/file"));
+        assert!(!internal.is_match("/xlower"));
+        assert!(!internal.is_match("/.lower2"));
+        assert!(!internal.is_match("/w"));
+    }
+
+    #[test]
+    fn test_seed_lower() {
+        let seed = std::env::temp_dir()
+            .join("DIFFuzzer-overlay-seed-test")
+            .join(SEED_DIR);
+        fs::remove_dir_all(seed.parent().unwrap()).unwrap_or(());
+        seed_lower(&seed).unwrap();
+        // seeding again (e.g. after failed teardown) keeps the same nodes
+        seed_lower(&seed).unwrap();
+        assert_eq!(
+            "lower layer data",
+            fs::read_to_string(seed.join("link")).unwrap()
+        );
+        assert_eq!(8192, fs::metadata(seed.join("file")).unwrap().len());
+        fs::remove_dir_all(seed.parent().unwrap()).unwrap();
+    }
+}
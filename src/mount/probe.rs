@@ -0,0 +1,164 @@
+use std::{fs, process::Command};
+
+use anyhow::bail;
+use log::info;
+
+use super::mount::FileSystemMount;
+
+const COMMON_TOOLS: &[&str] = &["modprobe", "rmmod", "mount", "umount", "stat"];
+const RAM_DISK_MODULE: &str = "brd";
+
+/// Checks what is available on the host before any filesystem is set up.
+pub trait HostProbe {
+    fn has_command(&self, cmd: &str) -> bool;
+    fn has_module(&self, module: &str) -> bool;
+}
+
+pub struct SystemProbe;
+
+impl HostProbe for SystemProbe {
+    fn has_command(&self, cmd: &str) -> bool {
+        Command::new("which")
+            .arg(cmd)
+            .output()
+            .is_ok_and(|output| output.status.success())
+    }
+
+    /// Filesystem can be built into kernel, in this case `/proc/filesystems` lists it.
+    fn has_module(&self, module: &str) -> bool {
+        let builtin = fs::read_to_string("/proc/filesystems").is_ok_and(|filesystems| {
+            filesystems
+                .lines()
+                .any(|line| line.split_whitespace().last() == Some(module))
+        });
+        builtin
+            || Command::new("modprobe")
+                .arg("-n")
+                .arg(module)
+                .output()
+                .is_ok_and(|output| output.status.success())
+    }
+}
+
+/// Returns a list of tools and modules required by filesystem but missing on the host.
+pub fn missing_requirements(fs: &dyn FileSystemMount, probe: &dyn HostProbe) -> Vec<String> {
+    let mut missing = vec![];
+    if fs.uses_ram_disk() {
+        let mkfs = fs.mkfs_cmd();
+        if !probe.has_command(&mkfs) {
+            missing.push(format!("command '{}'", mkfs));
+        }
+    }
+    let module = fs.mount_t();
+    if !probe.has_module(&module) {
+        missing.push(format!("module '{}'", module));
+    }
+    missing
+}
+
+/// Fails with a list of everything missing for selected filesystems.
+pub fn check_host(
+    filesystems: &[&dyn FileSystemMount],
+    probe: &dyn HostProbe,
+) -> anyhow::Result<()> {
+    let mut missing = vec![];
+    for tool in COMMON_TOOLS {
+        if !probe.has_command(tool) {
+            missing.push(format!("command '{}'", tool));
+        }
+    }
+    if filesystems.iter().any(|fs| fs.uses_ram_disk()) && !probe.has_module(RAM_DISK_MODULE) {
+        missing.push(format!("module '{}'", RAM_DISK_MODULE));
+    }
+    for fs in filesystems {
+        for requirement in missing_requirements(*fs, probe) {
+            missing.push(format!("{} ({})", requirement, fs));
+        }
+    }
+    if !missing.is_empty() {
+        bail!("missing on host:\n{}", missing.join("\n"));
+    }
+    info!(
+        "host supports {}",
+        filesystems
+            .iter()
+            .map(|fs| fs.to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::mount::{btrfs::Btrfs, ext4::Ext4, tmpfs::Tmpfs};
+
+    use super::*;
+
+    struct MockProbe {
+        commands: HashSet<&'static str>,
+        modules: HashSet<&'static str>,
+    }
+
+    impl HostProbe for MockProbe {
+        fn has_command(&self, cmd: &str) -> bool {
+            self.commands.contains(cmd)
+        }
+        fn has_module(&self, module: &str) -> bool {
+            self.modules.contains(module)
+        }
+    }
+
+    fn full_probe() -> MockProbe {
+        let mut commands: HashSet<&'static str> = COMMON_TOOLS.iter().copied().collect();
+        commands.insert("mkfs.ext4");
+        commands.insert("mkfs.btrfs");
+        MockProbe {
+            commands,
+            modules: HashSet::from(["brd", "ext4", "btrfs"]),
+        }
+    }
+
+    #[test]
+    fn test_all_present() {
+        let probe = full_probe();
+        assert!(missing_requirements(&Ext4::new(), &probe).is_empty());
+        check_host(&[&Ext4::new(), &Btrfs::new()], &probe).unwrap();
+    }
+
+    #[test]
+    fn test_missing_mkfs() {
+        let mut probe = full_probe();
+        probe.commands.remove("mkfs.btrfs");
+        assert!(missing_requirements(&Ext4::new(), &probe).is_empty());
+        assert_eq!(
+            vec!["command 'mkfs.btrfs'".to_owned()],
+            missing_requirements(&Btrfs::new(), &probe)
+        );
+        let err = check_host(&[&Ext4::new(), &Btrfs::new()], &probe).unwrap_err();
+        assert!(err.to_string().contains("command 'mkfs.btrfs' (Btrfs)"));
+    }
+
+    #[test]
+    fn test_missing_modules() {
+        let mut probe = full_probe();
+        probe.modules.remove("brd");
+        probe.modules.remove("ext4");
+        probe.commands.remove("umount");
+        let err = check_host(&[&Ext4::new()], &probe).unwrap_err().to_string();
+        assert!(err.contains("module 'brd'"));
+        assert!(err.contains("module 'ext4' (Ext4)"));
+        assert!(err.contains("command 'umount'"));
+    }
+
+    #[test]
+    fn test_tmpfs_needs_no_ram_disk() {
+        let mut probe = full_probe();
+        probe.modules.remove("brd");
+        probe.modules.insert("tmpfs");
+        check_host(&[&Tmpfs::new()], &probe).unwrap();
+        assert!(check_host(&[&Tmpfs::new(), &Ext4::new()], &probe).is_err());
+    }
+}
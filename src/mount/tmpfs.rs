@@ -0,0 +1,69 @@
+use std::fmt::Display;
+
+use crate::abstract_fs::flags::FallocateFlag;
+
+use super::mount::FileSystemMount;
+
+/// In-memory filesystem, fast reference for differential testing.
+///
+/// Mounted directly without block ram device and `mkfs`.
+pub struct Tmpfs;
+
+impl Display for Tmpfs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Tmpfs")
+    }
+}
+
+impl FileSystemMount for Tmpfs {
+    fn mount_t(&self) -> String {
+        "tmpfs".to_owned()
+    }
+    fn capacity_opt(&self, size: usize) -> Option<String> {
+        Some(format!("size={}k", size))
+    }
+
+    fn uses_ram_disk(&self) -> bool {
+        false
+    }
+
+    /// Only allocation and punching holes are supported.
+    fn fallocate_supported(&self, mode: &[FallocateFlag]) -> bool {
+        !mode.contains(&FallocateFlag::FALLOC_FL_ZERO_RANGE)
+    }
+}
+
+impl Tmpfs {
+    pub const fn new() -> Self {
+        Self {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::mount::mount::{devices, setup_commands};
+
+    use super::*;
+
+    #[test]
+    fn test_setup_commands() {
+        let commands: Vec<String> = setup_commands(
+            &Tmpfs::new(),
+            Path::new("/mnt/tmpfs"),
+            devices(0)[0],
+            1_000_000,
+        )
+        .iter()
+        .map(|(_, cmd)| format!("{:?}", cmd))
+        .collect();
+        assert_eq!(
+            vec!["\"mount\" \"-t\" \"tmpfs\" \"-o\" \"size=1000000k\" \"tmpfs\" \"/mnt/tmpfs\""],
+            commands
+        );
+        assert!(commands
+            .iter()
+            .all(|cmd| !cmd.contains("mkfs") && !cmd.contains("brd")));
+    }
+}
@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{fmt::Display, process::Command};
 
 use super::mount::FileSystemMount;
 
@@ -17,6 +17,12 @@ impl FileSystemMount for XFS {
     fn mount_t(&self) -> String {
         "xfs".to_owned()
     }
+
+    fn check_cmd(&self, device: &str) -> Option<Command> {
+        let mut cmd = Command::new("xfs_repair");
+        cmd.arg("-n").arg(device);
+        Some(cmd)
+    }
 }
 
 impl XFS {
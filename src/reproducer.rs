@@ -0,0 +1,52 @@
+use std::{fs, os::unix::fs::PermissionsExt, path::Path};
+
+use anyhow::Context;
+use log::info;
+
+use dif_fuzzer::abstract_fs::workload::Workload;
+
+/// Writes standalone C++ reproducer of testcase (and shell script if asked), nothing is mounted or run.
+pub fn run(test_path: &Path, output: &Path, shell: bool) -> anyhow::Result<()> {
+    info!("exporting reproducer of '{}'", test_path.display());
+    let input = fs::read_to_string(test_path)
+        .with_context(|| format!("failed to read testcase at '{}'", test_path.display()))?;
+    let input: Workload = serde_json::from_str(&input)
+        .with_context(|| format!("failed to parse testcase at '{}'", test_path.display()))?;
+    let executor_dir = Path::new("executor");
+    let read_executor = |name: &str| {
+        let path = executor_dir.join(name);
+        fs::read_to_string(&path)
+            .with_context(|| format!("failed to read executor source at '{}'", path.display()))
+    };
+    // nothing is written if workload can not be exported
+    let script = shell
+        .then(|| input.encode_shell())
+        .transpose()
+        .with_context(|| format!("failed to export shell script"))?;
+    let reproducer = input.encode_reproducer(
+        &read_executor("executor.h")?,
+        &read_executor("executor.cpp")?,
+    );
+    fs::write(output, reproducer)
+        .with_context(|| format!("failed to write reproducer at '{}'", output.display()))?;
+    info!("reproducer saved at '{}'", output.display());
+    if let Some(script) = script {
+        let script_path = output.with_extension("sh");
+        fs::write(&script_path, script).with_context(|| {
+            format!(
+                "failed to write shell script at '{}'",
+                script_path.display()
+            )
+        })?;
+        fs::set_permissions(&script_path, fs::Permissions::from_mode(0o755)).with_context(
+            || {
+                format!(
+                    "failed to make shell script executable at '{}'",
+                    script_path.display()
+                )
+            },
+        )?;
+        info!("shell script saved at '{}'", script_path.display());
+    }
+    Ok(())
+}
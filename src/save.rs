@@ -1,5 +1,6 @@
 use std::fs::OpenOptions;
 use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
 use std::{fs, path::Path};
 
 use anyhow::Context;
@@ -9,13 +10,24 @@ use crate::abstract_fs::{
     trace::TRACE_FILENAME,
     workload::Workload,
 };
+use crate::fuzzing::objective::statfs::StatfsDiff;
+use crate::fuzzing::reason::Reason;
 use crate::hasher::hasher::FileDiff::{DifferentHash, OneExists};
 use crate::hasher::hasher::{FileDiff, DIFF_HASH_FILENAME};
 
+pub const TESTCASE_JSON_FILENAME: &str = "test.json";
+
 pub fn save_testcase(dir: &Path, input_path: &Path, input: &Workload) -> anyhow::Result<()> {
+    // source is copied (not encoded again) because it can have extra calls (e.g. delays)
+    let compiled_source_path = input_path.with_file_name(TEST_SOURCE_FILENAME);
     let source_path = dir.join(TEST_SOURCE_FILENAME);
-    fs::write(&source_path, input.clone().encode_c())
-        .with_context(|| format!("failed to save source file to '{}'", source_path.display()))?;
+    fs::copy(&compiled_source_path, &source_path).with_context(|| {
+        format!(
+            "failed to copy source file from '{}' to '{}'",
+            compiled_source_path.display(),
+            source_path.display()
+        )
+    })?;
 
     let exe_path = dir.join(TEST_EXE_FILENAME);
     fs::copy(&input_path, &exe_path).with_context(|| {
@@ -26,7 +38,7 @@ pub fn save_testcase(dir: &Path, input_path: &Path, input: &Workload) -> anyhow:
         )
     })?;
 
-    let json_path = dir.join("test").with_extension("json");
+    let json_path = dir.join(TESTCASE_JSON_FILENAME);
     let json = serde_json::to_string_pretty(&input).with_context(|| {
         format!(
             "failed to copy workload as json at '{}'",
@@ -37,6 +49,13 @@ pub fn save_testcase(dir: &Path, input_path: &Path, input: &Workload) -> anyhow:
     Ok(())
 }
 
+/// Name of filesystem on one side of comparison (`fst`, `snd` or `other<index>`), used for its
+/// mountpoint and saved files, so the same filesystem compared with itself (e.g. with different
+/// data modes) does not overwrite them.
+pub fn side_name(side: &str, fs_name: &str) -> String {
+    format!("{}.{}", side, fs_name)
+}
+
 pub fn save_output(
     dir: &Path,
     trace_path: &Path,
@@ -64,6 +83,73 @@ pub fn save_output(
     Ok(())
 }
 
+pub const KERNEL_FILENAME: &str = "kernel.txt";
+
+/// Kernel (`/proc/version`) run was made on, so runs on different kernels can be compared.
+pub fn save_kernel(dir: &Path) -> anyhow::Result<()> {
+    let version = fs::read_to_string("/proc/version")
+        .with_context(|| format!("failed to read kernel version"))?;
+    let kernel_path = dir.join(KERNEL_FILENAME);
+    fs::write(&kernel_path, version)
+        .with_context(|| format!("failed to save kernel at '{}'", kernel_path.display()))
+}
+
+/// Hash of filesystem contents after run, see [`crate::fuzzing::objective::hash::HashHolder`].
+pub fn save_hash(dir: &Path, fs_name: &str, hash: u64) -> anyhow::Result<()> {
+    let hash_path = dir.join(format!("{}.hash.txt", fs_name));
+    fs::write(&hash_path, format!("{}\n", hash))
+        .with_context(|| format!("failed to save hash at '{}'", hash_path.display()))
+}
+
+/// Output of filesystem check (e.g. `fsck`) after replaying crash, see [`crate::mount::mount::FileSystemMount::check`].
+pub fn save_check(dir: &Path, fs_name: &str, output: &str) -> anyhow::Result<()> {
+    let check_path = dir.join(format!("{}.check.txt", fs_name));
+    fs::write(&check_path, output)
+        .with_context(|| format!("failed to save check output at '{}'", check_path.display()))
+}
+
+pub const FSTEST_FILENAME: &str = "fstest.sh";
+
+pub fn save_fstest(dir: &Path, input: &Workload, summary: &str) -> anyhow::Result<()> {
+    let fstest_path = dir.join(FSTEST_FILENAME);
+    fs::write(&fstest_path, input.encode_fstest(summary))
+        .with_context(|| format!("failed to save fstest at '{}'", fstest_path.display()))?;
+    fs::set_permissions(&fstest_path, fs::Permissions::from_mode(0o755)).with_context(|| {
+        format!(
+            "failed to make fstest executable at '{}'",
+            fstest_path.display()
+        )
+    })
+}
+
+pub const REASON_FILENAME: &str = "reason.txt";
+pub const REASON_JSON_FILENAME: &str = "reason.json";
+
+pub fn save_reason(dir: &Path, reason: &Reason) -> anyhow::Result<()> {
+    let reason_path = dir.join(REASON_FILENAME);
+    fs::write(&reason_path, reason.render())
+        .with_context(|| format!("failed to save reason at '{}'", reason_path.display()))?;
+
+    let json_path = dir.join(REASON_JSON_FILENAME);
+    let json = serde_json::to_string_pretty(reason)
+        .with_context(|| format!("failed to encode reason as json"))?;
+    fs::write(&json_path, json)
+        .with_context(|| format!("failed to save reason at '{}'", json_path.display()))
+}
+
+pub const STATFS_DIFF_FILENAME: &str = "diff_statfs.txt";
+
+pub fn save_statfs_diff(dir: &Path, diff: &[StatfsDiff]) -> anyhow::Result<()> {
+    let diff_path = dir.join(STATFS_DIFF_FILENAME);
+    let lines: Vec<String> = diff.iter().map(|d| format!("{}\n", d)).collect();
+    fs::write(&diff_path, lines.concat()).with_context(|| {
+        format!(
+            "failed to save statfs differences at '{}'",
+            diff_path.display()
+        )
+    })
+}
+
 pub fn save_diff(dir: &Path, diff_hash: Vec<FileDiff>) -> anyhow::Result<()> {
     let diff_hash_path = dir.join(DIFF_HASH_FILENAME);
     let mut file = OpenOptions::new()
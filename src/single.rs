@@ -6,22 +6,57 @@ use std::{
 };
 
 use anyhow::Context;
-use log::info;
+use log::{info, warn};
 
-use crate::{
-    abstract_fs::{trace::TRACE_FILENAME, workload::Workload},
-    harness::Harness,
-    mount::mount::FileSystemMount,
-    save::{save_output, save_testcase},
-    temp_dir::setup_temp_dir,
+use dif_fuzzer::{
+    abstract_fs::{
+        encode::Delays, trace::TRACE_FILENAME, validate::validate_compacting, workload::Workload,
+    },
+    config::Config,
+    fuzzing::{common::hasher_options, objective::hash::HashHolder},
+    harness::{Harness, HarnessOptions},
+    hasher::hasher::{with_excluded, HasherOptions},
+    mount::mount::{devices, FileSystemMount},
+    save::{save_hash, save_kernel, save_output, save_testcase},
+    temp_dir::Instance,
 };
 
+/// Settings of single run, taken from config.
+pub struct Settings {
+    pub fs_name: String,
+    pub harness: HarnessOptions,
+    pub delays: Option<Delays>,
+    pub compile_jobs: u8,
+    /// Number of nodes model checking testcase is compacted at.
+    pub compact_model_at: Option<usize>,
+    pub hasher: HasherOptions,
+    pub hash_exclude: Vec<String>,
+}
+
+impl From<&Config> for Settings {
+    fn from(config: &Config) -> Self {
+        Self {
+            fs_name: config.fs_name.clone(),
+            harness: HarnessOptions {
+                // filesystem is always recreated
+                persistent_runs: None,
+                ..HarnessOptions::from(config)
+            },
+            delays: config.delays.clone(),
+            compile_jobs: config.compile_jobs,
+            compact_model_at: config.compact_model_at(),
+            hasher: hasher_options(config),
+            hash_exclude: config.hash_exclude.clone(),
+        }
+    }
+}
+
 pub fn run(
     test_path: &Path,
     save_to_dir: &Path,
     keep_fs: bool,
     mount: &'static dyn FileSystemMount,
-    fs_name: String,
+    settings: Settings,
 ) {
     info!("running single test");
 
@@ -32,8 +67,15 @@ pub fn run(
     let input: Workload = serde_json::from_str(&input)
         .with_context(|| format!("failed to parse json"))
         .unwrap();
+    if let Err(err) = validate_compacting(&input, settings.compact_model_at) {
+        warn!(
+            "testcase is invalid in model, trace may not match it: {}",
+            err
+        );
+    }
 
-    let temp_dir = setup_temp_dir();
+    let instance = Instance::claim().unwrap();
+    let temp_dir = instance.setup_temp_dir();
     let test_dir = temp_dir.clone();
 
     let exec_dir = temp_dir.join("exec");
@@ -45,7 +87,11 @@ pub fn run(
 
     info!("compiling test at '{}'", test_dir.display());
     let input_path = input
-        .compile(test_dir.as_path())
+        .compile(
+            test_dir.as_path(),
+            settings.delays.as_ref(),
+            settings.compile_jobs,
+        )
         .with_context(|| format!("failed to compile test"))
         .unwrap();
 
@@ -53,20 +99,31 @@ pub fn run(
     let stderr = Rc::new(RefCell::new("".to_owned()));
 
     let fs_str = mount.to_string();
+    let fs_dir = instance
+        .mount_dir()
+        .join(fs_str.to_lowercase())
+        .join(settings.fs_name)
+        .into_boxed_path();
+    let mut hash = HashHolder::new(
+        fs_dir.clone(),
+        with_excluded(&mount.get_internal_dirs(), &settings.hash_exclude)
+            .with_context(|| format!("bad hash exclude pattern"))
+            .unwrap(),
+        settings.hasher,
+    );
     let harness = Harness::new(
         mount,
-        Path::new("/mnt")
-            .join(fs_str.to_lowercase())
-            .join(fs_name)
-            .into_boxed_path(),
+        fs_dir,
         exec_dir.to_owned().into_boxed_path(),
         stdout.clone(),
         stderr.clone(),
+        devices(instance.index)[0],
+        settings.harness,
     );
 
     info!("running harness");
     harness
-        .run(&input_path, keep_fs, None)
+        .run(&input_path, keep_fs, &mut [&mut hash])
         .with_context(|| format!("failed to run harness"))
         .unwrap();
 
@@ -83,4 +140,10 @@ pub fn run(
     )
     .with_context(|| format!("failed to save output"))
     .unwrap();
+    save_hash(save_to_dir, &fs_str, hash.hash())
+        .with_context(|| format!("failed to save hash"))
+        .unwrap();
+    save_kernel(save_to_dir)
+        .with_context(|| format!("failed to save kernel"))
+        .unwrap();
 }
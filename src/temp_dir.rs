@@ -1,13 +1,108 @@
-use anyhow::Context;
+use anyhow::{bail, Context};
 use log::info;
+use std::fs::{File, TryLockError};
 use std::path::{Path, PathBuf};
 use std::{env, fs};
 
-pub fn setup_temp_dir() -> PathBuf {
+/// Instances that can run on the same host, each has its own temporary directory,
+/// ram devices and mountpoints (see [`Instance`]).
+pub const MAX_INSTANCES: usize = 8;
+
+/// Slot taken by instance on this host, it is held until instance is dropped.
+///
+/// The first slot keeps plain names (`/tmp/DIFFuzzer`, `/mnt/<fs>` and the first ram devices),
+/// so single instance runs as before. Locks are taken per open file, so runners of the same
+/// process (e.g. synced fuzzers) take different slots too.
+pub struct Instance {
+    pub index: usize,
+    temp_dir: PathBuf,
+    _lock: Option<File>,
+}
+
+impl Instance {
+    /// Takes the first free slot, fails if every slot is taken.
+    #[cfg(not(test))]
+    pub fn claim() -> anyhow::Result<Self> {
+        let (index, lock) = take_slot(&env::temp_dir())?;
+        info!("running as instance {}", index);
+        Ok(Self {
+            index,
+            temp_dir: env::temp_dir().join(instance_name(index)),
+            _lock: Some(lock),
+        })
+    }
+
+    /// Unit tests do not mount filesystems, so runners only get their own temporary directory
+    /// and leave slots (and directories) of running instances alone.
+    #[cfg(test)]
+    pub fn claim() -> anyhow::Result<Self> {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static RUNNERS: AtomicUsize = AtomicUsize::new(0);
+        Ok(Self {
+            index: 0,
+            temp_dir: env::temp_dir().join(format!(
+                "DIFFuzzer-runner-{}-{}-test",
+                std::process::id(),
+                RUNNERS.fetch_add(1, Ordering::Relaxed)
+            )),
+            _lock: None,
+        })
+    }
+
+    /// Directory mountpoints of instance are made in.
+    pub fn mount_dir(&self) -> PathBuf {
+        match self.index {
+            0 => PathBuf::from("/mnt"),
+            _ => Path::new("/mnt").join(instance_name(self.index)),
+        }
+    }
+
+    /// Fresh temporary directory of instance with copy of executor sources.
+    pub fn setup_temp_dir(&self) -> PathBuf {
+        setup_temp_dir(&self.temp_dir)
+    }
+}
+
+/// Runners of unit tests leave nothing behind.
+#[cfg(test)]
+impl Drop for Instance {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.temp_dir).unwrap_or(());
+    }
+}
+
+/// Index and lock of the first slot no other process (or runner) holds, lock files are kept in `lock_dir`.
+fn take_slot(lock_dir: &Path) -> anyhow::Result<(usize, File)> {
+    for index in 0..MAX_INSTANCES {
+        let path = lock_dir.join(format!("{}.lock", instance_name(index)));
+        let file = File::create(&path)
+            .with_context(|| format!("failed to create lock file at '{}'", path.display()))?;
+        match file.try_lock() {
+            Ok(()) => return Ok((index, file)),
+            Err(TryLockError::WouldBlock) => continue,
+            Err(TryLockError::Error(err)) => {
+                return Err(err)
+                    .with_context(|| format!("failed to lock file at '{}'", path.display()))
+            }
+        }
+    }
+    bail!(
+        "every one of {} instances is running on this host",
+        MAX_INSTANCES
+    )
+}
+
+fn instance_name(index: usize) -> String {
+    match index {
+        0 => "DIFFuzzer".to_owned(),
+        _ => format!("DIFFuzzer-{}", index),
+    }
+}
+
+fn setup_temp_dir(temp_dir: &Path) -> PathBuf {
     info!("setting up temporary directory");
-    let temp_dir = env::temp_dir().join("DIFFuzzer");
-    fs::remove_dir_all(temp_dir.as_path()).unwrap_or(());
-    fs::create_dir(temp_dir.as_path())
+    fs::remove_dir_all(temp_dir).unwrap_or(());
+    fs::create_dir_all(temp_dir)
         .with_context(|| {
             format!(
                 "failed to create temporary directory at '{}'",
@@ -30,5 +125,29 @@ pub fn setup_temp_dir() -> PathBuf {
     fs::copy(executor_dir.join(executor_cpp), temp_dir.join(executor_cpp))
         .with_context(|| format!("failed to copy '{}'", executor_cpp))
         .unwrap();
-    temp_dir
+    temp_dir.to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instances_take_different_slots() {
+        let dir = env::temp_dir().join("DIFFuzzer-instances-test");
+        fs::remove_dir_all(&dir).unwrap_or(());
+        fs::create_dir(&dir).unwrap();
+        let (fst, fst_lock) = take_slot(&dir).unwrap();
+        let (snd, _snd_lock) = take_slot(&dir).unwrap();
+        assert_eq!((0, 1), (fst, snd));
+        drop(fst_lock);
+        let (fst, _fst_lock) = take_slot(&dir).unwrap();
+        assert_eq!(0, fst);
+
+        let _locks: Vec<File> = (2..MAX_INSTANCES)
+            .map(|_| take_slot(&dir).unwrap().1)
+            .collect();
+        assert!(take_slot(&dir).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -0,0 +1,25 @@
+use std::{fs, path::Path};
+
+use anyhow::Context;
+use log::info;
+
+use dif_fuzzer::abstract_fs::{
+    validate::{render_alive, validate_compacting},
+    workload::Workload,
+};
+
+/// Replays testcase in the model and prints resulting tree, nothing is mounted or compiled.
+///
+/// Model is compacted once it has `compact_at` nodes, see [`validate_compacting`].
+pub fn run(test_path: &Path, compact_at: Option<usize>) -> anyhow::Result<()> {
+    info!("validating '{}'", test_path.display());
+    let input = fs::read_to_string(test_path)
+        .with_context(|| format!("failed to read testcase at '{}'", test_path.display()))?;
+    let input: Workload = serde_json::from_str(&input)
+        .with_context(|| format!("failed to parse testcase at '{}'", test_path.display()))?;
+    let fs = validate_compacting(&input, compact_at)
+        .with_context(|| format!("testcase at '{}' is invalid", test_path.display()))?;
+    println!("valid");
+    print!("{}", render_alive(&fs.alive()));
+    Ok(())
+}
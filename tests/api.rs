@@ -0,0 +1,52 @@
+use dif_fuzzer::{
+    generate_new, AbstractFS, FsError, GeneratorConfig, ModeFlag, Operation, Workload,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+#[test]
+fn test_build_replay_encode() {
+    let mut workload = Workload::new();
+    workload.push(Operation::MKDIR {
+        path: "/foo".into(),
+        mode: vec![ModeFlag::S_IRWXU],
+    });
+    workload.push(Operation::CREATE {
+        path: "/foo/bar".into(),
+        mode: vec![ModeFlag::S_IRUSR, ModeFlag::S_IWUSR],
+    });
+    workload.push(Operation::RENAME {
+        old_path: "/foo/bar".into(),
+        new_path: "/baz".into(),
+        flags: vec![],
+    });
+
+    let mut fs = AbstractFS::new();
+    fs.replay(&workload).unwrap();
+    assert_eq!(workload, fs.recording);
+    assert!(fs.resolve_file("/baz".into()).is_ok());
+    assert_eq!(
+        Err(FsError::NotFound("/foo/bar".into())),
+        fs.resolve_file("/foo/bar".into()).map(|_| ())
+    );
+
+    let source = workload.encode_c(None);
+    assert!(source.contains("do_rename(\"/foo/bar\", \"/baz\");"));
+    assert_eq!(workload, Workload::decode_c(&source));
+    assert!(workload.encode_fstest("").contains("mv -T"));
+}
+
+#[test]
+fn test_replay_generated() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let workload = generate_new(
+        &mut rng,
+        50,
+        &GeneratorConfig {
+            root_bias: 0.2,
+            ..Default::default()
+        },
+    );
+    let mut fs = AbstractFS::new();
+    fs.replay(&workload).unwrap();
+    assert_eq!(workload, fs.recording);
+}